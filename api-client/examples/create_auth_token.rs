@@ -1,7 +1,8 @@
 use api_client::LighterClient;
 use std::env;
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("{}", "═".repeat(80));
     println!("🔐 CREATE AUTH TOKEN EXAMPLE");
     println!("{}", "═".repeat(80));
@@ -25,7 +26,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Create auth token with default expiry (7 hours = 25200 seconds)
     println!("📝 Creating auth token...");
     let default_expiry_seconds = 7 * 60 * 60; // 7 hours
-    let token = client.create_auth_token(default_expiry_seconds)?;
+    let token = client.create_auth_token(default_expiry_seconds).await?;
 
     println!("✅ Auth token created!");
     println!();
@@ -39,7 +40,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!();
     println!("📝 Creating short-lived token (10 minutes)...");
     let short_expiry_seconds = 10 * 60; // 10 minutes
-    let short_token = client.create_auth_token(short_expiry_seconds)?;
+    let short_token = client.create_auth_token(short_expiry_seconds).await?;
     println!("✅ Short-lived token created!");
     println!("  Token: {}", short_token.chars().take(50).collect::<String>() + "...");
     println!("  Expiry: {} seconds ({} minutes)", short_expiry_seconds, short_expiry_seconds / 60);
@@ -1,4 +1,4 @@
-use api_client::{CreateOrderRequest, LighterClient};
+use api_client::{BaseAmount, CreateOrderRequest, LighterClient, OrderType, Price, TimeInForce};
 use std::env;
 
 #[tokio::main]
@@ -29,13 +29,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         account_index,
         order_book_index: 1,   // 0 = BTC-USD or ETH-USD
         client_order_index: 0, // unique identifier
-        base_amount: 100,      // 0.001 tokens in smallest unit
-        price: 1040000,        // limit price in cents
+        base_amount: BaseAmount(100),      // 0.001 tokens in smallest unit
+        price: Price(1040000),        // limit price in cents
         is_ask: false,         // false = buy order
-        order_type: 0,         // 0 = LimitOrder
-        time_in_force: 1,      // 1 = GoodTillTime
+        order_type: OrderType::Limit,
+        time_in_force: TimeInForce::GoodTillTime,
         reduce_only: false,
-        trigger_price: 0,
+        trigger_price: Price::ZERO,
+        price_protection: None,
+        order_expiry: None,
     };
 
     let response = client.create_order(create_order_req).await?;
@@ -1,4 +1,4 @@
-use api_client::{LighterClient, CreateOrderRequest};
+use api_client::{BaseAmount, CreateOrderRequest, LighterClient, OrderType, Price, TimeInForce};
 use std::env;
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -80,13 +80,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         account_index,
         order_book_index: 0,
         client_order_index: client_order_index_base + 1,
-        base_amount: 1000,
-        price: 500000,
+        base_amount: BaseAmount(1000),
+        price: Price(500000),
         is_ask: false,
-        order_type: 4, // TAKE_PROFIT
-        time_in_force: 1, // GOOD_TILL_TIME
+        order_type: OrderType::TakeProfit,
+        time_in_force: TimeInForce::GoodTillTime,
         reduce_only: false,
-        trigger_price: 500000,
+        trigger_price: Price(500000),
+        price_protection: None,
+        order_expiry: None,
     };
 
     match client.create_order(tp_order).await {
@@ -110,13 +112,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         account_index,
         order_book_index: 0,
         client_order_index: client_order_index_base + 2,
-        base_amount: 1000,
-        price: 500000,
+        base_amount: BaseAmount(1000),
+        price: Price(500000),
         is_ask: false,
-        order_type: 2, // STOP_LOSS
-        time_in_force: 1, // GOOD_TILL_TIME
+        order_type: OrderType::StopLoss,
+        time_in_force: TimeInForce::GoodTillTime,
         reduce_only: false,
-        trigger_price: 500000,
+        trigger_price: Price(500000),
+        price_protection: None,
+        order_expiry: None,
     };
 
     match client.create_order(sl_order).await {
@@ -140,13 +144,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         account_index,
         order_book_index: 0,
         client_order_index: client_order_index_base + 3,
-        base_amount: 1000,
-        price: 500000,
+        base_amount: BaseAmount(1000),
+        price: Price(500000),
         is_ask: false,
-        order_type: 5, // TAKE_PROFIT_LIMIT
-        time_in_force: 1, // GOOD_TILL_TIME
+        order_type: OrderType::TakeProfitLimit,
+        time_in_force: TimeInForce::GoodTillTime,
         reduce_only: false,
-        trigger_price: 500000,
+        trigger_price: Price(500000),
+        price_protection: None,
+        order_expiry: None,
     };
 
     match client.create_order(tp_limit_order).await {
@@ -170,13 +176,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         account_index,
         order_book_index: 0,
         client_order_index: client_order_index_base + 4,
-        base_amount: 1000,
-        price: 500000,
+        base_amount: BaseAmount(1000),
+        price: Price(500000),
         is_ask: false,
-        order_type: 3, // STOP_LOSS_LIMIT
-        time_in_force: 1, // GOOD_TILL_TIME
+        order_type: OrderType::StopLossLimit,
+        time_in_force: TimeInForce::GoodTillTime,
         reduce_only: false,
-        trigger_price: 500000,
+        trigger_price: Price(500000),
+        price_protection: None,
+        order_expiry: None,
     };
 
     match client.create_order(sl_limit_order).await {
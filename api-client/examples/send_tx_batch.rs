@@ -1,6 +1,5 @@
-use api_client::{LighterClient, CreateOrderRequest};
+use api_client::{BaseAmount, CreateOrderRequest, LighterClient, OrderType, Price, TimeInForce};
 use std::env;
-use std::time::{SystemTime, UNIX_EPOCH};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -83,13 +82,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         account_index,
         order_book_index: 0,
         client_order_index: 1001,
-        base_amount: 100000,
-        price: 280000,
+        base_amount: BaseAmount(100000),
+        price: Price(280000),
         is_ask: true,
-        order_type: 0, // LIMIT
-        time_in_force: 1, // GOOD_TILL_TIME
+        order_type: OrderType::Limit,
+        time_in_force: TimeInForce::GoodTillTime,
         reduce_only: false,
-        trigger_price: 0,
+        trigger_price: Price::ZERO,
+        price_protection: None,
+        order_expiry: None,
     };
 
     let ask_response = client.create_order_with_nonce(ask_order, Some(current_nonce)).await?;
@@ -102,13 +103,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         account_index,
         order_book_index: 0,
         client_order_index: 1002,
-        base_amount: 200000,
-        price: 200000,
+        base_amount: BaseAmount(200000),
+        price: Price(200000),
         is_ask: false,
-        order_type: 0, // LIMIT
-        time_in_force: 1, // GOOD_TILL_TIME
+        order_type: OrderType::Limit,
+        time_in_force: TimeInForce::GoodTillTime,
         reduce_only: false,
-        trigger_price: 0,
+        trigger_price: Price::ZERO,
+        price_protection: None,
+        order_expiry: None,
     };
 
     let bid_response = client.create_order_with_nonce(bid_order, Some(current_nonce)).await?;
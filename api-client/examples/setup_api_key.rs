@@ -1,4 +1,4 @@
-use api_client::LighterClient;
+use api_client::{onboard, EthWallet};
 use signer::KeyManager;
 use std::env;
 
@@ -14,7 +14,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let base_url = env::var("BASE_URL")?;
     let account_index: i64 = env::var("ACCOUNT_INDEX")?.parse()?;
     let api_key_index: u8 = env::var("API_KEY_INDEX")?.parse()?;
-    let api_key = env::var("API_PRIVATE_KEY")?;
+    let _api_key = env::var("API_PRIVATE_KEY")?;
 
     println!("📋 Configuration:");
     println!("  Base URL: {}", base_url);
@@ -29,8 +29,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let new_public_key = new_key_manager.public_key_bytes();
 
     println!("✅ New API key generated!");
-    println!("  Private Key (hex): {}", hex::encode(&new_private_key));
-    println!("  Public Key (hex): {}", hex::encode(&new_public_key));
+    println!("  Private Key (hex): {}", hex::encode(new_private_key));
+    println!("  Public Key (hex): {}", hex::encode(new_public_key));
     println!();
 
     // Determine the new API key index (typically current + 1)
@@ -38,35 +38,36 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("📝 Setting up new API key at index {}...", new_api_key_index);
     println!();
 
-    // Note: change_api_key requires the new API key's private key to be used
-    // for signing the transaction. For a complete setup, you would:
-    // 1. Use your ETH private key to sign an L1 message
-    // 2. Use the new API key to sign the change_pub_key transaction
-    // 3. Submit both signatures
-    //
-    // This example shows the structure, but you need to handle L1 signature separately
+    // Registering a key needs an L1 signature from the account's Ethereum key.
+    // With ETH_PRIVATE_KEY set, the onboarding helper does the whole flow.
+    if let Ok(eth_private_key) = env::var("ETH_PRIVATE_KEY") {
+        let wallet = EthWallet::from_hex(&eth_private_key)?;
+        println!("📝 Registering a new API key for {}...", wallet.address_hex());
+
+        let onboarded = onboard(&base_url, &wallet, new_api_key_index).await?;
+
+        println!("✅ API key registered!");
+        println!("  Account Index: {}", onboarded.account_index);
+        println!("  Update your .env file:");
+        println!("    API_PRIVATE_KEY={}", onboarded.api_private_key.as_str());
+        println!("    API_KEY_INDEX={}", onboarded.api_key_index);
+        return Ok(());
+    }
+
     println!("⚠️  NOTE: change_api_key requires:");
     println!("  1. L1 signature (signed with ETH private key)");
     println!("  2. L2 signature (signed with new API private key)");
     println!("  3. The transaction must be signed with the NEW API key, not the current one");
     println!();
-    println!("  For complete setup, register the public key on the exchange.");
-    println!("  You'll need to sign an L1 message with your Ethereum wallet.");
+    println!("  Set ETH_PRIVATE_KEY to register the key from this example.");
     println!();
 
     // Example: Show how to create the client with new key
     println!("📝 Example: Using new API key for future transactions:");
     println!("  Update your .env file:");
-    println!("    API_PRIVATE_KEY={}", hex::encode(&new_private_key));
+    println!("    API_PRIVATE_KEY={}", hex::encode(new_private_key));
     println!("    API_KEY_INDEX={}", new_api_key_index);
     println!();
 
-    // If you have the new key manager ready, you can change the API key
-    // This is a placeholder - you'll need to implement the full flow with L1 signature
-    println!("💡 To complete the setup:");
-    println!("  1. Generate L1 signature using your ETH private key");
-    println!("  2. Use the new API key manager to sign the change_pub_key transaction");
-    println!("  3. Submit the transaction with both signatures");
-
     Ok(())
 }
@@ -1,4 +1,4 @@
-use api_client::LighterClient;
+use api_client::{LighterClient, MarginMode};
 use std::env;
 
 #[tokio::main]
@@ -83,16 +83,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("📝 Updating Leverage...");
     let market_index = 0u8;
     let leverage = 3u16; // 3x leverage
-    let margin_mode = 1u8; // 0 = CROSS_MARGIN, 1 = ISOLATED_MARGIN
+    let margin_mode = MarginMode::Isolated;
 
     match client.update_leverage(market_index, leverage, margin_mode).await {
-        Ok(response) => {
+        Ok(update) => {
+            let response = update.response;
             let code = response["code"].as_i64().unwrap_or_default();
             if code == 200 {
                 println!("✅ Leverage updated successfully!");
                 println!("  Market Index: {}", market_index);
-                println!("  Leverage: {}x", leverage);
-                println!("  Margin Mode: {}", if margin_mode == 0 { "CROSS" } else { "ISOLATED" });
+                println!("  Leverage: {}x (effective {:.3}x)", leverage, update.effective_leverage);
+                println!("  Margin Mode: {}", margin_mode);
             } else {
                 println!("⚠️  Leverage update returned code: {}", code);
                 if let Some(msg) = response["message"].as_str() {
@@ -0,0 +1,255 @@
+//! Account updates by polling.
+//!
+//! Where websockets are unavailable, `poll_account_stream` polls the account and its
+//! recent trades at a fixed interval and yields only what changed: new fills, positions
+//! whose size or entry price moved, and collateral changes. The first poll establishes
+//! the baseline and yields nothing.
+
+use crate::{LighterClient, Result};
+use futures_core::Stream;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Number of recent trades fetched per poll; fills beyond this within one interval are missed.
+const TRADES_PER_POLL: u32 = 100;
+
+/// A change between two polls.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AccountEvent {
+    /// A trade not seen in earlier polls (entry of the `trades` response).
+    Fill(Value),
+    /// Position size, side or entry price changed; `None` when there is no open position.
+    PositionChanged {
+        market_index: u8,
+        previous: Option<Value>,
+        current: Option<Value>,
+    },
+    /// Collateral changed (decimal strings as reported).
+    CollateralChanged { previous: Option<String>, current: Option<String> },
+}
+
+/// The parts of an account compared between polls.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct AccountSnapshot {
+    /// Open positions by market.
+    positions: BTreeMap<u8, Value>,
+    collateral: Option<String>,
+}
+
+impl AccountSnapshot {
+    pub(crate) fn from_account(account_info: &Value) -> Self {
+        let account = account_info["accounts"]
+            .as_array()
+            .and_then(|a| a.first())
+            .unwrap_or(account_info);
+
+        let positions = account["positions"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|p| {
+                let market_index = p["market_id"].as_u64()? as u8;
+                is_open(p).then(|| (market_index, p.clone()))
+            })
+            .collect();
+
+        Self {
+            positions,
+            collateral: account["collateral"].as_str().map(str::to_string),
+        }
+    }
+
+    /// Events turning `self` into `next`.
+    pub(crate) fn diff(&self, next: &AccountSnapshot) -> Vec<AccountEvent> {
+        let mut events = Vec::new();
+
+        let markets: BTreeSet<u8> = self.positions.keys().chain(next.positions.keys()).copied().collect();
+        for market_index in markets {
+            let previous = self.positions.get(&market_index);
+            let current = next.positions.get(&market_index);
+            let changed = match (previous, current) {
+                (Some(p), Some(c)) => position_key(p) != position_key(c),
+                (None, None) => false,
+                _ => true,
+            };
+            if changed {
+                events.push(AccountEvent::PositionChanged {
+                    market_index,
+                    previous: previous.cloned(),
+                    current: current.cloned(),
+                });
+            }
+        }
+
+        if self.collateral != next.collateral {
+            events.push(AccountEvent::CollateralChanged {
+                previous: self.collateral.clone(),
+                current: next.collateral.clone(),
+            });
+        }
+
+        events
+    }
+}
+
+fn is_open(position: &Value) -> bool {
+    position["position"]
+        .as_str()
+        .and_then(|s| s.parse::<f64>().ok())
+        .is_some_and(|size| size != 0.0)
+}
+
+/// Fields whose change is reported; PnL and margin move with the mark price and are ignored.
+fn position_key(position: &Value) -> (&Value, &Value, &Value) {
+    (&position["position"], &position["sign"], &position["avg_entry_price"])
+}
+
+/// Trades with an id above `last_seen`, oldest first, and the new highest id.
+pub(crate) fn new_fills(trades: &Value, last_seen: Option<i64>) -> (Vec<Value>, Option<i64>) {
+    let mut fills: Vec<&Value> = trades["trades"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|t| t["trade_id"].as_i64().is_some_and(|id| last_seen.is_none_or(|seen| id > seen)))
+        .collect();
+    fills.sort_by_key(|t| t["trade_id"].as_i64());
+
+    let highest = fills.last().and_then(|t| t["trade_id"].as_i64()).or(last_seen);
+    (fills.into_iter().cloned().collect(), highest)
+}
+
+/// Stream of `AccountEvent`s from `poll_account_stream`.
+///
+/// A failed poll yields its error and polling continues. Dropping the stream stops polling.
+pub struct AccountStream {
+    receiver: mpsc::Receiver<Result<AccountEvent>>,
+    task: JoinHandle<()>,
+}
+
+impl Stream for AccountStream {
+    type Item = Result<AccountEvent>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+impl AccountStream {
+    /// Wait for the next event; `None` once polling has stopped
+    pub async fn next_event(&mut self) -> Option<Result<AccountEvent>> {
+        self.receiver.recv().await
+    }
+}
+
+impl Drop for AccountStream {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl LighterClient {
+    /// Poll this account every `interval` and stream what changed
+    pub fn poll_account_stream(self: &Arc<Self>, interval: Duration) -> AccountStream {
+        let (sender, receiver) = mpsc::channel(256);
+        let client = Arc::clone(self);
+
+        let task = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            let mut snapshot: Option<AccountSnapshot> = None;
+            let mut last_trade: Option<i64> = None;
+
+            loop {
+                ticker.tick().await;
+                let events = match client.poll_account_once(&mut snapshot, &mut last_trade).await {
+                    Ok(events) => events.into_iter().map(Ok).collect(),
+                    Err(e) => vec![Err(e)],
+                };
+                for event in events {
+                    if sender.send(event).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+
+        AccountStream { receiver, task }
+    }
+
+    async fn poll_account_once(
+        &self,
+        snapshot: &mut Option<AccountSnapshot>,
+        last_trade: &mut Option<i64>,
+    ) -> Result<Vec<AccountEvent>> {
+        let account_info = self.get_account().await?;
+        let trades = self.get_trades(None, TRADES_PER_POLL).await?;
+
+        let next = AccountSnapshot::from_account(&account_info);
+        let (fills, highest) = new_fills(&trades, *last_trade);
+
+        let mut events = Vec::new();
+        // The first poll only sets the baseline
+        if let Some(previous) = snapshot.as_ref() {
+            events.extend(fills.into_iter().map(AccountEvent::Fill));
+            events.extend(previous.diff(&next));
+        }
+        *snapshot = Some(next);
+        *last_trade = highest;
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_snapshot_diff() {
+        let before = AccountSnapshot::from_account(&json!({"accounts": [{
+            "collateral": "100.00",
+            "positions": [
+                {"market_id": 0, "position": "0.50", "sign": 1, "avg_entry_price": "3000", "unrealized_pnl": "1"},
+                {"market_id": 1, "position": "2.0", "sign": -1, "avg_entry_price": "150", "unrealized_pnl": "0"},
+                {"market_id": 2, "position": "0.0", "sign": 1, "avg_entry_price": "0"}
+            ]
+        }]}));
+        let after = AccountSnapshot::from_account(&json!({"accounts": [{
+            "collateral": "100.00",
+            "positions": [
+                {"market_id": 0, "position": "0.50", "sign": 1, "avg_entry_price": "3000", "unrealized_pnl": "7"},
+                {"market_id": 3, "position": "1.0", "sign": 1, "avg_entry_price": "10"}
+            ]
+        }]}));
+
+        let events = before.diff(&after);
+        let changed: Vec<u8> = events
+            .iter()
+            .map(|e| match e {
+                AccountEvent::PositionChanged { market_index, .. } => *market_index,
+                other => panic!("unexpected {:?}", other),
+            })
+            .collect();
+        // PnL-only change on market 0 is ignored; market 2 was flat
+        assert_eq!(changed, vec![1, 3]);
+        assert!(before.diff(&before).is_empty());
+    }
+
+    #[test]
+    fn test_new_fills() {
+        let trades = json!({"trades": [{"trade_id": 12}, {"trade_id": 10}, {"trade_id": 11}]});
+        let (fills, highest) = new_fills(&trades, Some(10));
+        assert_eq!(fills, vec![json!({"trade_id": 11}), json!({"trade_id": 12})]);
+        assert_eq!(highest, Some(12));
+
+        let (fills, highest) = new_fills(&json!({"trades": []}), Some(12));
+        assert!(fills.is_empty());
+        assert_eq!(highest, Some(12));
+    }
+}
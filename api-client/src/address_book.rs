@@ -0,0 +1,223 @@
+//! Counterparty accounts by address or label.
+//!
+//! Transfers go to an account index. `resolve_account` finds the index of an account given
+//! either its index (checked to exist) or the Ethereum address that owns it, using the
+//! account lookup endpoints; `transfer_to` resolves the destination before signing.
+//!
+//! `AddressBook` names counterparties: a JSON file mapping labels to an index or an
+//! address, e.g. `{"treasury": 281, "desk": "0x1f9840a85d5af5bf1d1762f925bdaddc4201f984"}`.
+
+use crate::onboarding::lookup_account_index;
+use crate::{parse_response, ApiError, LighterClient, Result, TransferRequest, UsdcAmount};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// An account, by index or by the L1 address that owns it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum AccountRef {
+    Index(i64),
+    /// `0x`-prefixed Ethereum address; resolves to the address's main account.
+    L1Address(String),
+}
+
+impl From<i64> for AccountRef {
+    fn from(index: i64) -> Self {
+        AccountRef::Index(index)
+    }
+}
+
+/// Parses an account index (`281`) or an Ethereum address (`0x` and 40 hex digits).
+impl FromStr for AccountRef {
+    type Err = ApiError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if let Ok(index) = s.parse::<i64>() {
+            return Ok(AccountRef::Index(index));
+        }
+        match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+            Some(hex) if hex.len() == 40 && hex.bytes().all(|b| b.is_ascii_hexdigit()) => {
+                Ok(AccountRef::L1Address(format!("0x{}", hex.to_ascii_lowercase())))
+            }
+            _ => Err(ApiError::Api(format!("{:?} is neither an account index nor an L1 address", s))),
+        }
+    }
+}
+
+impl fmt::Display for AccountRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AccountRef::Index(index) => write!(f, "account {}", index),
+            AccountRef::L1Address(address) => write!(f, "{}", address),
+        }
+    }
+}
+
+/// Labelled counterparty accounts, stored as a JSON object.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct AddressBook {
+    entries: BTreeMap<String, AccountRef>,
+}
+
+impl AddressBook {
+    /// Read the address book at `path`; a missing file is an empty book.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(ApiError::AddressBook(format!("{}: {}", path.display(), e))),
+        };
+        let book: AddressBook = serde_json::from_str(&contents)
+            .map_err(|e| ApiError::AddressBook(format!("{}: {}", path.display(), e)))?;
+        // Addresses go through the same checks as on the command line
+        for (label, account) in &book.entries {
+            if let AccountRef::L1Address(address) = account {
+                AccountRef::from_str(address)
+                    .map_err(|_| ApiError::AddressBook(format!("{}: invalid address for {}", path.display(), label)))?;
+            }
+        }
+        Ok(book)
+    }
+
+    /// Write the address book to `path` as pretty-printed JSON.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        std::fs::write(path, serde_json::to_string_pretty(self)? + "\n")
+            .map_err(|e| ApiError::AddressBook(format!("{}: {}", path.display(), e)))
+    }
+
+    pub fn insert(&mut self, label: impl Into<String>, account: AccountRef) {
+        self.entries.insert(label.into(), account);
+    }
+
+    pub fn remove(&mut self, label: &str) -> Option<AccountRef> {
+        self.entries.remove(label)
+    }
+
+    pub fn get(&self, label: &str) -> Option<&AccountRef> {
+        self.entries.get(label)
+    }
+
+    /// Labels and their accounts, sorted by label.
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &AccountRef)> {
+        self.entries.iter()
+    }
+
+    /// The account labelled `name`, or `name` parsed as an index or address.
+    pub fn lookup(&self, name: &str) -> Result<AccountRef> {
+        match self.entries.get(name) {
+            Some(account) => Ok(account.clone()),
+            None => AccountRef::from_str(name)
+                .map_err(|_| ApiError::AddressBook(format!("No entry {:?}, and it is not an index or address", name))),
+        }
+    }
+}
+
+impl LighterClient {
+    /// Index of an account given by index or by owning L1 address
+    ///
+    /// An index is checked to exist; an address resolves to its main account. Fails with
+    /// `ApiError::Api` if there is no such account.
+    pub async fn resolve_account(&self, by: &AccountRef) -> Result<i64> {
+        let not_found = || ApiError::Api(format!("No Lighter account for {}", by));
+        match by {
+            AccountRef::L1Address(address) => lookup_account_index(&self.base_url, address).await?.ok_or_else(not_found),
+            AccountRef::Index(index) => {
+                let request = self
+                    .client
+                    .get(format!("{}/api/v1/account", self.base_url))
+                    .query(&[("by", "index".to_string()), ("value", index.to_string())]);
+                let response = self
+                    .guarded("/api/v1/account", async { parse_response(request.send().await?).await })
+                    .await?;
+                if account_exists(&response, *index)? {
+                    Ok(*index)
+                } else {
+                    Err(not_found())
+                }
+            }
+        }
+    }
+
+    /// Transfer USDC to an account given by index or L1 address
+    ///
+    /// Resolves `to` with `resolve_account` before signing, then sends a `transfer`.
+    pub async fn transfer_to(&self, to: &AccountRef, usdc_amount: UsdcAmount, fee: UsdcAmount, memo: [u8; 32]) -> Result<Value> {
+        let to_account_index = self.resolve_account(to).await?;
+        self.transfer(TransferRequest {
+            to_account_index,
+            usdc_amount,
+            fee,
+            memo,
+        })
+        .await
+    }
+}
+
+/// Whether an `account` response lists `index`.
+fn account_exists(response: &Value, index: i64) -> Result<bool> {
+    match response["code"].as_i64() {
+        Some(200) | None => {}
+        // 21100: account not found
+        Some(21100) => return Ok(false),
+        Some(_) => return Err(ApiError::Api(format!("Account lookup failed: {}", response))),
+    }
+    let accounts = response["accounts"].as_array().map(Vec::as_slice).unwrap_or_default();
+    Ok(accounts.iter().any(|account| account["index"].as_i64() == Some(index)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_account_refs_and_address_book() {
+        assert_eq!("281".parse::<AccountRef>().unwrap(), AccountRef::Index(281));
+        assert_eq!(
+            "0x1F9840a85d5aF5bf1D1762F925BDADdC4201F984".parse::<AccountRef>().unwrap(),
+            AccountRef::L1Address("0x1f9840a85d5af5bf1d1762f925bdaddc4201f984".to_string())
+        );
+        assert!("0x1234".parse::<AccountRef>().is_err());
+        assert!("treasury".parse::<AccountRef>().is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("address_book.json");
+        assert_eq!(AddressBook::load(&path).unwrap(), AddressBook::default());
+
+        let mut book = AddressBook::default();
+        book.insert("treasury", AccountRef::Index(281));
+        book.insert("desk", "0x1f9840a85d5af5bf1d1762f925bdaddc4201f984".parse().unwrap());
+        book.save(&path).unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&contents).unwrap(),
+            json!({"treasury": 281, "desk": "0x1f9840a85d5af5bf1d1762f925bdaddc4201f984"})
+        );
+
+        let loaded = AddressBook::load(&path).unwrap();
+        assert_eq!(loaded, book);
+        assert_eq!(loaded.lookup("treasury").unwrap(), AccountRef::Index(281));
+        assert_eq!(loaded.lookup("17").unwrap(), AccountRef::Index(17));
+        assert!(matches!(loaded.lookup("unknown"), Err(ApiError::AddressBook(_))));
+
+        std::fs::write(&path, r#"{"bad": "0xnothex"}"#).unwrap();
+        assert!(AddressBook::load(&path).is_err());
+    }
+
+    #[test]
+    fn test_account_exists() {
+        let found = json!({"code": 200, "total": 1, "accounts": [{"index": 281, "l1_address": "0xabc"}]});
+        assert!(account_exists(&found, 281).unwrap());
+        assert!(!account_exists(&json!({"code": 200, "accounts": []}), 281).unwrap());
+        assert!(!account_exists(&json!({"code": 21100, "message": "account not found"}), 281).unwrap());
+        assert!(account_exists(&json!({"code": 500}), 281).is_err());
+    }
+}
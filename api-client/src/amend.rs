@@ -0,0 +1,183 @@
+//! Cancel-replace of resting limit orders.
+//!
+//! `amend_order` first tries an in-place ModifyOrder. If the exchange rejects it, the old
+//! order is cancelled and its replacement created in a single sendTxBatch request with
+//! consecutive nonces, so no other transaction of this key can land between the two and
+//! the replacement is never created if the cancel fails.
+
+use crate::{ApiError, BaseAmount, CreateOrderRequest, LighterClient, ModifyOrderRequest, OrderType, Price, Result, TimeInForce};
+use serde_json::{json, Value};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// How `amend_order` applied the change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AmendPath {
+    /// Modified in place; the order keeps its order index.
+    Modified,
+    /// Cancelled and re-created with the same client order index; the order index changes.
+    Replaced,
+}
+
+/// Result of `amend_order`.
+#[derive(Debug, Clone)]
+pub struct AmendOutcome {
+    pub path: AmendPath,
+    /// Response of the transaction (or batch) that applied the change.
+    pub response: Value,
+}
+
+impl LighterClient {
+    /// Change the price and size of a resting limit order
+    ///
+    /// Uses ModifyOrder when the exchange accepts it and otherwise cancels and re-creates
+    /// the order atomically. Errors if the order is no longer active, so a filled order is
+    /// never re-opened. Trigger orders are not supported; use `modify_order` for those.
+    ///
+    /// # Arguments
+    /// * `market_index` - Market of the order
+    /// * `order_index` - Exchange order index of the resting order
+    /// * `new_price` - New price in price units
+    /// * `new_size` - New base amount in size units
+    pub async fn amend_order(&self, market_index: u8, order_index: i64, new_price: Price, new_size: BaseAmount) -> Result<AmendOutcome> {
+        let active = self.get_active_orders(market_index).await?;
+        let order = active["orders"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|o| o["order_index"].as_i64() == Some(order_index))
+            .ok_or_else(|| ApiError::Api(format!("Order {} is not active on market {}", order_index, market_index)))?;
+        let replacement = replacement_order(order, self.account_index, market_index, new_price, new_size)?;
+
+        let response = self
+            .modify_order(ModifyOrderRequest {
+                market_index,
+                order_index,
+                base_amount: new_size,
+                price: new_price,
+                trigger_price: Price::ZERO,
+            })
+            .await?;
+        if response["code"].as_i64() == Some(200) {
+            return Ok(AmendOutcome {
+                path: AmendPath::Modified,
+                response,
+            });
+        }
+
+        let response = self.cancel_and_create(market_index, order_index, &replacement).await?;
+        if response["code"].as_i64() != Some(200) {
+            return Err(ApiError::Api(format!("Cancel-replace of order {} failed: {}", order_index, response)));
+        }
+
+        Ok(AmendOutcome {
+            path: AmendPath::Replaced,
+            response,
+        })
+    }
+
+    /// Sends the cancel of `order_index` and the creation of `order` as one batch.
+    async fn cancel_and_create(&self, market_index: u8, order_index: i64, order: &CreateOrderRequest) -> Result<Value> {
+        let cancel_lease = self.lease_nonce().await?;
+        let create_lease = self.lease_nonce().await?;
+
+        let cancel = self
+            .sign_cancel_order_with_nonce(market_index, order_index, Some(cancel_lease.nonce()))
+            .await?
+            .tx_info;
+        let create = self.sign_replacement(order, create_lease.nonce()).await?;
+
+        let response = self.send_tx_batch(&[(15, cancel), (14, create)]).await; // CANCEL_ORDER, CREATE_ORDER
+        cancel_lease.settle_outcome(&response);
+        create_lease.settle_outcome(&response);
+        response
+    }
+
+    /// Signs a limit order with the client's expiry policy, like `create_order`.
+    async fn sign_replacement(&self, order: &CreateOrderRequest, nonce: i64) -> Result<Value> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
+        let order_expiry = self.expiry_policy.order_expiry(order, now);
+
+        let tx_info = json!({
+            "AccountIndex": self.account_index,
+            "ApiKeyIndex": self.api_key_index,
+            "MarketIndex": order.order_book_index,
+            "ClientOrderIndex": order.client_order_index,
+            "BaseAmount": order.base_amount,
+            "Price": order.price,
+            "IsAsk": if order.is_ask { 1 } else { 0 },
+            "Type": order.order_type,
+            "TimeInForce": order.time_in_force,
+            "ReduceOnly": if order.reduce_only { 1 } else { 0 },
+            "TriggerPrice": order.trigger_price,
+            "OrderExpiry": order_expiry,
+            "ExpiredAt": now + 599_000,
+            "Nonce": nonce,
+            "Sig": ""
+        });
+
+        Ok(self.sign_tx_info(tx_info, 14).await?.tx_info) // CREATE_ORDER
+    }
+}
+
+/// Builds the order that replaces `order` (an entry of `accountActiveOrders`).
+pub(crate) fn replacement_order(
+    order: &Value,
+    account_index: i64,
+    market_index: u8,
+    new_price: Price,
+    new_size: BaseAmount,
+) -> Result<CreateOrderRequest> {
+    if order["type"].as_str() != Some("limit") {
+        return Err(ApiError::Api(format!("amend_order only supports limit orders, got {}", order["type"])));
+    }
+    let time_in_force = match order["time_in_force"].as_str() {
+        Some("immediate-or-cancel") => TimeInForce::ImmediateOrCancel,
+        Some("good-till-time") => TimeInForce::GoodTillTime,
+        Some("post-only") => TimeInForce::PostOnly,
+        _ => return Err(ApiError::Api(format!("Unknown time in force {}", order["time_in_force"]))),
+    };
+    let client_order_index = order["client_order_index"]
+        .as_u64()
+        .ok_or_else(|| ApiError::Api(format!("Order has no client order index: {}", order)))?;
+
+    Ok(CreateOrderRequest {
+        account_index,
+        order_book_index: market_index,
+        client_order_index,
+        base_amount: new_size,
+        price: new_price,
+        is_ask: order["is_ask"].as_bool().unwrap_or(false),
+        order_type: OrderType::Limit,
+        time_in_force,
+        reduce_only: order["reduce_only"].as_bool().unwrap_or(false),
+        trigger_price: Price::ZERO,
+        price_protection: None,
+        order_expiry: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replacement_order_keeps_side_and_flags() {
+        let order = json!({
+            "order_index": 281475005218816i64,
+            "client_order_index": 17,
+            "type": "limit",
+            "time_in_force": "post-only",
+            "is_ask": true,
+            "reduce_only": true,
+        });
+        let replacement = replacement_order(&order, 42, 3, Price(305_000), BaseAmount(1_500)).unwrap();
+        assert_eq!(replacement.client_order_index, 17);
+        assert_eq!(replacement.price, Price(305_000));
+        assert_eq!(replacement.base_amount, BaseAmount(1_500));
+        assert_eq!(replacement.time_in_force, TimeInForce::PostOnly);
+        assert!(replacement.is_ask && replacement.reduce_only);
+
+        let trigger = json!({"client_order_index": 1, "type": "stop-loss", "time_in_force": "immediate-or-cancel"});
+        assert!(replacement_order(&trigger, 42, 3, Price(1), BaseAmount(1)).is_err());
+    }
+}
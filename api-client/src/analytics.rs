@@ -0,0 +1,129 @@
+//! Market data indicators.
+//!
+//! Order-book imbalance and microprice read an `OrderBook`; `FundingZScore` tracks a rolling
+//! window of funding rates. All of them are plain computations on data the caller already
+//! holds, so they can be evaluated on every book update.
+
+use crate::OrderBook;
+use std::collections::VecDeque;
+
+/// Size imbalance of the top `levels` of each side, in `[-1, 1]`
+///
+/// `(bid size - ask size) / (bid size + ask size)`: positive when bids outweigh asks.
+/// `None` if both sides are empty over those levels.
+pub fn imbalance(book: &OrderBook, levels: usize) -> Option<f64> {
+    let (bids, asks) = book.depth(levels);
+    let bid_size: f64 = bids.iter().map(|l| l.size).sum();
+    let ask_size: f64 = asks.iter().map(|l| l.size).sum();
+    let total = bid_size + ask_size;
+    (total > 0.0).then(|| (bid_size - ask_size) / total)
+}
+
+/// Mid price weighted by the opposite top-of-book size
+///
+/// `(bid * ask size + ask * bid size) / (bid size + ask size)`: leans towards the ask when
+/// the bid is heavier, since the ask is then more likely to trade next. `None` unless both
+/// sides have a level.
+pub fn microprice(book: &OrderBook) -> Option<f64> {
+    let bid = book.best_bid()?;
+    let ask = book.best_ask()?;
+    Some((bid.price * ask.size + ask.price * bid.size) / (bid.size + ask.size))
+}
+
+/// Z-score of the latest funding rate against a rolling window of past rates.
+#[derive(Debug, Clone)]
+pub struct FundingZScore {
+    window: usize,
+    rates: VecDeque<f64>,
+}
+
+impl FundingZScore {
+    /// Tracks the last `window` rates (at least 2).
+    pub fn new(window: usize) -> Self {
+        let window = window.max(2);
+        Self {
+            window,
+            rates: VecDeque::with_capacity(window),
+        }
+    }
+
+    /// Adds a funding rate, dropping the oldest one once the window is full, and returns
+    /// the new z-score.
+    pub fn push(&mut self, rate: f64) -> Option<f64> {
+        if self.rates.len() == self.window {
+            self.rates.pop_front();
+        }
+        self.rates.push_back(rate);
+        self.z_score()
+    }
+
+    /// Mean of the rates in the window.
+    pub fn mean(&self) -> Option<f64> {
+        (!self.rates.is_empty()).then(|| self.rates.iter().sum::<f64>() / self.rates.len() as f64)
+    }
+
+    /// Sample standard deviation of the rates in the window.
+    pub fn std_dev(&self) -> Option<f64> {
+        if self.rates.len() < 2 {
+            return None;
+        }
+        let mean = self.mean()?;
+        let variance =
+            self.rates.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (self.rates.len() - 1) as f64;
+        Some(variance.sqrt())
+    }
+
+    /// How many standard deviations the latest rate is from the window mean
+    ///
+    /// `None` with fewer than two rates or when all rates are equal.
+    pub fn z_score(&self) -> Option<f64> {
+        let std_dev = self.std_dev().filter(|s| *s > 0.0)?;
+        Some((self.rates.back()? - self.mean()?) / std_dev)
+    }
+
+    /// Whether the window holds `window` rates.
+    pub fn is_full(&self) -> bool {
+        self.rates.len() == self.window
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_book_indicators() {
+        let book = OrderBook::from_snapshot(
+            0,
+            &json!({"order_book": {
+                "bids": [{"price": "99", "size": "3"}, {"price": "98", "size": "1"}],
+                "asks": [{"price": "101", "size": "1"}, {"price": "102", "size": "3"}],
+            }}),
+        )
+        .unwrap();
+        assert_eq!(imbalance(&book, 1), Some(0.5));
+        assert_eq!(imbalance(&book, 2), Some(0.0));
+        // Heavier bid pulls the microprice towards the ask: (99 * 1 + 101 * 3) / 4
+        assert_eq!(microprice(&book), Some(100.5));
+
+        let empty = OrderBook::new(0);
+        assert_eq!(imbalance(&empty, 5), None);
+        assert_eq!(microprice(&empty), None);
+    }
+
+    #[test]
+    fn test_funding_z_score() {
+        let mut funding = FundingZScore::new(4);
+        assert_eq!(funding.push(0.01), None);
+        assert_eq!(funding.push(0.01), None);
+        funding.push(0.01);
+        funding.push(0.01);
+        assert!(funding.is_full());
+
+        // Window is now [0.01, 0.01, 0.01, 0.05]: mean 0.02, sample std dev 0.02
+        let z = funding.push(0.05).unwrap();
+        assert!((z - 1.5).abs() < 1e-9);
+        assert!((funding.mean().unwrap() - 0.02).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,183 @@
+//! API key inspection.
+//!
+//! An account has up to 255 API key slots. Signing with a key registered under a different
+//! index only shows up as an invalid signature, so `find_my_key_index` looks up which slot
+//! actually holds the local public key.
+//!
+//! `rotate_api_key` replaces the key of the client's slot. The exchange applies a
+//! ChangePubKey some time after accepting it, and until then only the old key signs valid
+//! transactions, so the client keeps signing with the old key until the exchange reports the
+//! new one and only then switches.
+
+use crate::{parse_response, ApiError, LighterClient, PublicKey, Result};
+use serde::{Deserialize, Serialize};
+use signer::KeyManager;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How often `rotate_api_key` checks whether the new key is registered.
+const ROTATION_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// How long `rotate_api_key` waits for the exchange to report the new key.
+pub const ROTATION_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Whether an API key slot holds a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiKeyStatus {
+    Registered,
+    /// The slot exists but no public key has been set.
+    Empty,
+}
+
+/// One API key slot of an account.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict", serde(deny_unknown_fields))]
+pub struct ApiKeyInfo {
+    #[serde(default)]
+    pub account_index: i64,
+    pub api_key_index: u8,
+    /// Hex-encoded 40-byte public key, with or without `0x`.
+    #[serde(default)]
+    pub public_key: String,
+    /// Next nonce of this key.
+    #[serde(default)]
+    pub nonce: i64,
+}
+
+impl ApiKeyInfo {
+    pub fn status(&self) -> ApiKeyStatus {
+        let key = self.public_key.trim_start_matches("0x");
+        if key.is_empty() || key.bytes().all(|b| b == b'0') {
+            ApiKeyStatus::Empty
+        } else {
+            ApiKeyStatus::Registered
+        }
+    }
+
+    /// The registered key in canonical form, if the slot holds a valid one.
+    pub fn parsed_public_key(&self) -> Option<PublicKey> {
+        PublicKey::from_hex(&self.public_key).ok()
+    }
+
+    /// Returns `true` if this slot holds `public_key` (compared in canonical form).
+    pub fn matches(&self, public_key: &PublicKey) -> bool {
+        self.parsed_public_key().as_ref() == Some(public_key)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct ApiKeysResponse {
+    #[serde(default)]
+    api_keys: Vec<ApiKeyInfo>,
+}
+
+impl LighterClient {
+    /// List the API key slots of this account
+    pub async fn get_api_keys(&self) -> Result<Vec<ApiKeyInfo>> {
+        let response_json = self
+            .get_authenticated_json(
+                "/api/v1/apikeys",
+                &[
+                    ("account_index", self.account_index.to_string()),
+                    // 255 lists every key index
+                    ("api_key_index", "255".to_string()),
+                ],
+            )
+            .await?;
+        let response: ApiKeysResponse = self.parse_typed("/api/v1/apikeys", response_json)?;
+
+        Ok(response.api_keys)
+    }
+
+    /// Find the API key index registered with the local private key
+    ///
+    /// Returns `None` if no slot of this account holds the local public key.
+    pub async fn find_my_key_index(&self) -> Result<Option<u8>> {
+        let public_key = self.signer().public_key();
+        let keys = self.get_api_keys().await?;
+
+        Ok(keys
+            .iter()
+            .find(|k| k.status() == ApiKeyStatus::Registered && k.matches(&public_key))
+            .map(|k| k.api_key_index))
+    }
+
+    /// Replace the API key of this client's slot
+    ///
+    /// Registers `new_key` (a freshly generated key if `None`) with a ChangePubKey signed by
+    /// the current key, waits until the exchange reports it for this `api_key_index`, then
+    /// switches the client to it and refetches the nonce. Returns the new public key.
+    ///
+    /// Fails if the exchange rejects the change or does not report the new key within
+    /// `ROTATION_TIMEOUT`; the client then keeps the old key. The change may still be applied
+    /// later, and a generated key is lost with the error, so pass a key you can recover
+    /// (e.g. from `KeyManager::derive_api_key`) when that matters.
+    pub async fn rotate_api_key(&self, new_key: Option<KeyManager>) -> Result<PublicKey> {
+        let new_key = new_key.unwrap_or_else(KeyManager::generate);
+        let public_key = new_key.public_key();
+
+        let response = self.change_api_key(&public_key.to_bytes()).await?;
+        if response["code"].as_i64() != Some(200) {
+            return Err(ApiError::Api(format!("Change API key rejected: {}", response)));
+        }
+
+        let started = Instant::now();
+        while self.registered_public_key().await?.as_ref() != Some(&public_key) {
+            if started.elapsed() + ROTATION_POLL_INTERVAL > ROTATION_TIMEOUT {
+                return Err(ApiError::Api(format!(
+                    "Timed out after {:?} waiting for API key {} to report {}",
+                    ROTATION_TIMEOUT, self.api_key_index, public_key
+                )));
+            }
+            tokio::time::sleep(ROTATION_POLL_INTERVAL).await;
+        }
+
+        *self.signer.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(new_key);
+        let fetched = self.fetch_nonce_from_api().await?;
+        self.nonces.resync(fetched);
+        tracing::info!(target: "api_client::api_keys", api_key_index = self.api_key_index, public_key = %public_key, "API key rotated");
+
+        Ok(public_key)
+    }
+
+    /// Public key the exchange holds for this client's slot, `None` if the slot is unknown or empty.
+    pub(crate) async fn registered_public_key(&self) -> Result<Option<PublicKey>> {
+        let response = self
+            .client
+            .get(format!("{}/api/v1/apiKey", self.base_url))
+            .query(&[
+                ("account_index", self.account_index.to_string()),
+                ("api_key_index", self.api_key_index.to_string()),
+            ])
+            .send()
+            .await?;
+        let response_json = parse_response(response).await?;
+
+        Ok(response_json["public_key"].as_str().and_then(|key| PublicKey::from_hex(key).ok()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_api_keys() {
+        let key = signer::KeyManager::generate().public_key();
+        let raw = format!(
+            r#"{{"code": 200, "api_keys": [
+                {{"account_index": 7, "api_key_index": 2, "nonce": 15, "public_key": "0x{}"}},
+                {{"account_index": 7, "api_key_index": 3, "nonce": 0, "public_key": "{}"}}
+            ]}}"#,
+            key.to_hex().to_uppercase(),
+            "0".repeat(80)
+        );
+
+        let response: ApiKeysResponse = serde_json::from_str(&raw).unwrap();
+        let keys = response.api_keys;
+        assert_eq!(keys[0].status(), ApiKeyStatus::Registered);
+        assert!(keys[0].matches(&key));
+        assert!(!keys[0].matches(&signer::KeyManager::generate().public_key()));
+        assert_eq!(keys[1].status(), ApiKeyStatus::Empty);
+        assert_eq!(keys[1].nonce, 0);
+    }
+}
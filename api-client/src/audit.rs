@@ -0,0 +1,193 @@
+//! Audit log of signed transactions.
+//!
+//! With an `AuditSink` configured, the client reports every transaction it signs (time,
+//! type, nonce, signed hash, payload and signature) before the transaction can be sent,
+//! whether or not it is posted afterwards. This is a record of what the key signed, kept
+//! apart from the exchange responses tracked by the journal.
+//!
+//! `FileAuditSink` appends JSON lines that form a hash chain: each line carries the SHA3-256
+//! digest of the line before it, so editing, removing or reordering a record breaks the
+//! chain, which `FileAuditSink::verify` detects.
+
+use crate::{ApiError, Result, SignedTx};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha3::{Digest, Sha3_256};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A signed transaction, as reported to an `AuditSink`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AuditRecord {
+    /// Signing time (ms since the epoch).
+    pub timestamp: i64,
+    pub account_index: i64,
+    pub api_key_index: u8,
+    pub tx_type: u32,
+    /// Hex of the Poseidon2 digest that was signed.
+    pub tx_hash: String,
+    pub nonce: i64,
+    /// Signed `tx_info`, `Sig` included.
+    pub tx_info: Value,
+    /// Base64 Schnorr signature (s || e), as in `Sig`.
+    pub signature: String,
+}
+
+impl AuditRecord {
+    /// Record of a transaction signed by the key of `account_index` / `api_key_index`
+    pub fn new(account_index: i64, api_key_index: u8, signed: &SignedTx) -> Result<Self> {
+        Ok(Self {
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64,
+            account_index,
+            api_key_index,
+            tx_type: signed.tx_type,
+            tx_hash: signed.tx_hash_hex(),
+            nonce: signed.tx_info["Nonce"].as_i64().unwrap_or(-1),
+            tx_info: signed.tx_info.clone(),
+            signature: signed.tx_info["Sig"].as_str().unwrap_or_default().to_string(),
+        })
+    }
+}
+
+/// Destination of audit records.
+///
+/// `record` is called synchronously after signing; an error fails the operation, so a
+/// transaction is never sent without its record.
+pub trait AuditSink: Send + Sync {
+    fn record(&self, record: &AuditRecord) -> Result<()>;
+}
+
+/// One line of a `FileAuditSink` file.
+#[derive(Serialize, Deserialize)]
+struct ChainedRecord {
+    /// SHA3-256 of the previous line (hex); all zeros for the first one.
+    prev_hash: String,
+    #[serde(flatten)]
+    record: AuditRecord,
+}
+
+/// Audit log in an append-only, hash-chained JSON-lines file.
+///
+/// Every record is synced to disk before `record` returns.
+pub struct FileAuditSink {
+    state: Mutex<ChainState>,
+}
+
+struct ChainState {
+    file: File,
+    last_hash: [u8; 32],
+}
+
+impl FileAuditSink {
+    /// Open (or create) the log at `path` and continue its chain
+    ///
+    /// Fails if the file ends in a partial line, which needs to be looked at before
+    /// anything is appended.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(path.as_ref())
+            .map_err(io_error)?;
+
+        let mut contents = String::new();
+        file.read_to_string(&mut contents).map_err(io_error)?;
+        if !contents.is_empty() && !contents.ends_with('\n') {
+            return Err(ApiError::Audit(format!(
+                "{} ends in a partial record",
+                path.as_ref().display()
+            )));
+        }
+        let last_hash = contents.lines().last().map(line_hash).unwrap_or([0; 32]);
+
+        Ok(Self {
+            state: Mutex::new(ChainState { file, last_hash }),
+        })
+    }
+
+    /// Check the hash chain of the log at `path` and return its records
+    ///
+    /// Fails with `ApiError::Audit` naming the first line that does not parse or does not
+    /// chain to the line before it.
+    pub fn verify(path: impl AsRef<Path>) -> Result<Vec<AuditRecord>> {
+        let contents = std::fs::read_to_string(path).map_err(io_error)?;
+        let mut prev_hash = [0; 32];
+        let mut records = Vec::new();
+        for (i, line) in contents.lines().enumerate() {
+            let chained: ChainedRecord = serde_json::from_str(line)
+                .map_err(|e| ApiError::Audit(format!("Line {} is not an audit record: {}", i + 1, e)))?;
+            if chained.prev_hash != hex::encode(prev_hash) {
+                return Err(ApiError::Audit(format!("Hash chain broken at line {}", i + 1)));
+            }
+            prev_hash = line_hash(line);
+            records.push(chained.record);
+        }
+        Ok(records)
+    }
+}
+
+impl AuditSink for FileAuditSink {
+    fn record(&self, record: &AuditRecord) -> Result<()> {
+        let mut state = self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let line = serde_json::to_string(&ChainedRecord {
+            prev_hash: hex::encode(state.last_hash),
+            record: record.clone(),
+        })?;
+        state.file.write_all(format!("{}\n", line).as_bytes()).map_err(io_error)?;
+        state.file.sync_data().map_err(io_error)?;
+        state.last_hash = line_hash(&line);
+        Ok(())
+    }
+}
+
+fn line_hash(line: &str) -> [u8; 32] {
+    Sha3_256::digest(line.as_bytes()).into()
+}
+
+fn io_error(e: std::io::Error) -> ApiError {
+    ApiError::Audit(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LighterClient;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_file_sink_chain() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+        let private_key = "bda332f3aaa2d9cfdd8920830ea37efce9636c671a426bd4cb9815007e2a2917604ab47857cbb200";
+        let client = |sink: FileAuditSink| {
+            LighterClient::builder("https://mainnet.zklighter.elliot.ai".to_string(), private_key, 7, 3)
+                .audit_sink(Arc::new(sink))
+                .build()
+                .unwrap()
+        };
+        let cancel = |nonce: i64| {
+            json!({"AccountIndex": 7, "ApiKeyIndex": 3, "MarketIndex": 0, "Index": 42,
+                "ExpiredAt": 1_700_000_000_000i64, "Nonce": nonce, "Sig": ""})
+        };
+
+        let signed = client(FileAuditSink::open(&path).unwrap()).sign_tx_info(cancel(10), 15).await.unwrap();
+        // A reopened log continues the chain
+        client(FileAuditSink::open(&path).unwrap()).sign_tx_info(cancel(11), 15).await.unwrap();
+
+        let records = FileAuditSink::verify(&path).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].tx_hash, signed.tx_hash_hex());
+        assert_eq!(records[0].signature, signed.tx_info["Sig"].as_str().unwrap());
+        assert_eq!((records[1].nonce, records[1].tx_type, records[1].account_index), (11, 15, 7));
+
+        // Changing an earlier record breaks the link to the next one
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, contents.replacen("\"Index\":42", "\"Index\":43", 1)).unwrap();
+        assert!(matches!(FileAuditSink::verify(&path), Err(ApiError::Audit(msg)) if msg.contains("line 2")));
+    }
+}
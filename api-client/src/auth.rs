@@ -0,0 +1,73 @@
+//! Authenticated read endpoints.
+//!
+//! Account-scoped GET endpoints expect an auth token (`deadline:account:key:signature`)
+//! in both the `Authorization` and `Auth` headers. `authenticated_get` builds such a
+//! request with a fresh token valid for the client's configured token lifetime.
+
+use crate::{parse_response, ApiError, LighterClient, Result};
+use reqwest::RequestBuilder;
+use serde_json::Value;
+use std::time::Duration;
+
+/// Default auth token lifetime.
+pub const DEFAULT_AUTH_TOKEN_LIFETIME: Duration = Duration::from_secs(600);
+
+impl LighterClient {
+    /// Lifetime of the auth tokens attached to authenticated requests
+    pub fn auth_token_lifetime(&self) -> Duration {
+        self.auth_token_lifetime
+    }
+
+    /// GET request to `path` (relative to the base URL) carrying a fresh auth token
+    pub(crate) async fn authenticated_get(&self, path: &str) -> Result<RequestBuilder> {
+        let auth_token = self.create_auth_token(self.auth_token_lifetime.as_secs() as i64).await?;
+        Ok(self
+            .client
+            .get(format!("{}{}", self.base_url, path))
+            .header("Authorization", &auth_token)
+            .header("Auth", &auth_token))
+    }
+
+    /// Authenticated GET returning the JSON body; a non-200 `code` becomes `ApiError::Api`
+    pub(crate) async fn get_authenticated_json(&self, path: &str, query: &[(&str, String)]) -> Result<Value> {
+        let request = self.authenticated_get(path).await?.query(query);
+
+        let response_json = self.guarded(path, async { parse_response(request.send().await?).await }).await?;
+        if let Some(code) = response_json["code"].as_i64() {
+            if code != 200 {
+                return Err(ApiError::Api(format!("Request to {} failed: {}", path, response_json)));
+            }
+        }
+
+        Ok(response_json)
+    }
+
+    /// Get the open orders of this account on a market
+    pub async fn get_active_orders(&self, market_id: u8) -> Result<Value> {
+        self.get_authenticated_json(
+            "/api/v1/accountActiveOrders",
+            &[
+                ("account_index", self.account_index.to_string()),
+                ("market_id", market_id.to_string()),
+            ],
+        )
+        .await
+    }
+
+    /// Get the most recent trades of this account
+    ///
+    /// # Arguments
+    /// * `market_id` - Restrict to one market, or `None` for all
+    /// * `limit` - Maximum number of trades returned
+    pub async fn get_trades(&self, market_id: Option<u8>, limit: u32) -> Result<Value> {
+        let mut query = vec![
+            ("account_index", self.account_index.to_string()),
+            ("sort_by", "timestamp".to_string()),
+            ("limit", limit.to_string()),
+        ];
+        if let Some(market_id) = market_id {
+            query.push(("market_id", market_id.to_string()));
+        }
+        self.get_authenticated_json("/api/v1/trades", &query).await
+    }
+}
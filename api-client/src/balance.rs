@@ -0,0 +1,90 @@
+//! Account balance summary.
+//!
+//! The account endpoint reports balances as decimal strings on the account object and
+//! per position. `BalanceSummary` reads the figures sizing and risk code needs, in quote
+//! currency (USDC).
+
+use crate::positions::account_entry;
+use crate::{ApiError, LighterClient, Result};
+use serde_json::Value;
+
+/// Collateral and margin usage of an account, in USDC.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BalanceSummary {
+    /// Total account value: collateral plus unrealized PnL (`total_asset_value`).
+    pub equity: f64,
+    /// Deposited collateral (`collateral`).
+    pub collateral: f64,
+    /// Collateral free to open positions or withdraw (`available_balance`).
+    pub available_balance: f64,
+    /// Initial margin of the open positions: position value times its initial margin fraction.
+    pub total_margin: f64,
+    /// Unrealized PnL summed over positions.
+    pub unrealized_pnl: f64,
+}
+
+impl BalanceSummary {
+    /// Reads the summary from a `get_account` response.
+    pub fn from_account(account_info: &Value) -> Result<Self> {
+        let account = account_entry(account_info);
+        let amount = |value: &Value, field: &str| {
+            decimal(&value[field]).ok_or_else(|| ApiError::Api(format!("Account without {}: {}", field, value)))
+        };
+
+        let mut total_margin = 0.0;
+        let mut unrealized_pnl = 0.0;
+        for position in account["positions"].as_array().into_iter().flatten() {
+            unrealized_pnl += decimal(&position["unrealized_pnl"]).unwrap_or(0.0);
+            let value = decimal(&position["position_value"]).unwrap_or(0.0).abs();
+            let fraction_percent = decimal(&position["initial_margin_fraction"]).unwrap_or(0.0);
+            total_margin += value * fraction_percent / 100.0;
+        }
+
+        let collateral = amount(account, "collateral")?;
+        Ok(Self {
+            equity: decimal(&account["total_asset_value"]).unwrap_or(collateral + unrealized_pnl),
+            collateral,
+            available_balance: amount(account, "available_balance")?,
+            total_margin,
+            unrealized_pnl,
+        })
+    }
+}
+
+/// A decimal string (or number) as `f64`.
+pub(crate) fn decimal(value: &Value) -> Option<f64> {
+    value.as_str().and_then(|s| s.trim().parse().ok()).or_else(|| value.as_f64())
+}
+
+impl LighterClient {
+    /// Equity, available balance, margin in use and unrealized PnL of the account
+    pub async fn get_balance_summary(&self) -> Result<BalanceSummary> {
+        BalanceSummary::from_account(&self.get_account().await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_summary_from_account() {
+        let account = json!({"code": 200, "total": 1, "accounts": [{
+            "collateral": "1000.000000",
+            "available_balance": "700.000000",
+            "total_asset_value": "1015.500000",
+            "positions": [
+                {"market_id": 0, "position_value": "1500.00", "initial_margin_fraction": "10.00", "unrealized_pnl": "20.5"},
+                {"market_id": 1, "position_value": "-1000.00", "initial_margin_fraction": "20.00", "unrealized_pnl": "-5"},
+            ],
+        }]});
+        let summary = BalanceSummary::from_account(&account).unwrap();
+        assert_eq!(summary.equity, 1015.5);
+        assert_eq!(summary.available_balance, 700.0);
+        assert!((summary.total_margin - 350.0).abs() < 1e-9);
+        assert!((summary.unrealized_pnl - 15.5).abs() < 1e-9);
+
+        assert!(BalanceSummary::from_account(&json!({"accounts": [{}]})).is_err());
+    }
+}
@@ -0,0 +1,416 @@
+//! OHLCV bars from the trade stream.
+//!
+//! `BarAggregator` folds trades into open/high/low/close/volume bars of one second or one
+//! minute per market. It can be driven by hand (`push` each trade, `flush` on a clock), or
+//! run as a task over a channel of trades with `spawn`, for example fed from a websocket
+//! `trade` subscription. `stream_bars` polls a market's recent trades instead and runs the
+//! same aggregation, so strategies get bars without keeping their own trade plumbing.
+//!
+//! A bar covers `[start_ms, start_ms + interval)` by trade timestamp and is emitted once a
+//! trade of a later bar arrives or the clock passes its end plus the grace period. Intervals
+//! without trades produce no bar. Trades are deduplicated by trade id per market, so
+//! overlapping polls are harmless; a trade older than the open bar is dropped, since its
+//! bar was already emitted.
+
+use crate::decimal::{parse_decimal, Decimal};
+use crate::{parse_response, ApiError, LighterClient, Result};
+use futures_core::Stream;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Trades requested per poll by `stream_bars`.
+const TRADES_PER_POLL: u32 = 100;
+
+/// A trade of a market, from `recentTrades` or a websocket trade message.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TradeTick {
+    pub market_index: u8,
+    pub trade_id: i64,
+    /// Milliseconds since the Unix epoch.
+    pub timestamp_ms: i64,
+    pub price: Decimal,
+    /// Size in base currency.
+    pub size: Decimal,
+}
+
+impl TradeTick {
+    /// Reads a trade entry (`trade_id`, `market_id`, `price`, `size`, `timestamp`); `None`
+    /// if a field is missing.
+    pub fn from_json(trade: &Value) -> Option<Self> {
+        Some(Self {
+            market_index: u8::try_from(trade["market_id"].as_u64()?).ok()?,
+            trade_id: trade["trade_id"].as_i64()?,
+            timestamp_ms: trade["timestamp"].as_i64()?,
+            price: parse_decimal(&trade["price"])?,
+            size: parse_decimal(&trade["size"])?,
+        })
+    }
+}
+
+/// Length of a bar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub enum BarInterval {
+    OneSecond,
+    OneMinute,
+}
+
+impl BarInterval {
+    pub fn millis(self) -> i64 {
+        match self {
+            BarInterval::OneSecond => 1_000,
+            BarInterval::OneMinute => 60_000,
+        }
+    }
+
+    /// Start of the bar containing `timestamp_ms`.
+    fn bar_start(self, timestamp_ms: i64) -> i64 {
+        timestamp_ms - timestamp_ms.rem_euclid(self.millis())
+    }
+}
+
+/// Trades of one market over one interval.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Bar {
+    pub market_index: u8,
+    pub interval: BarInterval,
+    /// Milliseconds since the Unix epoch, a multiple of the interval.
+    pub start_ms: i64,
+    pub open: Decimal,
+    pub high: Decimal,
+    pub low: Decimal,
+    pub close: Decimal,
+    /// Traded size in base currency.
+    pub volume: Decimal,
+    /// Number of trades.
+    pub trades: u32,
+}
+
+impl Bar {
+    fn new(trade: &TradeTick, interval: BarInterval, start_ms: i64) -> Self {
+        Self {
+            market_index: trade.market_index,
+            interval,
+            start_ms,
+            open: trade.price,
+            high: trade.price,
+            low: trade.price,
+            close: trade.price,
+            volume: trade.size,
+            trades: 1,
+        }
+    }
+
+    /// End of the bar, exclusive.
+    pub fn end_ms(&self) -> i64 {
+        self.start_ms + self.interval.millis()
+    }
+
+    fn add(&mut self, trade: &TradeTick) {
+        self.high = self.high.max(trade.price);
+        self.low = self.low.min(trade.price);
+        self.close = trade.price;
+        self.volume += trade.size;
+        self.trades += 1;
+    }
+}
+
+/// Builds bars of the configured intervals from trades of any number of markets.
+#[derive(Debug, Clone)]
+pub struct BarAggregator {
+    intervals: Vec<BarInterval>,
+    grace: Duration,
+    open: BTreeMap<(u8, BarInterval), Bar>,
+    /// End of the last bar emitted per market and interval; older trades are late.
+    closed_until: HashMap<(u8, BarInterval), i64>,
+    last_trade_id: HashMap<u8, i64>,
+}
+
+impl BarAggregator {
+    /// Bars of each of `intervals`, emitted as soon as the clock passes their end.
+    pub fn new(intervals: &[BarInterval]) -> Self {
+        let mut intervals = intervals.to_vec();
+        intervals.sort();
+        intervals.dedup();
+        Self {
+            intervals,
+            grace: Duration::ZERO,
+            open: BTreeMap::new(),
+            closed_until: HashMap::new(),
+            last_trade_id: HashMap::new(),
+        }
+    }
+
+    /// Keep a bar open for `grace` past its end, for trades that arrive late (e.g. up to a
+    /// poll interval when trades are polled).
+    pub fn with_grace(mut self, grace: Duration) -> Self {
+        self.grace = grace;
+        self
+    }
+
+    /// Add a trade; returns the bars it closed.
+    pub fn push(&mut self, trade: &TradeTick) -> Vec<Bar> {
+        let last = self.last_trade_id.entry(trade.market_index).or_insert(i64::MIN);
+        if trade.trade_id <= *last {
+            return Vec::new();
+        }
+        *last = trade.trade_id;
+
+        let mut closed = Vec::new();
+        for &interval in &self.intervals {
+            let key = (trade.market_index, interval);
+            let start_ms = interval.bar_start(trade.timestamp_ms);
+            if self.closed_until.get(&key).is_some_and(|&until| start_ms < until) {
+                continue;
+            }
+            match self.open.get_mut(&key) {
+                Some(bar) if bar.start_ms == start_ms => bar.add(trade),
+                Some(bar) if bar.start_ms > start_ms => {}
+                Some(bar) => closed.push(std::mem::replace(bar, Bar::new(trade, interval, start_ms))),
+                None => {
+                    self.open.insert(key, Bar::new(trade, interval, start_ms));
+                }
+            }
+        }
+        self.mark_closed(&closed);
+        closed
+    }
+
+    /// Close the bars that ended, plus the grace period, by `now_ms`.
+    pub fn flush(&mut self, now_ms: i64) -> Vec<Bar> {
+        let grace = self.grace.as_millis() as i64;
+        let due: Vec<_> = self
+            .open
+            .iter()
+            .filter(|(_, bar)| bar.end_ms() + grace <= now_ms)
+            .map(|(key, _)| *key)
+            .collect();
+        let closed: Vec<Bar> = due.iter().filter_map(|key| self.open.remove(key)).collect();
+        self.mark_closed(&closed);
+        closed
+    }
+
+    fn mark_closed(&mut self, closed: &[Bar]) {
+        for bar in closed {
+            self.closed_until.insert((bar.market_index, bar.interval), bar.end_ms());
+        }
+    }
+
+    /// When the next open bar is due to close, in milliseconds since the Unix epoch.
+    fn next_close_ms(&self) -> Option<i64> {
+        let grace = self.grace.as_millis() as i64;
+        self.open.values().map(|bar| bar.end_ms() + grace).min()
+    }
+
+    /// Aggregate the trades received on `trades` in a task, streaming bars as they close
+    ///
+    /// The stream ends once `trades` is closed and the open bars are flushed.
+    pub fn spawn(self, trades: mpsc::Receiver<TradeTick>) -> BarStream {
+        let (sender, receiver) = mpsc::channel(256);
+        let task = tokio::spawn(run(self, trades, sender));
+        BarStream {
+            receiver,
+            tasks: vec![task],
+        }
+    }
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as i64).unwrap_or_default()
+}
+
+async fn run(mut aggregator: BarAggregator, mut trades: mpsc::Receiver<TradeTick>, sender: mpsc::Sender<Result<Bar>>) {
+    loop {
+        let wait = aggregator
+            .next_close_ms()
+            .map(|close_ms| Duration::from_millis(close_ms.saturating_sub(now_ms()).max(0) as u64));
+
+        let closed = tokio::select! {
+            trade = trades.recv() => match trade {
+                Some(trade) => aggregator.push(&trade),
+                None => aggregator.flush(i64::MAX),
+            },
+            _ = tokio::time::sleep(wait.unwrap_or_default()), if wait.is_some() => aggregator.flush(now_ms()),
+        };
+        for bar in closed {
+            if sender.send(Ok(bar)).await.is_err() {
+                return;
+            }
+        }
+        if trades.is_closed() && aggregator.open.is_empty() {
+            return;
+        }
+    }
+}
+
+/// Stream of closed `Bar`s from `BarAggregator::spawn` or `stream_bars`.
+///
+/// A failed poll yields its error and polling continues. Dropping the stream stops it.
+pub struct BarStream {
+    receiver: mpsc::Receiver<Result<Bar>>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl Stream for BarStream {
+    type Item = Result<Bar>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+impl BarStream {
+    /// Wait for the next bar; `None` once the trades have ended
+    pub async fn next_bar(&mut self) -> Option<Result<Bar>> {
+        self.receiver.recv().await
+    }
+}
+
+impl Drop for BarStream {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+impl LighterClient {
+    /// Most recent trades of a market, oldest first
+    pub async fn get_recent_trades(&self, market_index: u8, limit: u32) -> Result<Vec<TradeTick>> {
+        let response = self
+            .client
+            .get(format!("{}/api/v1/recentTrades", self.base_url))
+            .query(&[("market_id", market_index.to_string()), ("limit", limit.to_string())])
+            .send()
+            .await?;
+
+        let response_json = parse_response(response).await?;
+        if response_json["code"].as_i64().is_some_and(|code| code != 200) {
+            return Err(ApiError::Api(format!("Recent trades request failed: {}", response_json)));
+        }
+
+        let mut trades: Vec<TradeTick> = response_json["trades"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(TradeTick::from_json)
+            .collect();
+        trades.sort_by_key(|trade| trade.trade_id);
+        Ok(trades)
+    }
+
+    /// Poll the trades of `market_index` every `poll_interval` and stream bars of `intervals`
+    ///
+    /// Bars close `poll_interval` after their end, so trades of the last poll still count.
+    pub fn stream_bars(self: &Arc<Self>, market_index: u8, intervals: &[BarInterval], poll_interval: Duration) -> BarStream {
+        let (trade_sender, trade_receiver) = mpsc::channel(TRADES_PER_POLL as usize);
+        let (sender, receiver) = mpsc::channel(256);
+        let aggregator = BarAggregator::new(intervals).with_grace(poll_interval);
+        let aggregation = tokio::spawn(run(aggregator, trade_receiver, sender.clone()));
+        let client = Arc::clone(self);
+
+        let poller = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(poll_interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                match client.get_recent_trades(market_index, TRADES_PER_POLL).await {
+                    Ok(trades) => {
+                        for trade in trades {
+                            if trade_sender.send(trade).await.is_err() {
+                                return;
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        if sender.send(Err(e)).await.is_err() {
+                            return;
+                        }
+                    }
+                }
+            }
+        });
+        BarStream {
+            receiver,
+            tasks: vec![aggregation, poller],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn trade(trade_id: i64, timestamp_ms: i64, price: i64, size: i64) -> TradeTick {
+        TradeTick {
+            market_index: 0,
+            trade_id,
+            timestamp_ms,
+            price: Decimal::from(price),
+            size: Decimal::from(size),
+        }
+    }
+
+    #[test]
+    fn test_bars_from_trades() {
+        let mut bars = BarAggregator::new(&[BarInterval::OneMinute, BarInterval::OneSecond]);
+        assert!(bars.push(&trade(1, 60_000, 100, 1)).is_empty());
+        assert!(bars.push(&trade(2, 60_400, 103, 2)).is_empty());
+        assert!(bars.push(&trade(2, 60_400, 103, 2)).is_empty()); // seen in an earlier poll
+        assert!(bars.push(&trade(3, 60_900, 99, 1)).is_empty());
+
+        // The next second closes the first 1s bar
+        let closed = bars.push(&trade(4, 61_200, 101, 4));
+        let expected = Bar {
+            market_index: 0,
+            interval: BarInterval::OneSecond,
+            start_ms: 60_000,
+            open: Decimal::from(100),
+            high: Decimal::from(103),
+            low: Decimal::from(99),
+            close: Decimal::from(99),
+            volume: Decimal::from(4),
+            trades: 3,
+        };
+        assert_eq!(closed, vec![expected]);
+
+        // A trade of the closed second is late and dropped from the 1s bars only
+        assert!(bars.push(&trade(5, 60_950, 120, 1)).is_empty());
+
+        // Quiet seconds emit no bars; the clock closes the open ones
+        assert!(bars.flush(61_999).is_empty());
+        let closed = bars.flush(120_000);
+        let summary: Vec<_> = closed.iter().map(|bar| (bar.interval, bar.start_ms, bar.trades)).collect();
+        assert_eq!(summary, vec![(BarInterval::OneSecond, 61_000, 1), (BarInterval::OneMinute, 60_000, 5)]);
+        let minute = &closed[1];
+        assert_eq!((minute.high, minute.close, minute.volume), (Decimal::from(120), Decimal::from(120), Decimal::from(9)));
+
+        let tick = TradeTick::from_json(&json!({
+            "trade_id": 9, "market_id": 1, "price": "3001.25", "size": "0.0500", "timestamp": 1_700_000_000_123i64
+        }))
+        .unwrap();
+        assert_eq!((tick.market_index, tick.price), (1, Decimal::new(300_125, 2)));
+        assert!(TradeTick::from_json(&json!({"trade_id": 9})).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_spawned_aggregator_flushes_on_close() {
+        let (sender, receiver) = mpsc::channel(8);
+        let mut stream = BarAggregator::new(&[BarInterval::OneMinute]).spawn(receiver);
+        sender.send(trade(1, 60_000, 100, 1)).await.unwrap();
+        sender.send(trade(2, 120_000, 101, 1)).await.unwrap();
+        drop(sender);
+
+        let first = stream.next_bar().await.unwrap().unwrap();
+        let second = stream.next_bar().await.unwrap().unwrap();
+        assert_eq!((first.start_ms, second.start_ms), (60_000, 120_000));
+        assert!(stream.next_bar().await.is_none());
+    }
+}
@@ -0,0 +1,204 @@
+//! Latency budgets and cancellation of client calls.
+//!
+//! Any async method of `LighterClient` can be bounded with `within`, from the `WithBudget`
+//! extension trait, by a deadline, a `CancellationToken`, or both:
+//!
+//! ```ignore
+//! use api_client::{Budget, WithBudget};
+//!
+//! client.create_order(order).within(Duration::from_millis(250)).await?;
+//! client.cancel_all_orders(tif, 0).within(shutdown.clone()).await?;
+//! client.modify_order(request).within(Budget::timeout(budget).with_token(shutdown.clone())).await?;
+//! ```
+//!
+//! When the deadline passes or the token is cancelled first, the call stops at whatever it
+//! was awaiting and fails with `ApiError::DeadlineExceeded` or `ApiError::Cancelled`; a call
+//! over budget before it starts is not started. Stopping drops the call, and with it the
+//! `NonceLease` it holds, so the nonce goes back to the pool for the next transaction.
+//!
+//! A call stopped while its sendTx request was in flight may still have reached the
+//! exchange. The released nonce is then already used: the next transaction leasing it is
+//! rejected for its nonce and replayed after a resync (see `nonce`), and the stopped
+//! transaction's outcome is found with `get_transaction` or the open orders, as for a lost
+//! response.
+
+use crate::{ApiError, Result};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tokio::time::Sleep;
+use tokio_util::sync::{CancellationToken, WaitForCancellationFutureOwned};
+
+/// How long a call may take and what may cancel it; unlimited by default.
+#[derive(Debug, Clone, Default)]
+pub struct Budget {
+    deadline: Option<Instant>,
+    token: Option<CancellationToken>,
+}
+
+impl Budget {
+    /// No deadline and no token.
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    /// A deadline `timeout` from now.
+    pub fn timeout(timeout: Duration) -> Self {
+        Self::deadline(Instant::now() + timeout)
+    }
+
+    pub fn deadline(deadline: Instant) -> Self {
+        Self {
+            deadline: Some(deadline),
+            token: None,
+        }
+    }
+
+    /// Cancelled with `token`, without a deadline.
+    pub fn cancelled_by(token: CancellationToken) -> Self {
+        Self {
+            deadline: None,
+            token: Some(token),
+        }
+    }
+
+    /// Also end at `deadline`; the earlier of two deadlines applies.
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(self.deadline.map_or(deadline, |current| current.min(deadline)));
+        self
+    }
+
+    /// Also cancel with `token`, replacing any previous token (use a child token of both to
+    /// combine them).
+    pub fn with_token(mut self, token: CancellationToken) -> Self {
+        self.token = Some(token);
+        self
+    }
+
+    /// Time left before the deadline, `None` without one.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// Whether the budget is already spent.
+    pub fn is_exhausted(&self) -> bool {
+        self.token.as_ref().is_some_and(CancellationToken::is_cancelled) || self.remaining() == Some(Duration::ZERO)
+    }
+}
+
+impl From<Duration> for Budget {
+    fn from(timeout: Duration) -> Self {
+        Budget::timeout(timeout)
+    }
+}
+
+impl From<Instant> for Budget {
+    fn from(deadline: Instant) -> Self {
+        Budget::deadline(deadline)
+    }
+}
+
+impl From<CancellationToken> for Budget {
+    fn from(token: CancellationToken) -> Self {
+        Budget::cancelled_by(token)
+    }
+}
+
+/// Bounds a client call by a `Budget`.
+pub trait WithBudget: Future + Sized {
+    /// This call, failing with `ApiError::DeadlineExceeded` or `ApiError::Cancelled` once
+    /// `budget` is spent.
+    fn within(self, budget: impl Into<Budget>) -> Within<Self>;
+}
+
+impl<F: Future> WithBudget for F {
+    fn within(self, budget: impl Into<Budget>) -> Within<Self> {
+        let budget = budget.into();
+        Within {
+            future: Box::pin(self),
+            deadline: budget.deadline.map(|deadline| {
+                let sleep = tokio::time::sleep_until(tokio::time::Instant::from_std(deadline));
+                (deadline, Box::pin(sleep))
+            }),
+            cancelled: budget.token.map(|token| Box::pin(token.cancelled_owned())),
+        }
+    }
+}
+
+/// Future returned by `WithBudget::within`.
+pub struct Within<F> {
+    future: Pin<Box<F>>,
+    deadline: Option<(Instant, Pin<Box<Sleep>>)>,
+    cancelled: Option<Pin<Box<WaitForCancellationFutureOwned>>>,
+}
+
+impl<F, T> Future for Within<F>
+where
+    F: Future<Output = Result<T>>,
+{
+    type Output = Result<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        // Checked before the call, so a spent budget never starts it
+        if let Some(cancelled) = this.cancelled.as_mut() {
+            if cancelled.as_mut().poll(cx).is_ready() {
+                return Poll::Ready(Err(ApiError::Cancelled));
+            }
+        }
+        if let Some((deadline, sleep)) = this.deadline.as_mut() {
+            // The timer only fires on a later tick, even for a deadline already past
+            if Instant::now() >= *deadline || sleep.as_mut().poll(cx).is_ready() {
+                return Poll::Ready(Err(ApiError::DeadlineExceeded));
+            }
+        }
+        this.future.as_mut().poll(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nonce::NonceManager;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_budget_stops_the_call_and_releases_its_nonce() {
+        let nonces = NonceManager::default();
+        nonces.init(10);
+        let hanging = |nonces: NonceManager| async move {
+            let _lease = nonces.try_lease().unwrap();
+            std::future::pending::<()>().await;
+            Ok(())
+        };
+
+        let result = hanging(nonces.clone()).within(Duration::from_millis(20)).await;
+        assert!(matches!(result, Err(ApiError::DeadlineExceeded)));
+        assert_eq!(nonces.status().unwrap().next_nonce(), 10);
+
+        let token = CancellationToken::new();
+        let cancel = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            cancel.cancel();
+        });
+        let result = hanging(nonces.clone()).within(Budget::timeout(Duration::from_secs(60)).with_token(token)).await;
+        assert!(matches!(result, Err(ApiError::Cancelled)));
+        assert_eq!(nonces.status().unwrap().next_nonce(), 10);
+
+        // A call within budget returns its own result; a spent budget does not start one
+        assert_eq!(async { Ok(7) }.within(Duration::from_secs(1)).await.unwrap(), 7);
+        let started = Arc::new(AtomicBool::new(false));
+        let flag = started.clone();
+        let result = async move {
+            flag.store(true, Ordering::SeqCst);
+            Ok(())
+        }
+        .within(Instant::now())
+        .await;
+        assert!(matches!(result, Err(ApiError::DeadlineExceeded)));
+        assert!(!started.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,448 @@
+//! `LighterClient` construction with HTTP tuning.
+//!
+//! Latency of the first order after an idle period is dominated by the TCP and TLS
+//! handshakes to the sendTx host. The builder exposes the connection pool and keep-alive
+//! settings of the underlying `reqwest::Client`, and `LighterClient::warm_up` opens
+//! pooled connections ahead of time.
+
+use crate::audit::AuditSink;
+use crate::auth::DEFAULT_AUTH_TOKEN_LIFETIME;
+use crate::circuit::{CircuitBreaker, CircuitBreakerConfig};
+use crate::expiry::ExpiryPolicy;
+use crate::journal::TxJournal;
+use crate::kill_switch::KillSwitch;
+use crate::nonce::{NonceManager, NonceResyncHook};
+use crate::notifier::Notifier;
+use crate::retry::RetryConfig;
+use crate::risk::{RiskConfig, RiskGuard};
+use crate::schema::SchemaWatch;
+use crate::signing::Signer;
+use crate::{ApiError, LighterClient, Result};
+use reqwest::Client;
+use signer::KeyManager;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinSet;
+use zeroize::Zeroizing;
+
+/// Builder for `LighterClient`.
+///
+/// ```no_run
+/// # use api_client::LighterClient;
+/// # use std::time::Duration;
+/// # fn main() -> api_client::Result<()> {
+/// let client = LighterClient::builder("https://mainnet.zklighter.elliot.ai".to_string(), "0x...", 1, 3)
+///     .pool_max_idle_per_host(8)
+///     .tcp_keepalive(Duration::from_secs(30))
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct LighterClientBuilder {
+    base_url: String,
+    private_key_hex: Zeroizing<String>,
+    // Signs instead of a key parsed from private_key_hex
+    signer: Option<Arc<dyn Signer>>,
+    account_index: i64,
+    api_key_index: u8,
+    price_protection: bool,
+    auth_token_lifetime: Duration,
+    http2_prior_knowledge: bool,
+    http2_keep_alive_interval: Option<Duration>,
+    pool_max_idle_per_host: Option<usize>,
+    pool_idle_timeout: Option<Duration>,
+    tcp_keepalive: Option<Duration>,
+    timeout: Option<Duration>,
+    journal: Option<Arc<dyn TxJournal>>,
+    notifier: Option<Arc<dyn Notifier>>,
+    expiry_policy: ExpiryPolicy,
+    nonce_resync_hook: Option<NonceResyncHook>,
+    audit_sink: Option<Arc<dyn AuditSink>>,
+    websocket_tx: bool,
+    retry_config: RetryConfig,
+    circuit_breaker: Option<CircuitBreakerConfig>,
+    log_schema_drift: bool,
+    risk: Option<RiskConfig>,
+    kill_switch: Option<KillSwitch>,
+}
+
+/// The private key is printed as `<redacted>`.
+impl std::fmt::Debug for LighterClientBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LighterClientBuilder")
+            .field("base_url", &self.base_url)
+            .field("private_key_hex", &"<redacted>")
+            .field("account_index", &self.account_index)
+            .field("api_key_index", &self.api_key_index)
+            .finish_non_exhaustive()
+    }
+}
+
+impl LighterClientBuilder {
+    pub fn new(base_url: String, private_key_hex: &str, account_index: i64, api_key_index: u8) -> Self {
+        Self {
+            base_url,
+            private_key_hex: Zeroizing::new(private_key_hex.to_string()),
+            signer: None,
+            account_index,
+            api_key_index,
+            price_protection: true,
+            auth_token_lifetime: DEFAULT_AUTH_TOKEN_LIFETIME,
+            http2_prior_knowledge: false,
+            http2_keep_alive_interval: None,
+            pool_max_idle_per_host: None,
+            pool_idle_timeout: None,
+            tcp_keepalive: None,
+            timeout: None,
+            journal: None,
+            notifier: None,
+            expiry_policy: ExpiryPolicy::default(),
+            nonce_resync_hook: None,
+            audit_sink: None,
+            websocket_tx: false,
+            retry_config: RetryConfig::default(),
+            circuit_breaker: None,
+            log_schema_drift: false,
+            risk: None,
+            kill_switch: None,
+        }
+    }
+
+    /// Builder of a client that signs with `signer` (see `signing`) and holds no private key.
+    pub fn with_signer(base_url: String, signer: Arc<dyn Signer>, account_index: i64, api_key_index: u8) -> Self {
+        let mut builder = Self::new(base_url, "", account_index, api_key_index);
+        builder.signer = Some(signer);
+        builder
+    }
+
+    /// Default price protection flag (see `LighterClient::with_price_protection`).
+    pub fn price_protection(mut self, enabled: bool) -> Self {
+        self.price_protection = enabled;
+        self
+    }
+
+    /// Lifetime of the auth tokens attached to authenticated GET requests (default 10 minutes).
+    pub fn auth_token_lifetime(mut self, lifetime: Duration) -> Self {
+        self.auth_token_lifetime = lifetime;
+        self
+    }
+
+    /// Speak HTTP/2 without negotiating it first.
+    pub fn http2_prior_knowledge(mut self, enabled: bool) -> Self {
+        self.http2_prior_knowledge = enabled;
+        self
+    }
+
+    /// Interval of HTTP/2 PING frames that keep idle connections open.
+    pub fn http2_keep_alive_interval(mut self, interval: Duration) -> Self {
+        self.http2_keep_alive_interval = Some(interval);
+        self
+    }
+
+    /// Maximum idle connections kept per host.
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /// How long an idle pooled connection is kept (reqwest default: 90 s).
+    pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// TCP keepalive probe interval for pooled sockets.
+    pub fn tcp_keepalive(mut self, interval: Duration) -> Self {
+        self.tcp_keepalive = Some(interval);
+        self
+    }
+
+    /// Overall timeout of each HTTP request.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Record every transaction in `journal` before it is sent (see `TxJournal`).
+    pub fn journal(mut self, journal: Arc<dyn TxJournal>) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    /// Send operational alerts (failed closes, exhausted retries) to `notifier`.
+    pub fn notifier(mut self, notifier: Arc<dyn Notifier>) -> Self {
+        self.notifier = Some(notifier);
+        self
+    }
+
+    /// Default `OrderExpiry` of orders that do not set one (see `ExpiryPolicy`).
+    pub fn expiry_policy(mut self, policy: ExpiryPolicy) -> Self {
+        self.expiry_policy = policy;
+        self
+    }
+
+    /// Call `hook` whenever a rejected nonce makes the client resync and replay a
+    /// transaction (see `NonceResync`).
+    pub fn on_nonce_resync(mut self, hook: NonceResyncHook) -> Self {
+        self.nonce_resync_hook = Some(hook);
+        self
+    }
+
+    /// Report every signed transaction to `sink` (see `AuditSink`).
+    pub fn audit_sink(mut self, sink: Arc<dyn AuditSink>) -> Self {
+        self.audit_sink = Some(sink);
+        self
+    }
+
+    /// Send transactions over the `/stream` websocket, falling back to REST (see `send_tx_ws`).
+    pub fn websocket_tx(mut self, enabled: bool) -> Self {
+        self.websocket_tx = enabled;
+        self
+    }
+
+    /// Retry delays of `create_order` and `update_leverage` per error class (see `RetryConfig`).
+    pub fn retry_config(mut self, config: RetryConfig) -> Self {
+        self.retry_config = config;
+        self
+    }
+
+    /// Fail fast on endpoints that keep failing with transport or 5xx errors (see `circuit`).
+    pub fn circuit_breaker(mut self, config: CircuitBreakerConfig) -> Self {
+        self.circuit_breaker = Some(config);
+        self
+    }
+
+    /// Log unknown and missing fields of typed responses, once each (see `schema`).
+    pub fn log_schema_drift(mut self, enabled: bool) -> Self {
+        self.log_schema_drift = enabled;
+        self
+    }
+
+    /// Check orders against per-market and account-wide caps before signing (see `risk`).
+    pub fn risk_limits(mut self, config: RiskConfig) -> Self {
+        self.risk = Some(config);
+        self
+    }
+
+    /// Refuse orders that add exposure while `kill_switch` is tripped (see `kill_switch`).
+    pub fn kill_switch(mut self, kill_switch: KillSwitch) -> Self {
+        self.kill_switch = Some(kill_switch);
+        self
+    }
+
+    pub fn build(self) -> Result<LighterClient> {
+        let signer: Arc<dyn Signer> = match self.signer {
+            Some(signer) => signer,
+            None => Arc::new(KeyManager::from_hex(&self.private_key_hex)?),
+        };
+
+        let mut http = Client::builder()
+            .tcp_keepalive(self.tcp_keepalive)
+            .pool_idle_timeout(self.pool_idle_timeout.or(Some(Duration::from_secs(90))));
+        if self.http2_prior_knowledge {
+            http = http.http2_prior_knowledge();
+        }
+        if let Some(interval) = self.http2_keep_alive_interval {
+            http = http
+                .http2_keep_alive_interval(interval)
+                .http2_keep_alive_while_idle(true);
+        }
+        if let Some(max) = self.pool_max_idle_per_host {
+            http = http.pool_max_idle_per_host(max);
+        }
+        if let Some(timeout) = self.timeout {
+            http = http.timeout(timeout);
+        }
+
+        Ok(LighterClient {
+            client: http.build()?,
+            base_url: self.base_url,
+            signer: std::sync::RwLock::new(signer),
+            account_index: self.account_index,
+            api_key_index: self.api_key_index,
+            nonces: NonceManager::default(),
+            price_protection: self.price_protection,
+            auth_token_lifetime: self.auth_token_lifetime,
+            journal: self.journal,
+            notifier: self.notifier,
+            expiry_policy: self.expiry_policy,
+            nonce_resync_hook: self.nonce_resync_hook,
+            audit_sink: self.audit_sink,
+            websocket_tx: self.websocket_tx,
+            tx_socket: Default::default(),
+            deadman_ttl: Default::default(),
+            retry_config: self.retry_config,
+            circuit: CircuitBreaker::new(self.circuit_breaker),
+            schema: SchemaWatch::new(self.log_schema_drift),
+            risk: RiskGuard::new(self.risk),
+            kill_switch: self.kill_switch,
+        })
+    }
+}
+
+impl LighterClient {
+    /// Start building a client with custom HTTP settings
+    pub fn builder(base_url: String, private_key_hex: &str, account_index: i64, api_key_index: u8) -> LighterClientBuilder {
+        LighterClientBuilder::new(base_url, private_key_hex, account_index, api_key_index)
+    }
+
+    /// Start building a client that signs through `signer` instead of a local private key
+    pub fn builder_with_signer(base_url: String, signer: Arc<dyn Signer>, account_index: i64, api_key_index: u8) -> LighterClientBuilder {
+        LighterClientBuilder::with_signer(base_url, signer, account_index, api_key_index)
+    }
+
+    /// Pre-establish pooled connections to the API host
+    ///
+    /// Sends `connections` concurrent requests to the base URL so the handshakes are done
+    /// before the first transaction. Succeeds if at least one connection was opened;
+    /// keep `connections` at or below `pool_max_idle_per_host` so they stay pooled.
+    pub async fn warm_up(&self, connections: usize) -> Result<()> {
+        let mut requests = JoinSet::new();
+        for _ in 0..connections.max(1) {
+            let request = self.client.get(format!("{}/", self.base_url));
+            requests.spawn(async move { request.send().await?.bytes().await });
+        }
+
+        let mut first_error = None;
+        let mut opened = 0;
+        while let Some(result) = requests.join_next().await {
+            match result {
+                Ok(Ok(_)) => opened += 1,
+                Ok(Err(e)) => {
+                    first_error.get_or_insert(e);
+                }
+                Err(e) => return Err(ApiError::Api(format!("Warm-up request task failed: {}", e))),
+            }
+        }
+
+        match first_error {
+            Some(e) if opened == 0 => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nonce::NonceResync;
+    use crate::retry::Backoff;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    const PRIVATE_KEY: &str = "bda332f3aaa2d9cfdd8920830ea37efce9636c671a426bd4cb9815007e2a2917604ab47857cbb200";
+
+    /// HTTP/1.1 server answering every request on a kept-alive connection, or never
+    /// answering at all. Returns its URL and the number of connections it accepted.
+    async fn serve(respond: bool) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let connections = Arc::new(AtomicUsize::new(0));
+        let accepted = connections.clone();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                accepted.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(async move {
+                    let mut request = Vec::new();
+                    let mut buf = [0u8; 1024];
+                    while let Ok(n) = socket.read(&mut buf).await {
+                        if n == 0 {
+                            return;
+                        }
+                        request.extend_from_slice(&buf[..n]);
+                        if respond && request.windows(4).any(|w| w == b"\r\n\r\n") {
+                            request.clear();
+                            let response = b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\n{}";
+                            if socket.write_all(response).await.is_err() {
+                                return;
+                            }
+                        }
+                    }
+                });
+            }
+        });
+        (url, connections)
+    }
+
+    async fn requests(client: &LighterClient, count: usize, pause: Duration) {
+        for _ in 0..count {
+            client.warm_up(1).await.unwrap();
+            tokio::time::sleep(pause).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pool_settings_reach_the_client() {
+        let (url, connections) = serve(true).await;
+        let client = LighterClient::builder(url.clone(), PRIVATE_KEY, 1, 3).build().unwrap();
+        assert_eq!(client.base_url, url);
+        requests(&client, 3, Duration::ZERO).await;
+        assert_eq!(connections.load(Ordering::SeqCst), 1, "pooled connection reused");
+
+        let (url, connections) = serve(true).await;
+        let client = LighterClient::builder(url, PRIVATE_KEY, 1, 3)
+            .pool_max_idle_per_host(0)
+            .build()
+            .unwrap();
+        requests(&client, 3, Duration::ZERO).await;
+        assert_eq!(connections.load(Ordering::SeqCst), 3, "nothing kept idle");
+
+        let (url, connections) = serve(true).await;
+        let client = LighterClient::builder(url, PRIVATE_KEY, 1, 3)
+            .pool_idle_timeout(Duration::from_millis(50))
+            .tcp_keepalive(Duration::from_secs(30))
+            .build()
+            .unwrap();
+        requests(&client, 2, Duration::from_millis(300)).await;
+        assert_eq!(connections.load(Ordering::SeqCst), 2, "idle connection expired");
+    }
+
+    #[tokio::test]
+    async fn test_timeout_reaches_the_client() {
+        let (url, connections) = serve(false).await;
+        let client = LighterClient::builder(url, PRIVATE_KEY, 1, 3)
+            .timeout(Duration::from_millis(100))
+            .build()
+            .unwrap();
+        let result = tokio::time::timeout(Duration::from_secs(5), client.warm_up(1)).await.unwrap();
+        assert!(matches!(result, Err(ApiError::Http(e)) if e.is_timeout()));
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_options_reach_the_client() {
+        let retry = RetryConfig::none().with_code(21120, Backoff::fixed(2, Duration::from_millis(10)));
+        let resyncs = Arc::new(AtomicUsize::new(0));
+        let counter = resyncs.clone();
+        let client = LighterClient::builder("http://localhost:1".to_string(), PRIVATE_KEY, 7, 4)
+            .retry_config(retry.clone())
+            .on_nonce_resync(Arc::new(move |_| {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }))
+            .price_protection(false)
+            .auth_token_lifetime(Duration::from_secs(60))
+            .build()
+            .unwrap();
+        assert_eq!(client.retry_config, retry);
+        assert_eq!((client.account_index, client.api_key_index), (7, 4));
+        assert!(!client.price_protection);
+        assert_eq!(client.auth_token_lifetime, Duration::from_secs(60));
+
+        let hook = client.nonce_resync_hook.as_ref().expect("resync hook kept");
+        hook(&NonceResync {
+            account_index: 7,
+            api_key_index: 4,
+            tx_type: 14,
+            rejected_nonce: 5,
+            fetched_nonce: 9,
+            code: 21104,
+            message: "invalid nonce".to_string(),
+        });
+        assert_eq!(resyncs.load(Ordering::SeqCst), 1);
+
+        let defaults = LighterClient::builder("http://localhost:1".to_string(), PRIVATE_KEY, 7, 4).build().unwrap();
+        assert_eq!(defaults.retry_config, RetryConfig::default());
+        assert!(defaults.nonce_resync_hook.is_none());
+    }
+}
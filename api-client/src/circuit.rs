@@ -0,0 +1,275 @@
+//! Circuit breaker for a degraded exchange.
+//!
+//! When the exchange answers with 5xx errors or requests fail before a response arrives,
+//! sending more requests only adds load and delays recovery. With a `CircuitBreakerConfig`
+//! set on the builder, the client counts consecutive transport and 5xx failures per
+//! endpoint (`/api/v1/sendTx`, `/api/v1/account`, ...). Once an endpoint reaches the
+//! threshold its circuit opens: requests to it fail at once with `ApiError::CircuitOpen`
+//! until the cool-down has passed. The next request is then let through as a probe
+//! (half-open); a success closes the circuit, a failure opens it for another cool-down.
+//!
+//! Rejections (any response the exchange answered) count as successes here; they say
+//! nothing about the exchange's health. `LighterClient::circuit_status` and
+//! `is_circuit_open` let a strategy pause trading while an endpoint is down.
+
+use crate::{Alert, ApiError, LighterClient, Result};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// When a circuit opens and how long it stays open.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive transport or 5xx failures of an endpoint that open its circuit.
+    pub failure_threshold: u32,
+    /// Time an open circuit fails fast before letting a probe request through.
+    pub cool_down: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    /// Open after 5 consecutive failures, probe again after 30 s.
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            cool_down: Duration::from_secs(30),
+        }
+    }
+}
+
+/// State of one endpoint's circuit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests go through.
+    Closed,
+    /// Requests fail with `ApiError::CircuitOpen` for `retry_in`.
+    Open { retry_in: Duration },
+    /// The cool-down has passed; the next request (or the one in flight) is a probe.
+    HalfOpen,
+}
+
+/// Circuit of an endpoint that has failed since it last succeeded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CircuitStatus {
+    /// Request path, e.g. `/api/v1/sendTx`.
+    pub endpoint: String,
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+}
+
+#[derive(Debug, Default)]
+struct EndpointState {
+    consecutive_failures: u32,
+    // Start of the current cool-down, `None` while closed
+    opened_at: Option<Instant>,
+    // A probe request is in flight
+    probing: bool,
+}
+
+/// Per-endpoint failure counts; does nothing without a config.
+#[derive(Debug, Default)]
+pub(crate) struct CircuitBreaker {
+    config: Option<CircuitBreakerConfig>,
+    endpoints: Mutex<HashMap<String, EndpointState>>,
+}
+
+/// Whether an outcome says the exchange is unhealthy.
+fn is_failure<T>(outcome: &Result<T>) -> bool {
+    match outcome {
+        Err(ApiError::Http(_)) => true,
+        Err(ApiError::UnexpectedResponse { status, .. }) => *status >= 500,
+        _ => false,
+    }
+}
+
+impl CircuitBreaker {
+    pub(crate) fn new(config: Option<CircuitBreakerConfig>) -> Self {
+        Self {
+            config,
+            endpoints: Mutex::default(),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, EndpointState>> {
+        self.endpoints.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// `ApiError::CircuitOpen` if `endpoint` is open and still cooling down.
+    fn open_error(config: &CircuitBreakerConfig, endpoint: &str, state: &EndpointState) -> Result<()> {
+        let Some(opened_at) = state.opened_at else {
+            return Ok(());
+        };
+        match config.cool_down.checked_sub(opened_at.elapsed()) {
+            Some(retry_in) if !retry_in.is_zero() => Err(ApiError::CircuitOpen {
+                endpoint: endpoint.to_string(),
+                retry_in,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Fails with `ApiError::CircuitOpen` while `endpoint` is open, without claiming the
+    /// probe; for callers that record state (e.g. the journal) before sending.
+    pub(crate) fn ensure_closed(&self, endpoint: &str) -> Result<()> {
+        let Some(config) = &self.config else {
+            return Ok(());
+        };
+        match self.lock().get(endpoint) {
+            Some(state) => Self::open_error(config, endpoint, state),
+            None => Ok(()),
+        }
+    }
+
+    /// Fails with `ApiError::CircuitOpen` while `endpoint` is open
+    ///
+    /// Once the cool-down has passed, lets one request through as a probe and restarts the
+    /// cool-down, so concurrent requests keep failing fast until the probe's outcome is
+    /// recorded (or the probe is dropped and another cool-down passes).
+    pub(crate) fn check(&self, endpoint: &str) -> Result<()> {
+        let Some(config) = &self.config else {
+            return Ok(());
+        };
+        let mut endpoints = self.lock();
+        let Some(state) = endpoints.get_mut(endpoint) else {
+            return Ok(());
+        };
+        if state.opened_at.is_none() {
+            return Ok(());
+        }
+        Self::open_error(config, endpoint, state)?;
+        state.opened_at = Some(Instant::now());
+        state.probing = true;
+        Ok(())
+    }
+
+    /// Counts the outcome of a request to `endpoint`; returns the failure count if this
+    /// failure opened the circuit.
+    pub(crate) fn record<T>(&self, endpoint: &str, outcome: &Result<T>) -> Option<u32> {
+        let config = self.config.as_ref()?;
+        let mut endpoints = self.lock();
+        if !is_failure(outcome) {
+            endpoints.remove(endpoint);
+            return None;
+        }
+
+        let state = endpoints.entry(endpoint.to_string()).or_default();
+        state.consecutive_failures += 1;
+        let opens = state.probing || (state.opened_at.is_none() && state.consecutive_failures >= config.failure_threshold);
+        if !opens {
+            return None;
+        }
+        let reopened = state.probing;
+        state.opened_at = Some(Instant::now());
+        state.probing = false;
+        (!reopened).then_some(state.consecutive_failures)
+    }
+
+    /// Endpoints with failures, sorted by path.
+    pub(crate) fn status(&self) -> Vec<CircuitStatus> {
+        let Some(config) = &self.config else {
+            return Vec::new();
+        };
+        let mut status: Vec<CircuitStatus> = self
+            .lock()
+            .iter()
+            .map(|(endpoint, state)| {
+                let retry_in = state.opened_at.map(|opened_at| config.cool_down.saturating_sub(opened_at.elapsed()));
+                let state_now = match retry_in {
+                    None => CircuitState::Closed,
+                    Some(_) if state.probing => CircuitState::HalfOpen,
+                    Some(retry_in) if retry_in.is_zero() => CircuitState::HalfOpen,
+                    Some(retry_in) => CircuitState::Open { retry_in },
+                };
+                CircuitStatus {
+                    endpoint: endpoint.clone(),
+                    state: state_now,
+                    consecutive_failures: state.consecutive_failures,
+                }
+            })
+            .collect();
+        status.sort_by(|a, b| a.endpoint.cmp(&b.endpoint));
+        status
+    }
+}
+
+impl LighterClient {
+    /// Circuits of the endpoints that have failed since they last succeeded
+    ///
+    /// Empty when no circuit breaker is configured or every endpoint is healthy.
+    pub fn circuit_status(&self) -> Vec<CircuitStatus> {
+        self.circuit.status()
+    }
+
+    /// Whether any endpoint's circuit is open or half-open, e.g. to pause quoting
+    pub fn is_circuit_open(&self) -> bool {
+        self.circuit_status().iter().any(|status| status.state != CircuitState::Closed)
+    }
+
+    /// Run `request` to `endpoint` through its circuit breaker
+    ///
+    /// Fails fast while the circuit is open; otherwise counts the outcome, alerting the
+    /// notifier when the circuit opens.
+    pub(crate) async fn guarded<T>(&self, endpoint: &str, request: impl Future<Output = Result<T>>) -> Result<T> {
+        self.circuit.check(endpoint)?;
+        let outcome = request.await;
+        if let Some(failures) = self.circuit.record(endpoint, &outcome) {
+            let cool_down = self.circuit.config.as_ref().map(|c| c.cool_down).unwrap_or_default();
+            tracing::warn!(target: "api_client::circuit", endpoint, failures, "circuit opened");
+            self.notify(Alert::circuit_opened(endpoint, failures, cool_down));
+        }
+        outcome
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unavailable() -> Result<()> {
+        Err(ApiError::UnexpectedResponse { status: 503, body: "Service Unavailable".to_string(), retry_after: None })
+    }
+
+    #[test]
+    fn test_circuit_opens_and_half_opens() {
+        let breaker = CircuitBreaker::new(Some(CircuitBreakerConfig {
+            failure_threshold: 2,
+            cool_down: Duration::from_millis(50),
+        }));
+        let endpoint = "/api/v1/sendTx";
+
+        assert_eq!(breaker.record(endpoint, &unavailable()), None);
+        // A rejection is an answer from a healthy exchange and resets the count
+        let rejected: Result<()> = Err(ApiError::UnexpectedResponse { status: 429, body: String::new(), retry_after: None });
+        assert_eq!(breaker.record(endpoint, &rejected), None);
+        assert!(breaker.status().is_empty());
+
+        breaker.record(endpoint, &unavailable());
+        assert_eq!(breaker.record(endpoint, &unavailable()), Some(2));
+        assert!(matches!(breaker.check(endpoint), Err(ApiError::CircuitOpen { .. })));
+        assert!(breaker.ensure_closed(endpoint).is_err());
+        assert!(breaker.check("/api/v1/account").is_ok());
+        assert!(matches!(breaker.status()[0].state, CircuitState::Open { .. }));
+
+        // After the cool-down one probe goes through; a failed probe reopens at once
+        std::thread::sleep(Duration::from_millis(60));
+        assert_eq!(breaker.status()[0].state, CircuitState::HalfOpen);
+        assert!(breaker.ensure_closed(endpoint).is_ok());
+        assert!(breaker.check(endpoint).is_ok());
+        assert!(breaker.check(endpoint).is_err());
+        assert_eq!(breaker.record(endpoint, &unavailable()), None);
+        assert!(matches!(breaker.status()[0].state, CircuitState::Open { .. }));
+
+        // A successful probe closes it
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(breaker.check(endpoint).is_ok());
+        breaker.record(endpoint, &Ok(()));
+        assert!(breaker.status().is_empty());
+        assert!(breaker.check(endpoint).is_ok());
+
+        let disabled = CircuitBreaker::default();
+        for _ in 0..10 {
+            disabled.record(endpoint, &unavailable());
+        }
+        assert!(disabled.check(endpoint).is_ok());
+    }
+}
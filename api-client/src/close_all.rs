@@ -0,0 +1,262 @@
+//! Closing every position and checking that it closed.
+//!
+//! `close_all_positions_auto` sends one reduce-only market order per open position and
+//! returns as soon as they are accepted; an accepted close can still fill partially (price
+//! protection, thin books), leaving part of the position open. `close_all_positions_verified`
+//! sends the same orders, then polls the account until every position is flat or
+//! `CloseAllConfig::settle_timeout` passes. What remains is closed again with reduce-only
+//! IOC orders priced off the current book, crossing the best opposite quote by
+//! `retry_slippage`, up to `max_attempts` rounds in all. The `CloseAllReport` says per market
+//! how much was open, how much still is, and how long it took.
+
+use crate::positions::{account_entry, position_size};
+use crate::{
+    ApiError, BaseAmount, CreateOrderRequest, LighterClient, OrderType, PositionSize, Price, Result, TimeInForce,
+};
+use std::collections::HashMap;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Polling and retries of `close_all_positions_verified`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CloseAllConfig {
+    /// Rounds of close orders, the first at market; at least 1.
+    pub max_attempts: u32,
+    /// Time each round is given to fill before the residual is closed again.
+    pub settle_timeout: Duration,
+    /// Interval of the position polls within a round.
+    pub poll_interval: Duration,
+    /// How far retries cross the best opposite quote, as a fraction of its price.
+    pub retry_slippage: f64,
+}
+
+impl Default for CloseAllConfig {
+    /// 3 rounds of up to 5 s, polled every 500 ms, retries crossing the book by 0.5%.
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            settle_timeout: Duration::from_secs(5),
+            poll_interval: Duration::from_millis(500),
+            retry_slippage: 0.005,
+        }
+    }
+}
+
+/// Outcome of closing one position.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PositionCloseReport {
+    pub market_index: u8,
+    pub was_long: bool,
+    /// Size open before the first close, in base amount units.
+    pub initial_base_amount: i64,
+    /// Size still open at the last poll; 0 when closed.
+    pub remaining_base_amount: i64,
+    /// Close orders sent.
+    pub attempts: u32,
+    /// From the first close order until the position was seen flat, or until giving up.
+    pub elapsed: Duration,
+    /// Last error or rejection of a close order, if any.
+    pub last_error: Option<String>,
+}
+
+impl PositionCloseReport {
+    pub fn is_closed(&self) -> bool {
+        self.remaining_base_amount == 0
+    }
+}
+
+/// Outcome of `close_all_positions_verified`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CloseAllReport {
+    /// Positions open at the start, by market index.
+    pub positions: Vec<PositionCloseReport>,
+    pub elapsed: Duration,
+}
+
+impl CloseAllReport {
+    /// Whether every position was seen flat.
+    pub fn is_flat(&self) -> bool {
+        self.positions.iter().all(PositionCloseReport::is_closed)
+    }
+
+    /// Positions still (partially) open.
+    pub fn remaining(&self) -> impl Iterator<Item = &PositionCloseReport> {
+        self.positions.iter().filter(|position| !position.is_closed())
+    }
+}
+
+/// Limit price of a retry closing a position: the best opposite quote crossed by `slippage`,
+/// in price units. `None` without a quote on that side.
+pub(crate) fn retry_price(best_opposite: Option<f64>, is_ask: bool, slippage: f64, price_decimals: u32) -> Option<Price> {
+    let quote = best_opposite.filter(|price| *price > 0.0)?;
+    let price = if is_ask { quote * (1.0 - slippage) } else { quote * (1.0 + slippage) };
+    // Round away from the book so the order still crosses
+    let scaled = price * 10f64.powi(price_decimals as i32);
+    let units = if is_ask { scaled.floor() } else { scaled.ceil() };
+    Price::try_from((units as i64).max(1)).ok()
+}
+
+impl LighterClient {
+    /// Close every open position, wait for the closes to fill and retry what remains
+    ///
+    /// See `close_all` for the procedure. Fails only if the positions cannot be read at
+    /// the start. Failed close orders are recorded in the report; failed polls are logged,
+    /// and the report keeps the sizes of the last successful one.
+    pub async fn close_all_positions_verified(&self, config: &CloseAllConfig) -> Result<CloseAllReport> {
+        let started = Instant::now();
+        let mut decimals = HashMap::new();
+        let initial = self.open_positions(&mut decimals).await?;
+        let mut reports: Vec<PositionCloseReport> = initial
+            .iter()
+            .map(|position| PositionCloseReport {
+                market_index: position.market_index,
+                was_long: position.is_long,
+                initial_base_amount: position.base_amount,
+                remaining_base_amount: position.base_amount,
+                attempts: 0,
+                elapsed: Duration::ZERO,
+                last_error: None,
+            })
+            .collect();
+
+        let mut open = initial;
+        for attempt in 0..config.max_attempts.max(1) {
+            if open.is_empty() {
+                break;
+            }
+            for position in &open {
+                let report = reports
+                    .iter_mut()
+                    .find(|report| report.market_index == position.market_index)
+                    .expect("positions only shrink");
+                report.attempts += 1;
+                let outcome = if attempt == 0 {
+                    self.close_position_with_size(position.market_index, position.is_long, position.base_amount)
+                        .await
+                } else {
+                    self.close_residual(position, config.retry_slippage).await
+                };
+                match outcome {
+                    Ok(response) if response["code"].as_i64().is_some_and(|code| code != 200) => {
+                        report.last_error = Some(response.to_string());
+                    }
+                    Ok(_) => {}
+                    Err(e) => report.last_error = Some(e.to_string()),
+                }
+            }
+
+            let deadline = Instant::now() + config.settle_timeout;
+            loop {
+                tokio::time::sleep(config.poll_interval.min(deadline.saturating_duration_since(Instant::now()))).await;
+                match self.open_positions(&mut decimals).await {
+                    Ok(positions) => {
+                        for report in reports.iter_mut().filter(|report| !report.is_closed()) {
+                            let position = positions.iter().find(|p| p.market_index == report.market_index);
+                            report.remaining_base_amount = position.map_or(0, |p| p.base_amount);
+                            report.elapsed = started.elapsed();
+                        }
+                        // Only the positions this call set out to close
+                        open = positions
+                            .into_iter()
+                            .filter(|p| reports.iter().any(|report| report.market_index == p.market_index))
+                            .collect();
+                    }
+                    Err(e) => tracing::warn!(target: "api_client::close_all", error = %e, "position poll failed"),
+                }
+                if open.is_empty() || Instant::now() >= deadline {
+                    break;
+                }
+            }
+        }
+
+        for report in reports.iter_mut().filter(|report| !report.is_closed()) {
+            report.elapsed = started.elapsed();
+        }
+        Ok(CloseAllReport {
+            positions: reports,
+            elapsed: started.elapsed(),
+        })
+    }
+
+    /// Open positions of the account; `decimals` caches the size decimals of each market.
+    async fn open_positions(&self, decimals: &mut HashMap<u8, u32>) -> Result<Vec<PositionSize>> {
+        let account_info = self.get_account().await?;
+        let mut positions = Vec::new();
+        for entry in account_entry(&account_info)["positions"].as_array().into_iter().flatten() {
+            let Some(market_index) = entry["market_id"].as_u64().and_then(|id| u8::try_from(id).ok()) else {
+                continue;
+            };
+            let size_decimals = match decimals.get(&market_index) {
+                Some(size_decimals) => *size_decimals,
+                None => {
+                    let size_decimals = self.market_size_decimals(market_index).await?;
+                    decimals.insert(market_index, size_decimals);
+                    size_decimals
+                }
+            };
+            positions.extend(position_size(entry, market_index, size_decimals)?);
+        }
+        positions.sort_by_key(|position| position.market_index);
+        Ok(positions)
+    }
+
+    /// Reduce-only IOC order for what is left of `position`, priced off the current book
+    async fn close_residual(&self, position: &PositionSize, slippage: f64) -> Result<serde_json::Value> {
+        let is_ask = position.is_long;
+        let book = self.get_order_book_snapshot(position.market_index, 1).await?;
+        let best_opposite = if is_ask { book.best_bid() } else { book.best_ask() };
+        let price_decimals = self.get_market_rules(position.market_index).await?.price_decimals;
+        let price = retry_price(best_opposite.map(|level| level.price), is_ask, slippage, price_decimals)
+            .ok_or_else(|| ApiError::Api(format!("No quotes to close against on market {}", position.market_index)))?;
+
+        self.create_order(CreateOrderRequest {
+            account_index: self.account_index,
+            order_book_index: position.market_index,
+            client_order_index: SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as u64,
+            base_amount: BaseAmount(position.base_amount),
+            price,
+            is_ask,
+            order_type: OrderType::Limit,
+            time_in_force: TimeInForce::ImmediateOrCancel,
+            reduce_only: true,
+            trigger_price: Price::ZERO,
+            price_protection: None,
+            order_expiry: None,
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_price_crosses_the_book() {
+        // Selling a long into a 3000.00 bid, 0.5% through
+        assert_eq!(retry_price(Some(3000.0), true, 0.005, 2), Some(Price(298_500)));
+        // Buying back a short from a 150.004 ask rounds up
+        assert_eq!(retry_price(Some(150.004), false, 0.0, 2), Some(Price(15_001)));
+        assert_eq!(retry_price(None, true, 0.005, 2), None);
+        assert_eq!(retry_price(Some(0.0), false, 0.005, 2), None);
+    }
+
+    #[test]
+    fn test_report_remaining() {
+        let position = |market_index, remaining_base_amount| PositionCloseReport {
+            market_index,
+            was_long: true,
+            initial_base_amount: 100,
+            remaining_base_amount,
+            attempts: 1,
+            elapsed: Duration::from_secs(1),
+            last_error: None,
+        };
+        let report = CloseAllReport {
+            positions: vec![position(0, 0), position(1, 40)],
+            elapsed: Duration::from_secs(2),
+        };
+        assert!(!report.is_flat());
+        assert_eq!(report.remaining().map(|p| p.market_index).collect::<Vec<_>>(), vec![1]);
+        assert!(CloseAllReport { positions: vec![position(0, 0)], elapsed: Duration::ZERO }.is_flat());
+    }
+}
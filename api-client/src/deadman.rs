@@ -0,0 +1,98 @@
+//! Deadman switch.
+//!
+//! A scheduled cancel-all (`cancel_all_orders` with `CANCEL_ALL_TIF_SCHEDULED`) makes the
+//! exchange cancel every open order at a given time unless it is pushed back first. Armed
+//! with a TTL and refreshed while the client runs, it pulls the account's quotes when the
+//! process dies or loses connectivity. `keep_deadman_alive` does the refreshing.
+
+use crate::{ApiError, LighterClient, Result};
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::task::JoinHandle;
+
+/// Cancel every open order now.
+pub const CANCEL_ALL_TIF_IMMEDIATE: u8 = 0;
+/// Cancel every open order at `time` (ms).
+pub const CANCEL_ALL_TIF_SCHEDULED: u8 = 1;
+/// Abort the scheduled cancel-all.
+pub const CANCEL_ALL_TIF_ABORT: u8 = 2;
+
+impl LighterClient {
+    /// Arm the deadman switch: cancel all orders `ttl` from now unless refreshed
+    ///
+    /// Replaces any earlier schedule. The TTL is kept for `refresh_deadman` once the
+    /// exchange accepts the schedule.
+    pub async fn set_deadman_switch(&self, ttl: Duration) -> Result<Value> {
+        if ttl.is_zero() {
+            return Err(ApiError::InvalidOrder("Deadman TTL must be positive".to_string()));
+        }
+        let response = self.schedule_cancel_all(ttl).await?;
+        if response["code"].as_i64() == Some(200) {
+            *self.deadman_lock() = Some(ttl);
+        }
+        Ok(response)
+    }
+
+    /// Push the scheduled cancel-all back to a full TTL from now
+    ///
+    /// Fails with `ApiError::InvalidOrder` if the switch is not armed.
+    pub async fn refresh_deadman(&self) -> Result<Value> {
+        let ttl = self
+            .deadman_switch()
+            .ok_or_else(|| ApiError::InvalidOrder("Deadman switch is not set".to_string()))?;
+        self.schedule_cancel_all(ttl).await
+    }
+
+    /// Disarm the deadman switch, aborting the scheduled cancel-all
+    pub async fn disable_deadman(&self) -> Result<Value> {
+        *self.deadman_lock() = None;
+        self.cancel_all_orders(CANCEL_ALL_TIF_ABORT, 0).await
+    }
+
+    /// TTL of the armed deadman switch
+    pub fn deadman_switch(&self) -> Option<Duration> {
+        *self.deadman_lock()
+    }
+
+    /// Refresh the deadman switch in the background, every third of its TTL
+    ///
+    /// The task holds only a weak reference: it stops once the client is dropped or the
+    /// switch is disabled, and the pending schedule then cancels the orders. A failed
+    /// refresh is logged and retried at the next tick, so one lost request does not trip
+    /// the switch as long as the TTL covers the retries. Refreshes lease their nonces from
+    /// the same pool as orders, so they can run alongside order traffic.
+    pub fn keep_deadman_alive(self: &Arc<Self>) -> JoinHandle<()> {
+        let client = Arc::downgrade(self);
+        tokio::spawn(async move {
+            loop {
+                let Some(ttl) = client.upgrade().and_then(|c| c.deadman_switch()) else {
+                    return;
+                };
+                tokio::time::sleep(ttl / 3).await;
+
+                let Some(client) = client.upgrade() else {
+                    return;
+                };
+                if client.deadman_switch().is_none() {
+                    return;
+                }
+                match client.refresh_deadman().await {
+                    Ok(response) if response["code"].as_i64() == Some(200) => {}
+                    Ok(response) => tracing::warn!(target: "api_client::deadman", %response, "deadman refresh rejected"),
+                    Err(e) => tracing::warn!(target: "api_client::deadman", error = %e, "deadman refresh failed"),
+                }
+            }
+        })
+    }
+
+    async fn schedule_cancel_all(&self, ttl: Duration) -> Result<Value> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
+        self.cancel_all_orders(CANCEL_ALL_TIF_SCHEDULED, now + ttl.as_millis() as i64)
+            .await
+    }
+
+    fn deadman_lock(&self) -> std::sync::MutexGuard<'_, Option<Duration>> {
+        self.deadman_ttl.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
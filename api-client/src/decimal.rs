@@ -0,0 +1,123 @@
+//! Exact decimal amounts.
+//!
+//! Lighter returns prices, sizes and balances as decimal strings (`"0.0400"`), and a few
+//! endpoints send plain JSON numbers instead. Parsing them as `f64` rounds large positions;
+//! the `string_or_number_decimal` serde helpers read either form into a `Decimal` and write
+//! it back as a string, the way the exchange sends it.
+//!
+//! ```ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct Fill {
+//!     #[serde(with = "string_or_number_decimal")]
+//!     size: Decimal,
+//!     #[serde(with = "string_or_number_decimal::option", default)]
+//!     fee: Option<Decimal>,
+//! }
+//! ```
+
+pub use rust_decimal::Decimal;
+use serde_json::Value;
+use std::str::FromStr;
+
+/// Reads a decimal string or a JSON number, `None` for anything else.
+///
+/// Strings are parsed exactly, in plain or scientific notation. Floats go through `f64`,
+/// which is as exact as serde_json read them.
+pub fn parse_decimal(value: &Value) -> Option<Decimal> {
+    match value {
+        Value::String(s) => {
+            let s = s.trim();
+            Decimal::from_str(s).or_else(|_| Decimal::from_scientific(s)).ok()
+        }
+        Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                Some(Decimal::from(i))
+            } else if let Some(u) = n.as_u64() {
+                Some(Decimal::from(u))
+            } else {
+                n.as_f64().and_then(|f| Decimal::try_from(f).ok())
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Serde helpers for a `Decimal` sent as a string or a number, written back as a string.
+pub mod string_or_number_decimal {
+    use super::{parse_decimal, Decimal};
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use serde_json::Value;
+
+    pub fn serialize<S: Serializer>(value: &Decimal, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+
+    /// An empty string reads as zero.
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Decimal, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        if value.as_str().is_some_and(|s| s.trim().is_empty()) {
+            return Ok(Decimal::ZERO);
+        }
+        parse_decimal(&value).ok_or_else(|| D::Error::custom(format!("expected a decimal, got {}", value)))
+    }
+
+    /// The same for an optional field; `null` and an empty string read as `None`.
+    pub mod option {
+        use super::{parse_decimal, Decimal};
+        use serde::de::Error;
+        use serde::{Deserialize, Deserializer, Serializer};
+        use serde_json::Value;
+
+        pub fn serialize<S: Serializer>(value: &Option<Decimal>, serializer: S) -> Result<S::Ok, S::Error> {
+            match value {
+                Some(value) => serializer.collect_str(value),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Decimal>, D::Error> {
+            let value = Value::deserialize(deserializer)?;
+            if value.is_null() || value.as_str().is_some_and(|s| s.trim().is_empty()) {
+                return Ok(None);
+            }
+            parse_decimal(&value)
+                .map(Some)
+                .ok_or_else(|| D::Error::custom(format!("expected a decimal, got {}", value)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Amounts {
+        #[serde(with = "string_or_number_decimal")]
+        size: Decimal,
+        #[serde(with = "string_or_number_decimal::option", default)]
+        fee: Option<Decimal>,
+    }
+
+    #[test]
+    fn test_string_or_number_decimal() {
+        // More digits than an f64 holds
+        let amounts: Amounts = serde_json::from_value(json!({"size": "123456789012.123456789", "fee": 2})).unwrap();
+        assert_eq!(amounts.size, Decimal::from_str("123456789012.123456789").unwrap());
+        assert_eq!(amounts.fee, Some(Decimal::from(2)));
+        assert_eq!(
+            serde_json::to_value(&amounts).unwrap(),
+            json!({"size": "123456789012.123456789", "fee": "2"})
+        );
+
+        let amounts: Amounts = serde_json::from_value(json!({"size": 0.25, "fee": ""})).unwrap();
+        assert_eq!((amounts.size, amounts.fee), (Decimal::new(25, 2), None));
+        assert_eq!(parse_decimal(&json!("1e-3")), Some(Decimal::new(1, 3)));
+        assert_eq!(parse_decimal(&json!("0.0400")).unwrap().to_string(), "0.0400");
+        assert!(serde_json::from_value::<Amounts>(json!({"size": "abc"})).is_err());
+        assert!(serde_json::from_value::<Amounts>(json!({"size": true})).is_err());
+    }
+}
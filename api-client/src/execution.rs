@@ -0,0 +1,293 @@
+//! Execution algorithms that work a large order as a series of smaller child orders.
+//!
+//! `TwapExecutor` splits a target size into equal slices posted at a fixed interval. Each
+//! child is a good-till-time limit order pegged to the passive touch; if the touch drifts
+//! further than `max_drift_ticks` while a child is resting, the unfilled remainder is
+//! cancelled and re-posted at the new price. Whatever a slice leaves unfilled is carried
+//! into the next one.
+
+use crate::{ApiError, BaseAmount, CreateOrderRequest, LighterClient, OrderType, Price, Result, TimeInForce};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Parameters of a TWAP run.
+#[derive(Debug, Clone)]
+pub struct TwapConfig {
+    pub market_index: u8,
+    /// `true` to sell, `false` to buy.
+    pub is_ask: bool,
+    /// Total size to execute, in base amount units.
+    pub total_base_amount: i64,
+    /// Number of child orders the total is split into.
+    pub slices: u32,
+    /// Time each slice is given before the next one starts.
+    pub interval: Duration,
+    /// How often a resting child is checked against the book.
+    pub drift_check_interval: Duration,
+    /// Ticks to price a child inside the spread from the passive touch (0 joins the touch).
+    pub aggressiveness_ticks: i64,
+    /// Re-post a resting child once the touch has moved more than this many ticks.
+    pub max_drift_ticks: i64,
+    /// Decimals of the market price, used to turn book prices into integer ticks.
+    pub price_decimals: u32,
+    /// Decimals of the market size, used to turn order sizes into base amounts.
+    pub size_decimals: u32,
+    pub reduce_only: bool,
+}
+
+/// Snapshot passed to the progress callback after each slice.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TwapProgress {
+    /// Slices completed so far.
+    pub slices_done: u32,
+    pub slices_total: u32,
+    pub filled_base_amount: i64,
+    pub remaining_base_amount: i64,
+    /// Number of cancel/replace cycles caused by price drift.
+    pub replacements: u32,
+    /// Price of the most recent child order.
+    pub last_price: i64,
+}
+
+/// Outcome of a finished TWAP run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TwapReport {
+    pub filled_base_amount: i64,
+    /// Size left unfilled after the last slice; its child order has been cancelled.
+    pub unfilled_base_amount: i64,
+    pub child_orders: u32,
+    pub replacements: u32,
+}
+
+type ProgressCallback<'a> = Box<dyn FnMut(&TwapProgress) + Send + 'a>;
+
+/// Works a large order as time-sliced child orders through a `LighterClient`.
+pub struct TwapExecutor<'a> {
+    client: &'a LighterClient,
+    config: TwapConfig,
+    on_progress: Option<ProgressCallback<'a>>,
+}
+
+struct RestingChild {
+    client_order_index: u64,
+    base_amount: i64,
+    reference_price: i64,
+    price: i64,
+}
+
+impl<'a> TwapExecutor<'a> {
+    pub fn new(client: &'a LighterClient, config: TwapConfig) -> Self {
+        Self {
+            client,
+            config,
+            on_progress: None,
+        }
+    }
+
+    /// Registers a callback invoked after every slice.
+    pub fn on_progress<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(&TwapProgress) + Send + 'a,
+    {
+        self.on_progress = Some(Box::new(callback));
+        self
+    }
+
+    /// Runs the schedule to completion.
+    pub async fn run(&mut self) -> Result<TwapReport> {
+        if self.config.slices == 0 || self.config.total_base_amount <= 0 {
+            return Err(ApiError::Api("TWAP needs at least one slice and a positive size".to_string()));
+        }
+
+        let schedule = slice_sizes(self.config.total_base_amount, self.config.slices);
+        let mut next_client_order_index = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as u64;
+        let mut progress = TwapProgress {
+            slices_done: 0,
+            slices_total: self.config.slices,
+            filled_base_amount: 0,
+            remaining_base_amount: self.config.total_base_amount,
+            replacements: 0,
+            last_price: 0,
+        };
+        let mut child_orders = 0u32;
+        let mut carry = 0i64;
+
+        for slice_amount in schedule {
+            let slice_deadline = Instant::now() + self.config.interval;
+            let target = slice_amount + carry;
+
+            let reference_price = self.reference_price().await?;
+            let mut child = self
+                .post_child(&mut next_client_order_index, target, reference_price)
+                .await?;
+            child_orders += 1;
+            progress.last_price = child.price;
+
+            while Instant::now() + self.config.drift_check_interval < slice_deadline {
+                tokio::time::sleep(self.config.drift_check_interval).await;
+
+                let reference_price = self.reference_price().await?;
+                if (reference_price - child.reference_price).abs() <= self.config.max_drift_ticks {
+                    continue;
+                }
+
+                let remaining = self.withdraw_child(&child).await?;
+                progress.filled_base_amount += child.base_amount - remaining;
+                if remaining == 0 {
+                    child.base_amount = 0;
+                    break;
+                }
+                child = self
+                    .post_child(&mut next_client_order_index, remaining, reference_price)
+                    .await?;
+                child_orders += 1;
+                progress.replacements += 1;
+                progress.last_price = child.price;
+            }
+
+            let now = Instant::now();
+            if now < slice_deadline {
+                tokio::time::sleep(slice_deadline - now).await;
+            }
+
+            carry = if child.base_amount > 0 {
+                let remaining = self.withdraw_child(&child).await?;
+                progress.filled_base_amount += child.base_amount - remaining;
+                remaining
+            } else {
+                0
+            };
+
+            progress.slices_done += 1;
+            progress.remaining_base_amount = self.config.total_base_amount - progress.filled_base_amount;
+            if let Some(callback) = self.on_progress.as_mut() {
+                callback(&progress);
+            }
+        }
+
+        Ok(TwapReport {
+            filled_base_amount: progress.filled_base_amount,
+            unfilled_base_amount: carry,
+            child_orders,
+            replacements: progress.replacements,
+        })
+    }
+
+    /// Passive touch in integer ticks: best bid when buying, best ask when selling.
+    async fn reference_price(&self) -> Result<i64> {
+        let book = self.client.get_order_book_snapshot(self.config.market_index, 1).await?;
+        let level = if self.config.is_ask { book.best_ask() } else { book.best_bid() };
+        let level = level.ok_or_else(|| {
+            ApiError::Api(format!("No liquidity on market {} to price a TWAP child", self.config.market_index))
+        })?;
+        Ok(to_units(level.price, self.config.price_decimals))
+    }
+
+    async fn post_child(
+        &self,
+        next_client_order_index: &mut u64,
+        base_amount: i64,
+        reference_price: i64,
+    ) -> Result<RestingChild> {
+        let price = child_price(reference_price, self.config.aggressiveness_ticks, self.config.is_ask);
+        let client_order_index = *next_client_order_index;
+        *next_client_order_index += 1;
+
+        let order = CreateOrderRequest {
+            account_index: self.client.account_index(),
+            order_book_index: self.config.market_index,
+            client_order_index,
+            base_amount: BaseAmount(base_amount),
+            price: Price::try_from(price)?,
+            is_ask: self.config.is_ask,
+            order_type: OrderType::Limit,
+            time_in_force: TimeInForce::GoodTillTime,
+            reduce_only: self.config.reduce_only,
+            trigger_price: Price::ZERO,
+            price_protection: None,
+            order_expiry: None,
+        };
+        let response = self.client.create_order(order).await?;
+        if response["code"].as_i64() != Some(200) {
+            return Err(ApiError::Api(format!("TWAP child order rejected: {}", response)));
+        }
+
+        Ok(RestingChild {
+            client_order_index,
+            base_amount,
+            reference_price,
+            price,
+        })
+    }
+
+    /// Cancels a child and returns how much of it was still unfilled.
+    async fn withdraw_child(&self, child: &RestingChild) -> Result<i64> {
+        let remaining = self.child_remaining(child).await?;
+        if remaining > 0 {
+            // Cancel accepts the client order index in place of the order index
+            self.client
+                .cancel_order(self.config.market_index, child.client_order_index as i64)
+                .await?;
+            // cancel_order takes its nonce straight from the API; resync the cache
+            self.client.refresh_nonce().await?;
+        }
+        Ok(remaining)
+    }
+
+    async fn child_remaining(&self, child: &RestingChild) -> Result<i64> {
+        let response_json = self.client.get_active_orders(self.config.market_index).await?;
+
+        let remaining = response_json["orders"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|o| o["client_order_index"].as_u64() == Some(child.client_order_index))
+            .and_then(|o| o["remaining_base_amount"].as_str().and_then(|s| s.parse::<f64>().ok()))
+            .map(|size| to_units(size, self.config.size_decimals))
+            // No longer active: filled (or expired, which the next slice absorbs)
+            .unwrap_or(0);
+
+        Ok(remaining.min(child.base_amount))
+    }
+}
+
+/// Splits `total` into `slices` near-equal parts; the first `total % slices` get one extra unit.
+pub(crate) fn slice_sizes(total: i64, slices: u32) -> Vec<i64> {
+    let slices = slices as i64;
+    let base = total / slices;
+    let extra = total % slices;
+    (0..slices).map(|i| base + if i < extra { 1 } else { 0 }).collect()
+}
+
+/// Prices a child `aggressiveness_ticks` inside the spread from the passive touch.
+pub(crate) fn child_price(reference_price: i64, aggressiveness_ticks: i64, is_ask: bool) -> i64 {
+    if is_ask {
+        (reference_price - aggressiveness_ticks).max(1)
+    } else {
+        reference_price + aggressiveness_ticks
+    }
+}
+
+fn to_units(value: f64, decimals: u32) -> i64 {
+    (value * 10f64.powi(decimals as i32)).round() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slice_sizes_sum_to_total() {
+        assert_eq!(slice_sizes(10, 3), vec![4, 3, 3]);
+        assert_eq!(slice_sizes(9, 3), vec![3, 3, 3]);
+        assert_eq!(slice_sizes(2, 4), vec![1, 1, 0, 0]);
+        assert_eq!(slice_sizes(1_000_003, 7).iter().sum::<i64>(), 1_000_003);
+    }
+
+    #[test]
+    fn test_child_price_moves_inside_spread() {
+        assert_eq!(child_price(10_000, 5, false), 10_005);
+        assert_eq!(child_price(10_000, 5, true), 9_995);
+        assert_eq!(child_price(3, 10, true), 1);
+        assert_eq!(to_units(3024.66, 2), 302_466);
+    }
+}
@@ -0,0 +1,97 @@
+//! Order expiry defaults.
+//!
+//! `OrderExpiry` is the time (ms) at which a resting order is cancelled by the exchange;
+//! 0 means no expiry and is required for orders that never rest. The client fills it in
+//! from its `ExpiryPolicy` unless the order sets `order_expiry` explicitly.
+
+use crate::{CreateOrderRequest, OrderType, TimeInForce};
+use std::time::Duration;
+
+/// Lifetime of resting limit orders unless configured otherwise (28 days).
+pub const DEFAULT_RESTING_EXPIRY: Duration = Duration::from_secs(28 * 24 * 60 * 60);
+
+/// Default `OrderExpiry` per kind of order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExpiryPolicy {
+    /// Limit orders that rest on the book (GoodTillTime or PostOnly); `None` for no expiry.
+    pub resting: Option<Duration>,
+    /// Stop-loss and take-profit orders; `None` (the default) for no expiry.
+    pub trigger: Option<Duration>,
+}
+
+impl Default for ExpiryPolicy {
+    fn default() -> Self {
+        Self {
+            resting: Some(DEFAULT_RESTING_EXPIRY),
+            trigger: None,
+        }
+    }
+}
+
+impl ExpiryPolicy {
+    /// `OrderExpiry` of `order` signed at `now_ms`
+    ///
+    /// An explicit `order.order_expiry` wins. Otherwise resting limit orders and trigger
+    /// orders get `now_ms` plus their configured lifetime, and everything else (market,
+    /// immediate-or-cancel and TWAP orders) gets 0.
+    pub fn order_expiry(&self, order: &CreateOrderRequest, now_ms: i64) -> i64 {
+        if let Some(order_expiry) = order.order_expiry {
+            return order_expiry;
+        }
+
+        let lifetime = if order.order_type.is_trigger() {
+            self.trigger
+        } else if order.order_type == OrderType::Limit && order.time_in_force != TimeInForce::ImmediateOrCancel {
+            self.resting
+        } else {
+            None
+        };
+        lifetime.map_or(0, |lifetime| now_ms + lifetime.as_millis() as i64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BaseAmount, Price};
+
+    fn order(order_type: OrderType, time_in_force: TimeInForce) -> CreateOrderRequest {
+        CreateOrderRequest {
+            account_index: 1,
+            order_book_index: 0,
+            client_order_index: 1,
+            base_amount: BaseAmount(100),
+            price: Price(300_000),
+            is_ask: false,
+            order_type,
+            time_in_force,
+            reduce_only: false,
+            trigger_price: Price::ZERO,
+            price_protection: None,
+            order_expiry: None,
+        }
+    }
+
+    #[test]
+    fn test_policy_by_order_kind() {
+        let now = 1_700_000_000_000;
+        let days_28 = DEFAULT_RESTING_EXPIRY.as_millis() as i64;
+        let policy = ExpiryPolicy::default();
+
+        assert_eq!(policy.order_expiry(&order(OrderType::Limit, TimeInForce::GoodTillTime), now), now + days_28);
+        assert_eq!(policy.order_expiry(&order(OrderType::Limit, TimeInForce::PostOnly), now), now + days_28);
+        assert_eq!(policy.order_expiry(&order(OrderType::Limit, TimeInForce::ImmediateOrCancel), now), 0);
+        assert_eq!(policy.order_expiry(&order(OrderType::Market, TimeInForce::ImmediateOrCancel), now), 0);
+        assert_eq!(policy.order_expiry(&order(OrderType::StopLoss, TimeInForce::GoodTillTime), now), 0);
+
+        let policy = ExpiryPolicy {
+            resting: Some(Duration::from_secs(60)),
+            trigger: Some(Duration::from_secs(3600)),
+        };
+        assert_eq!(policy.order_expiry(&order(OrderType::TakeProfitLimit, TimeInForce::GoodTillTime), now), now + 3_600_000);
+
+        let mut explicit = order(OrderType::Limit, TimeInForce::GoodTillTime);
+        explicit.order_expiry = Some(now + 5_000);
+        assert_eq!(policy.order_expiry(&explicit, now), now + 5_000);
+    }
+}
@@ -0,0 +1,197 @@
+//! Fee and margin estimates.
+//!
+//! `orderBookDetails` reports each market's maker and taker fee (percent strings) and its
+//! margin fractions (per 10,000: 500 is 5%, i.e. 20x). `MarketFees` reads them, and the
+//! estimates below apply them to an order the way the exchange does, so sizing code does
+//! not need fee rates or leverage limits from its own configuration.
+
+use crate::positions::decimal_to_units;
+use crate::validation::MarketRules;
+use crate::{ApiError, BaseAmount, CreateOrderRequest, LighterClient, OrderType, Price, Result, TimeInForce};
+use serde_json::Value;
+
+/// Margin fractions are expressed per this many units.
+pub const MARGIN_FRACTION_SCALE: u32 = 10_000;
+
+/// Fee rates and margin requirements of a market.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MarketFees {
+    /// Fee charged on the notional of maker fills, as a fraction (0.0002 = 0.02%).
+    pub maker_fee: f64,
+    /// Fee charged on the notional of taker fills, as a fraction.
+    pub taker_fee: f64,
+    /// Lowest initial margin fraction, which caps the leverage.
+    pub min_initial_margin_fraction: u32,
+    /// Initial margin fraction of accounts that never set a leverage.
+    pub default_initial_margin_fraction: u32,
+    pub maintenance_margin_fraction: u32,
+}
+
+impl MarketFees {
+    /// Reads fees and margin fractions from an `orderBookDetails` entry.
+    pub fn from_order_book_details(details: &Value) -> Result<Self> {
+        let fee = |field: &str| {
+            details[field]
+                .as_str()
+                .and_then(|s| s.parse::<f64>().ok())
+                .map(|percent| percent / 100.0)
+                .ok_or_else(|| ApiError::Api(format!("Market details without {}: {}", field, details)))
+        };
+        let fraction = |field: &str| {
+            let value = &details[field];
+            value
+                .as_u64()
+                .or_else(|| value.as_str().and_then(|s| s.parse().ok()))
+                .and_then(|v| u32::try_from(v).ok())
+                .filter(|v| (1..=MARGIN_FRACTION_SCALE).contains(v))
+                .ok_or_else(|| ApiError::Api(format!("Market details without {}: {}", field, details)))
+        };
+
+        let min_initial_margin_fraction = fraction("min_initial_margin_fraction")?;
+        Ok(Self {
+            maker_fee: fee("maker_fee")?,
+            taker_fee: fee("taker_fee")?,
+            min_initial_margin_fraction,
+            default_initial_margin_fraction: fraction("default_initial_margin_fraction")
+                .unwrap_or(min_initial_margin_fraction),
+            maintenance_margin_fraction: fraction("maintenance_margin_fraction")?,
+        })
+    }
+
+    /// Highest leverage the market allows.
+    pub fn max_leverage(&self) -> u32 {
+        MARGIN_FRACTION_SCALE / self.min_initial_margin_fraction
+    }
+
+    /// Initial margin fraction at `leverage`; errors for 0 or above `max_leverage`.
+    pub fn initial_margin_fraction(&self, leverage: u32) -> Result<f64> {
+        if leverage == 0 || leverage > self.max_leverage() {
+            return Err(ApiError::InvalidOrder(format!(
+                "Leverage must be between 1 and {}, got {}",
+                self.max_leverage(),
+                leverage
+            )));
+        }
+        Ok(1.0 / leverage as f64)
+    }
+
+    /// Fee rate `order` pays, assuming it takes liquidity unless it is post-only.
+    pub fn fee_rate(&self, order: &CreateOrderRequest) -> f64 {
+        if order.order_type == OrderType::Limit && order.time_in_force == TimeInForce::PostOnly {
+            self.maker_fee
+        } else {
+            self.taker_fee
+        }
+    }
+
+    /// Fee of `order` in quote currency.
+    pub fn estimate_fee(&self, rules: &MarketRules, order: &CreateOrderRequest) -> f64 {
+        rules.notional(order.base_amount, order.price) * self.fee_rate(order)
+    }
+
+    /// Initial margin `order` needs at `leverage`, in quote currency.
+    pub fn estimate_initial_margin(&self, rules: &MarketRules, order: &CreateOrderRequest, leverage: u32) -> Result<f64> {
+        Ok(rules.notional(order.base_amount, order.price) * self.initial_margin_fraction(leverage)?)
+    }
+
+    /// Largest size (base units) whose initial margin and taker fee at `price` fit in `balance`.
+    pub fn max_open_size(&self, rules: &MarketRules, price: Price, leverage: u32, balance: f64) -> Result<i64> {
+        if price.is_zero() {
+            return Err(ApiError::InvalidOrder("Price must be positive, got 0".to_string()));
+        }
+        let cost_per_unit = rules.notional(BaseAmount(1), price) * (self.initial_margin_fraction(leverage)? + self.taker_fee);
+        let units = (balance.max(0.0) / cost_per_unit).floor() as i64;
+        // Round down to what the market accepts
+        Ok(if units < rules.min_base_amount { 0 } else { units })
+    }
+}
+
+impl LighterClient {
+    /// Load the fees and margin fractions of a market from `orderBookDetails`
+    pub async fn get_market_fees(&self, market_index: u8) -> Result<MarketFees> {
+        MarketFees::from_order_book_details(&self.order_book_details(market_index).await?)
+    }
+
+    /// Estimated fee of `order` in quote currency
+    ///
+    /// Post-only orders pay the maker fee, everything else the taker fee. Market orders are
+    /// valued at their worst price.
+    pub async fn estimate_fee(&self, order: &CreateOrderRequest) -> Result<f64> {
+        let details = self.order_book_details(order.order_book_index).await?;
+        let rules = MarketRules::from_order_book_details(&details)?;
+        Ok(MarketFees::from_order_book_details(&details)?.estimate_fee(&rules, order))
+    }
+
+    /// Initial margin `order` needs at `leverage`, in quote currency
+    ///
+    /// Fails with `ApiError::InvalidOrder` if the market does not allow `leverage`.
+    pub async fn estimate_initial_margin(&self, order: &CreateOrderRequest, leverage: u32) -> Result<f64> {
+        let details = self.order_book_details(order.order_book_index).await?;
+        let rules = MarketRules::from_order_book_details(&details)?;
+        MarketFees::from_order_book_details(&details)?.estimate_initial_margin(&rules, order, leverage)
+    }
+
+    /// Largest position (base units) `balance` can open on a market at `leverage`
+    ///
+    /// Priced at the market's last trade and paying the taker fee; 0 if even the minimum
+    /// order size does not fit.
+    pub async fn max_open_size(&self, market_index: u8, leverage: u32, balance: f64) -> Result<i64> {
+        let details = self.order_book_details(market_index).await?;
+        let rules = MarketRules::from_order_book_details(&details)?;
+        let price = details["last_trade_price"]
+            .as_str()
+            .and_then(|s| decimal_to_units(s, rules.price_decimals))
+            .or_else(|| details["last_trade_price"].as_f64().map(|p| (p * 10f64.powi(rules.price_decimals as i32)) as i64))
+            .ok_or_else(|| ApiError::Api(format!("No last trade price for market {}", market_index)))?;
+        let price = Price::try_from(price)?;
+        MarketFees::from_order_book_details(&details)?.max_open_size(&rules, price, leverage, balance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_estimates() {
+        let details = json!({
+            "price_decimals": 2,
+            "size_decimals": 4,
+            "maker_fee": "0.0020",
+            "taker_fee": "0.0200",
+            "min_initial_margin_fraction": 500,
+            "maintenance_margin_fraction": 300,
+        });
+        let fees = MarketFees::from_order_book_details(&details).unwrap();
+        let rules = MarketRules::from_order_book_details(&details).unwrap();
+        assert_eq!(fees.max_leverage(), 20);
+        assert_eq!(fees.default_initial_margin_fraction, 500);
+
+        // 1 ETH at 3000.00
+        let mut order = CreateOrderRequest {
+            account_index: 1,
+            order_book_index: 0,
+            client_order_index: 1,
+            base_amount: BaseAmount(10_000),
+            price: Price(300_000),
+            is_ask: false,
+            order_type: OrderType::Limit,
+            time_in_force: TimeInForce::GoodTillTime,
+            reduce_only: false,
+            trigger_price: Price::ZERO,
+            price_protection: None,
+            order_expiry: None,
+        };
+        assert!((fees.estimate_fee(&rules, &order) - 0.6).abs() < 1e-9);
+        order.time_in_force = TimeInForce::PostOnly;
+        assert!((fees.estimate_fee(&rules, &order) - 0.06).abs() < 1e-9);
+
+        assert!((fees.estimate_initial_margin(&rules, &order, 6).unwrap() - 500.0).abs() < 1e-9);
+        assert!(matches!(fees.estimate_initial_margin(&rules, &order, 0), Err(ApiError::InvalidOrder(_))));
+        assert!(fees.estimate_initial_margin(&rules, &order, 21).is_err());
+
+        // 1000 USDC at 10x: 1000 / (3000 * (0.1 + 0.0002)) = 3.3266 ETH
+        assert_eq!(fees.max_open_size(&rules, Price(300_000), 10, 1000.0).unwrap(), 33_266);
+    }
+}
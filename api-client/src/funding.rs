@@ -0,0 +1,221 @@
+//! Funding payment history.
+//!
+//! `positionFunding` lists the funding the account paid or received on each of its
+//! positions, one entry per market and funding interval. `get_funding_payments` reads it a
+//! page at a time; `funding_carry` groups the payments into positions, so the realized
+//! carry of each position can be added to its trading PnL.
+
+use crate::decimal::{string_or_number_decimal, Decimal};
+use crate::{LighterClient, Result};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Funding paid or received on a position for one interval.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict", serde(deny_unknown_fields))]
+pub struct FundingPayment {
+    /// Seconds.
+    pub timestamp: i64,
+    #[serde(rename = "market_id")]
+    pub market_index: u8,
+    #[serde(default)]
+    pub funding_id: i64,
+    /// USDC credited to the account; negative when the position paid funding.
+    #[serde(with = "string_or_number_decimal")]
+    pub change: Decimal,
+    /// Funding rate of the interval.
+    #[serde(with = "string_or_number_decimal", default)]
+    pub rate: Decimal,
+    /// Position size the payment was computed on, in base currency.
+    #[serde(with = "string_or_number_decimal", default)]
+    pub position_size: Decimal,
+    /// `"long"` or `"short"`.
+    #[serde(default)]
+    pub position_side: String,
+}
+
+impl FundingPayment {
+    /// USDC credited to the account, the same as `change`.
+    pub fn amount(&self) -> Decimal {
+        self.change
+    }
+
+    pub fn is_long(&self) -> bool {
+        self.position_side != "short"
+    }
+}
+
+/// One page of funding payments.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FundingPage {
+    #[serde(rename = "position_fundings", default)]
+    pub payments: Vec<FundingPayment>,
+    /// Cursor for the next page, if any.
+    #[serde(default)]
+    pub next_cursor: Option<String>,
+}
+
+/// Which payments `get_funding_payments` returns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FundingFilter {
+    /// Restrict to one market, or `None` for all.
+    pub market_index: Option<u8>,
+    /// `Some(true)` for long positions only, `Some(false)` for shorts only.
+    pub is_long: Option<bool>,
+    /// Only payments in `[start, end]` (seconds). Applied to each page after it is fetched,
+    /// so a page can come back with fewer entries than `limit`.
+    pub between_timestamps: Option<(i64, i64)>,
+    /// Page size (the exchange allows 1 to 100).
+    pub limit: u32,
+    /// Cursor returned by the previous page, or `None` for the first page.
+    pub cursor: Option<String>,
+}
+
+impl Default for FundingFilter {
+    fn default() -> Self {
+        Self {
+            market_index: None,
+            is_long: None,
+            between_timestamps: None,
+            limit: 100,
+            cursor: None,
+        }
+    }
+}
+
+impl FundingFilter {
+    fn query(&self) -> Vec<(&'static str, String)> {
+        let mut query = vec![("limit", self.limit.clamp(1, 100).to_string())];
+        if let Some(market_index) = self.market_index {
+            query.push(("market_id", market_index.to_string()));
+        }
+        if let Some(is_long) = self.is_long {
+            query.push(("side", if is_long { "long" } else { "short" }.to_string()));
+        }
+        if let Some(cursor) = &self.cursor {
+            query.push(("cursor", cursor.clone()));
+        }
+        query
+    }
+
+    fn matches(&self, payment: &FundingPayment) -> bool {
+        self.between_timestamps
+            .is_none_or(|(start, end)| (start..=end).contains(&payment.timestamp))
+    }
+}
+
+/// Funding realized over one position: consecutive payments on a market with the same side.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FundingCarry {
+    pub market_index: u8,
+    pub is_long: bool,
+    /// Timestamps (s) of the first and last payment.
+    pub first_payment: i64,
+    pub last_payment: i64,
+    pub payments: usize,
+    /// Net USDC received; negative when the position paid funding.
+    pub carry: Decimal,
+}
+
+/// Groups payments into positions and sums the funding of each
+///
+/// A position is a run of payments on one market with the same side and no missed
+/// interval: a side flip or a gap longer than 1.5 `interval`s (the account was flat in
+/// between) starts a new one. Results are ordered by market, then time.
+pub fn funding_carry(payments: &[FundingPayment], interval: Duration) -> Vec<FundingCarry> {
+    let max_gap = (interval.as_secs_f64() * 1.5) as i64;
+    let mut sorted: Vec<&FundingPayment> = payments.iter().collect();
+    sorted.sort_by_key(|p| (p.market_index, p.timestamp));
+
+    let mut carries: Vec<FundingCarry> = Vec::new();
+    for payment in sorted {
+        match carries.last_mut() {
+            Some(carry)
+                if carry.market_index == payment.market_index
+                    && carry.is_long == payment.is_long()
+                    && payment.timestamp - carry.last_payment <= max_gap =>
+            {
+                carry.last_payment = payment.timestamp;
+                carry.payments += 1;
+                carry.carry += payment.amount();
+            }
+            _ => carries.push(FundingCarry {
+                market_index: payment.market_index,
+                is_long: payment.is_long(),
+                first_payment: payment.timestamp,
+                last_payment: payment.timestamp,
+                payments: 1,
+                carry: payment.amount(),
+            }),
+        }
+    }
+    carries
+}
+
+impl LighterClient {
+    /// Funding payments of this account, one page at a time
+    ///
+    /// Pass the returned `next_cursor` in `filter.cursor` to get the next page.
+    pub async fn get_funding_payments(&self, filter: &FundingFilter) -> Result<FundingPage> {
+        let mut query = vec![("account_index", self.account_index.to_string())];
+        query.extend(filter.query());
+        let response = self.get_authenticated_json("/api/v1/positionFunding", &query).await?;
+        let mut page: FundingPage = self.parse_typed("/api/v1/positionFunding", response)?;
+        page.payments.retain(|payment| filter.matches(payment));
+        Ok(page)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_and_carry() {
+        let page: FundingPage = serde_json::from_value(json!({
+            "code": 200,
+            "position_fundings": [
+                {"timestamp": 3600, "market_id": 0, "funding_id": 1, "change": "-0.50", "rate": "0.0001",
+                 "position_size": "1.0", "position_side": "long"},
+                {"timestamp": 7200, "market_id": 0, "change": "-0.25", "position_side": "long"},
+                {"timestamp": 3600, "market_id": 1, "change": "2.00", "position_side": "short"},
+                // Flat for two intervals, then long again
+                {"timestamp": 18000, "market_id": 0, "change": "-1.00", "position_side": "long"},
+                {"timestamp": 21600, "market_id": 0, "change": "0.10", "position_side": "short"}
+            ],
+            "next_cursor": "n"
+        }))
+        .unwrap();
+        assert_eq!(page.next_cursor.as_deref(), Some("n"));
+        assert_eq!(page.payments[0].amount(), Decimal::new(-5, 1));
+        assert_eq!(page.payments[0].rate, Decimal::new(1, 4));
+
+        let carries = funding_carry(&page.payments, Duration::from_secs(3600));
+        let summary: Vec<(u8, bool, usize, Decimal)> =
+            carries.iter().map(|c| (c.market_index, c.is_long, c.payments, c.carry)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                (0, true, 2, Decimal::new(-75, 2)),
+                (0, true, 1, Decimal::new(-1, 0)),
+                (0, false, 1, Decimal::new(1, 1)),
+                (1, false, 1, Decimal::new(2, 0)),
+            ]
+        );
+        assert_eq!((carries[0].first_payment, carries[0].last_payment), (3600, 7200));
+
+        let filter = FundingFilter {
+            market_index: Some(0),
+            is_long: Some(false),
+            between_timestamps: Some((0, 10_000)),
+            ..Default::default()
+        };
+        assert_eq!(
+            filter.query(),
+            vec![("limit", "100".to_string()), ("market_id", "0".to_string()), ("side", "short".to_string())]
+        );
+        assert!(filter.matches(&page.payments[1]));
+        assert!(!filter.matches(&page.payments[3]));
+    }
+}
@@ -0,0 +1,202 @@
+//! Validated construction of grouped orders.
+//!
+//! The exchange accepts three groupings, each with a fixed shape:
+//!
+//! | Grouping | Orders | Shape |
+//! |----------|--------|-------|
+//! | OTO (1) | 2 | entry order, then a TP or SL that is armed once it fills |
+//! | OCO (2) | 2 | a TP and an SL; filling one cancels the other |
+//! | OTOCO (3) | 3 | entry order, then a TP and an SL in OCO |
+//!
+//! Take-profit and stop-loss legs must be reduce-only trigger orders closing the entry
+//! (or, for OCO, the existing position). `GroupedOrdersBuilder` checks these invariants
+//! before anything is signed, so a malformed group fails locally instead of costing a
+//! nonce and a round trip.
+
+use crate::{ApiError, CreateGroupedOrdersRequest, CreateOrderRequest, GroupingType, OrderType, Result};
+
+/// Builds a `CreateGroupedOrdersRequest`, validating it for its grouping type.
+///
+/// Orders are sent in the order they are added: the entry order comes first for OTO and
+/// OTOCO; take-profit and stop-loss legs may come in either order.
+#[derive(Debug)]
+pub struct GroupedOrdersBuilder {
+    grouping_type: GroupingType,
+    orders: Vec<CreateOrderRequest>,
+}
+
+impl GroupedOrdersBuilder {
+    pub fn new(grouping_type: GroupingType) -> Self {
+        Self {
+            grouping_type,
+            orders: Vec::new(),
+        }
+    }
+
+    pub fn oto() -> Self {
+        Self::new(GroupingType::Oto)
+    }
+
+    pub fn oco() -> Self {
+        Self::new(GroupingType::Oco)
+    }
+
+    pub fn otoco() -> Self {
+        Self::new(GroupingType::Otoco)
+    }
+
+    pub fn order(mut self, order: CreateOrderRequest) -> Self {
+        self.orders.push(order);
+        self
+    }
+
+    /// Validate the group and return the request, or an `ApiError::InvalidOrder`
+    /// describing the first violated invariant.
+    pub fn build(self) -> Result<CreateGroupedOrdersRequest> {
+        validate_group(self.grouping_type, &self.orders)?;
+        Ok(CreateGroupedOrdersRequest {
+            grouping_type: self.grouping_type,
+            orders: self.orders,
+        })
+    }
+}
+
+fn invalid(message: String) -> ApiError {
+    ApiError::InvalidOrder(message)
+}
+
+fn is_take_profit(order: &CreateOrderRequest) -> bool {
+    order.order_type.is_take_profit()
+}
+
+fn is_stop_loss(order: &CreateOrderRequest) -> bool {
+    order.order_type.is_stop_loss()
+}
+
+pub(crate) fn validate_group(grouping_type: GroupingType, orders: &[CreateOrderRequest]) -> Result<()> {
+    let (name, expected_len) = match grouping_type {
+        GroupingType::Oto => ("OTO", 2),
+        GroupingType::Oco => ("OCO", 2),
+        GroupingType::Otoco => ("OTOCO", 3),
+    };
+    if orders.len() != expected_len {
+        return Err(invalid(format!(
+            "{} takes exactly {} orders, got {}",
+            name,
+            expected_len,
+            orders.len()
+        )));
+    }
+
+    let market = orders[0].order_book_index;
+    if let Some(other) = orders.iter().find(|o| o.order_book_index != market) {
+        return Err(invalid(format!(
+            "All orders of a group must be on one market, got {} and {}",
+            market, other.order_book_index
+        )));
+    }
+
+    // Entry order first for OTO and OTOCO; the rest are exit legs
+    let (entry, legs) = match grouping_type {
+        GroupingType::Oco => (None, orders),
+        _ => (Some(&orders[0]), &orders[1..]),
+    };
+
+    if let Some(entry) = entry {
+        if !matches!(entry.order_type, OrderType::Limit | OrderType::Market) {
+            return Err(invalid(format!(
+                "{} entry order must be a limit or market order, got type {}",
+                name, entry.order_type
+            )));
+        }
+        if entry.base_amount.0 <= 0 {
+            return Err(invalid(format!("{} entry order needs a positive base amount", name)));
+        }
+    }
+
+    for (i, leg) in legs.iter().enumerate() {
+        if !is_take_profit(leg) && !is_stop_loss(leg) {
+            return Err(invalid(format!(
+                "{} leg {} must be a take-profit or stop-loss order, got type {}",
+                name, i, leg.order_type
+            )));
+        }
+        if !leg.reduce_only {
+            return Err(invalid(format!("{} leg {} (type {}) must be reduce-only", name, i, leg.order_type)));
+        }
+        if leg.trigger_price.is_zero() {
+            return Err(invalid(format!("{} leg {} needs a trigger price", name, i)));
+        }
+        if let Some(entry) = entry {
+            if leg.is_ask == entry.is_ask {
+                return Err(invalid(format!("{} leg {} must be on the opposite side of the entry order", name, i)));
+            }
+        }
+    }
+
+    if legs.len() == 2 {
+        if legs[0].is_ask != legs[1].is_ask {
+            return Err(invalid(format!("{} take-profit and stop-loss must be on the same side", name)));
+        }
+        if !(legs.iter().any(is_take_profit) && legs.iter().any(is_stop_loss)) {
+            return Err(invalid(format!("{} needs one take-profit and one stop-loss order", name)));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BaseAmount, Price, TimeInForce};
+
+    fn order(order_type: OrderType, is_ask: bool, reduce_only: bool, trigger_price: u64) -> CreateOrderRequest {
+        CreateOrderRequest {
+            account_index: 1,
+            order_book_index: 0,
+            client_order_index: 0,
+            base_amount: BaseAmount(1000),
+            price: Price(3_000_000),
+            is_ask,
+            order_type,
+            time_in_force: TimeInForce::GoodTillTime,
+            reduce_only,
+            trigger_price: Price(trigger_price),
+            price_protection: None,
+            order_expiry: None,
+        }
+    }
+
+    #[test]
+    fn test_otoco_accepts_entry_with_tp_and_sl() {
+        let request = GroupedOrdersBuilder::otoco()
+            .order(order(OrderType::Limit, false, false, 0))
+            .order(order(OrderType::TakeProfit, true, true, 3_200_000))
+            .order(order(OrderType::StopLoss, true, true, 2_800_000))
+            .build()
+            .unwrap();
+        assert_eq!(request.grouping_type, GroupingType::Otoco);
+        assert_eq!(request.orders.len(), 3);
+    }
+
+    #[test]
+    fn test_rejects_malformed_groups() {
+        let too_few = GroupedOrdersBuilder::oco().order(order(OrderType::TakeProfit, true, true, 1)).build();
+        assert!(matches!(too_few, Err(ApiError::InvalidOrder(m)) if m.contains("exactly 2")));
+
+        let mut other_market = order(OrderType::StopLoss, true, true, 1);
+        other_market.order_book_index = 1;
+        let mixed = GroupedOrdersBuilder::oco().order(order(OrderType::TakeProfit, true, true, 1)).order(other_market).build();
+        assert!(matches!(mixed, Err(ApiError::InvalidOrder(m)) if m.contains("one market")));
+
+        let not_reduce_only = GroupedOrdersBuilder::oto()
+            .order(order(OrderType::Limit, false, false, 0))
+            .order(order(OrderType::StopLoss, true, false, 1))
+            .build();
+        assert!(matches!(not_reduce_only, Err(ApiError::InvalidOrder(m)) if m.contains("reduce-only")));
+
+        let two_stops = GroupedOrdersBuilder::oco().order(order(OrderType::StopLoss, true, true, 1)).order(order(OrderType::StopLossLimit, true, true, 1)).build();
+        assert!(matches!(two_stops, Err(ApiError::InvalidOrder(m)) if m.contains("one take-profit")));
+    }
+}
@@ -0,0 +1,350 @@
+//! Transaction journal.
+//!
+//! A signed transaction that was posted but whose response never arrived may or may not
+//! have been executed. With a journal configured, the client records every transaction
+//! (nonce, type and signed payload) before posting it and updates the entry from the
+//! response. After a crash, `resubmit_unresolved` settles the open entries: a nonce the
+//! exchange has already consumed means the transaction landed, otherwise the identical
+//! signed payload is posted again. Since a nonce can only be used once, a transaction is
+//! executed at most once however often it is resubmitted.
+//!
+//! `FileJournal` persists to an append-only JSON-lines file and `MemoryJournal` keeps
+//! entries in memory. Other stores (e.g. a database) can implement `TxJournal`.
+
+use crate::{ApiError, LighterClient, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Outcome of a journaled transaction.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum TxStatus {
+    /// Recorded, response not received yet.
+    Pending,
+    /// Accepted by the exchange (code 200).
+    Accepted { tx_hash: Option<String> },
+    /// Rejected by the exchange; the nonce was not consumed.
+    Rejected { code: i64, message: String },
+    /// Expired before it could be resubmitted; the nonce was not consumed.
+    Expired,
+    /// Sent, but no usable response (transport error, malformed body).
+    Unknown,
+}
+
+impl TxStatus {
+    /// Status for the result of posting a transaction to sendTx
+    pub fn from_response(response: &Result<Value>) -> Self {
+        let Ok(response) = response else {
+            return TxStatus::Unknown;
+        };
+        match response["code"].as_i64() {
+            Some(200) => TxStatus::Accepted {
+                tx_hash: response["tx_hash"].as_str().map(str::to_string),
+            },
+            Some(code) => TxStatus::Rejected {
+                code,
+                message: response["message"].as_str().unwrap_or_default().to_string(),
+            },
+            None => TxStatus::Unknown,
+        }
+    }
+
+    /// Whether the outcome is still open (`Pending` or `Unknown`)
+    pub fn is_unresolved(&self) -> bool {
+        matches!(self, TxStatus::Pending | TxStatus::Unknown)
+    }
+}
+
+/// A journaled transaction, keyed by account, API key and nonce.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct JournalEntry {
+    pub account_index: i64,
+    pub api_key_index: u8,
+    pub nonce: i64,
+    pub tx_type: u32,
+    /// Signed `tx_info` exactly as posted.
+    pub tx_info: Value,
+    pub price_protection: String,
+    /// `ExpiredAt` of the transaction (ms); it cannot be executed afterwards.
+    pub expired_at: i64,
+    pub status: TxStatus,
+    /// Time of the last status change (ms).
+    pub updated_at: i64,
+}
+
+impl JournalEntry {
+    /// Pending entry for a signed transaction
+    pub fn new(
+        account_index: i64,
+        api_key_index: u8,
+        tx_type: u32,
+        tx_info: &Value,
+        price_protection: &str,
+    ) -> Result<Self> {
+        let nonce = tx_info["Nonce"]
+            .as_i64()
+            .ok_or_else(|| ApiError::Journal("tx_info has no Nonce".to_string()))?;
+        Ok(Self {
+            account_index,
+            api_key_index,
+            nonce,
+            tx_type,
+            tx_info: tx_info.clone(),
+            price_protection: price_protection.to_string(),
+            expired_at: tx_info["ExpiredAt"].as_i64().unwrap_or(i64::MAX),
+            status: TxStatus::Pending,
+            updated_at: now_millis()?,
+        })
+    }
+
+    fn key(&self) -> (i64, u8, i64) {
+        (self.account_index, self.api_key_index, self.nonce)
+    }
+}
+
+/// Store of journaled transactions.
+///
+/// `record` inserts or replaces the entry with the same account, API key and nonce.
+pub trait TxJournal: Send + Sync {
+    fn record(&self, entry: &JournalEntry) -> Result<()>;
+
+    fn update(&self, account_index: i64, api_key_index: u8, nonce: i64, status: TxStatus) -> Result<()>;
+
+    /// Entries whose status is `Pending` or `Unknown`, ordered by account, key and nonce
+    fn unresolved(&self) -> Result<Vec<JournalEntry>>;
+}
+
+/// Journal kept in memory.
+#[derive(Default)]
+pub struct MemoryJournal {
+    entries: Mutex<BTreeMap<(i64, u8, i64), JournalEntry>>,
+}
+
+impl MemoryJournal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// All entries, ordered by account, key and nonce
+    pub fn entries(&self) -> Vec<JournalEntry> {
+        self.lock().values().cloned().collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<(i64, u8, i64), JournalEntry>> {
+        self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Applies a status change and returns the updated entry.
+    fn apply_update(&self, account_index: i64, api_key_index: u8, nonce: i64, status: TxStatus) -> Result<JournalEntry> {
+        let mut entries = self.lock();
+        let entry = entries.get_mut(&(account_index, api_key_index, nonce)).ok_or_else(|| {
+            ApiError::Journal(format!(
+                "No entry for account {} key {} nonce {}",
+                account_index, api_key_index, nonce
+            ))
+        })?;
+        entry.status = status;
+        entry.updated_at = now_millis()?;
+        Ok(entry.clone())
+    }
+}
+
+impl TxJournal for MemoryJournal {
+    fn record(&self, entry: &JournalEntry) -> Result<()> {
+        self.lock().insert(entry.key(), entry.clone());
+        Ok(())
+    }
+
+    fn update(&self, account_index: i64, api_key_index: u8, nonce: i64, status: TxStatus) -> Result<()> {
+        self.apply_update(account_index, api_key_index, nonce, status).map(|_| ())
+    }
+
+    fn unresolved(&self) -> Result<Vec<JournalEntry>> {
+        Ok(self.lock().values().filter(|e| e.status.is_unresolved()).cloned().collect())
+    }
+}
+
+/// Journal persisted to an append-only JSON-lines file.
+///
+/// Every change appends the full entry and syncs it to disk before returning; on open the
+/// file is replayed and the last line for each nonce wins. A truncated last line from an
+/// interrupted write is ignored.
+pub struct FileJournal {
+    state: MemoryJournal,
+    file: Mutex<File>,
+}
+
+impl FileJournal {
+    /// Open (or create) the journal at `path`
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let state = MemoryJournal::new();
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(path)
+            .map_err(io_error)?;
+
+        let mut contents = String::new();
+        file.read_to_string(&mut contents).map_err(io_error)?;
+        for line in contents.lines() {
+            if let Ok(entry) = serde_json::from_str::<JournalEntry>(line) {
+                state.record(&entry)?;
+            }
+        }
+        // Terminate a truncated last line so the next entry starts on its own line
+        if !contents.is_empty() && !contents.ends_with('\n') {
+            file.write_all(b"\n").map_err(io_error)?;
+        }
+
+        Ok(Self {
+            state,
+            file: Mutex::new(file),
+        })
+    }
+
+    /// All entries, ordered by account, key and nonce
+    pub fn entries(&self) -> Vec<JournalEntry> {
+        self.state.entries()
+    }
+
+    fn append(&self, entry: &JournalEntry) -> Result<()> {
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        let mut file = self.file.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        file.write_all(line.as_bytes()).map_err(io_error)?;
+        file.sync_data().map_err(io_error)
+    }
+}
+
+impl TxJournal for FileJournal {
+    fn record(&self, entry: &JournalEntry) -> Result<()> {
+        self.append(entry)?;
+        self.state.record(entry)
+    }
+
+    fn update(&self, account_index: i64, api_key_index: u8, nonce: i64, status: TxStatus) -> Result<()> {
+        let entry = self.state.apply_update(account_index, api_key_index, nonce, status)?;
+        self.append(&entry)
+    }
+
+    fn unresolved(&self) -> Result<Vec<JournalEntry>> {
+        self.state.unresolved()
+    }
+}
+
+fn io_error(e: std::io::Error) -> ApiError {
+    ApiError::Journal(e.to_string())
+}
+
+fn now_millis() -> Result<i64> {
+    Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64)
+}
+
+impl LighterClient {
+    /// Settle the journal's open transactions of this API key
+    ///
+    /// Call on startup, before sending new transactions. Entries whose nonce the exchange
+    /// has already consumed are marked accepted; expired ones are marked expired; the rest
+    /// are posted again with their original signed payload, lowest nonce first. Returns the
+    /// responses of the resubmitted transactions. The local nonce pool is resynced
+    /// afterwards. Without a journal this does nothing.
+    pub async fn resubmit_unresolved(&self) -> Result<Vec<Value>> {
+        let Some(journal) = &self.journal else {
+            return Ok(Vec::new());
+        };
+
+        let open: Vec<JournalEntry> = journal
+            .unresolved()?
+            .into_iter()
+            .filter(|e| e.account_index == self.account_index && e.api_key_index == self.api_key_index)
+            .collect();
+        if open.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let next_nonce = self.fetch_nonce_from_api().await?;
+        let now = now_millis()?;
+        let mut responses = Vec::new();
+        for entry in open {
+            if entry.nonce < next_nonce {
+                journal.update(entry.account_index, entry.api_key_index, entry.nonce, TxStatus::Accepted { tx_hash: None })?;
+            } else if entry.expired_at <= now {
+                journal.update(entry.account_index, entry.api_key_index, entry.nonce, TxStatus::Expired)?;
+            } else {
+                responses.push(self.send_tx(entry.tx_type, &entry.tx_info, &entry.price_protection).await?);
+            }
+        }
+
+        self.refresh_nonce().await?;
+        Ok(responses)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn entry(nonce: i64) -> JournalEntry {
+        let tx_info = json!({"AccountIndex": 7, "Nonce": nonce, "ExpiredAt": 1_000, "Sig": "c2ln"});
+        JournalEntry::new(7, 3, 14, &tx_info, "true").unwrap()
+    }
+
+    #[test]
+    fn test_status_from_response() {
+        let accepted = TxStatus::from_response(&Ok(json!({"code": 200, "tx_hash": "ab"})));
+        assert_eq!(accepted, TxStatus::Accepted { tx_hash: Some("ab".to_string()) });
+
+        let rejected = TxStatus::from_response(&Ok(json!({"code": 21104, "message": "invalid nonce"})));
+        assert_eq!(
+            rejected,
+            TxStatus::Rejected {
+                code: 21104,
+                message: "invalid nonce".to_string()
+            }
+        );
+
+        let lost = TxStatus::from_response(&Err(ApiError::Api("connection reset".to_string())));
+        assert_eq!(lost, TxStatus::Unknown);
+    }
+
+    #[test]
+    fn test_file_journal_replays_last_status() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tx.jsonl");
+
+        {
+            let journal = FileJournal::open(&path).unwrap();
+            journal.record(&entry(10)).unwrap();
+            journal.record(&entry(11)).unwrap();
+            journal.update(7, 3, 10, TxStatus::Accepted { tx_hash: None }).unwrap();
+            journal.update(7, 3, 11, TxStatus::Unknown).unwrap();
+        }
+        // Simulate a crash in the middle of a write
+        OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"{\"account_index\":7,\"api")
+            .unwrap();
+
+        let reopened = FileJournal::open(&path).unwrap();
+        reopened.record(&entry(12)).unwrap();
+        drop(reopened);
+
+        let reopened = FileJournal::open(&path).unwrap();
+        assert_eq!(reopened.entries().len(), 3);
+        let open = reopened.unresolved().unwrap();
+        assert_eq!(open.len(), 2);
+        assert_eq!(open[0].nonce, 11);
+        assert_eq!(open[0].tx_info["Sig"], "c2ln");
+    }
+}
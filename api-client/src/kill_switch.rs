@@ -0,0 +1,283 @@
+//! Operator kill switch.
+//!
+//! A `KillSwitch` set on the builder is consulted before every transaction is posted. While
+//! it is tripped, transactions that can add exposure (new orders that are not reduce-only,
+//! grouped orders with such an order, and modifies) fail with `ApiError::Halted` and are
+//! never sent. Cancels, reduce-only closes and account transactions go through, so a bot
+//! that is halted mid-close can still finish closing.
+//!
+//! The switch is tripped by any of:
+//!
+//! - a file: `with_file(path)` trips while the file exists, and its contents are the
+//!   reason. `trip` writes the file, so the halt survives a restart, and `reset` removes it.
+//!   Operators can `touch` it by hand. The check before a transaction reads it with
+//!   `tokio::fs`, off the runtime's worker threads.
+//! - an environment variable: `with_env(name)` trips while the variable is set to anything
+//!   but empty, `0` or `false`; read on every check.
+//! - a URL: `poll_url` fetches `{"halted": bool, "reason": "..."}` at an interval of at
+//!   least `MIN_POLL_INTERVAL`. Failed fetches keep the last answer.
+//! - `trip`, from code in the same process.
+
+use crate::{ApiError, LighterClient, Result};
+use serde::Deserialize;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Shortest interval `poll_url` fetches at; shorter ones are raised to it.
+pub const MIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Flag checked before each transaction is posted; clones share the tripped state.
+#[derive(Debug, Clone, Default)]
+pub struct KillSwitch {
+    file: Option<PathBuf>,
+    env_var: Option<String>,
+    // Reason given to `trip`
+    manual: Arc<Mutex<Option<String>>>,
+    // Last answer of the polled URL
+    remote: Arc<Mutex<Option<String>>>,
+}
+
+#[derive(Deserialize)]
+struct RemoteState {
+    halted: bool,
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+impl KillSwitch {
+    /// A switch tripped only by `trip`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Trip while `path` exists; `trip` and `reset` create and remove it.
+    pub fn with_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.file = Some(path.into());
+        self
+    }
+
+    /// Trip while the environment variable `name` is set (not empty, `0` or `false`).
+    pub fn with_env(mut self, name: impl Into<String>) -> Self {
+        self.env_var = Some(name.into());
+        self
+    }
+
+    /// Halt new exposure, writing the reason to the kill file if there is one.
+    pub fn trip(&self, reason: impl Into<String>) -> Result<()> {
+        let reason = reason.into();
+        if let Some(path) = &self.file {
+            std::fs::write(path, format!("{}\n", reason))
+                .map_err(|e| ApiError::Api(format!("Cannot write kill switch file {}: {}", path.display(), e)))?;
+        }
+        *lock(&self.manual) = Some(reason);
+        Ok(())
+    }
+
+    /// Clear `trip` and remove the kill file. The environment variable and the polled URL
+    /// are left to their owners.
+    pub fn reset(&self) -> Result<()> {
+        if let Some(path) = &self.file {
+            match std::fs::remove_file(path) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    return Err(ApiError::Api(format!("Cannot remove kill switch file {}: {}", path.display(), e)));
+                }
+            }
+        }
+        *lock(&self.manual) = None;
+        Ok(())
+    }
+
+    /// Why the switch is tripped, or `None` if it is not.
+    ///
+    /// Reads the kill file with blocking I/O; async code should not call it on a hot path.
+    pub fn tripped(&self) -> Option<String> {
+        let file = self.file.as_ref().and_then(|path| file_reason(path, std::fs::read_to_string(path)));
+        self.reason(file)
+    }
+
+    /// `tripped`, reading the kill file with `tokio::fs`.
+    async fn tripped_async(&self) -> Option<String> {
+        let file = match &self.file {
+            Some(path) if lock(&self.manual).is_none() => file_reason(path, tokio::fs::read_to_string(path).await),
+            _ => None,
+        };
+        self.reason(file)
+    }
+
+    /// The reason to halt, given the reason of the kill file.
+    fn reason(&self, file: Option<String>) -> Option<String> {
+        if let Some(reason) = lock(&self.manual).clone().or(file) {
+            return Some(reason);
+        }
+        if let Some(name) = &self.env_var {
+            let value = std::env::var(name).unwrap_or_default();
+            if !matches!(value.trim().to_ascii_lowercase().as_str(), "" | "0" | "false") {
+                return Some(format!("{}={}", name, value));
+            }
+        }
+        lock(&self.remote).clone()
+    }
+
+    pub fn is_tripped(&self) -> bool {
+        self.tripped().is_some()
+    }
+
+    /// Fetch `url` every `interval` and trip while it answers `{"halted": true}`
+    ///
+    /// Runs until the returned task is aborted. A failed fetch or an unreadable answer
+    /// keeps the previous state. An interval below `MIN_POLL_INTERVAL` is raised to it.
+    pub fn poll_url(&self, url: impl Into<String>, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let url = url.into();
+        let remote = self.remote.clone();
+        let http = reqwest::Client::new();
+        let interval = interval.max(MIN_POLL_INTERVAL);
+        tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(interval);
+            loop {
+                ticks.tick().await;
+                let state = async { http.get(&url).send().await?.error_for_status()?.json::<RemoteState>().await };
+                match state.await {
+                    Ok(state) => {
+                        *lock(&remote) = state
+                            .halted
+                            .then(|| state.reason.unwrap_or_else(|| format!("halted by {}", url)));
+                    }
+                    Err(e) => tracing::warn!(target: "api_client::kill_switch", %url, error = %e, "cannot read remote kill switch, keeping last state"),
+                }
+            }
+        })
+    }
+
+    /// `ApiError::Halted` if the switch is tripped and `tx_info` of `tx_type` can add exposure.
+    pub(crate) async fn check(&self, tx_type: u32, tx_info: &Value) -> Result<()> {
+        if !adds_exposure(tx_type, tx_info) {
+            return Ok(());
+        }
+        match self.tripped_async().await {
+            Some(reason) => Err(ApiError::Halted(reason)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Reason given by the kill file from an attempt to read it; `None` if it does not exist.
+/// A file that exists but cannot be read still trips the switch.
+fn file_reason(path: &Path, contents: std::io::Result<String>) -> Option<String> {
+    match contents {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        contents => {
+            let contents = contents.unwrap_or_default();
+            let reason = contents.trim();
+            Some(if reason.is_empty() {
+                format!("kill switch file {} exists", path.display())
+            } else {
+                reason.to_string()
+            })
+        }
+    }
+}
+
+fn lock(state: &Mutex<Option<String>>) -> std::sync::MutexGuard<'_, Option<String>> {
+    state.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Whether a transaction can open or grow a position.
+fn adds_exposure(tx_type: u32, tx_info: &Value) -> bool {
+    let reduce_only = |order: &Value| order["ReduceOnly"].as_i64() == Some(1);
+    match tx_type {
+        14 => !reduce_only(tx_info), // CREATE_ORDER
+        17 => true,                  // MODIFY_ORDER
+        28 => !tx_info["Orders"].as_array().is_some_and(|orders| orders.iter().all(reduce_only)), // CREATE_GROUPED_ORDERS
+        _ => false,
+    }
+}
+
+impl LighterClient {
+    /// The kill switch consulted before each transaction, if any
+    pub fn kill_switch(&self) -> Option<&KillSwitch> {
+        self.kill_switch.as_ref()
+    }
+
+    pub(crate) async fn check_kill_switch(&self, tx_type: u32, tx_info: &Value) -> Result<()> {
+        match &self.kill_switch {
+            Some(kill_switch) => kill_switch.check(tx_type, tx_info).await,
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_blocks_only_exposure() {
+        let kill_switch = KillSwitch::new();
+        let open = json!({"MarketIndex": 0, "ReduceOnly": 0});
+        let close = json!({"MarketIndex": 0, "ReduceOnly": 1});
+        assert!(kill_switch.check(14, &open).await.is_ok());
+
+        kill_switch.trip("runaway quoting").unwrap();
+        assert!(matches!(kill_switch.check(14, &open).await, Err(ApiError::Halted(reason)) if reason == "runaway quoting"));
+        assert!(kill_switch.check(14, &close).await.is_ok());
+        assert!(kill_switch.check(15, &json!({"Index": 7})).await.is_ok());
+        assert!(kill_switch.check(16, &json!({})).await.is_ok());
+        assert!(kill_switch.check(17, &json!({})).await.is_err());
+        assert!(kill_switch.check(28, &json!({"Orders": [close.clone(), close.clone()]})).await.is_ok());
+        assert!(kill_switch.check(28, &json!({"Orders": [close, open]})).await.is_err());
+
+        kill_switch.reset().unwrap();
+        assert!(!kill_switch.is_tripped());
+    }
+
+    #[test]
+    fn test_file_and_env() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("HALT");
+        let var = "LIGHTER_KILL_SWITCH_TEST";
+        let kill_switch = KillSwitch::new().with_file(&path).with_env(var);
+        assert!(!kill_switch.is_tripped());
+
+        // Touched by an operator
+        std::fs::write(&path, "").unwrap();
+        assert!(kill_switch.tripped().unwrap().contains("HALT"));
+        std::fs::remove_file(&path).unwrap();
+
+        // Tripped in code, persisted for the next process
+        kill_switch.trip("margin call").unwrap();
+        let restarted = KillSwitch::new().with_file(&path);
+        assert_eq!(restarted.tripped().as_deref(), Some("margin call"));
+        restarted.reset().unwrap();
+        assert!(!path.exists());
+
+        std::env::set_var(var, "false");
+        assert!(!restarted.is_tripped() && !KillSwitch::new().with_env(var).is_tripped());
+        std::env::set_var(var, "1");
+        assert_eq!(KillSwitch::new().with_env(var).tripped().as_deref(), Some("LIGHTER_KILL_SWITCH_TEST=1"));
+        std::env::remove_var(var);
+    }
+
+    #[tokio::test]
+    async fn test_file_checked_without_blocking_and_fast_polling_clamped() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("HALT");
+        let kill_switch = KillSwitch::new().with_file(&path);
+        let open = json!({"MarketIndex": 0, "ReduceOnly": 0});
+        assert!(kill_switch.check(14, &open).await.is_ok());
+
+        std::fs::write(&path, "desk halt\n").unwrap();
+        assert!(matches!(kill_switch.check(14, &open).await, Err(ApiError::Halted(reason)) if reason == "desk halt"));
+
+        // A zero interval would make tokio's interval panic
+        let poller = kill_switch.poll_url("http://127.0.0.1:9/halt", Duration::ZERO);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!poller.is_finished());
+        poller.abort();
+        assert!(poller.await.unwrap_err().is_cancelled());
+    }
+}
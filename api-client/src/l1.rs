@@ -0,0 +1,102 @@
+//! L1 (Ethereum) authorization of ChangePubKey.
+//!
+//! Registering an API key requires two signatures: the usual L2 Schnorr signature by the
+//! key being registered, and an L1 signature by the account's Ethereum address over a
+//! human-readable message that names the key, nonce and indices. The L1 signature is
+//! carried in the `L1Sig` field and is not part of the L2 transaction hash.
+
+use crate::{ApiError, LighterClient, NonceLease, Result};
+use serde_json::{json, Value};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Signs messages with the account's Ethereum key.
+///
+/// Implementations must produce an EIP-191 `personal_sign` signature (`r ‖ s ‖ v`, 65 bytes)
+/// over the UTF-8 bytes of `message`. Wallet-library errors can be reported as
+/// `ApiError::L1Signer`.
+pub trait L1Signer: Send + Sync {
+    fn sign_message(&self, message: &str) -> Result<[u8; 65]>;
+}
+
+/// L1 authorization attached to a ChangePubKey transaction.
+pub enum L1Authorization<'a> {
+    /// Sign the message for the leased nonce with this signer.
+    Signer(&'a dyn L1Signer),
+    /// A `0x`-prefixed hex signature computed beforehand over
+    /// `change_pub_key_message(..)` for exactly `nonce`.
+    Signature { nonce: i64, signature: String },
+}
+
+/// Message the L1 key signs to authorize a ChangePubKey (lighter-go `GetL1SignatureBody`).
+pub fn change_pub_key_message(public_key: &[u8; 40], nonce: i64, account_index: i64, api_key_index: u8) -> String {
+    format!(
+        "Register Lighter Account\n\npubkey: 0x{}\nnonce: 0x{:016x}\naccount index: 0x{:016x}\napi key index: 0x{:016x}\nOnly sign this message for a trusted client!",
+        hex::encode(public_key),
+        nonce as u64,
+        account_index as u64,
+        api_key_index as u64,
+    )
+}
+
+impl LighterClient {
+    /// Register this client's public key with L1 authorization
+    ///
+    /// The transaction is signed by this client's key and registers it at this client's
+    /// `api_key_index`, so build the client with the key being registered. The L1 signature
+    /// is produced by `l1` (or taken as given, in which case its nonce is used).
+    pub async fn change_api_key_with_l1(&self, l1: L1Authorization<'_>) -> Result<Value> {
+        let new_public_key = self.signer().public_key().to_bytes();
+
+        let (lease, l1_sig) = match l1 {
+            L1Authorization::Signer(signer) => {
+                let lease = self.lease_nonce().await?;
+                let message = change_pub_key_message(&new_public_key, lease.nonce(), self.account_index, self.api_key_index);
+                let signature = signer.sign_message(&message)?;
+                (lease, format!("0x{}", hex::encode(signature)))
+            }
+            L1Authorization::Signature { nonce, signature } => {
+                let hex_part = signature.strip_prefix("0x").unwrap_or(&signature);
+                if hex_part.len() != 130 || hex::decode(hex_part).is_err() {
+                    return Err(ApiError::L1Signer(format!("Expected a 65-byte hex signature, got {:?}", signature)));
+                }
+                (NonceLease::detached(nonce), format!("0x{}", hex_part))
+            }
+        };
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
+        let expired_at = now + 599_000;
+
+        let tx_info = json!({
+            "AccountIndex": self.account_index,
+            "ApiKeyIndex": self.api_key_index,
+            "PubKey": hex::encode(new_public_key),
+            "ExpiredAt": expired_at,
+            "Nonce": lease.nonce(),
+            "Sig": ""
+        });
+
+        let mut final_tx_info = self.sign_tx_info(tx_info, 8).await?.tx_info; // TX_TYPE_CHANGE_PUB_KEY
+        final_tx_info["L1Sig"] = json!(l1_sig);
+
+        let response = self.send_tx(8, &final_tx_info, self.price_protection_param(None)).await; // CHANGE_PUB_KEY
+        lease.settle_outcome(&response);
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_change_pub_key_message_format() {
+        let message = change_pub_key_message(&[0x11; 40], 5, 281474976710654, 3);
+        assert_eq!(
+            message,
+            format!(
+                "Register Lighter Account\n\npubkey: 0x{}\nnonce: 0x0000000000000005\naccount index: 0x0000fffffffffffe\napi key index: 0x0000000000000003\nOnly sign this message for a trusted client!",
+                "11".repeat(40)
+            )
+        );
+    }
+}
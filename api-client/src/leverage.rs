@@ -0,0 +1,191 @@
+//! Leverage settings.
+//!
+//! The exchange stores leverage as an initial margin fraction (IMF) per 10,000, so not
+//! every leverage is exactly representable: 6x needs an IMF of 1666.67. `update_leverage`
+//! rounds the IMF up, never granting more leverage than requested (1667 is 5.999x), checks
+//! the request against the market maximum and reports the leverage actually applied.
+//!
+//! `get_margin_settings` lists the setting of every market at once, and `ensure_leverage`
+//! sends an update only when the market is not already at the requested leverage and mode,
+//! so a bot can call it every cycle without signing a transaction each time.
+
+use crate::fees::{MarketFees, MARGIN_FRACTION_SCALE};
+use crate::positions::{decimal_to_units, find_position};
+use crate::{ApiError, LighterClient, MarginMode, MarketDetails, Result};
+use serde_json::Value;
+
+/// Leverage and margin mode of the account on a market.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LeverageSetting {
+    pub market_index: u8,
+    /// Initial margin fraction per 10,000.
+    pub initial_margin_fraction: u32,
+    pub margin_mode: MarginMode,
+}
+
+impl LeverageSetting {
+    pub fn leverage(&self) -> f64 {
+        MARGIN_FRACTION_SCALE as f64 / self.initial_margin_fraction as f64
+    }
+}
+
+/// Outcome of `update_leverage`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeverageUpdate {
+    /// Exchange response; the setting only changed if its code is 200
+    pub response: Value,
+    /// Initial margin fraction sent, per 10,000
+    pub initial_margin_fraction: u16,
+    /// Leverage that fraction amounts to, at most the requested one
+    pub effective_leverage: f64,
+}
+
+/// Initial margin fraction for `leverage` on a market with `fees`, rounded up.
+pub fn initial_margin_fraction(leverage: u16, fees: &MarketFees) -> Result<u16> {
+    if leverage == 0 || u32::from(leverage) > fees.max_leverage() {
+        return Err(ApiError::InvalidOrder(format!(
+            "Leverage must be between 1 and {}, got {}",
+            fees.max_leverage(),
+            leverage
+        )));
+    }
+    let fraction = MARGIN_FRACTION_SCALE.div_ceil(u32::from(leverage)).max(fees.min_initial_margin_fraction);
+    // At most MARGIN_FRACTION_SCALE, which fits
+    Ok(fraction as u16)
+}
+
+/// Reads the setting of a market from a `get_account` response, if the account has an entry there.
+pub(crate) fn leverage_from_account(account_info: &Value, market_index: u8) -> Option<LeverageSetting> {
+    let position = find_position(account_info, market_index)?;
+    let initial_margin_fraction = position["initial_margin_fraction"]
+        .as_str()
+        .and_then(|percent| decimal_to_units(percent, 2))
+        .and_then(|fraction| u32::try_from(fraction).ok())
+        .filter(|fraction| *fraction > 0)?;
+    let margin_mode = position["margin_mode"]
+        .as_u64()
+        .and_then(|mode| u8::try_from(mode).ok())
+        .and_then(|mode| MarginMode::try_from(mode).ok())
+        .unwrap_or(MarginMode::Cross);
+    Some(LeverageSetting {
+        market_index,
+        initial_margin_fraction,
+        margin_mode,
+    })
+}
+
+/// Setting of every market in `markets`: the account's own where it has one, otherwise the
+/// market default in cross margin. Ordered by market index.
+pub(crate) fn margin_settings(account_info: &Value, markets: &[MarketDetails]) -> Vec<LeverageSetting> {
+    let mut settings: Vec<LeverageSetting> = markets
+        .iter()
+        .map(|market| {
+            leverage_from_account(account_info, market.market_index).unwrap_or(LeverageSetting {
+                market_index: market.market_index,
+                initial_margin_fraction: market.fees.default_initial_margin_fraction,
+                margin_mode: MarginMode::Cross,
+            })
+        })
+        .collect();
+    settings.sort_by_key(|setting| setting.market_index);
+    settings
+}
+
+impl LighterClient {
+    /// Leverage and margin mode on every market, as `get_leverage` reports them
+    pub async fn get_margin_settings(&self) -> Result<Vec<LeverageSetting>> {
+        let markets = self.get_all_market_details().await?;
+        Ok(margin_settings(&self.get_account().await?, &markets))
+    }
+
+    /// Set the leverage and margin mode of a market unless they are already set
+    ///
+    /// `None` if the market already has the initial margin fraction `leverage` rounds to
+    /// (see `update_leverage`) and `margin_mode`; nothing is signed then. Otherwise the
+    /// outcome of `update_leverage`.
+    pub async fn ensure_leverage(
+        &self,
+        market_index: u8,
+        leverage: u16,
+        margin_mode: MarginMode,
+    ) -> Result<Option<LeverageUpdate>> {
+        let fees = self.get_market_fees(market_index).await?;
+        let target = initial_margin_fraction(leverage, &fees)?;
+        let current = self.get_leverage(market_index).await?;
+        if current.initial_margin_fraction == u32::from(target) && current.margin_mode == margin_mode {
+            return Ok(None);
+        }
+        self.update_leverage(market_index, leverage, margin_mode).await.map(Some)
+    }
+
+    /// Current leverage and margin mode on a market
+    ///
+    /// Read from the account's position entry; markets the account never traded or
+    /// configured report the market's default initial margin fraction in cross margin.
+    pub async fn get_leverage(&self, market_index: u8) -> Result<LeverageSetting> {
+        if let Some(setting) = leverage_from_account(&self.get_account().await?, market_index) {
+            return Ok(setting);
+        }
+
+        let fees = self.get_market_fees(market_index).await?;
+        Ok(LeverageSetting {
+            market_index,
+            initial_margin_fraction: fees.default_initial_margin_fraction,
+            margin_mode: MarginMode::Cross,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_fraction_rounding_and_limits() {
+        let fees = MarketFees {
+            maker_fee: 0.0,
+            taker_fee: 0.0,
+            min_initial_margin_fraction: 500,
+            default_initial_margin_fraction: 500,
+            maintenance_margin_fraction: 300,
+        };
+        assert_eq!(initial_margin_fraction(3, &fees).unwrap(), 3334);
+        assert_eq!(initial_margin_fraction(6, &fees).unwrap(), 1667);
+        assert_eq!(initial_margin_fraction(20, &fees).unwrap(), 500);
+        assert!(matches!(initial_margin_fraction(0, &fees), Err(ApiError::InvalidOrder(_))));
+        assert!(initial_margin_fraction(21, &fees).is_err());
+
+        let account = json!({"accounts": [{"positions": [
+            {"market_id": 0, "initial_margin_fraction": "16.67", "margin_mode": 1},
+        ]}]});
+        let setting = leverage_from_account(&account, 0).unwrap();
+        assert_eq!(setting.initial_margin_fraction, 1667);
+        assert_eq!(setting.margin_mode, MarginMode::Isolated);
+        assert!(setting.leverage() <= 6.0);
+        assert!(leverage_from_account(&account, 1).is_none());
+    }
+
+    #[test]
+    fn test_margin_settings_of_all_markets() {
+        let market = |market_id: u8, default_fraction: u32| {
+            MarketDetails::from_order_book_details(&json!({
+                "market_id": market_id, "symbol": "X", "status": "active",
+                "size_decimals": 4, "price_decimals": 2, "min_base_amount": "0.001", "min_quote_amount": "10",
+                "maker_fee": "0.0000", "taker_fee": "0.0000",
+                "min_initial_margin_fraction": 500, "default_initial_margin_fraction": default_fraction,
+                "maintenance_margin_fraction": 300,
+            }))
+            .unwrap()
+        };
+        let account = json!({"accounts": [{"positions": [
+            {"market_id": 1, "initial_margin_fraction": "10.00", "margin_mode": 1},
+        ]}]});
+
+        let settings = margin_settings(&account, &[market(1, 500), market(0, 2000)]);
+        assert_eq!(settings.iter().map(|s| s.market_index).collect::<Vec<_>>(), vec![0, 1]);
+        assert_eq!((settings[0].initial_margin_fraction, settings[0].margin_mode), (2000, MarginMode::Cross));
+        assert_eq!((settings[1].initial_margin_fraction, settings[1].margin_mode), (1000, MarginMode::Isolated));
+        assert_eq!(settings[1].leverage(), 10.0);
+    }
+}
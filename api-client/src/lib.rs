@@ -1,11 +1,115 @@
-use base64::Engine;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use signer::KeyManager;
+pub use signer::{AuthToken, PublicKey, Signature};
+pub use tokio_util::sync::CancellationToken;
 use std::time::{SystemTime, UNIX_EPOCH};
 use thiserror::Error;
 
+pub mod account_stream;
+pub mod address_book;
+pub mod amend;
+pub mod analytics;
+pub mod api_keys;
+pub mod audit;
+pub mod auth;
+pub mod balance;
+pub mod bars;
+pub mod budget;
+pub mod builder;
+pub mod circuit;
+pub mod close_all;
+pub mod deadman;
+pub mod decimal;
+pub mod execution;
+pub mod expiry;
+pub mod fees;
+pub mod funding;
+pub mod grouped;
+pub mod journal;
+pub mod kill_switch;
+pub mod l1;
+pub mod leverage;
+pub mod market_cache;
+pub mod market_details;
+pub mod nonce;
+pub mod notifier;
+pub mod onboarding;
+pub mod order_book;
+pub mod orders;
+pub mod pnl;
+pub mod positions;
+pub mod preview;
+pub mod quotes;
+pub mod rejection;
+pub mod replay;
+pub mod retry;
+pub mod risk;
+pub mod router;
+pub mod scheduler;
+pub mod schema;
+pub mod signing;
+pub mod status;
+pub mod tx_fields;
+pub mod tx_json;
+pub mod types;
+pub mod units;
+pub mod validation;
+pub mod whoami;
+pub mod withdraw;
+pub mod ws;
+
+pub use account_stream::{AccountEvent, AccountStream};
+pub use address_book::{AccountRef, AddressBook};
+pub use amend::{AmendOutcome, AmendPath};
+pub use analytics::FundingZScore;
+pub use api_keys::{ApiKeyInfo, ApiKeyStatus, ROTATION_TIMEOUT};
+pub use audit::{AuditRecord, AuditSink, FileAuditSink};
+pub use auth::DEFAULT_AUTH_TOKEN_LIFETIME;
+pub use balance::BalanceSummary;
+pub use bars::{Bar, BarAggregator, BarInterval, BarStream, TradeTick};
+pub use budget::{Budget, WithBudget, Within};
+pub use builder::LighterClientBuilder;
+pub use circuit::{CircuitBreakerConfig, CircuitState, CircuitStatus};
+pub use close_all::{CloseAllConfig, CloseAllReport, PositionCloseReport};
+pub use deadman::{CANCEL_ALL_TIF_ABORT, CANCEL_ALL_TIF_IMMEDIATE, CANCEL_ALL_TIF_SCHEDULED};
+pub use decimal::{parse_decimal, string_or_number_decimal, Decimal};
+pub use expiry::{ExpiryPolicy, DEFAULT_RESTING_EXPIRY};
+pub use execution::{TwapConfig, TwapExecutor, TwapProgress, TwapReport};
+pub use fees::{MarketFees, MARGIN_FRACTION_SCALE};
+pub use funding::{funding_carry, FundingCarry, FundingFilter, FundingPage, FundingPayment};
+pub use grouped::GroupedOrdersBuilder;
+pub use journal::{FileJournal, JournalEntry, MemoryJournal, TxJournal, TxStatus};
+pub use kill_switch::KillSwitch;
+pub use l1::{change_pub_key_message, L1Authorization, L1Signer};
+pub use leverage::{LeverageSetting, LeverageUpdate};
+pub use market_cache::{Cached, MarketCache};
+pub use market_details::{MarketDetails, DEFAULT_FUNDING_INTERVAL};
+pub use nonce::{is_nonce_error, NonceLease, NonceResync, NonceResyncHook, NonceStatus};
+pub use notifier::{Alert, DiscordNotifier, Notifier, Severity, TelegramNotifier};
+pub use onboarding::{onboard, EthWallet, OnboardedAccount};
+pub use order_book::{OrderBook, PriceLevel};
+pub use orders::{InactiveOrderFilter, Order, OrderPage, OrderStatus};
+pub use pnl::{PnlSnapshot, PnlStream, PositionPnl};
+pub use positions::PositionSize;
+pub use preview::OrderPreview;
+pub use quotes::{Quote, QuoteAction, QuoteSet, QuoteUpdate};
+pub use rejection::{check_response, CheckedResponse, RejectionKind};
+pub use replay::{AccountReplay, AccountTx, ReplayEntry, ReplayEvent};
+pub use retry::{Backoff, RetryConfig, CODE_INVALID_SIGNATURE, CODE_RATE_LIMITED};
+pub use risk::{RiskConfig, RiskLimit, RiskLimits};
+pub use router::OrderRouter;
+pub use scheduler::{ScheduledTx, TxScheduler};
+pub use schema::SchemaDrift;
+pub use signing::{RemoteSigner, Signer};
+pub use status::ExchangeStatus;
+pub use tx_json::tx_info_json;
+pub use types::{GroupingType, MarginDirection, MarginMode, OrderType, TimeInForce};
+pub use units::{BaseAmount, Price, UsdcAmount};
+pub use validation::{MarketRules, OrderValidator, RestingOrder};
+pub use whoami::{AccountTier, FeeSchedule, WhoAmI, PREMIUM_MAKER_FEE, PREMIUM_TAKER_FEE};
+pub use withdraw::{WithdrawFilter, WithdrawHistory, WithdrawLimits, WithdrawRecord, WithdrawStatus, WithdrawType};
+
 #[derive(Error, Debug)]
 pub enum ApiError {
     #[error("Signer error: {0}")]
@@ -18,49 +122,150 @@ pub enum ApiError {
     SystemTime(#[from] std::time::SystemTimeError),
     #[error("API error: {0}")]
     Api(String),
+    #[error("L1 signer error: {0}")]
+    L1Signer(String),
+    #[error("Journal error: {0}")]
+    Journal(String),
+    #[error("Audit log error: {0}")]
+    Audit(String),
+    #[error("Address book error: {0}")]
+    AddressBook(String),
+    /// A transaction was written to the websocket but its response never arrived.
+    #[error("WebSocket error: {0}")]
+    WebSocket(String),
+    #[error("Invalid order: {0}")]
+    InvalidOrder(String),
+    /// The withdraw is below the minimum or does not cover the fee; see `get_withdraw_limits`.
+    #[error("Invalid withdraw: {0}")]
+    InvalidWithdraw(String),
+    /// The call's `CancellationToken` was cancelled before it finished; see `budget`.
+    #[error("Cancelled")]
+    Cancelled,
+    /// The call ran past the deadline of its `Budget`; see `budget`.
+    #[error("Deadline exceeded")]
+    DeadlineExceeded,
+    /// A USDC amount is negative, finer than 1e-6 USDC or out of range; see `UsdcAmount`.
+    #[error("Invalid amount: {0}")]
+    InvalidAmount(String),
+    /// A `tx_info` field needed for the transaction hash is missing or has the wrong type.
+    #[error("Invalid tx field: {0}")]
+    InvalidTxField(String),
+    /// The response body was not the expected JSON (e.g. an HTML error page or a truncated body).
+    /// `retry_after` is the delay the server asked for with a `Retry-After` header.
+    #[error("Unexpected response (HTTP {status}): {body}")]
+    UnexpectedResponse { status: u16, body: String, retry_after: Option<std::time::Duration> },
+    /// The exchange answered with a code other than 200; see `rejection`. `retry_after` is the
+    /// delay the exchange suggested, if any (see `retry::retry_after`).
+    #[error("Rejected by the exchange (code {code}): {message}")]
+    Rejected { code: i64, message: String, response: Value, retry_after: Option<std::time::Duration> },
+    /// Requests to `endpoint` are failing fast after repeated transport or 5xx errors; see `circuit`.
+    #[error("Circuit open for {endpoint}, retry in {retry_in:?}")]
+    CircuitOpen { endpoint: String, retry_in: std::time::Duration },
+    /// The order would breach a limit of the client's `RiskConfig`; `market_index` is `None`
+    /// for account-wide limits. Nothing was signed or sent.
+    #[error("Risk limit ({limit}) of {} breached: {message}", .market_index.map_or("account".to_string(), |m| format!("market {}", m)))]
+    RiskRejected { market_index: Option<u8>, limit: RiskLimit, message: String },
+    /// The client's `KillSwitch` is tripped and the transaction could add exposure; the
+    /// reason is the one given when it was tripped. Nothing was sent.
+    #[error("Halted by kill switch: {0}")]
+    Halted(String),
+    /// The response to a transaction was lost and the exchange could not confirm whether it
+    /// was executed; see `retry`. Its nonce is treated as used, so it is never re-signed.
+    /// Look the transaction up by `tx_hash` before sending it again.
+    #[error("Transaction {tx_hash} (nonce {nonce}) may have been executed: {message}")]
+    PossiblyExecuted { tx_hash: String, nonce: i64, message: String },
 }
 
 pub type Result<T> = std::result::Result<T, ApiError>;
 
+/// Longest body kept in `ApiError::UnexpectedResponse`.
+const MAX_ERROR_BODY_LEN: usize = 4096;
+
+/// Reads the body of an API response as JSON
+///
+/// A body that does not parse becomes `ApiError::UnexpectedResponse` with the HTTP status and
+/// the raw body (cut to 4 KiB), so the payload is not lost behind the parser error. A
+/// `Retry-After` header (in seconds) is kept with the error, or added to a JSON body as
+/// `retry_after_ms` unless the body has its own hint.
+pub(crate) async fn parse_response(response: reqwest::Response) -> Result<Value> {
+    let status = response.status().as_u16();
+    let retry_after = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(std::time::Duration::from_secs);
+    let body = response.text().await?;
+    parse_body(status, body, retry_after)
+}
+
+fn parse_body(status: u16, mut body: String, retry_after: Option<std::time::Duration>) -> Result<Value> {
+    match serde_json::from_str::<Value>(&body) {
+        Ok(mut value) => {
+            if let Some(delay) = retry_after.filter(|_| value.is_object() && retry::retry_after(&value).is_none()) {
+                value["retry_after_ms"] = json!(delay.as_millis() as u64);
+            }
+            Ok(value)
+        }
+        Err(_) => {
+            if body.len() > MAX_ERROR_BODY_LEN {
+                let mut end = MAX_ERROR_BODY_LEN;
+                while !body.is_char_boundary(end) {
+                    end -= 1;
+                }
+                body.truncate(end);
+            }
+            Err(ApiError::UnexpectedResponse { status, body, retry_after })
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct CreateOrderRequest {
     pub account_index: i64,
     pub order_book_index: u8,
     pub client_order_index: u64,
-    pub base_amount: i64,
-    pub price: i64,
+    pub base_amount: BaseAmount,
+    pub price: Price,
     pub is_ask: bool,
-    pub order_type: u8,
-    pub time_in_force: u8,
+    pub order_type: OrderType,
+    pub time_in_force: TimeInForce,
     pub reduce_only: bool,
-    pub trigger_price: i64,
+    /// Trigger price of stop-loss and take-profit orders, `Price::ZERO` for none.
+    pub trigger_price: Price,
+    /// Overrides the client's price protection default for this order; `None` uses the default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub price_protection: Option<bool>,
+    /// Explicit `OrderExpiry` (ms, 0 = none); `None` applies the client's `ExpiryPolicy`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order_expiry: Option<i64>,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct TransferRequest {
     pub to_account_index: i64,
-    pub usdc_amount: i64,
-    pub fee: i64,
+    pub usdc_amount: UsdcAmount,
+    pub fee: UsdcAmount,
     pub memo: [u8; 32],
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct WithdrawRequest {
-    pub usdc_amount: u64,
+    pub usdc_amount: UsdcAmount,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct ModifyOrderRequest {
     pub market_index: u8,
     pub order_index: i64,
-    pub base_amount: i64,
-    pub price: u32,
-    pub trigger_price: u32,
+    pub base_amount: BaseAmount,
+    pub price: Price,
+    pub trigger_price: Price,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct CreateGroupedOrdersRequest {
-    pub grouping_type: u8,
+    pub grouping_type: GroupingType,
     pub orders: Vec<CreateOrderRequest>,
 }
 
@@ -94,80 +299,119 @@ pub struct BurnSharesRequest {
 #[derive(Serialize, Deserialize)]
 pub struct UpdateMarginRequest {
     pub market_index: u8,
-    pub usdc_amount: i64,
-    pub direction: u8, // 0 = RemoveFromIsolatedMargin, 1 = AddToIsolatedMargin
+    pub usdc_amount: UsdcAmount,
+    pub direction: MarginDirection,
+}
+
+/// A transaction signed by one of the `sign_*_with_nonce` methods.
+#[derive(Debug, Clone)]
+pub struct SignedTx {
+    pub tx_type: u32,
+    /// Signed `tx_info`, ready for sendTx.
+    pub tx_info: Value,
+    /// Poseidon2 digest the signature was produced over, see `compute_tx_hash`.
+    pub tx_hash: [u8; 40],
+    /// Schnorr signature (s || e), base64-encoded in `tx_info["Sig"]`.
+    pub signature: Signature,
+}
+
+impl SignedTx {
+    /// Hex encoding of `tx_hash`
+    pub fn tx_hash_hex(&self) -> String {
+        hex::encode(self.tx_hash)
+    }
+
+    /// `tx_info` as the client would post it, in lighter-go's field order (see `tx_json`)
+    pub fn tx_info_json(&self) -> Result<String> {
+        tx_json::tx_info_json(self.tx_type, &self.tx_info)
+    }
 }
 
+use nonce::NonceManager;
+use ws::Transport;
 use std::sync::Arc;
 use rand::RngCore;
-use tokio::sync::Mutex as AsyncMutex;
 
 pub struct LighterClient {
     client: Client,
     base_url: String,
-    key_manager: KeyManager,
+    // Signing key, replaced by rotate_api_key
+    signer: std::sync::RwLock<Arc<dyn Signer>>,
     account_index: i64,
     api_key_index: u8,
-    // Nonce cache for optimistic nonce management (like Python SDK)
-    // Fetches once from API, then increments locally
-    nonce_cache: Arc<AsyncMutex<NonceCache>>,
+    // Nonces are fetched once from the API, then leased out locally
+    nonces: NonceManager,
+    // Sent as the `price_protection` form field of sendTx unless an order overrides it
+    price_protection: bool,
+    // Lifetime of tokens attached by authenticated_get
+    auth_token_lifetime: std::time::Duration,
+    // Optional record of every transaction sent through send_tx
+    journal: Option<Arc<dyn TxJournal>>,
+    // Optional destination of operational alerts
+    notifier: Option<Arc<dyn Notifier>>,
+    // Default OrderExpiry of orders that do not set one
+    expiry_policy: ExpiryPolicy,
+    // Optional observer of nonce resyncs done by send_tx
+    nonce_resync_hook: Option<NonceResyncHook>,
+    // Optional record of every transaction signed by sign_tx_info
+    audit_sink: Option<Arc<dyn AuditSink>>,
+    // Send transactions over the websocket by default instead of REST
+    websocket_tx: bool,
+    // Socket used by send_tx_ws, connected on first use
+    tx_socket: ws::TxSocket,
+    // TTL of the armed deadman switch
+    deadman_ttl: std::sync::Mutex<Option<std::time::Duration>>,
+    // Retries of create_order and update_leverage per error class
+    retry_config: RetryConfig,
+    // Consecutive transport and 5xx failures per endpoint
+    circuit: circuit::CircuitBreaker,
+    // Unknown and missing fields of typed responses already logged
+    schema: schema::SchemaWatch,
+    // Pre-trade limits of create_order and the notional sent within their window
+    risk: risk::RiskGuard,
+    // Checked before each transaction is posted
+    kill_switch: Option<KillSwitch>,
 }
 
-struct NonceCache {
-    // Simple optimistic nonce management: fetch once, then increment locally
-    last_fetched_nonce: i64,  // Last nonce fetched from API (stored as nonce - 1, like Python)
-    nonce_offset: i64,        // How many nonces we've used since last fetch
+/// Shows the endpoint, indices and the fingerprint of the signing key, never the key.
+impl std::fmt::Debug for LighterClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LighterClient")
+            .field("base_url", &self.base_url)
+            .field("account_index", &self.account_index)
+            .field("api_key_index", &self.api_key_index)
+            .field("public_key", &self.signer().public_key().fingerprint())
+            .finish_non_exhaustive()
+    }
 }
 
-impl NonceCache {
-    fn new() -> Self {
-        Self {
-            last_fetched_nonce: -1,  // -1 means not initialized
-            nonce_offset: 0,
-        }
-    }
-    
-    fn get_next_nonce(&mut self) -> Option<i64> {
-        if self.last_fetched_nonce == -1 {
-            None  // Not initialized, need to fetch from API
-        } else {
-            // Increment offset and return next nonce
-            // Formula: (last_fetched_nonce - 1) + offset + 1 = last_fetched_nonce + offset
-            self.nonce_offset += 1;
-            Some(self.last_fetched_nonce + self.nonce_offset)
-        }
+impl LighterClient {
+    pub fn new(base_url: String, private_key_hex: &str, account_index: i64, api_key_index: u8) -> Result<Self> {
+        LighterClientBuilder::new(base_url, private_key_hex, account_index, api_key_index).build()
     }
-    
-    fn set_fetched_nonce(&mut self, nonce: i64) {
-        // Store as nonce - 1, so first increment gives us the correct nonce
-        // This matches Python's OptimisticNonceManager behavior
-        self.last_fetched_nonce = nonce - 1;
-        self.nonce_offset = 0;
+
+    /// Set the default price protection flag (enabled by default)
+    ///
+    /// The exchange applies price protection to create order, grouped orders and modify
+    /// order transactions, rejecting fills too far from the mark price. Other transaction
+    /// types accept the field and ignore it. Orders can override the default through
+    /// `CreateOrderRequest::price_protection`.
+    pub fn with_price_protection(mut self, enabled: bool) -> Self {
+        self.price_protection = enabled;
+        self
     }
-    
-    fn acknowledge_failure(&mut self) {
-        // Decrement offset on failure to allow retry with same nonce
-        // This matches Python's OptimisticNonceManager behavior
-        if self.nonce_offset > 0 {
-            self.nonce_offset -= 1;
-        }
+
+    /// Default price protection flag sent with transactions
+    pub fn price_protection(&self) -> bool {
+        self.price_protection
     }
-    
-}
 
-impl LighterClient {
-    pub fn new(base_url: String, private_key_hex: &str, account_index: i64, api_key_index: u8) -> Result<Self> {
-        let key_manager = KeyManager::from_hex(private_key_hex)?;
-        let client = Client::new();
-
-        Ok(Self {
-            client,
-            base_url,
-            key_manager,
-            account_index,
-            api_key_index,
-            nonce_cache: Arc::new(AsyncMutex::new(NonceCache::new())),
-        })
+    fn price_protection_param(&self, requested: Option<bool>) -> &'static str {
+        if requested.unwrap_or(self.price_protection) {
+            "true"
+        } else {
+            "false"
+        }
     }
 
     pub async fn create_order(&self, order: CreateOrderRequest) -> Result<Value> {
@@ -175,83 +419,25 @@ impl LighterClient {
     }
     
     /// Create order with optional nonce parameter and retry logic
-    /// If nonce is Some(n), uses that nonce (or -1 to fetch from API)
-    /// If nonce is None, uses optimistic nonce management
-    /// Automatically retries on invalid signature errors (21120) since same signature succeeds on retry
+    /// If nonce is Some(n), uses that nonce (or -1 to lease one from the client)
+    /// If nonce is None, leases a nonce that is committed on success and released on failure
+    /// Orders breaching the client's `RiskConfig` fail with `ApiError::RiskRejected` first
+    /// Retries invalid signatures (21120), rate limits and transport errors as set by the
+    /// client's `RetryConfig`, resyncing the nonce before each retry
+    /// A lost response is checked by tx hash before a retry; if that is not possible the
+    /// order fails with `ApiError::PossiblyExecuted` rather than risk a duplicate
     pub async fn create_order_with_nonce(&self, order: CreateOrderRequest, nonce: Option<i64>) -> Result<Value> {
-        const MAX_RETRIES: u32 = 5;
-        const RETRY_DELAY_MS: u64 = 3000; // 3 seconds between retries (as per testing: 3s apart = 100% success)
-        
-        // Fetch nonce once before retry loop - we'll reuse the same nonce for retries
-        let mut current_nonce = self.get_nonce_or_use(nonce).await?;
-        
-        let mut last_error: Option<ApiError> = None;
-        
-        for attempt in 0..=MAX_RETRIES {
-            if attempt > 0 {
-                // Wait 3 seconds between retries for 21120 errors (nonce timing issue)
-                tokio::time::sleep(tokio::time::Duration::from_millis(RETRY_DELAY_MS)).await;
-                
-                // Refresh nonce from API on retry to ensure we have the latest nonce
-                // This handles the case where API processed our previous attempt
-                match self.fetch_nonce_from_api().await {
-                    Ok(fresh_nonce) => {
-                        current_nonce = fresh_nonce;
-                        let mut cache = self.nonce_cache.lock().await;
-                        cache.set_fetched_nonce(fresh_nonce);
-                    }
-                    Err(_) => {
-                        // If fetch fails, continue with current nonce
-                    }
-                }
-            }
-            
-            match self.create_order_internal(&order, Some(current_nonce)).await {
-                Ok(response) => {
-                    let code = response["code"].as_i64().unwrap_or_default();
-                    if code == 200 {
-                        // Success - nonce was used, cache is already correct
-                        return Ok(response);
-                    } else if code == 21120 && attempt < MAX_RETRIES {
-                        // Invalid signature - retry with refreshed nonce after delay
-                        last_error = Some(ApiError::Api(format!("Invalid signature (code 21120) after {} attempts", attempt + 1)));
-                        continue;
-                    } else {
-                        // Other error or max retries reached
-                        {
-                            let mut cache = self.nonce_cache.lock().await;
-                            cache.acknowledge_failure();
-                        }
-                        return Ok(response);
-                    }
-                }
-                Err(e) => {
-                    if attempt < MAX_RETRIES {
-                        last_error = Some(e);
-                        continue;
-                    } else {
-                        {
-                            let mut cache = self.nonce_cache.lock().await;
-                            cache.acknowledge_failure();
-                        }
-                        return Err(e);
-                    }
-                }
-            }
-        }
-        
-        // If we get here, all retries failed
-        {
-            let mut cache = self.nonce_cache.lock().await;
-            cache.acknowledge_failure();
-        }
-        Err(last_error.unwrap_or_else(|| ApiError::Api("Failed after all retries".to_string())))
+        self.check_risk(&order).await?;
+        let lease = self.lease_or_use(nonce).await?;
+        let price_protection = self.price_protection_param(order.price_protection);
+        self.send_with_retries(14, price_protection, lease, |nonce| self.create_order_internal(&order, Some(nonce)))
+            .await
     }
     
-    /// Internal method to create order (without retry logic)
-    /// This is called by create_order_with_nonce for each retry attempt
+    /// Internal method to sign an order (without retry logic)
+    /// This is called by create_order_with_nonce for each retry attempt, which sends it
     /// Uses the provided nonce directly (no fetching)
-    async fn create_order_internal(&self, order: &CreateOrderRequest, nonce: Option<i64>) -> Result<Value> {
+    async fn create_order_internal(&self, order: &CreateOrderRequest, nonce: Option<i64>) -> Result<SignedTx> {
         let nonce = nonce.expect("Nonce should be provided to create_order_internal");
         
         // Create transaction info with expiry time
@@ -262,14 +448,7 @@ impl LighterClient {
         // Use 10 minutes - 1 second (599,000 ms) to match Go SDK exactly
         let expired_at = now + 599_000; // 10 minutes - 1 second (matches Go SDK)
         
-        // OrderExpiry: For limit orders with GoodTillTime, set to 28 days
-        // For other orders, use 0 (nil)
-        let order_expiry = if order.time_in_force == 1 && order.order_type == 0 {
-            // GoodTillTime limit order: 28 days expiry
-            now + (28 * 24 * 60 * 60 * 1000)
-        } else {
-            0 // NilOrderExpiry
-        };
+        let order_expiry = self.expiry_policy.order_expiry(order, now);
         
         let tx_info = json!({
             "AccountIndex": self.account_index,
@@ -288,35 +467,16 @@ impl LighterClient {
             "Nonce": nonce,
             "Sig": ""
         });
-        println!("[create_order] tx_info JSON: {}", tx_info);
-        let tx_json = serde_json::to_string(&tx_info)?;
-        println!("[create_order] tx_json string: {}", tx_json);
-        let signature = self.sign_transaction(&tx_json)?;
-        println!(
-            "[create_order] Signature (base64): {}",
-            base64::engine::general_purpose::STANDARD.encode(&signature)
+        let signed = self.sign_tx_info(tx_info, 14).await?; // CREATE_ORDER
+        tracing::debug!(
+            target: "api_client::orders",
+            nonce,
+            market_index = order.order_book_index,
+            client_order_index = order.client_order_index,
+            tx_hash = %signed.tx_hash_hex(),
+            "order signed"
         );
-        let mut final_tx_info = tx_info;
-        let sig_base64 = base64::engine::general_purpose::STANDARD.encode(&signature);
-        final_tx_info["Sig"] = json!(sig_base64);
-        
-        let final_tx_json = serde_json::to_string(&final_tx_info)?;
-        println!("[create_order] Final tx_info with signature: {}", final_tx_info);
-        let form_data = [
-            ("tx_type", "14"), // CREATE_ORDER
-            ("tx_info", &final_tx_json),
-        ];
-        let response = self
-            .client
-            .post(&format!("{}/api/v1/sendTx", self.base_url))
-            .form(&form_data)
-            .send()
-            .await?;
-        let response_text = response.text().await?;
-        println!("[create_order] Response text: {}", response_text);
-        let response_json: Value = serde_json::from_str(&response_text)?;
-        println!("[create_order] Response JSON: {}", response_json);
-        Ok(response_json)
+        Ok(signed)
     }
 
     pub async fn create_market_order(
@@ -345,25 +505,27 @@ impl LighterClient {
         base_amount: i64,
         avg_execution_price: i64,
         is_ask: bool,
-        nonce: Option<i64>,
+        _nonce: Option<i64>,
     ) -> Result<Value> {
         let order = CreateOrderRequest {
             account_index: self.account_index,
             order_book_index,
             client_order_index,
-            base_amount,
-            price: avg_execution_price,
+            base_amount: BaseAmount(base_amount),
+            price: Price::try_from(avg_execution_price)?,
             is_ask,
-            order_type: 1,    // MarketOrder
-            time_in_force: 0, // ImmediateOrCancel
+            order_type: OrderType::Market,
+            time_in_force: TimeInForce::ImmediateOrCancel,
             reduce_only: false,
-            trigger_price: 0,
+            trigger_price: Price::ZERO,
+            price_protection: None,
+            order_expiry: None,
         };
         self.create_order(order).await
     }
 
     pub async fn cancel_order(&self, order_book_index: u8, order_index: i64) -> Result<Value> {
-        let nonce = self.get_nonce().await?;
+        let lease = self.lease_nonce().await?;
         let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
         let expired_at = now + 599_000;
 
@@ -373,37 +535,24 @@ impl LighterClient {
             "MarketIndex": order_book_index,
             "Index": order_index,
             "ExpiredAt": expired_at,
-            "Nonce": nonce,
+            "Nonce": lease.nonce(),
             "Sig": ""
         });
 
-        let tx_json = serde_json::to_string(&tx_info)?;
-        let signature = self.sign_transaction_with_type(&tx_json, 15)?; // TX_TYPE_CANCEL_ORDER
-
-        let mut final_tx_info = tx_info;
-        final_tx_info["Sig"] = json!(base64::engine::general_purpose::STANDARD.encode(&signature));
-
-        let form_data = [
-            ("tx_type", "15"), // CANCEL_ORDER
-            ("tx_info", &serde_json::to_string(&final_tx_info)?),
-            ("price_protection", "true"),
-        ];
-
-        let response = self
-            .client
-            .post(&format!("{}/api/v1/sendTx", self.base_url))
-            .form(&form_data)
-            .send()
-            .await?;
-
-        let response_text = response.text().await?;
-        let response_json: Value = serde_json::from_str(&response_text)?;
+        let final_tx_info = self.sign_tx_info(tx_info, 15).await?.tx_info; // TX_TYPE_CANCEL_ORDER
 
-        Ok(response_json)
+        let response = self.send_tx(15, &final_tx_info, self.price_protection_param(None)).await; // CANCEL_ORDER
+        lease.settle_outcome(&response);
+        response
     }
 
+    /// Cancel all orders now, at `time` (ms), or abort a scheduled cancel-all
+    ///
+    /// `time_in_force` is one of `CANCEL_ALL_TIF_IMMEDIATE` (with `time` 0),
+    /// `CANCEL_ALL_TIF_SCHEDULED` or `CANCEL_ALL_TIF_ABORT`. The deadman methods
+    /// (`set_deadman_switch`) wrap the scheduled form.
     pub async fn cancel_all_orders(&self, time_in_force: u8, time: i64) -> Result<Value> {
-        let nonce = self.get_nonce().await?;
+        let lease = self.lease_nonce().await?;
         let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
         let expired_at = now + 599_000;
 
@@ -413,39 +562,22 @@ impl LighterClient {
             "TimeInForce": time_in_force,
             "Time": time,
             "ExpiredAt": expired_at,
-            "Nonce": nonce,
+            "Nonce": lease.nonce(),
             "Sig": ""
         });
 
-        let tx_json = serde_json::to_string(&tx_info)?;
-        let signature = self.sign_transaction_with_type(&tx_json, 16)?; // TX_TYPE_CANCEL_ALL_ORDERS
-
-        let mut final_tx_info = tx_info;
-        final_tx_info["Sig"] = json!(base64::engine::general_purpose::STANDARD.encode(&signature));
+        let final_tx_info = self.sign_tx_info(tx_info, 16).await?.tx_info; // TX_TYPE_CANCEL_ALL_ORDERS
 
-        let form_data = [
-            ("tx_type", "16"), // CANCEL_ALL_ORDERS
-            ("tx_info", &serde_json::to_string(&final_tx_info)?),
-            ("price_protection", "true"),
-        ];
-
-        let response = self
-            .client
-            .post(&format!("{}/api/v1/sendTx", self.base_url))
-            .form(&form_data)
-            .send()
-            .await?;
-
-        let response_text = response.text().await?;
-        let response_json: Value = serde_json::from_str(&response_text)?;
-
-        Ok(response_json)
+        let response = self.send_tx(16, &final_tx_info, self.price_protection_param(None)).await; // CANCEL_ALL_ORDERS
+        lease.settle_outcome(&response);
+        response
     }
 
     /// Close a position in a specific market
     /// 
-    /// Creates a market order with reduce_only=true to close the position.
-    /// Use this to close a position when you know the market and direction.
+    /// Creates a market order with reduce_only=true sized to the open position.
+    /// Fails if there is no position in the given direction.
+    /// See `close_position_with_size` and `close_position_partial` to close less.
     /// 
     /// # Arguments
     /// * `market_index` - Market index (0-based)
@@ -454,22 +586,18 @@ impl LighterClient {
     /// # Returns
     /// JSON response from the API
     pub async fn close_position(&self, market_index: u8, is_ask: bool) -> Result<Value> {
-        let order = CreateOrderRequest {
-            account_index: self.account_index,
-            order_book_index: market_index,
-            client_order_index: SystemTime::now()
-                .duration_since(UNIX_EPOCH)?
-                .as_millis() as u64,
-            base_amount: i64::MAX / 2, // Large amount to ensure position is closed
-            price: 0, // Market order
-            is_ask,
-            order_type: 1, // Market order
-            time_in_force: 0, // ImmediateOrCancel
-            reduce_only: true, // Only reduce position
-            trigger_price: 0,
-        };
-        
-        self.create_order(order).await
+        // Size the reduce-only order from the actual position; oversized amounts are rejected by some markets
+        let position = self
+            .get_position_size(market_index)
+            .await?
+            .filter(|p| p.is_long == is_ask)
+            .ok_or_else(|| ApiError::Api(format!(
+                "No {} position to close on market {}",
+                if is_ask { "long" } else { "short" },
+                market_index
+            )))?;
+
+        self.close_position_with_size(market_index, is_ask, position.base_amount).await
     }
     
     /// Get account information including positions
@@ -477,28 +605,22 @@ impl LighterClient {
     /// # Returns
     /// JSON response with account details including positions
     pub async fn get_account(&self) -> Result<Value> {
-        let auth_token = self.create_auth_token(600)?; // 10 minute expiry
         let account_index_str = self.account_index.to_string();
         
-        let response = self
-            .client
-            .get(&format!("{}/api/v1/account", self.base_url))
-            .query(&[("by", "index"), ("value", &account_index_str)])
-            .header("Authorization", &auth_token)
-            .header("Auth", &auth_token)
-            .send()
-            .await?;
-        
-        let response_text = response.text().await?;
-        let response_json: Value = serde_json::from_str(&response_text)?;
+        let request = self
+            .authenticated_get("/api/v1/account").await?
+            .query(&[("by", "index"), ("value", &account_index_str)]);
         
-        Ok(response_json)
+        self.guarded("/api/v1/account", async { parse_response(request.send().await?).await })
+            .await
     }
     
     /// Close all positions by querying account first
     /// 
     /// This method queries the account to find open positions, then closes them.
     /// More efficient than close_all_positions() as it only closes positions that exist.
+    /// Returns once the close orders are sent; use `close_all_positions_verified` to wait
+    /// for them to fill and retry what remains.
     /// 
     /// # Returns
     /// JSON response with results for each closed position
@@ -548,17 +670,11 @@ impl LighterClient {
                 // Get position amount - try multiple formats (string or number)
                 let position_amount = position.get("position")
                     .or_else(|| position.get("Position"))
-                    .and_then(|p| {
-                        if let Some(s) = p.as_str() {
-                            s.parse::<f64>().ok()
-                        } else {
-                            p.as_f64().or_else(|| p.as_i64().map(|v| v as f64))
-                        }
-                    })
-                    .unwrap_or(0.0);
+                    .and_then(parse_decimal)
+                    .unwrap_or_default();
                 
                 // Only close if position exists (non-zero)
-                if position_amount.abs() > 0.0001 {
+                if !position_amount.is_zero() {
                     // sign = 1 means long position, close by selling (is_ask = true)
                     // sign = -1 means short position, close by buying (is_ask = false)
                     let is_ask = sign > 0;
@@ -569,7 +685,7 @@ impl LighterClient {
                             results.push(json!({
                                 "market_index": market_index,
                                 "direction": if sign > 0 { "long" } else { "short" },
-                                "position_amount": position_amount,
+                                "position_amount": position_amount.to_string(),
                                 "status": if code == 200 { "success" } else { "failed" },
                                 "code": code,
                                 "response": response
@@ -579,7 +695,7 @@ impl LighterClient {
                             results.push(json!({
                                 "market_index": market_index,
                                 "direction": if sign > 0 { "long" } else { "short" },
-                                "position_amount": position_amount,
+                                "position_amount": position_amount.to_string(),
                                 "status": "error",
                                 "error": e.to_string()
                             }));
@@ -653,7 +769,7 @@ impl LighterClient {
     }
 
     pub async fn change_api_key(&self, new_public_key: &[u8; 40]) -> Result<Value> {
-        let nonce = self.get_nonce().await?;
+        let lease = self.lease_nonce().await?;
         let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
         let expired_at = now + 599_000;
 
@@ -662,41 +778,33 @@ impl LighterClient {
             "ApiKeyIndex": self.api_key_index,
             "PubKey": hex::encode(new_public_key),
             "ExpiredAt": expired_at,
-            "Nonce": nonce,
+            "Nonce": lease.nonce(),
             "Sig": ""
         });
 
-        let tx_json = serde_json::to_string(&tx_info)?;
-        let signature = self.sign_transaction_with_type(&tx_json, 8)?; // TX_TYPE_CHANGE_PUB_KEY
-
-        let mut final_tx_info = tx_info;
-        final_tx_info["Sig"] = json!(base64::engine::general_purpose::STANDARD.encode(&signature));
-
-        let form_data = [
-            ("tx_type", "8"), // CHANGE_PUB_KEY
-            ("tx_info", &serde_json::to_string(&final_tx_info)?),
-            ("price_protection", "true"),
-        ];
-
-        let response = self
-            .client
-            .post(&format!("{}/api/v1/sendTx", self.base_url))
-            .form(&form_data)
-            .send()
-            .await?;
+        let final_tx_info = self.sign_tx_info(tx_info, 8).await?.tx_info; // TX_TYPE_CHANGE_PUB_KEY
 
-        let response_text = response.text().await?;
-        let response_json: Value = serde_json::from_str(&response_text)?;
+        let response = self.send_tx(8, &final_tx_info, self.price_protection_param(None)).await; // CHANGE_PUB_KEY
+        lease.settle_outcome(&response);
+        response
+    }
 
-        Ok(response_json)
+    pub async fn create_auth_token(&self, expiry_seconds: i64) -> Result<String> {
+        Ok(self.auth_token(expiry_seconds).await?.to_string())
     }
 
-    pub fn create_auth_token(&self, expiry_seconds: i64) -> Result<String> {
+    /// Create an auth token valid for `expiry_seconds`, as a typed `AuthToken`
+    pub async fn auth_token(&self, expiry_seconds: i64) -> Result<AuthToken> {
         let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
         let deadline = now + expiry_seconds;
-        self.key_manager
-            .create_auth_token(deadline, self.account_index, self.api_key_index)
-            .map_err(|e| ApiError::Signer(e))
+        let hash = AuthToken::signing_hash(deadline, self.account_index, self.api_key_index);
+        Ok(AuthToken {
+            version: 0,
+            deadline,
+            account_index: self.account_index,
+            api_key_index: self.api_key_index,
+            signature: self.signer().sign(&hash).await?,
+        })
     }
 
     /// Update leverage for a market
@@ -704,113 +812,55 @@ impl LighterClient {
     /// # Arguments
     /// * `market_index` - Market index (0-based)
     /// * `leverage` - Leverage value (e.g., 3 for 3x leverage)
-    /// * `margin_mode` - Margin mode: 0 for CROSS_MARGIN, 1 for ISOLATED_MARGIN
+    /// * `margin_mode` - Cross or isolated margin
     ///
     /// # Returns
-    /// JSON response from the API
+    /// The exchange response with the initial margin fraction sent and the leverage it
+    /// amounts to. The fraction is rounded up, so the effective leverage never exceeds the
+    /// requested one (see `leverage` module). Fails with `ApiError::InvalidOrder` for a
+    /// leverage of 0 or above the market maximum.
     pub async fn update_leverage(
         &self,
         market_index: u8,
         leverage: u16,
-        margin_mode: u8,
-    ) -> Result<Value> {
-        const MAX_RETRIES: u32 = 5;
-        const RETRY_DELAY_MS: u64 = 3000; // 3 seconds between retries
-        
-        // Fetch nonce once before retry loop
-        let mut current_nonce = self.get_nonce_or_use(None).await?;
-        
-        let mut last_error: Option<ApiError> = None;
-        
-        for attempt in 0..=MAX_RETRIES {
-            if attempt > 0 {
-                // Wait 3 seconds between retries for 21120 errors (nonce timing issue)
-                tokio::time::sleep(tokio::time::Duration::from_millis(RETRY_DELAY_MS)).await;
-                
-                // Refresh nonce from API on retry
-                match self.fetch_nonce_from_api().await {
-                    Ok(fresh_nonce) => {
-                        current_nonce = fresh_nonce;
-                        let mut cache = self.nonce_cache.lock().await;
-                        cache.set_fetched_nonce(fresh_nonce);
-                    }
-                    Err(_) => {
-                        // If fetch fails, continue with current nonce
-                    }
-                }
-            }
-            
-            let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
-            let expired_at = now + 599_000;
-
-            // Calculate InitialMarginFraction: IMF = 10,000 / leverage
-            // Example: leverage 3x = 10,000 / 3 = 3333
-            let initial_margin_fraction = (10_000u32 / leverage as u32) as u16;
-
-            print!("[update_leverage] Calculated InitialMarginFraction: {} for leverage {}", initial_margin_fraction, leverage);
-
-            let tx_info = json!({
-                "AccountIndex": self.account_index,
-                "ApiKeyIndex": self.api_key_index,
-                "MarketIndex": market_index,
-                "InitialMarginFraction": initial_margin_fraction,
-                "MarginMode": margin_mode,
-                "ExpiredAt": expired_at,
-                "Nonce": current_nonce,
-                "Sig": ""
-            });
-
-            let tx_json = serde_json::to_string(&tx_info)?;
-            let signature = self.sign_transaction_with_type(&tx_json, 20)?; // TX_TYPE_UPDATE_LEVERAGE
-
-            let mut final_tx_info = tx_info;
-            final_tx_info["Sig"] = json!(base64::engine::general_purpose::STANDARD.encode(&signature));
-
-            let form_data = [
-                ("tx_type", "20"), // UPDATE_LEVERAGE
-                ("tx_info", &serde_json::to_string(&final_tx_info)?),
-                ("price_protection", "true"),
-            ];
-
-            let response = self
-                .client
-                .post(&format!("{}/api/v1/sendTx", self.base_url))
-                .form(&form_data)
-                .send()
-                .await?;
-
-            let response_text = response.text().await?;
-            let response_json: Value = serde_json::from_str(&response_text)?;
-            
-            let code = response_json["code"].as_i64().unwrap_or_default();
-            if code == 200 {
-                // Success - nonce was used, cache is already correct
-                return Ok(response_json);
-            } else if code == 21120 && attempt < MAX_RETRIES {
-                // Invalid signature - retry with refreshed nonce after delay
-                last_error = Some(ApiError::Api(format!("Invalid signature (code 21120) after {} attempts", attempt + 1)));
-                continue;
-            } else {
-                // Other error or max retries reached
-                {
-                    let mut cache = self.nonce_cache.lock().await;
-                    cache.acknowledge_failure();
-                }
-                return Ok(response_json);
-            }
-        }
-        
-        // If we get here, all retries failed
-        {
-            let mut cache = self.nonce_cache.lock().await;
-            cache.acknowledge_failure();
-        }
-        Err(last_error.unwrap_or_else(|| ApiError::Api("Failed after all retries".to_string())))
+        margin_mode: MarginMode,
+    ) -> Result<LeverageUpdate> {
+        let fees = self.get_market_fees(market_index).await?;
+        let initial_margin_fraction = leverage::initial_margin_fraction(leverage, &fees)?;
+
+        // Retried like create_order (see `RetryConfig`)
+        let lease = self.lease_nonce().await?;
+        let response = self
+            .send_with_retries(20, self.price_protection_param(None), lease, |nonce| async move {
+                let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
+                let expired_at = now + 599_000;
+
+                let tx_info = json!({
+                    "AccountIndex": self.account_index,
+                    "ApiKeyIndex": self.api_key_index,
+                    "MarketIndex": market_index,
+                    "InitialMarginFraction": initial_margin_fraction,
+                    "MarginMode": margin_mode,
+                    "ExpiredAt": expired_at,
+                    "Nonce": nonce,
+                    "Sig": ""
+                });
+
+                self.sign_tx_info(tx_info, 20).await // UPDATE_LEVERAGE
+            })
+            .await?;
+
+        Ok(LeverageUpdate {
+            response,
+            initial_margin_fraction,
+            effective_leverage: MARGIN_FRACTION_SCALE as f64 / initial_margin_fraction as f64,
+        })
     }
 
     /// Transfer USDC to another account
     pub async fn transfer(&self, request: TransferRequest) -> Result<Value> {
-        let nonce = self.get_next_nonce_from_cache().await?;
+        let lease = self.lease_nonce().await?;
+        let nonce = lease.nonce();
         let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
         let expired_at = now + 599_000;
 
@@ -826,34 +876,24 @@ impl LighterClient {
             "Sig": ""
         });
 
-        let tx_json = serde_json::to_string(&tx_info)?;
-        let signature = self.sign_transaction_with_type(&tx_json, 12)?; // TX_TYPE_TRANSFER
-
-        let mut final_tx_info = tx_info;
-        final_tx_info["Sig"] = json!(base64::engine::general_purpose::STANDARD.encode(&signature));
-
-        let form_data = [
-            ("tx_type", "12"), // TRANSFER
-            ("tx_info", &serde_json::to_string(&final_tx_info)?),
-            ("price_protection", "true"),
-        ];
-
-        let response = self
-            .client
-            .post(&format!("{}/api/v1/sendTx", self.base_url))
-            .form(&form_data)
-            .send()
-            .await?;
+        let final_tx_info = self.sign_tx_info(tx_info, 12).await?.tx_info; // TX_TYPE_TRANSFER
 
-        let response_text = response.text().await?;
-        let response_json: Value = serde_json::from_str(&response_text)?;
-
-        Ok(response_json)
+        let response = self.send_tx(12, &final_tx_info, self.price_protection_param(None)).await; // TRANSFER
+        lease.settle_outcome(&response);
+        response
     }
 
     /// Withdraw USDC from L2 to L1
+    ///
+    /// This only submits the L2 transaction; use `get_withdraw_history` and
+    /// `wait_for_withdraw` to follow it until it is claimable on L1. The amount is checked
+    /// against `get_withdraw_limits` first; one below the minimum or not covering the fee
+    /// fails with `ApiError::InvalidWithdraw` without signing or using a nonce.
     pub async fn withdraw(&self, request: WithdrawRequest) -> Result<Value> {
-        let nonce = self.get_next_nonce_from_cache().await?;
+        self.get_withdraw_limits().await?.check(&request)?;
+
+        let lease = self.lease_nonce().await?;
+        let nonce = lease.nonce();
         let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
         let expired_at = now + 599_000;
 
@@ -866,34 +906,19 @@ impl LighterClient {
             "Sig": ""
         });
 
-        let tx_json = serde_json::to_string(&tx_info)?;
-        let signature = self.sign_transaction_with_type(&tx_json, 13)?; // TX_TYPE_WITHDRAW
-
-        let mut final_tx_info = tx_info;
-        final_tx_info["Sig"] = json!(base64::engine::general_purpose::STANDARD.encode(&signature));
-
-        let form_data = [
-            ("tx_type", "13"), // WITHDRAW
-            ("tx_info", &serde_json::to_string(&final_tx_info)?),
-            ("price_protection", "true"),
-        ];
-
-        let response = self
-            .client
-            .post(&format!("{}/api/v1/sendTx", self.base_url))
-            .form(&form_data)
-            .send()
-            .await?;
-
-        let response_text = response.text().await?;
-        let response_json: Value = serde_json::from_str(&response_text)?;
+        let final_tx_info = self.sign_tx_info(tx_info, 13).await?.tx_info; // TX_TYPE_WITHDRAW
 
-        Ok(response_json)
+        let response = self.send_tx(13, &final_tx_info, self.price_protection_param(None)).await; // WITHDRAW
+        lease.settle_outcome(&response);
+        response
     }
 
     /// Modify an existing order
+    /// The new size and price are checked against the client's `RiskConfig` first
     pub async fn modify_order(&self, request: ModifyOrderRequest) -> Result<Value> {
-        let nonce = self.get_next_nonce_from_cache().await?;
+        self.check_modify_risk(&request).await?;
+        let lease = self.lease_nonce().await?;
+        let nonce = lease.nonce();
         let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
         let expired_at = now + 599_000;
 
@@ -910,34 +935,17 @@ impl LighterClient {
             "Sig": ""
         });
 
-        let tx_json = serde_json::to_string(&tx_info)?;
-        let signature = self.sign_transaction_with_type(&tx_json, 17)?; // TX_TYPE_MODIFY_ORDER
-
-        let mut final_tx_info = tx_info;
-        final_tx_info["Sig"] = json!(base64::engine::general_purpose::STANDARD.encode(&signature));
-
-        let form_data = [
-            ("tx_type", "17"), // MODIFY_ORDER
-            ("tx_info", &serde_json::to_string(&final_tx_info)?),
-            ("price_protection", "true"),
-        ];
-
-        let response = self
-            .client
-            .post(&format!("{}/api/v1/sendTx", self.base_url))
-            .form(&form_data)
-            .send()
-            .await?;
-
-        let response_text = response.text().await?;
-        let response_json: Value = serde_json::from_str(&response_text)?;
+        let final_tx_info = self.sign_tx_info(tx_info, 17).await?.tx_info; // TX_TYPE_MODIFY_ORDER
 
-        Ok(response_json)
+        let response = self.send_tx(17, &final_tx_info, self.price_protection_param(None)).await; // MODIFY_ORDER
+        lease.settle_outcome(&response);
+        response
     }
 
     /// Create a sub account
     pub async fn create_sub_account(&self) -> Result<Value> {
-        let nonce = self.get_next_nonce_from_cache().await?;
+        let lease = self.lease_nonce().await?;
+        let nonce = lease.nonce();
         let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
         let expired_at = now + 599_000;
 
@@ -949,34 +957,17 @@ impl LighterClient {
             "Sig": ""
         });
 
-        let tx_json = serde_json::to_string(&tx_info)?;
-        let signature = self.sign_transaction_with_type(&tx_json, 9)?; // TX_TYPE_CREATE_SUB_ACCOUNT
-
-        let mut final_tx_info = tx_info;
-        final_tx_info["Sig"] = json!(base64::engine::general_purpose::STANDARD.encode(&signature));
+        let final_tx_info = self.sign_tx_info(tx_info, 9).await?.tx_info; // TX_TYPE_CREATE_SUB_ACCOUNT
 
-        let form_data = [
-            ("tx_type", "9"), // CREATE_SUB_ACCOUNT
-            ("tx_info", &serde_json::to_string(&final_tx_info)?),
-            ("price_protection", "true"),
-        ];
-
-        let response = self
-            .client
-            .post(&format!("{}/api/v1/sendTx", self.base_url))
-            .form(&form_data)
-            .send()
-            .await?;
-
-        let response_text = response.text().await?;
-        let response_json: Value = serde_json::from_str(&response_text)?;
-
-        Ok(response_json)
+        let response = self.send_tx(9, &final_tx_info, self.price_protection_param(None)).await; // CREATE_SUB_ACCOUNT
+        lease.settle_outcome(&response);
+        response
     }
 
     /// Create a public pool
     pub async fn create_public_pool(&self, request: CreatePublicPoolRequest) -> Result<Value> {
-        let nonce = self.get_next_nonce_from_cache().await?;
+        let lease = self.lease_nonce().await?;
+        let nonce = lease.nonce();
         let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
         let expired_at = now + 599_000;
 
@@ -991,34 +982,17 @@ impl LighterClient {
             "Sig": ""
         });
 
-        let tx_json = serde_json::to_string(&tx_info)?;
-        let signature = self.sign_transaction_with_type(&tx_json, 10)?; // TX_TYPE_CREATE_PUBLIC_POOL
-
-        let mut final_tx_info = tx_info;
-        final_tx_info["Sig"] = json!(base64::engine::general_purpose::STANDARD.encode(&signature));
-
-        let form_data = [
-            ("tx_type", "10"), // CREATE_PUBLIC_POOL
-            ("tx_info", &serde_json::to_string(&final_tx_info)?),
-            ("price_protection", "true"),
-        ];
+        let final_tx_info = self.sign_tx_info(tx_info, 10).await?.tx_info; // TX_TYPE_CREATE_PUBLIC_POOL
 
-        let response = self
-            .client
-            .post(&format!("{}/api/v1/sendTx", self.base_url))
-            .form(&form_data)
-            .send()
-            .await?;
-
-        let response_text = response.text().await?;
-        let response_json: Value = serde_json::from_str(&response_text)?;
-
-        Ok(response_json)
+        let response = self.send_tx(10, &final_tx_info, self.price_protection_param(None)).await; // CREATE_PUBLIC_POOL
+        lease.settle_outcome(&response);
+        response
     }
 
     /// Update a public pool
     pub async fn update_public_pool(&self, request: UpdatePublicPoolRequest) -> Result<Value> {
-        let nonce = self.get_next_nonce_from_cache().await?;
+        let lease = self.lease_nonce().await?;
+        let nonce = lease.nonce();
         let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
         let expired_at = now + 599_000;
 
@@ -1034,34 +1008,17 @@ impl LighterClient {
             "Sig": ""
         });
 
-        let tx_json = serde_json::to_string(&tx_info)?;
-        let signature = self.sign_transaction_with_type(&tx_json, 11)?; // TX_TYPE_UPDATE_PUBLIC_POOL
-
-        let mut final_tx_info = tx_info;
-        final_tx_info["Sig"] = json!(base64::engine::general_purpose::STANDARD.encode(&signature));
-
-        let form_data = [
-            ("tx_type", "11"), // UPDATE_PUBLIC_POOL
-            ("tx_info", &serde_json::to_string(&final_tx_info)?),
-            ("price_protection", "true"),
-        ];
-
-        let response = self
-            .client
-            .post(&format!("{}/api/v1/sendTx", self.base_url))
-            .form(&form_data)
-            .send()
-            .await?;
-
-        let response_text = response.text().await?;
-        let response_json: Value = serde_json::from_str(&response_text)?;
+        let final_tx_info = self.sign_tx_info(tx_info, 11).await?.tx_info; // TX_TYPE_UPDATE_PUBLIC_POOL
 
-        Ok(response_json)
+        let response = self.send_tx(11, &final_tx_info, self.price_protection_param(None)).await; // UPDATE_PUBLIC_POOL
+        lease.settle_outcome(&response);
+        response
     }
 
     /// Mint shares in a public pool
     pub async fn mint_shares(&self, request: MintSharesRequest) -> Result<Value> {
-        let nonce = self.get_next_nonce_from_cache().await?;
+        let lease = self.lease_nonce().await?;
+        let nonce = lease.nonce();
         let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
         let expired_at = now + 599_000;
 
@@ -1075,34 +1032,17 @@ impl LighterClient {
             "Sig": ""
         });
 
-        let tx_json = serde_json::to_string(&tx_info)?;
-        let signature = self.sign_transaction_with_type(&tx_json, 18)?; // TX_TYPE_MINT_SHARES
-
-        let mut final_tx_info = tx_info;
-        final_tx_info["Sig"] = json!(base64::engine::general_purpose::STANDARD.encode(&signature));
-
-        let form_data = [
-            ("tx_type", "18"), // MINT_SHARES
-            ("tx_info", &serde_json::to_string(&final_tx_info)?),
-            ("price_protection", "true"),
-        ];
-
-        let response = self
-            .client
-            .post(&format!("{}/api/v1/sendTx", self.base_url))
-            .form(&form_data)
-            .send()
-            .await?;
-
-        let response_text = response.text().await?;
-        let response_json: Value = serde_json::from_str(&response_text)?;
+        let final_tx_info = self.sign_tx_info(tx_info, 18).await?.tx_info; // TX_TYPE_MINT_SHARES
 
-        Ok(response_json)
+        let response = self.send_tx(18, &final_tx_info, self.price_protection_param(None)).await; // MINT_SHARES
+        lease.settle_outcome(&response);
+        response
     }
 
     /// Burn shares from a public pool
     pub async fn burn_shares(&self, request: BurnSharesRequest) -> Result<Value> {
-        let nonce = self.get_next_nonce_from_cache().await?;
+        let lease = self.lease_nonce().await?;
+        let nonce = lease.nonce();
         let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
         let expired_at = now + 599_000;
 
@@ -1116,34 +1056,17 @@ impl LighterClient {
             "Sig": ""
         });
 
-        let tx_json = serde_json::to_string(&tx_info)?;
-        let signature = self.sign_transaction_with_type(&tx_json, 19)?; // TX_TYPE_BURN_SHARES
-
-        let mut final_tx_info = tx_info;
-        final_tx_info["Sig"] = json!(base64::engine::general_purpose::STANDARD.encode(&signature));
+        let final_tx_info = self.sign_tx_info(tx_info, 19).await?.tx_info; // TX_TYPE_BURN_SHARES
 
-        let form_data = [
-            ("tx_type", "19"), // BURN_SHARES
-            ("tx_info", &serde_json::to_string(&final_tx_info)?),
-            ("price_protection", "true"),
-        ];
-
-        let response = self
-            .client
-            .post(&format!("{}/api/v1/sendTx", self.base_url))
-            .form(&form_data)
-            .send()
-            .await?;
-
-        let response_text = response.text().await?;
-        let response_json: Value = serde_json::from_str(&response_text)?;
-
-        Ok(response_json)
+        let response = self.send_tx(19, &final_tx_info, self.price_protection_param(None)).await; // BURN_SHARES
+        lease.settle_outcome(&response);
+        response
     }
 
     /// Update margin for isolated margin positions
     pub async fn update_margin(&self, request: UpdateMarginRequest) -> Result<Value> {
-        let nonce = self.get_next_nonce_from_cache().await?;
+        let lease = self.lease_nonce().await?;
+        let nonce = lease.nonce();
         let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
         let expired_at = now + 599_000;
 
@@ -1158,34 +1081,21 @@ impl LighterClient {
             "Sig": ""
         });
 
-        let tx_json = serde_json::to_string(&tx_info)?;
-        let signature = self.sign_transaction_with_type(&tx_json, 29)?; // TX_TYPE_UPDATE_MARGIN
-
-        let mut final_tx_info = tx_info;
-        final_tx_info["Sig"] = json!(base64::engine::general_purpose::STANDARD.encode(&signature));
-
-        let form_data = [
-            ("tx_type", "29"), // UPDATE_MARGIN
-            ("tx_info", &serde_json::to_string(&final_tx_info)?),
-            ("price_protection", "true"),
-        ];
-
-        let response = self
-            .client
-            .post(&format!("{}/api/v1/sendTx", self.base_url))
-            .form(&form_data)
-            .send()
-            .await?;
-
-        let response_text = response.text().await?;
-        let response_json: Value = serde_json::from_str(&response_text)?;
+        let final_tx_info = self.sign_tx_info(tx_info, 29).await?.tx_info; // TX_TYPE_UPDATE_MARGIN
 
-        Ok(response_json)
+        let response = self.send_tx(29, &final_tx_info, self.price_protection_param(None)).await; // UPDATE_MARGIN
+        lease.settle_outcome(&response);
+        response
     }
 
     /// Create grouped orders (OCO, OTO, etc.)
+    /// Each order is checked against the client's `RiskConfig` first
     pub async fn create_grouped_orders(&self, request: CreateGroupedOrdersRequest) -> Result<Value> {
-        let nonce = self.get_next_nonce_from_cache().await?;
+        for (leg, order) in request.orders.iter().enumerate() {
+            self.check_risk_adding(order, leg + 1).await?;
+        }
+        let lease = self.lease_nonce().await?;
+        let nonce = lease.nonce();
         let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
         let expired_at = now + 599_000;
 
@@ -1200,7 +1110,7 @@ impl LighterClient {
                 "TimeInForce": order.time_in_force,
                 "ReduceOnly": if order.reduce_only { 1 } else { 0 },
                 "TriggerPrice": order.trigger_price,
-                "OrderExpiry": 0,
+                "OrderExpiry": self.expiry_policy.order_expiry(order, now),
             })
         }).collect();
 
@@ -1214,31 +1124,126 @@ impl LighterClient {
             "Sig": ""
         });
 
-        let tx_json = serde_json::to_string(&tx_info)?;
-        let signature = self.sign_transaction_with_type(&tx_json, 28)?; // TX_TYPE_CREATE_GROUPED_ORDERS
+        let final_tx_info = self.sign_tx_info(tx_info, 28).await?.tx_info; // TX_TYPE_CREATE_GROUPED_ORDERS
+
+        // One flag per transaction: the first order that sets an override decides it
+        let price_protection = self.price_protection_param(request.orders.iter().find_map(|o| o.price_protection));
+        let response = self.send_tx(28, &final_tx_info, price_protection).await; // CREATE_GROUPED_ORDERS
+        lease.settle_outcome(&response);
+        response
+    }
+    
+    /// Post a signed transaction to sendTx and return the response JSON
+    ///
+    /// With a journal configured, the transaction is recorded before it is sent and its
+    /// status updated from the response. If the exchange rejects the nonce, the nonce is
+    /// refetched and the transaction re-signed and sent once more (see `NonceResync`).
+    ///
+    /// Goes over the websocket when the client was built with `websocket_tx(true)`. Fails
+    /// with `ApiError::Halted` without sending while a kill switch blocks the transaction.
+    async fn send_tx(&self, tx_type: u32, tx_info: &Value, price_protection: &str) -> Result<Value> {
+        let transport = if self.websocket_tx { Transport::WebSocket } else { Transport::Http };
+        self.send_tx_via(transport, tx_type, tx_info, price_protection).await
+    }
 
-        let mut final_tx_info = tx_info;
-        final_tx_info["Sig"] = json!(base64::engine::general_purpose::STANDARD.encode(&signature));
+    async fn send_tx_via(&self, transport: Transport, tx_type: u32, tx_info: &Value, price_protection: &str) -> Result<Value> {
+        let response = self.post_tx(transport, tx_type, tx_info, price_protection).await?;
+        if nonce::is_nonce_error(&response) {
+            return self.resync_and_replay(transport, tx_type, tx_info, price_protection, response).await;
+        }
+        Ok(response)
+    }
+
+    /// Post one signed transaction, journaling it if a journal is configured
+    async fn post_tx(&self, transport: Transport, tx_type: u32, tx_info: &Value, price_protection: &str) -> Result<Value> {
+        let tx_info_json = tx_json::tx_info_json(tx_type, tx_info)?;
+        self.check_kill_switch(tx_type, tx_info).await?;
+        // A transaction the circuit breaker stops is never sent, so it is not journaled
+        if transport == Transport::Http {
+            self.circuit.ensure_closed("/api/v1/sendTx")?;
+        }
+        let journaled = match &self.journal {
+            Some(journal) => {
+                let entry = JournalEntry::new(self.account_index, self.api_key_index, tx_type, tx_info, price_protection)?;
+                journal.record(&entry)?;
+                Some((journal, entry.nonce))
+            }
+            None => None,
+        };
 
+        let tx_type_str = tx_type.to_string();
         let form_data = [
-            ("tx_type", "28"), // CREATE_GROUPED_ORDERS
-            ("tx_info", &serde_json::to_string(&final_tx_info)?),
-            ("price_protection", "true"),
+            ("tx_type", tx_type_str.as_str()),
+            ("tx_info", &tx_info_json),
+            ("price_protection", price_protection),
         ];
 
-        let response = self
-            .client
-            .post(&format!("{}/api/v1/sendTx", self.base_url))
-            .form(&form_data)
-            .send()
-            .await?;
+        let over_socket = match transport {
+            Transport::WebSocket => self.post_tx_ws(tx_type, tx_info, price_protection).await,
+            Transport::Http => None,
+        };
+        let result = match over_socket {
+            Some(result) => result,
+            None => self.post_tx_http(&form_data).await,
+        };
 
-        let response_text = response.text().await?;
-        let response_json: Value = serde_json::from_str(&response_text)?;
+        if let Some((journal, nonce)) = journaled {
+            journal.update(self.account_index, self.api_key_index, nonce, TxStatus::from_response(&result))?;
+        }
 
-        Ok(response_json)
+        result
     }
-    
+
+    async fn post_tx_http(&self, form_data: &[(&str, &str)]) -> Result<Value> {
+        let request = self.client.post(format!("{}/api/v1/sendTx", self.base_url)).form(form_data);
+        self.guarded("/api/v1/sendTx", async { parse_response(request.send().await?).await })
+            .await
+    }
+
+    /// Post signed transactions to sendTxBatch; the exchange applies them in order
+    ///
+    /// Each transaction is journaled like in `send_tx`.
+    pub(crate) async fn send_tx_batch(&self, txs: &[(u32, Value)]) -> Result<Value> {
+        let tx_types: Vec<u32> = txs.iter().map(|(tx_type, _)| *tx_type).collect();
+        let tx_infos = txs
+            .iter()
+            .map(|(tx_type, tx_info)| tx_json::tx_info_json(*tx_type, tx_info))
+            .collect::<Result<Vec<String>>>()?;
+
+        for (tx_type, tx_info) in txs {
+            self.check_kill_switch(*tx_type, tx_info).await?;
+        }
+        self.circuit.ensure_closed("/api/v1/sendTxBatch")?;
+        let mut journaled = Vec::new();
+        if let Some(journal) = &self.journal {
+            // Entries are resubmitted one by one, so they carry the per-transaction flag
+            let price_protection = self.price_protection_param(None);
+            for (tx_type, tx_info) in txs {
+                let entry = JournalEntry::new(self.account_index, self.api_key_index, *tx_type, tx_info, price_protection)?;
+                journal.record(&entry)?;
+                journaled.push(entry.nonce);
+            }
+        }
+
+        let form_data = [
+            ("tx_types", serde_json::to_string(&tx_types)?),
+            ("tx_infos", serde_json::to_string(&tx_infos)?),
+        ];
+
+        let request = self.client.post(format!("{}/api/v1/sendTxBatch", self.base_url)).form(&form_data);
+        let result = self
+            .guarded("/api/v1/sendTxBatch", async { parse_response(request.send().await?).await })
+            .await;
+
+        if let Some(journal) = &self.journal {
+            for nonce in journaled {
+                journal.update(self.account_index, self.api_key_index, nonce, TxStatus::from_response(&result))?;
+            }
+        }
+
+        result
+    }
+
     /// Fetch a single nonce from API
     async fn fetch_nonce_from_api(&self) -> Result<i64> {
         let url = format!(
@@ -1246,9 +1251,9 @@ impl LighterClient {
             self.base_url, self.account_index, self.api_key_index
         );
         
-        let response = self.client.get(&url).send().await?;
-        let response_text = response.text().await?;
-        let response_json: Value = serde_json::from_str(&response_text)?;
+        let response_json = self
+            .guarded("/api/v1/nextNonce", async { parse_response(self.client.get(&url).send().await?).await })
+            .await?;
         
         let nonce = response_json["nonce"]
             .as_i64()
@@ -1271,55 +1276,37 @@ impl LighterClient {
         i64::from_le_bytes(nonce_bytes)
     }
     
-    /// Get next nonce using optimistic nonce management
-    /// Fetches from API once, then increments locally
-    /// Only fetches again if cache is not initialized
-    async fn get_next_nonce_from_cache(&self) -> Result<i64> {
-        let mut cache = self.nonce_cache.lock().await;
-        
-        // If cache is initialized, use optimistic nonce management
-        if let Some(nonce) = cache.get_next_nonce() {
-            return Ok(nonce);
+    /// Lease a nonce, or wrap a caller-provided one
+    /// Some(-1) and None lease from the client; any other value is used as given
+    async fn lease_or_use(&self, provided_nonce: Option<i64>) -> Result<NonceLease> {
+        match provided_nonce {
+            Some(nonce) if nonce != -1 => Ok(NonceLease::detached(nonce)),
+            _ => self.lease_nonce().await,
         }
-        
-        // Cache not initialized, fetch from API
-        drop(cache); // Release lock before async call
-        let nonce = self.fetch_nonce_from_api().await?;
-        
-        // Update cache with fetched nonce
-        let mut cache = self.nonce_cache.lock().await;
-        cache.set_fetched_nonce(nonce);
-        
-        // Return the fetched nonce (first use)
-        Ok(nonce)
     }
     
-    /// Get next nonce using optimistic nonce management
-    /// If provided_nonce is Some(n), uses that nonce (or -1 to fetch from cache)
-    /// If provided_nonce is None, gets nonce from cache (fetches once, then increments)
+    /// Get next nonce for a transaction the caller signs and sends itself
+    /// If provided_nonce is Some(n), uses that nonce (or -1 to take one from the client)
+    /// If provided_nonce is None, takes the next nonce from the client
+    /// The nonce is treated as consumed; use `lease_nonce` to give it back on failure
     pub async fn get_nonce_or_use(&self, provided_nonce: Option<i64>) -> Result<i64> {
-        if let Some(nonce) = provided_nonce {
-            if nonce == -1 {
-                self.get_next_nonce_from_cache().await
-            } else {
-                Ok(nonce)
-            }
-        } else {
-            self.get_next_nonce_from_cache().await
-        }
+        let lease = self.lease_or_use(provided_nonce).await?;
+        let nonce = lease.nonce();
+        lease.commit();
+        Ok(nonce)
     }
     
     /// Refresh nonce from API (useful for manual refresh)
+    /// Outstanding leases keep their nonces; nonces below the fetched one are dropped from the pool
     pub async fn refresh_nonce(&self) -> Result<i64> {
         let nonce = self.fetch_nonce_from_api().await?;
-        let mut cache = self.nonce_cache.lock().await;
-        cache.set_fetched_nonce(nonce);
+        self.nonces.resync(nonce);
         Ok(nonce)
     }
     
     /// Get next nonce from API (public method)
     /// This fetches a fresh nonce from the API each time
-    /// For optimistic nonce management, use get_next_nonce_from_cache instead
+    /// For locally managed nonces, use lease_nonce instead
     pub async fn get_nonce(&self) -> Result<i64> {
         self.fetch_nonce_from_api().await
     }
@@ -1333,9 +1320,9 @@ impl LighterClient {
     /// * `tx_json` - JSON string representation of the transaction
     ///
     /// # Returns
-    /// An 80-byte signature array
-    pub fn sign_transaction(&self, tx_json: &str) -> Result<[u8; 80]> {
-        self.sign_transaction_internal(tx_json, 14) // CREATE_ORDER
+    /// The Schnorr signature; `to_base64` gives the `Sig` field
+    pub async fn sign_transaction(&self, tx_json: &str) -> Result<Signature> {
+        self.sign_transaction_internal(tx_json, 14).await // CREATE_ORDER
     }
 
     /// Signs a transaction with a specific transaction type.
@@ -1345,9 +1332,9 @@ impl LighterClient {
     /// * `tx_type` - Transaction type code (e.g., 14 for CREATE_ORDER, 15 for CANCEL_ORDER, 20 for UPDATE_LEVERAGE)
     ///
     /// # Returns
-    /// An 80-byte signature array
-    pub fn sign_transaction_with_type(&self, tx_json: &str, tx_type: u32) -> Result<[u8; 80]> {
-        self.sign_transaction_internal(tx_json, tx_type)
+    /// The Schnorr signature; `to_base64` gives the `Sig` field
+    pub async fn sign_transaction_with_type(&self, tx_json: &str, tx_type: u32) -> Result<Signature> {
+        self.sign_transaction_internal(tx_json, tx_type).await
     }
 
     /// Internal method to sign a transaction.
@@ -1366,438 +1353,180 @@ impl LighterClient {
     /// * `tx_type` - Transaction type code
     ///
     /// # Returns
-    /// An 80-byte signature array (s || e format)
-    fn sign_transaction_internal(&self, tx_json: &str, tx_type: u32) -> Result<[u8; 80]> {
-        // Parse the transaction JSON to extract fields
-        let tx_value: Value = serde_json::from_str(tx_json)?;
+    /// The Schnorr signature (s || e format)
+    async fn sign_transaction_internal(&self, tx_json: &str, tx_type: u32) -> Result<Signature> {
+        let tx_info: Value = serde_json::from_str(tx_json)?;
+        Ok(self.sign_tx_info(tx_info, tx_type).await?.signature)
+    }
+
+    /// Lighter chain ID the transaction hash commits to (304 for mainnet, 300 otherwise)
+    pub fn lighter_chain_id(&self) -> u32 {
+        if self.base_url.contains("mainnet") { 304 } else { 300 }
+    }
+
+    /// Computes the 40-byte digest that gets signed for a transaction.
+    ///
+    /// This is the exact message passed to the Schnorr signer, for logging or for comparing
+    /// against the Go SDK when a signature is rejected. Supports every transaction type the
+    /// client signs; the `Sig` field of `tx_json` is ignored.
+    ///
+    /// # Arguments
+    /// * `tx_json` - JSON string representation of the transaction
+    /// * `tx_type` - Transaction type code (e.g., 14 for CREATE_ORDER)
+    pub fn compute_tx_hash(&self, tx_json: &str, tx_type: u32) -> Result<[u8; 40]> {
+        Self::transaction_hash(tx_json, tx_type, self.lighter_chain_id())
+    }
 
-        // Determine chain ID based on base URL
-        // Mainnet: 304, Testnet: 300
-        let lighter_chain_id = if self.base_url.contains("mainnet") { 304u32 } else { 300u32 };
-        let nonce = tx_value["Nonce"].as_i64().unwrap_or(0);
-        let expired_at = tx_value["ExpiredAt"].as_i64().unwrap_or(0);
-        let account_index = tx_value["AccountIndex"].as_i64().unwrap_or(0);
-        let api_key_index = tx_value["ApiKeyIndex"].as_u64().unwrap_or(0) as u32;
+    /// Signs `tx_info` (with an empty `Sig`) and fills in the signature.
+    ///
+    /// Every transaction the client signs goes through here, so each one is reported to
+    /// the audit sink, if one is configured.
+    async fn sign_tx_info(&self, mut tx_info: Value, tx_type: u32) -> Result<SignedTx> {
+        let tx_json = serde_json::to_string(&tx_info)?;
+        let tx_hash = self.compute_tx_hash(&tx_json, tx_type)?;
+        let signature = self.signer().sign(&tx_hash).await?;
+        tx_info["Sig"] = json!(signature.to_base64());
+
+        let signed = SignedTx {
+            tx_type,
+            tx_info,
+            tx_hash,
+            signature,
+        };
+        if let Some(sink) = &self.audit_sink {
+            sink.record(&AuditRecord::new(self.account_index, self.api_key_index, &signed)?)?;
+        }
+        Ok(signed)
+    }
 
+    /// Computes the 40-byte Poseidon2 hash that gets signed for a transaction.
+    ///
+    /// Fields are read from the transaction JSON and converted to Goldilocks elements
+    /// in the same order as lighter-go before hashing.
+    fn transaction_hash(tx_json: &str, tx_type: u32, lighter_chain_id: u32) -> Result<[u8; 40]> {
         use poseidon_hash::Goldilocks;
+        use tx_fields::{array_field, element, usdc_limbs, str_field};
 
-        // Helper function to convert signed i64 to Goldilocks field element
-        // Handles sign extension properly for negative values
-        let to_goldi_i64 = |val: i64| Goldilocks::from_i64(val);
+        // Parse the transaction JSON to extract fields
+        let tx: Value = serde_json::from_str(tx_json)?;
 
-        let elements = match tx_type {
+        // Transfer and Withdraw sign FromAccountIndex in place of AccountIndex
+        let account_key = match tx_type {
+            12 | 13 => "FromAccountIndex",
+            _ => "AccountIndex",
+        };
+        let mut elements = vec![
+            Goldilocks::from_canonical_u64(lighter_chain_id as u64),
+            Goldilocks::from_canonical_u64(tx_type as u64),
+            element::<i64>(&tx, "Nonce")?,
+            element::<i64>(&tx, "ExpiredAt")?,
+            element::<i64>(&tx, account_key)?,
+            element::<u8>(&tx, "ApiKeyIndex")?,
+        ];
+
+        match tx_type {
             14 => {
                 // CREATE_ORDER: 16 elements
-                let market_index = tx_value["MarketIndex"].as_u64().unwrap_or(0) as u32;
-                let client_order_index = tx_value["ClientOrderIndex"].as_i64().unwrap_or(0);
-                let base_amount = tx_value["BaseAmount"].as_i64().unwrap_or(0);
-                let price = tx_value["Price"]
-                    .as_u64()
-                    .or_else(|| tx_value["Price"].as_i64().map(|v| v as u64))
-                    .unwrap_or(0) as u32;
-                let is_ask = tx_value["IsAsk"]
-                    .as_u64()
-                    .or_else(|| tx_value["IsAsk"].as_i64().map(|v| v as u64))
-                    .unwrap_or(0) as u32;
-                let order_type = tx_value["Type"]
-                    .as_u64()
-                    .or_else(|| tx_value["Type"].as_i64().map(|v| v as u64))
-                    .unwrap_or(0) as u32;
-                let time_in_force = tx_value["TimeInForce"]
-                    .as_u64()
-                    .or_else(|| tx_value["TimeInForce"].as_i64().map(|v| v as u64))
-                    .unwrap_or(0) as u32;
-                let reduce_only = tx_value["ReduceOnly"]
-                    .as_u64()
-                    .or_else(|| tx_value["ReduceOnly"].as_i64().map(|v| v as u64))
-                    .unwrap_or(0) as u32;
-                let trigger_price = tx_value["TriggerPrice"]
-                    .as_u64()
-                    .or_else(|| tx_value["TriggerPrice"].as_i64().map(|v| v as u64))
-                    .unwrap_or(0) as u32;
-                let order_expiry = tx_value["OrderExpiry"].as_i64().unwrap_or(0);
-
-                vec![
-                    Goldilocks::from_canonical_u64(lighter_chain_id as u64),
-                    Goldilocks::from_canonical_u64(tx_type as u64),
-                    to_goldi_i64(nonce),
-                    to_goldi_i64(expired_at),
-                    to_goldi_i64(account_index),
-                    Goldilocks::from_canonical_u64(api_key_index as u64),
-                    Goldilocks::from_canonical_u64(market_index as u64),
-                    to_goldi_i64(client_order_index),
-                    to_goldi_i64(base_amount),
-                    Goldilocks::from_canonical_u64(price as u64),
-                    Goldilocks::from_canonical_u64(is_ask as u64),
-                    Goldilocks::from_canonical_u64(order_type as u64),
-                    Goldilocks::from_canonical_u64(time_in_force as u64),
-                    Goldilocks::from_canonical_u64(reduce_only as u64),
-                    Goldilocks::from_canonical_u64(trigger_price as u64),
-                    to_goldi_i64(order_expiry),
-                ]
+                elements.extend(order_elements(&tx)?);
             }
             15 => {
                 // CANCEL_ORDER: 8 elements
-                let market_index = tx_value["MarketIndex"].as_u64().unwrap_or(0) as u32;
-                let order_index = tx_value["Index"].as_i64().unwrap_or(0);
-
-                vec![
-                    Goldilocks::from_canonical_u64(lighter_chain_id as u64),
-                    Goldilocks::from_canonical_u64(tx_type as u64),
-                    to_goldi_i64(nonce),
-                    to_goldi_i64(expired_at),
-                    to_goldi_i64(account_index),
-                    Goldilocks::from_canonical_u64(api_key_index as u64),
-                    Goldilocks::from_canonical_u64(market_index as u64),
-                    to_goldi_i64(order_index),
-                ]
+                elements.push(element::<u32>(&tx, "MarketIndex")?);
+                elements.push(element::<i64>(&tx, "Index")?);
             }
             16 => {
                 // CANCEL_ALL_ORDERS: 8 elements
-                let time_in_force = tx_value["TimeInForce"]
-                    .as_u64()
-                    .or_else(|| tx_value["TimeInForce"].as_i64().map(|v| v as u64))
-                    .unwrap_or(0) as u32;
-                let time = tx_value["Time"].as_i64().unwrap_or(0);
-
-                vec![
-                    Goldilocks::from_canonical_u64(lighter_chain_id as u64),
-                    Goldilocks::from_canonical_u64(tx_type as u64),
-                    to_goldi_i64(nonce),
-                    to_goldi_i64(expired_at),
-                    to_goldi_i64(account_index),
-                    Goldilocks::from_canonical_u64(api_key_index as u64),
-                    Goldilocks::from_canonical_u64(time_in_force as u64),
-                    to_goldi_i64(time),
-                ]
+                elements.push(element::<u32>(&tx, "TimeInForce")?);
+                elements.push(element::<i64>(&tx, "Time")?);
             }
             8 => {
-                // CHANGE_PUB_KEY: needs pubkey parsing (ArrayFromCanonicalLittleEndianBytes)
-                let pubkey_hex = tx_value["PubKey"].as_str().unwrap_or("");
-                let pubkey_bytes = hex::decode(pubkey_hex).map_err(|e| ApiError::Api(format!("Invalid PubKey hex: {}", e)))?;
+                // CHANGE_PUB_KEY: public key as 5 little-endian limbs (ArrayFromCanonicalLittleEndianBytes)
+                let pubkey_bytes = hex::decode(str_field(&tx, "PubKey")?)
+                    .map_err(|e| ApiError::InvalidTxField(format!("PubKey is not hex: {}", e)))?;
                 if pubkey_bytes.len() != 40 {
-                    return Err(ApiError::Api("PubKey must be 40 bytes".to_string()));
-                }
-                // Convert 40-byte public key to 5 Goldilocks elements (8 bytes per element)
-                let mut pubkey_elems = Vec::new();
-                for i in 0..5 {
-                    let chunk = &pubkey_bytes[i * 8..(i + 1) * 8];
-                    let val = u64::from_le_bytes(chunk.try_into().unwrap());
-                    pubkey_elems.push(Goldilocks::from_canonical_u64(val));
+                    return Err(ApiError::InvalidTxField("PubKey must be 40 bytes".to_string()));
                 }
-
-                let mut elems = vec![
-                    Goldilocks::from_canonical_u64(lighter_chain_id as u64),
-                    Goldilocks::from_canonical_u64(tx_type as u64),
-                    to_goldi_i64(nonce),
-                    to_goldi_i64(expired_at),
-                    to_goldi_i64(account_index),
-                    Goldilocks::from_canonical_u64(api_key_index as u64),
-                ];
-                elems.extend(pubkey_elems);
-                elems
+                elements.extend(poseidon_hash::array_from_le_bytes(&pubkey_bytes));
             }
             20 => {
                 // UPDATE_LEVERAGE: 9 elements
-                // Order: lighterChainId, txType, nonce, expiredAt, accountIndex, apiKeyIndex, marketIndex, initialMarginFraction, marginMode
-                let market_index = tx_value["MarketIndex"]
-                    .as_u64()
-                    .or_else(|| tx_value["MarketIndex"].as_i64().map(|v| v as u64))
-                    .unwrap_or(0) as u32;
-                let initial_margin_fraction = tx_value["InitialMarginFraction"]
-                    .as_u64()
-                    .or_else(|| tx_value["InitialMarginFraction"].as_i64().map(|v| v as u64))
-                    .unwrap_or(0) as u32;
-                let margin_mode = tx_value["MarginMode"]
-                    .as_u64()
-                    .or_else(|| tx_value["MarginMode"].as_i64().map(|v| v as u64))
-                    .unwrap_or(0) as u32;
-
-                vec![
-                    Goldilocks::from_canonical_u64(lighter_chain_id as u64),
-                    Goldilocks::from_canonical_u64(tx_type as u64),
-                    to_goldi_i64(nonce),
-                    to_goldi_i64(expired_at),
-                    to_goldi_i64(account_index),
-                    Goldilocks::from_canonical_u64(api_key_index as u64),
-                    Goldilocks::from_canonical_u64(market_index as u64),
-                    Goldilocks::from_canonical_u64(initial_margin_fraction as u64),
-                    Goldilocks::from_canonical_u64(margin_mode as u64),
-                ]
+                elements.push(element::<u32>(&tx, "MarketIndex")?);
+                elements.push(element::<u32>(&tx, "InitialMarginFraction")?);
+                elements.push(element::<u32>(&tx, "MarginMode")?);
             }
             9 => {
-                // CREATE_SUB_ACCOUNT: 6 elements
-                vec![
-                    Goldilocks::from_canonical_u64(lighter_chain_id as u64),
-                    Goldilocks::from_canonical_u64(tx_type as u64),
-                    to_goldi_i64(nonce),
-                    to_goldi_i64(expired_at),
-                    to_goldi_i64(account_index),
-                    Goldilocks::from_canonical_u64(api_key_index as u64),
-                ]
+                // CREATE_SUB_ACCOUNT: 6 elements, common fields only
             }
             10 => {
                 // CREATE_PUBLIC_POOL: 9 elements
-                let operator_fee = tx_value["OperatorFee"].as_i64().unwrap_or(0);
-                let initial_total_shares = tx_value["InitialTotalShares"].as_i64().unwrap_or(0);
-                let min_operator_share_rate = tx_value["MinOperatorShareRate"].as_i64().unwrap_or(0);
-
-                vec![
-                    Goldilocks::from_canonical_u64(lighter_chain_id as u64),
-                    Goldilocks::from_canonical_u64(tx_type as u64),
-                    to_goldi_i64(nonce),
-                    to_goldi_i64(expired_at),
-                    to_goldi_i64(account_index),
-                    Goldilocks::from_canonical_u64(api_key_index as u64),
-                    to_goldi_i64(operator_fee),
-                    to_goldi_i64(initial_total_shares),
-                    to_goldi_i64(min_operator_share_rate),
-                ]
+                elements.push(element::<i64>(&tx, "OperatorFee")?);
+                elements.push(element::<i64>(&tx, "InitialTotalShares")?);
+                elements.push(element::<i64>(&tx, "MinOperatorShareRate")?);
             }
             11 => {
-                // UPDATE_PUBLIC_POOL: 9 elements
-                let public_pool_index = tx_value["PublicPoolIndex"].as_i64().unwrap_or(0);
-                let status = tx_value["Status"]
-                    .as_u64()
-                    .or_else(|| tx_value["Status"].as_i64().map(|v| v as u64))
-                    .unwrap_or(0) as u32;
-                let operator_fee = tx_value["OperatorFee"].as_i64().unwrap_or(0);
-                let min_operator_share_rate = tx_value["MinOperatorShareRate"].as_i64().unwrap_or(0);
-
-                vec![
-                    Goldilocks::from_canonical_u64(lighter_chain_id as u64),
-                    Goldilocks::from_canonical_u64(tx_type as u64),
-                    to_goldi_i64(nonce),
-                    to_goldi_i64(expired_at),
-                    to_goldi_i64(account_index),
-                    Goldilocks::from_canonical_u64(api_key_index as u64),
-                    to_goldi_i64(public_pool_index),
-                    Goldilocks::from_canonical_u64(status as u64),
-                    to_goldi_i64(operator_fee),
-                    to_goldi_i64(min_operator_share_rate),
-                ]
+                // UPDATE_PUBLIC_POOL: 10 elements
+                elements.push(element::<i64>(&tx, "PublicPoolIndex")?);
+                elements.push(element::<u32>(&tx, "Status")?);
+                elements.push(element::<i64>(&tx, "OperatorFee")?);
+                elements.push(element::<i64>(&tx, "MinOperatorShareRate")?);
             }
             12 => {
-                // TRANSFER: 11 elements
-                // Note: Transfer uses FromAccountIndex, not AccountIndex
-                let from_account_index = tx_value["FromAccountIndex"].as_i64().unwrap_or(account_index);
-                let to_account_index = tx_value["ToAccountIndex"].as_i64().unwrap_or(0);
-                let usdc_amount = tx_value["USDCAmount"].as_i64().unwrap_or(0);
-                let fee = tx_value["Fee"].as_i64().unwrap_or(0);
-
-                // USDCAmount and Fee are split into two u64 elements each (low 32 bits, high 32 bits)
-                vec![
-                    Goldilocks::from_canonical_u64(lighter_chain_id as u64),
-                    Goldilocks::from_canonical_u64(tx_type as u64),
-                    to_goldi_i64(nonce),
-                    to_goldi_i64(expired_at),
-                    to_goldi_i64(from_account_index),
-                    Goldilocks::from_canonical_u64(api_key_index as u64),
-                    to_goldi_i64(to_account_index),
-                    Goldilocks::from_canonical_u64((usdc_amount as u64 & 0xFFFFFFFF) as u64),
-                    Goldilocks::from_canonical_u64((usdc_amount as u64 >> 32) as u64),
-                    Goldilocks::from_canonical_u64((fee as u64 & 0xFFFFFFFF) as u64),
-                    Goldilocks::from_canonical_u64((fee as u64 >> 32) as u64),
-                ]
+                // TRANSFER: 11 elements; USDCAmount and Fee are split into low and high 32 bits
+                elements.push(element::<i64>(&tx, "ToAccountIndex")?);
+                elements.extend(usdc_limbs(&tx, "USDCAmount")?);
+                elements.extend(usdc_limbs(&tx, "Fee")?);
             }
             13 => {
-                // WITHDRAW: 8 elements
-                // Note: Withdraw uses FromAccountIndex, not AccountIndex
-                let from_account_index = tx_value["FromAccountIndex"].as_i64().unwrap_or(account_index);
-                let usdc_amount = tx_value["USDCAmount"].as_u64().unwrap_or(0);
-
-                // USDCAmount is split into two u64 elements (low 32 bits, high 32 bits)
-                vec![
-                    Goldilocks::from_canonical_u64(lighter_chain_id as u64),
-                    Goldilocks::from_canonical_u64(tx_type as u64),
-                    to_goldi_i64(nonce),
-                    to_goldi_i64(expired_at),
-                    to_goldi_i64(from_account_index),
-                    Goldilocks::from_canonical_u64(api_key_index as u64),
-                    Goldilocks::from_canonical_u64(usdc_amount & 0xFFFFFFFF),
-                    Goldilocks::from_canonical_u64(usdc_amount >> 32),
-                ]
+                // WITHDRAW: 8 elements; USDCAmount is split into low and high 32 bits
+                elements.extend(usdc_limbs(&tx, "USDCAmount")?);
             }
             17 => {
                 // MODIFY_ORDER: 11 elements
-                let market_index = tx_value["MarketIndex"].as_u64().unwrap_or(0) as u32;
-                let order_index = tx_value["Index"].as_i64().unwrap_or(0);
-                let base_amount = tx_value["BaseAmount"].as_i64().unwrap_or(0);
-                let price = tx_value["Price"]
-                    .as_u64()
-                    .or_else(|| tx_value["Price"].as_i64().map(|v| v as u64))
-                    .unwrap_or(0) as u32;
-                let trigger_price = tx_value["TriggerPrice"]
-                    .as_u64()
-                    .or_else(|| tx_value["TriggerPrice"].as_i64().map(|v| v as u64))
-                    .unwrap_or(0) as u32;
-
-                vec![
-                    Goldilocks::from_canonical_u64(lighter_chain_id as u64),
-                    Goldilocks::from_canonical_u64(tx_type as u64),
-                    to_goldi_i64(nonce),
-                    to_goldi_i64(expired_at),
-                    to_goldi_i64(account_index),
-                    Goldilocks::from_canonical_u64(api_key_index as u64),
-                    Goldilocks::from_canonical_u64(market_index as u64),
-                    to_goldi_i64(order_index),
-                    to_goldi_i64(base_amount),
-                    Goldilocks::from_canonical_u64(price as u64),
-                    Goldilocks::from_canonical_u64(trigger_price as u64),
-                ]
+                elements.push(element::<u32>(&tx, "MarketIndex")?);
+                elements.push(element::<i64>(&tx, "Index")?);
+                elements.push(element::<BaseAmount>(&tx, "BaseAmount")?);
+                elements.push(element::<Price>(&tx, "Price")?);
+                elements.push(element::<Price>(&tx, "TriggerPrice")?);
             }
-            18 => {
-                // MINT_SHARES: 8 elements
-                let public_pool_index = tx_value["PublicPoolIndex"].as_i64().unwrap_or(0);
-                let share_amount = tx_value["ShareAmount"].as_i64().unwrap_or(0);
-
-                vec![
-                    Goldilocks::from_canonical_u64(lighter_chain_id as u64),
-                    Goldilocks::from_canonical_u64(tx_type as u64),
-                    to_goldi_i64(nonce),
-                    to_goldi_i64(expired_at),
-                    to_goldi_i64(account_index),
-                    Goldilocks::from_canonical_u64(api_key_index as u64),
-                    to_goldi_i64(public_pool_index),
-                    to_goldi_i64(share_amount),
-                ]
-            }
-            19 => {
-                // BURN_SHARES: 8 elements
-                let public_pool_index = tx_value["PublicPoolIndex"].as_i64().unwrap_or(0);
-                let share_amount = tx_value["ShareAmount"].as_i64().unwrap_or(0);
-
-                vec![
-                    Goldilocks::from_canonical_u64(lighter_chain_id as u64),
-                    Goldilocks::from_canonical_u64(tx_type as u64),
-                    to_goldi_i64(nonce),
-                    to_goldi_i64(expired_at),
-                    to_goldi_i64(account_index),
-                    Goldilocks::from_canonical_u64(api_key_index as u64),
-                    to_goldi_i64(public_pool_index),
-                    to_goldi_i64(share_amount),
-                ]
+            18 | 19 => {
+                // MINT_SHARES / BURN_SHARES: 8 elements
+                elements.push(element::<i64>(&tx, "PublicPoolIndex")?);
+                elements.push(element::<i64>(&tx, "ShareAmount")?);
             }
             28 => {
                 // CREATE_GROUPED_ORDERS: variable elements
                 // Matches Go SDK: HashNoPad for each order, then HashNToOne to aggregate
-                use poseidon_hash::{hash_no_pad, hash_n_to_one, empty_hash_out};
-                
-                let grouping_type = tx_value["GroupingType"]
-                    .as_u64()
-                    .or_else(|| tx_value["GroupingType"].as_i64().map(|v| v as u64))
-                    .unwrap_or(0) as u32;
-                
-                let orders_array = tx_value["Orders"].as_array().cloned().unwrap_or_default();
-                
-                let mut elems = vec![
-                    Goldilocks::from_canonical_u64(lighter_chain_id as u64),
-                    Goldilocks::from_canonical_u64(tx_type as u64),
-                    to_goldi_i64(nonce),
-                    to_goldi_i64(expired_at),
-                    to_goldi_i64(account_index),
-                    Goldilocks::from_canonical_u64(api_key_index as u64),
-                    Goldilocks::from_canonical_u64(grouping_type as u64),
-                ];
-
-                // Hash each order individually using HashNoPad, then aggregate
-                let mut aggregated_order_hash = empty_hash_out();
-                for (index, order) in orders_array.iter().enumerate() {
-                    let market_index = order["MarketIndex"].as_u64().unwrap_or(0) as u32;
-                    let client_order_index = order["ClientOrderIndex"].as_i64().unwrap_or(0);
-                    let base_amount = order["BaseAmount"].as_i64().unwrap_or(0);
-                    let price = order["Price"]
-                        .as_u64()
-                        .or_else(|| order["Price"].as_i64().map(|v| v as u64))
-                        .unwrap_or(0) as u32;
-                    let is_ask = order["IsAsk"]
-                        .as_u64()
-                        .or_else(|| order["IsAsk"].as_i64().map(|v| v as u64))
-                        .unwrap_or(0) as u32;
-                    let order_type = order["Type"]
-                        .as_u64()
-                        .or_else(|| order["Type"].as_i64().map(|v| v as u64))
-                        .unwrap_or(0) as u32;
-                    let time_in_force = order["TimeInForce"]
-                        .as_u64()
-                        .or_else(|| order["TimeInForce"].as_i64().map(|v| v as u64))
-                        .unwrap_or(0) as u32;
-                    let reduce_only = order["ReduceOnly"]
-                        .as_u64()
-                        .or_else(|| order["ReduceOnly"].as_i64().map(|v| v as u64))
-                        .unwrap_or(0) as u32;
-                    let trigger_price = order["TriggerPrice"]
-                        .as_u64()
-                        .or_else(|| order["TriggerPrice"].as_i64().map(|v| v as u64))
-                        .unwrap_or(0) as u32;
-                    let order_expiry = order["OrderExpiry"].as_i64().unwrap_or(0);
+                use poseidon_hash::{hash_n_to_one, hash_no_pad, empty_hash_out};
+
+                elements.push(element::<u32>(&tx, "GroupingType")?);
 
+                let mut aggregated_order_hash = empty_hash_out();
+                for (index, order) in array_field(&tx, "Orders")?.iter().enumerate() {
                     // Hash this order's fields (10 elements → 4 elements)
-                    let order_fields = vec![
-                        Goldilocks::from_canonical_u64(market_index as u64),
-                        to_goldi_i64(client_order_index),
-                        to_goldi_i64(base_amount),
-                        Goldilocks::from_canonical_u64(price as u64),
-                        Goldilocks::from_canonical_u64(is_ask as u64),
-                        Goldilocks::from_canonical_u64(order_type as u64),
-                        Goldilocks::from_canonical_u64(time_in_force as u64),
-                        Goldilocks::from_canonical_u64(reduce_only as u64),
-                        Goldilocks::from_canonical_u64(trigger_price as u64),
-                        to_goldi_i64(order_expiry),
-                    ];
-                    
-                    let order_hash = hash_no_pad(&order_fields);
-                    
-                    if index == 0 {
-                        aggregated_order_hash = order_hash;
+                    let order_hash = hash_no_pad(&order_elements(order)?);
+                    aggregated_order_hash = if index == 0 {
+                        order_hash
                     } else {
-                        aggregated_order_hash = hash_n_to_one(&[aggregated_order_hash, order_hash]);
-                    }
+                        hash_n_to_one(&[aggregated_order_hash, order_hash])
+                    };
                 }
-
-                // Append aggregated hash (4 elements) to main elements
-                elems.extend_from_slice(&aggregated_order_hash);
-
-                elems
+                elements.extend_from_slice(&aggregated_order_hash);
             }
             29 => {
-                // UPDATE_MARGIN: 10 elements
-                let market_index = tx_value["MarketIndex"]
-                    .as_u64()
-                    .or_else(|| tx_value["MarketIndex"].as_i64().map(|v| v as u64))
-                    .unwrap_or(0) as u32;
-                let usdc_amount = tx_value["USDCAmount"].as_i64().unwrap_or(0);
-                let direction = tx_value["Direction"]
-                    .as_u64()
-                    .or_else(|| tx_value["Direction"].as_i64().map(|v| v as u64))
-                    .unwrap_or(0) as u32;
-
-                // USDCAmount is split into two u64 elements (low 32 bits, high 32 bits)
-                vec![
-                    Goldilocks::from_canonical_u64(lighter_chain_id as u64),
-                    Goldilocks::from_canonical_u64(tx_type as u64),
-                    to_goldi_i64(nonce),
-                    to_goldi_i64(expired_at),
-                    to_goldi_i64(account_index),
-                    Goldilocks::from_canonical_u64(api_key_index as u64),
-                    Goldilocks::from_canonical_u64(market_index as u64),
-                    Goldilocks::from_canonical_u64((usdc_amount as u64 & 0xFFFFFFFF) as u64),
-                    Goldilocks::from_canonical_u64((usdc_amount as u64 >> 32) as u64),
-                    Goldilocks::from_canonical_u64(direction as u64),
-                ]
+                // UPDATE_MARGIN: 10 elements; USDCAmount is split into low and high 32 bits
+                elements.push(element::<u32>(&tx, "MarketIndex")?);
+                elements.extend(usdc_limbs(&tx, "USDCAmount")?);
+                elements.push(element::<u32>(&tx, "Direction")?);
             }
             _ => {
                 return Err(ApiError::Api(format!("Unsupported transaction type: {}", tx_type)));
             }
-        };
-        
+        }
+
         // Hash the Goldilocks field elements using Poseidon2 to produce a 40-byte hash
         use poseidon_hash::hash_to_quintic_extension;
         let hash_result = hash_to_quintic_extension(&elements);
-        let message_array = hash_result.to_bytes_le();
-
-        // Sign the transaction hash using Schnorr signature
-        self.key_manager.sign(&message_array).map_err(|e| ApiError::Signer(e))
+        Ok(hash_result.to_bytes_le())
     }
 
     // ============================================================================
@@ -1805,22 +1534,17 @@ impl LighterClient {
     // These match Go SDK's Sign* functions
     // ============================================================================
 
-    /// Sign a create order transaction and return JSON (doesn't send to API)
+    /// Sign a create order transaction and return it with its hash (doesn't send to API)
     pub async fn sign_create_order_with_nonce(
         &self,
         order: CreateOrderRequest,
         nonce: Option<i64>,
-    ) -> Result<Value> {
+    ) -> Result<SignedTx> {
         let nonce = self.get_nonce_or_use(nonce).await?;
         let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
         let expired_at = now + 599_000; // 10 minutes - 1 second (in milliseconds)
         
-        let order_expiry = if order.trigger_price == 0 && order.order_type == 0 {
-            // Default expiry for limit orders: 28 days
-            now + (28 * 24 * 60 * 60 * 1000)
-        } else {
-            0
-        };
+        let order_expiry = self.expiry_policy.order_expiry(&order, now);
 
         let tx_info = json!({
             "AccountIndex": self.account_index,
@@ -1840,23 +1564,18 @@ impl LighterClient {
             "Sig": ""
         });
         
-        let tx_json = serde_json::to_string(&tx_info)?;
-        let signature = self.sign_transaction(&tx_json)?;
-        
-        let mut final_tx_info = tx_info;
-        let sig_base64 = base64::engine::general_purpose::STANDARD.encode(&signature);
-        final_tx_info["Sig"] = json!(sig_base64);
-        
-        Ok(final_tx_info)
+        let signed = self.sign_tx_info(tx_info, 14).await?; // TX_TYPE_CREATE_ORDER
+
+        Ok(signed)
     }
 
-    /// Sign a cancel order transaction and return JSON (doesn't send to API)
+    /// Sign a cancel order transaction and return it with its hash (doesn't send to API)
     pub async fn sign_cancel_order_with_nonce(
         &self,
         market_index: u8,
         order_index: i64,
         nonce: Option<i64>,
-    ) -> Result<Value> {
+    ) -> Result<SignedTx> {
         let nonce = self.get_nonce_or_use(nonce).await?;
         let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
         let expired_at = now + 599_000;
@@ -1871,22 +1590,18 @@ impl LighterClient {
             "Sig": ""
         });
 
-        let tx_json = serde_json::to_string(&tx_info)?;
-        let signature = self.sign_transaction_with_type(&tx_json, 15)?; // TX_TYPE_CANCEL_ORDER
-
-        let mut final_tx_info = tx_info;
-        final_tx_info["Sig"] = json!(base64::engine::general_purpose::STANDARD.encode(&signature));
+        let signed = self.sign_tx_info(tx_info, 15).await?; // TX_TYPE_CANCEL_ORDER
 
-        Ok(final_tx_info)
+        Ok(signed)
     }
 
-    /// Sign a cancel all orders transaction and return JSON (doesn't send to API)
+    /// Sign a cancel all orders transaction and return it with its hash (doesn't send to API)
     pub async fn sign_cancel_all_orders_with_nonce(
         &self,
         time_in_force: u8,
         time: i64,
         nonce: Option<i64>,
-    ) -> Result<Value> {
+    ) -> Result<SignedTx> {
         let nonce = self.get_nonce_or_use(nonce).await?;
         let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
         let expired_at = now + 599_000;
@@ -1901,21 +1616,17 @@ impl LighterClient {
             "Sig": ""
         });
 
-        let tx_json = serde_json::to_string(&tx_info)?;
-        let signature = self.sign_transaction_with_type(&tx_json, 16)?; // TX_TYPE_CANCEL_ALL_ORDERS
-
-        let mut final_tx_info = tx_info;
-        final_tx_info["Sig"] = json!(base64::engine::general_purpose::STANDARD.encode(&signature));
+        let signed = self.sign_tx_info(tx_info, 16).await?; // TX_TYPE_CANCEL_ALL_ORDERS
 
-        Ok(final_tx_info)
+        Ok(signed)
     }
 
-    /// Sign a withdraw transaction and return JSON (doesn't send to API)
+    /// Sign a withdraw transaction and return it with its hash (doesn't send to API)
     pub async fn sign_withdraw_with_nonce(
         &self,
-        usdc_amount: u64,
+        usdc_amount: UsdcAmount,
         nonce: Option<i64>,
-    ) -> Result<Value> {
+    ) -> Result<SignedTx> {
         let nonce = self.get_nonce_or_use(nonce).await?;
         let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
         let expired_at = now + 599_000;
@@ -1929,24 +1640,20 @@ impl LighterClient {
             "Sig": ""
         });
 
-        let tx_json = serde_json::to_string(&tx_info)?;
-        let signature = self.sign_transaction_with_type(&tx_json, 13)?; // TX_TYPE_WITHDRAW
-
-        let mut final_tx_info = tx_info;
-        final_tx_info["Sig"] = json!(base64::engine::general_purpose::STANDARD.encode(&signature));
+        let signed = self.sign_tx_info(tx_info, 13).await?; // TX_TYPE_WITHDRAW
 
-        Ok(final_tx_info)
+        Ok(signed)
     }
 
-    /// Sign a transfer transaction and return JSON with MessageToSign (doesn't send to API)
+    /// Sign a transfer transaction and return it with its hash and MessageToSign (doesn't send to API)
     pub async fn sign_transfer_with_nonce(
         &self,
         to_account_index: i64,
-        usdc_amount: i64,
-        fee: i64,
+        usdc_amount: UsdcAmount,
+        fee: UsdcAmount,
         memo: [u8; 32],
         nonce: Option<i64>,
-    ) -> Result<Value> {
+    ) -> Result<SignedTx> {
         let nonce = self.get_nonce_or_use(nonce).await?;
         let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
         let expired_at = now + 599_000;
@@ -1963,26 +1670,22 @@ impl LighterClient {
             "Sig": ""
         });
 
-        let tx_json = serde_json::to_string(&tx_info)?;
-        let signature = self.sign_transaction_with_type(&tx_json, 12)?; // TX_TYPE_TRANSFER
-
-        let mut final_tx_info = tx_info;
-        final_tx_info["Sig"] = json!(base64::engine::general_purpose::STANDARD.encode(&signature));
+        let mut signed = self.sign_tx_info(tx_info, 12).await?; // TX_TYPE_TRANSFER
 
         // Add MessageToSign field (like Go SDK does)
         // For transfer, the L1 signature body is the memo as a string
         let message_to_sign = String::from_utf8_lossy(&memo).to_string();
-        final_tx_info["MessageToSign"] = json!(message_to_sign);
+        signed.tx_info["MessageToSign"] = json!(message_to_sign);
 
-        Ok(final_tx_info)
+        Ok(signed)
     }
 
-    /// Sign a change pub key transaction and return JSON with MessageToSign (doesn't send to API)
+    /// Sign a change pub key transaction and return it with its hash and MessageToSign (doesn't send to API)
     pub async fn sign_change_pub_key_with_nonce(
         &self,
         new_public_key: [u8; 40],
         nonce: Option<i64>,
-    ) -> Result<Value> {
+    ) -> Result<SignedTx> {
         let nonce = self.get_nonce_or_use(nonce).await?;
         let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
         let expired_at = now + 599_000;
@@ -1996,33 +1699,23 @@ impl LighterClient {
             "Sig": ""
         });
 
-        let tx_json = serde_json::to_string(&tx_info)?;
-        let signature = self.sign_transaction_with_type(&tx_json, 8)?; // TX_TYPE_CHANGE_PUB_KEY
-
-        let mut final_tx_info = tx_info;
-        final_tx_info["Sig"] = json!(base64::engine::general_purpose::STANDARD.encode(&signature));
+        let mut signed = self.sign_tx_info(tx_info, 8).await?; // TX_TYPE_CHANGE_PUB_KEY
 
-        // Add MessageToSign field (like Go SDK does)
-        // For change pub key, the L1 signature body is a formatted string
-        let message_to_sign = format!(
-            "ChangePubKey\nAccountIndex: {}\nApiKeyIndex: {}\nPubKey: {}",
-            self.account_index,
-            self.api_key_index,
-            hex::encode(new_public_key)
-        );
-        final_tx_info["MessageToSign"] = json!(message_to_sign);
+        // Add MessageToSign field (like Go SDK does): the body the L1 key signs for L1Sig
+        let message_to_sign = l1::change_pub_key_message(&new_public_key, nonce, self.account_index, self.api_key_index);
+        signed.tx_info["MessageToSign"] = json!(message_to_sign);
 
-        Ok(final_tx_info)
+        Ok(signed)
     }
 
-    /// Sign an update leverage transaction and return JSON (doesn't send to API)
+    /// Sign an update leverage transaction and return it with its hash (doesn't send to API)
     pub async fn sign_update_leverage_with_nonce(
         &self,
         market_index: u8,
         initial_margin_fraction: u16,
-        margin_mode: u8,
+        margin_mode: MarginMode,
         nonce: Option<i64>,
-    ) -> Result<Value> {
+    ) -> Result<SignedTx> {
         let nonce = self.get_nonce_or_use(nonce).await?;
         let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
         let expired_at = now + 599_000;
@@ -2038,20 +1731,16 @@ impl LighterClient {
             "Sig": ""
         });
 
-        let tx_json = serde_json::to_string(&tx_info)?;
-        let signature = self.sign_transaction_with_type(&tx_json, 20)?; // TX_TYPE_UPDATE_LEVERAGE
-
-        let mut final_tx_info = tx_info;
-        final_tx_info["Sig"] = json!(base64::engine::general_purpose::STANDARD.encode(&signature));
+        let signed = self.sign_tx_info(tx_info, 20).await?; // TX_TYPE_UPDATE_LEVERAGE
 
-        Ok(final_tx_info)
+        Ok(signed)
     }
 
-    /// Sign a create sub account transaction and return JSON (doesn't send to API)
+    /// Sign a create sub account transaction and return it with its hash (doesn't send to API)
     pub async fn sign_create_sub_account_with_nonce(
         &self,
         nonce: Option<i64>,
-    ) -> Result<Value> {
+    ) -> Result<SignedTx> {
         let nonce = self.get_nonce_or_use(nonce).await?;
         let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
         let expired_at = now + 599_000;
@@ -2064,25 +1753,23 @@ impl LighterClient {
             "Sig": ""
         });
 
-        let tx_json = serde_json::to_string(&tx_info)?;
-        let signature = self.sign_transaction_with_type(&tx_json, 9)?; // TX_TYPE_CREATE_SUB_ACCOUNT
-
-        let mut final_tx_info = tx_info;
-        final_tx_info["Sig"] = json!(base64::engine::general_purpose::STANDARD.encode(&signature));
+        let signed = self.sign_tx_info(tx_info, 9).await?; // TX_TYPE_CREATE_SUB_ACCOUNT
 
-        Ok(final_tx_info)
+        Ok(signed)
     }
 
-    /// Sign a modify order transaction and return JSON (doesn't send to API)
+    /// Sign a modify order transaction and return it with its hash (doesn't send to API)
+    /// A price or trigger price above `Price::MAX` fails with `ApiError::InvalidOrder`
     pub async fn sign_modify_order_with_nonce(
         &self,
         market_index: u8,
         order_index: i64,
-        base_amount: i64,
-        price: u32,
-        trigger_price: u32,
+        base_amount: BaseAmount,
+        price: Price,
+        trigger_price: Price,
         nonce: Option<i64>,
-    ) -> Result<Value> {
+    ) -> Result<SignedTx> {
+        let (price, trigger_price) = (price.to_tx_field()?, trigger_price.to_tx_field()?);
         let nonce = self.get_nonce_or_use(nonce).await?;
         let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
         let expired_at = now + 599_000;
@@ -2100,23 +1787,19 @@ impl LighterClient {
             "Sig": ""
         });
 
-        let tx_json = serde_json::to_string(&tx_info)?;
-        let signature = self.sign_transaction_with_type(&tx_json, 17)?; // TX_TYPE_MODIFY_ORDER
-
-        let mut final_tx_info = tx_info;
-        final_tx_info["Sig"] = json!(base64::engine::general_purpose::STANDARD.encode(&signature));
+        let signed = self.sign_tx_info(tx_info, 17).await?; // TX_TYPE_MODIFY_ORDER
 
-        Ok(final_tx_info)
+        Ok(signed)
     }
 
-    /// Sign a create public pool transaction and return JSON (doesn't send to API)
+    /// Sign a create public pool transaction and return it with its hash (doesn't send to API)
     pub async fn sign_create_public_pool_with_nonce(
         &self,
         operator_fee: i64,
         initial_total_shares: i64,
         min_operator_share_rate: i64,
         nonce: Option<i64>,
-    ) -> Result<Value> {
+    ) -> Result<SignedTx> {
         let nonce = self.get_nonce_or_use(nonce).await?;
         let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
         let expired_at = now + 599_000;
@@ -2132,16 +1815,12 @@ impl LighterClient {
             "Sig": ""
         });
 
-        let tx_json = serde_json::to_string(&tx_info)?;
-        let signature = self.sign_transaction_with_type(&tx_json, 10)?; // TX_TYPE_CREATE_PUBLIC_POOL
-
-        let mut final_tx_info = tx_info;
-        final_tx_info["Sig"] = json!(base64::engine::general_purpose::STANDARD.encode(&signature));
+        let signed = self.sign_tx_info(tx_info, 10).await?; // TX_TYPE_CREATE_PUBLIC_POOL
 
-        Ok(final_tx_info)
+        Ok(signed)
     }
 
-    /// Sign an update public pool transaction and return JSON (doesn't send to API)
+    /// Sign an update public pool transaction and return it with its hash (doesn't send to API)
     pub async fn sign_update_public_pool_with_nonce(
         &self,
         public_pool_index: i64,
@@ -2149,7 +1828,7 @@ impl LighterClient {
         operator_fee: i64,
         min_operator_share_rate: i64,
         nonce: Option<i64>,
-    ) -> Result<Value> {
+    ) -> Result<SignedTx> {
         let nonce = self.get_nonce_or_use(nonce).await?;
         let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
         let expired_at = now + 599_000;
@@ -2166,22 +1845,18 @@ impl LighterClient {
             "Sig": ""
         });
 
-        let tx_json = serde_json::to_string(&tx_info)?;
-        let signature = self.sign_transaction_with_type(&tx_json, 11)?; // TX_TYPE_UPDATE_PUBLIC_POOL
-
-        let mut final_tx_info = tx_info;
-        final_tx_info["Sig"] = json!(base64::engine::general_purpose::STANDARD.encode(&signature));
+        let signed = self.sign_tx_info(tx_info, 11).await?; // TX_TYPE_UPDATE_PUBLIC_POOL
 
-        Ok(final_tx_info)
+        Ok(signed)
     }
 
-    /// Sign a mint shares transaction and return JSON (doesn't send to API)
+    /// Sign a mint shares transaction and return it with its hash (doesn't send to API)
     pub async fn sign_mint_shares_with_nonce(
         &self,
         public_pool_index: i64,
         share_amount: i64,
         nonce: Option<i64>,
-    ) -> Result<Value> {
+    ) -> Result<SignedTx> {
         let nonce = self.get_nonce_or_use(nonce).await?;
         let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
         let expired_at = now + 599_000;
@@ -2196,22 +1871,18 @@ impl LighterClient {
             "Sig": ""
         });
 
-        let tx_json = serde_json::to_string(&tx_info)?;
-        let signature = self.sign_transaction_with_type(&tx_json, 18)?; // TX_TYPE_MINT_SHARES
-
-        let mut final_tx_info = tx_info;
-        final_tx_info["Sig"] = json!(base64::engine::general_purpose::STANDARD.encode(&signature));
+        let signed = self.sign_tx_info(tx_info, 18).await?; // TX_TYPE_MINT_SHARES
 
-        Ok(final_tx_info)
+        Ok(signed)
     }
 
-    /// Sign a burn shares transaction and return JSON (doesn't send to API)
+    /// Sign a burn shares transaction and return it with its hash (doesn't send to API)
     pub async fn sign_burn_shares_with_nonce(
         &self,
         public_pool_index: i64,
         share_amount: i64,
         nonce: Option<i64>,
-    ) -> Result<Value> {
+    ) -> Result<SignedTx> {
         let nonce = self.get_nonce_or_use(nonce).await?;
         let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
         let expired_at = now + 599_000;
@@ -2226,23 +1897,19 @@ impl LighterClient {
             "Sig": ""
         });
 
-        let tx_json = serde_json::to_string(&tx_info)?;
-        let signature = self.sign_transaction_with_type(&tx_json, 19)?; // TX_TYPE_BURN_SHARES
-
-        let mut final_tx_info = tx_info;
-        final_tx_info["Sig"] = json!(base64::engine::general_purpose::STANDARD.encode(&signature));
+        let signed = self.sign_tx_info(tx_info, 19).await?; // TX_TYPE_BURN_SHARES
 
-        Ok(final_tx_info)
+        Ok(signed)
     }
 
-    /// Sign an update margin transaction and return JSON (doesn't send to API)
+    /// Sign an update margin transaction and return it with its hash (doesn't send to API)
     pub async fn sign_update_margin_with_nonce(
         &self,
         market_index: u8,
-        usdc_amount: i64,
-        direction: u8,
+        usdc_amount: UsdcAmount,
+        direction: MarginDirection,
         nonce: Option<i64>,
-    ) -> Result<Value> {
+    ) -> Result<SignedTx> {
         let nonce = self.get_nonce_or_use(nonce).await?;
         let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
         let expired_at = now + 599_000;
@@ -2258,22 +1925,18 @@ impl LighterClient {
             "Sig": ""
         });
 
-        let tx_json = serde_json::to_string(&tx_info)?;
-        let signature = self.sign_transaction_with_type(&tx_json, 29)?; // TX_TYPE_UPDATE_MARGIN
-
-        let mut final_tx_info = tx_info;
-        final_tx_info["Sig"] = json!(base64::engine::general_purpose::STANDARD.encode(&signature));
+        let signed = self.sign_tx_info(tx_info, 29).await?; // TX_TYPE_UPDATE_MARGIN
 
-        Ok(final_tx_info)
+        Ok(signed)
     }
 
-    /// Sign a create grouped orders transaction and return JSON (doesn't send to API)
+    /// Sign a create grouped orders transaction and return it with its hash (doesn't send to API)
     pub async fn sign_create_grouped_orders_with_nonce(
         &self,
-        grouping_type: u8,
+        grouping_type: GroupingType,
         orders: Vec<CreateOrderRequest>,
         nonce: Option<i64>,
-    ) -> Result<Value> {
+    ) -> Result<SignedTx> {
         let nonce = self.get_nonce_or_use(nonce).await?;
         let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
         let expired_at = now + 599_000;
@@ -2289,7 +1952,7 @@ impl LighterClient {
                 "TimeInForce": order.time_in_force,
                 "ReduceOnly": if order.reduce_only { 1 } else { 0 },
                 "TriggerPrice": order.trigger_price,
-                "OrderExpiry": 0,
+                "OrderExpiry": self.expiry_policy.order_expiry(order, now),
             })
         }).collect();
 
@@ -2303,13 +1966,9 @@ impl LighterClient {
             "Sig": ""
         });
 
-        let tx_json = serde_json::to_string(&tx_info)?;
-        let signature = self.sign_transaction_with_type(&tx_json, 28)?; // TX_TYPE_CREATE_GROUPED_ORDERS
-
-        let mut final_tx_info = tx_info;
-        final_tx_info["Sig"] = json!(base64::engine::general_purpose::STANDARD.encode(&signature));
+        let signed = self.sign_tx_info(tx_info, 28).await?; // TX_TYPE_CREATE_GROUPED_ORDERS
 
-        Ok(final_tx_info)
+        Ok(signed)
     }
 
     // ============================================================================
@@ -2326,9 +1985,12 @@ impl LighterClient {
         self.api_key_index
     }
 
-    /// Get key manager (for auth token generation)
-    pub fn key_manager(&self) -> &KeyManager {
-        &self.key_manager
+    /// The signer of transactions and auth tokens (see `signing`)
+    ///
+    /// Returns the signer in use at the time of the call; `rotate_api_key` replaces it.
+    pub fn signer(&self) -> Arc<dyn Signer> {
+        // A panicking writer cannot leave the Arc half-written
+        self.signer.read().unwrap_or_else(|e| e.into_inner()).clone()
     }
 
     /// Check API key on server (for CheckClient functionality)
@@ -2339,26 +2001,112 @@ impl LighterClient {
         );
         
         let response = self.client.get(&url).send().await?;
-        let response_text = response.text().await?;
-        let response_json: Value = serde_json::from_str(&response_text)?;
+        let response_json = parse_response(response).await?;
         
         let server_pubkey = response_json["public_key"]
             .as_str()
             .ok_or_else(|| ApiError::Api("Invalid API key response format".to_string()))?;
         
-        let local_pubkey_bytes = self.key_manager.public_key_bytes();
-        let local_pubkey_hex = hex::encode(local_pubkey_bytes);
-        
-        // Remove 0x prefix if present
-        let server_pubkey_clean = server_pubkey.strip_prefix("0x").unwrap_or(server_pubkey);
+        // Compare canonical encodings; the server may normalize limbs or hex differently
+        let local_pubkey = self.signer().public_key();
+        let matches = PublicKey::from_hex(server_pubkey)
+            .map(|server| server == local_pubkey)
+            .unwrap_or(false);
         
-        if server_pubkey_clean != local_pubkey_hex {
+        if !matches {
             return Err(ApiError::Api(format!(
-                "private key does not match the one on Lighter. ownPubKey: {} response: {}",
-                local_pubkey_hex, server_pubkey
+                "private key does not match the one on Lighter. ownPubKey: {} response: {} (find_my_key_index() looks up the right api_key_index)",
+                local_pubkey, server_pubkey
             )));
         }
         
         Ok(())
     }
 }
+
+/// The 10 order fields hashed by CreateOrder and by each order of CreateGroupedOrders.
+fn order_elements(order: &Value) -> Result<Vec<poseidon_hash::Goldilocks>> {
+    use tx_fields::element;
+
+    Ok(vec![
+        element::<u32>(order, "MarketIndex")?,
+        element::<i64>(order, "ClientOrderIndex")?,
+        element::<BaseAmount>(order, "BaseAmount")?,
+        element::<Price>(order, "Price")?,
+        element::<u8>(order, "IsAsk")?,
+        element::<u8>(order, "Type")?,
+        element::<u8>(order, "TimeInForce")?,
+        element::<u8>(order, "ReduceOnly")?,
+        element::<Price>(order, "TriggerPrice")?,
+        element::<i64>(order, "OrderExpiry")?,
+    ])
+}
+
+#[cfg(test)]
+mod vector_tests;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_body_keeps_raw_payload() {
+        assert_eq!(parse_body(200, r#"{"code":200}"#.to_string(), None).unwrap()["code"], 200);
+
+        match parse_body(502, "<html>Bad Gateway</html>".to_string(), None) {
+            Err(ApiError::UnexpectedResponse { status, body, .. }) => {
+                assert_eq!(status, 502);
+                assert_eq!(body, "<html>Bad Gateway</html>");
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let truncated = format!("{{\"data\":\"{}", "é".repeat(4000));
+        match parse_body(200, truncated, None) {
+            Err(ApiError::UnexpectedResponse { body, .. }) => assert!(body.len() <= MAX_ERROR_BODY_LEN),
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        // A Retry-After header travels with the error, or into a JSON body without its own hint
+        let retry_after = Some(std::time::Duration::from_secs(2));
+        match parse_body(429, "Too Many Requests".to_string(), retry_after) {
+            Err(error) => assert_eq!(error.retry_after(), retry_after),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        let throttled = parse_body(429, r#"{"code":429}"#.to_string(), retry_after).unwrap();
+        assert_eq!(throttled["retry_after_ms"], 2000);
+        let own_hint = parse_body(429, r#"{"code":429,"retry_after":5}"#.to_string(), retry_after).unwrap();
+        assert_eq!(retry::retry_after(&own_hint), Some(std::time::Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn test_debug_hides_private_key() {
+        let private_key = "bda332f3aaa2d9cfdd8920830ea37efce9636c671a426bd4cb9815007e2a2917604ab47857cbb200";
+        let builder = LighterClient::builder("http://localhost".to_string(), private_key, 1, 3);
+        let client = LighterClient::new("http://localhost".to_string(), private_key, 1, 3).unwrap();
+
+        let fingerprint = client.signer().public_key().fingerprint();
+        for output in [format!("{:?}", builder), format!("{:?}", client)] {
+            assert!(!output.contains(&private_key[..16]), "{}", output);
+        }
+        assert!(format!("{:?}", client).contains(&fingerprint));
+    }
+
+    #[tokio::test]
+    async fn test_sign_modify_order_checks_price() {
+        let private_key = "bda332f3aaa2d9cfdd8920830ea37efce9636c671a426bd4cb9815007e2a2917604ab47857cbb200";
+        let client = LighterClient::new("http://localhost".to_string(), private_key, 1, 3).unwrap();
+
+        let signed = client
+            .sign_modify_order_with_nonce(0, 7, BaseAmount(1_000), Price(305_000), Price::ZERO, Some(5))
+            .await
+            .unwrap();
+        assert_eq!(signed.tx_info["Price"], 305_000);
+
+        let too_wide = Price(u64::from(u32::MAX) + 1);
+        for (price, trigger_price) in [(too_wide, Price::ZERO), (Price(305_000), too_wide)] {
+            let result = client.sign_modify_order_with_nonce(0, 7, BaseAmount(1_000), price, trigger_price, Some(5)).await;
+            assert!(matches!(result, Err(ApiError::InvalidOrder(_))), "{:?}", result.map(|s| s.tx_info));
+        }
+    }
+}
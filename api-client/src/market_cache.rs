@@ -0,0 +1,201 @@
+//! Cached market metadata.
+//!
+//! Market lists and per-market details (decimals, minimum sizes, fees) change rarely, but
+//! callers tend to fetch them on every cycle. `MarketCache` keeps the last response for a
+//! configurable TTL. Each result says whether it is stale: if a refresh fails, the previous
+//! value is returned and marked stale instead of failing the caller. Prices are not cached;
+//! fetch them with `get_order_book_snapshot`.
+
+use crate::validation::MarketRules;
+use crate::{parse_response, ApiError, LighterClient, Result};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// A cached value and its age.
+#[derive(Debug, Clone)]
+pub struct Cached<T> {
+    pub value: T,
+    pub fetched_at: Instant,
+    /// Older than the TTL: the refresh failed and this is the last good value.
+    pub stale: bool,
+}
+
+struct Entry<T> {
+    value: T,
+    fetched_at: Instant,
+}
+
+/// TTL cache of `get_markets` and `orderBookDetails` responses.
+pub struct MarketCache {
+    client: Arc<LighterClient>,
+    ttl: Duration,
+    markets: Mutex<Option<Entry<Arc<Vec<Value>>>>>,
+    details: Mutex<HashMap<u8, Entry<Value>>>,
+    // Serializes refreshes so concurrent callers share one request
+    refresh: tokio::sync::Mutex<()>,
+}
+
+impl MarketCache {
+    pub fn new(client: Arc<LighterClient>, ttl: Duration) -> Self {
+        Self {
+            client,
+            ttl,
+            markets: Mutex::new(None),
+            details: Mutex::new(HashMap::new()),
+            refresh: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// All markets (entries of `orderBooks`)
+    pub async fn markets(&self) -> Result<Cached<Arc<Vec<Value>>>> {
+        if let Some(cached) = fresh(lock(&self.markets).as_ref(), self.ttl) {
+            return Ok(cached);
+        }
+
+        let _refresh = self.refresh.lock().await;
+        // Another caller may have refreshed while we waited
+        if let Some(cached) = fresh(lock(&self.markets).as_ref(), self.ttl) {
+            return Ok(cached);
+        }
+
+        match self.client.get_markets().await {
+            Ok(markets) => {
+                let entry = Entry {
+                    value: Arc::new(markets),
+                    fetched_at: Instant::now(),
+                };
+                let cached = to_cached(&entry, false);
+                *lock(&self.markets) = Some(entry);
+                Ok(cached)
+            }
+            Err(e) => lock(&self.markets).as_ref().map(|entry| to_cached(entry, true)).ok_or(e),
+        }
+    }
+
+    /// The `orderBooks` entry of a market, or `None` if the market does not exist
+    pub async fn market(&self, market_index: u8) -> Result<Cached<Option<Value>>> {
+        let markets = self.markets().await?;
+        Ok(Cached {
+            value: find_market(&markets.value, market_index).cloned(),
+            fetched_at: markets.fetched_at,
+            stale: markets.stale,
+        })
+    }
+
+    /// The `orderBookDetails` entry of a market
+    pub async fn details(&self, market_index: u8) -> Result<Cached<Value>> {
+        if let Some(cached) = fresh(lock(&self.details).get(&market_index), self.ttl) {
+            return Ok(cached);
+        }
+
+        let _refresh = self.refresh.lock().await;
+        if let Some(cached) = fresh(lock(&self.details).get(&market_index), self.ttl) {
+            return Ok(cached);
+        }
+
+        match self.client.order_book_details(market_index).await {
+            Ok(details) => {
+                let entry = Entry {
+                    value: details,
+                    fetched_at: Instant::now(),
+                };
+                let cached = to_cached(&entry, false);
+                lock(&self.details).insert(market_index, entry);
+                Ok(cached)
+            }
+            Err(e) => lock(&self.details).get(&market_index).map(|entry| to_cached(entry, true)).ok_or(e),
+        }
+    }
+
+    /// Trading rules of a market from the cached details
+    pub async fn market_rules(&self, market_index: u8) -> Result<MarketRules> {
+        MarketRules::from_order_book_details(&self.details(market_index).await?.value)
+    }
+
+    /// Drop everything cached; the next lookups fetch again
+    pub fn invalidate(&self) {
+        *lock(&self.markets) = None;
+        lock(&self.details).clear();
+    }
+
+    /// Drop the cached details of one market
+    pub fn invalidate_market(&self, market_index: u8) {
+        lock(&self.details).remove(&market_index);
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn to_cached<T: Clone>(entry: &Entry<T>, stale: bool) -> Cached<T> {
+    Cached {
+        value: entry.value.clone(),
+        fetched_at: entry.fetched_at,
+        stale,
+    }
+}
+
+/// The entry as a fresh `Cached`, or `None` if there is none or it is older than `ttl`.
+fn fresh<T: Clone>(entry: Option<&Entry<T>>, ttl: Duration) -> Option<Cached<T>> {
+    entry
+        .filter(|entry| entry.fetched_at.elapsed() < ttl)
+        .map(|entry| to_cached(entry, false))
+}
+
+fn find_market(markets: &[Value], market_index: u8) -> Option<&Value> {
+    markets
+        .iter()
+        .find(|m| m["market_id"].as_u64() == Some(market_index as u64))
+}
+
+impl LighterClient {
+    /// List all markets with their fees, size limits and supported decimals
+    ///
+    /// Returns the `order_books` entries of `/api/v1/orderBooks`. See `MarketCache` for a
+    /// cached view.
+    pub async fn get_markets(&self) -> Result<Vec<Value>> {
+        let response = self
+            .client
+            .get(format!("{}/api/v1/orderBooks", self.base_url))
+            .send()
+            .await?;
+
+        let response_json = parse_response(response).await?;
+        if let Some(code) = response_json["code"].as_i64() {
+            if code != 200 {
+                return Err(ApiError::Api(format!("Markets request failed: {}", response_json)));
+            }
+        }
+
+        response_json["order_books"]
+            .as_array()
+            .cloned()
+            .ok_or_else(|| ApiError::Api(format!("Markets response without order_books: {}", response_json)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_freshness_and_lookup() {
+        let entry = Entry {
+            value: vec![json!({"symbol": "ETH", "market_id": 0}), json!({"symbol": "BTC", "market_id": 1})],
+            fetched_at: Instant::now(),
+        };
+        assert!(fresh(Some(&entry), Duration::from_secs(60)).is_some_and(|c| !c.stale));
+        assert!(fresh(Some(&entry), Duration::ZERO).is_none());
+
+        assert_eq!(find_market(&entry.value, 1).unwrap()["symbol"], "BTC");
+        assert!(find_market(&entry.value, 7).is_none());
+    }
+}
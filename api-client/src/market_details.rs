@@ -0,0 +1,167 @@
+//! Typed market metadata.
+//!
+//! `orderBookDetails` carries everything a strategy needs to size and price orders on a
+//! market: decimals and tick, minimum sizes, fees, margin fractions and open interest.
+//! `MarketDetails` reads all of it at once, building on `MarketRules` and `MarketFees` so
+//! conversions and estimates agree with the rest of the client.
+
+use crate::positions::decimal_to_units;
+use crate::validation::MarketRules;
+use crate::{parse_response, ApiError, LighterClient, MarketCache, MarketFees, Result};
+use serde_json::Value;
+use std::time::Duration;
+
+/// Funding is exchanged every hour on Lighter perpetuals.
+pub const DEFAULT_FUNDING_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Trading parameters and state of a market.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarketDetails {
+    pub market_index: u8,
+    /// e.g. `"ETH"`.
+    pub symbol: String,
+    /// `"active"` while the market accepts orders.
+    pub status: String,
+    /// Decimals, tick and size limits.
+    pub rules: MarketRules,
+    /// Fee rates and margin fractions.
+    pub fees: MarketFees,
+    pub funding_interval: Duration,
+    /// Open interest in base currency.
+    pub open_interest: f64,
+    /// Last trade price in price units, if the market traded.
+    pub last_trade_price: Option<i64>,
+}
+
+impl MarketDetails {
+    /// Reads an `orderBookDetails` entry.
+    pub fn from_order_book_details(details: &Value) -> Result<Self> {
+        let market_index = details["market_id"]
+            .as_u64()
+            .and_then(|id| u8::try_from(id).ok())
+            .ok_or_else(|| ApiError::Api(format!("Market details without market_id: {}", details)))?;
+        let rules = MarketRules::from_order_book_details(details)?;
+        let number = |field: &str| {
+            let value = &details[field];
+            value.as_f64().or_else(|| value.as_str().and_then(|s| s.parse().ok()))
+        };
+        let last_trade_price = match &details["last_trade_price"] {
+            Value::String(price) => decimal_to_units(price, rules.price_decimals),
+            price => price.as_f64().map(|p| (p * 10f64.powi(rules.price_decimals as i32)).round() as i64),
+        };
+
+        Ok(Self {
+            market_index,
+            symbol: details["symbol"].as_str().unwrap_or_default().to_string(),
+            status: details["status"].as_str().unwrap_or_default().to_string(),
+            fees: MarketFees::from_order_book_details(details)?,
+            funding_interval: details["funding_interval"]
+                .as_u64()
+                .map(Duration::from_secs)
+                .unwrap_or(DEFAULT_FUNDING_INTERVAL),
+            open_interest: number("open_interest").unwrap_or_default(),
+            last_trade_price: last_trade_price.filter(|p| *p > 0),
+            rules,
+        })
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.status == "active"
+    }
+
+    /// Smallest price increment in quote currency, e.g. 0.01.
+    pub fn tick_size(&self) -> f64 {
+        self.rules.price_tick as f64 / 10f64.powi(self.rules.price_decimals as i32)
+    }
+
+    /// Smallest order size in base currency, e.g. 0.001.
+    pub fn min_order_size(&self) -> f64 {
+        self.rules.min_base_amount as f64 / 10f64.powi(self.rules.size_decimals as i32)
+    }
+
+    /// Highest leverage the market allows.
+    pub fn max_leverage(&self) -> u32 {
+        self.fees.max_leverage()
+    }
+
+    pub fn maker_fee(&self) -> f64 {
+        self.fees.maker_fee
+    }
+
+    pub fn taker_fee(&self) -> f64 {
+        self.fees.taker_fee
+    }
+}
+
+impl LighterClient {
+    /// Typed details of a market
+    pub async fn get_market_details(&self, market_index: u8) -> Result<MarketDetails> {
+        MarketDetails::from_order_book_details(&self.order_book_details(market_index).await?)
+    }
+
+    /// Typed details of every market, in the order the exchange lists them
+    pub async fn get_all_market_details(&self) -> Result<Vec<MarketDetails>> {
+        let response = self
+            .client
+            .get(format!("{}/api/v1/orderBookDetails", self.base_url))
+            .send()
+            .await?;
+
+        let response_json = parse_response(response).await?;
+        if let Some(code) = response_json["code"].as_i64() {
+            if code != 200 {
+                return Err(ApiError::Api(format!("Order book details request failed: {}", response_json)));
+            }
+        }
+        response_json["order_book_details"]
+            .as_array()
+            .ok_or_else(|| ApiError::Api(format!("Order book details response without order_book_details: {}", response_json)))?
+            .iter()
+            .map(MarketDetails::from_order_book_details)
+            .collect()
+    }
+}
+
+impl MarketCache {
+    /// Typed details of a market from the cached `orderBookDetails` entry
+    pub async fn market_details(&self, market_index: u8) -> Result<MarketDetails> {
+        MarketDetails::from_order_book_details(&self.details(market_index).await?.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_market_details() {
+        let details = MarketDetails::from_order_book_details(&json!({
+            "market_id": 0,
+            "symbol": "ETH",
+            "status": "active",
+            "price_decimals": 2,
+            "size_decimals": 4,
+            "min_base_amount": "0.0050",
+            "min_quote_amount": "10.000000",
+            "maker_fee": "0.0000",
+            "taker_fee": "0.0200",
+            "min_initial_margin_fraction": 200,
+            "maintenance_margin_fraction": 120,
+            "open_interest": 12345.6789,
+            "last_trade_price": 3012.45,
+        }))
+        .unwrap();
+        assert!(details.is_active());
+        assert_eq!(details.symbol, "ETH");
+        assert_eq!(details.tick_size(), 0.01);
+        assert_eq!(details.min_order_size(), 0.005);
+        assert_eq!(details.max_leverage(), 50);
+        assert_eq!((details.maker_fee(), details.taker_fee()), (0.0, 0.0002));
+        assert_eq!(details.funding_interval, DEFAULT_FUNDING_INTERVAL);
+        assert_eq!(details.open_interest, 12345.6789);
+        assert_eq!(details.last_trade_price, Some(301_245));
+
+        assert!(MarketDetails::from_order_book_details(&json!({"symbol": "ETH"})).is_err());
+    }
+}
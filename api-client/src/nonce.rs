@@ -0,0 +1,512 @@
+//! Nonce leasing.
+//!
+//! Every L2 transaction of an API key needs the next unused nonce. The client hands nonces
+//! out locally after fetching the starting value once from `nextNonce`. Each nonce is
+//! held by a `NonceLease` until the transaction is settled: a committed lease is consumed,
+//! a released (or dropped) lease goes back to the pool and is handed out again before any
+//! fresh nonce, so a failed transaction never leaves a gap, however many tasks submit
+//! concurrently through the same client.
+//!
+//! When the exchange rejects a transaction for its nonce anyway (another process using
+//! the same API key, or a pool that drifted), `send_tx` refetches the nonce, re-signs the
+//! transaction with a fresh lease and sends it once more. An optional hook observes each
+//! resync.
+//!
+//! `nonce_status` shows the pool (last fetched nonce, how many were handed out since and
+//! how long ago it was fetched). Resyncs and released nonces are also emitted as `tracing`
+//! events with target `api_client::nonce`, so a stuck nonce can be followed with
+//! `RUST_LOG=api_client::nonce=debug` under any subscriber.
+
+use crate::retry::response_lost;
+use crate::ws::Transport;
+use crate::{ApiError, LighterClient, Result};
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Response codes of a transaction rejected for its nonce.
+const NONCE_ERROR_CODES: [i64; 1] = [21104];
+
+/// A resynchronization of the nonce pool after a nonce rejection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NonceResync {
+    pub account_index: i64,
+    pub api_key_index: u8,
+    pub tx_type: u32,
+    /// Nonce of the rejected transaction
+    pub rejected_nonce: i64,
+    /// Next nonce reported by the API; the transaction is replayed with it
+    pub fetched_nonce: i64,
+    /// Code and message of the rejection
+    pub code: i64,
+    pub message: String,
+}
+
+/// Callback invoked before a transaction is replayed after a nonce resync.
+pub type NonceResyncHook = Arc<dyn Fn(&NonceResync) + Send + Sync>;
+
+/// Whether a sendTx response rejects the transaction's nonce
+///
+/// Matches the known nonce error codes, and any other error whose message mentions the nonce.
+pub fn is_nonce_error(response: &Value) -> bool {
+    let Some(code) = response["code"].as_i64().filter(|code| *code != 200) else {
+        return false;
+    };
+    NONCE_ERROR_CODES.contains(&code)
+        || response["message"]
+            .as_str()
+            .is_some_and(|message| message.to_ascii_lowercase().contains("nonce"))
+}
+
+/// Snapshot of a client's nonce pool.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NonceStatus {
+    /// Nonce last fetched from the API; the base of the local offsets.
+    pub base: i64,
+    /// Fresh nonces handed out since the fetch (the next fresh nonce is `base + offset`).
+    pub offset: i64,
+    /// Nonces handed out, released and waiting to be reused, lowest first.
+    pub released: Vec<i64>,
+    /// Time since the base was fetched.
+    pub age: Duration,
+}
+
+impl NonceStatus {
+    /// Next nonce a lease gets: the lowest released one, or the next fresh one.
+    pub fn next_nonce(&self) -> i64 {
+        self.released.first().copied().unwrap_or(self.base + self.offset)
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct NonceState {
+    /// Next fresh nonce; `None` until fetched from the API.
+    next: Option<i64>,
+    /// Value last fetched from the API; everything below it is known to be consumed.
+    floor: i64,
+    /// Nonces handed out and then released, reused lowest first.
+    released: BTreeSet<i64>,
+    /// Nonces handed out and not settled yet.
+    leased: BTreeSet<i64>,
+    /// When `floor` was fetched.
+    fetched_at: Option<Instant>,
+}
+
+impl NonceState {
+    fn acquire(&mut self) -> Option<i64> {
+        let next = self.next?;
+        let nonce = match self.released.pop_first() {
+            Some(nonce) => nonce,
+            None => {
+                self.next = Some(next + 1);
+                next
+            }
+        };
+        self.leased.insert(nonce);
+        Some(nonce)
+    }
+
+    fn commit(&mut self, nonce: i64) {
+        self.leased.remove(&nonce);
+    }
+
+    fn release(&mut self, nonce: i64) {
+        self.leased.remove(&nonce);
+        // Leases from before a resync may be older than what the API reported
+        let current = nonce >= self.floor && self.next.is_some_and(|next| nonce < next);
+        if current {
+            self.released.insert(nonce);
+        }
+        tracing::debug!(target: "api_client::nonce", nonce, reused = current, released = self.released.len(), "nonce released");
+    }
+
+    fn reset(&mut self, fetched: i64) {
+        self.next = Some(fetched);
+        self.floor = fetched;
+        self.released.clear();
+        self.leased.clear();
+        self.fetched_at = Some(Instant::now());
+    }
+
+    /// Moves the floor to `fetched` without touching outstanding leases: fresh nonces
+    /// continue above the highest one, and the unused nonces between are reused first.
+    fn resync(&mut self, fetched: i64) {
+        let next = self.leased.last().map_or(fetched, |highest| fetched.max(highest + 1));
+        self.released = (fetched..next).filter(|nonce| !self.leased.contains(nonce)).collect();
+        self.next = Some(next);
+        self.floor = fetched;
+        self.fetched_at = Some(Instant::now());
+    }
+
+    fn status(&self) -> Option<NonceStatus> {
+        Some(NonceStatus {
+            base: self.floor,
+            offset: self.next? - self.floor,
+            released: self.released.iter().copied().collect(),
+            age: self.fetched_at.map(|at| at.elapsed()).unwrap_or_default(),
+        })
+    }
+}
+
+/// Shared nonce pool of one client.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct NonceManager {
+    state: Arc<Mutex<NonceState>>,
+}
+
+impl NonceManager {
+    /// Leases a nonce if the pool has been initialized.
+    pub(crate) fn try_lease(&self) -> Option<NonceLease> {
+        let nonce = self.lock().acquire()?;
+        Some(NonceLease {
+            nonce,
+            state: Some(self.state.clone()),
+        })
+    }
+
+    /// Initializes the pool with `fetched` unless another task already did.
+    pub(crate) fn init(&self, fetched: i64) {
+        let mut state = self.lock();
+        if state.next.is_none() {
+            state.reset(fetched);
+            tracing::debug!(target: "api_client::nonce", fetched, "nonce pool initialized");
+        }
+    }
+
+    /// Resynchronizes the pool with the API value
+    ///
+    /// Nonces below `fetched` are consumed. Leases still outstanding keep their nonces, so
+    /// none of them is handed out twice; the pool continues above the highest.
+    pub(crate) fn resync(&self, fetched: i64) {
+        let mut state = self.lock();
+        if state.next.is_none() {
+            state.reset(fetched);
+            return;
+        }
+        state.resync(fetched);
+        tracing::info!(
+            target: "api_client::nonce",
+            fetched,
+            next = ?state.next,
+            outstanding = state.leased.len(),
+            reused = state.released.len(),
+            "nonce pool resynced"
+        );
+    }
+
+    pub(crate) fn status(&self) -> Option<NonceStatus> {
+        self.lock().status()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, NonceState> {
+        // The state stays consistent even if a holder panicked
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A nonce reserved for one transaction.
+///
+/// Call `commit` once the exchange accepted the transaction and `release` if it was
+/// rejected. Dropping an unsettled lease releases it, so early returns through `?` give
+/// the nonce back.
+#[derive(Debug)]
+pub struct NonceLease {
+    nonce: i64,
+    state: Option<Arc<Mutex<NonceState>>>,
+}
+
+impl NonceLease {
+    /// A lease for a caller-chosen nonce; settling it does not touch the client's pool.
+    pub fn detached(nonce: i64) -> Self {
+        Self { nonce, state: None }
+    }
+
+    pub fn nonce(&self) -> i64 {
+        self.nonce
+    }
+
+    /// Marks the nonce as consumed.
+    pub fn commit(mut self) {
+        if let Some(state) = self.state.take() {
+            state.lock().unwrap_or_else(|e| e.into_inner()).commit(self.nonce);
+        }
+    }
+
+    /// Returns the nonce to the pool.
+    pub fn release(self) {
+        drop(self);
+    }
+
+    /// Commits on a `code: 200` response and releases otherwise.
+    pub fn settle(self, response: &Value) {
+        if response["code"].as_i64() == Some(200) {
+            self.commit();
+        } else {
+            self.release();
+        }
+    }
+
+    /// Settles with the outcome of sending the transaction: like `settle` for a response,
+    /// committed if the transaction may have been executed, released on any other error.
+    pub fn settle_outcome(self, outcome: &Result<Value>) {
+        match outcome {
+            Ok(response) => self.settle(response),
+            Err(ApiError::PossiblyExecuted { .. }) => self.commit(),
+            Err(_) => self.release(),
+        }
+    }
+}
+
+impl Drop for NonceLease {
+    fn drop(&mut self) {
+        if let Some(state) = self.state.take() {
+            state.lock().unwrap_or_else(|e| e.into_inner()).release(self.nonce);
+        }
+    }
+}
+
+impl LighterClient {
+    /// State of the local nonce pool, or `None` before the first nonce was fetched
+    pub fn nonce_status(&self) -> Option<NonceStatus> {
+        self.nonces.status()
+    }
+
+    /// Lease the next nonce for a transaction
+    ///
+    /// Fetches the starting nonce from the API on first use, then hands nonces out
+    /// locally. Settle the lease with the transaction's outcome.
+    pub async fn lease_nonce(&self) -> Result<NonceLease> {
+        if let Some(lease) = self.nonces.try_lease() {
+            return Ok(lease);
+        }
+
+        let fetched = self.fetch_nonce_from_api().await?;
+        self.nonces.init(fetched);
+        match self.nonces.try_lease() {
+            Some(lease) => Ok(lease),
+            None => unreachable!("nonce pool initialized above"),
+        }
+    }
+
+    /// Refetch the nonce after `rejection`, re-sign `tx_info` with a fresh lease and send it once more
+    ///
+    /// Transactions carrying an L1 signature cannot be re-signed and return `rejection`. A
+    /// transaction the exchange already has is not replayed: its nonce was taken by a copy of
+    /// it (a delivery duplicated on the way), and its response is returned as accepted. If
+    /// that cannot be checked, or the replay gets no response, the call fails with
+    /// `ApiError::PossiblyExecuted`.
+    pub(crate) async fn resync_and_replay(
+        &self,
+        transport: Transport,
+        tx_type: u32,
+        tx_info: &Value,
+        price_protection: &str,
+        rejection: Value,
+    ) -> Result<Value> {
+        if tx_info.get("L1Sig").is_some() {
+            return Ok(rejection);
+        }
+
+        let tx_hash = hex::encode(self.compute_tx_hash(&tx_info.to_string(), tx_type)?);
+        match self.find_lost(&tx_hash, tx_info).await {
+            Some(Ok(response)) => return Ok(response),
+            Some(Err(_)) => {}
+            None => {
+                return Err(ApiError::PossiblyExecuted {
+                    tx_hash,
+                    nonce: tx_info["Nonce"].as_i64().unwrap_or_default(),
+                    message: "its nonce was used and the transaction could not be looked up".to_string(),
+                })
+            }
+        }
+
+        let fetched = self.fetch_nonce_from_api().await?;
+        tracing::warn!(
+            target: "api_client::nonce",
+            tx_type,
+            rejected_nonce = tx_info["Nonce"].as_i64().unwrap_or_default(),
+            code = rejection["code"].as_i64().unwrap_or_default(),
+            fetched,
+            "nonce rejected, resyncing"
+        );
+        self.nonces.resync(fetched);
+        let lease = self.lease_nonce().await?;
+
+        if let Some(hook) = &self.nonce_resync_hook {
+            hook(&NonceResync {
+                account_index: self.account_index,
+                api_key_index: self.api_key_index,
+                tx_type,
+                rejected_nonce: tx_info["Nonce"].as_i64().unwrap_or_default(),
+                fetched_nonce: lease.nonce(),
+                code: rejection["code"].as_i64().unwrap_or_default(),
+                message: rejection["message"].as_str().unwrap_or_default().to_string(),
+            });
+        }
+
+        let tx_info = self.resign(tx_type, tx_info.clone(), lease.nonce()).await?;
+        let response = match self.post_tx(transport, tx_type, &tx_info, price_protection).await {
+            Ok(response) => response,
+            // The replay may have been executed; retrying the original would place it twice
+            Err(e) if response_lost(&e) => {
+                lease.commit();
+                return Err(ApiError::PossiblyExecuted {
+                    tx_hash: hex::encode(self.compute_tx_hash(&tx_info.to_string(), tx_type)?),
+                    nonce: tx_info["Nonce"].as_i64().unwrap_or_default(),
+                    message: e.to_string(),
+                });
+            }
+            Err(e) => return Err(e),
+        };
+        lease.settle(&response);
+        Ok(response)
+    }
+
+    /// Replaces nonce, expiry and signature of a signed transaction.
+    pub(crate) async fn resign(&self, tx_type: u32, mut tx_info: Value, nonce: i64) -> Result<Value> {
+        if tx_info.get("L1Sig").is_some() {
+            // The L1 signature covers the nonce and cannot be renewed here
+            return Err(ApiError::Api("Cannot re-sign a transaction carrying an L1 signature".to_string()));
+        }
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
+        tx_info["Nonce"] = json!(nonce);
+        tx_info["ExpiredAt"] = json!(now + 599_000);
+        tx_info["Sig"] = json!("");
+        Ok(self.sign_tx_info(tx_info, tx_type).await?.tx_info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manager(start: i64) -> NonceManager {
+        let manager = NonceManager::default();
+        manager.init(start);
+        manager
+    }
+
+    #[test]
+    fn test_released_nonces_are_reused_first() {
+        let nonces = manager(10);
+        let a = nonces.try_lease().unwrap();
+        let b = nonces.try_lease().unwrap();
+        let c = nonces.try_lease().unwrap();
+        assert_eq!((a.nonce(), b.nonce(), c.nonce()), (10, 11, 12));
+
+        // Two concurrent failures, one success
+        c.release();
+        a.commit();
+        drop(b);
+
+        assert_eq!(nonces.try_lease().unwrap().nonce(), 11);
+        let d = nonces.try_lease().unwrap();
+        assert_eq!(d.nonce(), 11);
+        let e = nonces.try_lease().unwrap();
+        assert_eq!(e.nonce(), 12);
+        assert_eq!(nonces.try_lease().unwrap().nonce(), 13);
+    }
+
+    #[test]
+    fn test_reset_discards_stale_leases() {
+        let nonces = manager(5);
+        let stale = nonces.try_lease().unwrap();
+        nonces.resync(20);
+        stale.release();
+        assert_eq!(nonces.try_lease().unwrap().nonce(), 20);
+
+        let uninitialized = NonceManager::default();
+        assert!(uninitialized.try_lease().is_none());
+        assert_eq!(uninitialized.status(), None);
+        NonceLease::detached(3).release();
+    }
+
+    #[test]
+    fn test_resync_keeps_outstanding_leases() {
+        let nonces = manager(10);
+        let a = nonces.try_lease().unwrap();
+        let b = nonces.try_lease().unwrap();
+        let c = nonces.try_lease().unwrap();
+        c.release();
+
+        // The API has seen none of them; 10 and 11 are still in flight
+        nonces.resync(10);
+        assert_eq!(nonces.try_lease().unwrap().nonce(), 12);
+        let d = nonces.try_lease().unwrap();
+        assert_eq!(d.nonce(), 12);
+        assert_eq!(nonces.try_lease().unwrap().nonce(), 13);
+
+        // 10 landed and 11 failed: 11 is reused before anything fresh
+        a.commit();
+        nonces.resync(11);
+        b.release();
+        let status = nonces.status().unwrap();
+        assert_eq!((status.base, status.next_nonce(), status.released.clone()), (11, 11, vec![11]));
+        drop(d);
+    }
+
+    #[test]
+    fn test_concurrent_leases_survive_resyncs() {
+        use std::collections::HashSet;
+
+        let nonces = manager(0);
+        let held = Arc::new(Mutex::new(HashSet::new()));
+        let workers: Vec<_> = (0..8)
+            .map(|worker| {
+                let nonces = nonces.clone();
+                let held = held.clone();
+                std::thread::spawn(move || {
+                    for i in 0..2000 {
+                        let lease = nonces.try_lease().unwrap();
+                        assert!(held.lock().unwrap().insert(lease.nonce()), "nonce {} leased twice", lease.nonce());
+                        if i % 50 == worker {
+                            // A stale fetch, as when other transactions are still in flight
+                            let floor = nonces.status().unwrap().base;
+                            nonces.resync(floor);
+                        }
+                        std::thread::yield_now();
+                        held.lock().unwrap().remove(&lease.nonce());
+                        if i % 3 == 0 {
+                            lease.release();
+                        } else {
+                            lease.commit();
+                        }
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_nonce_status() {
+        let nonces = manager(40);
+        let a = nonces.try_lease().unwrap();
+        let b = nonces.try_lease().unwrap();
+        let _c = nonces.try_lease().unwrap();
+        a.commit();
+        b.release();
+
+        let status = nonces.status().unwrap();
+        assert_eq!((status.base, status.offset, status.released.clone()), (40, 3, vec![41]));
+        assert_eq!(status.next_nonce(), 41);
+        assert!(status.age < Duration::from_secs(5));
+
+        nonces.resync(50);
+        let status = nonces.status().unwrap();
+        assert_eq!((status.base, status.offset, status.next_nonce()), (50, 0, 50));
+        assert!(status.released.is_empty());
+    }
+
+    #[test]
+    fn test_nonce_errors() {
+        assert!(is_nonce_error(&json!({"code": 21104, "message": "invalid nonce"})));
+        assert!(is_nonce_error(&json!({"code": 21999, "message": "Nonce too high"})));
+        assert!(!is_nonce_error(&json!({"code": 21120, "message": "invalid signature"})));
+        assert!(!is_nonce_error(&json!({"code": 200, "message": "nonce"})));
+    }
+}
@@ -0,0 +1,210 @@
+//! Operational alerts.
+//!
+//! A `Notifier` delivers `Alert`s to a chat channel. With a notifier configured, the
+//! client raises alerts for conditions that need a human: a reduce-only close that failed,
+//! or a transaction still rejected with an invalid signature (21120) after every retry.
+//! Callers can send their own alerts, such as over-exposure detected by a strategy,
+//! through the same notifier. Delivery runs in the background and never fails the
+//! operation that raised the alert.
+
+use crate::{ApiError, LighterClient, Result};
+use futures_core::future::BoxFuture;
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::fmt;
+use std::time::Duration;
+
+/// Severity of an alert.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warning,
+    Critical,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Info => "INFO",
+            Severity::Warning => "WARNING",
+            Severity::Critical => "CRITICAL",
+        })
+    }
+}
+
+/// An event to report.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alert {
+    pub severity: Severity,
+    pub title: String,
+    pub message: String,
+}
+
+impl Alert {
+    pub fn new(severity: Severity, title: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            title: title.into(),
+            message: message.into(),
+        }
+    }
+
+    /// A reduce-only close was rejected or could not be sent
+    pub fn close_failed(account_index: i64, market_index: u8, reason: impl fmt::Display) -> Self {
+        Self::new(
+            Severity::Critical,
+            "Close failed",
+            format!("Account {} could not close on market {}: {}", account_index, market_index, reason),
+        )
+    }
+
+    /// A transaction was rejected with an invalid signature (21120) on every attempt
+    pub fn signature_retries_exhausted(account_index: i64, tx_type: u32, attempts: u32) -> Self {
+        Self::new(
+            Severity::Warning,
+            "Repeated invalid signature",
+            format!(
+                "Account {} tx type {} was rejected with code 21120 on all {} attempts",
+                account_index, tx_type, attempts
+            ),
+        )
+    }
+
+    /// An endpoint's circuit opened after repeated transport or 5xx failures
+    pub fn circuit_opened(endpoint: &str, failures: u32, cool_down: Duration) -> Self {
+        Self::new(
+            Severity::Warning,
+            "Circuit open",
+            format!(
+                "{} failed {} times in a row; requests fail fast for {} s",
+                endpoint,
+                failures,
+                cool_down.as_secs()
+            ),
+        )
+    }
+
+    /// Plain-text rendering used by the chat notifiers
+    pub fn to_text(&self) -> String {
+        format!("[{}] {}\n{}", self.severity, self.title, self.message)
+    }
+}
+
+/// Destination of alerts.
+pub trait Notifier: Send + Sync {
+    fn notify<'a>(&'a self, alert: &'a Alert) -> BoxFuture<'a, Result<()>>;
+}
+
+/// Posts alerts to a Telegram chat through the Bot API.
+pub struct TelegramNotifier {
+    client: Client,
+    bot_token: String,
+    chat_id: String,
+}
+
+impl TelegramNotifier {
+    /// # Arguments
+    /// * `bot_token` - Token of the bot, as issued by BotFather
+    /// * `chat_id` - Chat (or channel, e.g. `@ops_alerts`) the bot posts to
+    pub fn new(bot_token: impl Into<String>, chat_id: impl Into<String>) -> Self {
+        Self {
+            client: Client::new(),
+            bot_token: bot_token.into(),
+            chat_id: chat_id.into(),
+        }
+    }
+
+    fn payload(&self, alert: &Alert) -> Value {
+        json!({
+            "chat_id": self.chat_id,
+            "text": alert.to_text(),
+            "disable_web_page_preview": true,
+        })
+    }
+}
+
+impl Notifier for TelegramNotifier {
+    fn notify<'a>(&'a self, alert: &'a Alert) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let url = format!("https://api.telegram.org/bot{}/sendMessage", self.bot_token);
+            let response = self.client.post(url).json(&self.payload(alert)).send().await?;
+            check_status("Telegram", response).await
+        })
+    }
+}
+
+/// Posts alerts to a Discord channel through an incoming webhook.
+pub struct DiscordNotifier {
+    client: Client,
+    webhook_url: String,
+}
+
+impl DiscordNotifier {
+    pub fn new(webhook_url: impl Into<String>) -> Self {
+        Self {
+            client: Client::new(),
+            webhook_url: webhook_url.into(),
+        }
+    }
+
+    fn payload(alert: &Alert) -> Value {
+        // Discord rejects messages over 2000 characters
+        let text: String = alert.to_text().chars().take(2000).collect();
+        json!({ "content": text })
+    }
+}
+
+impl Notifier for DiscordNotifier {
+    fn notify<'a>(&'a self, alert: &'a Alert) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let response = self.client.post(&self.webhook_url).json(&Self::payload(alert)).send().await?;
+            check_status("Discord", response).await
+        })
+    }
+}
+
+async fn check_status(service: &str, response: reqwest::Response) -> Result<()> {
+    if response.status().is_success() {
+        return Ok(());
+    }
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    Err(ApiError::Api(format!("{} notification failed ({}): {}", service, status, body)))
+}
+
+impl LighterClient {
+    /// Send `alert` to the configured notifier in the background
+    ///
+    /// Does nothing without a notifier. Delivery failures are logged, not returned.
+    pub fn notify(&self, alert: Alert) {
+        let Some(notifier) = self.notifier.clone() else {
+            return;
+        };
+        tokio::spawn(async move {
+            if let Err(e) = notifier.notify(&alert).await {
+                tracing::warn!(target: "api_client::notifier", title = %alert.title, error = %e, "failed to deliver alert");
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payloads() {
+        let alert = Alert::close_failed(7, 3, "code 21733");
+        assert_eq!(alert.severity, Severity::Critical);
+        assert_eq!(
+            alert.to_text(),
+            "[CRITICAL] Close failed\nAccount 7 could not close on market 3: code 21733"
+        );
+
+        let telegram = TelegramNotifier::new("token", "@ops");
+        assert_eq!(telegram.payload(&alert)["chat_id"], "@ops");
+
+        let long = Alert::new(Severity::Info, "t", "x".repeat(5000));
+        assert_eq!(DiscordNotifier::payload(&long)["content"].as_str().unwrap().len(), 2000);
+    }
+}
@@ -0,0 +1,202 @@
+//! Account bootstrap from an Ethereum key.
+//!
+//! A Lighter account is created by depositing from an Ethereum address. Trading from this
+//! crate additionally needs an API key registered on that account, which takes a
+//! ChangePubKey transaction authorized by the Ethereum key. `onboard` does the whole
+//! sequence: look up the account of the address, generate an API key, register it and
+//! return a client that signs with it.
+
+use crate::l1::{L1Authorization, L1Signer};
+use crate::{parse_response, ApiError, LighterClient, Result};
+use k256::ecdsa::SigningKey;
+use serde_json::Value;
+use sha3::{Digest, Keccak256};
+use signer::KeyManager;
+use std::time::{Duration, Instant};
+use zeroize::Zeroizing;
+
+/// How long `onboard` waits for a newly registered key to become visible.
+const KEY_REGISTRATION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// An Ethereum private key usable as an `L1Signer`.
+pub struct EthWallet {
+    key: SigningKey,
+}
+
+impl EthWallet {
+    /// Parse a 32-byte secp256k1 private key from hex (optionally `0x`-prefixed).
+    pub fn from_hex(private_key_hex: &str) -> Result<Self> {
+        let hex_str = private_key_hex.strip_prefix("0x").unwrap_or(private_key_hex);
+        let bytes = Zeroizing::new(
+            hex::decode(hex_str).map_err(|e| ApiError::L1Signer(format!("Invalid Ethereum key hex: {}", e)))?,
+        );
+        let key = SigningKey::from_slice(&bytes)
+            .map_err(|_| ApiError::L1Signer("Invalid Ethereum private key".to_string()))?;
+        Ok(Self { key })
+    }
+
+    /// The 20-byte address of this key.
+    pub fn address(&self) -> [u8; 20] {
+        let public_key = self.key.verifying_key().to_encoded_point(false);
+        let hash = Keccak256::digest(&public_key.as_bytes()[1..]);
+        let mut address = [0u8; 20];
+        address.copy_from_slice(&hash[12..]);
+        address
+    }
+
+    /// The address as lowercase `0x`-prefixed hex.
+    pub fn address_hex(&self) -> String {
+        format!("0x{}", hex::encode(self.address()))
+    }
+}
+
+impl L1Signer for EthWallet {
+    fn sign_message(&self, message: &str) -> Result<[u8; 65]> {
+        let digest = personal_message_hash(message.as_bytes());
+        let (signature, recovery_id) = self
+            .key
+            .sign_prehash_recoverable(&digest)
+            .map_err(|e| ApiError::L1Signer(e.to_string()))?;
+
+        let mut out = [0u8; 65];
+        out[..64].copy_from_slice(&signature.to_bytes());
+        out[64] = 27 + recovery_id.to_byte();
+        Ok(out)
+    }
+}
+
+/// EIP-191 hash: `keccak256("\x19Ethereum Signed Message:\n" ‖ len ‖ message)`.
+fn personal_message_hash(message: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    hasher.update(format!("\x19Ethereum Signed Message:\n{}", message.len()).as_bytes());
+    hasher.update(message);
+    hasher.finalize().into()
+}
+
+/// Result of a successful `onboard`.
+pub struct OnboardedAccount {
+    /// Client signing with the newly registered API key.
+    pub client: LighterClient,
+    pub account_index: i64,
+    pub api_key_index: u8,
+    /// Hex private key of the new API key; persist it, it cannot be recovered.
+    pub api_private_key: Zeroizing<String>,
+}
+
+/// Look up the main account owned by an Ethereum address
+///
+/// Returns `None` if the address has not deposited yet.
+pub async fn lookup_account_index(base_url: &str, l1_address: &str) -> Result<Option<i64>> {
+    let response = reqwest::Client::new()
+        .get(format!("{}/api/v1/accountsByL1Address", base_url))
+        .query(&[("l1_address", l1_address)])
+        .send()
+        .await?;
+
+    let response_json = parse_response(response).await?;
+    match response_json["code"].as_i64() {
+        Some(200) | None => Ok(master_account_index(&response_json)),
+        // 21100: account not found
+        Some(21100) => Ok(None),
+        Some(_) => Err(ApiError::Api(format!("Account lookup failed: {}", response_json))),
+    }
+}
+
+/// Bootstrap API access for the account of `wallet`
+///
+/// Generates a new API key, registers it at `api_key_index` with L1 authorization and
+/// waits until the exchange reports it. Indices 0 and 1 are used by the web and mobile
+/// apps; pick one of 2..=254.
+pub async fn onboard(base_url: &str, wallet: &EthWallet, api_key_index: u8) -> Result<OnboardedAccount> {
+    onboard_with_signer(base_url, wallet, &wallet.address_hex(), api_key_index).await
+}
+
+/// `onboard` with any `L1Signer`, for keys held by an external wallet
+pub async fn onboard_with_signer(
+    base_url: &str,
+    signer: &dyn L1Signer,
+    l1_address: &str,
+    api_key_index: u8,
+) -> Result<OnboardedAccount> {
+    let account_index = lookup_account_index(base_url, l1_address)
+        .await?
+        .ok_or_else(|| {
+            ApiError::Api(format!(
+                "No Lighter account for {}; deposit from this address first",
+                l1_address
+            ))
+        })?;
+
+    let key_bytes = Zeroizing::new(KeyManager::generate().private_key_bytes());
+    let api_private_key = Zeroizing::new(format!("0x{}", hex::encode(&key_bytes[..])));
+    let client = LighterClient::new(base_url.to_string(), &api_private_key, account_index, api_key_index)?;
+
+    let response = client.change_api_key_with_l1(L1Authorization::Signer(signer)).await?;
+    if response["code"].as_i64() != Some(200) {
+        return Err(ApiError::Api(format!("ChangePubKey rejected: {}", response)));
+    }
+
+    let started = Instant::now();
+    while client.check_api_key().await.is_err() {
+        if started.elapsed() > KEY_REGISTRATION_TIMEOUT {
+            return Err(ApiError::Api(format!(
+                "API key {} was submitted but not visible after {:?}",
+                api_key_index, KEY_REGISTRATION_TIMEOUT
+            )));
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+
+    Ok(OnboardedAccount {
+        client,
+        account_index,
+        api_key_index,
+        api_private_key,
+    })
+}
+
+/// Picks the main (non-sub) account, falling back to the lowest index.
+fn master_account_index(response: &Value) -> Option<i64> {
+    let accounts = response["sub_accounts"].as_array()?;
+    accounts
+        .iter()
+        .find(|a| a["account_type"].as_i64() == Some(0))
+        .or_else(|| accounts.iter().min_by_key(|a| a["index"].as_i64().unwrap_or(i64::MAX)))
+        .and_then(|a| a["index"].as_i64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+    use serde_json::json;
+
+    #[test]
+    fn test_wallet_address_and_personal_sign() {
+        let wallet = EthWallet::from_hex("0x0000000000000000000000000000000000000000000000000000000000000001").unwrap();
+        assert_eq!(wallet.address_hex(), "0x7e5f4552091a69125d5dfcb7b8c2659029395bdf");
+
+        let message = "Register Lighter Account";
+        let signature = wallet.sign_message(message).unwrap();
+        let recovered = VerifyingKey::recover_from_prehash(
+            &personal_message_hash(message.as_bytes()),
+            &Signature::from_slice(&signature[..64]).unwrap(),
+            RecoveryId::from_byte(signature[64] - 27).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(&recovered, wallet.key.verifying_key());
+    }
+
+    #[test]
+    fn test_master_account_index() {
+        let response = json!({
+            "code": 200,
+            "sub_accounts": [
+                {"index": 281474976710650i64, "account_type": 1},
+                {"index": 42, "account_type": 0}
+            ]
+        });
+        assert_eq!(master_account_index(&response), Some(42));
+        assert_eq!(master_account_index(&json!({"code": 200, "sub_accounts": []})), None);
+    }
+}
@@ -0,0 +1,275 @@
+//! Local L2 order book.
+//!
+//! An `OrderBook` is seeded from the REST `orderBookOrders` snapshot (or the first websocket
+//! `order_book/{market}` message) and kept current by applying the websocket deltas, where each
+//! level carries the new total size at that price and a size of zero removes the level.
+
+use crate::{parse_response, ApiError, LighterClient, Result};
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+/// Price used as a map key; ordered with `f64::total_cmp`.
+#[derive(Debug, Clone, Copy)]
+struct PriceKey(f64);
+
+impl PartialEq for PriceKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.total_cmp(&other.0) == Ordering::Equal
+    }
+}
+
+impl Eq for PriceKey {}
+
+impl PartialOrd for PriceKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PriceKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+/// Aggregated size resting at one price.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PriceLevel {
+    pub price: f64,
+    pub size: f64,
+}
+
+/// Order book for a single market.
+#[derive(Debug, Clone, Default)]
+pub struct OrderBook {
+    market_id: u8,
+    bids: BTreeMap<PriceKey, f64>,
+    asks: BTreeMap<PriceKey, f64>,
+    offset: Option<i64>,
+}
+
+impl OrderBook {
+    /// Creates an empty book for `market_id`.
+    pub fn new(market_id: u8) -> Self {
+        Self {
+            market_id,
+            ..Default::default()
+        }
+    }
+
+    /// Builds a book from a snapshot.
+    ///
+    /// Accepts either the REST `orderBookOrders` response (individual orders with
+    /// `remaining_base_amount`, aggregated here by price) or a websocket
+    /// `order_book` payload (levels with `size`).
+    pub fn from_snapshot(market_id: u8, snapshot: &Value) -> Result<Self> {
+        let mut book = Self::new(market_id);
+        let payload = snapshot.get("order_book").unwrap_or(snapshot);
+
+        for (side, key) in [(&mut book.bids, "bids"), (&mut book.asks, "asks")] {
+            for entry in payload[key].as_array().into_iter().flatten() {
+                let (price, size) = parse_level(entry)?;
+                if size > 0.0 {
+                    *side.entry(PriceKey(price)).or_insert(0.0) += size;
+                }
+            }
+        }
+        book.offset = snapshot["offset"].as_i64();
+
+        Ok(book)
+    }
+
+    /// Applies a websocket `update/order_book` message.
+    ///
+    /// Each level replaces the size at its price; zero removes it. Messages whose
+    /// `offset` is not newer than the last applied one are ignored, and `false` is
+    /// returned for them.
+    pub fn apply_delta(&mut self, update: &Value) -> Result<bool> {
+        let offset = update["offset"].as_i64();
+        if let (Some(new), Some(current)) = (offset, self.offset) {
+            if new <= current {
+                return Ok(false);
+            }
+        }
+
+        let payload = update.get("order_book").unwrap_or(update);
+        for (side, key) in [(&mut self.bids, "bids"), (&mut self.asks, "asks")] {
+            for entry in payload[key].as_array().into_iter().flatten() {
+                let (price, size) = parse_level(entry)?;
+                if size > 0.0 {
+                    side.insert(PriceKey(price), size);
+                } else {
+                    side.remove(&PriceKey(price));
+                }
+            }
+        }
+        if offset.is_some() {
+            self.offset = offset;
+        }
+
+        Ok(true)
+    }
+
+    /// Market this book belongs to.
+    pub fn market_id(&self) -> u8 {
+        self.market_id
+    }
+
+    /// Offset of the last applied snapshot or delta, if the feed provided one.
+    pub fn offset(&self) -> Option<i64> {
+        self.offset
+    }
+
+    /// Highest bid.
+    pub fn best_bid(&self) -> Option<PriceLevel> {
+        self.bids.iter().next_back().map(|(p, s)| PriceLevel { price: p.0, size: *s })
+    }
+
+    /// Lowest ask.
+    pub fn best_ask(&self) -> Option<PriceLevel> {
+        self.asks.iter().next().map(|(p, s)| PriceLevel { price: p.0, size: *s })
+    }
+
+    /// Midpoint of the best bid and ask.
+    pub fn mid(&self) -> Option<f64> {
+        Some((self.best_bid()?.price + self.best_ask()?.price) / 2.0)
+    }
+
+    /// Best ask minus best bid.
+    pub fn spread(&self) -> Option<f64> {
+        Some(self.best_ask()?.price - self.best_bid()?.price)
+    }
+
+    /// Top `n` levels of each side, best first: `(bids, asks)`.
+    pub fn depth(&self, n: usize) -> (Vec<PriceLevel>, Vec<PriceLevel>) {
+        let bids = self
+            .bids
+            .iter()
+            .rev()
+            .take(n)
+            .map(|(p, s)| PriceLevel { price: p.0, size: *s })
+            .collect();
+        let asks = self
+            .asks
+            .iter()
+            .take(n)
+            .map(|(p, s)| PriceLevel { price: p.0, size: *s })
+            .collect();
+        (bids, asks)
+    }
+
+    /// Returns `true` if both sides are empty.
+    pub fn is_empty(&self) -> bool {
+        self.bids.is_empty() && self.asks.is_empty()
+    }
+}
+
+fn parse_number(value: &Value) -> Option<f64> {
+    value
+        .as_str()
+        .and_then(|s| s.parse::<f64>().ok())
+        .or_else(|| value.as_f64())
+}
+
+fn parse_level(entry: &Value) -> Result<(f64, f64)> {
+    let price = parse_number(&entry["price"])
+        .ok_or_else(|| ApiError::Api(format!("Order book level without price: {}", entry)))?;
+    let size = parse_number(&entry["size"])
+        .or_else(|| parse_number(&entry["remaining_base_amount"]))
+        .ok_or_else(|| ApiError::Api(format!("Order book level without size: {}", entry)))?;
+    Ok((price, size))
+}
+
+impl LighterClient {
+    /// Fetch an order book snapshot for a market
+    ///
+    /// # Arguments
+    /// * `market_id` - Market index
+    /// * `limit` - Maximum number of orders per side
+    pub async fn get_order_book_snapshot(&self, market_id: u8, limit: u32) -> Result<OrderBook> {
+        let response = self
+            .client
+            .get(format!("{}/api/v1/orderBookOrders", self.base_url))
+            .query(&[("market_id", market_id.to_string()), ("limit", limit.to_string())])
+            .send()
+            .await?;
+
+        let response_json = parse_response(response).await?;
+        if let Some(code) = response_json["code"].as_i64() {
+            if code != 200 {
+                return Err(ApiError::Api(format!("Order book request failed: {}", response_json)));
+            }
+        }
+
+        OrderBook::from_snapshot(market_id, &response_json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_snapshot_aggregates_orders_by_price() {
+        let snapshot = json!({
+            "code": 200,
+            "bids": [
+                {"order_index": 1, "price": "99.5", "remaining_base_amount": "1.0"},
+                {"order_index": 2, "price": "99.5", "remaining_base_amount": "2.0"},
+                {"order_index": 3, "price": "98.0", "remaining_base_amount": "5.0"}
+            ],
+            "asks": [
+                {"order_index": 4, "price": "100.5", "remaining_base_amount": "4.0"},
+                {"order_index": 5, "price": "101.0", "remaining_base_amount": "1.5"}
+            ]
+        });
+
+        let book = OrderBook::from_snapshot(1, &snapshot).unwrap();
+        assert_eq!(book.best_bid(), Some(PriceLevel { price: 99.5, size: 3.0 }));
+        assert_eq!(book.best_ask(), Some(PriceLevel { price: 100.5, size: 4.0 }));
+        assert_eq!(book.mid(), Some(100.0));
+        assert_eq!(book.spread(), Some(1.0));
+
+        let (bids, asks) = book.depth(5);
+        assert_eq!(bids.iter().map(|l| l.price).collect::<Vec<_>>(), vec![99.5, 98.0]);
+        assert_eq!(asks.iter().map(|l| l.price).collect::<Vec<_>>(), vec![100.5, 101.0]);
+    }
+
+    #[test]
+    fn test_deltas_replace_remove_and_skip_stale() {
+        let mut book = OrderBook::from_snapshot(
+            0,
+            &json!({
+                "offset": 10,
+                "order_book": {
+                    "bids": [{"price": "10.0", "size": "1"}],
+                    "asks": [{"price": "11.0", "size": "1"}, {"price": "12.0", "size": "3"}]
+                }
+            }),
+        )
+        .unwrap();
+
+        let applied = book
+            .apply_delta(&json!({
+                "offset": 11,
+                "type": "update/order_book",
+                "order_book": {
+                    "bids": [{"price": "10.5", "size": "2"}],
+                    "asks": [{"price": "11.0", "size": "0"}]
+                }
+            }))
+            .unwrap();
+        assert!(applied);
+        assert_eq!(book.best_bid(), Some(PriceLevel { price: 10.5, size: 2.0 }));
+        assert_eq!(book.best_ask(), Some(PriceLevel { price: 12.0, size: 3.0 }));
+
+        let stale = book
+            .apply_delta(&json!({"offset": 11, "order_book": {"asks": [{"price": "11.5", "size": "1"}]}}))
+            .unwrap();
+        assert!(!stale);
+        assert_eq!(book.best_ask().unwrap().price, 12.0);
+        assert_eq!(book.offset(), Some(11));
+    }
+}
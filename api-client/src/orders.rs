@@ -0,0 +1,256 @@
+//! Typed order listings.
+//!
+//! `accountActiveOrders` and `accountInactiveOrders` return the account's orders as JSON.
+//! `get_open_orders` and `get_inactive_orders` parse them into `Order`s; inactive orders are
+//! paged with a cursor and can be filtered by market, side and time range.
+
+use crate::decimal::{string_or_number_decimal, Decimal};
+use crate::{ApiError, LighterClient, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Lifecycle state of an order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OrderStatus {
+    /// Accepted, not matched yet.
+    InProgress,
+    /// Trigger order waiting for its trigger price.
+    Pending,
+    /// Resting on the book.
+    Open,
+    Filled,
+    Canceled,
+    CanceledPostOnly,
+    CanceledReduceOnly,
+    CanceledPositionNotAllowed,
+    CanceledMarginNotAllowed,
+    CanceledTooMuchSlippage,
+    CanceledNotEnoughLiquidity,
+    CanceledSelfTrade,
+    CanceledExpired,
+    CanceledOco,
+    CanceledChild,
+    CanceledLiquidation,
+    /// A status this client does not know about yet.
+    #[serde(other)]
+    Unknown,
+}
+
+impl OrderStatus {
+    /// Returns `true` while the order can still fill.
+    pub fn is_active(&self) -> bool {
+        matches!(self, OrderStatus::InProgress | OrderStatus::Pending | OrderStatus::Open)
+    }
+
+    /// Returns `true` for every cancellation reason.
+    pub fn is_canceled(&self) -> bool {
+        !matches!(
+            self,
+            OrderStatus::InProgress | OrderStatus::Pending | OrderStatus::Open | OrderStatus::Filled | OrderStatus::Unknown
+        )
+    }
+}
+
+/// An order of the account. Amounts and prices are decimal strings, e.g. `"0.1000"`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict", serde(deny_unknown_fields))]
+pub struct Order {
+    /// Exchange-assigned index, used by `cancel_order` and `modify_order`.
+    pub order_index: i64,
+    pub client_order_index: i64,
+    pub market_index: u8,
+    pub is_ask: bool,
+    pub status: OrderStatus,
+    /// `"limit"`, `"market"`, `"stop-loss"`, ...
+    #[serde(rename = "type", default)]
+    pub order_type: String,
+    /// `"good-till-time"`, `"immediate-or-cancel"` or `"post-only"`.
+    #[serde(default)]
+    pub time_in_force: String,
+    #[serde(default)]
+    pub reduce_only: bool,
+    /// Limit price in quote currency.
+    #[serde(with = "string_or_number_decimal")]
+    pub price: Decimal,
+    #[serde(with = "string_or_number_decimal", default)]
+    pub trigger_price: Decimal,
+    /// Sizes in base currency.
+    #[serde(with = "string_or_number_decimal", default)]
+    pub initial_base_amount: Decimal,
+    #[serde(with = "string_or_number_decimal", default)]
+    pub remaining_base_amount: Decimal,
+    #[serde(with = "string_or_number_decimal", default)]
+    pub filled_base_amount: Decimal,
+    #[serde(with = "string_or_number_decimal", default)]
+    pub filled_quote_amount: Decimal,
+    /// Expiry of a resting order (ms), 0 for none.
+    #[serde(default)]
+    pub order_expiry: i64,
+    #[serde(default)]
+    pub timestamp: i64,
+}
+
+/// One page of orders.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OrderPage {
+    #[serde(default)]
+    pub orders: Vec<Order>,
+    /// Cursor for the next page, if any.
+    #[serde(default)]
+    pub next_cursor: Option<String>,
+}
+
+/// Which inactive orders `get_inactive_orders` returns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InactiveOrderFilter {
+    /// Restrict to one market, or `None` for all.
+    pub market_index: Option<u8>,
+    /// `Some(true)` for asks only, `Some(false)` for bids only.
+    pub is_ask: Option<bool>,
+    /// Only orders created in `[start, end]` (seconds).
+    pub between_timestamps: Option<(i64, i64)>,
+    /// Page size (the exchange allows 1 to 100).
+    pub limit: u32,
+    /// Cursor returned by the previous page, or `None` for the first page.
+    pub cursor: Option<String>,
+}
+
+impl Default for InactiveOrderFilter {
+    fn default() -> Self {
+        Self {
+            market_index: None,
+            is_ask: None,
+            between_timestamps: None,
+            limit: 100,
+            cursor: None,
+        }
+    }
+}
+
+impl InactiveOrderFilter {
+    fn query(&self) -> Vec<(&'static str, String)> {
+        let mut query = vec![("limit", self.limit.clamp(1, 100).to_string())];
+        if let Some(market_index) = self.market_index {
+            query.push(("market_id", market_index.to_string()));
+        }
+        if let Some(is_ask) = self.is_ask {
+            query.push(("ask_filter", u8::from(is_ask).to_string()));
+        }
+        if let Some((start, end)) = self.between_timestamps {
+            query.push(("between_timestamps", format!("{}-{}", start, end)));
+        }
+        if let Some(cursor) = &self.cursor {
+            query.push(("cursor", cursor.clone()));
+        }
+        query
+    }
+}
+
+impl LighterClient {
+    /// Open orders of this account
+    ///
+    /// # Arguments
+    /// * `market_index` - Restrict to one market, or `None` to query every market in turn
+    pub async fn get_open_orders(&self, market_index: Option<u8>) -> Result<Vec<Order>> {
+        let markets = match market_index {
+            Some(market_index) => vec![market_index],
+            None => self
+                .get_markets()
+                .await?
+                .iter()
+                .filter_map(|market| market["market_id"].as_u64())
+                .filter_map(|id| u8::try_from(id).ok())
+                .collect(),
+        };
+
+        let mut orders = Vec::new();
+        for market_index in markets {
+            let response = self.get_active_orders(market_index).await?;
+            orders.extend(self.parse_typed::<OrderPage>("/api/v1/accountActiveOrders", response)?.orders);
+        }
+        Ok(orders)
+    }
+
+    /// Cancel the open order sent with `client_order_index`
+    ///
+    /// Looks the order up among the open orders of the market to find its exchange order
+    /// index. Fails with `ApiError::InvalidOrder` if no open order carries that id, e.g.
+    /// because it already filled or was never accepted.
+    pub async fn cancel_order_by_client_id(&self, market_index: u8, client_order_index: i64) -> Result<Value> {
+        let order = self
+            .get_open_orders(Some(market_index))
+            .await?
+            .into_iter()
+            .find(|order| order.client_order_index == client_order_index)
+            .ok_or_else(|| {
+                ApiError::InvalidOrder(format!(
+                    "No open order with client_order_index {} on market {}",
+                    client_order_index, market_index
+                ))
+            })?;
+        self.cancel_order(market_index, order.order_index).await
+    }
+
+    /// Filled, cancelled and expired orders of this account, one page at a time
+    ///
+    /// Pass the returned `next_cursor` in `filter.cursor` to get the next page.
+    pub async fn get_inactive_orders(&self, filter: &InactiveOrderFilter) -> Result<OrderPage> {
+        let mut query = vec![("account_index", self.account_index.to_string())];
+        query.extend(filter.query());
+        let response = self.get_authenticated_json("/api/v1/accountInactiveOrders", &query).await?;
+        self.parse_typed("/api/v1/accountInactiveOrders", response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_orders_and_filter() {
+        let response = json!({
+            "code": 200,
+            "orders": [
+                {"order_index": 281474976710657i64, "client_order_index": 12, "market_index": 0, "is_ask": false,
+                 "status": "open", "type": "limit", "time_in_force": "good-till-time", "reduce_only": false,
+                 "price": "3000.00", "initial_base_amount": "0.1000", "remaining_base_amount": "0.0400",
+                 "filled_base_amount": "0.0600", "order_expiry": 1760000000000i64, "timestamp": 1759990000},
+                {"order_index": 5, "client_order_index": 13, "market_index": 1, "is_ask": true,
+                 "status": "canceled-post-only", "price": "61000.0"},
+                {"order_index": 6, "client_order_index": 14, "market_index": 1, "is_ask": true,
+                 "status": "queued", "price": "61000.0"}
+            ],
+            "next_cursor": "abc"
+        });
+        let page: OrderPage = serde_json::from_value(response).unwrap();
+        assert_eq!(page.next_cursor.as_deref(), Some("abc"));
+        assert_eq!(page.orders[0].status, OrderStatus::Open);
+        assert!(page.orders[0].status.is_active());
+        assert_eq!(page.orders[0].remaining_base_amount, Decimal::new(400, 4));
+        assert_eq!(page.orders[0].filled_quote_amount, Decimal::ZERO);
+        assert_eq!(page.orders[1].status, OrderStatus::CanceledPostOnly);
+        assert!(page.orders[1].status.is_canceled());
+        assert_eq!(page.orders[2].status, OrderStatus::Unknown);
+        assert!(!page.orders[2].status.is_canceled());
+
+        let filter = InactiveOrderFilter {
+            market_index: Some(1),
+            is_ask: Some(true),
+            between_timestamps: Some((1, 2)),
+            limit: 500,
+            cursor: Some("abc".to_string()),
+        };
+        assert_eq!(
+            filter.query(),
+            vec![
+                ("limit", "100".to_string()),
+                ("market_id", "1".to_string()),
+                ("ask_filter", "1".to_string()),
+                ("between_timestamps", "1-2".to_string()),
+                ("cursor", "abc".to_string()),
+            ]
+        );
+    }
+}
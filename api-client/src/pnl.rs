@@ -0,0 +1,183 @@
+//! Periodic PnL snapshots.
+//!
+//! `stream_pnl` polls the account at a fixed interval and yields a `PnlSnapshot` each time:
+//! equity, margin usage and, per open position, its unrealized and realized PnL and the
+//! funding it has paid. Unlike `poll_account_stream`, every poll yields, so a dashboard can
+//! plot the values as they move with the mark price.
+
+use crate::balance::{decimal, BalanceSummary};
+use crate::positions::account_entry;
+use crate::{LighterClient, Result};
+use futures_core::Stream;
+use serde::Serialize;
+use serde_json::Value;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// PnL of one open position, in USDC unless noted.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PositionPnl {
+    pub market_index: u8,
+    /// Size in base currency, negative when short.
+    pub size: f64,
+    pub avg_entry_price: f64,
+    /// Absolute value of the position at the mark price.
+    pub position_value: f64,
+    pub unrealized_pnl: f64,
+    pub realized_pnl: f64,
+    /// Funding paid since the position was opened, negative when it received funding;
+    /// `None` if the account response does not report it.
+    pub funding_paid: Option<f64>,
+}
+
+/// Account PnL at one poll.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PnlSnapshot {
+    /// Milliseconds since the Unix epoch when the account was read.
+    pub timestamp_ms: u64,
+    pub equity: f64,
+    pub unrealized_pnl: f64,
+    /// Initial margin of the open positions over equity: 0 when flat, 1 when no collateral
+    /// is free. `None` when equity is not positive.
+    pub margin_ratio: Option<f64>,
+    /// Open positions, by market index.
+    pub positions: Vec<PositionPnl>,
+}
+
+impl PnlSnapshot {
+    /// Reads the snapshot from a `get_account` response.
+    pub fn from_account(account_info: &Value, timestamp_ms: u64) -> Result<Self> {
+        let summary = BalanceSummary::from_account(account_info)?;
+        let mut positions: Vec<PositionPnl> = account_entry(account_info)["positions"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(position_pnl)
+            .collect();
+        positions.sort_by_key(|position| position.market_index);
+
+        Ok(Self {
+            timestamp_ms,
+            equity: summary.equity,
+            unrealized_pnl: summary.unrealized_pnl,
+            margin_ratio: (summary.equity > 0.0).then(|| summary.total_margin / summary.equity),
+            positions,
+        })
+    }
+}
+
+/// PnL of an account `positions` entry, `None` for a flat or unreadable one.
+fn position_pnl(position: &Value) -> Option<PositionPnl> {
+    let size = decimal(&position["position"])?;
+    if size == 0.0 {
+        return None;
+    }
+    // sign: 1 = long, -1 = short; some responses also sign the size itself
+    let sign = position["sign"].as_i64().unwrap_or(1) as f64 * size.signum();
+    Some(PositionPnl {
+        market_index: u8::try_from(position["market_id"].as_u64()?).ok()?,
+        size: size.abs().copysign(sign),
+        avg_entry_price: decimal(&position["avg_entry_price"]).unwrap_or(0.0),
+        position_value: decimal(&position["position_value"]).unwrap_or(0.0).abs(),
+        unrealized_pnl: decimal(&position["unrealized_pnl"]).unwrap_or(0.0),
+        realized_pnl: decimal(&position["realized_pnl"]).unwrap_or(0.0),
+        funding_paid: decimal(&position["total_funding_paid_out"]),
+    })
+}
+
+/// Stream of `PnlSnapshot`s from `stream_pnl`.
+///
+/// A failed poll yields its error and polling continues. Dropping the stream stops polling.
+pub struct PnlStream {
+    receiver: mpsc::Receiver<Result<PnlSnapshot>>,
+    task: JoinHandle<()>,
+}
+
+impl Stream for PnlStream {
+    type Item = Result<PnlSnapshot>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+impl PnlStream {
+    /// Wait for the next snapshot; `None` once polling has stopped
+    pub async fn next_snapshot(&mut self) -> Option<Result<PnlSnapshot>> {
+        self.receiver.recv().await
+    }
+}
+
+impl Drop for PnlStream {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl LighterClient {
+    /// Current PnL of the account and its open positions
+    pub async fn get_pnl_snapshot(&self) -> Result<PnlSnapshot> {
+        let account_info = self.get_account().await?;
+        let timestamp_ms = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as u64;
+        PnlSnapshot::from_account(&account_info, timestamp_ms)
+    }
+
+    /// Poll this account every `interval` and stream a `PnlSnapshot` per poll
+    ///
+    /// Snapshots are buffered up to 16; while the consumer lags behind, polling waits.
+    pub fn stream_pnl(self: &Arc<Self>, interval: Duration) -> PnlStream {
+        let (sender, receiver) = mpsc::channel(16);
+        let client = Arc::clone(self);
+
+        let task = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                if sender.send(client.get_pnl_snapshot().await).await.is_err() {
+                    return;
+                }
+            }
+        });
+
+        PnlStream { receiver, task }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_snapshot_from_account() {
+        let account = json!({"code": 200, "total": 1, "accounts": [{
+            "collateral": "1000.000000",
+            "available_balance": "700.000000",
+            "total_asset_value": "1015.000000",
+            "positions": [
+                {"market_id": 1, "position": "2.0", "sign": -1, "avg_entry_price": "150", "position_value": "290.00",
+                 "initial_margin_fraction": "20.00", "unrealized_pnl": "10", "realized_pnl": "-1.5"},
+                {"market_id": 0, "position": "0.50", "sign": 1, "avg_entry_price": "3000", "position_value": "1505.00",
+                 "initial_margin_fraction": "10.00", "unrealized_pnl": "5", "realized_pnl": "0",
+                 "total_funding_paid_out": "0.75"},
+                {"market_id": 2, "position": "0.0", "sign": 1, "avg_entry_price": "0"}
+            ]
+        }]});
+
+        let snapshot = PnlSnapshot::from_account(&account, 1_700_000_000_000).unwrap();
+        assert_eq!(snapshot.equity, 1015.0);
+        assert_eq!(snapshot.unrealized_pnl, 15.0);
+        // 1505 * 10% + 290 * 20% = 208.5 of margin
+        assert!((snapshot.margin_ratio.unwrap() - 208.5 / 1015.0).abs() < 1e-12);
+
+        assert_eq!(snapshot.positions.iter().map(|p| p.market_index).collect::<Vec<_>>(), vec![0, 1]);
+        let (long, short) = (&snapshot.positions[0], &snapshot.positions[1]);
+        assert_eq!((long.size, long.funding_paid), (0.5, Some(0.75)));
+        assert_eq!((short.size, short.realized_pnl, short.funding_paid), (-2.0, -1.5, None));
+    }
+}
@@ -0,0 +1,225 @@
+//! Position lookups and sized reduce-only closes.
+//!
+//! Positions are reported as decimal strings; closing orders need integer base amounts,
+//! so the market's `size_decimals` is fetched from `orderBookDetails` to convert them.
+
+use crate::{parse_response, Alert, ApiError, BaseAmount, CreateOrderRequest, LighterClient, OrderType, Price, Result, TimeInForce};
+use serde_json::Value;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// An open position converted to order units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PositionSize {
+    pub market_index: u8,
+    /// Absolute size in base amount units.
+    pub base_amount: i64,
+    pub is_long: bool,
+}
+
+impl LighterClient {
+    /// Get the open position on a market, or `None` if the account is flat there
+    pub async fn get_position_size(&self, market_index: u8) -> Result<Option<PositionSize>> {
+        let account_info = self.get_account().await?;
+        let position = match find_position(&account_info, market_index) {
+            Some(position) => position,
+            None => return Ok(None),
+        };
+
+        let size_decimals = self.market_size_decimals(market_index).await?;
+        position_size(position, market_index, size_decimals)
+    }
+
+    /// Close an explicit amount of a position with a reduce-only market order
+    ///
+    /// # Arguments
+    /// * `market_index` - Market index
+    /// * `is_ask` - `true` to close a long (sell), `false` to close a short (buy)
+    /// * `base_amount` - Size to close in base amount units
+    pub async fn close_position_with_size(&self, market_index: u8, is_ask: bool, base_amount: i64) -> Result<Value> {
+        if base_amount <= 0 {
+            return Err(ApiError::Api(format!("Close size must be positive, got {}", base_amount)));
+        }
+
+        let order = CreateOrderRequest {
+            account_index: self.account_index,
+            order_book_index: market_index,
+            client_order_index: SystemTime::now()
+                .duration_since(UNIX_EPOCH)?
+                .as_millis() as u64,
+            base_amount: BaseAmount(base_amount),
+            price: Price::ZERO, // Market order
+            is_ask,
+            order_type: OrderType::Market,
+            time_in_force: TimeInForce::ImmediateOrCancel,
+            reduce_only: true, // Only reduce position
+            trigger_price: Price::ZERO,
+            price_protection: None,
+            order_expiry: None,
+        };
+
+        let result = self.create_order(order).await;
+        match &result {
+            Ok(response) if response["code"].as_i64() != Some(200) => {
+                self.notify(Alert::close_failed(self.account_index, market_index, response));
+            }
+            Err(e) => self.notify(Alert::close_failed(self.account_index, market_index, e)),
+            Ok(_) => {}
+        }
+        result
+    }
+
+    /// Close a fraction of the open position on a market
+    ///
+    /// The direction is taken from the position. `fraction` must be in `(0, 1]`;
+    /// the resulting size is rounded to the nearest unit, and at least one unit is closed.
+    pub async fn close_position_partial(&self, market_index: u8, fraction: f64) -> Result<Value> {
+        if !(fraction > 0.0 && fraction <= 1.0) {
+            return Err(ApiError::Api(format!("Close fraction must be in (0, 1], got {}", fraction)));
+        }
+
+        let position = self
+            .get_position_size(market_index)
+            .await?
+            .ok_or_else(|| ApiError::Api(format!("No open position on market {}", market_index)))?;
+
+        let base_amount = fraction_of(position.base_amount, fraction);
+        self.close_position_with_size(market_index, position.is_long, base_amount)
+            .await
+    }
+
+    pub(crate) async fn market_size_decimals(&self, market_index: u8) -> Result<u32> {
+        self.order_book_details(market_index).await?["size_decimals"]
+            .as_u64()
+            .map(|d| d as u32)
+            .ok_or_else(|| ApiError::Api(format!("No size decimals for market {}", market_index)))
+    }
+
+    /// The `orderBookDetails` entry of a market
+    pub(crate) async fn order_book_details(&self, market_index: u8) -> Result<Value> {
+        let response = self
+            .client
+            .get(format!("{}/api/v1/orderBookDetails", self.base_url))
+            .query(&[("market_id", market_index.to_string())])
+            .send()
+            .await?;
+
+        let response_json = parse_response(response).await?;
+        if let Some(code) = response_json["code"].as_i64() {
+            if code != 200 {
+                return Err(ApiError::Api(format!("Order book details request failed: {}", response_json)));
+            }
+        }
+
+        response_json["order_book_details"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|d| d["market_id"].as_u64() == Some(market_index as u64))
+            .cloned()
+            .ok_or_else(|| ApiError::Api(format!("No details for market {}", market_index)))
+    }
+}
+
+/// The account object of a `get_account` response (first of `accounts`, or the response itself).
+pub(crate) fn account_entry(account_info: &Value) -> &Value {
+    account_info["accounts"]
+        .as_array()
+        .and_then(|a| a.first())
+        .unwrap_or(account_info)
+}
+
+/// Finds the position entry for `market_index` in a `get_account` response.
+pub(crate) fn find_position(account_info: &Value, market_index: u8) -> Option<&Value> {
+    account_entry(account_info)["positions"]
+        .as_array()?
+        .iter()
+        .find(|p| p["market_id"].as_u64() == Some(market_index as u64))
+}
+
+/// Size of an account `positions` entry in base amount units, `None` if flat.
+pub(crate) fn position_size(position: &Value, market_index: u8, size_decimals: u32) -> Result<Option<PositionSize>> {
+    let amount = position["position"]
+        .as_str()
+        .and_then(|s| decimal_to_units(s, size_decimals))
+        .ok_or_else(|| ApiError::Api(format!("Unreadable position size: {}", position["position"])))?;
+    if amount == 0 {
+        return Ok(None);
+    }
+
+    // sign: 1 = long, -1 = short; some responses also sign the size itself
+    let sign = position["sign"].as_i64().unwrap_or(1) * amount.signum();
+    Ok(Some(PositionSize {
+        market_index,
+        base_amount: amount.abs(),
+        is_long: sign > 0,
+    }))
+}
+
+/// Parses a decimal string such as `"-1.2300"` into integer units with `decimals` places.
+///
+/// Digits beyond `decimals` are truncated.
+pub(crate) fn decimal_to_units(value: &str, decimals: u32) -> Option<i64> {
+    let value = value.trim();
+    let (negative, digits) = match value.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, value),
+    };
+    let (whole, frac) = digits.split_once('.').unwrap_or((digits, ""));
+    if whole.is_empty() && frac.is_empty() {
+        return None;
+    }
+
+    let mut units: i64 = if whole.is_empty() { 0 } else { whole.parse().ok()? };
+    let mut frac_digits = frac.chars();
+    for _ in 0..decimals {
+        let digit = match frac_digits.next() {
+            Some(c) => c.to_digit(10)? as i64,
+            None => 0,
+        };
+        units = units.checked_mul(10)?.checked_add(digit)?;
+    }
+    if !frac_digits.all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    Some(if negative { -units } else { units })
+}
+
+fn fraction_of(base_amount: i64, fraction: f64) -> i64 {
+    ((base_amount as f64 * fraction).round() as i64).clamp(1, base_amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_decimal_to_units() {
+        assert_eq!(decimal_to_units("1.2345", 4), Some(12345));
+        assert_eq!(decimal_to_units("-0.5", 4), Some(-5000));
+        assert_eq!(decimal_to_units("3", 2), Some(300));
+        assert_eq!(decimal_to_units("0.123456", 4), Some(1234));
+        assert_eq!(decimal_to_units("1.2x", 4), None);
+        assert_eq!(decimal_to_units("", 4), None);
+    }
+
+    #[test]
+    fn test_find_position_and_fraction() {
+        let account = json!({
+            "code": 200,
+            "accounts": [{
+                "positions": [
+                    {"market_id": 0, "sign": 1, "position": "0.0000"},
+                    {"market_id": 1, "sign": -1, "position": "2.5000"}
+                ]
+            }]
+        });
+        assert_eq!(find_position(&account, 1).unwrap()["sign"], -1);
+        assert!(find_position(&account, 7).is_none());
+
+        assert_eq!(fraction_of(25_000, 0.5), 12_500);
+        assert_eq!(fraction_of(3, 0.01), 1);
+        assert_eq!(fraction_of(3, 1.0), 3);
+    }
+}
@@ -0,0 +1,196 @@
+//! Pre-trade preview of an order's effect on the account.
+//!
+//! `preview_order` combines the market's details with the account's balance, position and
+//! leverage to estimate what filling an order would cost: fee, change in initial margin,
+//! the available balance left and how the liquidation price moves. The exchange has no
+//! preview endpoint, so the figures are computed locally, at the order's price.
+//!
+//! The liquidation price is that of a cross-margin account whose other positions do not
+//! move: equity must stay above this position's maintenance margin plus the other
+//! positions' margin. Those are counted at their initial margin, which is at least the
+//! maintenance margin, so the estimate errs towards a closer liquidation.
+
+use crate::balance::{decimal, BalanceSummary};
+use crate::fees::MARGIN_FRACTION_SCALE;
+use crate::leverage::leverage_from_account;
+use crate::positions::{account_entry, find_position};
+use crate::{CreateOrderRequest, LighterClient, MarketDetails, Result};
+use serde_json::Value;
+
+/// Estimated effect of filling an order, in quote currency (USDC) unless noted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrderPreview {
+    /// Order value at its price.
+    pub notional: f64,
+    /// Fee of a full fill.
+    pub fee: f64,
+    /// Position on the market before and after the fill, in base currency (negative for shorts).
+    pub position_before: f64,
+    pub position_after: f64,
+    /// Change of the initial margin the position ties up; negative when it shrinks.
+    pub margin_change: f64,
+    /// Available balance left after the margin change and the fee.
+    pub available_after: f64,
+    /// Estimated liquidation price before and after the fill, `None` without a position.
+    pub liquidation_price_before: Option<f64>,
+    pub liquidation_price_after: Option<f64>,
+}
+
+impl OrderPreview {
+    /// Previews `order` from the market's details and a `get_account` response.
+    pub fn compute(order: &CreateOrderRequest, details: &MarketDetails, account_info: &Value) -> Result<Self> {
+        let balance = BalanceSummary::from_account(account_info)?;
+        let rules = &details.rules;
+        let market_index = order.order_book_index;
+
+        let price = order.price.0 as f64 / 10f64.powi(rules.price_decimals as i32);
+        let size = order.base_amount.0 as f64 / 10f64.powi(rules.size_decimals as i32);
+        let position_before = find_position(account_info, market_index).map(signed_position).unwrap_or(0.0);
+        let mut position_after = position_before + if order.is_ask { -size } else { size };
+        if order.reduce_only && position_after * position_before < 0.0 {
+            // Reduce-only orders stop at flat
+            position_after = 0.0;
+        }
+
+        let initial_fraction = leverage_from_account(account_info, market_index)
+            .map(|setting| setting.initial_margin_fraction)
+            .unwrap_or(details.fees.default_initial_margin_fraction) as f64
+            / MARGIN_FRACTION_SCALE as f64;
+        let maintenance_fraction = details.fees.maintenance_margin_fraction as f64 / MARGIN_FRACTION_SCALE as f64;
+
+        let notional = rules.notional(order.base_amount, order.price);
+        let fee = notional * details.fees.fee_rate(order);
+        let margin_change = (position_after.abs() - position_before.abs()) * price * initial_fraction;
+
+        // Initial margin of the positions on other markets
+        let other_margin: f64 = account_entry(account_info)["positions"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|p| p["market_id"].as_u64() != Some(market_index as u64))
+            .map(|p| {
+                let value = decimal(&p["position_value"]).unwrap_or(0.0).abs();
+                value * decimal(&p["initial_margin_fraction"]).unwrap_or(0.0) / 100.0
+            })
+            .sum();
+        let buffer = balance.equity - other_margin;
+
+        Ok(Self {
+            notional,
+            fee,
+            position_before,
+            position_after,
+            margin_change,
+            available_after: balance.available_balance - margin_change - fee,
+            liquidation_price_before: liquidation_price(position_before, price, buffer, maintenance_fraction),
+            liquidation_price_after: liquidation_price(position_after, price, buffer - fee, maintenance_fraction),
+        })
+    }
+
+    /// Whether the available balance covers the margin and the fee.
+    pub fn is_affordable(&self) -> bool {
+        self.available_after >= 0.0 || self.margin_change <= 0.0
+    }
+}
+
+/// Signed size of a position entry (`sign` 1 = long, -1 = short).
+fn signed_position(position: &Value) -> f64 {
+    let size = decimal(&position["position"]).unwrap_or(0.0);
+    position["sign"].as_f64().unwrap_or(1.0) * size
+}
+
+/// Price at which equity `buffer` (valued at `price`) falls to the position's maintenance
+/// margin, or `None` if the position is flat or cannot be liquidated.
+///
+/// Solves `buffer + position * (p - price) = |position| * p * maintenance_fraction`.
+fn liquidation_price(position: f64, price: f64, buffer: f64, maintenance_fraction: f64) -> Option<f64> {
+    if position == 0.0 {
+        return None;
+    }
+    let denominator = position.abs() * maintenance_fraction - position;
+    let liquidation = (buffer - position * price) / denominator;
+    (liquidation.is_finite() && liquidation > 0.0).then_some(liquidation)
+}
+
+impl LighterClient {
+    /// Estimate the fee, margin and liquidation price impact of `order` before sending it
+    ///
+    /// Reads the market details and the account; see `OrderPreview` for what is estimated.
+    pub async fn preview_order(&self, order: &CreateOrderRequest) -> Result<OrderPreview> {
+        let details = self.get_market_details(order.order_book_index).await?;
+        let account_info = self.get_account().await?;
+        OrderPreview::compute(order, &details, &account_info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BaseAmount, OrderType, Price, TimeInForce};
+    use serde_json::json;
+
+    #[test]
+    fn test_preview_long_entry() {
+        let details = MarketDetails::from_order_book_details(&json!({
+            "market_id": 0,
+            "price_decimals": 2,
+            "size_decimals": 4,
+            "maker_fee": "0.0000",
+            "taker_fee": "0.0200",
+            "min_initial_margin_fraction": 500,
+            "default_initial_margin_fraction": 1000,
+            "maintenance_margin_fraction": 300,
+        }))
+        .unwrap();
+        let account = json!({"accounts": [{
+            "collateral": "1000.000000",
+            "available_balance": "1000.000000",
+            "total_asset_value": "1000.000000",
+            "positions": [],
+        }]});
+        // Buy 1 ETH at 3000.00 with the default 10x
+        let mut order = CreateOrderRequest {
+            account_index: 1,
+            order_book_index: 0,
+            client_order_index: 1,
+            base_amount: BaseAmount(10_000),
+            price: Price(300_000),
+            is_ask: false,
+            order_type: OrderType::Limit,
+            time_in_force: TimeInForce::GoodTillTime,
+            reduce_only: false,
+            trigger_price: Price::ZERO,
+            price_protection: None,
+            order_expiry: None,
+        };
+        let preview = OrderPreview::compute(&order, &details, &account).unwrap();
+        assert!((preview.fee - 0.6).abs() < 1e-9);
+        assert!((preview.margin_change - 300.0).abs() < 1e-9);
+        assert!((preview.available_after - 699.4).abs() < 1e-9);
+        assert_eq!((preview.position_before, preview.position_after), (0.0, 1.0));
+        assert_eq!(preview.liquidation_price_before, None);
+        // 999.4 + (p - 3000) = 0.03 p  =>  p = 2000.6 / 0.97
+        let liquidation = preview.liquidation_price_after.unwrap();
+        assert!((liquidation - 2000.6 / 0.97).abs() < 1e-6);
+        assert!(preview.is_affordable());
+
+        // 5 ETH needs 1500 of margin
+        order.base_amount = BaseAmount(50_000);
+        assert!(!OrderPreview::compute(&order, &details, &account).unwrap().is_affordable());
+
+        // Reduce-only sell of 2 ETH against a 1 ETH long stops at flat
+        let long = json!({"accounts": [{
+            "collateral": "1000", "available_balance": "700", "total_asset_value": "1000",
+            "positions": [{"market_id": 0, "position": "1.0000", "sign": 1, "position_value": "3000",
+                           "initial_margin_fraction": "10.00"}],
+        }]});
+        order.base_amount = BaseAmount(20_000);
+        order.is_ask = true;
+        order.reduce_only = true;
+        let close = OrderPreview::compute(&order, &details, &long).unwrap();
+        assert_eq!((close.position_before, close.position_after), (1.0, 0.0));
+        assert!((close.margin_change + 300.0).abs() < 1e-9);
+        assert!(close.liquidation_price_before.is_some());
+        assert_eq!(close.liquidation_price_after, None);
+    }
+}
@@ -0,0 +1,295 @@
+//! Keeping a set of quotes on the book with the fewest transactions.
+//!
+//! A market-making loop that cancels everything and re-places it every tick spends two
+//! nonces per quote and sends each order to the back of the queue. `QuoteSet::apply` takes
+//! the quotes wanted on one market, compares them with the account's open orders and sends
+//! only the difference, in one sendTxBatch request:
+//!
+//! - a resting quote that is no longer wanted is cancelled
+//! - one whose price or size changed is modified in place, keeping its order index
+//! - one that changed side, or a new quote, is created
+//! - one that is unchanged is left alone, keeping its queue position
+//!
+//! Quotes are matched by client order index, which names a quote from tick to tick (for
+//! example one per level and side). Only orders carrying the index of a quote the set
+//! placed or is asked to place are touched; other orders of the account are ignored.
+
+use crate::orders::Order;
+use crate::positions::decimal_to_units;
+use crate::{
+    ApiError, BaseAmount, CreateOrderRequest, LighterClient, MarketRules, NonceLease, OrderType, Price, Result,
+    TimeInForce,
+};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+
+/// An order a market maker wants resting on the book.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quote {
+    /// Identifies the quote across ticks; the open order with this index is its previous version.
+    pub client_order_index: u64,
+    pub is_ask: bool,
+    pub price: Price,
+    pub base_amount: BaseAmount,
+}
+
+/// A change `QuoteSet::apply` makes to the open orders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuoteAction {
+    Cancel { client_order_index: u64, order_index: i64 },
+    Modify { client_order_index: u64, order_index: i64, price: Price, base_amount: BaseAmount },
+    Create(Quote),
+}
+
+/// Outcome of `QuoteSet::apply`.
+#[derive(Debug, Clone)]
+pub struct QuoteUpdate {
+    /// Changes sent, cancels first, then modifies, then creates.
+    pub actions: Vec<QuoteAction>,
+    /// Response of the batch; `None` if the book already matched.
+    pub response: Option<Value>,
+}
+
+impl QuoteUpdate {
+    /// Whether nothing had to change.
+    pub fn is_unchanged(&self) -> bool {
+        self.actions.is_empty()
+    }
+}
+
+/// The quotes kept on one market.
+#[derive(Debug, Clone)]
+pub struct QuoteSet {
+    market_index: u8,
+    time_in_force: TimeInForce,
+    /// Client order indices of the quotes this set placed and may still rest.
+    managed: BTreeSet<u64>,
+}
+
+impl QuoteSet {
+    /// Quotes on `market_index`, created post-only.
+    pub fn new(market_index: u8) -> Self {
+        Self {
+            market_index,
+            time_in_force: TimeInForce::PostOnly,
+            managed: BTreeSet::new(),
+        }
+    }
+
+    /// Create quotes with `time_in_force` instead of post-only.
+    pub fn with_time_in_force(mut self, time_in_force: TimeInForce) -> Self {
+        self.time_in_force = time_in_force;
+        self
+    }
+
+    pub fn market_index(&self) -> u8 {
+        self.market_index
+    }
+
+    /// Make the open orders of the market match `desired`
+    ///
+    /// Reads the open orders and market decimals, then sends the changes `diff` finds as one
+    /// batch with consecutive nonces. An empty `desired` cancels every quote of the set.
+    /// Fails with `ApiError::InvalidOrder` if two quotes share a client order index, and
+    /// with `ApiError::Api` if the exchange rejects the batch; the next call starts over
+    /// from the orders then open.
+    pub async fn apply(&mut self, client: &LighterClient, desired: &[Quote]) -> Result<QuoteUpdate> {
+        let rules = client.get_market_rules(self.market_index).await?;
+        let open = client.get_open_orders(Some(self.market_index)).await?;
+        let actions = self.diff(&resting_quotes(&open, &rules), desired)?;
+        // Quotes being placed are managed from now on, even if the batch gets no answer
+        self.managed.extend(desired.iter().map(|quote| quote.client_order_index));
+        if actions.is_empty() {
+            return Ok(QuoteUpdate { actions, response: None });
+        }
+
+        let mut leases = Vec::with_capacity(actions.len());
+        let mut txs = Vec::with_capacity(actions.len());
+        for action in &actions {
+            let lease = client.lease_nonce().await?;
+            txs.push(self.sign(client, action, &lease).await?);
+            leases.push(lease);
+        }
+        let response = client.send_tx_batch(&txs).await;
+        for lease in leases {
+            lease.settle_outcome(&response);
+        }
+        let response = response?;
+        if response["code"].as_i64() != Some(200) {
+            return Err(ApiError::Api(format!("Quote update on market {} failed: {}", self.market_index, response)));
+        }
+
+        self.managed = desired.iter().map(|quote| quote.client_order_index).collect();
+        Ok(QuoteUpdate {
+            actions,
+            response: Some(response),
+        })
+    }
+
+    /// Changes that turn the `resting` quotes (open orders as quotes, with their order
+    /// indices) into `desired`
+    fn diff(&self, resting: &[(i64, Quote)], desired: &[Quote]) -> Result<Vec<QuoteAction>> {
+        let mut wanted = HashMap::new();
+        for quote in desired {
+            if wanted.insert(quote.client_order_index, quote).is_some() {
+                return Err(ApiError::InvalidOrder(format!(
+                    "Client order index {} is used by two quotes",
+                    quote.client_order_index
+                )));
+            }
+        }
+
+        let mut cancels = Vec::new();
+        let mut modifies = Vec::new();
+        let mut kept = BTreeSet::new();
+        for (order_index, current) in resting {
+            let id = current.client_order_index;
+            if !wanted.contains_key(&id) && !self.managed.contains(&id) {
+                continue;
+            }
+            let cancel = QuoteAction::Cancel { client_order_index: id, order_index: *order_index };
+            match wanted.get(&id) {
+                // A second order with the index, or one on the wrong side, cannot be modified
+                Some(quote) if quote.is_ask == current.is_ask && kept.insert(id) => {
+                    if quote.price != current.price || quote.base_amount != current.base_amount {
+                        modifies.push(QuoteAction::Modify {
+                            client_order_index: id,
+                            order_index: *order_index,
+                            price: quote.price,
+                            base_amount: quote.base_amount,
+                        });
+                    }
+                }
+                _ => cancels.push(cancel),
+            }
+        }
+        let creates = desired
+            .iter()
+            .filter(|quote| !kept.contains(&quote.client_order_index))
+            .map(|quote| QuoteAction::Create(*quote));
+
+        Ok(cancels.into_iter().chain(modifies).chain(creates).collect())
+    }
+
+    async fn sign(&self, client: &LighterClient, action: &QuoteAction, lease: &NonceLease) -> Result<(u32, Value)> {
+        let nonce = Some(lease.nonce());
+        let signed = match *action {
+            QuoteAction::Cancel { order_index, .. } => {
+                client.sign_cancel_order_with_nonce(self.market_index, order_index, nonce).await?
+            }
+            QuoteAction::Modify { order_index, price, base_amount, .. } => {
+                client
+                    .sign_modify_order_with_nonce(self.market_index, order_index, base_amount, price, Price::ZERO, nonce)
+                    .await?
+            }
+            QuoteAction::Create(quote) => {
+                let order = CreateOrderRequest {
+                    account_index: client.account_index(),
+                    order_book_index: self.market_index,
+                    client_order_index: quote.client_order_index,
+                    base_amount: quote.base_amount,
+                    price: quote.price,
+                    is_ask: quote.is_ask,
+                    order_type: OrderType::Limit,
+                    time_in_force: self.time_in_force,
+                    reduce_only: false,
+                    trigger_price: Price::ZERO,
+                    price_protection: None,
+                    order_expiry: None,
+                };
+                client.sign_create_order_with_nonce(order, nonce).await?
+            }
+        };
+        Ok((signed.tx_type, signed.tx_info))
+    }
+}
+
+/// Open limit orders as quotes in price and size units, with their order indices; the
+/// size is what remains to fill.
+fn resting_quotes(orders: &[Order], rules: &MarketRules) -> Vec<(i64, Quote)> {
+    orders
+        .iter()
+        .filter(|order| order.order_type == "limit" && order.status.is_active())
+        .filter_map(|order| {
+            let price = decimal_to_units(&order.price.to_string(), rules.price_decimals)?;
+            let size = decimal_to_units(&order.remaining_base_amount.to_string(), rules.size_decimals)?;
+            let quote = Quote {
+                client_order_index: u64::try_from(order.client_order_index).ok()?,
+                is_ask: order.is_ask,
+                price: Price::try_from(price).ok()?,
+                base_amount: BaseAmount(size),
+            };
+            Some((order.order_index, quote))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quote(client_order_index: u64, is_ask: bool, price: u64, size: i64) -> Quote {
+        Quote {
+            client_order_index,
+            is_ask,
+            price: Price(price),
+            base_amount: BaseAmount(size),
+        }
+    }
+
+    #[test]
+    fn test_diff_sends_only_changes() {
+        let mut set = QuoteSet::new(0);
+        set.managed.extend([1, 2, 3, 4]);
+        let resting = vec![
+            (101, quote(1, false, 300_000, 1_000)), // unchanged
+            (102, quote(2, false, 299_900, 1_000)), // repriced
+            (103, quote(3, true, 300_100, 1_000)),  // no longer wanted
+            (104, quote(4, true, 300_200, 1_000)),  // flipped side
+            (105, quote(9, true, 300_300, 1_000)),  // not a quote of the set
+        ];
+        let desired = [
+            quote(1, false, 300_000, 1_000),
+            quote(2, false, 299_950, 500),
+            quote(4, false, 299_800, 1_000),
+            quote(5, true, 300_150, 2_000),
+        ];
+
+        let actions = set.diff(&resting, &desired).unwrap();
+        assert_eq!(
+            actions,
+            vec![
+                QuoteAction::Cancel { client_order_index: 3, order_index: 103 },
+                QuoteAction::Cancel { client_order_index: 4, order_index: 104 },
+                QuoteAction::Modify {
+                    client_order_index: 2,
+                    order_index: 102,
+                    price: Price(299_950),
+                    base_amount: BaseAmount(500),
+                },
+                QuoteAction::Create(desired[2]),
+                QuoteAction::Create(desired[3]),
+            ]
+        );
+
+        assert!(set.diff(&resting[..1], &desired[..1]).unwrap().is_empty());
+        assert!(matches!(set.diff(&[], &[desired[0], desired[0]]), Err(ApiError::InvalidOrder(_))));
+    }
+
+    #[test]
+    fn test_resting_quotes_in_units() {
+        let order: Order = serde_json::from_value(serde_json::json!({
+            "order_index": 7, "client_order_index": 3, "market_index": 0, "is_ask": true, "status": "open",
+            "type": "limit", "price": "3001.25", "remaining_base_amount": "0.0500",
+        }))
+        .unwrap();
+        let market = serde_json::from_value::<Order>(serde_json::json!({
+            "order_index": 8, "client_order_index": 4, "market_index": 0, "is_ask": true, "status": "open",
+            "type": "market", "price": "3001.25",
+        }))
+        .unwrap();
+
+        let quotes = resting_quotes(&[order, market], &MarketRules::new(2, 4));
+        assert_eq!(quotes, vec![(7, quote(3, true, 300_125, 500))]);
+    }
+}
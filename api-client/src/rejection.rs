@@ -0,0 +1,138 @@
+//! Exchange rejections as errors.
+//!
+//! Transaction methods return the exchange's JSON response as `Ok` whatever its `code`, so a
+//! rejected order only shows up if the caller checks the code. `CheckedResponse::checked`
+//! turns any code other than 200 into `ApiError::Rejected`, which keeps the code, message and
+//! full response, so trading logic can propagate rejections with `?`:
+//!
+//! ```ignore
+//! use api_client::CheckedResponse;
+//!
+//! let response = client.cancel_order(0, 7).await.checked()?;
+//! ```
+//!
+//! `RejectionKind` sorts the codes the client knows into the cases a caller usually handles
+//! differently.
+
+use crate::nonce::is_nonce_error;
+use crate::retry::{retry_after, CODE_INVALID_SIGNATURE, CODE_RATE_LIMITED};
+use crate::{ApiError, Result};
+use serde_json::Value;
+use std::time::Duration;
+
+/// What a rejection code means for the caller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RejectionKind {
+    /// The exchange could not verify the signature (21120), e.g. a wrong api_key_index.
+    InvalidSignature,
+    /// The transaction's nonce was not the next one of its API key.
+    InvalidNonce,
+    /// Too many requests (429); retry after a pause.
+    RateLimited,
+    /// Any other rejection, usually of the transaction's content (margin, price, size...).
+    Other,
+}
+
+impl RejectionKind {
+    /// Classifies a response; `None` if it is not a rejection.
+    pub fn of(response: &Value) -> Option<Self> {
+        let code = response["code"].as_i64().filter(|code| *code != 200)?;
+        Some(match code {
+            CODE_INVALID_SIGNATURE => Self::InvalidSignature,
+            CODE_RATE_LIMITED => Self::RateLimited,
+            _ if is_nonce_error(response) => Self::InvalidNonce,
+            _ => Self::Other,
+        })
+    }
+}
+
+/// Returns `response` if it has no code or code 200, `ApiError::Rejected` otherwise.
+pub fn check_response(response: Value) -> Result<Value> {
+    match response["code"].as_i64() {
+        Some(code) if code != 200 => Err(ApiError::Rejected {
+            code,
+            message: response["message"].as_str().unwrap_or_default().to_string(),
+            retry_after: retry_after(&response),
+            response,
+        }),
+        _ => Ok(response),
+    }
+}
+
+/// Converts exchange rejections into errors; see the module docs.
+pub trait CheckedResponse {
+    fn checked(self) -> Result<Value>;
+}
+
+impl CheckedResponse for Result<Value> {
+    fn checked(self) -> Result<Value> {
+        self.and_then(check_response)
+    }
+}
+
+impl ApiError {
+    /// Exchange code of a rejection, or HTTP status of an unexpected response.
+    pub fn code(&self) -> Option<i64> {
+        match self {
+            ApiError::Rejected { code, .. } => Some(*code),
+            ApiError::UnexpectedResponse { status, .. } => Some(i64::from(*status)),
+            _ => None,
+        }
+    }
+
+    /// Delay the exchange asked for before trying again, e.g. when rate limited.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            ApiError::Rejected { retry_after, .. } | ApiError::UnexpectedResponse { retry_after, .. } => *retry_after,
+            ApiError::CircuitOpen { retry_in, .. } => Some(*retry_in),
+            _ => None,
+        }
+    }
+
+    /// Kind of a rejection, `None` for other errors.
+    pub fn rejection_kind(&self) -> Option<RejectionKind> {
+        match self {
+            ApiError::Rejected { response, .. } => RejectionKind::of(response),
+            _ => None,
+        }
+    }
+
+    /// Full exchange response of a rejection.
+    pub fn response(&self) -> Option<&Value> {
+        match self {
+            ApiError::Rejected { response, .. } => Some(response),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_checked_responses() {
+        let accepted = json!({"code": 200, "tx_hash": "ab"});
+        assert_eq!(Ok(accepted.clone()).checked().unwrap(), accepted);
+        assert!(check_response(json!({"accounts": []})).is_ok());
+
+        let error = Ok(json!({"code": 21120, "message": "invalid signature"})).checked().unwrap_err();
+        assert_eq!(error.code(), Some(21120));
+        assert_eq!(error.rejection_kind(), Some(RejectionKind::InvalidSignature));
+        assert_eq!(error.response().unwrap()["message"], "invalid signature");
+        assert_eq!(error.to_string(), "Rejected by the exchange (code 21120): invalid signature");
+
+        let kind = |response: Value| check_response(response).unwrap_err().rejection_kind();
+        assert_eq!(kind(json!({"code": 21104, "message": "invalid nonce"})), Some(RejectionKind::InvalidNonce));
+        assert_eq!(kind(json!({"code": 429})), Some(RejectionKind::RateLimited));
+        let throttled = check_response(json!({"code": 429, "retry_after_ms": 1500})).unwrap_err();
+        assert_eq!(throttled.retry_after(), Some(Duration::from_millis(1500)));
+        assert_eq!(error.retry_after(), None);
+        assert_eq!(kind(json!({"code": 21706, "message": "not enough margin"})), Some(RejectionKind::Other));
+
+        // Transport errors pass through untouched
+        let lost: Result<Value> = Err(ApiError::WebSocket("no response".to_string()));
+        assert!(matches!(lost.checked(), Err(ApiError::WebSocket(_))));
+    }
+}
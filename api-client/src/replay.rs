@@ -0,0 +1,403 @@
+//! Account history from the transaction log.
+//!
+//! `replay_account` reads the account's transactions (`accountTxs`) and fills (`trades`)
+//! executed in a time range and merges them into one timeline of typed events, oldest
+//! first: orders placed, modified and cancelled, fills, transfers, withdrawals, deposits,
+//! margin and leverage changes. Transactions the replay does not interpret are kept as
+//! `ReplayEvent::Other` so the timeline has no holes.
+//!
+//! Along the way it keeps running totals from the start of the range: the net size traded
+//! on each market and the USDC moved in and out of the account. Added to a snapshot of the
+//! account taken at the start of the range, they give its positions and collateral at any
+//! event; on their own they explain what changed and why, for audits and PnL breakdowns.
+//!
+//! `accountTxs` is paged newest first with the `index` of the oldest transaction seen;
+//! `trades` is paged oldest first from the start of the range with a cursor. Both are
+//! read to the end of the range before anything is returned.
+
+use crate::decimal::{parse_decimal, Decimal};
+use crate::{LighterClient, MarginDirection, Result, UsdcAmount};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+/// Page size of both history endpoints.
+const PAGE_LIMIT: u32 = 100;
+
+/// A transaction of the account's log (entry of `accountTxs`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict", serde(deny_unknown_fields))]
+pub struct AccountTx {
+    pub hash: String,
+    #[serde(rename = "type")]
+    pub tx_type: u32,
+    /// `tx_info` as signed, a JSON string.
+    #[serde(default)]
+    pub info: String,
+    /// Execution details, a JSON string.
+    #[serde(default)]
+    pub event_info: String,
+    #[serde(default)]
+    pub status: i64,
+    #[serde(default)]
+    pub transaction_index: i64,
+    #[serde(default)]
+    pub l1_address: String,
+    #[serde(default)]
+    pub account_index: i64,
+    #[serde(default)]
+    pub nonce: i64,
+    #[serde(default)]
+    pub expire_at: i64,
+    #[serde(default)]
+    pub block_height: i64,
+    #[serde(default)]
+    pub queued_at: i64,
+    /// Milliseconds.
+    #[serde(default)]
+    pub executed_at: i64,
+    #[serde(default)]
+    pub sequence_index: i64,
+    #[serde(default)]
+    pub parent_hash: String,
+}
+
+/// One page of `accountTxs`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct AccountTxPage {
+    #[serde(default)]
+    txs: Vec<AccountTx>,
+}
+
+/// What happened to the account. Prices and sizes of orders are in price and size units as
+/// signed; fills and USDC amounts are decimals.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplayEvent {
+    OrderPlaced {
+        market_index: u8,
+        client_order_index: i64,
+        is_ask: bool,
+        base_amount: i64,
+        price: i64,
+        reduce_only: bool,
+    },
+    OrderModified { market_index: u8, order_index: i64, base_amount: i64, price: i64 },
+    OrderCancelled { market_index: u8, order_index: i64 },
+    AllOrdersCancelled,
+    /// A trade the account took part in.
+    Fill {
+        market_index: u8,
+        trade_id: i64,
+        is_ask: bool,
+        size: Decimal,
+        price: Decimal,
+        /// Net size traded on the market since the start of the range, negative when net short.
+        net_size: Decimal,
+    },
+    /// USDC sent to (`amount` negative) or received from another account.
+    Transfer { counterparty: i64, amount: Decimal, fee: Decimal },
+    Withdrawal { amount: Decimal },
+    Deposit { amount: Decimal },
+    /// USDC moved into (positive) or out of the isolated margin of a market.
+    MarginChanged { market_index: u8, amount: Decimal },
+    LeverageChanged { market_index: u8, initial_margin_fraction: i64, margin_mode: i64 },
+    /// A transaction the replay does not interpret.
+    Other { tx_type: u32 },
+}
+
+/// An event with when and through which transaction it happened.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayEntry {
+    /// Milliseconds.
+    pub timestamp: i64,
+    /// Hash of the transaction; for fills, of the transaction that matched.
+    pub tx_hash: String,
+    /// Transaction type; 0 for fills.
+    pub tx_type: u32,
+    pub event: ReplayEvent,
+    /// USDC moved into the account since the start of the range by transfers, deposits and
+    /// withdrawals, after this event; trading PnL and fees are not included.
+    pub net_transfers: Decimal,
+}
+
+/// The timeline of `replay_account`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AccountReplay {
+    /// Oldest first; at the same millisecond, transactions in log order before fills.
+    pub entries: Vec<ReplayEntry>,
+    /// Net size traded per market over the range.
+    pub net_sizes: BTreeMap<u8, Decimal>,
+    /// USDC moved into the account over the range, as in `ReplayEntry::net_transfers`.
+    pub net_transfers: Decimal,
+}
+
+impl AccountReplay {
+    /// Builds the timeline of `account_index` from its transactions and trades
+    pub fn from_history(account_index: i64, txs: &[AccountTx], trades: &[Value]) -> Self {
+        let mut events: Vec<(i64, i64, String, u32, ReplayEvent)> = Vec::new();
+        for tx in txs {
+            let info: Value = serde_json::from_str(&tx.info).unwrap_or_default();
+            let event = tx_event(account_index, tx.tx_type, &info);
+            events.push((tx.executed_at, tx.transaction_index, tx.hash.clone(), tx.tx_type, event));
+        }
+        for trade in trades {
+            if let Some((timestamp, event)) = fill_event(account_index, trade) {
+                let tx_hash = trade["tx_hash"].as_str().unwrap_or_default().to_string();
+                events.push((timestamp, i64::MAX, tx_hash, 0, event));
+            }
+        }
+        events.sort_by_key(|(timestamp, order, ..)| (*timestamp, *order));
+
+        let mut replay = Self::default();
+        for (timestamp, _, tx_hash, tx_type, mut event) in events {
+            match &mut event {
+                ReplayEvent::Fill { market_index, is_ask, size, net_size, .. } => {
+                    let net = replay.net_sizes.entry(*market_index).or_default();
+                    *net += if *is_ask { -*size } else { *size };
+                    *net_size = *net;
+                }
+                ReplayEvent::Transfer { amount, fee, .. } => {
+                    replay.net_transfers += *amount;
+                    if amount.is_sign_negative() {
+                        replay.net_transfers -= *fee;
+                    }
+                }
+                ReplayEvent::Withdrawal { amount } => replay.net_transfers -= *amount,
+                ReplayEvent::Deposit { amount } => replay.net_transfers += *amount,
+                _ => {}
+            }
+            replay.entries.push(ReplayEntry {
+                timestamp,
+                tx_hash,
+                tx_type,
+                event,
+                net_transfers: replay.net_transfers,
+            });
+        }
+        replay
+    }
+
+    /// Entries of one market, fills and order events alike.
+    pub fn market(&self, market_index: u8) -> impl Iterator<Item = &ReplayEntry> {
+        self.entries.iter().filter(move |entry| event_market(&entry.event) == Some(market_index))
+    }
+}
+
+fn event_market(event: &ReplayEvent) -> Option<u8> {
+    match event {
+        ReplayEvent::OrderPlaced { market_index, .. }
+        | ReplayEvent::OrderModified { market_index, .. }
+        | ReplayEvent::OrderCancelled { market_index, .. }
+        | ReplayEvent::Fill { market_index, .. }
+        | ReplayEvent::MarginChanged { market_index, .. }
+        | ReplayEvent::LeverageChanged { market_index, .. } => Some(*market_index),
+        _ => None,
+    }
+}
+
+fn usdc(info: &Value, field: &str) -> Decimal {
+    info[field].as_u64().map(|units| UsdcAmount(units).to_decimal()).unwrap_or_default()
+}
+
+/// Event of a transaction from its `tx_info`.
+fn tx_event(account_index: i64, tx_type: u32, info: &Value) -> ReplayEvent {
+    let int = |field: &str| info[field].as_i64().unwrap_or_default();
+    let market_index = info["MarketIndex"].as_u64().and_then(|m| u8::try_from(m).ok());
+    match (tx_type, market_index) {
+        // L1_DEPOSIT
+        (1, _) => ReplayEvent::Deposit { amount: usdc(info, "USDCAmount") },
+        // CREATE_ORDER
+        (14, Some(market_index)) => ReplayEvent::OrderPlaced {
+            market_index,
+            client_order_index: int("ClientOrderIndex"),
+            is_ask: int("IsAsk") == 1,
+            base_amount: int("BaseAmount"),
+            price: int("Price"),
+            reduce_only: int("ReduceOnly") == 1,
+        },
+        // CANCEL_ORDER
+        (15, Some(market_index)) => ReplayEvent::OrderCancelled { market_index, order_index: int("Index") },
+        // CANCEL_ALL_ORDERS
+        (16, _) => ReplayEvent::AllOrdersCancelled,
+        // MODIFY_ORDER
+        (17, Some(market_index)) => ReplayEvent::OrderModified {
+            market_index,
+            order_index: int("Index"),
+            base_amount: int("BaseAmount"),
+            price: int("Price"),
+        },
+        // TRANSFER
+        (12, _) => {
+            let outgoing = int("FromAccountIndex") == account_index;
+            let amount = usdc(info, "USDCAmount");
+            ReplayEvent::Transfer {
+                counterparty: if outgoing { int("ToAccountIndex") } else { int("FromAccountIndex") },
+                amount: if outgoing { -amount } else { amount },
+                fee: usdc(info, "Fee"),
+            }
+        }
+        // WITHDRAW
+        (13, _) => ReplayEvent::Withdrawal { amount: usdc(info, "USDCAmount") },
+        // UPDATE_LEVERAGE
+        (20, Some(market_index)) => ReplayEvent::LeverageChanged {
+            market_index,
+            initial_margin_fraction: int("InitialMarginFraction"),
+            margin_mode: int("MarginMode"),
+        },
+        // UPDATE_MARGIN
+        (29, Some(market_index)) => {
+            let amount = usdc(info, "USDCAmount");
+            let adds = int("Direction") == MarginDirection::AddToIsolatedMargin as i64;
+            ReplayEvent::MarginChanged {
+                market_index,
+                amount: if adds { amount } else { -amount },
+            }
+        }
+        _ => ReplayEvent::Other { tx_type },
+    }
+}
+
+/// Fill of a `trades` entry for `account_index`, with its timestamp.
+fn fill_event(account_index: i64, trade: &Value) -> Option<(i64, ReplayEvent)> {
+    let is_ask = if trade["ask_account_id"].as_i64() == Some(account_index) {
+        true
+    } else if trade["bid_account_id"].as_i64() == Some(account_index) {
+        false
+    } else {
+        return None;
+    };
+    let event = ReplayEvent::Fill {
+        market_index: u8::try_from(trade["market_id"].as_u64()?).ok()?,
+        trade_id: trade["trade_id"].as_i64()?,
+        is_ask,
+        size: parse_decimal(&trade["size"])?,
+        price: parse_decimal(&trade["price"])?,
+        net_size: Decimal::ZERO,
+    };
+    Some((trade["timestamp"].as_i64()?, event))
+}
+
+impl LighterClient {
+    /// Timeline of this account's transactions and fills executed in `range` (milliseconds)
+    ///
+    /// See `replay` for what the events and running totals cover.
+    pub async fn replay_account(&self, range: Range<i64>) -> Result<AccountReplay> {
+        let txs = self.account_txs(&range).await?;
+        let trades = self.trades_between(&range).await?;
+        Ok(AccountReplay::from_history(self.account_index, &txs, &trades))
+    }
+
+    /// Transactions of the account executed in `range`.
+    async fn account_txs(&self, range: &Range<i64>) -> Result<Vec<AccountTx>> {
+        let mut txs = Vec::new();
+        let mut index: Option<i64> = None;
+        loop {
+            let mut query = vec![
+                ("by", "account_index".to_string()),
+                ("value", self.account_index.to_string()),
+                ("limit", PAGE_LIMIT.to_string()),
+            ];
+            if let Some(index) = index {
+                query.push(("index", index.to_string()));
+            }
+            let response = self.get_authenticated_json("/api/v1/accountTxs", &query).await?;
+            let page: AccountTxPage = self.parse_typed("/api/v1/accountTxs", response)?;
+            let full = page.txs.len() >= PAGE_LIMIT as usize;
+            let oldest = page.txs.iter().map(|tx| tx.transaction_index).min();
+            let reached_start = page.txs.iter().any(|tx| tx.executed_at < range.start);
+            txs.extend(page.txs.into_iter().filter(|tx| range.contains(&tx.executed_at)));
+
+            match oldest {
+                Some(oldest) if full && !reached_start && index.is_none_or(|index| oldest < index) => {
+                    index = Some(oldest - 1);
+                }
+                _ => return Ok(txs),
+            }
+        }
+    }
+
+    /// Trades of the account in `range`.
+    async fn trades_between(&self, range: &Range<i64>) -> Result<Vec<Value>> {
+        let mut trades = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let mut query = vec![
+                ("account_index", self.account_index.to_string()),
+                ("sort_by", "timestamp".to_string()),
+                ("sort_dir", "asc".to_string()),
+                ("from", range.start.to_string()),
+                ("limit", PAGE_LIMIT.to_string()),
+            ];
+            if let Some(cursor) = &cursor {
+                query.push(("cursor", cursor.clone()));
+            }
+            let page = self.get_authenticated_json("/api/v1/trades", &query).await?;
+            let entries = page["trades"].as_array().cloned().unwrap_or_default();
+            let past_end = entries.iter().any(|t| t["timestamp"].as_i64().is_some_and(|ts| ts >= range.end));
+            trades.extend(
+                entries
+                    .into_iter()
+                    .filter(|t| t["timestamp"].as_i64().is_some_and(|ts| range.contains(&ts))),
+            );
+
+            match page["next_cursor"].as_str().filter(|next| !next.is_empty()) {
+                Some(next) if !past_end && cursor.as_deref() != Some(next) => cursor = Some(next.to_string()),
+                _ => return Ok(trades),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::str::FromStr;
+
+    fn tx(tx_type: u32, transaction_index: i64, executed_at: i64, info: Value) -> AccountTx {
+        serde_json::from_value(json!({
+            "hash": format!("h{}", transaction_index), "type": tx_type, "info": info.to_string(),
+            "transaction_index": transaction_index, "executed_at": executed_at,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_timeline_and_totals() {
+        let txs = vec![
+            tx(14, 2, 2_000, json!({"AccountIndex": 7, "MarketIndex": 0, "ClientOrderIndex": 5, "IsAsk": 0,
+                                    "BaseAmount": 1000, "Price": 300000, "ReduceOnly": 0})),
+            tx(12, 1, 1_000, json!({"FromAccountIndex": 9, "ToAccountIndex": 7, "USDCAmount": 250_000_000, "Fee": 0})),
+            tx(13, 4, 4_000, json!({"FromAccountIndex": 7, "USDCAmount": 50_000_000})),
+            tx(29, 5, 5_000, json!({"AccountIndex": 7, "MarketIndex": 0, "USDCAmount": 10_000_000, "Direction": 0})),
+            tx(23, 6, 6_000, json!({})),
+        ];
+        let trades = vec![
+            json!({"trade_id": 1, "tx_hash": "t1", "market_id": 0, "size": "0.1000", "price": "3000.00",
+                   "bid_account_id": 7, "ask_account_id": 8, "timestamp": 2_000}),
+            json!({"trade_id": 2, "tx_hash": "t2", "market_id": 0, "size": "0.0400", "price": "3010.00",
+                   "bid_account_id": 8, "ask_account_id": 7, "timestamp": 3_000}),
+            // Someone else's trade
+            json!({"trade_id": 3, "market_id": 0, "size": "1", "price": "1", "bid_account_id": 1,
+                   "ask_account_id": 2, "timestamp": 3_500}),
+        ];
+
+        let replay = AccountReplay::from_history(7, &txs, &trades);
+        let kinds: Vec<(i64, u32)> = replay.entries.iter().map(|e| (e.timestamp, e.tx_type)).collect();
+        assert_eq!(kinds, vec![(1_000, 12), (2_000, 14), (2_000, 0), (3_000, 0), (4_000, 13), (5_000, 29), (6_000, 23)]);
+
+        let d = |s: &str| Decimal::from_str(s).unwrap();
+        assert_eq!(
+            replay.entries[0].event,
+            ReplayEvent::Transfer { counterparty: 9, amount: d("250"), fee: Decimal::ZERO }
+        );
+        assert!(matches!(replay.entries[3].event, ReplayEvent::Fill { is_ask: true, net_size, .. } if net_size == d("0.06")));
+        assert_eq!(replay.entries[5].event, ReplayEvent::MarginChanged { market_index: 0, amount: d("-10") });
+        assert_eq!(replay.entries[6].event, ReplayEvent::Other { tx_type: 23 });
+        assert_eq!(replay.entries[4].net_transfers, d("200"));
+        assert_eq!(replay.net_transfers, d("200"));
+        assert_eq!(replay.net_sizes.get(&0), Some(&d("0.06")));
+        assert_eq!(replay.market(0).count(), 4);
+    }
+}
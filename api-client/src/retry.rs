@@ -0,0 +1,435 @@
+//! Retry delays per error class.
+//!
+//! `create_order` and `update_leverage` sign and send again when a transaction is rejected
+//! with a retryable code, or when it never reached the exchange. `RetryConfig` sets how
+//! often and how long to wait for each class: a `Backoff` per response code (21120 invalid
+//! signature, 429 rate limit, ...) and one for transport errors. Before each retry the
+//! nonce is resynced with the API, as the previous attempt may have been applied.
+//!
+//! When the exchange says how long to wait (a `Retry-After` header, or a `retry_after` /
+//! `retry_after_ms` field in the response), that delay replaces the backoff's, up to
+//! `RetryConfig::max_retry_after`. The same hint is kept in `ApiError::Rejected` and
+//! `ApiError::UnexpectedResponse` (see `ApiError::retry_after`), so a strategy that gets a
+//! throttled error back can slow down or shrink its orders.
+//!
+//! A transport error leaves open whether the exchange executed the transaction. Re-signing
+//! it with the next nonce would then place it twice, so the signed tx hash serves as the
+//! idempotency key of the request: before a retry the client looks the hash up
+//! (`get_transaction`). If the exchange has it, the attempt succeeded and its hash is
+//! returned as if the response had arrived. If it does not, the retry goes ahead with a
+//! resynced nonce. If the lookup fails as well, the same signed transaction is posted again,
+//! which cannot execute twice since its nonce can only be used once. When the retries run
+//! out without an answer either way, the call fails with `ApiError::PossiblyExecuted`.
+
+use crate::nonce::is_nonce_error;
+use crate::ws::Transport;
+use crate::{parse_response, Alert, ApiError, LighterClient, NonceLease, Result, SignedTx, TxStatus};
+use rand::Rng;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::Duration;
+
+/// Response code of a transaction rejected for an invalid signature.
+pub const CODE_INVALID_SIGNATURE: i64 = 21120;
+/// Response code (or HTTP status) of a rate-limited request.
+pub const CODE_RATE_LIMITED: i64 = 429;
+
+/// Delay a response asks for before the next attempt
+///
+/// Reads `retry_after_ms` (milliseconds) or `retry_after` (seconds, a number or a numeric
+/// string); `None` if the response has neither or a negative value.
+pub fn retry_after(response: &Value) -> Option<Duration> {
+    let seconds = |value: &Value| match value {
+        Value::String(s) => s.trim().parse::<f64>().ok(),
+        other => other.as_f64(),
+    };
+    let seconds = match response.get("retry_after_ms") {
+        Some(millis) => seconds(millis)? / 1000.0,
+        None => seconds(response.get("retry_after")?)?,
+    };
+    Duration::try_from_secs_f64(seconds).ok()
+}
+
+/// How many times to retry and how long to wait before each retry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Backoff {
+    pub max_retries: u32,
+    /// Delay before the first retry.
+    pub initial_delay: Duration,
+    /// Factor applied to the delay after each retry; 1.0 keeps it fixed.
+    pub multiplier: f64,
+    /// Upper bound of the delay, before jitter.
+    pub max_delay: Duration,
+    /// Random spread of each delay as a fraction of it, e.g. 0.2 for ±20%.
+    pub jitter: f64,
+}
+
+impl Backoff {
+    /// `max_retries` retries, `delay` apart.
+    pub fn fixed(max_retries: u32, delay: Duration) -> Self {
+        Self {
+            max_retries,
+            initial_delay: delay,
+            multiplier: 1.0,
+            max_delay: delay,
+            jitter: 0.0,
+        }
+    }
+
+    /// `max_retries` retries, doubling the delay from `initial_delay` up to `max_delay`.
+    pub fn exponential(max_retries: u32, initial_delay: Duration, max_delay: Duration) -> Self {
+        Self {
+            max_retries,
+            initial_delay,
+            multiplier: 2.0,
+            max_delay,
+            jitter: 0.0,
+        }
+    }
+
+    /// Spread each delay randomly by `fraction` of it (clamped to `[0, 1]`).
+    pub fn with_jitter(mut self, fraction: f64) -> Self {
+        self.jitter = fraction.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Delay before retry number `retry` (1-based), without jitter.
+    pub fn base_delay(&self, retry: u32) -> Duration {
+        let exponent = retry.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let seconds = self.initial_delay.as_secs_f64() * self.multiplier.max(1.0).powi(exponent);
+        Duration::from_secs_f64(seconds.min(self.max_delay.as_secs_f64()))
+    }
+
+    /// Delay before retry number `retry` (1-based), with jitter applied.
+    pub fn delay(&self, retry: u32) -> Duration {
+        let base = self.base_delay(retry);
+        if self.jitter <= 0.0 {
+            return base;
+        }
+        let factor = rand::thread_rng().gen_range(1.0 - self.jitter..=1.0 + self.jitter);
+        base.mul_f64(factor)
+    }
+}
+
+/// Retry policy of `create_order` and `update_leverage`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryConfig {
+    /// Backoff per response code; codes not listed are not retried. A non-JSON response
+    /// is looked up by its HTTP status.
+    pub per_code: HashMap<i64, Backoff>,
+    /// Backoff for requests that failed before a response arrived, with a 5xx status or
+    /// with a success status but an unreadable body; `None` to return those errors at once.
+    pub transport: Option<Backoff>,
+    /// Longest server-suggested delay waited before a retry; longer hints are cut to it.
+    pub max_retry_after: Duration,
+}
+
+impl Default for RetryConfig {
+    /// 21120 and transport errors: 5 retries 3 s apart (3 s apart was enough for an
+    /// invalid signature to go through in testing). 429: 5 retries from 1 s, doubling up
+    /// to 30 s, with 20% jitter. Server-suggested delays are honored up to 60 s.
+    fn default() -> Self {
+        let mut per_code = HashMap::new();
+        per_code.insert(CODE_INVALID_SIGNATURE, Backoff::fixed(5, Duration::from_secs(3)));
+        per_code.insert(
+            CODE_RATE_LIMITED,
+            Backoff::exponential(5, Duration::from_secs(1), Duration::from_secs(30)).with_jitter(0.2),
+        );
+        Self {
+            per_code,
+            transport: Some(Backoff::fixed(5, Duration::from_secs(3))),
+            max_retry_after: Duration::from_secs(60),
+        }
+    }
+}
+
+impl RetryConfig {
+    /// No retries at all.
+    pub fn none() -> Self {
+        Self {
+            per_code: HashMap::new(),
+            transport: None,
+            max_retry_after: Duration::from_secs(60),
+        }
+    }
+
+    /// Set the backoff of response code `code`.
+    pub fn with_code(mut self, code: i64, backoff: Backoff) -> Self {
+        self.per_code.insert(code, backoff);
+        self
+    }
+
+    /// Set the backoff of transport errors.
+    pub fn with_transport(mut self, backoff: Option<Backoff>) -> Self {
+        self.transport = backoff;
+        self
+    }
+
+    /// Set the longest server-suggested delay honored; `Duration::ZERO` ignores the hints.
+    pub fn with_max_retry_after(mut self, max: Duration) -> Self {
+        self.max_retry_after = max;
+        self
+    }
+
+    /// Delay before retry number `retry` of `outcome`: the server's hint if it gave one
+    /// (capped), otherwise the backoff's.
+    fn delay(&self, outcome: &Result<Value>, backoff: &Backoff, retry: u32) -> Duration {
+        let hint = match outcome {
+            Ok(response) => retry_after(response),
+            Err(error) => error.retry_after(),
+        };
+        match hint.filter(|_| !self.max_retry_after.is_zero()) {
+            Some(hint) => hint.min(self.max_retry_after),
+            None => backoff.delay(retry),
+        }
+    }
+
+    /// Error class of an attempt's outcome and its backoff, or `None` if it is final.
+    fn backoff_for(&self, outcome: &Result<Value>) -> Option<(Option<i64>, &Backoff)> {
+        match outcome {
+            Ok(response) => {
+                let code = response["code"].as_i64()?;
+                self.per_code.get(&code).map(|backoff| (Some(code), backoff))
+            }
+            Err(error @ ApiError::UnexpectedResponse { status, .. }) => {
+                let code = i64::from(*status);
+                match self.per_code.get(&code) {
+                    Some(backoff) => Some((Some(code), backoff)),
+                    None if response_lost(error) => self.transport.as_ref().map(|backoff| (None, backoff)),
+                    None => None,
+                }
+            }
+            // A websocket transaction without a response may have been applied; see `ws`
+            Err(ApiError::Http(_)) => self.transport.as_ref().map(|backoff| (None, backoff)),
+            Err(_) => None,
+        }
+    }
+}
+
+/// Whether a failed send leaves open if the transaction was executed: no response, a 5xx
+/// status, or a success status whose body cannot be read (as good as no response)
+pub(crate) fn response_lost(error: &ApiError) -> bool {
+    match error {
+        ApiError::Http(_) => true,
+        ApiError::UnexpectedResponse { status, .. } => *status >= 500 || (200..300).contains(status),
+        _ => false,
+    }
+}
+
+impl LighterClient {
+    /// Retry policy of `create_order` and `update_leverage`
+    pub fn retry_config(&self) -> &RetryConfig {
+        &self.retry_config
+    }
+
+    /// Look up a transaction by its hash (hex, as in `SignedTx::tx_hash_hex`)
+    ///
+    /// `None` if the exchange answers that it does not know the hash; an error if it cannot
+    /// be asked.
+    pub async fn get_transaction(&self, tx_hash: &str) -> Result<Option<Value>> {
+        let response = self
+            .client
+            .get(format!("{}/api/v1/tx", self.base_url))
+            .query(&[("by", "hash"), ("value", tx_hash)])
+            .send()
+            .await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let response = parse_response(response).await?;
+        match response["code"].as_i64() {
+            Some(200) | None => Ok(Some(response)),
+            Some(_) => Ok(None),
+        }
+    }
+
+    /// Sign with `sign` and the nonce of `lease`, and send, until the exchange answers with
+    /// a final response or the error class runs out of retries
+    ///
+    /// Each class counts its retries separately. The lease is settled with the final
+    /// response; on a final error it is dropped, releasing the nonce. After a transport
+    /// error the attempt is looked up by hash before it is re-signed (see the module docs).
+    pub(crate) async fn send_with_retries<F, Fut>(
+        &self,
+        tx_type: u32,
+        price_protection: &str,
+        mut lease: NonceLease,
+        mut sign: F,
+    ) -> Result<Value>
+    where
+        F: FnMut(i64) -> Fut,
+        Fut: Future<Output = Result<SignedTx>>,
+    {
+        let transport = if self.websocket_tx { Transport::WebSocket } else { Transport::Http };
+        let mut retries: HashMap<Option<i64>, u32> = HashMap::new();
+        let mut signed = sign(lease.nonce()).await?;
+        // Whether `signed` is posted again unchanged, after a lost response
+        let mut repost = false;
+        loop {
+            let outcome = if repost {
+                self.post_tx(transport, tx_type, &signed.tx_info, price_protection).await
+            } else {
+                self.send_tx_via(transport, tx_type, &signed.tx_info, price_protection).await
+            };
+            if repost && outcome.as_ref().is_ok_and(is_nonce_error) {
+                // The nonce was used since the lost attempt, most likely by it
+                lease.commit();
+                return Err(possibly_executed(&signed, "its nonce was used before the response arrived"));
+            }
+            let Some((class, backoff)) = self.retry_config.backoff_for(&outcome) else {
+                match &outcome {
+                    Ok(response) => lease.settle(response),
+                    // Handing the nonce out again could only get the next transaction rejected
+                    Err(ApiError::PossiblyExecuted { .. }) => lease.commit(),
+                    Err(_) => {}
+                }
+                return outcome;
+            };
+            // No response: the transaction may have been executed
+            let lost = class.is_none();
+
+            let retry = retries.entry(class).or_default();
+            if *retry >= backoff.max_retries {
+                if class == Some(CODE_INVALID_SIGNATURE) {
+                    self.notify(Alert::signature_retries_exhausted(self.account_index, tx_type, *retry + 1));
+                }
+                if lost {
+                    return match self.find_lost(&signed.tx_hash_hex(), &signed.tx_info).await {
+                        Some(Ok(response)) => {
+                            lease.commit();
+                            Ok(response)
+                        }
+                        // Not executed; the lease is dropped with the original error
+                        Some(Err(_)) => outcome,
+                        None => {
+                            lease.commit();
+                            let error = outcome.err().map_or_else(|| "no response".to_string(), |e| e.to_string());
+                            Err(possibly_executed(&signed, &error))
+                        }
+                    };
+                }
+                if let Ok(response) = &outcome {
+                    lease.settle(response);
+                }
+                return outcome;
+            }
+            *retry += 1;
+            tokio::time::sleep(self.retry_config.delay(&outcome, backoff, *retry)).await;
+
+            if lost {
+                match self.find_lost(&signed.tx_hash_hex(), &signed.tx_info).await {
+                    Some(Ok(response)) => {
+                        lease.commit();
+                        return Ok(response);
+                    }
+                    Some(Err(_)) => {}
+                    None => {
+                        repost = true;
+                        continue;
+                    }
+                }
+            }
+            repost = false;
+
+            // The previous attempt was not applied, or was rejected; resync so the retry
+            // gets the next nonce. If the fetch fails, the current nonce is reused.
+            if let Ok(fresh_nonce) = self.fetch_nonce_from_api().await {
+                lease.release();
+                self.nonces.resync(fresh_nonce);
+                lease = self.lease_nonce().await?;
+            }
+            signed = sign(lease.nonce()).await?;
+        }
+    }
+
+    /// Whether a transaction whose response was lost was executed: `Some(Ok)` with a sendTx
+    /// style response if the exchange has it, `Some(Err)` if it does not, `None` if the
+    /// lookup failed
+    pub(crate) async fn find_lost(&self, tx_hash: &str, tx_info: &Value) -> Option<Result<Value>> {
+        match self.get_transaction(tx_hash).await {
+            Ok(Some(tx)) => {
+                tracing::info!(target: "api_client::retry", tx_hash, "transaction was executed, its response was lost");
+                if let (Some(journal), Some(nonce)) = (&self.journal, tx_info["Nonce"].as_i64()) {
+                    let status = TxStatus::Accepted { tx_hash: Some(tx_hash.to_string()) };
+                    if let Err(e) = journal.update(self.account_index, self.api_key_index, nonce, status) {
+                        tracing::warn!(target: "api_client::retry", tx_hash, error = %e, "cannot update journal");
+                    }
+                }
+                Some(Ok(json!({"code": 200, "tx_hash": tx_hash, "tx": tx})))
+            }
+            Ok(None) => Some(Err(ApiError::Api(format!("Transaction {} not found", tx_hash)))),
+            Err(e) => {
+                tracing::warn!(target: "api_client::retry", tx_hash, error = %e, "cannot look up transaction");
+                None
+            }
+        }
+    }
+}
+
+fn possibly_executed(signed: &SignedTx, message: &str) -> ApiError {
+    ApiError::PossiblyExecuted {
+        tx_hash: signed.tx_hash_hex(),
+        nonce: signed.tx_info["Nonce"].as_i64().unwrap_or_default(),
+        message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_delays_and_classes() {
+        let backoff = Backoff::exponential(4, Duration::from_millis(500), Duration::from_secs(3));
+        let delays: Vec<u64> = (1..=5).map(|retry| backoff.delay(retry).as_millis() as u64).collect();
+        assert_eq!(delays, vec![500, 1000, 2000, 3000, 3000]);
+
+        let jittered = backoff.clone().with_jitter(0.5);
+        for _ in 0..100 {
+            let delay = jittered.delay(2).as_millis();
+            assert!((500..=1500).contains(&delay), "{}", delay);
+        }
+        assert_eq!(Backoff::fixed(5, Duration::from_secs(3)).delay(5), Duration::from_secs(3));
+
+        let config = RetryConfig::default();
+        let class = |outcome: Result<Value>| config.backoff_for(&outcome).map(|(class, _)| class);
+        assert_eq!(class(Ok(serde_json::json!({"code": 21120}))), Some(Some(21120)));
+        assert_eq!(class(Ok(serde_json::json!({"code": 200}))), None);
+        let rate_limited = ApiError::UnexpectedResponse { status: 429, body: "Too Many Requests".to_string(), retry_after: None };
+        assert_eq!(class(Err(rate_limited)), Some(Some(429)));
+        let bad_gateway = ApiError::UnexpectedResponse { status: 502, body: String::new(), retry_after: None };
+        assert_eq!(class(Err(bad_gateway)), Some(None));
+        let truncated = ApiError::UnexpectedResponse { status: 200, body: "{\"code\":".to_string(), retry_after: None };
+        assert_eq!(class(Err(truncated)), Some(None));
+        assert_eq!(class(Err(ApiError::WebSocket("no response".to_string()))), None);
+        assert!(RetryConfig::none().backoff_for(&Ok(serde_json::json!({"code": 21120}))).is_none());
+    }
+
+    #[test]
+    fn test_server_retry_hints() {
+        use serde_json::json;
+
+        assert_eq!(retry_after(&json!({"code": 429, "retry_after_ms": 250})), Some(Duration::from_millis(250)));
+        assert_eq!(retry_after(&json!({"code": 429, "retry_after": "2"})), Some(Duration::from_secs(2)));
+        assert_eq!(retry_after(&json!({"code": 429, "retry_after": -1})), None);
+        assert_eq!(retry_after(&json!({"code": 429})), None);
+
+        // The hint replaces the backoff's delay, up to the cap
+        let config = RetryConfig::default().with_max_retry_after(Duration::from_secs(10));
+        let backoff = Backoff::fixed(5, Duration::from_secs(3));
+        let delay = |outcome: Result<Value>| config.delay(&outcome, &backoff, 1);
+        assert_eq!(delay(Ok(json!({"code": 429, "retry_after_ms": 500}))), Duration::from_millis(500));
+        assert_eq!(delay(Ok(json!({"code": 429, "retry_after": 120}))), Duration::from_secs(10));
+        assert_eq!(delay(Ok(json!({"code": 429}))), Duration::from_secs(3));
+        let throttled = ApiError::UnexpectedResponse {
+            status: 429,
+            body: String::new(),
+            retry_after: Some(Duration::from_secs(4)),
+        };
+        assert_eq!(delay(Err(throttled)), Duration::from_secs(4));
+
+        let ignoring = RetryConfig::default().with_max_retry_after(Duration::ZERO);
+        assert_eq!(ignoring.delay(&Ok(json!({"retry_after": 1})), &backoff, 1), Duration::from_secs(3));
+    }
+}
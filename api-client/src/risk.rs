@@ -0,0 +1,418 @@
+//! Pre-trade risk limits.
+//!
+//! `OrderValidator` catches orders the exchange would refuse; it does not stop a strategy
+//! with a wrong size or price in its config from sending valid orders that are far too big,
+//! or too many. With a `RiskConfig` set on the builder, `create_order` (and everything built
+//! on it: market orders, `OrderRouter`, TWAP slices) checks each order against per-market
+//! and account-wide caps before a nonce is leased or anything is signed:
+//!
+//! - the quote value of orders sent within a sliding window (one minute by default),
+//! - the number of open orders, counting the new one,
+//! - the absolute position the order would leave if it filled (per market only).
+//!
+//! Each leg of `create_grouped_orders` is checked the same way, and `modify_order` checks the
+//! new size and price of the order it changes, which already counts as open.
+//!
+//! A breach fails with `ApiError::RiskRejected`. Reduce-only orders are never blocked, so
+//! positions can always be closed. Orders count toward the notional window once they pass
+//! the check, whether or not the exchange accepts them.
+//!
+//! Open orders and positions are read from the exchange for every checked order, and only
+//! for the limits that are set; the account-wide open order cap queries every market.
+
+use crate::validation::MarketRules;
+use crate::{ApiError, CreateOrderRequest, LighterClient, ModifyOrderRequest, OrderType, Result, TimeInForce};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Caps on the orders of one market, or of the whole account.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RiskLimits {
+    /// Quote value of the orders sent within `RiskConfig::window`, this one included.
+    pub max_notional: Option<f64>,
+    /// Open orders, this one included.
+    pub max_open_orders: Option<usize>,
+    /// Absolute position in base amount units if the order filled; ignored account-wide,
+    /// where markets have different units.
+    pub max_position: Option<i64>,
+}
+
+impl RiskLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn max_notional(mut self, max: f64) -> Self {
+        self.max_notional = Some(max);
+        self
+    }
+
+    pub fn max_open_orders(mut self, max: usize) -> Self {
+        self.max_open_orders = Some(max);
+        self
+    }
+
+    pub fn max_position(mut self, max: i64) -> Self {
+        self.max_position = Some(max);
+        self
+    }
+}
+
+/// Per-market and account-wide risk limits.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RiskConfig {
+    /// Length of the sliding window of `max_notional`.
+    pub window: Duration,
+    /// Limits over all markets together.
+    pub account: RiskLimits,
+    pub markets: HashMap<u8, RiskLimits>,
+}
+
+impl Default for RiskConfig {
+    /// No limits, over a one-minute window.
+    fn default() -> Self {
+        Self {
+            window: Duration::from_secs(60),
+            account: RiskLimits::default(),
+            markets: HashMap::new(),
+        }
+    }
+}
+
+impl RiskConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Limits over all markets together.
+    pub fn with_account_limits(mut self, limits: RiskLimits) -> Self {
+        self.account = limits;
+        self
+    }
+
+    /// Limits of orders on `market_index`.
+    pub fn with_market(mut self, market_index: u8, limits: RiskLimits) -> Self {
+        self.markets.insert(market_index, limits);
+        self
+    }
+}
+
+/// The limit an order would have breached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RiskLimit {
+    Notional,
+    OpenOrders,
+    Position,
+}
+
+impl fmt::Display for RiskLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RiskLimit::Notional => "notional",
+            RiskLimit::OpenOrders => "open orders",
+            RiskLimit::Position => "position",
+        })
+    }
+}
+
+/// Open orders and position the checks run against.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct Exposure {
+    pub(crate) market_open_orders: usize,
+    pub(crate) account_open_orders: usize,
+    /// Signed position on the order's market, positive when long.
+    pub(crate) position: i64,
+}
+
+/// Risk limits and the notional sent within the window; does nothing without a config.
+#[derive(Debug, Default)]
+pub(crate) struct RiskGuard {
+    config: Option<RiskConfig>,
+    // Time, market and notional of the orders that passed the check
+    sent: Mutex<VecDeque<(Instant, u8, f64)>>,
+    // Decimals of the markets with a notional limit, fetched once
+    rules: Mutex<HashMap<u8, MarketRules>>,
+}
+
+impl RiskGuard {
+    pub(crate) fn new(config: Option<RiskConfig>) -> Self {
+        Self {
+            config,
+            ..Self::default()
+        }
+    }
+
+    fn market_limits(&self, market_index: u8) -> Option<&RiskLimits> {
+        self.config.as_ref()?.markets.get(&market_index)
+    }
+
+    /// Checks `order` of quote value `notional` and records it in the window if it passes.
+    /// `new_orders` is the number of orders its transaction adds to the book up to this one.
+    pub(crate) fn check(
+        &self,
+        order: &CreateOrderRequest,
+        notional: f64,
+        exposure: &Exposure,
+        new_orders: usize,
+        now: Instant,
+    ) -> Result<()> {
+        let Some(config) = &self.config else {
+            return Ok(());
+        };
+        let market = order.order_book_index;
+        let market_limits = config.markets.get(&market);
+        let reject = |scope: Option<u8>, limit: RiskLimit, message: String| ApiError::RiskRejected {
+            market_index: scope,
+            limit,
+            message,
+        };
+
+        let mut sent = self.sent.lock().unwrap_or_else(|e| e.into_inner());
+        while sent.front().is_some_and(|(at, _, _)| now.duration_since(*at) >= config.window) {
+            sent.pop_front();
+        }
+
+        for (scope, limits) in [(Some(market), market_limits), (None, Some(&config.account))] {
+            let Some(limits) = limits else { continue };
+            if let Some(max) = limits.max_notional {
+                let recent: f64 = sent
+                    .iter()
+                    .filter(|(_, m, _)| scope.is_none_or(|scope| *m == scope))
+                    .map(|(_, _, notional)| notional)
+                    .sum();
+                if recent + notional > max {
+                    return Err(reject(
+                        scope,
+                        RiskLimit::Notional,
+                        format!("{} sent in {:?}, {} more would exceed {}", recent, config.window, notional, max),
+                    ));
+                }
+            }
+            if let Some(max) = limits.max_open_orders {
+                let open = if scope.is_some() { exposure.market_open_orders } else { exposure.account_open_orders };
+                if open + new_orders > max {
+                    return Err(reject(scope, RiskLimit::OpenOrders, format!("{} open, limit {}", open, max)));
+                }
+            }
+        }
+
+        if let Some(max) = market_limits.and_then(|limits| limits.max_position) {
+            let delta = if order.is_ask { -order.base_amount.0 } else { order.base_amount.0 };
+            let after = exposure.position.saturating_add(delta);
+            // Orders that shrink the position pass even above the limit
+            if after.unsigned_abs() > max.unsigned_abs() && after.unsigned_abs() > exposure.position.unsigned_abs() {
+                return Err(reject(
+                    Some(market),
+                    RiskLimit::Position,
+                    format!("Position {} would become {}, limit {}", exposure.position, after, max),
+                ));
+            }
+        }
+
+        sent.push_back((now, market, notional));
+        Ok(())
+    }
+}
+
+impl LighterClient {
+    /// Risk limits checked before orders are signed, if any
+    pub fn risk_config(&self) -> Option<&RiskConfig> {
+        self.risk.config.as_ref()
+    }
+
+    /// Check `order` against the client's `RiskConfig`
+    ///
+    /// Fetches the open orders and the position the configured limits need. Fails with
+    /// `ApiError::RiskRejected` on a breach; passing orders count toward the notional window.
+    pub async fn check_risk(&self, order: &CreateOrderRequest) -> Result<()> {
+        self.check_risk_adding(order, 1).await
+    }
+
+    /// Check the new size and price of the order `request` modifies
+    ///
+    /// Looks the order up among the open orders of its market for its side and reduce-only
+    /// flag, and fails with `ApiError::InvalidOrder` if it is not open.
+    pub(crate) async fn check_modify_risk(&self, request: &ModifyOrderRequest) -> Result<()> {
+        if self.risk.config.is_none() {
+            return Ok(());
+        }
+        let open = self
+            .get_open_orders(Some(request.market_index))
+            .await?
+            .into_iter()
+            .find(|order| order.order_index == request.order_index)
+            .ok_or_else(|| {
+                ApiError::InvalidOrder(format!(
+                    "Order {} is not open on market {}",
+                    request.order_index, request.market_index
+                ))
+            })?;
+        let resized = CreateOrderRequest {
+            account_index: self.account_index,
+            order_book_index: request.market_index,
+            client_order_index: u64::try_from(open.client_order_index).unwrap_or_default(),
+            base_amount: request.base_amount,
+            price: request.price,
+            is_ask: open.is_ask,
+            order_type: OrderType::Limit,
+            time_in_force: TimeInForce::GoodTillTime,
+            reduce_only: open.reduce_only,
+            trigger_price: request.trigger_price,
+            price_protection: None,
+            order_expiry: None,
+        };
+        // The order is already among the open ones
+        self.check_risk_adding(&resized, 0).await
+    }
+
+    /// Check `order` as the `new_orders`-th order its transaction adds to the book
+    pub(crate) async fn check_risk_adding(&self, order: &CreateOrderRequest, new_orders: usize) -> Result<()> {
+        let Some(config) = &self.risk.config else {
+            return Ok(());
+        };
+        if order.reduce_only {
+            return Ok(());
+        }
+        let market = order.order_book_index;
+        let market_limits = self.risk.market_limits(market).cloned().unwrap_or_default();
+
+        let notional = if market_limits.max_notional.is_some() || config.account.max_notional.is_some() {
+            if order.price.is_zero() {
+                return Err(ApiError::RiskRejected {
+                    market_index: Some(market),
+                    limit: RiskLimit::Notional,
+                    message: "Order without a price cannot be valued".to_string(),
+                });
+            }
+            self.risk_rules(market).await?.notional(order.base_amount, order.price)
+        } else {
+            0.0
+        };
+
+        let mut exposure = Exposure::default();
+        if market_limits.max_open_orders.is_some() {
+            exposure.market_open_orders = self.get_open_orders(Some(market)).await?.len();
+        }
+        if config.account.max_open_orders.is_some() {
+            exposure.account_open_orders = self.get_open_orders(None).await?.len();
+        }
+        if market_limits.max_position.is_some() {
+            exposure.position = match self.get_position_size(market).await? {
+                Some(position) if position.is_long => position.base_amount,
+                Some(position) => -position.base_amount,
+                None => 0,
+            };
+        }
+
+        self.risk.check(order, notional, &exposure, new_orders, Instant::now())
+    }
+
+    async fn risk_rules(&self, market_index: u8) -> Result<MarketRules> {
+        if let Some(rules) = self.risk.rules.lock().unwrap_or_else(|e| e.into_inner()).get(&market_index) {
+            return Ok(rules.clone());
+        }
+        let rules = self.get_market_rules(market_index).await?;
+        self.risk
+            .rules
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(market_index, rules.clone());
+        Ok(rules)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BaseAmount, OrderType, Price, TimeInForce};
+
+    fn order(market: u8, is_ask: bool, base_amount: i64) -> CreateOrderRequest {
+        CreateOrderRequest {
+            account_index: 1,
+            order_book_index: market,
+            client_order_index: 0,
+            base_amount: BaseAmount(base_amount),
+            price: Price(300_000),
+            is_ask,
+            order_type: OrderType::Limit,
+            time_in_force: TimeInForce::GoodTillTime,
+            reduce_only: false,
+            trigger_price: Price::ZERO,
+            price_protection: None,
+            order_expiry: None,
+        }
+    }
+
+    fn rejected_limit(result: Result<()>) -> Option<(Option<u8>, RiskLimit)> {
+        match result {
+            Err(ApiError::RiskRejected { market_index, limit, .. }) => Some((market_index, limit)),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_notional_window() {
+        let config = RiskConfig::new()
+            .with_window(Duration::from_secs(60))
+            .with_market(0, RiskLimits::new().max_notional(1_000.0))
+            .with_account_limits(RiskLimits::new().max_notional(1_500.0));
+        let guard = RiskGuard::new(Some(config));
+        let flat = Exposure::default();
+        let start = Instant::now();
+
+        assert!(guard.check(&order(0, false, 1), 600.0, &flat, 1, start).is_ok());
+        let over = guard.check(&order(0, false, 1), 600.0, &flat, 1, start + Duration::from_secs(1));
+        assert_eq!(rejected_limit(over), Some((Some(0), RiskLimit::Notional)));
+        // Other markets only see the account-wide cap
+        assert!(guard.check(&order(1, false, 1), 600.0, &flat, 1, start + Duration::from_secs(2)).is_ok());
+        let account = guard.check(&order(1, false, 1), 600.0, &flat, 1, start + Duration::from_secs(3));
+        assert_eq!(rejected_limit(account), Some((None, RiskLimit::Notional)));
+        // The first order leaves the window
+        assert!(guard.check(&order(0, false, 1), 600.0, &flat, 1, start + Duration::from_secs(61)).is_ok());
+    }
+
+    #[test]
+    fn test_open_orders_and_position() {
+        let config = RiskConfig::new()
+            .with_market(0, RiskLimits::new().max_open_orders(2).max_position(100))
+            .with_account_limits(RiskLimits::new().max_open_orders(5));
+        let guard = RiskGuard::new(Some(config));
+        let now = Instant::now();
+        let exposure = |market_open_orders, account_open_orders, position| Exposure {
+            market_open_orders,
+            account_open_orders,
+            position,
+        };
+
+        assert!(guard.check(&order(0, false, 10), 0.0, &exposure(1, 1, 0), 1, now).is_ok());
+        let market = guard.check(&order(0, false, 10), 0.0, &exposure(2, 2, 0), 1, now);
+        assert_eq!(rejected_limit(market), Some((Some(0), RiskLimit::OpenOrders)));
+        let account = guard.check(&order(0, false, 10), 0.0, &exposure(0, 5, 0), 1, now);
+        assert_eq!(rejected_limit(account), Some((None, RiskLimit::OpenOrders)));
+        // A modified order is already open; the second leg of a group counts the first
+        assert!(guard.check(&order(0, false, 10), 0.0, &exposure(2, 2, 0), 0, now).is_ok());
+        let second_leg = guard.check(&order(0, false, 10), 0.0, &exposure(1, 1, 0), 2, now);
+        assert_eq!(rejected_limit(second_leg), Some((Some(0), RiskLimit::OpenOrders)));
+
+        let long = guard.check(&order(0, false, 30), 0.0, &exposure(0, 0, 80), 1, now);
+        assert_eq!(rejected_limit(long), Some((Some(0), RiskLimit::Position)));
+        // Selling from a long above the limit reduces it
+        assert!(guard.check(&order(0, true, 30), 0.0, &exposure(0, 0, 150), 1, now).is_ok());
+        let short = guard.check(&order(0, true, 130), 0.0, &exposure(0, 0, 20), 1, now);
+        assert_eq!(rejected_limit(short), Some((Some(0), RiskLimit::Position)));
+    }
+
+    #[test]
+    fn test_no_config_allows_everything() {
+        let guard = RiskGuard::new(None);
+        let huge = order(0, false, i64::MAX);
+        assert!(guard.check(&huge, f64::MAX, &Exposure::default(), 1, Instant::now()).is_ok());
+    }
+}
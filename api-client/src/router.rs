@@ -0,0 +1,166 @@
+//! Per-market ordering of order operations.
+//!
+//! Tasks sharing a `LighterClient` race for nonces, so a cancel and the replace that
+//! should follow it can reach the exchange in either order. `OrderRouter` queues the
+//! operations of each market and runs them one at a time in the order they were submitted,
+//! while operations on different markets run in parallel, up to a concurrency cap.
+//!
+//! An operation that hangs (a sendTx that never answers) would hold its market's queue
+//! forever. `with_latency_budget` bounds each operation, queue wait included, and
+//! `execute_within` takes a `Budget` per call; an operation over budget fails with
+//! `ApiError::DeadlineExceeded` and releases its nonce and its place in the queue.
+
+use crate::{Budget, CreateOrderRequest, LighterClient, ModifyOrderRequest, Result, WithBudget};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Semaphore;
+
+/// Routes order operations through one client, serialized per market.
+pub struct OrderRouter {
+    client: Arc<LighterClient>,
+    // FIFO queue of each market; tokio mutexes grant the lock in request order
+    markets: Mutex<HashMap<u8, Arc<tokio::sync::Mutex<()>>>>,
+    // Caps operations in flight across markets
+    in_flight: Semaphore,
+    latency_budget: Option<Duration>,
+}
+
+impl OrderRouter {
+    /// Router running at most `max_concurrency` operations at once (at least 1).
+    pub fn new(client: Arc<LighterClient>, max_concurrency: usize) -> Self {
+        Self {
+            client,
+            markets: Mutex::new(HashMap::new()),
+            in_flight: Semaphore::new(max_concurrency.max(1)),
+            latency_budget: None,
+        }
+    }
+
+    /// Fail operations that take longer than `budget` from submission, queue wait included.
+    pub fn with_latency_budget(mut self, budget: Duration) -> Self {
+        self.latency_budget = Some(budget);
+        self
+    }
+
+    pub fn client(&self) -> &Arc<LighterClient> {
+        &self.client
+    }
+
+    /// Run `operation` in the queue of `market_index`
+    ///
+    /// It starts once every operation submitted earlier for the market has finished and a
+    /// concurrency slot is free. Waiting for the market does not hold a slot, so a busy
+    /// market never blocks the others. Bounded by the router's latency budget, if set.
+    pub async fn execute<F, Fut, T>(&self, market_index: u8, operation: F) -> Result<T>
+    where
+        F: FnOnce(Arc<LighterClient>) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let budget = self.latency_budget.map(Budget::timeout).unwrap_or_default();
+        self.execute_within(market_index, budget, operation).await
+    }
+
+    /// Like `execute`, bounded by `budget` instead of the router's latency budget
+    pub async fn execute_within<F, Fut, T>(&self, market_index: u8, budget: impl Into<Budget>, operation: F) -> Result<T>
+    where
+        F: FnOnce(Arc<LighterClient>) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let queue = self.queue(market_index);
+        async {
+            let _turn = queue.lock().await;
+            let _slot = self.in_flight.acquire().await.expect("router semaphore is never closed");
+            operation(self.client.clone()).await
+        }
+        .within(budget)
+        .await
+    }
+
+    pub async fn create_order(&self, order: CreateOrderRequest) -> Result<Value> {
+        self.execute(order.order_book_index, |client| async move { client.create_order(order).await })
+            .await
+    }
+
+    pub async fn cancel_order(&self, market_index: u8, order_index: i64) -> Result<Value> {
+        self.execute(market_index, |client| async move { client.cancel_order(market_index, order_index).await })
+            .await
+    }
+
+    pub async fn modify_order(&self, request: ModifyOrderRequest) -> Result<Value> {
+        self.execute(request.market_index, |client| async move { client.modify_order(request).await })
+            .await
+    }
+
+    fn queue(&self, market_index: u8) -> Arc<tokio::sync::Mutex<()>> {
+        let mut markets = self.markets.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        markets.entry(market_index).or_default().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_per_market_order_and_concurrency_cap() {
+        let private_key = "bda332f3aaa2d9cfdd8920830ea37efce9636c671a426bd4cb9815007e2a2917604ab47857cbb200";
+        let client = LighterClient::new("http://127.0.0.1:9".to_string(), private_key, 1, 0).unwrap();
+        let router = Arc::new(OrderRouter::new(Arc::new(client), 2));
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+
+        let mut tasks = Vec::new();
+        for i in 0..12u64 {
+            let market = (i % 3) as u8;
+            let (router, log, running, peak) = (router.clone(), log.clone(), running.clone(), peak.clone());
+            tasks.push(tokio::spawn(async move {
+                router
+                    .execute(market, |_| async move {
+                        peak.fetch_max(running.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+                        // Later operations finish faster, so only the queue keeps them in order
+                        tokio::time::sleep(Duration::from_millis(20 - i)).await;
+                        log.lock().unwrap().push((market, i));
+                        running.fetch_sub(1, Ordering::SeqCst);
+                        Ok(())
+                    })
+                    .await
+            }));
+            // Let the task reach its market's queue before the next one is spawned
+            tokio::task::yield_now().await;
+        }
+        for task in tasks {
+            task.await.unwrap().unwrap();
+        }
+
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+        let log = log.lock().unwrap();
+        for market in 0..3u8 {
+            let order: Vec<u64> = log.iter().filter(|(m, _)| *m == market).map(|(_, i)| *i).collect();
+            assert!(order.windows(2).all(|w| w[0] < w[1]), "market {}: {:?}", market, order);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_latency_budget_frees_a_hanging_market() {
+        let private_key = "bda332f3aaa2d9cfdd8920830ea37efce9636c671a426bd4cb9815007e2a2917604ab47857cbb200";
+        let client = LighterClient::new("http://127.0.0.1:9".to_string(), private_key, 1, 0).unwrap();
+        let router = Arc::new(OrderRouter::new(Arc::new(client), 2).with_latency_budget(Duration::from_millis(30)));
+
+        let hanging = tokio::spawn({
+            let router = router.clone();
+            async move { router.execute(0, |_| std::future::pending::<Result<()>>()).await }
+        });
+        tokio::task::yield_now().await;
+        // Queued behind the hanging operation, with a budget of its own
+        let next = router.execute_within(0, Duration::from_secs(5), |_| async { Ok(7) }).await;
+
+        assert!(matches!(hanging.await.unwrap(), Err(crate::ApiError::DeadlineExceeded)));
+        assert_eq!(next.unwrap(), 7);
+    }
+}
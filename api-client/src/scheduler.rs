@@ -0,0 +1,183 @@
+//! Delayed transaction submission.
+//!
+//! `TxScheduler` holds signed transactions until their execute-at time and submits them
+//! from a background task. A transaction signed well ahead of time may no longer be
+//! valid when it is due: if its `ExpiredAt` has passed (or is about to), it is re-signed
+//! with a freshly leased nonce and a new expiry. A rejected nonce is resynced by `send_tx`.
+
+use crate::{ApiError, LighterClient, Result};
+use serde_json::Value;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+
+/// Transactions expiring within this margin of their submission are re-signed.
+const EXPIRY_MARGIN_MS: i64 = 5_000;
+
+struct Job {
+    execute_at: SystemTime,
+    seq: u64,
+    tx_type: u32,
+    tx_info: Value,
+    result: oneshot::Sender<Result<Value>>,
+}
+
+impl PartialEq for Job {
+    fn eq(&self, other: &Self) -> bool {
+        (self.execute_at, self.seq) == (other.execute_at, other.seq)
+    }
+}
+
+impl Eq for Job {}
+
+impl PartialOrd for Job {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Job {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.execute_at, self.seq).cmp(&(other.execute_at, other.seq))
+    }
+}
+
+/// Handle to a scheduled transaction.
+pub struct ScheduledTx {
+    result: oneshot::Receiver<Result<Value>>,
+}
+
+impl ScheduledTx {
+    /// Wait for the submission and return the exchange response
+    ///
+    /// Errors if the scheduler shut down before the transaction was due.
+    pub async fn result(self) -> Result<Value> {
+        self.result
+            .await
+            .unwrap_or_else(|_| Err(ApiError::Api("Scheduler stopped before the transaction was sent".to_string())))
+    }
+}
+
+/// Submits transactions at scheduled times from a background task.
+///
+/// Due transactions are sent one at a time in execute-at order (ties in scheduling
+/// order). Dropping the scheduler stops it; transactions not yet due are discarded.
+pub struct TxScheduler {
+    sender: mpsc::UnboundedSender<Job>,
+    task: JoinHandle<()>,
+    next_seq: AtomicU64,
+}
+
+impl TxScheduler {
+    /// Start a scheduler submitting through `client`
+    pub fn spawn(client: Arc<LighterClient>) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let task = tokio::spawn(run(client, receiver));
+        Self {
+            sender,
+            task,
+            next_seq: AtomicU64::new(0),
+        }
+    }
+
+    /// Submit a signed transaction at `execute_at`
+    ///
+    /// # Arguments
+    /// * `tx_type` - Transaction type code, used for re-signing
+    /// * `tx_info` - Signed transaction, e.g. from `sign_create_order_with_nonce`
+    /// * `execute_at` - Submission time; times in the past submit immediately
+    pub fn schedule(&self, tx_type: u32, tx_info: Value, execute_at: SystemTime) -> Result<ScheduledTx> {
+        let (result, receiver) = oneshot::channel();
+        let seq = self.next_seq.fetch_add(1, Ordering::Relaxed);
+        self.sender
+            .send(Job {
+                execute_at,
+                seq,
+                tx_type,
+                tx_info,
+                result,
+            })
+            .map_err(|_| ApiError::Api("Scheduler is not running".to_string()))?;
+        Ok(ScheduledTx { result: receiver })
+    }
+
+    /// Submit a signed transaction after `delay`
+    pub fn schedule_in(&self, tx_type: u32, tx_info: Value, delay: Duration) -> Result<ScheduledTx> {
+        self.schedule(tx_type, tx_info, SystemTime::now() + delay)
+    }
+}
+
+impl Drop for TxScheduler {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn run(client: Arc<LighterClient>, mut receiver: mpsc::UnboundedReceiver<Job>) {
+    let mut queue: BinaryHeap<Reverse<Job>> = BinaryHeap::new();
+    loop {
+        let wait = queue
+            .peek()
+            .map(|Reverse(job)| job.execute_at.duration_since(SystemTime::now()).unwrap_or_default());
+
+        tokio::select! {
+            job = receiver.recv() => match job {
+                Some(job) => queue.push(Reverse(job)),
+                None => return,
+            },
+            _ = tokio::time::sleep(wait.unwrap_or_default()), if wait.is_some() => {
+                if let Some(Reverse(job)) = queue.pop() {
+                    let result = client.submit_scheduled(job.tx_type, job.tx_info).await;
+                    let _ = job.result.send(result);
+                }
+            }
+        }
+    }
+}
+
+impl LighterClient {
+    /// Sends a previously signed transaction, re-signing it if it has expired.
+    /// A nonce rejection is handled by `send_tx`.
+    async fn submit_scheduled(&self, tx_type: u32, tx_info: Value) -> Result<Value> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
+        if !needs_resign(&tx_info, now) {
+            return self.send_tx(tx_type, &tx_info, self.price_protection_param(None)).await;
+        }
+
+        let lease = self.lease_nonce().await?;
+        let tx_info = self.resign(tx_type, tx_info, lease.nonce()).await?;
+        let response = self.send_tx(tx_type, &tx_info, self.price_protection_param(None)).await;
+        lease.settle_outcome(&response);
+        response
+    }
+}
+
+/// Whether `tx_info` expires within the margin of `now_ms` (or has no readable expiry).
+fn needs_resign(tx_info: &Value, now_ms: i64) -> bool {
+    tx_info["ExpiredAt"]
+        .as_i64()
+        .is_none_or(|expired_at| expired_at <= now_ms + EXPIRY_MARGIN_MS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nonce::is_nonce_error;
+    use serde_json::json;
+
+    #[test]
+    fn test_needs_resign() {
+        let now = 1_700_000_000_000;
+        assert!(!needs_resign(&json!({"ExpiredAt": now + 60_000}), now));
+        assert!(needs_resign(&json!({"ExpiredAt": now + 1_000}), now));
+        assert!(needs_resign(&json!({"ExpiredAt": now - 1}), now));
+        assert!(needs_resign(&json!({}), now));
+
+        assert!(is_nonce_error(&json!({"code": 21104, "message": "invalid nonce"})));
+        assert!(!is_nonce_error(&json!({"code": 200, "message": "nonce ok"})));
+    }
+}
@@ -0,0 +1,148 @@
+//! Detection of exchange response schema changes.
+//!
+//! Typed responses (`Order`, `FundingPayment`, `WithdrawRecord`, `ApiKeyInfo` and their
+//! pages) are parsed leniently: fields the client does not know are ignored and missing
+//! optional fields take their defaults. That keeps trading running through an exchange
+//! release, but also hides the change until some parsing logic quietly goes wrong.
+//!
+//! Two ways to notice earlier:
+//!
+//! - With `LighterClientBuilder::log_schema_drift`, every typed response is compared with
+//!   what the client understood of it. Unknown fields and fields that were missing (and
+//!   defaulted) are logged once per endpoint and field path, as `tracing` warnings with
+//!   target `api_client::schema`, e.g.
+//!   `/api/v1/positionFunding: unknown field position_fundings[].funding_fee`.
+//! - With the `strict` feature, the entry types deny unknown fields, so a response with a
+//!   new field fails to parse with `ApiError::Json`. Meant for CI runs against testnet, not
+//!   for production.
+
+use crate::{LighterClient, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+/// Envelope fields of every response, not part of the typed payload.
+const ENVELOPE_FIELDS: [&str; 2] = ["code", "message"];
+
+/// Fields by which a response differs from what the client parsed out of it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SchemaDrift {
+    /// Fields of the response the client does not know, as paths like `orders[].owner`.
+    pub unknown: Vec<String>,
+    /// Fields the client knows that the response did not carry.
+    pub missing: Vec<String>,
+}
+
+impl SchemaDrift {
+    /// Compares a raw response with the re-serialized value the client parsed from it.
+    pub fn between(raw: &Value, parsed: &Value) -> Self {
+        let mut drift = SchemaDrift::default();
+        drift.compare("", raw, parsed);
+        drift.unknown.retain(|path| !ENVELOPE_FIELDS.contains(&path.as_str()));
+        drift.unknown.sort();
+        drift.unknown.dedup();
+        drift.missing.sort();
+        drift.missing.dedup();
+        drift
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.unknown.is_empty() && self.missing.is_empty()
+    }
+
+    fn compare(&mut self, path: &str, raw: &Value, parsed: &Value) {
+        let join = |key: &str| if path.is_empty() { key.to_string() } else { format!("{}.{}", path, key) };
+        match (raw, parsed) {
+            (Value::Object(raw), Value::Object(parsed)) => {
+                for (key, value) in raw {
+                    match parsed.get(key) {
+                        Some(parsed_value) => self.compare(&join(key), value, parsed_value),
+                        None => self.unknown.push(join(key)),
+                    }
+                }
+                let absent = parsed.keys().filter(|key| !raw.contains_key(*key));
+                self.missing.extend(absent.map(|key| join(key)));
+            }
+            (Value::Array(raw), Value::Array(parsed)) => {
+                let items = format!("{}[]", path);
+                for (raw, parsed) in raw.iter().zip(parsed) {
+                    self.compare(&items, raw, parsed);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Logs schema drift once per endpoint and field; does nothing unless enabled.
+#[derive(Debug, Default)]
+pub(crate) struct SchemaWatch {
+    enabled: bool,
+    reported: Mutex<HashSet<String>>,
+}
+
+impl SchemaWatch {
+    pub(crate) fn new(enabled: bool) -> Self {
+        Self { enabled, reported: Mutex::default() }
+    }
+
+    /// Logs the fields of `drift` not reported for `endpoint` before; returns them.
+    fn report(&self, endpoint: &str, drift: &SchemaDrift) -> Vec<String> {
+        let mut reported = self.reported.lock().unwrap_or_else(|e| e.into_inner());
+        let unknown = drift.unknown.iter().map(|path| format!("{}: unknown field {}", endpoint, path));
+        let missing = drift.missing.iter().map(|path| format!("{}: missing field {}", endpoint, path));
+        let new: Vec<String> = unknown.chain(missing).filter(|line| reported.insert(line.clone())).collect();
+        for line in &new {
+            tracing::warn!(target: "api_client::schema", "{}", line);
+        }
+        new
+    }
+}
+
+impl LighterClient {
+    /// Parse a typed response of `endpoint`, logging schema drift if enabled
+    pub(crate) fn parse_typed<T: DeserializeOwned + Serialize>(&self, endpoint: &str, response: Value) -> Result<T> {
+        let parsed = T::deserialize(&response)?;
+        if self.schema.enabled {
+            let drift = SchemaDrift::between(&response, &serde_json::to_value(&parsed)?);
+            self.schema.report(endpoint, &drift);
+        }
+        Ok(parsed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OrderPage;
+    use serde_json::json;
+
+    #[test]
+    fn test_schema_drift() {
+        let order = |order_index: i64| {
+            json!({"order_index": order_index, "client_order_index": 1, "market_index": 0, "is_ask": false,
+                   "status": "open", "price": "3000.00"})
+        };
+        let page: OrderPage = serde_json::from_value(json!({"orders": [order(7), order(8)]})).unwrap();
+        // The exchange adds fields the client does not know yet
+        let (mut first, mut second) = (order(7), order(8));
+        first["owner_account_index"] = json!(1);
+        second["owner_account_index"] = json!(1);
+        second["nonce"] = json!(5);
+        let raw = json!({"code": 200, "orders": [first, second]});
+
+        let drift = SchemaDrift::between(&raw, &serde_json::to_value(&page).unwrap());
+        assert_eq!(drift.unknown, vec!["orders[].nonce", "orders[].owner_account_index"]);
+        assert!(drift.missing.contains(&"orders[].trigger_price".to_string()));
+        assert!(drift.missing.contains(&"next_cursor".to_string()));
+        assert!(!drift.missing.iter().any(|path| path.ends_with("order_index")));
+
+        // Each field is reported once per endpoint
+        let watch = SchemaWatch::new(true);
+        assert_eq!(watch.report("/api/v1/accountActiveOrders", &drift).len(), drift.unknown.len() + drift.missing.len());
+        assert!(watch.report("/api/v1/accountActiveOrders", &drift).is_empty());
+        assert!(!watch.report("/api/v1/accountInactiveOrders", &drift).is_empty());
+    }
+}
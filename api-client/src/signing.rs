@@ -0,0 +1,134 @@
+//! Where transactions and auth tokens get signed.
+//!
+//! The client signs through a `Signer`: by default the `KeyManager` built from the private
+//! key given to the builder, or any other implementation passed to
+//! `LighterClient::builder_with_signer`. This lets the key live outside the trading host,
+//! in an HSM or a signing service, with the client only ever handing out 40-byte hashes.
+//!
+//! `RemoteSigner` is such a signer over HTTP. It speaks a small JSON protocol:
+//!
+//! - `GET {url}/public_key` answers `{"public_key": "<80 hex chars>"}`
+//! - `POST {url}/sign` with `{"public_key": "<hex>", "message": "<80 hex chars>"}` answers
+//!   `{"signature": "<160 hex chars>"}`, the `s ‖ e` signature of the message
+//!
+//! Requests carry `Authorization: Bearer <token>` when a token is set. Every signature is
+//! verified against the public key before use, so a misbehaving service fails the request
+//! instead of getting a transaction rejected by the exchange. Services speaking another
+//! protocol (gRPC, a vendor HSM API) implement `Signer` directly.
+
+use crate::{ApiError, Result};
+use futures_core::future::BoxFuture;
+use reqwest::Client;
+use serde_json::{json, Value};
+use signer::{KeyManager, PublicKey, Signature};
+use std::fmt;
+use zeroize::Zeroizing;
+
+/// Holder of the API private key.
+pub trait Signer: Send + Sync {
+    /// Public key of the signing key, as registered with the exchange.
+    fn public_key(&self) -> PublicKey;
+
+    /// Schnorr signature of a 40-byte transaction or auth token hash.
+    fn sign<'a>(&'a self, message: &'a [u8; 40]) -> BoxFuture<'a, Result<Signature>>;
+}
+
+impl Signer for KeyManager {
+    fn public_key(&self) -> PublicKey {
+        KeyManager::public_key(self)
+    }
+
+    fn sign<'a>(&'a self, message: &'a [u8; 40]) -> BoxFuture<'a, Result<Signature>> {
+        Box::pin(async move { KeyManager::sign(self, message).map_err(ApiError::Signer) })
+    }
+}
+
+/// Signs through a remote signing service, see `signing` for the protocol.
+pub struct RemoteSigner {
+    client: Client,
+    url: String,
+    bearer_token: Option<Zeroizing<String>>,
+    public_key: PublicKey,
+}
+
+/// The bearer token is not printed.
+impl fmt::Debug for RemoteSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RemoteSigner")
+            .field("url", &self.url)
+            .field("public_key", &self.public_key.fingerprint())
+            .finish_non_exhaustive()
+    }
+}
+
+impl RemoteSigner {
+    /// Connect to the service at `url` and fetch its public key
+    pub async fn connect(url: impl Into<String>, bearer_token: Option<&str>) -> Result<Self> {
+        Self::connect_with_client(Client::new(), url, bearer_token).await
+    }
+
+    /// Like `connect`, over a preconfigured HTTP client (timeouts, client certificates)
+    pub async fn connect_with_client(client: Client, url: impl Into<String>, bearer_token: Option<&str>) -> Result<Self> {
+        let url = url.into().trim_end_matches('/').to_string();
+        let bearer_token = bearer_token.map(|token| Zeroizing::new(token.to_string()));
+
+        let mut request = client.get(format!("{}/public_key", url));
+        if let Some(token) = &bearer_token {
+            request = request.bearer_auth(token.as_str());
+        }
+        let body = Self::json(request.send().await?).await?;
+        let public_key = body["public_key"]
+            .as_str()
+            .ok_or_else(|| ApiError::Api(format!("Remote signer answered without a public key: {}", body)))?;
+        let public_key = PublicKey::from_hex(public_key)?;
+
+        Ok(Self {
+            client,
+            url,
+            bearer_token,
+            public_key,
+        })
+    }
+
+    async fn json(response: reqwest::Response) -> Result<Value> {
+        let status = response.status();
+        let text = response.text().await?;
+        if !status.is_success() {
+            return Err(ApiError::Api(format!("Remote signer returned {}: {}", status, text)));
+        }
+        Ok(serde_json::from_str(&text)?)
+    }
+}
+
+impl Signer for RemoteSigner {
+    fn public_key(&self) -> PublicKey {
+        self.public_key
+    }
+
+    fn sign<'a>(&'a self, message: &'a [u8; 40]) -> BoxFuture<'a, Result<Signature>> {
+        Box::pin(async move {
+            let mut request = self.client.post(format!("{}/sign", self.url)).json(&json!({
+                "public_key": self.public_key.to_hex(),
+                "message": hex::encode(message),
+            }));
+            if let Some(token) = &self.bearer_token {
+                request = request.bearer_auth(token.as_str());
+            }
+            let body = Self::json(request.send().await?).await?;
+            let signature = body["signature"]
+                .as_str()
+                .ok_or_else(|| ApiError::Api(format!("Remote signer answered without a signature: {}", body)))?;
+            let signature = Signature::from_hex(signature).map_err(|e| ApiError::Signer(e.into()))?;
+
+            let valid = goldilocks_crypto::verify_signature(signature.as_bytes(), message, &self.public_key.to_bytes())
+                .map_err(|e| ApiError::Signer(e.into()))?;
+            if !valid {
+                return Err(ApiError::Api(format!(
+                    "Remote signer returned a signature that does not verify under {}",
+                    self.public_key
+                )));
+            }
+            Ok(signature)
+        })
+    }
+}
@@ -0,0 +1,94 @@
+//! Exchange health.
+//!
+//! The API root (`GET /`) reports whether the exchange is up, its network and the server
+//! time. `get_exchange_status` reads it together with the round trip of the request, so a
+//! strategy can stop entering positions during an outage or maintenance instead of finding
+//! out from a rejected second leg. `ping` only measures the round trip.
+
+use crate::{parse_response, ApiError, LighterClient, Result};
+use serde_json::Value;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// State of the exchange as reported by the API root.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExchangeStatus {
+    /// `status` field of the response: 1 or 200 while the exchange is up.
+    pub status: i64,
+    pub network_id: Option<i64>,
+    /// Server time (s), if reported.
+    pub server_time: Option<i64>,
+    /// Whether the exchange announced maintenance (false if it reports no such flag).
+    pub maintenance: bool,
+    /// Round trip of the status request.
+    pub latency: Duration,
+}
+
+impl ExchangeStatus {
+    fn from_response(response: &Value, latency: Duration) -> Result<Self> {
+        let status = response["status"]
+            .as_i64()
+            .or_else(|| response["code"].as_i64())
+            .ok_or_else(|| ApiError::Api(format!("Status response without status: {}", response)))?;
+        Ok(Self {
+            status,
+            network_id: response["network_id"].as_i64(),
+            server_time: response["timestamp"].as_i64(),
+            maintenance: response["maintenance"].as_bool().unwrap_or(false),
+            latency,
+        })
+    }
+
+    /// Whether the exchange is up and not in maintenance.
+    pub fn is_operational(&self) -> bool {
+        matches!(self.status, 1 | 200) && !self.maintenance
+    }
+
+    /// Server time minus local time, in seconds, if the server reported its time.
+    pub fn clock_skew(&self) -> Option<i64> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs() as i64;
+        Some(self.server_time? - now)
+    }
+}
+
+impl LighterClient {
+    /// Status of the exchange and the round trip to it
+    ///
+    /// Fails if the API cannot be reached; check `is_operational` before trading.
+    pub async fn get_exchange_status(&self) -> Result<ExchangeStatus> {
+        let started = Instant::now();
+        let response = self.client.get(format!("{}/", self.base_url)).send().await?;
+        let response = parse_response(response).await?;
+        ExchangeStatus::from_response(&response, started.elapsed())
+    }
+
+    /// Round trip of a request to the API root
+    ///
+    /// Any HTTP response counts; fails only if the API cannot be reached.
+    pub async fn ping(&self) -> Result<Duration> {
+        let started = Instant::now();
+        self.client.get(format!("{}/", self.base_url)).send().await?;
+        Ok(started.elapsed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_status_response() {
+        let latency = Duration::from_millis(12);
+        let status = ExchangeStatus::from_response(&json!({"status": 200, "network_id": 304, "timestamp": 1760000000}), latency).unwrap();
+        assert!(status.is_operational());
+        assert_eq!(status.network_id, Some(304));
+        assert_eq!(status.server_time, Some(1760000000));
+        assert_eq!(status.latency, latency);
+
+        let maintenance = ExchangeStatus::from_response(&json!({"status": 1, "maintenance": true}), latency).unwrap();
+        assert!(!maintenance.is_operational());
+        assert_eq!(maintenance.clock_skew(), None);
+        assert!(!ExchangeStatus::from_response(&json!({"status": 503}), latency).unwrap().is_operational());
+        assert!(ExchangeStatus::from_response(&json!({}), latency).is_err());
+    }
+}
@@ -0,0 +1,126 @@
+//! Typed extraction of `tx_info` fields for transaction hashing.
+//!
+//! Every field that goes into a transaction hash is read through `TxField`, which fixes
+//! both the JSON type a field must have and how it becomes a Goldilocks element. A missing
+//! field, a field of the wrong JSON type, or a value out of range for its width is an error
+//! instead of silently hashing (and signing) zero.
+
+use crate::{ApiError, Result, UsdcAmount};
+use poseidon_hash::Goldilocks;
+use serde_json::Value;
+
+/// A scalar `tx_info` field type and its Goldilocks encoding.
+pub trait TxField: Sized + Copy {
+    /// Name used in error messages.
+    const KIND: &'static str;
+
+    /// Reads the value; `None` if it is not an integer in range.
+    fn from_json(value: &Value) -> Option<Self>;
+
+    /// The element hashed for this value.
+    fn to_goldilocks(self) -> Goldilocks;
+}
+
+/// Signed fields are encoded modulo p, so -1 becomes p - 1 as in lighter-go.
+impl TxField for i64 {
+    const KIND: &'static str = "i64";
+
+    fn from_json(value: &Value) -> Option<Self> {
+        value.as_i64()
+    }
+
+    fn to_goldilocks(self) -> Goldilocks {
+        Goldilocks::from_i64(self)
+    }
+}
+
+impl TxField for u64 {
+    const KIND: &'static str = "u64";
+
+    fn from_json(value: &Value) -> Option<Self> {
+        value.as_u64()
+    }
+
+    fn to_goldilocks(self) -> Goldilocks {
+        Goldilocks::from_canonical_u64(self)
+    }
+}
+
+macro_rules! narrow_unsigned_field {
+    ($($ty:ty),*) => {$(
+        impl TxField for $ty {
+            const KIND: &'static str = stringify!($ty);
+
+            fn from_json(value: &Value) -> Option<Self> {
+                value.as_u64().and_then(|v| <$ty>::try_from(v).ok())
+            }
+
+            fn to_goldilocks(self) -> Goldilocks {
+                Goldilocks::from_canonical_u64(self as u64)
+            }
+        }
+    )*};
+}
+
+narrow_unsigned_field!(u8, u16, u32);
+
+/// Reads `key` of `tx` as `T`.
+pub fn field<T: TxField>(tx: &Value, key: &str) -> Result<T> {
+    let value = tx
+        .get(key)
+        .ok_or_else(|| ApiError::InvalidTxField(format!("missing field {}", key)))?;
+    T::from_json(value)
+        .ok_or_else(|| ApiError::InvalidTxField(format!("{} must be {}, got {}", key, T::KIND, value)))
+}
+
+/// Reads `key` of `tx` as `T` and encodes it as a Goldilocks element.
+pub fn element<T: TxField>(tx: &Value, key: &str) -> Result<Goldilocks> {
+    field::<T>(tx, key).map(T::to_goldilocks)
+}
+
+/// Reads `key` of `tx` as a `UsdcAmount` and splits it into its low and high 32 bits.
+pub fn usdc_limbs(tx: &Value, key: &str) -> Result<[Goldilocks; 2]> {
+    field::<u64>(tx, key).map(|units| UsdcAmount(units).to_limbs())
+}
+
+/// Reads `key` of `tx` as a string.
+pub fn str_field<'a>(tx: &'a Value, key: &str) -> Result<&'a str> {
+    let value = tx
+        .get(key)
+        .ok_or_else(|| ApiError::InvalidTxField(format!("missing field {}", key)))?;
+    value
+        .as_str()
+        .ok_or_else(|| ApiError::InvalidTxField(format!("{} must be a string, got {}", key, value)))
+}
+
+/// Reads `key` of `tx` as an array.
+pub fn array_field<'a>(tx: &'a Value, key: &str) -> Result<&'a [Value]> {
+    let value = tx
+        .get(key)
+        .ok_or_else(|| ApiError::InvalidTxField(format!("missing field {}", key)))?;
+    value
+        .as_array()
+        .map(Vec::as_slice)
+        .ok_or_else(|| ApiError::InvalidTxField(format!("{} must be an array, got {}", key, value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_fields_are_strict() {
+        let tx = json!({"BaseAmount": -5, "Price": 305000, "IsAsk": 1, "Market": "0", "Big": 300});
+
+        assert_eq!(field::<i64>(&tx, "BaseAmount").unwrap(), -5);
+        assert_eq!(element::<i64>(&tx, "BaseAmount").unwrap(), Goldilocks::from_i64(-5));
+        assert_eq!(field::<u32>(&tx, "Price").unwrap(), 305000);
+
+        // A typo'd key, a negative unsigned value, a string and an overflow are all rejected
+        assert!(matches!(field::<i64>(&tx, "Baseamount"), Err(ApiError::InvalidTxField(_))));
+        assert!(field::<u32>(&tx, "BaseAmount").is_err());
+        assert!(field::<u8>(&tx, "Market").is_err());
+        assert!(field::<u8>(&tx, "Big").is_err());
+    }
+}
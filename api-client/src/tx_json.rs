@@ -0,0 +1,145 @@
+//! `tx_info` JSON in lighter-go's field order.
+//!
+//! `serde_json` objects are sorted maps, so a `tx_info` built with `json!` serializes its
+//! keys alphabetically, while lighter-go marshals its tx structs in declaration order. The
+//! signed hash is computed from the fields, not the text, but the text is what the exchange
+//! receives and reconstructs, and what tooling diffs against lighter-go. `tx_info_json`
+//! writes the fields of the tx type's Go struct in declaration order, then any other fields
+//! (such as `MessageToSign`) sorted. Values are written as `serde_json` writes them, which
+//! has not been compared with the output of Go's `json.Marshal`.
+//!
+//! Every transaction the client posts (REST, batch and websocket) goes through it, and
+//! `SignedTx::tx_info_json` gives the same text for transactions signed without sending.
+
+use crate::{ApiError, Result};
+use serde_json::Value;
+
+/// Fields of lighter-go's `OrderInfo`, also the entries of grouped orders.
+const ORDER_FIELDS: &[&str] = &[
+    "MarketIndex",
+    "ClientOrderIndex",
+    "BaseAmount",
+    "Price",
+    "IsAsk",
+    "Type",
+    "TimeInForce",
+    "ReduceOnly",
+    "TriggerPrice",
+    "OrderExpiry",
+];
+
+/// Go struct field order of the `tx_info` of `tx_type`, without the common tail
+/// (`ExpiredAt`, `Nonce`, `Sig`, `L1Sig`); `None` for an unknown type.
+fn head_fields(tx_type: u32) -> Option<&'static [&'static str]> {
+    Some(match tx_type {
+        8 => &["AccountIndex", "ApiKeyIndex", "PubKey"],                    // CHANGE_PUB_KEY
+        9 => &["AccountIndex", "ApiKeyIndex"],                              // CREATE_SUB_ACCOUNT
+        10 => &["AccountIndex", "ApiKeyIndex", "OperatorFee", "InitialTotalShares", "MinOperatorShareRate"], // CREATE_PUBLIC_POOL
+        11 => &["AccountIndex", "ApiKeyIndex", "PublicPoolIndex", "Status", "OperatorFee", "MinOperatorShareRate"], // UPDATE_PUBLIC_POOL
+        12 => &["FromAccountIndex", "ApiKeyIndex", "ToAccountIndex", "USDCAmount", "Fee", "Memo"], // TRANSFER
+        13 => &["FromAccountIndex", "ApiKeyIndex", "USDCAmount"],           // WITHDRAW
+        14 => &[
+            "AccountIndex",
+            "ApiKeyIndex",
+            "MarketIndex",
+            "ClientOrderIndex",
+            "BaseAmount",
+            "Price",
+            "IsAsk",
+            "Type",
+            "TimeInForce",
+            "ReduceOnly",
+            "TriggerPrice",
+            "OrderExpiry",
+        ], // CREATE_ORDER
+        15 => &["AccountIndex", "ApiKeyIndex", "MarketIndex", "Index"],     // CANCEL_ORDER
+        16 => &["AccountIndex", "ApiKeyIndex", "TimeInForce", "Time"],      // CANCEL_ALL_ORDERS
+        17 => &["AccountIndex", "ApiKeyIndex", "MarketIndex", "Index", "BaseAmount", "Price", "TriggerPrice"], // MODIFY_ORDER
+        18 | 19 => &["AccountIndex", "ApiKeyIndex", "PublicPoolIndex", "ShareAmount"], // MINT_SHARES, BURN_SHARES
+        20 => &["AccountIndex", "ApiKeyIndex", "MarketIndex", "InitialMarginFraction", "MarginMode"], // UPDATE_LEVERAGE
+        28 => &["AccountIndex", "ApiKeyIndex", "GroupingType", "Orders"],   // CREATE_GROUPED_ORDERS
+        29 => &["AccountIndex", "ApiKeyIndex", "MarketIndex", "USDCAmount", "Direction"], // UPDATE_MARGIN
+        _ => return None,
+    })
+}
+
+/// Common to every tx type, after the type's own fields; `L1Sig` only on L1-authorized ones.
+const TAIL_FIELDS: &[&str] = &["ExpiredAt", "Nonce", "Sig", "L1Sig"];
+
+/// Serializes `tx_info` of `tx_type` with lighter-go's field order
+///
+/// Fails with `ApiError::InvalidTxField` if `tx_info` is not an object or `tx_type` is not
+/// a supported transaction type.
+pub fn tx_info_json(tx_type: u32, tx_info: &Value) -> Result<String> {
+    let head = head_fields(tx_type).ok_or_else(|| ApiError::InvalidTxField(format!("unknown tx type {}", tx_type)))?;
+    let mut out = String::new();
+    write_object(&mut out, tx_info, &[head, TAIL_FIELDS].concat())?;
+    Ok(out)
+}
+
+fn write_object(out: &mut String, value: &Value, order: &[&str]) -> Result<()> {
+    let object = value
+        .as_object()
+        .ok_or_else(|| ApiError::InvalidTxField(format!("tx_info must be an object, got {}", value)))?;
+    // Known fields in order, then the rest in the map's (sorted) order
+    let known = order.iter().filter_map(|key| object.get_key_value(*key));
+    let rest = object.iter().filter(|(key, _)| !order.contains(&key.as_str()));
+
+    out.push('{');
+    for (i, (key, field)) in known.chain(rest).enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str(&serde_json::to_string(key)?);
+        out.push(':');
+        match (key.as_str(), field) {
+            ("Orders", Value::Array(orders)) => {
+                out.push('[');
+                for (j, order) in orders.iter().enumerate() {
+                    if j > 0 {
+                        out.push(',');
+                    }
+                    write_object(out, order, ORDER_FIELDS)?;
+                }
+                out.push(']');
+            }
+            _ => out.push_str(&serde_json::to_string(field)?),
+        }
+    }
+    out.push('}');
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const TX_INFO_JSON_VECTORS: &str = include_str!("../../test-vectors/tx_info_json.json");
+
+    // The vectors are written by hand in the declared field order of the Go structs, not
+    // captured from lighter-go, so this pins the layout without proving it matches
+    #[test]
+    fn test_tx_info_json_declared_order() {
+        let file: Value = serde_json::from_str(TX_INFO_JSON_VECTORS).unwrap();
+        for case in file["cases"].as_array().unwrap() {
+            let tx_type = case["tx_type"].as_u64().unwrap() as u32;
+            let expected = case["json"].as_str().unwrap();
+            // The expected text parses back to the same value, in any key order
+            let tx_info: Value = serde_json::from_str(expected).unwrap();
+            assert_eq!(tx_info_json(tx_type, &tx_info).unwrap(), expected, "tx type {}", tx_type);
+        }
+    }
+
+    #[test]
+    fn test_extra_fields_and_errors() {
+        let signed = json!({"Sig": "c2ln", "Nonce": 5, "MessageToSign": "m", "AccountIndex": 1, "ApiKeyIndex": 2,
+                            "Index": 7, "MarketIndex": 0, "ExpiredAt": 9});
+        assert_eq!(
+            tx_info_json(15, &signed).unwrap(),
+            r#"{"AccountIndex":1,"ApiKeyIndex":2,"MarketIndex":0,"Index":7,"ExpiredAt":9,"Nonce":5,"Sig":"c2ln","MessageToSign":"m"}"#
+        );
+        assert!(matches!(tx_info_json(99, &signed), Err(ApiError::InvalidTxField(_))));
+        assert!(tx_info_json(15, &json!([1, 2])).is_err());
+    }
+}
@@ -0,0 +1,134 @@
+//! Typed transaction enums.
+//!
+//! Each enum serializes as its `u8` wire value, so request structs and `tx_info` JSON keep
+//! the exchange's numeric encoding. `TryFrom<u8>` rejects unknown values with
+//! `ApiError::InvalidOrder`.
+
+use crate::ApiError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+macro_rules! wire_enum {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident {
+            $($(#[$vmeta:meta])* $variant:ident = $value:literal,)+
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+        #[serde(into = "u8", try_from = "u8")]
+        #[repr(u8)]
+        pub enum $name {
+            $($(#[$vmeta])* $variant = $value,)+
+        }
+
+        impl From<$name> for u8 {
+            fn from(value: $name) -> u8 {
+                value as u8
+            }
+        }
+
+        impl TryFrom<u8> for $name {
+            type Error = ApiError;
+
+            fn try_from(value: u8) -> Result<Self, ApiError> {
+                match value {
+                    $($value => Ok($name::$variant),)+
+                    other => Err(ApiError::InvalidOrder(format!("Unknown {} {}", stringify!($name), other))),
+                }
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Debug::fmt(self, f)
+            }
+        }
+    };
+}
+
+wire_enum! {
+    /// Order type (`Type` in `tx_info`).
+    pub enum OrderType {
+        Limit = 0,
+        Market = 1,
+        StopLoss = 2,
+        StopLossLimit = 3,
+        TakeProfit = 4,
+        TakeProfitLimit = 5,
+        Twap = 6,
+    }
+}
+
+impl OrderType {
+    /// Stop-loss orders, market or limit.
+    pub fn is_stop_loss(self) -> bool {
+        matches!(self, OrderType::StopLoss | OrderType::StopLossLimit)
+    }
+
+    /// Take-profit orders, market or limit.
+    pub fn is_take_profit(self) -> bool {
+        matches!(self, OrderType::TakeProfit | OrderType::TakeProfitLimit)
+    }
+
+    /// Orders that rest until their trigger price is reached.
+    pub fn is_trigger(self) -> bool {
+        self.is_stop_loss() || self.is_take_profit()
+    }
+}
+
+wire_enum! {
+    /// Time in force of an order (`TimeInForce` in `tx_info`).
+    pub enum TimeInForce {
+        ImmediateOrCancel = 0,
+        GoodTillTime = 1,
+        PostOnly = 2,
+    }
+}
+
+wire_enum! {
+    /// Relationship between the orders of a grouped order transaction.
+    pub enum GroupingType {
+        /// One order triggers the other.
+        Oto = 1,
+        /// One order cancels the other.
+        Oco = 2,
+        /// One order triggers an OCO pair.
+        Otoco = 3,
+    }
+}
+
+wire_enum! {
+    /// Margin mode of a position.
+    pub enum MarginMode {
+        Cross = 0,
+        Isolated = 1,
+    }
+}
+
+wire_enum! {
+    /// Direction of an isolated margin transfer.
+    pub enum MarginDirection {
+        RemoveFromIsolatedMargin = 0,
+        AddToIsolatedMargin = 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_wire_format() {
+        assert_eq!(json!(OrderType::TakeProfitLimit), json!(5));
+        assert_eq!(serde_json::from_value::<TimeInForce>(json!(2)).unwrap(), TimeInForce::PostOnly);
+        assert!(serde_json::from_value::<MarginMode>(json!(7)).is_err());
+
+        for value in 0..=6u8 {
+            assert_eq!(u8::from(OrderType::try_from(value).unwrap()), value);
+        }
+        assert!(matches!(GroupingType::try_from(0), Err(ApiError::InvalidOrder(_))));
+    }
+}
@@ -0,0 +1,242 @@
+//! Integer prices, sizes and USDC amounts of transactions.
+//!
+//! Orders carry prices and sizes as integers in the market's units (`price_decimals`,
+//! `size_decimals`). `Price` and `BaseAmount` keep the two apart in request structs, and
+//! serialize as plain JSON numbers, so `tx_info` is unchanged.
+//!
+//! Transfers, withdraws and margin updates carry USDC in units of 1e-6 as `UsdcAmount`,
+//! which converts from and to `Decimal` exactly and is hashed as its low and high 32 bits
+//! (`to_limbs`), the one encoding every USDC field of a transaction uses.
+//!
+//! The exchange signs prices as 32-bit fields. `Price` holds a `u64` so a computed price
+//! that does not fit is caught rather than wrapped: converting it for a transaction
+//! (`to_tx_field`, or the hashing in `sign_tx_info`) fails with `ApiError::InvalidOrder`
+//! or `ApiError::InvalidTxField` instead of signing a truncated price.
+
+use crate::decimal::Decimal;
+use crate::tx_fields::TxField;
+use crate::{ApiError, Result};
+use poseidon_hash::Goldilocks;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+
+/// Price in the market's price units; zero means no price (e.g. no trigger).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Price(pub u64);
+
+impl Price {
+    pub const ZERO: Price = Price(0);
+    /// Largest price a transaction can carry.
+    pub const MAX: Price = Price(u32::MAX as u64);
+
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    /// The price as the transactions' 32-bit field, or `ApiError::InvalidOrder` if it
+    /// does not fit.
+    pub fn to_tx_field(self) -> Result<u32> {
+        u32::try_from(self.0)
+            .map_err(|_| ApiError::InvalidOrder(format!("Price {} exceeds the largest price {}", self.0, Self::MAX)))
+    }
+}
+
+impl From<u32> for Price {
+    fn from(units: u32) -> Self {
+        Price(u64::from(units))
+    }
+}
+
+/// Fails on a negative price, as from a signed computation that went wrong.
+impl TryFrom<i64> for Price {
+    type Error = ApiError;
+
+    fn try_from(units: i64) -> Result<Self> {
+        u64::try_from(units)
+            .map(Price)
+            .map_err(|_| ApiError::InvalidOrder(format!("Price must not be negative, got {}", units)))
+    }
+}
+
+impl fmt::Display for Price {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Order size in the market's size units.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct BaseAmount(pub i64);
+
+impl BaseAmount {
+    pub const ZERO: BaseAmount = BaseAmount(0);
+
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl From<i64> for BaseAmount {
+    fn from(units: i64) -> Self {
+        BaseAmount(units)
+    }
+}
+
+impl fmt::Display for BaseAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// USDC amount in units of 1e-6 USDC.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct UsdcAmount(pub u64);
+
+impl UsdcAmount {
+    pub const ZERO: UsdcAmount = UsdcAmount(0);
+    /// Decimals of USDC in transactions.
+    pub const DECIMALS: u32 = 6;
+
+    /// The amount `usdc` is, e.g. `25.5` for 25 500 000 units. Fails with
+    /// `ApiError::InvalidAmount` if it is negative, finer than 1e-6 or does not fit.
+    pub fn from_decimal(usdc: Decimal) -> Result<Self> {
+        if usdc.is_sign_negative() && !usdc.is_zero() {
+            return Err(ApiError::InvalidAmount(format!("USDC amount must not be negative, got {}", usdc)));
+        }
+        if usdc.normalize().scale() > Self::DECIMALS {
+            return Err(ApiError::InvalidAmount(format!(
+                "USDC amount {} has more than {} decimals",
+                usdc,
+                Self::DECIMALS
+            )));
+        }
+        let mut units = usdc;
+        units.rescale(Self::DECIMALS);
+        u64::try_from(units.mantissa())
+            .map(UsdcAmount)
+            .map_err(|_| ApiError::InvalidAmount(format!("USDC amount {} is out of range", usdc)))
+    }
+
+    /// The amount in USDC, with 6 decimals.
+    pub fn to_decimal(self) -> Decimal {
+        Decimal::from_i128_with_scale(i128::from(self.0), Self::DECIMALS)
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    /// `None` on overflow.
+    pub fn checked_add(self, other: UsdcAmount) -> Option<UsdcAmount> {
+        self.0.checked_add(other.0).map(UsdcAmount)
+    }
+
+    /// `None` if `other` is larger.
+    pub fn checked_sub(self, other: UsdcAmount) -> Option<UsdcAmount> {
+        self.0.checked_sub(other.0).map(UsdcAmount)
+    }
+
+    /// `None` on overflow.
+    pub fn checked_mul(self, factor: u64) -> Option<UsdcAmount> {
+        self.0.checked_mul(factor).map(UsdcAmount)
+    }
+
+    /// The amount as hashed in transactions: its low 32 bits, then its high 32 bits.
+    pub fn to_limbs(self) -> [Goldilocks; 2] {
+        [
+            Goldilocks::from_canonical_u64(self.0 & 0xFFFF_FFFF),
+            Goldilocks::from_canonical_u64(self.0 >> 32),
+        ]
+    }
+}
+
+impl From<u64> for UsdcAmount {
+    fn from(units: u64) -> Self {
+        UsdcAmount(units)
+    }
+}
+
+/// Formatted in USDC, e.g. `25.500000`.
+impl fmt::Display for UsdcAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.to_decimal().fmt(f)
+    }
+}
+
+/// Read as a 32-bit field: a larger value is an error, never truncated.
+impl TxField for Price {
+    const KIND: &'static str = "price (u32)";
+
+    fn from_json(value: &Value) -> Option<Self> {
+        value.as_u64().filter(|units| *units <= Price::MAX.0).map(Price)
+    }
+
+    fn to_goldilocks(self) -> Goldilocks {
+        Goldilocks::from_canonical_u64(self.0)
+    }
+}
+
+/// Encoded modulo p like any signed field.
+impl TxField for BaseAmount {
+    const KIND: &'static str = "base amount (i64)";
+
+    fn from_json(value: &Value) -> Option<Self> {
+        value.as_i64().map(BaseAmount)
+    }
+
+    fn to_goldilocks(self) -> Goldilocks {
+        Goldilocks::from_i64(self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tx_fields::{element, usdc_limbs};
+    use serde_json::json;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_price_range() {
+        assert_eq!(Price::from(305_000u32).to_tx_field().unwrap(), 305_000);
+        assert!(Price(u64::from(u32::MAX) + 1).to_tx_field().is_err());
+        assert!(Price::try_from(-1i64).is_err());
+        assert_eq!(Price::try_from(42i64).unwrap(), Price(42));
+
+        // Serialized as plain numbers
+        assert_eq!(json!({"Price": Price(7), "BaseAmount": BaseAmount(-5)}), json!({"Price": 7, "BaseAmount": -5}));
+
+        // A price past 32 bits is rejected when hashing, not wrapped to a small one
+        let tx = json!({"Price": 4_294_967_296u64 + 305_000, "BaseAmount": -5});
+        assert!(matches!(element::<Price>(&tx, "Price"), Err(ApiError::InvalidTxField(_))));
+        assert_eq!(element::<BaseAmount>(&tx, "BaseAmount").unwrap(), Goldilocks::from_i64(-5));
+    }
+
+    #[test]
+    fn test_usdc_amount() {
+        let amount = UsdcAmount::from_decimal(Decimal::from_str("25.5").unwrap()).unwrap();
+        assert_eq!(amount, UsdcAmount(25_500_000));
+        assert_eq!(amount.to_decimal(), Decimal::from_str("25.500000").unwrap());
+        assert_eq!(amount.to_string(), "25.500000");
+        assert_eq!(UsdcAmount::from_decimal(Decimal::from_str("1.2500000").unwrap()).unwrap(), UsdcAmount(1_250_000));
+        assert!(matches!(UsdcAmount::from_decimal(Decimal::from_str("-1").unwrap()), Err(ApiError::InvalidAmount(_))));
+        assert!(matches!(UsdcAmount::from_decimal(Decimal::from_str("0.0000001").unwrap()), Err(ApiError::InvalidAmount(_))));
+        assert!(UsdcAmount::from_decimal(Decimal::MAX).is_err());
+
+        assert_eq!(amount.checked_sub(UsdcAmount(500_000)), Some(UsdcAmount(25_000_000)));
+        assert_eq!(UsdcAmount(1).checked_sub(UsdcAmount(2)), None);
+        assert_eq!(UsdcAmount(u64::MAX).checked_add(UsdcAmount(1)), None);
+        assert_eq!(UsdcAmount(u64::MAX / 2 + 1).checked_mul(2), None);
+
+        // Hashed as low then high 32 bits, read from plain JSON numbers
+        let large = UsdcAmount((7 << 32) + 25_500_000);
+        assert_eq!(large.to_limbs(), [Goldilocks::from_canonical_u64(25_500_000), Goldilocks::from_canonical_u64(7)]);
+        let tx = json!({"USDCAmount": large, "Fee": -1});
+        assert_eq!(usdc_limbs(&tx, "USDCAmount").unwrap(), large.to_limbs());
+        assert!(matches!(usdc_limbs(&tx, "Fee"), Err(ApiError::InvalidTxField(_))));
+    }
+}
@@ -0,0 +1,279 @@
+//! Client-side order checks.
+//!
+//! `OrderValidator` rejects orders the exchange would refuse (or that the caller almost
+//! certainly did not intend) before they are signed, so they cost neither a round trip nor
+//! a nonce. Limits come from `MarketRules`, usually loaded from `orderBookDetails`.
+
+use crate::positions::decimal_to_units;
+use crate::{ApiError, BaseAmount, CreateOrderRequest, LighterClient, OrderType, Price, Result};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Trading limits of a market, in integer order units.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarketRules {
+    pub price_decimals: u32,
+    pub size_decimals: u32,
+    /// Prices must be a multiple of this many price units.
+    pub price_tick: i64,
+    pub min_base_amount: i64,
+    pub max_base_amount: Option<i64>,
+    /// Minimum order value in quote currency.
+    pub min_notional: f64,
+    pub max_notional: Option<f64>,
+}
+
+impl MarketRules {
+    /// Rules with no size or notional limits and a tick of one price unit.
+    pub fn new(price_decimals: u32, size_decimals: u32) -> Self {
+        Self {
+            price_decimals,
+            size_decimals,
+            price_tick: 1,
+            min_base_amount: 1,
+            max_base_amount: None,
+            min_notional: 0.0,
+            max_notional: None,
+        }
+    }
+
+    /// Reads decimals, `min_base_amount` and `min_quote_amount` from an
+    /// `orderBookDetails` entry.
+    pub fn from_order_book_details(details: &Value) -> Result<Self> {
+        let decimals = |field: &str| {
+            details[field]
+                .as_u64()
+                .map(|d| d as u32)
+                .ok_or_else(|| ApiError::Api(format!("Market details without {}: {}", field, details)))
+        };
+        let mut rules = Self::new(decimals("price_decimals")?, decimals("size_decimals")?);
+
+        if let Some(min_base) = details["min_base_amount"]
+            .as_str()
+            .and_then(|s| decimal_to_units(s, rules.size_decimals))
+        {
+            rules.min_base_amount = min_base.max(1);
+        }
+        if let Some(min_quote) = details["min_quote_amount"].as_str().and_then(|s| s.parse::<f64>().ok()) {
+            rules.min_notional = min_quote;
+        }
+        Ok(rules)
+    }
+
+    /// Order value in quote currency.
+    pub fn notional(&self, base_amount: BaseAmount, price: Price) -> f64 {
+        (base_amount.0 as f64 / 10f64.powi(self.size_decimals as i32)) * (price.0 as f64 / 10f64.powi(self.price_decimals as i32))
+    }
+}
+
+/// A resting order of this account, used for self-trade checks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RestingOrder {
+    pub is_ask: bool,
+    pub price: Price,
+}
+
+/// Checks orders against per-market rules and, optionally, the account's own resting orders.
+#[derive(Debug, Clone, Default)]
+pub struct OrderValidator {
+    markets: HashMap<u8, MarketRules>,
+    prevent_self_trade: bool,
+}
+
+impl OrderValidator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use `rules` for orders on `market_index`.
+    pub fn with_market(mut self, market_index: u8, rules: MarketRules) -> Self {
+        self.markets.insert(market_index, rules);
+        self
+    }
+
+    /// Reject orders that would trade against the account's own resting orders.
+    pub fn prevent_self_trade(mut self, enabled: bool) -> Self {
+        self.prevent_self_trade = enabled;
+        self
+    }
+
+    pub fn rules(&self, market_index: u8) -> Option<&MarketRules> {
+        self.markets.get(&market_index)
+    }
+
+    pub fn prevents_self_trade(&self) -> bool {
+        self.prevent_self_trade
+    }
+
+    /// Check `order`; `own_orders` are the account's resting orders on the same market and
+    /// are only consulted when self-trade prevention is enabled.
+    ///
+    /// Returns `ApiError::InvalidOrder` naming the violated limit. Markets without rules
+    /// only get the self-trade check.
+    pub fn validate(&self, order: &CreateOrderRequest, own_orders: &[RestingOrder]) -> Result<()> {
+        let is_market = order.order_type == OrderType::Market;
+        if order.base_amount.0 <= 0 {
+            return Err(ApiError::InvalidOrder(format!("Base amount must be positive, got {}", order.base_amount)));
+        }
+        if order.price.is_zero() && !is_market {
+            return Err(ApiError::InvalidOrder(format!("Price must be positive, got {}", order.price)));
+        }
+
+        if let Some(rules) = self.markets.get(&order.order_book_index) {
+            if !is_market && rules.price_tick > 1 && !order.price.0.is_multiple_of(rules.price_tick.unsigned_abs()) {
+                return Err(ApiError::InvalidOrder(format!(
+                    "Price {} is not a multiple of the tick {}",
+                    order.price, rules.price_tick
+                )));
+            }
+            if order.base_amount.0 < rules.min_base_amount {
+                return Err(ApiError::InvalidOrder(format!(
+                    "Base amount {} is below the market minimum {}",
+                    order.base_amount, rules.min_base_amount
+                )));
+            }
+            if let Some(max) = rules.max_base_amount.filter(|max| order.base_amount.0 > *max) {
+                return Err(ApiError::InvalidOrder(format!(
+                    "Base amount {} is above the limit {}",
+                    order.base_amount, max
+                )));
+            }
+            // Reduce-only orders may close positions smaller than the minimum notional
+            if !order.price.is_zero() && !order.reduce_only {
+                let notional = rules.notional(order.base_amount, order.price);
+                if notional < rules.min_notional {
+                    return Err(ApiError::InvalidOrder(format!(
+                        "Order value {} is below the market minimum {}",
+                        notional, rules.min_notional
+                    )));
+                }
+                if let Some(max) = rules.max_notional.filter(|max| notional > *max) {
+                    return Err(ApiError::InvalidOrder(format!("Order value {} is above the limit {}", notional, max)));
+                }
+            }
+        }
+
+        if self.prevent_self_trade {
+            let crossed = own_orders.iter().find(|resting| {
+                resting.is_ask != order.is_ask
+                    && (is_market
+                        || if order.is_ask {
+                            resting.price >= order.price
+                        } else {
+                            resting.price <= order.price
+                        })
+            });
+            if let Some(resting) = crossed {
+                return Err(ApiError::InvalidOrder(format!(
+                    "Order at {} would trade against own {} at {}",
+                    order.price,
+                    if resting.is_ask { "ask" } else { "bid" },
+                    resting.price
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads the resting orders of an `accountActiveOrders` response.
+pub(crate) fn resting_orders(active_orders: &Value, price_decimals: u32) -> Vec<RestingOrder> {
+    active_orders["orders"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|o| {
+            Some(RestingOrder {
+                is_ask: o["is_ask"].as_bool()?,
+                price: Price::try_from(decimal_to_units(o["price"].as_str()?, price_decimals)?).ok()?,
+            })
+        })
+        .collect()
+}
+
+impl LighterClient {
+    /// Load the trading rules of a market from `orderBookDetails`
+    pub async fn get_market_rules(&self, market_index: u8) -> Result<MarketRules> {
+        let details = self.order_book_details(market_index).await?;
+        MarketRules::from_order_book_details(&details)
+    }
+
+    /// Validate `order` with `validator`, fetching this account's resting orders when
+    /// self-trade prevention is enabled
+    pub async fn validate_order(&self, validator: &OrderValidator, order: &CreateOrderRequest) -> Result<()> {
+        let own_orders = if validator.prevents_self_trade() {
+            let price_decimals = match validator.rules(order.order_book_index) {
+                Some(rules) => rules.price_decimals,
+                None => self.get_market_rules(order.order_book_index).await?.price_decimals,
+            };
+            let active = self.get_active_orders(order.order_book_index).await?;
+            resting_orders(&active, price_decimals)
+        } else {
+            Vec::new()
+        };
+        validator.validate(order, &own_orders)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TimeInForce;
+    use serde_json::json;
+
+    fn limit(is_ask: bool, price: i64, base_amount: i64) -> CreateOrderRequest {
+        CreateOrderRequest {
+            account_index: 1,
+            order_book_index: 0,
+            client_order_index: 0,
+            base_amount: BaseAmount(base_amount),
+            price: Price::try_from(price).unwrap(),
+            is_ask,
+            order_type: OrderType::Limit,
+            time_in_force: TimeInForce::GoodTillTime,
+            reduce_only: false,
+            trigger_price: Price::ZERO,
+            price_protection: None,
+            order_expiry: None,
+        }
+    }
+
+    #[test]
+    fn test_market_rules_limits() {
+        let details = json!({
+            "market_id": 0,
+            "price_decimals": 2,
+            "size_decimals": 4,
+            "min_base_amount": "0.0050",
+            "min_quote_amount": "10.000000"
+        });
+        let mut rules = MarketRules::from_order_book_details(&details).unwrap();
+        assert_eq!(rules.min_base_amount, 50);
+        rules.price_tick = 10;
+        let validator = OrderValidator::new().with_market(0, rules);
+
+        // 0.01 ETH at 3000.00 = 30 USDC
+        assert!(validator.validate(&limit(false, 300_000, 100), &[]).is_ok());
+        let off_tick = validator.validate(&limit(false, 300_005, 100), &[]);
+        assert!(matches!(off_tick, Err(ApiError::InvalidOrder(m)) if m.contains("tick")));
+        let too_small = validator.validate(&limit(false, 300_000, 40), &[]);
+        assert!(matches!(too_small, Err(ApiError::InvalidOrder(m)) if m.contains("minimum 50")));
+        // 0.005 ETH at 1000.00 = 5 USDC
+        let low_value = validator.validate(&limit(false, 100_000, 50), &[]);
+        assert!(matches!(low_value, Err(ApiError::InvalidOrder(m)) if m.contains("value")));
+    }
+
+    #[test]
+    fn test_self_trade_prevention() {
+        let own = resting_orders(&json!({"orders": [{"is_ask": true, "price": "3001.50"}]}), 2);
+        assert_eq!(own, vec![RestingOrder { is_ask: true, price: Price(300_150) }]);
+
+        let validator = OrderValidator::new().prevent_self_trade(true);
+        assert!(validator.validate(&limit(false, 300_100, 100), &own).is_ok());
+        assert!(validator.validate(&limit(false, 300_150, 100), &own).is_err());
+        // Same side never crosses
+        assert!(validator.validate(&limit(true, 290_000, 100), &own).is_ok());
+        assert!(OrderValidator::new().validate(&limit(false, 300_150, 100), &own).is_ok());
+    }
+}
@@ -0,0 +1,46 @@
+//! Transaction hash regression vectors (see `test-vectors/README.md`).
+
+use crate::LighterClient;
+use serde_json::Value;
+
+const TX_HASH_VECTORS: &str = include_str!("../../test-vectors/tx_hashes.json");
+
+#[test]
+fn test_tx_hash_vectors() {
+    let file: Value = serde_json::from_str(TX_HASH_VECTORS).unwrap();
+    let cases = file["cases"].as_array().unwrap();
+
+    let mut tx_types: Vec<u64> = cases.iter().map(|c| c["tx_type"].as_u64().unwrap()).collect();
+    tx_types.sort_unstable();
+    tx_types.dedup();
+    assert_eq!(tx_types.len(), 15, "every supported tx type has a vector");
+
+    for case in cases {
+        let tx_type = case["tx_type"].as_u64().unwrap() as u32;
+        let chain_id = case["chain_id"].as_u64().unwrap() as u32;
+        let tx_json = case["tx_info"].to_string();
+
+        let hash = LighterClient::transaction_hash(&tx_json, tx_type, chain_id).unwrap();
+        assert_eq!(hex::encode(hash), case["hash"].as_str().unwrap(), "case {}", case);
+    }
+}
+
+#[tokio::test]
+async fn test_signed_tx_carries_hash() {
+    let file: Value = serde_json::from_str(TX_HASH_VECTORS).unwrap();
+    let case = file["cases"].as_array().unwrap().iter().find(|c| c["chain_id"] == 304).unwrap();
+    let tx_type = case["tx_type"].as_u64().unwrap() as u32;
+
+    let private_key = "bda332f3aaa2d9cfdd8920830ea37efce9636c671a426bd4cb9815007e2a2917604ab47857cbb200";
+    let client = LighterClient::new("https://mainnet.zklighter.elliot.ai".to_string(), private_key, 1, 0).unwrap();
+    assert_eq!(client.lighter_chain_id(), 304);
+    assert_eq!(
+        hex::encode(client.compute_tx_hash(&case["tx_info"].to_string(), tx_type).unwrap()),
+        case["hash"].as_str().unwrap()
+    );
+
+    let signed = client.sign_tx_info(case["tx_info"].clone(), tx_type).await.unwrap();
+    assert_eq!(signed.tx_hash_hex(), case["hash"].as_str().unwrap());
+    let public_key = client.signer().public_key().to_bytes();
+    assert!(goldilocks_crypto::verify_signature(signed.signature.as_bytes(), &signed.tx_hash, &public_key).unwrap());
+}
@@ -0,0 +1,174 @@
+//! Identity of the client's account and key.
+//!
+//! `whoami` answers the questions an onboarding or deployment script asks before trading:
+//! which account and API key slot the client signs for, whether the exchange holds the
+//! client's public key in that slot, and which tier (and so which fees) the account is on.
+//! It combines the `account` and `apiKey` endpoints into one `WhoAmI`.
+//!
+//! Lighter has two account tiers. Standard accounts trade without fees and with higher
+//! latency; premium accounts pay maker and taker fees. The tier is read from the account's
+//! `account_tier`, sent as `"standard"`/`"premium"` or as 0/1. The fees are the account's
+//! `maker_fee` and `taker_fee` (percent, like `orderBookDetails`) when the account reports
+//! them, and otherwise the tier's base rates.
+
+use crate::balance::decimal;
+use crate::positions::account_entry;
+use crate::{LighterClient, PublicKey, Result};
+use serde_json::Value;
+use std::fmt;
+
+/// Maker fee of premium accounts without a volume discount, as a fraction.
+pub const PREMIUM_MAKER_FEE: f64 = 0.00002;
+/// Taker fee of premium accounts without a volume discount, as a fraction.
+pub const PREMIUM_TAKER_FEE: f64 = 0.0002;
+
+/// Fee and latency tier of an account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountTier {
+    /// No trading fees.
+    Standard,
+    /// Lower latency, with maker and taker fees.
+    Premium,
+    /// A tier this client does not know about, or none reported.
+    Unknown,
+}
+
+impl AccountTier {
+    fn from_account(account: &Value) -> Self {
+        let tier = &account["account_tier"];
+        match (tier.as_str().map(str::to_ascii_lowercase).as_deref(), tier.as_i64()) {
+            (Some("standard"), _) | (_, Some(0)) => AccountTier::Standard,
+            (Some("premium"), _) | (_, Some(1)) => AccountTier::Premium,
+            _ => AccountTier::Unknown,
+        }
+    }
+}
+
+impl fmt::Display for AccountTier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            AccountTier::Standard => "standard",
+            AccountTier::Premium => "premium",
+            AccountTier::Unknown => "unknown",
+        })
+    }
+}
+
+/// Trading fees of an account, as fractions (0.0002 = 0.02%).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeeSchedule {
+    pub maker_fee: f64,
+    pub taker_fee: f64,
+}
+
+impl FeeSchedule {
+    /// The account's own fees if reported, else the base rates of `tier`; `None` if
+    /// neither is known.
+    fn from_account(account: &Value, tier: AccountTier) -> Option<Self> {
+        let percent = |field: &str| decimal(&account[field]).map(|percent| percent / 100.0);
+        if let (Some(maker_fee), Some(taker_fee)) = (percent("maker_fee"), percent("taker_fee")) {
+            return Some(Self { maker_fee, taker_fee });
+        }
+        match tier {
+            AccountTier::Standard => Some(Self { maker_fee: 0.0, taker_fee: 0.0 }),
+            AccountTier::Premium => Some(Self {
+                maker_fee: PREMIUM_MAKER_FEE,
+                taker_fee: PREMIUM_TAKER_FEE,
+            }),
+            AccountTier::Unknown => None,
+        }
+    }
+}
+
+/// Who the client signs as, from `whoami`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WhoAmI {
+    pub account_index: i64,
+    pub api_key_index: u8,
+    /// Ethereum address owning the account, if reported.
+    pub l1_address: Option<String>,
+    /// Key the exchange holds in the client's slot; `None` if the slot is empty or unknown.
+    pub registered_public_key: Option<PublicKey>,
+    /// Key the client signs with.
+    pub local_public_key: PublicKey,
+    pub tier: AccountTier,
+    /// `None` if the account reports neither fees nor a known tier.
+    pub fees: Option<FeeSchedule>,
+}
+
+impl WhoAmI {
+    /// Whether transactions signed by the client will verify, i.e. the slot holds its key.
+    pub fn key_registered(&self) -> bool {
+        self.registered_public_key == Some(self.local_public_key)
+    }
+
+    fn from_responses(
+        account_index: i64,
+        api_key_index: u8,
+        account_info: &Value,
+        registered_public_key: Option<PublicKey>,
+        local_public_key: PublicKey,
+    ) -> Self {
+        let account = account_entry(account_info);
+        let tier = AccountTier::from_account(account);
+        Self {
+            account_index,
+            api_key_index,
+            l1_address: account["l1_address"].as_str().filter(|a| !a.is_empty()).map(str::to_string),
+            registered_public_key,
+            local_public_key,
+            tier,
+            fees: FeeSchedule::from_account(account, tier),
+        }
+    }
+}
+
+impl LighterClient {
+    /// Account, API key slot, registered key and tier of this client
+    ///
+    /// Reads the `account` and `apiKey` endpoints. A key mismatch is not an error: check
+    /// `WhoAmI::key_registered`, or use `check_api_key` to fail on it.
+    pub async fn whoami(&self) -> Result<WhoAmI> {
+        let account_info = self.get_account().await?;
+        let registered_public_key = self.registered_public_key().await?;
+
+        Ok(WhoAmI::from_responses(
+            self.account_index,
+            self.api_key_index,
+            &account_info,
+            registered_public_key,
+            self.signer().public_key(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_whoami_from_responses() {
+        let key = signer::KeyManager::generate().public_key();
+        let account_info = json!({"code": 200, "total": 1, "accounts": [
+            {"index": 7, "l1_address": "0x1f9840a85d5af5bf1d1762f925bdaddc4201f984", "account_tier": "premium"}
+        ]});
+
+        let me = WhoAmI::from_responses(7, 3, &account_info, Some(key), key);
+        assert_eq!(me.l1_address.as_deref(), Some("0x1f9840a85d5af5bf1d1762f925bdaddc4201f984"));
+        assert!(me.key_registered());
+        assert_eq!(me.tier, AccountTier::Premium);
+        assert_eq!(me.fees, Some(FeeSchedule { maker_fee: PREMIUM_MAKER_FEE, taker_fee: PREMIUM_TAKER_FEE }));
+
+        // Reported fees win over the tier's base rates
+        let discounted = json!({"index": 7, "account_tier": 1, "maker_fee": "0.0000", "taker_fee": "0.0150"});
+        let me = WhoAmI::from_responses(7, 3, &discounted, None, key);
+        assert!(!me.key_registered());
+        assert_eq!(me.fees, Some(FeeSchedule { maker_fee: 0.0, taker_fee: 0.00015 }));
+
+        let me = WhoAmI::from_responses(7, 3, &json!({"index": 7, "account_tier": 0}), None, key);
+        assert_eq!((me.tier, me.fees), (AccountTier::Standard, Some(FeeSchedule { maker_fee: 0.0, taker_fee: 0.0 })));
+        let me = WhoAmI::from_responses(7, 3, &json!({"index": 7}), None, key);
+        assert_eq!((me.tier, me.fees, me.l1_address), (AccountTier::Unknown, None, None));
+    }
+}
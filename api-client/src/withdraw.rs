@@ -0,0 +1,286 @@
+//! Withdraw tracking.
+//!
+//! `LighterClient::withdraw` only submits the L2 transaction. The helpers here follow a
+//! withdraw through L2 confirmation to L1 claimability using the withdraw history endpoint.
+//!
+//! `get_withdraw_limits` reads the minimum withdraw amount and the current fee; `withdraw`
+//! checks requests against them before signing, so an amount the exchange would reject does
+//! not use up a nonce.
+
+use crate::decimal::{parse_decimal, string_or_number_decimal, Decimal};
+use crate::{parse_response, ApiError, LighterClient, Result, UsdcAmount, WithdrawRequest};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::{Duration, Instant};
+
+/// Lifecycle state of a withdraw as reported by the exchange.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WithdrawStatus {
+    /// Accepted on L2, waiting for the batch to be verified on L1.
+    Pending,
+    /// Verified on L1 and ready to be claimed from the bridge (secure withdraws).
+    Claimable,
+    /// Funds have arrived on L1.
+    Completed,
+    /// The withdraw was rejected.
+    Failed,
+    /// The withdraw was rejected and the amount returned to the L2 balance.
+    Refunded,
+    /// A status this client does not know about yet.
+    #[serde(other)]
+    Unknown,
+}
+
+impl WithdrawStatus {
+    /// Returns `true` once the withdraw will not change state without user action.
+    pub fn is_final(&self) -> bool {
+        !matches!(self, WithdrawStatus::Pending | WithdrawStatus::Unknown)
+    }
+}
+
+/// Bridge path used by a withdraw.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WithdrawType {
+    /// Proven on L1, then claimed by the user.
+    Secure,
+    /// Paid out on L1 by a liquidity provider.
+    Fast,
+    #[serde(other)]
+    Unknown,
+}
+
+/// A single entry of the withdraw history.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict", serde(deny_unknown_fields))]
+pub struct WithdrawRecord {
+    pub id: String,
+    /// USDC amount, sent as a decimal string, e.g. `"25.000000"`.
+    #[serde(with = "string_or_number_decimal")]
+    pub amount: Decimal,
+    pub timestamp: i64,
+    pub status: WithdrawStatus,
+    #[serde(rename = "type")]
+    pub withdraw_type: WithdrawType,
+    #[serde(default)]
+    pub l1_tx_hash: Option<String>,
+}
+
+/// One page of withdraw history.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WithdrawHistory {
+    #[serde(default)]
+    pub withdraws: Vec<WithdrawRecord>,
+    /// Cursor for the next page, if any.
+    #[serde(default)]
+    pub cursor: Option<String>,
+}
+
+/// Minimum amount and fee of withdraws.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WithdrawLimits {
+    /// Smallest `usdc_amount` the exchange accepts.
+    pub min_usdc_amount: UsdcAmount,
+    /// Fee taken from each withdraw.
+    pub fee: UsdcAmount,
+}
+
+impl WithdrawLimits {
+    fn from_response(response: &Value) -> Result<Self> {
+        let usdc_units = |name: &str| {
+            let amount = parse_decimal(&response[name])
+                .ok_or_else(|| ApiError::Api(format!("Withdraw info without {}: {}", name, response)))?;
+            UsdcAmount::from_decimal(amount)
+                .map_err(|_| ApiError::Api(format!("Withdraw info with invalid {}: {}", name, response[name])))
+        };
+        Ok(Self {
+            min_usdc_amount: usdc_units("min_withdraw_amount")?,
+            fee: usdc_units("withdraw_fee")?,
+        })
+    }
+
+    /// Fails with `ApiError::InvalidWithdraw` if the exchange would reject `request`.
+    pub fn check(&self, request: &WithdrawRequest) -> Result<()> {
+        if request.usdc_amount < self.min_usdc_amount {
+            return Err(ApiError::InvalidWithdraw(format!(
+                "Amount {} is below the minimum withdraw of {} USDC",
+                request.usdc_amount, self.min_usdc_amount
+            )));
+        }
+        if request.usdc_amount <= self.fee {
+            return Err(ApiError::InvalidWithdraw(format!(
+                "Amount {} does not cover the withdraw fee of {} USDC",
+                request.usdc_amount, self.fee
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Which withdraws `get_withdraw_history` returns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WithdrawFilter {
+    #[default]
+    All,
+    Pending,
+    Claimable,
+}
+
+impl WithdrawFilter {
+    fn as_str(&self) -> &'static str {
+        match self {
+            WithdrawFilter::All => "all",
+            WithdrawFilter::Pending => "pending",
+            WithdrawFilter::Claimable => "claimable",
+        }
+    }
+}
+
+impl LighterClient {
+    /// Get the withdraw history of this account
+    ///
+    /// # Arguments
+    /// * `filter` - Restrict the page to pending or claimable withdraws
+    /// * `cursor` - Cursor returned by the previous page, or `None` for the first page
+    pub async fn get_withdraw_history(
+        &self,
+        filter: WithdrawFilter,
+        cursor: Option<&str>,
+    ) -> Result<WithdrawHistory> {
+        let account_index_str = self.account_index.to_string();
+
+        let mut query = vec![
+            ("account_index", account_index_str.as_str()),
+            ("filter", filter.as_str()),
+        ];
+        if let Some(cursor) = cursor {
+            query.push(("cursor", cursor));
+        }
+
+        let response = self
+            .authenticated_get("/api/v1/withdraw/history").await?
+            .query(&query)
+            .send()
+            .await?;
+
+        let response_json = parse_response(response).await?;
+        if let Some(code) = response_json["code"].as_i64() {
+            if code != 200 {
+                return Err(ApiError::Api(format!("Withdraw history request failed: {}", response_json)));
+            }
+        }
+        let history: WithdrawHistory = self.parse_typed("/api/v1/withdraw/history", response_json)?;
+
+        Ok(history)
+    }
+
+    /// Minimum withdraw amount and current withdraw fee
+    pub async fn get_withdraw_limits(&self) -> Result<WithdrawLimits> {
+        let response = self
+            .client
+            .get(format!("{}/api/v1/withdraw/info", self.base_url))
+            .send()
+            .await?;
+
+        let response_json = parse_response(response).await?;
+        if let Some(code) = response_json["code"].as_i64() {
+            if code != 200 {
+                return Err(ApiError::Api(format!("Withdraw info request failed: {}", response_json)));
+            }
+        }
+        WithdrawLimits::from_response(&response_json)
+    }
+
+    /// Poll the withdraw history until withdraw `id` reaches a final state
+    ///
+    /// Returns the record once it is claimable, completed, failed or refunded.
+    /// Fails with `ApiError::Api` if `timeout` elapses first.
+    pub async fn wait_for_withdraw(
+        &self,
+        id: &str,
+        poll_interval: Duration,
+        timeout: Duration,
+    ) -> Result<WithdrawRecord> {
+        let started = Instant::now();
+
+        loop {
+            if let Some(record) = self.find_withdraw(id).await? {
+                if record.status.is_final() {
+                    return Ok(record);
+                }
+            }
+
+            if started.elapsed() + poll_interval > timeout {
+                return Err(ApiError::Api(format!(
+                    "Timed out after {:?} waiting for withdraw {}",
+                    timeout, id
+                )));
+            }
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+
+    async fn find_withdraw(&self, id: &str) -> Result<Option<WithdrawRecord>> {
+        let mut cursor: Option<String> = None;
+
+        loop {
+            let page = self
+                .get_withdraw_history(WithdrawFilter::All, cursor.as_deref())
+                .await?;
+            if let Some(record) = page.withdraws.iter().find(|w| w.id == id) {
+                return Ok(Some(record.clone()));
+            }
+
+            match page.cursor {
+                Some(next) if !next.is_empty() && !page.withdraws.is_empty() => cursor = Some(next),
+                _ => return Ok(None),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_withdraw_history() {
+        let raw = r#"{
+            "code": 200,
+            "withdraws": [
+                {"id": "17", "amount": "25.000000", "timestamp": 1760000000, "status": "claimable", "type": "secure", "l1_tx_hash": "0xabc"},
+                {"id": "18", "amount": "5.000000", "timestamp": 1760000100, "status": "pending", "type": "fast"},
+                {"id": "19", "amount": "1.000000", "timestamp": 1760000200, "status": "queued", "type": "instant"}
+            ],
+            "cursor": "abc"
+        }"#;
+
+        let history: WithdrawHistory = serde_json::from_str(raw).unwrap();
+        assert_eq!(history.withdraws.len(), 3);
+        assert_eq!(history.withdraws[0].status, WithdrawStatus::Claimable);
+        assert!(history.withdraws[0].status.is_final());
+        assert_eq!(history.withdraws[1].withdraw_type, WithdrawType::Fast);
+        assert!(!history.withdraws[1].status.is_final());
+        assert_eq!(history.withdraws[1].l1_tx_hash, None);
+        assert_eq!(history.withdraws[2].status, WithdrawStatus::Unknown);
+        assert_eq!(history.cursor.as_deref(), Some("abc"));
+    }
+
+    #[test]
+    fn test_withdraw_limits() {
+        let response = serde_json::json!({"code": 200, "min_withdraw_amount": "5.000000", "withdraw_fee": 1.5});
+        let limits = WithdrawLimits::from_response(&response).unwrap();
+        assert_eq!(limits, WithdrawLimits { min_usdc_amount: UsdcAmount(5_000_000), fee: UsdcAmount(1_500_000) });
+
+        assert!(limits.check(&WithdrawRequest { usdc_amount: UsdcAmount(5_000_000) }).is_ok());
+        assert!(matches!(
+            limits.check(&WithdrawRequest { usdc_amount: UsdcAmount(4_999_999) }),
+            Err(ApiError::InvalidWithdraw(_))
+        ));
+        let no_minimum = WithdrawLimits { min_usdc_amount: UsdcAmount::ZERO, fee: UsdcAmount(1_500_000) };
+        assert!(no_minimum.check(&WithdrawRequest { usdc_amount: UsdcAmount(1_500_000) }).is_err());
+
+        assert!(WithdrawLimits::from_response(&serde_json::json!({"code": 200})).is_err());
+    }
+}
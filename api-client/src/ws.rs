@@ -0,0 +1,190 @@
+//! Transaction submission over websocket.
+//!
+//! The exchange accepts signed transactions on its `/stream` websocket (`jsonapi/sendtx`),
+//! which saves the per-request HTTP overhead on latency-sensitive paths such as quote
+//! updates. `send_tx_ws` keeps one socket open, connecting on first use and again after a
+//! failure, and posts one transaction at a time on it since responses carry no request id.
+//!
+//! If the socket cannot be opened or the message cannot be written, the transaction falls
+//! back to REST. Once the message is written the outcome belongs to the exchange: a lost
+//! response is an `ApiError::WebSocket` and the transaction is not posted again, as a
+//! REST retry rejected for its nonce would otherwise be re-signed and executed twice.
+
+use crate::{ApiError, LighterClient, Result, SignedTx};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+use tokio::time::timeout;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+
+/// Time allowed to open the socket before falling back to REST.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Time allowed for the response to a written transaction.
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(10);
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// How a transaction reaches the exchange.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Transport {
+    Http,
+    /// Websocket, or REST if the socket is unavailable.
+    WebSocket,
+}
+
+/// Why a websocket submission failed.
+pub(crate) enum WsFailure {
+    /// Nothing was written; the transaction can go over REST.
+    NotSent(String),
+    /// Written, but no response arrived; the outcome is unknown.
+    NoResponse(String),
+}
+
+/// Lazily connected socket shared by all submissions of a client.
+#[derive(Default)]
+pub(crate) struct TxSocket {
+    socket: Mutex<Option<Socket>>,
+}
+
+impl TxSocket {
+    /// Send one signed transaction and wait for its response
+    pub(crate) async fn send(&self, url: &str, tx_type: u32, tx_info: &Value) -> std::result::Result<Value, WsFailure> {
+        let mut guard = self.socket.lock().await;
+        if guard.is_none() {
+            let (socket, _) = timeout(CONNECT_TIMEOUT, connect_async(url))
+                .await
+                .map_err(|_| WsFailure::NotSent(format!("Connecting to {} timed out", url)))?
+                .map_err(|e| WsFailure::NotSent(e.to_string()))?;
+            *guard = Some(socket);
+        }
+        let Some(socket) = guard.as_mut() else {
+            unreachable!("socket connected above");
+        };
+
+        // Spliced in as text so the transaction keeps lighter-go's field order
+        let tx_info_json = crate::tx_json::tx_info_json(tx_type, tx_info).map_err(|e| WsFailure::NotSent(e.to_string()))?;
+        let message = format!(r#"{{"type":"jsonapi/sendtx","data":{{"tx_type":{},"tx_info":{}}}}}"#, tx_type, tx_info_json);
+        if let Err(e) = socket.send(Message::Text(message)).await {
+            *guard = None;
+            return Err(WsFailure::NotSent(e.to_string()));
+        }
+
+        match timeout(RESPONSE_TIMEOUT, read_response(socket)).await {
+            Ok(Ok(response)) => Ok(response),
+            Ok(Err(e)) => {
+                *guard = None;
+                Err(WsFailure::NoResponse(e))
+            }
+            Err(_) => {
+                // A late response would be read as the answer to the next transaction
+                *guard = None;
+                Err(WsFailure::NoResponse("No response within 10 s".to_string()))
+            }
+        }
+    }
+}
+
+/// Reads messages until the response to a sendtx, answering keep-alive pings on the way.
+async fn read_response(socket: &mut Socket) -> std::result::Result<Value, String> {
+    while let Some(message) = socket.next().await {
+        let text = match message.map_err(|e| e.to_string())? {
+            Message::Text(text) => text,
+            Message::Close(frame) => return Err(format!("Socket closed: {:?}", frame)),
+            // Protocol pings are answered by tungstenite
+            _ => continue,
+        };
+        let Ok(value) = serde_json::from_str::<Value>(&text) else {
+            continue;
+        };
+        if value["type"] == "ping" {
+            socket
+                .send(Message::Text(json!({"type": "pong"}).to_string()))
+                .await
+                .map_err(|e| e.to_string())?;
+            continue;
+        }
+        if let Some(response) = tx_response(&value) {
+            return Ok(response);
+        }
+    }
+    Err("Socket closed".to_string())
+}
+
+/// The sendTx-shaped response (`code`, `message`, `tx_hash`) carried by a socket message, if any.
+fn tx_response(message: &Value) -> Option<Value> {
+    if message["type"] == "error" || message.get("error").is_some() {
+        let error = message.get("error").unwrap_or(message);
+        return Some(json!({
+            "code": error["code"].as_i64().unwrap_or_default(),
+            "message": error["message"].as_str().unwrap_or_default(),
+        }));
+    }
+    [message.get("data"), Some(message)]
+        .into_iter()
+        .flatten()
+        .find(|candidate| candidate.get("code").is_some())
+        .cloned()
+}
+
+/// Websocket URL of the `/stream` endpoint of an API base URL.
+pub(crate) fn stream_url(base_url: &str) -> String {
+    let base = base_url.trim_end_matches('/');
+    let socket_base = if let Some(host) = base.strip_prefix("https://") {
+        format!("wss://{}", host)
+    } else if let Some(host) = base.strip_prefix("http://") {
+        format!("ws://{}", host)
+    } else {
+        base.to_string()
+    };
+    format!("{}/stream", socket_base)
+}
+
+impl LighterClient {
+    /// Send a signed transaction over the websocket, falling back to REST
+    ///
+    /// Journaled and resynced on nonce errors like the REST path. Falls back to sendTx if
+    /// the socket cannot be opened or written to; once the transaction is written, a lost
+    /// response fails with `ApiError::WebSocket` instead (see the module docs). Transactions
+    /// with price protection disabled always go over REST, as the socket message has no
+    /// such flag.
+    pub async fn send_tx_ws(&self, signed: &SignedTx) -> Result<Value> {
+        let price_protection = self.price_protection_param(None);
+        self.send_tx_via(Transport::WebSocket, signed.tx_type, &signed.tx_info, price_protection)
+            .await
+    }
+
+    /// Submits over the socket, or `None` if the transaction should go over REST.
+    pub(crate) async fn post_tx_ws(&self, tx_type: u32, tx_info: &Value, price_protection: &str) -> Option<Result<Value>> {
+        if price_protection != "true" {
+            return None;
+        }
+        match self.tx_socket.send(&stream_url(&self.base_url), tx_type, tx_info).await {
+            Ok(response) => Some(Ok(response)),
+            Err(WsFailure::NotSent(reason)) => {
+                tracing::info!(target: "api_client::ws", %reason, "websocket unavailable, sending over REST");
+                None
+            }
+            Err(WsFailure::NoResponse(reason)) => Some(Err(ApiError::WebSocket(reason))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_url_and_responses() {
+        assert_eq!(stream_url("https://mainnet.zklighter.elliot.ai/"), "wss://mainnet.zklighter.elliot.ai/stream");
+        assert_eq!(stream_url("http://127.0.0.1:8080"), "ws://127.0.0.1:8080/stream");
+
+        let accepted = json!({"type": "jsonapi/sendtx", "data": {"code": 200, "tx_hash": "ab"}});
+        assert_eq!(tx_response(&accepted), Some(json!({"code": 200, "tx_hash": "ab"})));
+        let rejected = json!({"error": {"code": 21104, "message": "invalid nonce"}});
+        assert_eq!(tx_response(&rejected), Some(json!({"code": 21104, "message": "invalid nonce"})));
+        assert_eq!(tx_response(&json!({"type": "connected", "session_id": "x"})), None);
+    }
+}
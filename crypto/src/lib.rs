@@ -21,6 +21,8 @@
 //! - **Schnorr Signatures**: Signature generation and verification using Poseidon2 hashing
 //! - **Scalar Field**: Efficient scalar operations for private keys and nonces
 //! - **Point Arithmetic**: Addition, multiplication, encoding, and decoding
+//! - **MuSig**: Two-round multi-party signing under one aggregate key (see [`musig`])
+//! - **VRF**: Verifiable pseudorandom outputs with proofs, over the same keys (see [`vrf`])
 //!
 //! ## Dependencies
 //!
@@ -55,10 +57,16 @@
 //!
 //! [`poseidon-hash`]: https://crates.io/crates/poseidon-hash
 
+
+pub mod musig;
 pub mod schnorr;
 pub mod scalar_field;
+pub mod signature;
+pub mod vrf;
 
 pub use scalar_field::ScalarField;
+pub use signature::Signature;
+pub use vrf::{hash_to_curve, vrf_prove, vrf_verify, VrfOutput, VrfProof};
 
 pub use poseidon_hash::{Goldilocks, Fp5Element};
 
@@ -73,15 +81,39 @@ pub enum CryptoError {
     /// The private key has an invalid length.
     #[error("Invalid private key length: expected 40 bytes, got {0}")]
     InvalidPrivateKeyLength(usize),
+    /// The private key is zero or not below the group order.
+    #[error("Invalid private key: must be nonzero and below the group order")]
+    InvalidPrivateKey,
+    /// A scalar is not reduced modulo the group order.
+    #[error("Invalid scalar: not below the group order")]
+    NonCanonicalScalar,
     /// The signature format is invalid.
     #[error("Invalid signature format")]
     InvalidSignature,
+    /// A signature component is not reduced modulo the group order.
+    #[error("Invalid signature: s or e is not a canonical scalar")]
+    NonCanonicalSignature,
     /// The signature has an invalid length.
     #[error("Invalid signature length: expected 80 bytes, got {0}")]
     InvalidSignatureLength(usize),
     /// The message has an invalid length.
     #[error("Invalid message length: expected 40 bytes, got {0}")]
     InvalidMessageLength(usize),
+    /// A VRF proof has the wrong length or its point does not decode.
+    #[error("Invalid VRF proof encoding")]
+    InvalidVrfProof,
+    /// MuSig key aggregation needs at least two distinct keys.
+    #[error("Invalid key set: need at least two distinct public keys")]
+    InvalidKeySet,
+    /// The signer's public key is not part of the aggregate key.
+    #[error("Public key is not part of the aggregate key")]
+    UnknownSigner,
+    /// A MuSig public nonce has the wrong length or does not decode.
+    #[error("Invalid MuSig nonce")]
+    InvalidNonce,
+    /// A MuSig partial signature is malformed, or one is missing.
+    #[error("Invalid MuSig partial signature")]
+    InvalidPartialSignature,
     /// The public key is invalid or cannot be decoded.
     #[error("Invalid public key: cannot decode as encoded point")]
     InvalidPublicKey,
@@ -93,3 +125,9 @@ pub enum CryptoError {
 /// Result type for cryptographic operations.
 pub type Result<T> = std::result::Result<T, CryptoError>;
 
+
+#[cfg(test)]
+mod vector_tests;
+
+#[cfg(test)]
+mod property_tests;
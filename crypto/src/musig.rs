@@ -0,0 +1,336 @@
+//! Two-round multi-party Schnorr signing (MuSig2) over ECgFp5.
+//!
+//! Several holders of separate keys agree on one aggregate public key and jointly produce
+//! ordinary Schnorr signatures under it: the result verifies with `verify_signature`, so the
+//! exchange cannot tell it from a single-key signature. No party ever holds the aggregate
+//! private key. An account's API key can so be split between, say, a trading server and an
+//! approval service, and nothing is signed unless both take part.
+//!
+//! Signing is n-of-n: every key in the `KeyAggContext` must sign. A 2-of-n policy is a set of
+//! aggregate keys, one per pair.
+//!
+//! 1. Key aggregation: `KeyAggContext::new` sorts the public keys and computes
+//!    `X = Σ aᵢ·Pᵢ` with `aᵢ = H(L, Pᵢ)`, `L` the list of all keys. The coefficients stop a
+//!    party from choosing its key to cancel the others.
+//! 2. Round 1: each party draws a `SecretNonce` for one message and sends its `PublicNonce`
+//!    (`R₁ᵢ ‖ R₂ᵢ`) to the others. `AggregateNonce::sum` adds them up.
+//! 3. Round 2: with the aggregate nonce and the message, each party builds the same
+//!    `SigningSession` and calls `partial_sign`, consuming its secret nonce. The session
+//!    computes `b = H(X, R₁, R₂, m)`, `R = R₁ + b·R₂` and the exchange's challenge
+//!    `e = H(R ‖ m)`, and each partial signature is `sᵢ = r₁ᵢ + b·r₂ᵢ - e·aᵢ·xᵢ`.
+//! 4. `SigningSession::aggregate` adds the partial signatures into the `s ‖ e` signature.
+//!    `verify_partial` tells which party misbehaved if the result does not verify.
+//!
+//! A secret nonce must be used for exactly one signature: `partial_sign` takes it by value
+//! and it cannot be cloned or serialized. Reusing one reveals the signer's key share.
+
+use crate::schnorr::{challenge, message_to_fp5};
+use crate::{CryptoError, Fp5Element, Point, Result, ScalarField, Signature};
+use poseidon_hash::{array_from_le_bytes, hash_to_quintic_extension};
+use rand::{CryptoRng, RngCore};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+/// Domain separation tags of the key aggregation coefficients and of the nonce coefficient.
+const KEY_AGG_TAG: &[u8] = b"lighter-musig-keyagg-v1";
+const NONCE_TAG: &[u8] = b"lighter-musig-noncecoef-v1";
+
+/// The signers' public keys and the aggregate key they sign for.
+#[derive(Clone, Debug)]
+pub struct KeyAggContext {
+    /// Encoded public keys, sorted by their bytes.
+    public_keys: Vec<[u8; 40]>,
+    coefficients: Vec<ScalarField>,
+    aggregate: Point,
+}
+
+impl KeyAggContext {
+    /// Aggregates the signers' 40-byte public keys.
+    ///
+    /// The keys are sorted first, so every party gets the same aggregate key whatever order
+    /// it lists them in. Fails on fewer than two keys, a repeated key or a key that does not
+    /// decode.
+    pub fn new(public_keys: &[[u8; 40]]) -> Result<Self> {
+        let mut points = Vec::with_capacity(public_keys.len());
+        for key in public_keys {
+            let encoded = Fp5Element::from_bytes_le(key).map_err(|_| CryptoError::InvalidPublicKey)?;
+            let point = Point::decode(&encoded).filter(|point| !point.is_neutral()).ok_or(CryptoError::InvalidPublicKey)?;
+            points.push((point.encode().to_bytes_le(), point));
+        }
+        points.sort_by_key(|(key, _)| *key);
+        if points.len() < 2 || points.windows(2).any(|pair| pair[0].0 == pair[1].0) {
+            return Err(CryptoError::InvalidKeySet);
+        }
+
+        let mut list = array_from_le_bytes(KEY_AGG_TAG);
+        for (key, _) in &points {
+            list.extend(array_from_le_bytes(key));
+        }
+        let list_hash = hash_to_quintic_extension(&list);
+
+        let mut coefficients = Vec::with_capacity(points.len());
+        let mut aggregate = Point::neutral();
+        for (key, point) in &points {
+            let mut input = array_from_le_bytes(KEY_AGG_TAG);
+            input.extend_from_slice(&list_hash.0);
+            input.extend(array_from_le_bytes(key));
+            let coefficient = ScalarField::from_fp5_element(&hash_to_quintic_extension(&input));
+            aggregate = aggregate.add(&point.mul(&coefficient));
+            coefficients.push(coefficient);
+        }
+        if aggregate.is_neutral() {
+            return Err(CryptoError::InvalidKeySet);
+        }
+
+        Ok(Self {
+            public_keys: points.into_iter().map(|(key, _)| key).collect(),
+            coefficients,
+            aggregate,
+        })
+    }
+
+    /// The 40-byte aggregate public key, registered with the exchange like any API key.
+    pub fn aggregate_public_key(&self) -> [u8; 40] {
+        self.aggregate.encode().to_bytes_le()
+    }
+
+    /// The signers' public keys, sorted.
+    pub fn public_keys(&self) -> &[[u8; 40]] {
+        &self.public_keys
+    }
+
+    /// Aggregation coefficient of `public_key`, or `UnknownSigner` if it is not in the set.
+    fn coefficient(&self, public_key: &[u8; 40]) -> Result<ScalarField> {
+        let index = self
+            .public_keys
+            .binary_search(public_key)
+            .map_err(|_| CryptoError::UnknownSigner)?;
+        Ok(self.coefficients[index])
+    }
+}
+
+/// One party's nonce pair for one signature. Wiped on drop, never cloned.
+pub struct SecretNonce {
+    r1: ScalarField,
+    r2: ScalarField,
+    public: PublicNonce,
+}
+
+impl SecretNonce {
+    /// Draws a nonce pair from `rng`.
+    pub fn generate<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
+        let r1 = ScalarField::sample(rng);
+        let r2 = ScalarField::sample(rng);
+        let public = PublicNonce::from_points(&Point::mul_generator(&r1), &Point::mul_generator(&r2));
+        Self { r1, r2, public }
+    }
+
+    /// The commitment `R₁ ‖ R₂` to send to the other parties in round 1.
+    pub fn public_nonce(&self) -> PublicNonce {
+        self.public
+    }
+}
+
+impl Zeroize for SecretNonce {
+    fn zeroize(&mut self) {
+        self.r1.zeroize();
+        self.r2.zeroize();
+    }
+}
+
+impl Drop for SecretNonce {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for SecretNonce {}
+
+impl std::fmt::Debug for SecretNonce {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SecretNonce").field("public", &self.public).finish_non_exhaustive()
+    }
+}
+
+/// A nonce commitment: two encoded points `R₁ ‖ R₂` (80 bytes).
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct PublicNonce([u8; 80]);
+
+impl PublicNonce {
+    pub const LEN: usize = 80;
+
+    /// Parses `R₁ ‖ R₂`, rejecting points that do not decode or are not canonically encoded.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != Self::LEN {
+            return Err(CryptoError::InvalidNonce);
+        }
+        let mut out = [0u8; 80];
+        out.copy_from_slice(bytes);
+        let nonce = Self(out);
+        nonce.points()?;
+        Ok(nonce)
+    }
+
+    pub fn to_bytes(&self) -> [u8; 80] {
+        self.0
+    }
+
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.0)
+    }
+
+    /// Parses hex in either case, with or without a `0x` prefix.
+    pub fn from_hex(encoded: &str) -> Result<Self> {
+        let encoded = encoded.trim();
+        let encoded = encoded.strip_prefix("0x").unwrap_or(encoded);
+        Self::from_bytes(&hex::decode(encoded)?)
+    }
+
+    fn from_points(r1: &Point, r2: &Point) -> Self {
+        let mut bytes = [0u8; 80];
+        bytes[..40].copy_from_slice(&r1.encode().to_bytes_le());
+        bytes[40..].copy_from_slice(&r2.encode().to_bytes_le());
+        Self(bytes)
+    }
+
+    fn points(&self) -> Result<(Point, Point)> {
+        let decode = |bytes: &[u8]| {
+            let encoded = Fp5Element::from_bytes_le(bytes).map_err(|_| CryptoError::InvalidNonce)?;
+            Point::decode(&encoded)
+                .filter(|point| point.encode().to_bytes_le()[..] == *bytes)
+                .ok_or(CryptoError::InvalidNonce)
+        };
+        Ok((decode(&self.0[..40])?, decode(&self.0[40..])?))
+    }
+}
+
+/// Sum of every party's `PublicNonce`, the same for all parties.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct AggregateNonce(PublicNonce);
+
+impl AggregateNonce {
+    /// Adds up the public nonces of all signers.
+    pub fn sum(nonces: &[PublicNonce]) -> Result<Self> {
+        if nonces.is_empty() {
+            return Err(CryptoError::InvalidNonce);
+        }
+        let (mut r1, mut r2) = (Point::neutral(), Point::neutral());
+        for nonce in nonces {
+            let (a, b) = nonce.points()?;
+            r1 = r1.add(&a);
+            r2 = r2.add(&b);
+        }
+        Ok(Self(PublicNonce::from_points(&r1, &r2)))
+    }
+
+    pub fn to_bytes(&self) -> [u8; 80] {
+        self.0.to_bytes()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        PublicNonce::from_bytes(bytes).map(Self)
+    }
+}
+
+/// One party's share of the signature: a scalar `sᵢ` (40 bytes, canonical).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PartialSignature(ScalarField);
+
+impl PartialSignature {
+    pub fn to_bytes(&self) -> [u8; 40] {
+        self.0.to_bytes_le()
+    }
+
+    /// Parses `sᵢ`, rejecting values that are not below the group order.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != 40 {
+            return Err(CryptoError::InvalidPartialSignature);
+        }
+        let s = ScalarField::from_bytes_le(bytes).map_err(|_| CryptoError::InvalidPartialSignature)?;
+        if !s.is_canonical() {
+            return Err(CryptoError::NonCanonicalScalar);
+        }
+        Ok(Self(s))
+    }
+}
+
+/// Round 2 of signing one 40-byte message hash, identical at every party.
+#[derive(Clone, Debug)]
+pub struct SigningSession {
+    context: KeyAggContext,
+    nonce: AggregateNonce,
+    /// Nonce coefficient `b`.
+    b: ScalarField,
+    /// Challenge `e = H(R ‖ m)`.
+    e: ScalarField,
+}
+
+impl SigningSession {
+    /// Starts round 2 for `message` with the aggregate of all round-1 nonces.
+    pub fn new(context: &KeyAggContext, nonce: &AggregateNonce, message: &[u8]) -> Result<Self> {
+        let message_fp5 = message_to_fp5(message)?;
+        let (r1, r2) = nonce.0.points()?;
+
+        let mut input = array_from_le_bytes(NONCE_TAG);
+        input.extend_from_slice(&context.aggregate.encode().0);
+        input.extend_from_slice(&r1.encode().0);
+        input.extend_from_slice(&r2.encode().0);
+        input.extend_from_slice(&message_fp5.0);
+        let b = ScalarField::from_fp5_element(&hash_to_quintic_extension(&input));
+
+        let r = r1.add(&r2.mul_var_time(&b));
+        let e = challenge(&r.encode(), &message_fp5);
+
+        Ok(Self { context: context.clone(), nonce: *nonce, b, e })
+    }
+
+    /// Signs this session's message with one key share, consuming its nonce.
+    ///
+    /// `secret_nonce` must be the one whose public nonce went into the aggregate nonce.
+    /// Fails with `UnknownSigner` if the key is not part of the aggregate key.
+    pub fn partial_sign(&self, private_key: &[u8], secret_nonce: SecretNonce) -> Result<PartialSignature> {
+        if private_key.len() != 40 {
+            return Err(CryptoError::InvalidPrivateKeyLength(private_key.len()));
+        }
+        let x = Zeroizing::new(ScalarField::from_bytes_le(private_key)
+            .map_err(|_| CryptoError::InvalidPrivateKeyLength(private_key.len()))?);
+        if !x.is_valid() {
+            return Err(CryptoError::InvalidPrivateKey);
+        }
+        let public_key = Point::mul_generator(&x).encode().to_bytes_le();
+        let a = self.context.coefficient(&public_key)?;
+
+        // sᵢ = r₁ + b·r₂ - e·aᵢ·xᵢ
+        let nonce = Zeroizing::new(secret_nonce.r1.add(self.b.mul(&secret_nonce.r2)));
+        let e_times_key = Zeroizing::new(self.e.mul(&a).mul(&x));
+        Ok(PartialSignature(nonce.sub(*e_times_key)))
+    }
+
+    /// Checks one party's partial signature against its public key and public nonce.
+    pub fn verify_partial(&self, public_key: &[u8; 40], public_nonce: &PublicNonce, partial: &PartialSignature) -> Result<bool> {
+        let a = self.context.coefficient(public_key)?;
+        let encoded = Fp5Element::from_bytes_le(public_key).map_err(|_| CryptoError::InvalidPublicKey)?;
+        let point = Point::decode(&encoded).ok_or(CryptoError::InvalidPublicKey)?;
+        let (r1, r2) = public_nonce.points()?;
+
+        // sᵢ·G + e·aᵢ·Pᵢ == R₁ᵢ + b·R₂ᵢ
+        let lhs = Point::mul_add2(&Point::generator(), &point, &partial.0, &self.e.mul(&a));
+        let rhs = r1.add(&r2.mul_var_time(&self.b));
+        Ok(lhs.equals(&rhs))
+    }
+
+    /// Adds up the partial signatures of every signer into the `s ‖ e` signature.
+    pub fn aggregate(&self, partials: &[PartialSignature]) -> Result<Signature> {
+        if partials.len() != self.context.public_keys.len() {
+            return Err(CryptoError::InvalidPartialSignature);
+        }
+        let s = partials
+            .iter()
+            .fold(ScalarField::ZERO, |sum, partial| sum.add(partial.0));
+        Signature::from_parts(&s, &self.e)
+    }
+
+    /// The aggregate nonce this session was started with.
+    pub fn aggregate_nonce(&self) -> AggregateNonce {
+        self.nonce
+    }
+}
@@ -0,0 +1,301 @@
+use crate::musig::{AggregateNonce, KeyAggContext, PartialSignature, PublicNonce, SecretNonce, SigningSession};
+use crate::{sign_with_nonce, verify_signature, vrf_prove, vrf_verify, CryptoError, Point, ScalarField, Signature, VrfProof};
+use num_bigint::{BigInt, BigUint};
+use proptest::prelude::*;
+
+fn to_big(s: &ScalarField) -> BigUint {
+    BigUint::from_bytes_le(&s.to_bytes_le())
+}
+
+fn order() -> BigUint {
+    to_big(&ScalarField::N)
+}
+
+fn scalar() -> impl Strategy<Value = ScalarField> {
+    prop::collection::vec(any::<u8>(), 48).prop_map(|seed| {
+        let reduced = BigUint::from_bytes_le(&seed) % order();
+        let mut bytes = [0u8; 40];
+        let le = reduced.to_bytes_le();
+        bytes[..le.len()].copy_from_slice(&le);
+        ScalarField::from_bytes_le(&bytes).unwrap()
+    })
+}
+
+proptest! {
+    #[test]
+    fn scalar_bytes_round_trip(a in scalar()) {
+        prop_assert_eq!(ScalarField::from_bytes_le(&a.to_bytes_le()).unwrap(), a);
+    }
+
+    #[test]
+    fn scalar_ops_match_biguint(a in scalar(), b in scalar()) {
+        let n = order();
+        prop_assert_eq!(to_big(&a.add(b)), (to_big(&a) + to_big(&b)) % &n);
+        prop_assert_eq!(to_big(&a.sub(b)), (to_big(&a) + &n - to_big(&b)) % &n);
+        prop_assert_eq!(to_big(&a.mul(&b)), (to_big(&a) * to_big(&b)) % &n);
+        prop_assert_eq!(a.add(b).sub(b), a);
+    }
+
+    #[test]
+    fn scalar_order_and_hex_match_biguint(a in scalar(), b in scalar()) {
+        prop_assert_eq!(a.cmp(&b), to_big(&a).cmp(&to_big(&b)));
+        prop_assert!(a < ScalarField::N);
+        prop_assert_eq!(ScalarField::from_hex(&a.to_hex()).unwrap(), a);
+        prop_assert_eq!(a.is_valid(), !a.is_zero());
+
+        let mut aliased = [0u8; 40];
+        let le = (to_big(&a) + order()).to_bytes_le();
+        aliased[..le.len()].copy_from_slice(&le);
+        let aliased = ScalarField::from_bytes_le(&aliased).unwrap();
+        prop_assert!(aliased >= ScalarField::N && !aliased.is_valid());
+        prop_assert!(matches!(ScalarField::from_hex(&aliased.to_hex()), Err(CryptoError::NonCanonicalScalar)));
+    }
+
+    #[test]
+    fn sampled_scalars_are_reduced(_seed in any::<u8>()) {
+        prop_assert!(to_big(&ScalarField::sample_crypto()) < order());
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(16))]
+
+    #[test]
+    fn point_mul_matches_var_time(a in scalar()) {
+        let g = Point::generator();
+        let p = g.mul(&a);
+        prop_assert!(p.equals(&g.mul_var_time(&a)));
+        prop_assert!(p.mul(&a).equals(&p.mul_var_time(&a)));
+    }
+
+    #[test]
+    fn mul_generator_matches_mul(a in scalar()) {
+        prop_assert!(Point::mul_generator(&a).equals(&Point::generator().mul(&a)));
+    }
+
+    #[test]
+    fn point_mul_is_additive(a in scalar(), b in scalar()) {
+        let g = Point::generator();
+        prop_assert!(g.mul(&a).add(&g.mul(&b)).equals(&g.mul(&a.add(b))));
+    }
+
+    #[test]
+    fn sign_verify_round_trip(sk in scalar(), k in scalar(), msg in prop::array::uniform5(0..0xffffffff00000001u64)) {
+        let mut message = [0u8; 40];
+        for (i, limb) in msg.iter().enumerate() {
+            message[i * 8..(i + 1) * 8].copy_from_slice(&limb.to_le_bytes());
+        }
+        let public_key = Point::generator().mul(&sk).encode().to_bytes_le();
+        let signature = sign_with_nonce(&sk.to_bytes_le(), &message, &k.to_bytes_le()).unwrap();
+        prop_assert!(verify_signature(&signature, &message, &public_key).unwrap());
+
+        let typed = Signature::from_bytes(&signature).unwrap();
+        prop_assert_eq!(Signature::from_base64(&typed.to_base64()).unwrap(), typed);
+        prop_assert_eq!(typed.to_string().parse::<Signature>().unwrap(), typed);
+        prop_assert_eq!(Signature::from_hex(&format!("0x{}", hex::encode_upper(signature))).unwrap(), typed);
+    }
+
+    #[test]
+    fn musig_signatures_verify_under_aggregate_key(
+        keys in prop::collection::vec(scalar(), 2..4),
+        seed in any::<u64>(),
+        message in prop::array::uniform5(0..0xffffffff00000001u64),
+    ) {
+        use rand::{rngs::StdRng, SeedableRng};
+        prop_assume!(keys.iter().all(ScalarField::is_valid));
+        let message: Vec<u8> = message.iter().flat_map(|limb| limb.to_le_bytes()).collect();
+        let public_keys: Vec<[u8; 40]> = keys.iter().map(|key| Point::mul_generator(key).encode().to_bytes_le()).collect();
+        prop_assume!(public_keys.windows(2).all(|pair| pair[0] != pair[1]));
+
+        // Every party lists the keys in its own order
+        let context = KeyAggContext::new(&public_keys).unwrap();
+        let mut reversed = public_keys.clone();
+        reversed.reverse();
+        prop_assert_eq!(KeyAggContext::new(&reversed).unwrap().aggregate_public_key(), context.aggregate_public_key());
+
+        // Round 1: nonce commitments travel as bytes
+        let mut rng = StdRng::seed_from_u64(seed);
+        let secret_nonces: Vec<SecretNonce> = keys.iter().map(|_| SecretNonce::generate(&mut rng)).collect();
+        let public_nonces: Vec<PublicNonce> = secret_nonces
+            .iter()
+            .map(|nonce| PublicNonce::from_bytes(&nonce.public_nonce().to_bytes()).unwrap())
+            .collect();
+        let aggregate_nonce = AggregateNonce::sum(&public_nonces).unwrap();
+
+        // Round 2
+        let session = SigningSession::new(&context, &aggregate_nonce, &message).unwrap();
+        let mut partials = Vec::new();
+        for ((key, secret_nonce), (public_key, public_nonce)) in keys.iter().zip(secret_nonces).zip(public_keys.iter().zip(&public_nonces)) {
+            let partial = session.partial_sign(&key.to_bytes_le(), secret_nonce).unwrap();
+            prop_assert!(session.verify_partial(public_key, public_nonce, &partial).unwrap());
+            partials.push(PartialSignature::from_bytes(&partial.to_bytes()).unwrap());
+        }
+
+        let signature = session.aggregate(&partials).unwrap();
+        prop_assert!(verify_signature(signature.as_bytes(), &message, &context.aggregate_public_key()).unwrap());
+        prop_assert!(session.aggregate(&partials[1..]).is_err());
+
+        // A share swapped between parties is caught by the partial check
+        prop_assert!(!session.verify_partial(&public_keys[0], &public_nonces[0], &partials[1]).unwrap());
+        prop_assert!(matches!(
+            session.partial_sign(&ScalarField::TWO.to_bytes_le(), SecretNonce::generate(&mut rng)),
+            Err(CryptoError::UnknownSigner)
+        ));
+    }
+
+    #[test]
+    fn vrf_proofs_verify_and_bind(sk in scalar(), message in prop::collection::vec(any::<u8>(), 0..64), flip in 0usize..120) {
+        prop_assume!(sk.is_valid());
+        let public_key = Point::generator().mul(&sk).encode().to_bytes_le();
+        let (output, proof) = vrf_prove(&sk.to_bytes_le(), &message).unwrap();
+        prop_assert_eq!(vrf_verify(&public_key, &message, &proof).unwrap(), Some(output));
+        prop_assert_eq!(VrfProof::from_bytes(&proof.to_bytes()).unwrap(), proof);
+
+        let mut other = message.clone();
+        other.push(0);
+        prop_assert_eq!(vrf_verify(&public_key, &other, &proof).unwrap(), None);
+
+        // Any changed byte either fails to parse or fails to verify
+        let mut tampered = proof.to_bytes();
+        tampered[flip] ^= 1;
+        if let Ok(tampered) = VrfProof::from_bytes(&tampered) {
+            prop_assert_eq!(vrf_verify(&public_key, &message, &tampered).unwrap(), None);
+        }
+    }
+}
+
+proptest! {
+    #[test]
+    fn signature_rejects_unreduced_scalars(s in scalar(), e in scalar()) {
+        prop_assert!(Signature::from_parts(&s, &e).is_ok());
+
+        // s + N fits in 320 bits for every reduced s and encodes the same residue
+        let aliased = ScalarField::from_bytes_le(&{
+            let mut bytes = [0u8; 40];
+            let le = (to_big(&s) + order()).to_bytes_le();
+            bytes[..le.len()].copy_from_slice(&le);
+            bytes
+        }).unwrap();
+        prop_assert!(matches!(
+            Signature::from_parts(&aliased, &e),
+            Err(CryptoError::NonCanonicalSignature)
+        ));
+    }
+
+    #[test]
+    fn recode_signed_reconstructs_scalar(a in scalar(), window in 1usize..=31) {
+        let digits = a.recode_signed(window);
+        prop_assert_eq!(digits.len(), (319 + window) / window);
+        let half = 1i64 << (window - 1);
+        prop_assert!(digits.iter().all(|d| (-half..=half).contains(&i64::from(*d))));
+        let value = digits
+            .iter()
+            .rev()
+            .fold(BigInt::from(0), |acc, d| (acc << window) + BigInt::from(*d));
+        prop_assert_eq!(value, BigInt::from(to_big(&a)));
+    }
+}
+
+#[test]
+fn point_mul_edge_scalars() {
+    let g = Point::generator();
+    let one = ScalarField::from_bytes_le(&{
+        let mut bytes = [0u8; 40];
+        bytes[0] = 1;
+        bytes
+    })
+    .unwrap();
+    let n_minus_one = ScalarField::from_bytes_le(&{
+        let mut bytes = [0u8; 40];
+        let le = (order() - 1u32).to_bytes_le();
+        bytes[..le.len()].copy_from_slice(&le);
+        bytes
+    })
+    .unwrap();
+
+    // Formerly special-cased scalars now go through the ladder
+    assert!(g.mul(&ScalarField::from_bytes_le(&[0u8; 40]).unwrap()).equals(&Point::neutral()));
+    assert!(g.mul(&one).equals(&g));
+    assert!(g.mul(&n_minus_one).add(&g).equals(&Point::neutral()));
+    assert!(g.mul(&n_minus_one).equals(&g.mul_var_time(&n_minus_one)));
+    assert!(Point::mul_generator(&one).equals(&g));
+    assert!(Point::mul_generator(&n_minus_one).add(&g).equals(&Point::neutral()));
+
+    let mut lookup = crate::schnorr::AffinePoint::neutral();
+    let window = g.make_window_affine();
+    for k in -16..=16 {
+        lookup.set_lookup(&window, k);
+        let expected = Point::lookup_var_time(&window, k);
+        assert_eq!((lookup.x, lookup.u), (expected.x, expected.u), "digit {}", k);
+    }
+}
+
+#[test]
+fn scalar_add_reduces_sums_past_the_order() {
+    // add once returned the unreduced sum whenever it reached N
+    assert_eq!(ScalarField::NEG_ONE.add(ScalarField::ONE), ScalarField::ZERO);
+    assert_eq!(ScalarField::NEG_ONE.add(ScalarField::TWO), ScalarField::ONE);
+    assert_eq!(ScalarField::NEG_ONE.add(ScalarField::NEG_ONE), ScalarField::NEG_ONE.sub(ScalarField::ONE));
+    assert_eq!(ScalarField::ONE.add(ScalarField::ONE), ScalarField::TWO);
+    assert_eq!(ScalarField::ZERO.add(ScalarField::ZERO), ScalarField::ZERO);
+}
+
+/// Hands out the given 40-byte blocks in order.
+/// Hands out the bytes of its blocks in order.
+struct ScriptedRng(std::collections::VecDeque<u8>);
+
+impl ScriptedRng {
+    fn new(blocks: &[[u8; 40]]) -> Self {
+        ScriptedRng(blocks.iter().flatten().copied().collect())
+    }
+}
+
+impl rand::RngCore for ScriptedRng {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        assert!(dest.len() <= self.0.len(), "script exhausted");
+        let len = dest.len();
+        for (byte, scripted) in dest.iter_mut().zip(self.0.drain(..len)) {
+            *byte = scripted;
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> std::result::Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl rand::CryptoRng for ScriptedRng {}
+
+#[test]
+fn sample_rejects_candidates_from_the_order_up() {
+    // sample_crypto once rejection-sampled against a mis-encoded order and kept
+    // candidates at or above N
+    let n = ScalarField::N.to_bytes_le();
+    let mut n_plus_one = n;
+    n_plus_one[0] += 1;
+    let mut rng = ScriptedRng::new(&[[0xff; 40], n, n_plus_one, ScalarField::NEG_ONE.to_bytes_le(), [0; 40]]);
+    assert_eq!(ScalarField::sample(&mut rng), ScalarField::NEG_ONE);
+    assert_eq!(ScalarField::sample(&mut rng), ScalarField::ZERO);
+}
+
+#[test]
+fn scripted_rng_reads_words_little_endian() {
+    let mut block = [0u8; 40];
+    block[..12].copy_from_slice(&[1, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 1]);
+    let mut rng = ScriptedRng::new(&[block]);
+    assert_eq!(rand::RngCore::next_u32(&mut rng), 1);
+    assert_eq!(rand::RngCore::next_u64(&mut rng), 2 | 1 << 56);
+}
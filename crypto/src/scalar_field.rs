@@ -1,5 +1,8 @@
+use std::cmp::Ordering;
 use std::fmt;
 use num_bigint::BigUint;
+use crate::{CryptoError, Result};
+use zeroize::{Zeroize, Zeroizing};
 
 /// Scalar field element for the ECgFp5 curve.
 ///
@@ -10,7 +13,7 @@ use num_bigint::BigUint;
 /// # Example
 ///
 /// ```rust
-/// use crypto::ScalarField;
+/// use goldilocks_crypto::ScalarField;
 ///
 /// // Generate a random scalar (cryptographically secure)
 /// let scalar = ScalarField::sample_crypto();
@@ -22,7 +25,13 @@ use num_bigint::BigUint;
 /// // Convert to bytes
 /// let bytes = scalar.to_bytes_le();
 /// ```
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+///
+/// Scalars order by the integer their limbs encode, so `scalar < ScalarField::N` holds for
+/// every reduced scalar.
+///
+/// Scalars are mostly secrets (private keys, nonces), so `Debug` and `Display` print
+/// `ScalarField(<redacted>)`; `to_hex` gives the value where it is really needed.
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub struct ScalarField(pub [u64; 5]);
 
 impl ScalarField {
@@ -83,6 +92,7 @@ impl ScalarField {
     /// Internal addition function (without modular reduction).
     ///
     /// This is a low-level function used internally. Use `add()` for normal operations.
+    #[allow(clippy::needless_range_loop)] // limb-by-limb carry chain, as in lighter-go
     pub fn add_inner(&self, a: ScalarField) -> ScalarField {
         let mut r = [0u64; 5];
         let mut c = 0u64;
@@ -100,6 +110,7 @@ impl ScalarField {
     ///
     /// Returns the result and a borrow flag. This is a low-level function used internally.
     /// Use `sub()` for normal operations.
+    #[allow(clippy::needless_range_loop)] // limb-by-limb borrow chain, as in lighter-go
     pub fn sub_inner(&self, a: &ScalarField) -> (ScalarField, u64) {
         let mut r = [0u64; 5];
         let mut c = 0u64;
@@ -133,6 +144,17 @@ impl ScalarField {
         }
     }
     
+    /// Returns `true` if the limbs encode a value below the group order `N`.
+    pub fn is_canonical(&self) -> bool {
+        // Subtracting N borrows exactly when self < N
+        self.sub_inner(&Self::N).1 != 0
+    }
+
+    /// Returns `true` if the scalar can serve as a private key or nonce: in `[1, N)`.
+    pub fn is_valid(&self) -> bool {
+        self.is_canonical() && !self.is_zero()
+    }
+    
     /// Conditionally selects between two scalars.
     ///
     /// Returns `a1` if `c != 0`, otherwise returns `a0`.
@@ -152,7 +174,7 @@ impl ScalarField {
     /// # Example
     ///
     /// ```rust
-    /// use crypto::ScalarField;
+    /// use goldilocks_crypto::ScalarField;
     ///
     /// let a = ScalarField::ONE;
     /// let b = ScalarField::TWO;
@@ -161,18 +183,8 @@ impl ScalarField {
     pub fn add(&self, rhs: ScalarField) -> ScalarField {
         let r0 = self.add_inner(rhs);
         let (r1, c) = r0.sub_inner(&Self::N);
-        // Go: Select(c, r1, &r0)
-        // Go's Select(c, a0, a1): if c==0 return a0, else return a1
-        // So Select(c, r1, &r0) means: if c==0 return r1, else return r0
-        // But we want: if c==0 (no borrow, r0 < N) return r0, else return r1
-        // So we need to swap arguments: Select(c, r0, r1) but that's backwards too...
-        // Actually: Go Select(c, a0, a1) when c=0 returns a0
-        // We call Select(c, r1, &r0), so when c=0 we get r1, when c!=0 we get r0
-        // We want: when c=0 (r0 < N) return r0, when c!=0 (r0 >= N) return r1
-        // So we need: Select(c, r1, &r0) which is backwards!
-        // Let's check: Select(0, r1, &r0) returns r1, but we want r0
-        // So we should call: Select(c, &r0, &r1)
-        Self::select(c, &r0, &r1)
+        // Go: Select(c, &r1, &r0) - keep r0 - N unless subtracting N borrowed
+        Self::select(c, &r1, &r0)
     }
     
     /// Subtracts two scalars with modular reduction.
@@ -237,15 +249,14 @@ impl ScalarField {
     /// # Example
     ///
     /// ```rust
-    /// use crypto::ScalarField;
+    /// use goldilocks_crypto::ScalarField;
     ///
     /// let a = ScalarField::TWO;
     /// let b = ScalarField::TWO;
     /// let product = a.mul(&b);
     /// ```
     pub fn mul(&self, rhs: &ScalarField) -> ScalarField {
-        let res = self.monty_mul(&Self::R2).monty_mul(rhs);
-        res
+        self.monty_mul(&Self::R2).monty_mul(rhs)
     }
     
     /// Computes the square of this scalar.
@@ -263,7 +274,7 @@ impl ScalarField {
     /// # Example
     ///
     /// ```rust
-    /// use crypto::ScalarField;
+    /// use goldilocks_crypto::ScalarField;
     ///
     /// let a = ScalarField::from_bytes_le(&[1; 40]).unwrap();
     /// let b = ScalarField::from_bytes_le(&[2; 40]).unwrap();
@@ -289,8 +300,39 @@ impl ScalarField {
         result
     }
     
+    /// Lower-case hex of the 40 little-endian bytes (80 characters), the encoding of
+    /// private keys in configuration.
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.to_bytes_le())
+    }
+
+    /// Parses `to_hex` output, in either case, with or without a `0x` prefix.
+    ///
+    /// Fails with `CryptoError::NonCanonicalScalar` for a value not below the group order,
+    /// which `from_bytes_le` would accept unreduced.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use goldilocks_crypto::ScalarField;
+    ///
+    /// let scalar = ScalarField::from_u64(7);
+    /// assert_eq!(ScalarField::from_hex(&scalar.to_hex()).unwrap(), scalar);
+    /// assert!(ScalarField::from_hex(&"ff".repeat(40)).is_err());
+    /// ```
+    pub fn from_hex(encoded: &str) -> Result<Self> {
+        let encoded = encoded.trim();
+        let encoded = encoded.strip_prefix("0x").unwrap_or(encoded);
+        let bytes = Zeroizing::new(hex::decode(encoded)?);
+        let scalar = Self::from_bytes_le(&bytes).map_err(|_| CryptoError::InvalidPrivateKeyLength(bytes.len()))?;
+        if !scalar.is_canonical() {
+            return Err(CryptoError::NonCanonicalScalar);
+        }
+        Ok(scalar)
+    }
+
     // Convert from little-endian bytes
-    pub fn from_bytes_le(data: &[u8]) -> Result<Self, String> {
+    pub fn from_bytes_le(data: &[u8]) -> std::result::Result<Self, String> {
         if data.len() != 40 {
             return Err("Invalid length".to_string());
         }
@@ -344,10 +386,15 @@ impl ScalarField {
         ScalarField(result)
     }
     
-    // Recode scalar into signed digits for windowed multiplication (width-w signed recoding)
-    /// Recodes a scalar for signed windowed scalar multiplication.
+    /// Recodes a scalar into signed base-`2^window_width` digits, least significant first.
     ///
-    /// This is an internal function used for efficient point multiplication.
+    /// Returns `(319 + w) / w` digits `d_i` in `[-2^(w-1), 2^(w-1)]` with
+    /// `sum(d_i * 2^(w*i))` equal to the scalar: each `w`-bit window plus the carry from
+    /// the previous one, minus `2^w` (carrying one) when it exceeds `2^(w-1)`. `Point::mul` and `Point::mul_generator` walk these
+    /// digits with a table of `1..=2^(w-1)` multiples, negating for negative digits.
+    ///
+    /// The limbs are read as they are, so the scalar must be in canonical form (see
+    /// `to_canonical`). `window_width` must be between 1 and 31.
     pub fn recode_signed(&self, window_width: usize) -> Vec<i32> {
         let w = window_width as i32;
         let mw = (1u32 << w) - 1;
@@ -364,7 +411,7 @@ impl ScalarField {
         let mut j = 0;
         let mut cc: u32 = 0;
         
-        for i in 0..num_digits {
+        for digit in digits.iter_mut() {
             // Get next w-bit chunk in bb
             let mut bb: u32;
             if acc_len < w {
@@ -399,10 +446,11 @@ impl ScalarField {
                 acc >>= shift_amt;
             }
             
-            // If bb is greater than 2^(w-1), subtract 2^w and propagate a carry
+            // If bb is greater than 2^(w-1), subtract 2^w and propagate a carry; wraps like
+            // Go's int32 so a 31-bit window does not overflow
             bb = bb.wrapping_add(cc);
             cc = (hw.wrapping_sub(bb)) >> 31;
-            digits[i] = (bb as i32) - ((cc << w) as i32);
+            *digit = (bb as i32).wrapping_sub((cc << w) as i32);
         }
         
         digits
@@ -441,37 +489,47 @@ impl ScalarField {
     /// # Example
     ///
     /// ```rust
-    /// use crypto::ScalarField;
+    /// use goldilocks_crypto::ScalarField;
     ///
     /// let private_key = ScalarField::sample_crypto();
     /// ```
     pub fn sample_crypto() -> ScalarField {
-        use rand::Rng;
-        
-        // Generate random big int in range [0, ORDER)
-        // ORDER = 1067993516717146951041484916571792702745057740581727230159139685185762082554198619328292418486241
-        let order_bytes = hex::decode("e80fd996948bffe1e8885c39d724a09c7fffffe6cfb806397ffffff1000000167ffffffd80000007")
-            .expect("invalid ORDER hex");
-        
-        let order_big = BigUint::from_bytes_be(&order_bytes);
-        
-        // Generate random value less than ORDER
-        // We generate random bytes and check if less than ORDER
-        let mut rng = rand::thread_rng();
-        let mut random_bytes = [0u8; 40];
+        Self::sample(&mut rand::thread_rng())
+    }
+
+    /// Generates a uniformly random scalar from `rng`.
+    ///
+    /// Same as `sample_crypto` with a caller-supplied generator: a seeded one gives
+    /// reproducible scalars in tests, and targets without `thread_rng` can bring their
+    /// own entropy source.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use goldilocks_crypto::ScalarField;
+    /// use rand::{rngs::StdRng, SeedableRng};
+    ///
+    /// let a = ScalarField::sample(&mut StdRng::seed_from_u64(7));
+    /// let b = ScalarField::sample(&mut StdRng::seed_from_u64(7));
+    /// assert_eq!(a, b);
+    /// ```
+    pub fn sample<R: rand::RngCore + rand::CryptoRng + ?Sized>(rng: &mut R) -> ScalarField {
+        // Generate random value in range [0, ORDER) by rejection sampling.
+        // Candidates are compared against N limb-wise so no heap copies of the
+        // secret are made; the stack buffer is wiped before returning.
+        let mut random_bytes = Zeroizing::new([0u8; 40]);
         
         loop {
             // Generate random bytes
-            for byte in &mut random_bytes {
-                *byte = rng.gen();
-            }
+            rng.fill_bytes(&mut random_bytes[..]);
             
-            let random_big = BigUint::from_bytes_le(&random_bytes);
-            if random_big < order_big {
-                // Convert to limbs
-                let limbs_array = Self::bigint_to_limbs(random_big);
-                return ScalarField(limbs_array);
+            let mut candidate = Self::from_bytes_le(&random_bytes[..]).expect("40-byte buffer");
+            // A borrow when subtracting N means candidate < N
+            let (_, borrow) = candidate.sub_inner(&Self::N);
+            if borrow != 0 {
+                return candidate;
             }
+            candidate.zeroize();
         }
     }
     
@@ -515,9 +573,37 @@ impl ScalarField {
     }
 }
 
+/// Wipes the limbs in place.
+///
+/// `ScalarField` is `Copy`, so it cannot wipe itself on drop; owners of secret
+/// scalars (such as `KeyManager`) call this from their own `Drop`.
+impl Zeroize for ScalarField {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl Ord for ScalarField {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Most significant limb first
+        self.0.iter().rev().cmp(other.0.iter().rev())
+    }
+}
+
+impl PartialOrd for ScalarField {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Debug for ScalarField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ScalarField(<redacted>)")
+    }
+}
+
 impl fmt::Display for ScalarField {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "ScalarField({:016x}{:016x}{:016x}{:016x}{:016x})", 
-               self.0[4], self.0[3], self.0[2], self.0[1], self.0[0])
+        fmt::Debug::fmt(self, f)
     }
 }
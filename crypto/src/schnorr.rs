@@ -1,5 +1,8 @@
 use crate::{CryptoError, Result, Goldilocks, Fp5Element, ScalarField};
 use thiserror::Error;
+use zeroize::Zeroizing;
+#[cfg(feature = "precompute")]
+use std::sync::OnceLock;
 
 #[derive(Error, Debug)]
 pub enum SchnorrError {
@@ -53,6 +56,7 @@ const GENERATOR_ECG_FP5_POINT: Point = Point {
 };
 
 #[derive(Debug, Clone)]
+#[derive(Default)]
 pub struct Scalar([u64; 4]);
 
 impl Scalar {
@@ -162,19 +166,10 @@ impl Scalar {
         
         // For now, use a simplified conversion that takes the first 4 elements (32 bytes)
         // This matches the scalar field size (4*64 = 256 bits)
-        let mut scalar_limbs = [0u64; 4];
-        for i in 0..4 {
-            scalar_limbs[i] = e_fp5.0[i].0;
-        }
-        Scalar(scalar_limbs)
+        Scalar(std::array::from_fn(|i| e_fp5.0[i].0))
     }
 }
 
-impl Default for Scalar {
-    fn default() -> Self {
-        Scalar([0, 0, 0, 0])
-    }
-}
 
 #[derive(Debug, Clone, Copy)]
 pub struct AffinePoint {
@@ -207,6 +202,10 @@ impl AffinePoint {
         self.u = self.u.neg();
     }
     
+    /// Sets this point to `k * P` from a window `[P, 2P, ..., nP]` in constant time
+    ///
+    /// Every window entry is read and the negation is always computed, so neither the
+    /// memory access pattern nor the branches depend on `k`. `k = 0` gives the neutral point.
     pub fn set_lookup(&mut self, win: &[AffinePoint], k: i32) {
         // sign = 0xFFFFFFFF if k < 0, 0x00000000 otherwise
         let sign = (k >> 31) as u32;
@@ -218,25 +217,52 @@ impl AffinePoint {
         let mut x = Fp5Element::zero();
         let mut u = Fp5Element::zero();
         
-        for i in 0..win.len() {
+        for (i, entry) in win.iter().enumerate() {
             let m = km1.wrapping_sub(i as u32);
+            // c = all ones if m == 0 (entry i holds ka * P), zero otherwise
             let c_1 = (m | (!m).wrapping_add(1)) >> 31;
             let c = (c_1 as u64).wrapping_sub(1);
-            if c != 0 {
-                x = win[i].x;
-                u = win[i].u;
-            }
+            x = ct_select(c, &x, &entry.x);
+            u = ct_select(c, &u, &entry.u);
         }
         
         // If k < 0, then we must negate the point.
         let c = (sign as u64) | ((sign as u64) << 32);
         self.x = x;
-        self.u = u;
-        
-        if c != 0 {
-            self.u = self.u.neg();
-        }
+        self.u = ct_select(c, &u, &u.neg());
+    }
+}
+
+/// Window width of scalar multiplication: digits are in `-16..=16`.
+const WINDOW: usize = 5;
+
+/// Number of signed digits of a scalar at `WINDOW` bits each.
+#[cfg(feature = "precompute")]
+const GENERATOR_DIGITS: usize = (319 + WINDOW) / WINDOW;
+
+/// Windows `[1..=16] * 32^i * G` for every digit position `i`, built on first use (80 KiB).
+#[cfg(feature = "precompute")]
+fn generator_table() -> &'static [Vec<AffinePoint>] {
+    static TABLE: OnceLock<Vec<Vec<AffinePoint>>> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let mut base = Point::generator();
+        (0..GENERATOR_DIGITS)
+            .map(|_| {
+                let win = base.make_window_affine();
+                base = base.set_m_double(WINDOW as u32);
+                win
+            })
+            .collect()
+    })
+}
+
+/// `b` if `mask` is all ones, `a` if it is zero, without branching on `mask`.
+fn ct_select(mask: u64, a: &Fp5Element, b: &Fp5Element) -> Fp5Element {
+    let mut out = *a;
+    for (limb, (x, y)) in out.0.iter_mut().zip(a.0.iter().zip(b.0.iter())) {
+        limb.0 = x.0 ^ (mask & (x.0 ^ y.0));
     }
+    out
 }
 
 /// Point on the ECgFp5 elliptic curve.
@@ -247,7 +273,7 @@ impl AffinePoint {
 /// # Example
 ///
 /// ```rust
-/// use crypto::{Point, ScalarField};
+/// use goldilocks_crypto::{Point, ScalarField};
 ///
 /// // Get the generator point
 /// let generator = Point::generator();
@@ -364,10 +390,10 @@ impl Point {
     /// # Example
     ///
     /// ```rust
-    /// use crypto::{Point, ScalarField};
+    /// use goldilocks_crypto::{Point, ScalarField};
     ///
     /// let generator = Point::generator();
-    /// let public_key = generator.mul(&some_scalar);
+    /// let public_key = generator.mul(&ScalarField::sample_crypto());
     /// let s = ScalarField::sample_crypto();
     /// let e = ScalarField::sample_crypto();
     /// let result = Point::mul_add2(&generator, &public_key, &s, &e);
@@ -471,7 +497,9 @@ impl Point {
     /// Multiplies this point by a scalar (scalar multiplication).
     ///
     /// This is the core operation for key generation and signature verification.
-    /// Uses windowed scalar multiplication for efficiency.
+    /// Uses windowed scalar multiplication for efficiency. The running time does not depend
+    /// on the scalar: every digit, including zero digits and the scalars 0 and 1, goes
+    /// through the same constant-time window lookup and point addition.
     ///
     /// Note: The scalar should be in canonical form. If you have a scalar in Montgomery
     /// form (e.g., from `mul()`), convert it to canonical first using `monty_mul(&ScalarField::ONE)`.
@@ -479,24 +507,47 @@ impl Point {
     /// # Example
     ///
     /// ```rust
-    /// use crypto::{Point, ScalarField};
+    /// use goldilocks_crypto::{Point, ScalarField};
     ///
     /// let generator = Point::generator();
     /// let scalar = ScalarField::sample_crypto();
     /// let result = generator.mul(&scalar);
     /// ```
     pub fn mul(&self, scalar: &ScalarField) -> Point {
-        if scalar.0 == [0, 0, 0, 0, 0] {
-            return Point::neutral();
+        self.mul_windowed(scalar, Self::lookup)
+    }
+
+    /// Multiplies this point by a public scalar.
+    ///
+    /// Same result as `mul`, but window lookups take time depending on the scalar digits.
+    /// Only use it where the scalar is not secret, e.g. signature verification.
+    pub fn mul_var_time(&self, scalar: &ScalarField) -> Point {
+        self.mul_windowed(scalar, Self::lookup_var_time)
+    }
+
+    /// Multiplies the generator by `scalar`, in constant time.
+    ///
+    /// Equal to `Point::generator().mul(scalar)`. With the `precompute` feature (on by
+    /// default) it reads a static table of `32^i * G` windows, built on the first call,
+    /// and needs one point addition per scalar digit instead of five doublings and an
+    /// addition. Without the feature it falls back to `mul`.
+    pub fn mul_generator(scalar: &ScalarField) -> Point {
+        #[cfg(feature = "precompute")]
+        {
+            scalar
+                .recode_signed(WINDOW)
+                .iter()
+                .zip(generator_table())
+                .fold(Point::neutral(), |acc, (digit, win)| acc.add_affine(&Self::lookup(win, *digit)))
         }
-        
-        // Special case for scalar 1
-        if scalar.0 == [1, 0, 0, 0, 0] {
-            return self.clone();
+        #[cfg(not(feature = "precompute"))]
+        {
+            Self::generator().mul(scalar)
         }
-        
+    }
+
+    fn mul_windowed(&self, scalar: &ScalarField, lookup: fn(&[AffinePoint], i32) -> AffinePoint) -> Point {
         // Windowed multiplication algorithm (optimized)
-        const WINDOW: usize = 5;
         
         // Make window with affine points
         let win = self.make_window_affine();
@@ -506,14 +557,14 @@ impl Point {
         // Scalars from bytes are canonical, but scalars from mul() are Montgomery
         let digits = scalar.recode_signed(WINDOW);
         
-        // Start with the last digit (least significant)
-        let mut result = Self::lookup_var_time(&win, digits[digits.len() - 1]).to_point();
+        // Start with the most significant digit (the last one); the formulas are
+        // complete, so a zero scalar needs no special case
+        let mut result = lookup(&win, digits[digits.len() - 1]).to_point();
 
-        // Process remaining digits from right to left (least significant to most significant)
+        // Process the remaining digits from most to least significant
         for i in (0..digits.len() - 1).rev() {
             result = result.set_m_double(WINDOW as u32);
-            let lookup = Self::lookup(&win, digits[i]);
-            result = result.add_affine(&lookup);
+            result = result.add_affine(&lookup(&win, digits[i]));
         }
         
         result
@@ -524,7 +575,7 @@ impl Point {
     pub fn make_window_affine(&self) -> Vec<AffinePoint> {
         const WIN_SIZE: usize = 16; // 2^(5-1)
         let mut tmp = vec![Point::neutral(); WIN_SIZE];
-        tmp[0] = self.clone();
+        tmp[0] = *self;
         
         for i in 1..WIN_SIZE {
             if i & 1 == 0 {
@@ -589,9 +640,9 @@ impl Point {
         if k == 0 {
             AffinePoint::neutral()
         } else if k > 0 {
-            win[k as usize - 1].clone()
+            win[k as usize - 1]
         } else {
-            let mut res = win[(-k) as usize - 1].clone();
+            let mut res = win[(-k) as usize - 1];
             res.set_neg();
             res
         }
@@ -607,7 +658,7 @@ impl Point {
     // Multiple doublings - optimized point multiplication by 2^n
     pub fn set_m_double(&self, n: u32) -> Point {
         if n == 0 {
-            return self.clone();
+            return *self;
         }
         if n == 1 {
             return self.double();
@@ -733,11 +784,8 @@ impl Point {
     /// The encoding represents the point in a canonical form suitable for
     /// hashing and serialization.
     pub fn encode(&self) -> Fp5Element {
-        if self.u.is_zero() {
-            Fp5Element::zero()
-        } else {
-            self.t.mul(&self.u.inverse())
-        }
+        // Division by zero gives zero, the encoding of the neutral point
+        self.t.div(&self.u)
     }
     
     /// Checks if two points are equal using fractional coordinates.
@@ -746,7 +794,8 @@ impl Point {
     pub fn equals(&self, other: &Point) -> bool {
         let left = self.u.mul(&other.t);
         let right = other.u.mul(&self.t);
-        left.0.iter().zip(right.0.iter()).all(|(a, b)| a.0 == b.0)
+        // Limbs are not necessarily canonical (p and 0 are the same element)
+        left.equals(&right)
     }
     
     /// Decodes an Fp5Element back to a Point.
@@ -781,11 +830,8 @@ impl Point {
         let r = if success { r } else { Fp5::zero() };
         
         // Step 4: Solve quadratic: x1 = (e + r) / 2, x2 = (e - r) / 2
-        // Division by 2: multiply by inverse of 2
-        let fp5_two = Fp5::from_uint64_array([2, 0, 0, 0, 0]);
-        let two_inv = fp5_two.inverse();
-        let x1 = e.add(&r).mul(&two_inv);
-        let x2 = e.sub(&r).mul(&two_inv);
+        let x1 = e.add(&r).div(&Fp5::two());
+        let x2 = e.sub(&r).div(&Fp5::two());
         
         // Step 5: Choose x based on Legendre symbol
         // We want the solution that is NOT a square
@@ -824,6 +870,17 @@ impl Point {
     }
 }
 
+/// Schnorr challenge `e = H(R || message)`: Poseidon2 of the encoded nonce point and the
+/// message elements, reduced to a scalar.
+pub(crate) fn challenge(r_encoded: &Fp5Element, message_fp5: &Fp5Element) -> ScalarField {
+    use poseidon_hash::hash_to_quintic_extension;
+    // Use fixed-size array instead of Vec to avoid heap allocation
+    let mut pre_image = [Goldilocks::zero(); 10];
+    pre_image[..5].copy_from_slice(&r_encoded.0);
+    pre_image[5..].copy_from_slice(&message_fp5.0);
+    ScalarField::from_fp5_element(&hash_to_quintic_extension(&pre_image))
+}
+
 /// Helper function to convert message bytes to Fp5Element consistently.
 /// This ensures the same conversion is used in both signing and verification.
 ///
@@ -831,7 +888,7 @@ impl Point {
 /// - Message is 40 bytes (5 * 8 bytes)
 /// - Each 8-byte chunk is interpreted as little-endian u64
 /// - Converted to Goldilocks field elements and assembled into Fp5Element
-fn message_to_fp5(message: &[u8]) -> Result<Fp5Element> {
+pub(crate) fn message_to_fp5(message: &[u8]) -> Result<Fp5Element> {
     if message.len() != 40 {
         return Err(CryptoError::InvalidMessageLength(message.len()));
     }
@@ -908,56 +965,39 @@ pub fn sign_with_nonce(private_key: &[u8], message: &[u8], nonce_bytes: &[u8]) -
     }
     
     // Convert private key to 5-limb scalar field element (40 bytes = 5 * 8 bytes)
-    let private_scalar = ScalarField::from_bytes_le(private_key)
-        .map_err(|_| CryptoError::InvalidPrivateKeyLength(private_key.len()))?;
+    // Secret intermediates are wrapped in Zeroizing so they are wiped on every return path
+    let private_scalar = Zeroizing::new(ScalarField::from_bytes_le(private_key)
+        .map_err(|_| CryptoError::InvalidPrivateKeyLength(private_key.len()))?);
     
     // Convert nonce to 5-limb scalar field element
-    let mut nonce_bytes_40 = [0u8; 40];
+    let mut nonce_bytes_40 = Zeroizing::new([0u8; 40]);
     let copy_len = nonce_bytes.len().min(40);
     nonce_bytes_40[..copy_len].copy_from_slice(&nonce_bytes[..copy_len]);
-    let nonce_scalar = ScalarField::from_bytes_le(&nonce_bytes_40)
-        .map_err(|_| CryptoError::InvalidPrivateKeyLength(nonce_bytes.len()))?;
+    let nonce_scalar = Zeroizing::new(ScalarField::from_bytes_le(&nonce_bytes_40[..])
+        .map_err(|_| CryptoError::InvalidPrivateKeyLength(nonce_bytes.len()))?);
     
     // Convert message to Fp5Element (quintic extension field element)
     // Use helper function to ensure consistency with verification
     let message_fp5 = message_to_fp5(message)?;
     
     // Step 1: Compute R = nonce * generator_point
-    let generator = Point::generator();
-    let r_point = generator.mul(&nonce_scalar);
+    let r_point = Point::mul_generator(&nonce_scalar);
     let r_encoded = r_point.encode();
     
     // Step 2: Compute challenge e = H(R || message)
-    // Prepare pre-image: concatenate R elements (5) and message elements (5) = 10 total
-    use poseidon_hash::hash_to_quintic_extension;
-    // Use fixed-size array instead of Vec to avoid heap allocation
-    let mut pre_image = [Goldilocks::zero(); 10];
-    pre_image[..5].copy_from_slice(&r_encoded.0);
-    pre_image[5..].copy_from_slice(&message_fp5.0);
-    
-    // Hash the pre-image using Poseidon2 to get challenge e
-    let e_fp5 = hash_to_quintic_extension(&pre_image);
-    let e_scalar = ScalarField::from_fp5_element(&e_fp5);
+    let e_scalar = challenge(&r_encoded, &message_fp5);
     
     // Step 3: Compute response s = nonce - e * private_key
     // Note: mul() returns canonical form (Go keeps limbs in normal representation)
-    let e_times_private = e_scalar.mul(&private_scalar);
-    let s = nonce_scalar.sub(e_times_private);
+    let e_times_private = Zeroizing::new(e_scalar.mul(&private_scalar));
+    let s = nonce_scalar.sub(*e_times_private);
     
     // Step 4: Assemble signature as (s || e)
     // Signature format: 40 bytes for s, 40 bytes for e (little-endian)
-    // Use fixed-size array instead of Vec to avoid heap allocation
-    let mut signature = [0u8; 80];
-    
-    // Add s (40 bytes from scalar)
-    let s_bytes = s.to_bytes_le();
-    signature[..40].copy_from_slice(&s_bytes);
-    
-    // Add e (40 bytes from scalar)
-    let e_bytes = e_scalar.to_bytes_le();
-    signature[40..].copy_from_slice(&e_bytes);
+    // Both halves are checked to be reduced mod N; the exchange rejects anything else
+    let signature = crate::Signature::from_parts(&s, &e_scalar)?;
     
-    Ok(signature.to_vec())
+    Ok(signature.to_bytes().to_vec())
 }
 
 /// Verifies a Schnorr signature.
@@ -979,11 +1019,11 @@ pub fn sign_with_nonce(private_key: &[u8], message: &[u8], nonce_bytes: &[u8]) -
 /// # Example
 ///
 /// ```rust
-/// use crypto::{sign_with_nonce, verify_signature, ScalarField};
+/// use goldilocks_crypto::{sign_with_nonce, verify_signature, Point, ScalarField};
 ///
 /// let private_key = ScalarField::sample_crypto();
 /// let private_key_bytes = private_key.to_bytes_le();
-/// let public_key_bytes = private_key_bytes; // Simplified for example
+/// let public_key_bytes = Point::generator().mul(&private_key).encode().to_bytes_le();
 ///
 /// let message = [0u8; 40];
 /// let nonce = ScalarField::sample_crypto();
@@ -991,6 +1031,7 @@ pub fn sign_with_nonce(private_key: &[u8], message: &[u8], nonce_bytes: &[u8]) -
 ///
 /// let signature = sign_with_nonce(&private_key_bytes, &message, &nonce_bytes).unwrap();
 /// let is_valid = verify_signature(&signature, &message, &public_key_bytes).unwrap();
+/// assert!(is_valid);
 /// ```
 pub fn verify_signature(signature: &[u8], message: &[u8], public_key: &[u8]) -> Result<bool> {
     if signature.len() != 80 {
@@ -1031,8 +1072,7 @@ pub fn verify_signature(signature: &[u8], message: &[u8], public_key: &[u8]) ->
 
     // Compute R = s * G + e * public_key
     // Using separate multiplications and addition (this should work correctly)
-    let generator = Point::generator();
-    let s_g = generator.mul(&s);
+    let s_g = Point::mul_generator(&s);
     let e_public = public_point.mul(&e);
     let r_point = s_g.add(&e_public);
 
@@ -1040,14 +1080,7 @@ pub fn verify_signature(signature: &[u8], message: &[u8], public_key: &[u8]) ->
     let r_encoded = r_point.encode();
 
     // Compute e' = H(r || message) using Poseidon2 hash
-    use poseidon_hash::hash_to_quintic_extension;
-    // Use fixed-size array instead of Vec to avoid heap allocation
-    let mut pre_image = [Goldilocks::zero(); 10];
-    pre_image[..5].copy_from_slice(&r_encoded.0);
-    pre_image[5..].copy_from_slice(&message_fp5.0);
-
-    let e_prime_fp5 = hash_to_quintic_extension(&pre_image);
-    let e_prime_scalar = ScalarField::from_fp5_element(&e_prime_fp5);
+    let e_prime_scalar = challenge(&r_encoded, &message_fp5);
 
     // Verify e == e'
     let is_valid = e.equals(&e_prime_scalar);
@@ -1092,7 +1125,7 @@ fn monty_mul(a: &[u64; 4], b: &[u64; 4]) -> [u64; 4] {
     
     let mut temp = 0u128;
     for i in 0..4 {
-        temp += (q as u128) * (N[i] as u128) + (result[i] as u128);
+        temp += q * (N[i] as u128) + (result[i] as u128);
         if i < 3 {
             result[i] = (temp & 0xFFFFFFFFFFFFFFFF) as u64;
             temp >>= 64;
@@ -1109,6 +1142,7 @@ fn monty_mul(a: &[u64; 4], b: &[u64; 4]) -> [u64; 4] {
 }
 
 impl Scalar {
+    #[allow(clippy::needless_range_loop)] // limb-by-limb borrow chain
     pub fn sub(&self, other: &Scalar) -> Scalar {
         let mut result = [0u64; 4];
         let mut borrow = 0u64;
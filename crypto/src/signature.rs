@@ -0,0 +1,126 @@
+//! Typed Schnorr signatures.
+//!
+//! A signature is the 80-byte concatenation `s ‖ e` of two little-endian scalars. Both
+//! halves must be reduced modulo the group order: a scalar `v + n` verifies the same as `v`
+//! locally, but the exchange rejects it, so non-canonical encodings are refused at
+//! construction instead of surfacing later as code 21120.
+
+use crate::{CryptoError, Result, ScalarField};
+use base64::Engine;
+use std::fmt;
+use std::str::FromStr;
+
+/// A Schnorr signature with canonical `s` and `e` components.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Signature([u8; 80]);
+
+impl Signature {
+    /// Length of the encoded signature in bytes.
+    pub const LEN: usize = 80;
+
+    /// Parses `s ‖ e`, rejecting wrong lengths and non-canonical scalars.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use goldilocks_crypto::Signature;
+    ///
+    /// assert!(Signature::from_bytes(&[0u8; 80]).is_ok());
+    /// assert!(Signature::from_bytes(&[0xff; 80]).is_err());
+    /// ```
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != Self::LEN {
+            return Err(CryptoError::InvalidSignatureLength(bytes.len()));
+        }
+        for half in bytes.chunks_exact(40) {
+            let scalar = ScalarField::from_bytes_le(half).map_err(|_| CryptoError::InvalidSignature)?;
+            if !scalar.is_canonical() {
+                return Err(CryptoError::NonCanonicalSignature);
+            }
+        }
+
+        let mut out = [0u8; 80];
+        out.copy_from_slice(bytes);
+        Ok(Self(out))
+    }
+
+    /// Builds a signature from its two components.
+    pub fn from_parts(s: &ScalarField, e: &ScalarField) -> Result<Self> {
+        let mut bytes = [0u8; 80];
+        bytes[..40].copy_from_slice(&s.to_bytes_le());
+        bytes[40..].copy_from_slice(&e.to_bytes_le());
+        Self::from_bytes(&bytes)
+    }
+
+    pub fn to_bytes(&self) -> [u8; 80] {
+        self.0
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 80] {
+        &self.0
+    }
+
+    /// The response scalar `s`.
+    pub fn s(&self) -> ScalarField {
+        ScalarField::from_bytes_le(&self.0[..40]).expect("40-byte half")
+    }
+
+    /// The challenge scalar `e`.
+    pub fn e(&self) -> ScalarField {
+        ScalarField::from_bytes_le(&self.0[40..]).expect("40-byte half")
+    }
+
+    /// Standard base64, the encoding used in the `Sig` field of transactions.
+    pub fn to_base64(&self) -> String {
+        base64::engine::general_purpose::STANDARD.encode(self.0)
+    }
+
+    pub fn from_base64(encoded: &str) -> Result<Self> {
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(encoded.trim())
+            .map_err(|_| CryptoError::InvalidSignature)?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Lower-case hex (160 characters), the encoding used in auth tokens.
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.0)
+    }
+
+    /// Parses hex in either case, with or without a `0x` prefix.
+    pub fn from_hex(encoded: &str) -> Result<Self> {
+        let encoded = encoded.trim();
+        let encoded = encoded.strip_prefix("0x").unwrap_or(encoded);
+        Self::from_bytes(&hex::decode(encoded)?)
+    }
+}
+
+impl fmt::Display for Signature {
+    /// Formats as lower-case hex, see `to_hex`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+impl FromStr for Signature {
+    type Err = CryptoError;
+
+    /// Parses hex, see `from_hex`.
+    fn from_str(s: &str) -> Result<Self> {
+        Self::from_hex(s)
+    }
+}
+
+impl From<Signature> for [u8; 80] {
+    fn from(signature: Signature) -> Self {
+        signature.0
+    }
+}
+
+impl TryFrom<&[u8]> for Signature {
+    type Error = CryptoError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self> {
+        Self::from_bytes(bytes)
+    }
+}
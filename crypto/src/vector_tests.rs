@@ -0,0 +1,70 @@
+//! Scalar vectors from reference arithmetic, recoding digits from a Python model and Schnorr
+//! regression vectors (see `test-vectors/README.md`).
+
+use crate::{sign_with_nonce, verify_signature, Point, ScalarField};
+use serde_json::Value;
+
+const SCALAR_VECTORS: &str = include_str!("../../test-vectors/scalar.json");
+const SCHNORR_VECTORS: &str = include_str!("../../test-vectors/schnorr.json");
+
+fn cases(raw: &str) -> Vec<Value> {
+    let file: Value = serde_json::from_str(raw).expect("vector file is valid JSON");
+    file["cases"].as_array().expect("vector file has cases").clone()
+}
+
+fn bytes(value: &Value) -> Vec<u8> {
+    hex::decode(value.as_str().expect("hex string")).expect("valid hex")
+}
+
+fn scalar(value: &Value) -> ScalarField {
+    ScalarField::from_bytes_le(&bytes(value)).expect("40-byte scalar")
+}
+
+#[test]
+fn test_scalar_vectors() {
+    for case in cases(SCALAR_VECTORS) {
+        if case["op"] == "recode_signed" {
+            continue;
+        }
+        let a = scalar(&case["a"]);
+        let b = scalar(&case["b"]);
+        let result = match case["op"].as_str().unwrap() {
+            "add" => a.add(b),
+            "sub" => a.sub(b),
+            "mul" => a.mul(&b),
+            other => panic!("unknown scalar op {}", other),
+        };
+        assert_eq!(result, scalar(&case["expected"]), "case {}", case);
+    }
+}
+
+/// Digits from the Python model of the recoding in `generate_field_vectors.py`; this checks
+/// agreement with that model, not with lighter-go.
+#[test]
+fn test_recode_signed_matches_python_model() {
+    for case in cases(SCALAR_VECTORS).into_iter().filter(|case| case["op"] == "recode_signed") {
+        let window = case["window"].as_u64().unwrap() as usize;
+        let expected: Vec<i32> = serde_json::from_value(case["expected"].clone()).unwrap();
+        assert_eq!(scalar(&case["a"]).recode_signed(window), expected, "case {}", case);
+    }
+}
+
+#[test]
+fn test_schnorr_vectors() {
+    for case in cases(SCHNORR_VECTORS) {
+        let private_key = bytes(&case["private_key"]);
+        let public_key = bytes(&case["public_key"]);
+        let message = bytes(&case["message"]);
+        let signature = bytes(&case["signature"]);
+
+        let derived = Point::generator()
+            .mul(&ScalarField::from_bytes_le(&private_key).unwrap())
+            .encode()
+            .to_bytes_le();
+        assert_eq!(derived.to_vec(), public_key, "public key for case {}", case);
+
+        let signed = sign_with_nonce(&private_key, &message, &bytes(&case["nonce"])).unwrap();
+        assert_eq!(signed, signature, "signature for case {}", case);
+        assert!(verify_signature(&signature, &message, &public_key).unwrap());
+    }
+}
@@ -0,0 +1,247 @@
+//! Verifiable random function over ECgFp5.
+//!
+//! An ECVRF-style construction (after RFC 9381) with Poseidon2 in place of SHA-256. For a key
+//! `x` with public key `Y = x·G` and a message `alpha`:
+//!
+//! 1. `H = hash_to_curve(Y, alpha)`, a point whose discrete log nobody knows
+//! 2. `Gamma = x·H`
+//! 3. `k` is derived from `x` and `H`, `U = k·G`, `V = k·H`
+//! 4. `c = Poseidon2(Y, H, Gamma, U, V)` as a scalar, `s = k - c·x`
+//!
+//! The proof is `Gamma ‖ c ‖ s` (120 bytes) and the output is a Poseidon2 hash of `Gamma`.
+//! A verifier recomputes `U = s·G + c·Y` and `V = s·H + c·Gamma` and checks the challenge,
+//! which shows that `Gamma` has the same discrete log to `H` as `Y` to `G`. The output is
+//! therefore fixed by the key and the message, unpredictable without the private key, and
+//! checkable by anyone holding the public key.
+//!
+//! `hash_to_curve` is try-and-increment: Poseidon2 of the inputs and a counter is decoded as
+//! a point, retrying with the next counter until it decodes (about half of all field elements
+//! do). Its running time depends on the message, which is public.
+
+use crate::{CryptoError, Fp5Element, Goldilocks, Point, Result, ScalarField};
+use poseidon_hash::{array_from_le_bytes, hash_to_quintic_extension};
+use zeroize::Zeroizing;
+
+/// Domain separation tag of every hash in the construction; followed by one of the
+/// `*_DOMAIN` elements.
+const VRF_TAG: &[u8] = b"lighter-ecgfp5-vrf-v1";
+const HASH_TO_CURVE_DOMAIN: u64 = 1;
+const NONCE_DOMAIN: u64 = 2;
+const CHALLENGE_DOMAIN: u64 = 3;
+const OUTPUT_DOMAIN: u64 = 4;
+
+/// The pseudorandom output of the VRF for a key and a message.
+pub type VrfOutput = [u8; 40];
+
+/// Proof that a `VrfOutput` was computed with the private key of a public key.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct VrfProof([u8; 120]);
+
+impl VrfProof {
+    /// Length of the encoded proof in bytes.
+    pub const LEN: usize = 120;
+
+    /// Parses `Gamma ‖ c ‖ s`, rejecting wrong lengths, points that do not decode and
+    /// non-canonical scalars.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != Self::LEN {
+            return Err(CryptoError::InvalidVrfProof);
+        }
+        // Gamma must be the canonical encoding of a point, or one proof would have several encodings
+        let gamma = Fp5Element::from_bytes_le(&bytes[..40]).map_err(|_| CryptoError::InvalidVrfProof)?;
+        match Point::decode(&gamma) {
+            Some(point) if point.encode().to_bytes_le()[..] == bytes[..40] => {}
+            _ => return Err(CryptoError::InvalidVrfProof),
+        }
+        for half in bytes[40..].chunks_exact(40) {
+            let scalar = ScalarField::from_bytes_le(half).map_err(|_| CryptoError::InvalidVrfProof)?;
+            if !scalar.is_canonical() {
+                return Err(CryptoError::NonCanonicalScalar);
+            }
+        }
+
+        let mut out = [0u8; 120];
+        out.copy_from_slice(bytes);
+        Ok(Self(out))
+    }
+
+    fn from_parts(gamma: &Point, c: &ScalarField, s: &ScalarField) -> Self {
+        let mut bytes = [0u8; 120];
+        bytes[..40].copy_from_slice(&gamma.encode().to_bytes_le());
+        bytes[40..80].copy_from_slice(&c.to_bytes_le());
+        bytes[80..].copy_from_slice(&s.to_bytes_le());
+        Self(bytes)
+    }
+
+    pub fn to_bytes(&self) -> [u8; 120] {
+        self.0
+    }
+
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.0)
+    }
+
+    /// Parses hex in either case, with or without a `0x` prefix.
+    pub fn from_hex(encoded: &str) -> Result<Self> {
+        let encoded = encoded.trim();
+        let encoded = encoded.strip_prefix("0x").unwrap_or(encoded);
+        Self::from_bytes(&hex::decode(encoded)?)
+    }
+
+    /// The output this proof attests to. Only meaningful once `vrf_verify` accepts the proof.
+    pub fn output(&self) -> VrfOutput {
+        output_of(&self.gamma_encoded())
+    }
+
+    fn gamma_encoded(&self) -> Fp5Element {
+        Fp5Element::from_bytes_le(&self.0[..40]).expect("40-byte point")
+    }
+
+    fn c(&self) -> ScalarField {
+        ScalarField::from_bytes_le(&self.0[40..80]).expect("40-byte scalar")
+    }
+
+    fn s(&self) -> ScalarField {
+        ScalarField::from_bytes_le(&self.0[80..]).expect("40-byte scalar")
+    }
+}
+
+/// Hashes a message to a point of the group, bound to the public key it is evaluated under.
+///
+/// The discrete log of the result to the generator is unknown, and the result is never the
+/// neutral point.
+pub fn hash_to_curve(public_key: &[u8], message: &[u8]) -> Result<Point> {
+    let public_key = decode_public_key(public_key)?.encode();
+    Ok(hash_to_curve_encoded(&public_key, message))
+}
+
+fn hash_to_curve_encoded(public_key: &Fp5Element, message: &[u8]) -> Point {
+    let mut input = tagged(HASH_TO_CURVE_DOMAIN);
+    input.extend_from_slice(&public_key.0);
+    input.push(Goldilocks::from_canonical_u64(message.len() as u64));
+    input.extend(array_from_le_bytes(message));
+    input.push(Goldilocks::zero());
+    let counter = input.len() - 1;
+
+    let mut round = 0u64;
+    loop {
+        input[counter] = Goldilocks::from_canonical_u64(round);
+        if let Some(point) = Point::decode(&hash_to_quintic_extension(&input)).filter(|point| !point.is_neutral()) {
+            return point;
+        }
+        round += 1;
+    }
+}
+
+/// Evaluates the VRF on `message` with `private_key` (40 bytes, little-endian).
+///
+/// Returns the output and a proof of it for `vrf_verify`. Both are deterministic: the same
+/// key and message always give the same output and the same proof.
+///
+/// # Example
+///
+/// ```rust
+/// use goldilocks_crypto::{vrf_prove, vrf_verify, Point, ScalarField};
+///
+/// let private_key = ScalarField::sample_crypto();
+/// let public_key = Point::generator().mul(&private_key).encode().to_bytes_le();
+///
+/// let (output, proof) = vrf_prove(&private_key.to_bytes_le(), b"round 42").unwrap();
+/// assert_eq!(vrf_verify(&public_key, b"round 42", &proof).unwrap(), Some(output));
+/// assert_eq!(vrf_verify(&public_key, b"round 43", &proof).unwrap(), None);
+/// ```
+pub fn vrf_prove(private_key: &[u8], message: &[u8]) -> Result<(VrfOutput, VrfProof)> {
+    if private_key.len() != 40 {
+        return Err(CryptoError::InvalidPrivateKeyLength(private_key.len()));
+    }
+    let x = Zeroizing::new(ScalarField::from_bytes_le(private_key)
+        .map_err(|_| CryptoError::InvalidPrivateKeyLength(private_key.len()))?);
+    if !x.is_valid() {
+        return Err(CryptoError::InvalidPrivateKey);
+    }
+
+    let public_key = Point::mul_generator(&x).encode();
+    let h = hash_to_curve_encoded(&public_key, message);
+    let gamma = h.mul(&x);
+
+    let k = Zeroizing::new(nonce(private_key, &h.encode()));
+    let u = Point::mul_generator(&k);
+    let v = h.mul(&k);
+
+    let c = challenge(&public_key, &h, &gamma, &u, &v);
+    let c_times_x = Zeroizing::new(c.mul(&x));
+    let s = k.sub(*c_times_x);
+
+    let proof = VrfProof::from_parts(&gamma, &c, &s);
+    Ok((proof.output(), proof))
+}
+
+/// Checks a proof of `vrf_prove` for `message` under `public_key`.
+///
+/// Returns the proven output if the proof is valid and `None` if it is not, or an error if the
+/// public key does not decode.
+pub fn vrf_verify(public_key: &[u8], message: &[u8], proof: &VrfProof) -> Result<Option<VrfOutput>> {
+    let y = decode_public_key(public_key)?;
+    let public_key = y.encode();
+    let gamma = Point::decode(&proof.gamma_encoded()).ok_or(CryptoError::InvalidVrfProof)?;
+    let (c, s) = (proof.c(), proof.s());
+
+    let h = hash_to_curve_encoded(&public_key, message);
+    let u = Point::mul_add2(&Point::generator(), &y, &s, &c);
+    let v = Point::mul_add2(&h, &gamma, &s, &c);
+
+    let valid = challenge(&public_key, &h, &gamma, &u, &v).equals(&c);
+    Ok(valid.then(|| proof.output()))
+}
+
+fn decode_public_key(public_key: &[u8]) -> Result<Point> {
+    if public_key.len() != 40 {
+        return Err(CryptoError::InvalidPrivateKeyLength(public_key.len()));
+    }
+    let encoded = Fp5Element::from_bytes_le(public_key).map_err(|_| CryptoError::InvalidPublicKey)?;
+    Point::decode(&encoded).filter(|point| !point.is_neutral()).ok_or(CryptoError::InvalidPublicKey)
+}
+
+fn tagged(domain: u64) -> Vec<Goldilocks> {
+    let mut input = array_from_le_bytes(VRF_TAG);
+    input.push(Goldilocks::from_canonical_u64(domain));
+    input
+}
+
+/// Deterministic nonce from the private key and `H`, rejection-sampled like
+/// `KeyManager::derive_api_key` so it is uniform below the group order.
+fn nonce(private_key: &[u8], h: &Fp5Element) -> ScalarField {
+    let mut input = tagged(NONCE_DOMAIN);
+    input.extend(array_from_le_bytes(private_key));
+    input.extend_from_slice(&h.0);
+    input.push(Goldilocks::zero());
+    let counter = input.len() - 1;
+
+    let mut round = 0u64;
+    let k = loop {
+        input[counter] = Goldilocks::from_canonical_u64(round);
+        let candidate_bytes = Zeroizing::new(hash_to_quintic_extension(&input).to_bytes_le());
+        let candidate = ScalarField::from_bytes_le(&candidate_bytes[..]).expect("40-byte hash");
+        if candidate.is_valid() {
+            break candidate;
+        }
+        round += 1;
+    };
+    input.iter_mut().for_each(|element| *element = Goldilocks::zero());
+    k
+}
+
+fn challenge(public_key: &Fp5Element, h: &Point, gamma: &Point, u: &Point, v: &Point) -> ScalarField {
+    let mut input = tagged(CHALLENGE_DOMAIN);
+    input.extend_from_slice(&public_key.0);
+    for point in [h, gamma, u, v] {
+        input.extend_from_slice(&point.encode().0);
+    }
+    ScalarField::from_fp5_element(&hash_to_quintic_extension(&input))
+}
+
+fn output_of(gamma: &Fp5Element) -> VrfOutput {
+    let mut input = tagged(OUTPUT_DOMAIN);
+    input.extend_from_slice(&gamma.0);
+    hash_to_quintic_extension(&input).to_bytes_le()
+}
@@ -0,0 +1,41 @@
+//! Untrusted signature and public key encodings: raw bytes, base64 and hex parsing, and
+//! verification of arbitrary signature, message and key bytes.
+//!
+//! Parsing must reject bad input with an error, and anything it accepts must round-trip
+//! through its encodings unchanged.
+
+#![no_main]
+
+use goldilocks_crypto::{verify_signature, ScalarField, Signature};
+use libfuzzer_sys::fuzz_target;
+use signer::PublicKey;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(signature) = Signature::from_bytes(data) {
+        assert_eq!(&signature.to_bytes()[..], data);
+        assert_eq!(Signature::from_base64(&signature.to_base64()).unwrap(), signature);
+        assert_eq!(Signature::from_hex(&signature.to_hex()).unwrap(), signature);
+        assert_eq!(Signature::from_parts(&signature.s(), &signature.e()).unwrap(), signature);
+    }
+    if let Ok(key) = PublicKey::from_bytes(data) {
+        assert_eq!(PublicKey::from_hex(&key.to_hex()).unwrap(), key);
+    }
+    let _ = ScalarField::from_bytes_le(data);
+
+    if let Ok(text) = std::str::from_utf8(data) {
+        let _ = Signature::from_base64(text);
+        let _ = Signature::from_hex(text);
+        let _ = text.parse::<Signature>();
+        let _ = PublicKey::from_hex(text);
+        let _ = ScalarField::from_hex(text);
+    }
+
+    // Signature, message and key cut from the input at fixed lengths, so that well-sized
+    // but otherwise arbitrary values are common
+    if data.len() >= 160 {
+        let (signature, rest) = data.split_at(80);
+        let (message, public_key) = rest.split_at(40);
+        let _ = verify_signature(signature, message, &public_key[..40]);
+    }
+    let _ = verify_signature(data, data, data);
+});
@@ -0,0 +1,131 @@
+//! Arbitrary JSON values under the `tx_info` field names, through the typed field reads,
+//! the transaction hash of every supported type, and the Go-ordered `tx_info` text.
+//!
+//! Structured inputs reach the per-type field conversions that raw text rarely gets past
+//! the parser to: integers at the edges of every width, negative and fractional numbers,
+//! strings and arrays where numbers are expected, and nested grouped orders.
+
+#![no_main]
+
+use api_client::tx_fields::{array_field, element, field, str_field, usdc_limbs};
+use api_client::tx_json::tx_info_json;
+use api_client::{BaseAmount, LighterClient, Price};
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use serde_json::{Map, Number, Value};
+use std::sync::OnceLock;
+
+const PRIVATE_KEY: &str = "bda332f3aaa2d9cfdd8920830ea37efce9636c671a426bd4cb9815007e2a2917604ab47857cbb200";
+
+/// Every field name the transaction hash reads.
+const KEYS: &[&str] = &[
+    "Nonce",
+    "ExpiredAt",
+    "AccountIndex",
+    "FromAccountIndex",
+    "ApiKeyIndex",
+    "MarketIndex",
+    "ClientOrderIndex",
+    "BaseAmount",
+    "Price",
+    "IsAsk",
+    "Type",
+    "TimeInForce",
+    "ReduceOnly",
+    "TriggerPrice",
+    "OrderExpiry",
+    "Index",
+    "Time",
+    "PubKey",
+    "InitialMarginFraction",
+    "MarginMode",
+    "OperatorFee",
+    "InitialTotalShares",
+    "MinOperatorShareRate",
+    "PublicPoolIndex",
+    "Status",
+    "ToAccountIndex",
+    "USDCAmount",
+    "Fee",
+    "ShareAmount",
+    "GroupingType",
+    "Orders",
+    "Direction",
+    "Sig",
+];
+
+/// Transaction types the client signs, see `compute_tx_hash`.
+const TX_TYPES: &[u32] = &[8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 28, 29];
+
+#[derive(Arbitrary, Debug)]
+enum Json {
+    Null,
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    Str(String),
+    Array(Vec<Json>),
+    Object(Vec<(u8, Json)>),
+}
+
+impl Json {
+    fn into_value(self) -> Value {
+        match self {
+            Json::Null => Value::Null,
+            Json::Bool(b) => Value::Bool(b),
+            Json::Int(n) => Value::from(n),
+            Json::UInt(n) => Value::from(n),
+            Json::Float(x) => Number::from_f64(x).map_or(Value::Null, Value::Number),
+            Json::Str(s) => Value::String(s),
+            Json::Array(items) => Value::Array(items.into_iter().map(Json::into_value).collect()),
+            Json::Object(fields) => object(fields),
+        }
+    }
+}
+
+fn object(fields: Vec<(u8, Json)>) -> Value {
+    let map: Map<String, Value> = fields
+        .into_iter()
+        .map(|(key, value)| (KEYS[key as usize % KEYS.len()].to_string(), value.into_value()))
+        .collect();
+    Value::Object(map)
+}
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    tx_type: u8,
+    fields: Vec<(u8, Json)>,
+}
+
+fn client() -> &'static LighterClient {
+    static CLIENT: OnceLock<LighterClient> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        LighterClient::new("https://testnet.zklighter.elliot.ai".to_string(), PRIVATE_KEY, 1, 0).expect("client")
+    })
+}
+
+fuzz_target!(|input: Input| {
+    let tx = object(input.fields);
+
+    for key in KEYS {
+        let _ = element::<i64>(&tx, key);
+        let _ = element::<u8>(&tx, key);
+        let _ = element::<u16>(&tx, key);
+        let _ = element::<u32>(&tx, key);
+        let _ = element::<Price>(&tx, key);
+        let _ = element::<BaseAmount>(&tx, key);
+        let _ = str_field(&tx, key);
+        let _ = array_field(&tx, key);
+
+        // The two 32-bit limbs of a USDC amount put back together are the amount
+        if let (Ok(units), Ok([low, high])) = (field::<u64>(&tx, key), usdc_limbs(&tx, key)) {
+            assert_eq!(low.to_canonical_u64() | (high.to_canonical_u64() << 32), units);
+        }
+    }
+
+    let tx_type = TX_TYPES[input.tx_type as usize % TX_TYPES.len()];
+    let tx_json = tx.to_string();
+    let _ = client().compute_tx_hash(&tx_json, tx_type);
+    let _ = tx_info_json(tx_type, &tx);
+});
@@ -0,0 +1,33 @@
+//! Raw `tx_info` text into the hash-and-sign path of `sign_transaction_with_type`.
+//!
+//! Any input must hash, or fail with an error; a hash that comes out must sign to a
+//! signature that verifies against it.
+
+#![no_main]
+
+use api_client::LighterClient;
+use libfuzzer_sys::fuzz_target;
+use signer::KeyManager;
+use std::sync::OnceLock;
+
+const PRIVATE_KEY: &str = "bda332f3aaa2d9cfdd8920830ea37efce9636c671a426bd4cb9815007e2a2917604ab47857cbb200";
+
+fn client() -> &'static (LighterClient, KeyManager) {
+    static CLIENT: OnceLock<(LighterClient, KeyManager)> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        let client = LighterClient::new("https://testnet.zklighter.elliot.ai".to_string(), PRIVATE_KEY, 1, 0)
+            .expect("client");
+        (client, KeyManager::from_hex(PRIVATE_KEY).expect("key"))
+    })
+}
+
+fuzz_target!(|input: (u32, &str)| {
+    let (tx_type, tx_json) = input;
+    let (client, key) = client();
+
+    let Ok(hash) = client.compute_tx_hash(tx_json, tx_type) else {
+        return;
+    };
+    let signature = key.sign(&hash).expect("a hash always signs");
+    assert!(goldilocks_crypto::verify_signature(&signature.to_bytes(), &hash, &key.public_key_bytes()).unwrap());
+});
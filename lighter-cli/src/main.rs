@@ -0,0 +1,368 @@
+//! Command-line trading tool for Lighter.
+//!
+//! Wraps `LighterClient` for operational interventions: inspect the account, place and cancel
+//! orders, move funds and change leverage without writing a program. Credentials come from
+//! the same variables as the examples (`BASE_URL`, `API_PRIVATE_KEY`, `ACCOUNT_INDEX`,
+//! `API_KEY_INDEX`, also read from `.env`) or from the matching flags. Amounts and prices are
+//! given in human units (`0.1`, `3012.5`) and converted with the market's decimals.
+//!
+//! Results print as tables, or as JSON with `--json`. A transaction the exchange rejects
+//! exits with an error.
+
+mod output;
+
+use api_client::{
+    AddressBook, BaseAmount, CheckedResponse, CreateOrderRequest, Decimal, LighterClient, MarginMode, OrderType,
+    Price, TimeInForce, UsdcAmount, WithdrawRequest, CANCEL_ALL_TIF_IMMEDIATE,
+};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use output::{field, Format, Table};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Parser)]
+#[command(name = "lighter-cli", version, about = "Trade on Lighter from the command line")]
+struct Cli {
+    #[arg(long, env = "BASE_URL", default_value = "https://mainnet.zklighter.elliot.ai")]
+    base_url: String,
+    /// API private key (40 bytes hex)
+    #[arg(long, env = "API_PRIVATE_KEY", hide_env_values = true)]
+    private_key: String,
+    #[arg(long, env = "ACCOUNT_INDEX")]
+    account_index: i64,
+    #[arg(long, env = "API_KEY_INDEX")]
+    api_key_index: u8,
+    /// Print JSON instead of tables
+    #[arg(long, global = true)]
+    json: bool,
+    /// JSON file of labelled counterparties, for `transfer --to`
+    #[arg(long, env = "ADDRESS_BOOK", global = true, default_value = "address_book.json")]
+    address_book: PathBuf,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Account, API key slot, key registration and fee tier of the client
+    Whoami,
+    /// Balances of the account
+    Account,
+    /// Open positions
+    Positions,
+    /// Create, cancel or list orders
+    #[command(subcommand)]
+    Order(OrderCommand),
+    /// Cancel every open order
+    CancelAll,
+    /// Transfer USDC to another account
+    Transfer {
+        /// Account index, L1 address or address book label
+        #[arg(long)]
+        to: String,
+        /// USDC, e.g. 25.5
+        #[arg(long)]
+        amount: String,
+        /// Transfer fee in USDC
+        #[arg(long, default_value = "0")]
+        fee: String,
+    },
+    /// Withdraw USDC to L1
+    Withdraw {
+        /// USDC, e.g. 25.5
+        #[arg(long)]
+        amount: String,
+    },
+    /// Set the leverage of a market
+    Leverage {
+        #[arg(long)]
+        market: u8,
+        #[arg(long)]
+        leverage: u16,
+        /// Isolated instead of cross margin
+        #[arg(long)]
+        isolated: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum OrderCommand {
+    Create(CreateArgs),
+    Cancel {
+        #[arg(long)]
+        market: u8,
+        /// Exchange order index (see `order list`)
+        #[arg(long)]
+        order_index: i64,
+    },
+    /// Open orders, of one market or all
+    List {
+        #[arg(long)]
+        market: Option<u8>,
+    },
+}
+
+/// Create a limit order, or a market order with `--type market` (`--price` is then the worst fill price)
+#[derive(Args)]
+struct CreateArgs {
+    #[arg(long)]
+    market: u8,
+    #[arg(long, value_enum)]
+    side: Side,
+    /// Size in base currency, e.g. 0.1
+    #[arg(long)]
+    size: String,
+    /// Price in quote currency, e.g. 3012.5
+    #[arg(long)]
+    price: String,
+    #[arg(long = "type", value_enum, default_value = "limit")]
+    order_type: CliOrderType,
+    #[arg(long, value_enum, default_value = "gtt")]
+    tif: Tif,
+    #[arg(long)]
+    reduce_only: bool,
+    /// Defaults to the current time in milliseconds
+    #[arg(long)]
+    client_order_index: Option<u64>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Side {
+    Buy,
+    Sell,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum CliOrderType {
+    Limit,
+    Market,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Tif {
+    Gtt,
+    Ioc,
+    PostOnly,
+}
+
+/// Converts a decimal amount to integer units with `decimals` decimals, rejecting extra precision.
+fn to_units(amount: &str, decimals: u32) -> Result<i64, String> {
+    let value = Decimal::from_str(amount.trim()).map_err(|e| format!("invalid amount {:?}: {}", amount, e))?;
+    if value.normalize().scale() > decimals {
+        return Err(format!("{} has more than {} decimals", amount, decimals));
+    }
+    let mut units = value;
+    units.rescale(decimals);
+    i64::try_from(units.mantissa()).map_err(|_| format!("{} is out of range", amount))
+}
+
+fn to_usdc(amount: &str) -> Result<UsdcAmount, String> {
+    let value = Decimal::from_str(amount.trim()).map_err(|e| format!("invalid amount {:?}: {}", amount, e))?;
+    UsdcAmount::from_decimal(value).map_err(|e| e.to_string())
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or_default()
+}
+
+/// The account object of a `get_account` response.
+fn account_entry(account_info: &Value) -> &Value {
+    account_info["accounts"].get(0).unwrap_or(account_info)
+}
+
+/// Prints a transaction response, its hash as a table.
+fn print_tx(format: Format, response: &Value) {
+    let mut table = Table::new(&["CODE", "TX_HASH"]);
+    table.row(vec![field(response, "code"), field(response, "tx_hash")]);
+    output::print(format, &table, response);
+}
+
+async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
+    let format = if cli.json { Format::Json } else { Format::Table };
+    let client = LighterClient::new(cli.base_url, &cli.private_key, cli.account_index, cli.api_key_index)?;
+
+    match cli.command {
+        Command::Whoami => {
+            let me = client.whoami().await?;
+            let (maker_fee, taker_fee) = me
+                .fees
+                .map(|fees| (format!("{}%", fees.maker_fee * 100.0), format!("{}%", fees.taker_fee * 100.0)))
+                .unwrap_or_default();
+            let mut table = Table::new(&["FIELD", "VALUE"]);
+            table.row(vec!["account_index".to_string(), me.account_index.to_string()]);
+            table.row(vec!["api_key_index".to_string(), me.api_key_index.to_string()]);
+            table.row(vec!["l1_address".to_string(), me.l1_address.clone().unwrap_or_default()]);
+            table.row(vec!["public_key".to_string(), me.local_public_key.to_string()]);
+            table.row(vec!["key_registered".to_string(), me.key_registered().to_string()]);
+            table.row(vec!["tier".to_string(), me.tier.to_string()]);
+            table.row(vec!["maker_fee".to_string(), maker_fee]);
+            table.row(vec!["taker_fee".to_string(), taker_fee]);
+            let json = json!({
+                "account_index": me.account_index,
+                "api_key_index": me.api_key_index,
+                "l1_address": me.l1_address,
+                "public_key": me.local_public_key.to_string(),
+                "registered_public_key": me.registered_public_key.map(|key| key.to_string()),
+                "key_registered": me.key_registered(),
+                "tier": me.tier.to_string(),
+                "maker_fee": me.fees.map(|fees| fees.maker_fee),
+                "taker_fee": me.fees.map(|fees| fees.taker_fee),
+            });
+            output::print(format, &table, &json);
+        }
+        Command::Account => {
+            let account_info = client.get_account().await.checked()?;
+            let account = account_entry(&account_info);
+            let mut table = Table::new(&["FIELD", "VALUE"]);
+            for name in ["index", "l1_address", "collateral", "available_balance", "total_asset_value"] {
+                table.row(vec![name.to_string(), field(account, name)]);
+            }
+            output::print(format, &table, account);
+        }
+        Command::Positions => {
+            let account_info = client.get_account().await.checked()?;
+            let positions: Vec<Value> = account_entry(&account_info)["positions"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter(|p| api_client::parse_decimal(&p["position"]).is_some_and(|size| !size.is_zero()))
+                .cloned()
+                .collect();
+            let mut table = Table::new(&["MARKET", "SYMBOL", "SIDE", "SIZE", "ENTRY", "VALUE", "UNREALIZED_PNL", "LIQUIDATION"]);
+            for p in &positions {
+                let side = if p["sign"].as_i64() == Some(-1) { "short" } else { "long" };
+                table.row(vec![
+                    field(p, "market_id"),
+                    field(p, "symbol"),
+                    side.to_string(),
+                    field(p, "position"),
+                    field(p, "avg_entry_price"),
+                    field(p, "position_value"),
+                    field(p, "unrealized_pnl"),
+                    field(p, "liquidation_price"),
+                ]);
+            }
+            output::print(format, &table, &json!(positions));
+        }
+        Command::Order(OrderCommand::Create(args)) => {
+            let details = client.get_market_details(args.market).await?;
+            let base_amount = to_units(&args.size, details.rules.size_decimals)?;
+            let price = to_units(&args.price, details.rules.price_decimals)?;
+            let is_ask = matches!(args.side, Side::Sell);
+            let client_order_index = args.client_order_index.unwrap_or_else(now_millis);
+            let response = match args.order_type {
+                CliOrderType::Market => {
+                    client
+                        .create_market_order(args.market, client_order_index, base_amount, price, is_ask)
+                        .await
+                }
+                CliOrderType::Limit => {
+                    client
+                        .create_order(CreateOrderRequest {
+                            account_index: client.account_index(),
+                            order_book_index: args.market,
+                            client_order_index,
+                            base_amount: BaseAmount(base_amount),
+                            price: Price::try_from(price)?,
+                            is_ask,
+                            order_type: OrderType::Limit,
+                            time_in_force: match args.tif {
+                                Tif::Gtt => TimeInForce::GoodTillTime,
+                                Tif::Ioc => TimeInForce::ImmediateOrCancel,
+                                Tif::PostOnly => TimeInForce::PostOnly,
+                            },
+                            reduce_only: args.reduce_only,
+                            trigger_price: Price::ZERO,
+                            price_protection: None,
+                            order_expiry: None,
+                        })
+                        .await
+                }
+            };
+            print_tx(format, &response.checked()?);
+        }
+        Command::Order(OrderCommand::Cancel { market, order_index }) => {
+            print_tx(format, &client.cancel_order(market, order_index).await.checked()?);
+        }
+        Command::Order(OrderCommand::List { market }) => {
+            let orders = client.get_open_orders(market).await?;
+            let mut table = Table::new(&["MARKET", "ORDER_INDEX", "CLIENT_INDEX", "SIDE", "TYPE", "PRICE", "REMAINING", "FILLED", "STATUS"]);
+            for order in &orders {
+                table.row(vec![
+                    order.market_index.to_string(),
+                    order.order_index.to_string(),
+                    order.client_order_index.to_string(),
+                    if order.is_ask { "sell" } else { "buy" }.to_string(),
+                    order.order_type.clone(),
+                    order.price.to_string(),
+                    order.remaining_base_amount.to_string(),
+                    order.filled_base_amount.to_string(),
+                    format!("{:?}", order.status),
+                ]);
+            }
+            output::print(format, &table, &serde_json::to_value(&orders)?);
+        }
+        Command::CancelAll => {
+            print_tx(format, &client.cancel_all_orders(CANCEL_ALL_TIF_IMMEDIATE, 0).await.checked()?);
+        }
+        Command::Transfer { to, amount, fee } => {
+            let to = AddressBook::load(&cli.address_book)?.lookup(&to)?;
+            let usdc_amount = to_usdc(&amount)?;
+            let fee = to_usdc(&fee)?;
+            print_tx(format, &client.transfer_to(&to, usdc_amount, fee, [0u8; 32]).await.checked()?);
+        }
+        Command::Withdraw { amount } => {
+            let usdc_amount = to_usdc(&amount)?;
+            print_tx(format, &client.withdraw(WithdrawRequest { usdc_amount }).await.checked()?);
+        }
+        Command::Leverage { market, leverage, isolated } => {
+            let margin_mode = if isolated { MarginMode::Isolated } else { MarginMode::Cross };
+            let update = client.update_leverage(market, leverage, margin_mode).await?;
+            let response = Ok(update.response).checked()?;
+            let mut table = Table::new(&["MARKET", "LEVERAGE", "EFFECTIVE", "MARGIN_MODE", "TX_HASH"]);
+            table.row(vec![
+                market.to_string(),
+                leverage.to_string(),
+                format!("{:.3}", update.effective_leverage),
+                margin_mode.to_string(),
+                field(&response, "tx_hash"),
+            ]);
+            output::print(format, &table, &response);
+        }
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() {
+    dotenv::dotenv().ok();
+    if let Err(e) = run(Cli::parse()).await {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_units() {
+        assert_eq!(to_units("0.1", 4), Ok(1000));
+        assert_eq!(to_units("3012.50", 2), Ok(301_250));
+        assert_eq!(to_units("25", UsdcAmount::DECIMALS), Ok(25_000_000));
+        assert!(to_units("0.00001", 4).is_err());
+        assert!(to_units("abc", 2).is_err());
+        assert_eq!(to_usdc("25.5"), Ok(UsdcAmount(25_500_000)));
+        assert!(to_usdc("-1").is_err());
+
+        let cli = Cli::try_parse_from([
+            "lighter-cli", "--private-key", "00", "--account-index", "1", "--api-key-index", "3",
+            "order", "create", "--market", "0", "--side", "sell", "--size", "0.1", "--price", "3000", "--type", "market",
+        ])
+        .unwrap();
+        assert!(matches!(cli.command, Command::Order(OrderCommand::Create(CreateArgs { order_type: CliOrderType::Market, .. }))));
+    }
+}
@@ -0,0 +1,88 @@
+//! Table and JSON output.
+
+use serde_json::Value;
+
+/// How results are printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Table,
+    Json,
+}
+
+/// Rows of text printed with aligned columns.
+#[derive(Debug, Default)]
+pub struct Table {
+    headers: Vec<&'static str>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new(headers: &[&'static str]) -> Self {
+        Self { headers: headers.to_vec(), rows: Vec::new() }
+    }
+
+    pub fn row(&mut self, cells: Vec<String>) {
+        self.rows.push(cells);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Columns padded to their widest cell, separated by two spaces.
+    pub fn render(&self) -> String {
+        let mut widths: Vec<usize> = self.headers.iter().map(|h| h.len()).collect();
+        for row in &self.rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        let line = |cells: Vec<&str>| {
+            let padded: Vec<String> = cells
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect();
+            padded.join("  ").trim_end().to_string()
+        };
+        let mut lines = vec![line(self.headers.clone())];
+        lines.extend(self.rows.iter().map(|row| line(row.iter().map(String::as_str).collect())));
+        lines.join("\n")
+    }
+}
+
+/// Prints `table`, or `json` in JSON mode.
+pub fn print(format: Format, table: &Table, json: &Value) {
+    match format {
+        Format::Json => println!("{}", serde_json::to_string_pretty(json).unwrap_or_default()),
+        Format::Table if table.is_empty() => println!("(none)"),
+        Format::Table => println!("{}", table.render()),
+    }
+}
+
+/// Text of a JSON field: strings unquoted, missing fields as `-`.
+pub fn field(value: &Value, name: &str) -> String {
+    match &value[name] {
+        Value::Null => "-".to_string(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_table_render() {
+        let mut table = Table::new(&["MARKET", "SIDE", "SIZE"]);
+        table.row(vec!["0".to_string(), "long".to_string(), "1.5000".to_string()]);
+        table.row(vec!["12".to_string(), "short".to_string(), "0.01".to_string()]);
+        assert_eq!(table.render(), "MARKET  SIDE   SIZE\n0       long   1.5000\n12      short  0.01");
+
+        let value = json!({"symbol": "ETH", "market_id": 0});
+        assert_eq!((field(&value, "symbol"), field(&value, "market_id"), field(&value, "sign")), ("ETH".into(), "0".into(), "-".into()));
+    }
+}
@@ -0,0 +1,425 @@
+//! Unreliable network between the client and a server.
+//!
+//! `FaultyTransport` is an HTTP proxy in front of a base URL, usually a `MockExchange`. The
+//! client is pointed at `FaultyTransport::url()` instead of the server's, and every request
+//! passing through may be delayed, dropped before it reaches the server, dropped after the
+//! server handled it, delivered twice, or answered with a corrupted body, each at the rate
+//! set in `ChaosConfig`. Dropped requests and responses close the connection without an
+//! answer, so the client sees them as transport errors.
+//!
+//! The faults are drawn from an RNG seeded in `FaultyTransport::start`: requests sent one
+//! after the other meet the same faults for the same seed, so a failing seed replays. (Which
+//! concurrent request draws first is up to the scheduler.)
+//!
+//! `duplicate_orders` checks the transactions a `MockExchange` accepted for orders placed
+//! more than once, the failure the nonce and retry handling must rule out.
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! use mock_exchange::{duplicate_orders, ChaosConfig, FaultyTransport, MockExchange};
+//!
+//! let exchange = MockExchange::start().await?;
+//! let config = ChaosConfig {
+//!     drop_response: 0.1,
+//!     duplicate: 0.1,
+//!     ..ChaosConfig::default()
+//! };
+//! let transport = FaultyTransport::start(exchange.url(), config, 7).await?;
+//!
+//! // Point a LighterClient at transport.url() and place orders, then
+//! assert!(duplicate_orders(&exchange.sent_txs()).is_empty());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{Endpoint, SentTx};
+use hyper::client::HttpConnector;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Client, Request, Response, Server, StatusCode};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashSet;
+use std::convert::Infallible;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+const TX_TYPE_CREATE_ORDER: u32 = 14;
+const TX_TYPE_CREATE_GROUPED_ORDERS: u32 = 28;
+
+/// Fault rates of a `FaultyTransport`, each the probability that a request meets it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChaosConfig {
+    /// Delay added before a request is forwarded, drawn uniformly from
+    /// `min_latency..=max_latency`
+    pub min_latency: Duration,
+    pub max_latency: Duration,
+    /// Close the connection without forwarding the request
+    pub drop_request: f64,
+    /// Forward the request, then close the connection without the response
+    pub drop_response: f64,
+    /// Forward the request twice and answer with the response to the second copy, as a
+    /// proxy retrying upstream would
+    pub duplicate: f64,
+    /// Answer with the response body cut short at a random length
+    pub corrupt: f64,
+    /// Endpoints the faults apply to, `None` for all; other requests pass through untouched
+    pub endpoints: Option<Vec<Endpoint>>,
+}
+
+impl Default for ChaosConfig {
+    /// No faults: every request is forwarded as is.
+    fn default() -> Self {
+        Self {
+            min_latency: Duration::ZERO,
+            max_latency: Duration::ZERO,
+            drop_request: 0.0,
+            drop_response: 0.0,
+            duplicate: 0.0,
+            corrupt: 0.0,
+            endpoints: None,
+        }
+    }
+}
+
+/// Requests a `FaultyTransport` handled and the faults they met.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChaosStats {
+    pub requests: u64,
+    pub delayed: u64,
+    pub dropped_requests: u64,
+    pub dropped_responses: u64,
+    pub duplicated: u64,
+    pub corrupted: u64,
+}
+
+/// Faults drawn for one request.
+#[derive(Debug, Default)]
+struct Plan {
+    latency: Duration,
+    drop_request: bool,
+    drop_response: bool,
+    duplicate: bool,
+    /// Fraction of the body kept
+    corrupt: Option<f64>,
+}
+
+struct Inner {
+    upstream: String,
+    client: Client<HttpConnector>,
+    config: Mutex<ChaosConfig>,
+    rng: Mutex<StdRng>,
+    stats: Mutex<ChaosStats>,
+}
+
+/// A running fault-injecting proxy; shut down when dropped.
+pub struct FaultyTransport {
+    addr: SocketAddr,
+    seed: u64,
+    inner: Arc<Inner>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl FaultyTransport {
+    /// Start proxying to `upstream` (a base URL such as `MockExchange::url()`) on an
+    /// ephemeral localhost port, drawing faults from an RNG seeded with `seed`
+    pub async fn start(upstream: impl Into<String>, config: ChaosConfig, seed: u64) -> hyper::Result<Self> {
+        let inner = Arc::new(Inner {
+            upstream: upstream.into().trim_end_matches('/').to_string(),
+            client: Client::new(),
+            config: Mutex::new(config),
+            rng: Mutex::new(StdRng::seed_from_u64(seed)),
+            stats: Mutex::new(ChaosStats::default()),
+        });
+        let service_inner = inner.clone();
+        let make_service = make_service_fn(move |_| {
+            let inner = service_inner.clone();
+            async move { Ok::<_, Infallible>(service_fn(move |request| proxy(inner.clone(), request))) }
+        });
+
+        let server = Server::try_bind(&SocketAddr::from(([127, 0, 0, 1], 0)))?.serve(make_service);
+        let addr = server.local_addr();
+        let (shutdown, shutdown_rx) = oneshot::channel();
+        tokio::spawn(server.with_graceful_shutdown(async {
+            shutdown_rx.await.ok();
+        }));
+
+        Ok(Self {
+            addr,
+            seed,
+            inner,
+            shutdown: Some(shutdown),
+        })
+    }
+
+    /// Base URL to give to `LighterClient`
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Seed the faults are drawn with, to replay a failing run
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Change the fault rates of the requests that follow
+    pub fn set_config(&self, config: ChaosConfig) {
+        *lock(&self.inner.config) = config;
+    }
+
+    /// Requests handled so far and the faults they met
+    pub fn stats(&self) -> ChaosStats {
+        *lock(&self.inner.stats)
+    }
+}
+
+impl Drop for FaultyTransport {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            shutdown.send(()).ok();
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+impl Inner {
+    fn plan(&self, endpoint: Option<Endpoint>) -> Plan {
+        let config = lock(&self.config).clone();
+        let affected = match (&config.endpoints, endpoint) {
+            (None, _) => true,
+            (Some(endpoints), Some(endpoint)) => endpoints.contains(&endpoint),
+            (Some(_), None) => false,
+        };
+        if !affected {
+            return Plan::default();
+        }
+
+        let mut rng = lock(&self.rng);
+        let latency = if config.max_latency > config.min_latency {
+            rng.gen_range(config.min_latency..=config.max_latency)
+        } else {
+            config.min_latency
+        };
+        let mut happens = |rate: f64| rate > 0.0 && rng.gen_bool(rate.min(1.0));
+        let plan = Plan {
+            latency,
+            drop_request: happens(config.drop_request),
+            drop_response: happens(config.drop_response),
+            duplicate: happens(config.duplicate),
+            corrupt: happens(config.corrupt).then(|| rng.gen_range(0.0..1.0)),
+        };
+
+        let mut stats = lock(&self.stats);
+        stats.requests += 1;
+        stats.delayed += u64::from(!plan.latency.is_zero());
+        if plan.drop_request {
+            stats.dropped_requests += 1;
+        } else if plan.drop_response {
+            stats.dropped_responses += 1;
+        } else {
+            stats.duplicated += u64::from(plan.duplicate);
+            stats.corrupted += u64::from(plan.corrupt.is_some());
+        }
+        plan
+    }
+
+    /// Sends a copy of the request to the upstream server
+    async fn forward(&self, parts: &hyper::http::request::Parts, body: &hyper::body::Bytes) -> Result<Response<Body>, hyper::Error> {
+        let uri = format!(
+            "{}{}",
+            self.upstream,
+            parts.uri.path_and_query().map(|path| path.as_str()).unwrap_or("/")
+        );
+        let mut request = Request::builder().method(parts.method.clone()).uri(uri);
+        for (name, value) in &parts.headers {
+            if name != hyper::header::HOST {
+                request = request.header(name, value);
+            }
+        }
+        let request = request.body(Body::from(body.clone())).expect("request built from a valid one");
+        self.client.request(request).await
+    }
+}
+
+fn connection_lost(what: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::ConnectionReset, format!("{} dropped by FaultyTransport", what))
+}
+
+async fn proxy(inner: Arc<Inner>, request: Request<Body>) -> Result<Response<Body>, io::Error> {
+    let plan = inner.plan(Endpoint::from_path(request.uri().path()));
+    if !plan.latency.is_zero() {
+        tokio::time::sleep(plan.latency).await;
+    }
+    // Returning an error makes hyper close the connection without a response
+    if plan.drop_request {
+        return Err(connection_lost("request"));
+    }
+
+    let (parts, body) = request.into_parts();
+    let body = match hyper::body::to_bytes(body).await {
+        Ok(body) => body,
+        Err(e) => return Ok(http_error(StatusCode::BAD_REQUEST, &e.to_string())),
+    };
+    let mut response = match inner.forward(&parts, &body).await {
+        Ok(response) => response,
+        Err(e) => return Ok(http_error(StatusCode::BAD_GATEWAY, &e.to_string())),
+    };
+    if plan.duplicate {
+        match inner.forward(&parts, &body).await {
+            Ok(second) => response = second,
+            Err(e) => return Ok(http_error(StatusCode::BAD_GATEWAY, &e.to_string())),
+        }
+    }
+    if plan.drop_response {
+        return Err(connection_lost("response"));
+    }
+
+    let Some(kept) = plan.corrupt else {
+        return Ok(response);
+    };
+    let (mut parts, body) = response.into_parts();
+    let body = match hyper::body::to_bytes(body).await {
+        Ok(body) => body,
+        Err(e) => return Ok(http_error(StatusCode::BAD_GATEWAY, &e.to_string())),
+    };
+    let cut = ((body.len() as f64 * kept) as usize).min(body.len().saturating_sub(1));
+    parts.headers.remove(hyper::header::CONTENT_LENGTH);
+    Ok(Response::from_parts(parts, Body::from(body.slice(..cut))))
+}
+
+/// Orders among `sent` whose client order index was already used by an earlier accepted
+/// order of the same account and market, i.e. orders placed more than once
+///
+/// Covers single orders and the orders of grouped transactions.
+pub fn duplicate_orders(sent: &[SentTx]) -> Vec<&SentTx> {
+    let mut seen = HashSet::new();
+    let mut duplicates = Vec::new();
+    for tx in sent {
+        let orders = match tx.tx_type {
+            TX_TYPE_CREATE_ORDER => vec![&tx.tx_info],
+            TX_TYPE_CREATE_GROUPED_ORDERS => tx.tx_info["Orders"].as_array().into_iter().flatten().collect(),
+            _ => continue,
+        };
+        let mut duplicate = false;
+        for order in orders {
+            let key = (tx.account_index, order["MarketIndex"].as_i64(), order["ClientOrderIndex"].as_i64());
+            duplicate |= !seen.insert(key);
+        }
+        if duplicate {
+            duplicates.push(tx);
+        }
+    }
+    duplicates
+}
+
+fn http_error(status: StatusCode, body: &str) -> Response<Body> {
+    let mut response = Response::new(Body::from(body.to_string()));
+    *response.status_mut() = status;
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockExchange;
+    use serde_json::{json, Value};
+
+    /// Status and body of each of `count` requests sent one after the other, `None` for
+    /// the ones that lost their connection
+    async fn outcomes(transport: &FaultyTransport, count: usize) -> Vec<Option<(u16, Vec<u8>)>> {
+        let client = Client::builder().pool_max_idle_per_host(0).build_http::<Body>();
+        let uri: hyper::Uri = format!("{}/api/v1/nextNonce?account_index=1&api_key_index=3", transport.url())
+            .parse()
+            .unwrap();
+        let mut outcomes = Vec::new();
+        for _ in 0..count {
+            let outcome = match client.get(uri.clone()).await {
+                Ok(response) => {
+                    let status = response.status().as_u16();
+                    hyper::body::to_bytes(response.into_body()).await.ok().map(|body| (status, body.to_vec()))
+                }
+                Err(_) => None,
+            };
+            outcomes.push(outcome);
+        }
+        outcomes
+    }
+
+    #[tokio::test]
+    async fn test_same_seed_replays_the_same_faults() {
+        let exchange = MockExchange::start().await.unwrap();
+        let config = ChaosConfig {
+            max_latency: Duration::from_millis(2),
+            drop_request: 0.2,
+            drop_response: 0.2,
+            duplicate: 0.2,
+            corrupt: 0.2,
+            ..ChaosConfig::default()
+        };
+        let first = FaultyTransport::start(exchange.url(), config.clone(), 7).await.unwrap();
+        let second = FaultyTransport::start(exchange.url(), config.clone(), 7).await.unwrap();
+        let other = FaultyTransport::start(exchange.url(), config, 8).await.unwrap();
+        assert_eq!(first.seed(), 7);
+
+        let replayed = outcomes(&first, 40).await;
+        assert_eq!(outcomes(&second, 40).await, replayed);
+        assert_eq!(first.stats(), second.stats());
+        let stats = first.stats();
+        assert_eq!(stats.requests, 40);
+        assert!(stats.dropped_requests > 0 && stats.dropped_responses > 0 && stats.corrupted > 0, "{:?}", stats);
+        assert_eq!(replayed.iter().filter(|outcome| outcome.is_none()).count() as u64, stats.dropped_requests + stats.dropped_responses);
+
+        assert_ne!(outcomes(&other, 40).await, replayed);
+        assert_ne!(other.stats(), stats);
+    }
+
+    #[tokio::test]
+    async fn test_unlisted_endpoints_pass_through() {
+        let exchange = MockExchange::start().await.unwrap();
+        let config = ChaosConfig {
+            drop_request: 1.0,
+            endpoints: Some(vec![Endpoint::SendTx]),
+            ..ChaosConfig::default()
+        };
+        let transport = FaultyTransport::start(exchange.url(), config, 7).await.unwrap();
+        assert!(outcomes(&transport, 5).await.iter().all(|outcome| matches!(outcome, Some((200, _)))));
+        assert_eq!(transport.stats(), ChaosStats::default());
+    }
+
+    fn sent(tx_type: u32, account_index: i64, nonce: i64, tx_info: Value) -> SentTx {
+        SentTx {
+            tx_type,
+            tx_info,
+            account_index,
+            api_key_index: 3,
+            nonce,
+            price_protection: None,
+            tx_hash: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_duplicate_orders() {
+        let order = |market: i64, client_order_index: i64| json!({"MarketIndex": market, "ClientOrderIndex": client_order_index});
+        let txs = vec![
+            sent(TX_TYPE_CREATE_ORDER, 1, 0, order(0, 1)),
+            sent(TX_TYPE_CREATE_ORDER, 1, 1, order(1, 1)),
+            sent(TX_TYPE_CREATE_ORDER, 2, 0, order(0, 1)),
+            // Cancels carry no client order index of a new order
+            sent(15, 1, 2, json!({"MarketIndex": 0, "Index": 1})),
+            sent(TX_TYPE_CREATE_GROUPED_ORDERS, 1, 3, json!({"Orders": [order(0, 2), order(0, 3)]})),
+            sent(TX_TYPE_CREATE_ORDER, 1, 4, order(0, 1)),
+            sent(TX_TYPE_CREATE_GROUPED_ORDERS, 1, 5, json!({"Orders": [order(0, 4), order(0, 3)]})),
+            sent(TX_TYPE_CREATE_ORDER, 1, 6, order(0, 5)),
+            sent(TX_TYPE_CREATE_ORDER, 1, 7, order(0, 1)),
+        ];
+        assert!(duplicate_orders(&txs[..5]).is_empty());
+        let duplicates: Vec<i64> = duplicate_orders(&txs).iter().map(|tx| tx.nonce).collect();
+        assert_eq!(duplicates, vec![4, 5, 7]);
+    }
+}
@@ -0,0 +1,1170 @@
+//! In-memory Lighter API for integration tests.
+//!
+//! `MockExchange` is an axum server for the endpoints the client needs to sign and submit transactions:
+//! `sendTx`, `sendTxBatch`, `nextNonce`, `account`, `apiKey`, `accountActiveOrders` and `tx`, plus the status root. It tracks the next nonce
+//! of every API key the way the exchange does, rejecting a transaction whose nonce is not
+//! the expected one, and records every accepted transaction. An accepted ChangePubKey
+//! registers its key with `apiKey` right away. Signatures are not verified.
+//!
+//! Failures are injected per endpoint with `MockExchange::inject`: each request consumes
+//! the oldest queued `Fault`, so queuing two `Fault::InvalidSignature` makes the next two
+//! submissions fail with 21120 and the third succeed.
+//!
+//! Network failures between the client and the server (latency, lost requests and
+//! responses, duplicated deliveries, corrupted bodies) are injected at random by a
+//! `FaultyTransport` placed in front of the mock, see `faulty`.
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! use mock_exchange::{Endpoint, Fault, MockExchange};
+//!
+//! let exchange = MockExchange::start().await?;
+//! exchange.set_nonce(1, 3, 100);
+//! exchange.inject(Endpoint::SendTx, Fault::InvalidSignature);
+//!
+//! // Point a LighterClient at exchange.url() and exercise the retry path,
+//! // then inspect what was accepted
+//! assert!(exchange.sent_txs().iter().all(|tx| tx.nonce == 100));
+//! # Ok(())
+//! # }
+//! ```
+
+mod faulty;
+
+pub use faulty::{duplicate_orders, ChaosConfig, ChaosStats, FaultyTransport};
+
+use axum::body::Bytes;
+use axum::extract::{Query, State as Shared};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post, MethodRouter};
+use axum::{Json, Router, Server};
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+/// Response code of a transaction with an invalid signature.
+pub const CODE_INVALID_SIGNATURE: i64 = 21120;
+/// Response code of a transaction whose nonce is not the next one of its API key.
+pub const CODE_INVALID_NONCE: i64 = 21104;
+/// Response code of a lookup of an unknown account or API key.
+pub const CODE_NOT_FOUND: i64 = 21100;
+
+const TX_TYPE_CHANGE_PUB_KEY: u32 = 8;
+
+/// An endpoint served by the mock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Endpoint {
+    SendTx,
+    SendTxBatch,
+    NextNonce,
+    Account,
+    ApiKey,
+    /// Open orders of an account on one market
+    ActiveOrders,
+    /// Lookup of an accepted transaction by hash
+    Tx,
+    /// The API root, reporting exchange status
+    Status,
+}
+
+impl Endpoint {
+    const ALL: [Endpoint; 8] = [
+        Endpoint::SendTx,
+        Endpoint::SendTxBatch,
+        Endpoint::NextNonce,
+        Endpoint::Account,
+        Endpoint::ApiKey,
+        Endpoint::ActiveOrders,
+        Endpoint::Tx,
+        Endpoint::Status,
+    ];
+
+    fn from_path(path: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|endpoint| endpoint.path() == path)
+    }
+
+    fn path(self) -> &'static str {
+        match self {
+            Endpoint::SendTx => "/api/v1/sendTx",
+            Endpoint::SendTxBatch => "/api/v1/sendTxBatch",
+            Endpoint::NextNonce => "/api/v1/nextNonce",
+            Endpoint::Account => "/api/v1/account",
+            Endpoint::ApiKey => "/api/v1/apiKey",
+            Endpoint::ActiveOrders => "/api/v1/accountActiveOrders",
+            Endpoint::Tx => "/api/v1/tx",
+            Endpoint::Status => "/",
+        }
+    }
+
+    /// Route serving the endpoint with its HTTP method
+    fn route(self) -> MethodRouter<Arc<Mutex<State>>> {
+        let handler = move |Shared(state): Shared<Arc<Mutex<State>>>,
+                            Query(query): Query<HashMap<String, String>>,
+                            body: Bytes| handle(state, self, query, body);
+        match self {
+            Endpoint::SendTx | Endpoint::SendTxBatch => post(handler),
+            _ => get(handler),
+        }
+    }
+}
+
+/// A failure served instead of the normal response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fault {
+    /// Reject with code 21120, as the exchange does for a signature it cannot verify
+    InvalidSignature,
+    /// Reject with code 21104 and consume the nonce, as if another sender had used it first
+    NonceConflict,
+    /// Wait before handling the request normally; longer than the client timeout to
+    /// simulate a timeout
+    Delay(Duration),
+    /// Respond with an HTTP error status and a plain-text body
+    Http(u16, String),
+    /// Reject with code 429 and a `Retry-After` header of the given whole seconds
+    RateLimited(Duration),
+    /// Handle the request normally, then respond 502 as a proxy that lost the response would
+    LostResponse,
+}
+
+/// A transaction accepted by the mock.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SentTx {
+    pub tx_type: u32,
+    pub tx_info: Value,
+    pub account_index: i64,
+    pub api_key_index: u8,
+    pub nonce: i64,
+    /// `price_protection` form field, absent for batches
+    pub price_protection: Option<String>,
+    /// Hash returned to the client
+    pub tx_hash: String,
+}
+
+/// Hash given to an accepted transaction, from its type and `tx_info`.
+pub type TxHasher = Arc<dyn Fn(u32, &Value) -> String + Send + Sync>;
+
+#[derive(Default)]
+struct State {
+    nonces: HashMap<(i64, u8), i64>,
+    api_keys: HashMap<(i64, u8), String>,
+    accounts: HashMap<i64, Value>,
+    active_orders: HashMap<(i64, u8), Vec<Value>>,
+    faults: HashMap<Endpoint, VecDeque<Fault>>,
+    sent: Vec<SentTx>,
+    maintenance: bool,
+    hasher: Option<TxHasher>,
+}
+
+/// A running mock server; shut down when dropped.
+pub struct MockExchange {
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl MockExchange {
+    /// Start serving on an ephemeral localhost port
+    pub async fn start() -> hyper::Result<Self> {
+        let state = Arc::new(Mutex::new(State::default()));
+        let router = Endpoint::ALL
+            .into_iter()
+            .fold(Router::new(), |router, endpoint| router.route(endpoint.path(), endpoint.route()))
+            .with_state(state.clone());
+
+        let server = Server::try_bind(&SocketAddr::from(([127, 0, 0, 1], 0)))?.serve(router.into_make_service());
+        let addr = server.local_addr();
+        let (shutdown, shutdown_rx) = oneshot::channel();
+        tokio::spawn(server.with_graceful_shutdown(async {
+            shutdown_rx.await.ok();
+        }));
+
+        Ok(Self {
+            addr,
+            state,
+            shutdown: Some(shutdown),
+        })
+    }
+
+    /// Base URL to give to `LighterClient`
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Set the next nonce of an API key (0 until set)
+    pub fn set_nonce(&self, account_index: i64, api_key_index: u8, nonce: i64) {
+        self.lock().nonces.insert((account_index, api_key_index), nonce);
+    }
+
+    /// The next nonce the mock expects from an API key
+    pub fn next_nonce(&self, account_index: i64, api_key_index: u8) -> i64 {
+        self.lock().nonces.get(&(account_index, api_key_index)).copied().unwrap_or(0)
+    }
+
+    /// Register the public key (hex) returned by `apiKey` for an API key
+    pub fn set_api_key(&self, account_index: i64, api_key_index: u8, public_key_hex: impl Into<String>) {
+        self.lock()
+            .api_keys
+            .insert((account_index, api_key_index), public_key_hex.into());
+    }
+
+    /// Set the account object returned by `account`, e.g. with `positions`
+    pub fn set_account(&self, account_index: i64, account: Value) {
+        self.lock().accounts.insert(account_index, account);
+    }
+
+    /// Set the orders returned by `accountActiveOrders` for a market (none until set)
+    pub fn set_active_orders(&self, account_index: i64, market_index: u8, orders: Vec<Value>) {
+        self.lock().active_orders.insert((account_index, market_index), orders);
+    }
+
+    /// Report maintenance in the status response
+    pub fn set_maintenance(&self, maintenance: bool) {
+        self.lock().maintenance = maintenance;
+    }
+
+    /// Hash accepted transactions with `hasher` instead of numbering them, e.g. with the
+    /// client's `compute_tx_hash` so that lookups by a signed hash find them
+    pub fn set_tx_hasher(&self, hasher: TxHasher) {
+        self.lock().hasher = Some(hasher);
+    }
+
+    /// Queue a fault for the next request to `endpoint`
+    pub fn inject(&self, endpoint: Endpoint, fault: Fault) {
+        self.lock().faults.entry(endpoint).or_default().push_back(fault);
+    }
+
+    /// Drop all queued faults
+    pub fn clear_faults(&self) {
+        self.lock().faults.clear();
+    }
+
+    /// Transactions accepted so far, in order
+    pub fn sent_txs(&self) -> Vec<SentTx> {
+        self.lock().sent.clone()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        lock(&self.state)
+    }
+}
+
+impl Drop for MockExchange {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            shutdown.send(()).ok();
+        }
+    }
+}
+
+fn lock(state: &Mutex<State>) -> MutexGuard<'_, State> {
+    state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+async fn handle(state: Arc<Mutex<State>>, endpoint: Endpoint, query: HashMap<String, String>, body: Bytes) -> Response {
+    let fault = lock(&state).faults.get_mut(&endpoint).and_then(VecDeque::pop_front);
+    match &fault {
+        Some(Fault::Delay(delay)) => tokio::time::sleep(*delay).await,
+        Some(Fault::Http(status, body)) => {
+            let status = StatusCode::from_u16(*status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+            return http_error(status, body);
+        }
+        Some(Fault::RateLimited(retry_after)) => {
+            let retry_after = [(header::RETRY_AFTER, retry_after.as_secs().to_string())];
+            return (StatusCode::TOO_MANY_REQUESTS, retry_after, Json(reject(429, "too many requests"))).into_response();
+        }
+        _ => {}
+    }
+
+    let body = match endpoint {
+        Endpoint::SendTx | Endpoint::SendTxBatch => {
+            let form = params(&String::from_utf8_lossy(&body));
+            let mut state = lock(&state);
+            match (endpoint, &fault) {
+                (_, Some(Fault::InvalidSignature)) => reject(CODE_INVALID_SIGNATURE, "invalid signature"),
+                (Endpoint::SendTx, fault) => send_tx(&mut state, &form, fault == &Some(Fault::NonceConflict)),
+                (_, fault) => send_tx_batch(&mut state, &form, fault == &Some(Fault::NonceConflict)),
+            }
+        }
+        Endpoint::NextNonce => match key_params(&query) {
+            Some(key) => json!({"code": 200, "nonce": lock(&state).nonces.get(&key).copied().unwrap_or(0)}),
+            None => reject(400, "account_index and api_key_index are required"),
+        },
+        Endpoint::Account => {
+            let account_index = query.get("value").and_then(|v| v.parse::<i64>().ok());
+            match account_index.and_then(|index| lock(&state).accounts.get(&index).cloned()) {
+                Some(account) => json!({"code": 200, "total": 1, "accounts": [account]}),
+                None => reject(CODE_NOT_FOUND, "account not found"),
+            }
+        }
+        Endpoint::ApiKey => match key_params(&query).and_then(|key| lock(&state).api_keys.get(&key).cloned()) {
+            Some(public_key) => json!({"code": 200, "public_key": public_key}),
+            None => reject(CODE_NOT_FOUND, "api key not found"),
+        },
+        Endpoint::ActiveOrders => {
+            let account_index = query.get("account_index").and_then(|v| v.parse::<i64>().ok());
+            let market_index = query.get("market_id").and_then(|v| v.parse::<u8>().ok());
+            match account_index.zip(market_index) {
+                Some(key) => json!({"code": 200, "orders": lock(&state).active_orders.get(&key).cloned().unwrap_or_default()}),
+                None => reject(400, "account_index and market_id are required"),
+            }
+        }
+        Endpoint::Tx => {
+            let hash = query.get("value").filter(|_| query.get("by").map(String::as_str) == Some("hash"));
+            let state = lock(&state);
+            match hash.and_then(|hash| state.sent.iter().find(|tx| &tx.tx_hash == hash)) {
+                Some(tx) => json!({"code": 200, "hash": tx.tx_hash, "type": tx.tx_type, "info": tx.tx_info.to_string(),
+                                   "nonce": tx.nonce, "account_index": tx.account_index}),
+                None => reject(CODE_NOT_FOUND, "transaction not found"),
+            }
+        }
+        Endpoint::Status => {
+            let timestamp = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default();
+            json!({"status": 200, "network_id": 300, "timestamp": timestamp, "maintenance": lock(&state).maintenance})
+        }
+    };
+
+    if fault == Some(Fault::LostResponse) {
+        return http_error(StatusCode::BAD_GATEWAY, "upstream connection lost");
+    }
+    Json(body).into_response()
+}
+
+fn send_tx(state: &mut State, form: &HashMap<String, String>, conflict: bool) -> Value {
+    let tx = form
+        .get("tx_type")
+        .zip(form.get("tx_info"))
+        .and_then(|(tx_type, tx_info)| parse_tx(tx_type.parse().ok()?, tx_info));
+    let Some(mut tx) = tx else {
+        return reject(400, "tx_type and tx_info are required");
+    };
+    tx.price_protection = form.get("price_protection").cloned();
+
+    if let Err(response) = check_nonce(state, &tx, conflict) {
+        return response;
+    }
+    let tx_hash = accept(state, tx);
+    json!({"code": 200, "tx_hash": tx_hash})
+}
+
+/// Applies the batch only if every nonce is valid, like the exchange does
+fn send_tx_batch(state: &mut State, form: &HashMap<String, String>, conflict: bool) -> Value {
+    let types: Option<Vec<u32>> = form.get("tx_types").and_then(|v| serde_json::from_str(v).ok());
+    let infos: Option<Vec<String>> = form.get("tx_infos").and_then(|v| serde_json::from_str(v).ok());
+    let txs: Option<Vec<SentTx>> = types.zip(infos).and_then(|(types, infos)| {
+        if types.len() != infos.len() {
+            return None;
+        }
+        types.into_iter().zip(&infos).map(|(tx_type, info)| parse_tx(tx_type, info)).collect()
+    });
+    let Some(txs) = txs else {
+        return reject(400, "tx_types and tx_infos must be JSON arrays of equal length");
+    };
+
+    let nonces = state.nonces.clone();
+    for (i, tx) in txs.iter().enumerate() {
+        if let Err(response) = check_nonce(state, tx, conflict && i == 0) {
+            state.nonces = nonces;
+            return response;
+        }
+        *state.nonces.entry((tx.account_index, tx.api_key_index)).or_default() += 1;
+    }
+    state.nonces = nonces;
+
+    let hashes: Vec<String> = txs.into_iter().map(|tx| accept(state, tx)).collect();
+    json!({"code": 200, "tx_hash": hashes})
+}
+
+fn parse_tx(tx_type: u32, tx_info: &str) -> Option<SentTx> {
+    let tx_info: Value = serde_json::from_str(tx_info).ok()?;
+    // Transfers and withdrawals name the sender FromAccountIndex
+    let account_index = tx_info["AccountIndex"]
+        .as_i64()
+        .or_else(|| tx_info["FromAccountIndex"].as_i64())?;
+    let api_key_index = u8::try_from(tx_info["ApiKeyIndex"].as_u64()?).ok()?;
+    let nonce = tx_info["Nonce"].as_i64()?;
+    Some(SentTx {
+        tx_type,
+        tx_info,
+        account_index,
+        api_key_index,
+        nonce,
+        price_protection: None,
+        tx_hash: String::new(),
+    })
+}
+
+fn check_nonce(state: &mut State, tx: &SentTx, conflict: bool) -> Result<(), Value> {
+    let next = state.nonces.entry((tx.account_index, tx.api_key_index)).or_default();
+    if conflict {
+        // Someone else took the nonce the client was about to use
+        *next += 1;
+    }
+    if tx.nonce != *next {
+        return Err(reject(
+            CODE_INVALID_NONCE,
+            &format!("invalid nonce: expected {}, got {}", next, tx.nonce),
+        ));
+    }
+    Ok(())
+}
+
+fn accept(state: &mut State, mut tx: SentTx) -> String {
+    *state.nonces.entry((tx.account_index, tx.api_key_index)).or_default() += 1;
+    if let (TX_TYPE_CHANGE_PUB_KEY, Some(public_key)) = (tx.tx_type, tx.tx_info["PubKey"].as_str()) {
+        state.api_keys.insert((tx.account_index, tx.api_key_index), public_key.to_string());
+    }
+    tx.tx_hash = match &state.hasher {
+        Some(hasher) => hasher(tx.tx_type, &tx.tx_info),
+        None => format!("{:080x}", state.sent.len() + 1),
+    };
+    let tx_hash = tx.tx_hash.clone();
+    state.sent.push(tx);
+    tx_hash
+}
+
+fn params(encoded: &str) -> HashMap<String, String> {
+    form_urlencoded::parse(encoded.as_bytes()).into_owned().collect()
+}
+
+fn key_params(params: &HashMap<String, String>) -> Option<(i64, u8)> {
+    Some((
+        params.get("account_index")?.parse().ok()?,
+        params.get("api_key_index")?.parse().ok()?,
+    ))
+}
+
+fn reject(code: i64, message: &str) -> Value {
+    json!({"code": code, "message": message})
+}
+
+fn http_error(status: StatusCode, body: &str) -> Response {
+    (status, body.to_string()).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use api_client::{
+        ApiError, AuditRecord, AuditSink, Backoff, BaseAmount, CheckedResponse, CircuitBreakerConfig, CircuitState,
+        CreateGroupedOrdersRequest, CreateOrderRequest, GroupingType, KillSwitch, LighterClient, ModifyOrderRequest,
+        NonceResync, OrderType, Price, RemoteSigner, RetryConfig, RiskConfig, RiskLimit, RiskLimits, TimeInForce,
+        TransferRequest, UsdcAmount, WithBudget,
+    };
+
+    const PRIVATE_KEY: &str =
+        "bda332f3aaa2d9cfdd8920830ea37efce9636c671a426bd4cb9815007e2a2917604ab47857cbb200";
+
+    #[tokio::test]
+    async fn test_nonces_and_faults() {
+        let exchange = MockExchange::start().await.unwrap();
+        exchange.set_nonce(1, 3, 100);
+        let client = LighterClient::new(exchange.url(), PRIVATE_KEY, 1, 3).unwrap();
+
+        assert_eq!(client.get_nonce().await.unwrap(), 100);
+        let response = client.cancel_order(0, 7).await.unwrap();
+        assert_eq!(response["code"], 200);
+        assert_eq!(exchange.next_nonce(1, 3), 101);
+
+        exchange.inject(Endpoint::SendTx, Fault::InvalidSignature);
+        assert_eq!(client.cancel_order(0, 7).await.unwrap()["code"], CODE_INVALID_SIGNATURE);
+
+        exchange.inject(Endpoint::SendTx, Fault::Http(502, "bad gateway".to_string()));
+        assert!(client.cancel_order(0, 7).await.is_err());
+
+        let sent = exchange.sent_txs();
+        assert_eq!(sent.iter().map(|tx| tx.nonce).collect::<Vec<_>>(), vec![100]);
+        assert_eq!(sent[0].tx_type, 15);
+        assert_eq!(sent[0].tx_info["Index"], 7);
+    }
+
+    #[tokio::test]
+    async fn test_budget_releases_the_nonce_of_a_hanging_send() {
+        let exchange = MockExchange::start().await.unwrap();
+        exchange.set_nonce(1, 3, 100);
+        let client = LighterClient::new(exchange.url(), PRIVATE_KEY, 1, 3).unwrap();
+        client.get_nonce().await.unwrap();
+
+        exchange.inject(Endpoint::SendTx, Fault::Delay(Duration::from_secs(5)));
+        let error = client.cancel_order(0, 7).within(Duration::from_millis(50)).await.unwrap_err();
+        assert!(matches!(error, ApiError::DeadlineExceeded), "{}", error);
+
+        // The next transaction gets the nonce back instead of leaving a gap
+        assert_eq!(client.cancel_order(0, 8).await.unwrap()["code"], 200);
+        assert_eq!(exchange.sent_txs().iter().map(|tx| tx.nonce).collect::<Vec<_>>(), vec![100]);
+    }
+
+    #[tokio::test]
+    async fn test_nonce_conflict_resync() {
+        let exchange = MockExchange::start().await.unwrap();
+        exchange.set_nonce(1, 3, 100);
+        let resyncs = Arc::new(Mutex::new(Vec::new()));
+        let observed = resyncs.clone();
+        let client = LighterClient::builder(exchange.url(), PRIVATE_KEY, 1, 3)
+            .on_nonce_resync(Arc::new(move |resync: &NonceResync| observed.lock().unwrap().push(resync.clone())))
+            .build()
+            .unwrap();
+
+        // The conflicting sender consumes 100; the client resyncs and replays with 101
+        exchange.inject(Endpoint::SendTx, Fault::NonceConflict);
+        let response = client.cancel_order(0, 7).await.unwrap();
+        assert_eq!(response["code"], 200);
+        assert_eq!(exchange.sent_txs().iter().map(|tx| tx.nonce).collect::<Vec<_>>(), vec![101]);
+
+        let resyncs = resyncs.lock().unwrap();
+        assert_eq!(resyncs.len(), 1);
+        assert_eq!((resyncs[0].rejected_nonce, resyncs[0].fetched_nonce), (100, 101));
+        assert_eq!(resyncs[0].code, CODE_INVALID_NONCE);
+    }
+
+    #[tokio::test]
+    async fn test_cancels_lease_from_the_order_pool() {
+        let exchange = MockExchange::start().await.unwrap();
+        exchange.set_nonce(1, 3, 100);
+        let resyncs = Arc::new(Mutex::new(0));
+        let observed = resyncs.clone();
+        let client = LighterClient::builder(exchange.url(), PRIVATE_KEY, 1, 3)
+            .on_nonce_resync(Arc::new(move |_: &NonceResync| *observed.lock().unwrap() += 1))
+            .build()
+            .unwrap();
+        let order = || CreateOrderRequest {
+            account_index: 1,
+            order_book_index: 0,
+            client_order_index: 1,
+            base_amount: BaseAmount(1000),
+            price: Price(300_000),
+            is_ask: false,
+            order_type: OrderType::Limit,
+            time_in_force: TimeInForce::GoodTillTime,
+            reduce_only: false,
+            trigger_price: Price::ZERO,
+            price_protection: None,
+            order_expiry: None,
+        };
+
+        // Orders and cancels interleaved on one pool, without a nonce conflict in between
+        assert_eq!(client.create_order(order()).await.unwrap()["code"], 200);
+        assert_eq!(client.cancel_order(0, 7).await.unwrap()["code"], 200);
+        assert_eq!(client.cancel_all_orders(0, 0).await.unwrap()["code"], 200);
+        assert_eq!(client.create_order(order()).await.unwrap()["code"], 200);
+        assert_eq!(exchange.sent_txs().iter().map(|tx| tx.nonce).collect::<Vec<_>>(), vec![100, 101, 102, 103]);
+        assert_eq!(*resyncs.lock().unwrap(), 0);
+
+        // A cancel does not take the nonce of an order still being signed, and a rejected
+        // cancel gives its own nonce back
+        let held = client.lease_nonce().await.unwrap();
+        exchange.inject(Endpoint::SendTx, Fault::InvalidSignature);
+        client.cancel_order(0, 7).await.unwrap();
+        assert_eq!(held.nonce(), 104);
+        assert_eq!(client.nonce_status().unwrap().next_nonce(), 105);
+    }
+
+    #[tokio::test]
+    async fn test_websocket_falls_back_to_rest() {
+        let exchange = MockExchange::start().await.unwrap();
+        exchange.set_nonce(1, 3, 100);
+        // The mock has no /stream endpoint, so the socket never opens
+        let client = LighterClient::builder(exchange.url(), PRIVATE_KEY, 1, 3)
+            .websocket_tx(true)
+            .build()
+            .unwrap();
+
+        assert_eq!(client.cancel_order(0, 7).await.unwrap()["code"], 200);
+        assert_eq!(exchange.sent_txs().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_deadman_switch() {
+        let exchange = MockExchange::start().await.unwrap();
+        exchange.set_nonce(1, 3, 100);
+        let client = Arc::new(LighterClient::new(exchange.url(), PRIVATE_KEY, 1, 3).unwrap());
+        assert!(client.refresh_deadman().await.is_err());
+
+        let ttl = Duration::from_millis(300);
+        assert_eq!(client.set_deadman_switch(ttl).await.unwrap()["code"], 200);
+        assert_eq!(client.deadman_switch(), Some(ttl));
+        let refresher = client.keep_deadman_alive();
+        tokio::time::sleep(Duration::from_millis(350)).await;
+
+        client.disable_deadman().await.unwrap();
+        refresher.await.unwrap();
+        let sent = exchange.sent_txs();
+        let schedules = sent.iter().filter(|tx| tx.tx_info["TimeInForce"] == 1).count();
+        assert!(schedules >= 3, "armed and refreshed at least twice, got {}", schedules);
+        let abort = sent.last().unwrap();
+        assert_eq!((abort.tx_type, abort.tx_info["TimeInForce"].as_u64(), abort.tx_info["Time"].as_i64()), (16, Some(2), Some(0)));
+    }
+
+    #[tokio::test]
+    async fn test_deadman_refresh_expiry_and_shutdown() {
+        let now_ms = || std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_millis() as i64;
+        let schedules = |exchange: &MockExchange| -> Vec<i64> {
+            let sent = exchange.sent_txs();
+            sent.iter().filter(|tx| tx.tx_type == 16 && tx.tx_info["TimeInForce"] == 1).map(|tx| tx.tx_info["Time"].as_i64().unwrap()).collect()
+        };
+        let exchange = MockExchange::start().await.unwrap();
+        exchange.set_nonce(1, 3, 100);
+        let client = Arc::new(LighterClient::new(exchange.url(), PRIVATE_KEY, 1, 3).unwrap());
+        let order = CreateOrderRequest {
+            account_index: 1,
+            order_book_index: 0,
+            client_order_index: 1,
+            base_amount: BaseAmount(1000),
+            price: Price(300_000),
+            is_ask: false,
+            order_type: OrderType::Limit,
+            time_in_force: TimeInForce::GoodTillTime,
+            reduce_only: false,
+            trigger_price: Price::ZERO,
+            price_protection: None,
+            order_expiry: None,
+        };
+        assert!(client.set_deadman_switch(Duration::ZERO).await.is_err());
+
+        // Refresh: each one pushes the schedule back, on nonces shared with the orders
+        let ttl = Duration::from_millis(300);
+        client.set_deadman_switch(ttl).await.unwrap();
+        let refresher = client.keep_deadman_alive();
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert_eq!(client.create_order(order).await.unwrap()["code"], 200);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let times = schedules(&exchange);
+        assert!(times.len() >= 3, "armed and refreshed at least twice, got {:?}", times);
+        assert!(times.windows(2).all(|pair| pair[1] > pair[0]), "{:?}", times);
+        let nonces: Vec<i64> = exchange.sent_txs().iter().map(|tx| tx.nonce).collect();
+        assert_eq!(nonces, (100..100 + nonces.len() as i64).collect::<Vec<_>>());
+
+        // Expiry: while refreshes fail the last schedule runs out, and the refresher keeps trying
+        for _ in 0..5 {
+            exchange.inject(Endpoint::SendTx, Fault::Http(502, "bad gateway".to_string()));
+        }
+        let accepted = schedules(&exchange).len();
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        let times = schedules(&exchange);
+        assert_eq!(times.len(), accepted);
+        assert!(*times.last().unwrap() < now_ms(), "the schedule has expired");
+        exchange.clear_faults();
+        let recovered = async {
+            while schedules(&exchange).len() == accepted {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(1), recovered).await.unwrap();
+        assert!(*schedules(&exchange).last().unwrap() > now_ms());
+
+        // Shutdown: the refresher ends with the client, leaving the schedule to cancel the orders
+        drop(client);
+        tokio::time::timeout(ttl, refresher).await.unwrap().unwrap();
+        let sent = exchange.sent_txs();
+        assert!(sent.iter().all(|tx| tx.tx_info["TimeInForce"] != 2), "never aborted");
+    }
+
+    #[tokio::test]
+    async fn test_account_and_api_key() {
+        let exchange = MockExchange::start().await.unwrap();
+        let client = LighterClient::new(exchange.url(), PRIVATE_KEY, 1, 3).unwrap();
+
+        assert!(client.check_api_key().await.is_err());
+        exchange.set_api_key(1, 3, client.signer().public_key().to_string());
+        client.check_api_key().await.unwrap();
+
+        exchange.set_account(1, json!({"index": 1, "positions": []}));
+        let account = client.get_account().await.unwrap();
+        assert_eq!(account["accounts"][0]["index"], 1);
+        let me = client.whoami().await.unwrap();
+        assert_eq!((me.account_index, me.api_key_index), (1, 3));
+        assert!(me.key_registered());
+
+        exchange.set_api_key(1, 3, "0".repeat(80));
+        assert!(!client.whoami().await.unwrap().key_registered());
+
+        exchange.inject(Endpoint::Account, Fault::Http(503, "maintenance".to_string()));
+        assert!(client.get_account().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_exchange_status() {
+        let exchange = MockExchange::start().await.unwrap();
+        let client = LighterClient::new(exchange.url(), PRIVATE_KEY, 1, 3).unwrap();
+
+        let status = client.get_exchange_status().await.unwrap();
+        assert!(status.is_operational());
+        assert!(status.clock_skew().unwrap().abs() <= 1);
+
+        exchange.set_maintenance(true);
+        assert!(!client.get_exchange_status().await.unwrap().is_operational());
+
+        exchange.inject(Endpoint::Status, Fault::Http(503, "unavailable".to_string()));
+        assert!(client.get_exchange_status().await.is_err());
+        exchange.inject(Endpoint::Status, Fault::Delay(Duration::from_millis(50)));
+        assert!(client.ping().await.unwrap() >= Duration::from_millis(50));
+    }
+    #[tokio::test]
+    async fn test_rotate_api_key() {
+        let exchange = MockExchange::start().await.unwrap();
+        exchange.set_nonce(1, 3, 100);
+        let client = LighterClient::new(exchange.url(), PRIVATE_KEY, 1, 3).unwrap();
+        let old_key = client.signer().public_key();
+        exchange.set_api_key(1, 3, old_key.to_string());
+
+        let new_key = signer::KeyManager::derive_api_key(&[7u8; 32], 3).unwrap();
+        let expected = new_key.public_key();
+        assert_eq!(client.rotate_api_key(Some(new_key)).await.unwrap(), expected);
+        assert_eq!(client.signer().public_key(), expected);
+        client.check_api_key().await.unwrap();
+
+        // Signed by the old key, then the client continues with the refetched nonce
+        let sent = exchange.sent_txs();
+        assert_eq!((sent[0].tx_type, sent[0].nonce), (8, 100));
+        assert_eq!(sent[0].tx_info["PubKey"], expected.to_hex());
+        assert_eq!(client.cancel_order(0, 7).await.unwrap()["code"], 200);
+        assert_eq!(exchange.sent_txs()[1].nonce, 101);
+
+        // A rejected change keeps the current key
+        exchange.inject(Endpoint::SendTx, Fault::InvalidSignature);
+        assert!(client.rotate_api_key(None).await.is_err());
+        assert_eq!(client.signer().public_key(), expected);
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker() {
+        let exchange = MockExchange::start().await.unwrap();
+        exchange.set_nonce(1, 3, 100);
+        exchange.set_account(1, json!({"index": 1, "positions": []}));
+        let client = LighterClient::builder(exchange.url(), PRIVATE_KEY, 1, 3)
+            .circuit_breaker(CircuitBreakerConfig {
+                failure_threshold: 2,
+                cool_down: Duration::from_millis(100),
+            })
+            .build()
+            .unwrap();
+
+        for _ in 0..2 {
+            exchange.inject(Endpoint::SendTx, Fault::Http(503, "unavailable".to_string()));
+            assert!(client.cancel_order(0, 7).await.is_err());
+        }
+        assert!(client.is_circuit_open());
+        let status = client.circuit_status();
+        assert_eq!((status[0].endpoint.as_str(), status[0].consecutive_failures), ("/api/v1/sendTx", 2));
+        assert!(matches!(status[0].state, CircuitState::Open { .. }));
+
+        // sendTx fails fast without reaching the exchange; other endpoints still work
+        let error = client.cancel_order(0, 7).await.unwrap_err();
+        assert!(matches!(error, ApiError::CircuitOpen { ref endpoint, .. } if endpoint == "/api/v1/sendTx"));
+        assert!(exchange.sent_txs().is_empty());
+        client.get_account().await.unwrap();
+
+        // After the cool-down a successful probe closes the circuit
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert_eq!(client.cancel_order(0, 7).await.unwrap()["code"], 200);
+        assert!(!client.is_circuit_open());
+        assert_eq!(exchange.sent_txs()[0].nonce, 100);
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_is_honored() {
+        let exchange = MockExchange::start().await.unwrap();
+        exchange.set_nonce(1, 3, 100);
+        // The backoff alone would wait 10 s; the server asks for 1 s
+        let client = LighterClient::builder(exchange.url(), PRIVATE_KEY, 1, 3)
+            .retry_config(RetryConfig::none().with_code(429, Backoff::fixed(1, Duration::from_secs(10))))
+            .build()
+            .unwrap();
+        let order = || CreateOrderRequest {
+            account_index: 1,
+            order_book_index: 0,
+            client_order_index: 1,
+            base_amount: BaseAmount(1000),
+            price: Price(300_000),
+            is_ask: false,
+            order_type: OrderType::Limit,
+            time_in_force: TimeInForce::GoodTillTime,
+            reduce_only: false,
+            trigger_price: Price::ZERO,
+            price_protection: None,
+            order_expiry: None,
+        };
+
+        exchange.inject(Endpoint::SendTx, Fault::RateLimited(Duration::from_secs(1)));
+        let started = std::time::Instant::now();
+        assert_eq!(client.create_order(order()).await.unwrap()["code"], 200);
+        let waited = started.elapsed();
+        assert!(waited >= Duration::from_secs(1) && waited < Duration::from_secs(5), "{:?}", waited);
+
+        // Once retries run out, the hint is kept in the error
+        exchange.inject(Endpoint::SendTx, Fault::RateLimited(Duration::from_secs(1)));
+        exchange.inject(Endpoint::SendTx, Fault::RateLimited(Duration::from_secs(1)));
+        let error = client.create_order(order()).await.checked().unwrap_err();
+        assert_eq!(error.retry_after(), Some(Duration::from_secs(1)));
+    }
+
+    #[tokio::test]
+    async fn test_kill_switch_allows_closing() {
+        let exchange = MockExchange::start().await.unwrap();
+        exchange.set_nonce(1, 3, 100);
+        let kill_switch = KillSwitch::new();
+        let client = LighterClient::builder(exchange.url(), PRIVATE_KEY, 1, 3)
+            .kill_switch(kill_switch.clone())
+            .build()
+            .unwrap();
+        let order = |reduce_only: bool| CreateOrderRequest {
+            account_index: 1,
+            order_book_index: 0,
+            client_order_index: 1,
+            base_amount: BaseAmount(1000),
+            price: Price(300_000),
+            is_ask: true,
+            order_type: OrderType::Limit,
+            time_in_force: TimeInForce::GoodTillTime,
+            reduce_only,
+            trigger_price: Price::ZERO,
+            price_protection: None,
+            order_expiry: None,
+        };
+
+        kill_switch.trip("operator halt").unwrap();
+        assert!(matches!(client.create_order(order(false)).await, Err(ApiError::Halted(reason)) if reason == "operator halt"));
+        assert!(exchange.sent_txs().is_empty());
+
+        // The refused order's nonce is reused by the close and the cancel
+        assert_eq!(client.create_order(order(true)).await.unwrap()["code"], 200);
+        assert_eq!(client.cancel_order(0, 7).await.unwrap()["code"], 200);
+        assert_eq!(exchange.sent_txs().iter().map(|tx| tx.nonce).collect::<Vec<_>>(), vec![100, 101]);
+
+        kill_switch.reset().unwrap();
+        assert_eq!(client.create_order(order(false)).await.unwrap()["code"], 200);
+    }
+
+    #[tokio::test]
+    async fn test_lost_response_is_not_duplicated() {
+        let exchange = MockExchange::start().await.unwrap();
+        exchange.set_nonce(1, 3, 100);
+        let hasher = LighterClient::new(exchange.url(), PRIVATE_KEY, 1, 3).unwrap();
+        exchange.set_tx_hasher(Arc::new(move |tx_type, tx_info| {
+            let hash = hasher.compute_tx_hash(&tx_info.to_string(), tx_type).unwrap();
+            hash.iter().map(|byte| format!("{:02x}", byte)).collect()
+        }));
+        let client = LighterClient::builder(exchange.url(), PRIVATE_KEY, 1, 3)
+            .retry_config(RetryConfig::none().with_transport(Some(Backoff::fixed(2, Duration::from_millis(10)))))
+            .build()
+            .unwrap();
+        let order = || CreateOrderRequest {
+            account_index: 1,
+            order_book_index: 0,
+            client_order_index: 1,
+            base_amount: BaseAmount(1000),
+            price: Price(300_000),
+            is_ask: false,
+            order_type: OrderType::Limit,
+            time_in_force: TimeInForce::GoodTillTime,
+            reduce_only: false,
+            trigger_price: Price::ZERO,
+            price_protection: None,
+            order_expiry: None,
+        };
+
+        // Executed, response lost: found by hash instead of placed again with nonce 101
+        exchange.inject(Endpoint::SendTx, Fault::LostResponse);
+        let response = client.create_order(order()).await.unwrap();
+        let sent = exchange.sent_txs();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(response["tx_hash"].as_str(), Some(sent[0].tx_hash.as_str()));
+
+        // Not executed: retried with a resynced nonce
+        exchange.inject(Endpoint::SendTx, Fault::Http(502, "bad gateway".to_string()));
+        assert_eq!(client.create_order(order()).await.unwrap()["code"], 200);
+        assert_eq!(exchange.sent_txs().iter().map(|tx| tx.nonce).collect::<Vec<_>>(), vec![100, 101]);
+
+        // Executed, and the lookup fails too: the same transaction is posted again and its
+        // used nonce gives it away
+        exchange.inject(Endpoint::SendTx, Fault::LostResponse);
+        exchange.inject(Endpoint::Tx, Fault::Http(503, "unavailable".to_string()));
+        let error = client.create_order(order()).await.unwrap_err();
+        assert!(matches!(error, ApiError::PossiblyExecuted { nonce: 102, .. }), "{}", error);
+        assert_eq!(exchange.sent_txs().len(), 3);
+    }
+
+    /// Signing service answering with `public_key`, signing with `key`
+    async fn start_signer(public_key: String, key: signer::KeyManager) -> SocketAddr {
+        fn authorized(headers: &axum::http::HeaderMap) -> bool {
+            headers.get(header::AUTHORIZATION).and_then(|v| v.to_str().ok()) == Some("Bearer s3cret")
+        }
+        let key = Arc::new(key);
+        let router = Router::new()
+            .route(
+                "/public_key",
+                get(move |headers: axum::http::HeaderMap| async move {
+                    if !authorized(&headers) {
+                        return http_error(StatusCode::UNAUTHORIZED, "unauthorized");
+                    }
+                    Json(json!({"public_key": public_key})).into_response()
+                }),
+            )
+            .route(
+                "/sign",
+                post(move |headers: axum::http::HeaderMap, Json(body): Json<Value>| async move {
+                    if !authorized(&headers) {
+                        return http_error(StatusCode::UNAUTHORIZED, "unauthorized");
+                    }
+                    let message: [u8; 40] = hex::decode(body["message"].as_str().unwrap()).unwrap().try_into().unwrap();
+                    Json(json!({"signature": key.sign(&message).unwrap().to_hex()})).into_response()
+                }),
+            );
+        let server = Server::try_bind(&SocketAddr::from(([127, 0, 0, 1], 0))).unwrap().serve(router.into_make_service());
+        let addr = server.local_addr();
+        tokio::spawn(server);
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_remote_signer() {
+        let exchange = MockExchange::start().await.unwrap();
+        exchange.set_nonce(1, 3, 100);
+        let key = signer::KeyManager::generate();
+        let public_key = key.public_key();
+        let signer_addr = start_signer(public_key.to_hex(), key).await;
+
+        assert!(RemoteSigner::connect(format!("http://{}", signer_addr), None).await.is_err());
+        let remote = RemoteSigner::connect(format!("http://{}/", signer_addr), Some("s3cret")).await.unwrap();
+        let client = LighterClient::builder_with_signer(exchange.url(), Arc::new(remote), 1, 3).build().unwrap();
+        assert_eq!(client.signer().public_key(), public_key);
+
+        assert_eq!(client.cancel_order(0, 7).await.unwrap()["code"], 200);
+        let sent = &exchange.sent_txs()[0];
+        let tx_hash = client.compute_tx_hash(&sent.tx_info.to_string(), 15).unwrap();
+        let signature = api_client::Signature::from_base64(sent.tx_info["Sig"].as_str().unwrap()).unwrap();
+        assert!(goldilocks_crypto::verify_signature(signature.as_bytes(), &tx_hash, &public_key.to_bytes()).unwrap());
+
+        // A service signing with another key than it advertises is caught before sending
+        let impostor = start_signer(public_key.to_hex(), signer::KeyManager::generate()).await;
+        let remote = RemoteSigner::connect(format!("http://{}", impostor), Some("s3cret")).await.unwrap();
+        let client = LighterClient::builder_with_signer(exchange.url(), Arc::new(remote), 1, 3).build().unwrap();
+        assert!(client.cancel_order(0, 7).await.is_err());
+        assert_eq!(exchange.sent_txs().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_orders_survive_an_unreliable_network() {
+        let mut totals = ChaosStats::default();
+        for seed in 0..4 {
+            let exchange = MockExchange::start().await.unwrap();
+            exchange.set_nonce(1, 3, 100);
+            let hasher = LighterClient::new(exchange.url(), PRIVATE_KEY, 1, 3).unwrap();
+            exchange.set_tx_hasher(Arc::new(move |tx_type, tx_info| {
+                hex::encode(hasher.compute_tx_hash(&tx_info.to_string(), tx_type).unwrap())
+            }));
+            let config = ChaosConfig {
+                max_latency: Duration::from_millis(5),
+                drop_request: 0.1,
+                drop_response: 0.1,
+                duplicate: 0.1,
+                corrupt: 0.1,
+                ..ChaosConfig::default()
+            };
+            let transport = FaultyTransport::start(exchange.url(), config, seed).await.unwrap();
+            let client = LighterClient::builder(transport.url(), PRIVATE_KEY, 1, 3)
+                .retry_config(RetryConfig::none().with_transport(Some(Backoff::fixed(3, Duration::from_millis(5)))))
+                .build()
+                .unwrap();
+            let order = |client_order_index| CreateOrderRequest {
+                account_index: 1,
+                order_book_index: 0,
+                client_order_index,
+                base_amount: BaseAmount(1000),
+                price: Price(300_000),
+                is_ask: false,
+                order_type: OrderType::Limit,
+                time_in_force: TimeInForce::GoodTillTime,
+                reduce_only: false,
+                trigger_price: Price::ZERO,
+                price_protection: None,
+                order_expiry: None,
+            };
+
+            let mut outcomes = Vec::new();
+            for client_order_index in 1..=20 {
+                outcomes.push((client_order_index, client.create_order(order(client_order_index)).await));
+            }
+            let sent = exchange.sent_txs();
+            let executed = |client_order_index: u64| {
+                sent.iter().any(|tx| tx.tx_info["ClientOrderIndex"].as_u64() == Some(client_order_index))
+            };
+            assert!(duplicate_orders(&sent).is_empty(), "seed {}: {:?}", seed, duplicate_orders(&sent));
+            for (client_order_index, outcome) in &outcomes {
+                // Accepted orders executed, and only an uncertain outcome may hide an execution
+                match outcome {
+                    Ok(response) if response["code"] == 200 => {
+                        assert!(executed(*client_order_index), "seed {}: {} {}", seed, client_order_index, response)
+                    }
+                    Err(ApiError::PossiblyExecuted { .. }) => {}
+                    outcome => assert!(!executed(*client_order_index), "seed {}: {} {:?}", seed, client_order_index, outcome),
+                }
+            }
+
+            // Once the network heals, the nonces are back in step
+            transport.set_config(ChaosConfig::default());
+            assert_eq!(client.create_order(order(21)).await.unwrap()["code"], 200, "seed {}", seed);
+            assert!(duplicate_orders(&exchange.sent_txs()).is_empty());
+
+            let stats = transport.stats();
+            totals.dropped_requests += stats.dropped_requests;
+            totals.dropped_responses += stats.dropped_responses;
+            totals.duplicated += stats.duplicated;
+            totals.corrupted += stats.corrupted;
+        }
+        assert!(totals.dropped_requests > 0 && totals.dropped_responses > 0, "{:?}", totals);
+        assert!(totals.duplicated > 0 && totals.corrupted > 0, "{:?}", totals);
+    }
+
+    #[derive(Default)]
+    struct MemoryAuditSink(Mutex<Vec<AuditRecord>>);
+
+    impl AuditSink for MemoryAuditSink {
+        fn record(&self, record: &AuditRecord) -> api_client::Result<()> {
+            self.0.lock().unwrap().push(record.clone());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_every_signed_tx_is_audited() {
+        let exchange = MockExchange::start().await.unwrap();
+        exchange.set_nonce(1, 3, 100);
+        let sink = Arc::new(MemoryAuditSink::default());
+        let client = LighterClient::builder(exchange.url(), PRIVATE_KEY, 1, 3).audit_sink(sink.clone()).build().unwrap();
+
+        let transfer = TransferRequest {
+            to_account_index: 42,
+            usdc_amount: UsdcAmount(25_000_000),
+            fee: UsdcAmount(0),
+            memo: [0; 32],
+        };
+        assert_eq!(client.transfer(transfer).await.unwrap()["code"], 200);
+        assert_eq!(client.cancel_order(0, 7).await.unwrap()["code"], 200);
+        let raw = json!({"AccountIndex": 1, "ApiKeyIndex": 3, "MarketIndex": 0, "Index": 8,
+            "ExpiredAt": 1_700_000_000_000i64, "Nonce": 500, "Sig": ""});
+        let signature = client.sign_transaction_with_type(&raw.to_string(), 15).await.unwrap();
+
+        let records = sink.0.lock().unwrap().clone();
+        let sent = exchange.sent_txs();
+        assert_eq!(records.iter().map(|r| (r.tx_type, r.nonce)).collect::<Vec<_>>(), vec![(12, 100), (15, 101), (15, 500)]);
+        for (record, tx) in records.iter().zip(&sent) {
+            assert_eq!(record.tx_info, tx.tx_info);
+        }
+        assert_eq!(records[0].tx_info["ToAccountIndex"], 42);
+        assert_eq!(records[2].signature, signature.to_base64());
+    }
+
+    #[tokio::test]
+    async fn test_possibly_executed_transfer_keeps_its_nonce() {
+        let exchange = MockExchange::start().await.unwrap();
+        exchange.set_nonce(1, 3, 100);
+        let client = LighterClient::new(exchange.url(), PRIVATE_KEY, 1, 3).unwrap();
+        let transfer = || TransferRequest {
+            to_account_index: 42,
+            usdc_amount: UsdcAmount(25_000_000),
+            fee: UsdcAmount(0),
+            memo: [0; 32],
+        };
+
+        // The nonce is reported used and the transaction cannot be looked up: it may be on
+        // chain, so its nonce must not go back to the pool
+        exchange.inject(Endpoint::SendTx, Fault::NonceConflict);
+        exchange.inject(Endpoint::Tx, Fault::Http(503, "unavailable".to_string()));
+        let error = client.transfer(transfer()).await.unwrap_err();
+        assert!(matches!(error, ApiError::PossiblyExecuted { nonce: 100, .. }), "{}", error);
+        let status = client.nonce_status().unwrap();
+        assert!(status.released.is_empty(), "{:?}", status.released);
+        assert_eq!(status.next_nonce(), 101);
+
+        // Any other failure gives the nonce back
+        exchange.inject(Endpoint::SendTx, Fault::Http(400, "bad request".to_string()));
+        assert!(client.transfer(transfer()).await.is_err());
+        assert_eq!(client.nonce_status().unwrap().released, vec![101]);
+    }
+
+    fn open_order(order_index: i64, is_ask: bool) -> Value {
+        json!({"order_index": order_index, "client_order_index": 7, "market_index": 0, "is_ask": is_ask,
+               "status": "open", "type": "limit", "reduce_only": false, "price": "3000.00"})
+    }
+
+    fn leg(client_order_index: u64, base_amount: i64) -> CreateOrderRequest {
+        CreateOrderRequest {
+            account_index: 1,
+            order_book_index: 0,
+            client_order_index,
+            base_amount: BaseAmount(base_amount),
+            price: Price(300_000),
+            is_ask: false,
+            order_type: OrderType::Limit,
+            time_in_force: TimeInForce::GoodTillTime,
+            reduce_only: false,
+            trigger_price: Price::ZERO,
+            price_protection: None,
+            order_expiry: None,
+        }
+    }
+
+    fn rejected_limit<T: std::fmt::Debug>(result: Result<T, ApiError>) -> Option<RiskLimit> {
+        match result {
+            Err(ApiError::RiskRejected { limit, .. }) => Some(limit),
+            other => panic!("not a risk rejection: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_grouped_orders_check_every_leg() {
+        let exchange = MockExchange::start().await.unwrap();
+        exchange.set_nonce(1, 3, 100);
+        exchange.set_active_orders(1, 0, vec![open_order(11, false)]);
+        let client = LighterClient::builder(exchange.url(), PRIVATE_KEY, 1, 3)
+            .risk_limits(RiskConfig::new().with_market(0, RiskLimits::new().max_open_orders(2)))
+            .build()
+            .unwrap();
+        let group = |legs| CreateGroupedOrdersRequest {
+            grouping_type: GroupingType::Oco,
+            orders: legs,
+        };
+
+        // One order is open, so the second leg would make three
+        let rejected = client.create_grouped_orders(group(vec![leg(1, 10), leg(2, 10)])).await;
+        assert_eq!(rejected_limit(rejected), Some(RiskLimit::OpenOrders));
+        assert!(exchange.sent_txs().is_empty());
+
+        assert_eq!(client.create_grouped_orders(group(vec![leg(3, 10)])).await.unwrap()["code"], 200);
+        assert_eq!(exchange.sent_txs()[0].tx_type, 28);
+    }
+
+    #[tokio::test]
+    async fn test_modify_order_checks_the_new_size() {
+        let exchange = MockExchange::start().await.unwrap();
+        exchange.set_nonce(1, 3, 100);
+        exchange.set_account(1, json!({"index": 1, "positions": []}));
+        exchange.set_active_orders(1, 0, vec![open_order(11, false)]);
+        let client = LighterClient::builder(exchange.url(), PRIVATE_KEY, 1, 3)
+            .risk_limits(RiskConfig::new().with_market(0, RiskLimits::new().max_open_orders(1).max_position(100)))
+            .build()
+            .unwrap();
+        let modify = |order_index, base_amount| ModifyOrderRequest {
+            market_index: 0,
+            order_index,
+            base_amount: BaseAmount(base_amount),
+            price: Price(300_000),
+            trigger_price: Price::ZERO,
+        };
+
+        // The modified order is already open, so it does not count against the cap
+        assert_eq!(client.modify_order(modify(11, 50)).await.unwrap()["code"], 200);
+        // A buy of 150 from flat would leave a position above 100
+        assert_eq!(rejected_limit(client.modify_order(modify(11, 150)).await), Some(RiskLimit::Position));
+        let unknown = client.modify_order(modify(12, 50)).await.unwrap_err();
+        assert!(matches!(unknown, ApiError::InvalidOrder(_)), "{}", unknown);
+        assert_eq!(exchange.sent_txs().len(), 1);
+    }
+}
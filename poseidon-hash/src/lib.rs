@@ -47,6 +47,7 @@
 //! let hash = hash_to_quintic_extension(&elements);
 //! ```
 
+
 /// Goldilocks field element.
 ///
 /// The Goldilocks field uses prime modulus p = 2^64 - 2^32 + 1, which is optimized for:
@@ -77,14 +78,23 @@ impl Goldilocks {
     
     /// The order of the field (same as MODULUS)
     pub const ORDER: u64 = Self::MODULUS;
+
+    /// Largest `k` with 2^k dividing p - 1.
+    pub const TWO_ADICITY: usize = 32;
+
+    /// Generator of the multiplicative group.
+    pub const MULTIPLICATIVE_GROUP_GENERATOR: Goldilocks = Goldilocks(7);
+
+    /// Primitive 2^32-th root of unity: `MULTIPLICATIVE_GROUP_GENERATOR^((p - 1) / 2^32)`.
+    pub const POWER_OF_TWO_GENERATOR: Goldilocks = Goldilocks(1753635133440165772);
     
     /// Returns the zero element of the field.
-    pub fn zero() -> Self {
+    pub const fn zero() -> Self {
         Goldilocks(0)
     }
     
     /// Returns the multiplicative identity (one) of the field.
-    pub fn one() -> Self {
+    pub const fn one() -> Self {
         Goldilocks(1)
     }
     
@@ -198,30 +208,73 @@ impl Goldilocks {
         self.add(self)
     }
     
+    /// Computes the additive inverse (negation) of this field element.
+    pub fn neg(&self) -> Goldilocks {
+        Goldilocks::zero().sub(self)
+    }
+    
     /// Computes the multiplicative inverse of this field element.
     ///
-    /// Uses Fermat's little theorem: a^(-1) ≡ a^(p-2) mod p
+    /// Computes a^(p-2) (Fermat's little theorem) with the plonky2 addition chain:
+    /// 72 multiplications instead of the ~127 of square-and-multiply, in constant time.
+    /// Zero maps to zero.
     ///
-    /// # Panics
+    /// # Example
+    ///
+    /// ```rust
+    /// use poseidon_hash::Goldilocks;
     ///
-    /// This function will panic if called on zero (which has no inverse).
-    /// Use `inverse_or_zero()` if you need to handle zero elements.
+    /// let a = Goldilocks::from_canonical_u64(7);
+    /// assert!(a.mul(&a.inverse()).equals(&Goldilocks::one()));
+    /// ```
     pub fn inverse(&self) -> Goldilocks {
-        // Fermat's little theorem: a^(p-2) ≡ a^(-1) mod p
-        // p = 2^64 - 2^32 + 1
-        // p - 2 = 2^64 - 2^32 - 1
-        let mut result = Goldilocks::one();
-        let mut base = *self;
-        let mut exp = Self::MODULUS - 2;
-        
-        while exp > 0 {
-            if exp & 1 == 1 {
-                result = result.mul(&base);
+        // p - 2 = 0b1111111111111111111111111111111_0_11111111111111111111111111111111
+        //         (31 ones, a zero, 32 ones)
+        let t2 = self.square().mul(self); // 11
+        let t3 = t2.square().mul(self); // 111
+        let t6 = t3.exp_power_of_2(3).mul(&t3); // 6 ones
+        let t12 = t6.exp_power_of_2(6).mul(&t6); // 12 ones
+        let t24 = t12.exp_power_of_2(12).mul(&t12); // 24 ones
+        let t30 = t24.exp_power_of_2(6).mul(&t6); // 30 ones
+        let t31 = t30.square().mul(self); // 31 ones
+        let t63 = t31.exp_power_of_2(32).mul(&t31); // 31 ones, 0, 31 ones
+        t63.square().mul(self)
+    }
+
+    /// Inverts every element of `elements` with a single field inversion.
+    ///
+    /// Uses Montgomery's trick: 3(n-1) multiplications plus one inversion instead of n
+    /// inversions. Zero elements map to zero, as with `inverse()`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use poseidon_hash::Goldilocks;
+    ///
+    /// let values = [Goldilocks::from_canonical_u64(2), Goldilocks::zero(), Goldilocks::from_canonical_u64(5)];
+    /// let inverses = Goldilocks::batch_inverse(&values);
+    /// assert!(inverses[0].equals(&values[0].inverse()));
+    /// assert!(inverses[1].is_zero());
+    /// ```
+    pub fn batch_inverse(elements: &[Goldilocks]) -> Vec<Goldilocks> {
+        // prefix[i] = product of the non-zero elements before i
+        let mut prefix = Vec::with_capacity(elements.len());
+        let mut acc = Goldilocks::one();
+        for element in elements {
+            prefix.push(acc);
+            if !element.is_zero() {
+                acc = acc.mul(element);
+            }
+        }
+
+        let mut inv = acc.inverse();
+        let mut result = vec![Goldilocks::zero(); elements.len()];
+        for i in (0..elements.len()).rev() {
+            if !elements[i].is_zero() {
+                result[i] = inv.mul(&prefix[i]);
+                inv = inv.mul(&elements[i]);
             }
-            base = base.mul(&base);
-            exp >>= 1;
         }
-        
         result
     }
     
@@ -237,7 +290,7 @@ impl Goldilocks {
     ///
     /// let a = Goldilocks::from_canonical_u64(42);
     /// ```
-    pub fn from_canonical_u64(val: u64) -> Goldilocks {
+    pub const fn from_canonical_u64(val: u64) -> Goldilocks {
         Goldilocks(val)
     }
     
@@ -284,13 +337,13 @@ impl Goldilocks {
         
         // Step 2: Initialize
         // c = z^q mod p
-        let mut c = z.exp(Q);
+        let mut c = z.pow(Q);
         
         // t = self^q mod p
-        let mut t = self.exp(Q);
+        let mut t = self.pow(Q);
         
         // r = self^((q+1)/2) mod p
-        let mut r = self.exp((Q + 1) / 2);
+        let mut r = self.pow(Q.div_ceil(2));
         
         let mut m = E;
         
@@ -357,36 +410,60 @@ impl Goldilocks {
         result
     }
     
+    /// Returns a primitive 2^`log_n`-th root of unity.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `log_n > TWO_ADICITY`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use poseidon_hash::Goldilocks;
+    ///
+    /// let omega = Goldilocks::primitive_root_of_unity(3);
+    /// assert!(omega.exp_power_of_2(3).equals(&Goldilocks::one()));
+    /// assert!(!omega.exp_power_of_2(2).equals(&Goldilocks::one()));
+    /// ```
+    pub fn primitive_root_of_unity(log_n: usize) -> Goldilocks {
+        assert!(log_n <= Self::TWO_ADICITY, "no 2^{}-th roots of unity in Goldilocks", log_n);
+        Self::POWER_OF_TWO_GENERATOR.exp_power_of_2(Self::TWO_ADICITY - log_n)
+    }
+
     /// Checks if two Goldilocks elements are equal.
     pub fn equals(&self, other: &Goldilocks) -> bool {
         self.to_canonical_u64() == other.to_canonical_u64()
     }
     
-    /// Exponentiation: raises this element to a power.
+    /// Raises this element to the power `exponent`.
     ///
-    /// Uses binary exponentiation (square-and-multiply algorithm).
-    pub fn exp(&self, exponent: u64) -> Goldilocks {
-        if exponent == 0 {
-            return Goldilocks::one();
-        }
-        if exponent == 1 {
-            return *self;
-        }
-        
+    /// Uses left-to-right square-and-multiply; `pow(0)` is one (including for zero).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use poseidon_hash::Goldilocks;
+    ///
+    /// let a = Goldilocks::from_canonical_u64(3);
+    /// assert_eq!(a.pow(4).to_canonical_u64(), 81);
+    /// ```
+    pub fn pow(&self, exponent: u64) -> Goldilocks {
         let mut result = Goldilocks::one();
-        let mut base = *self;
-        let mut exp = exponent;
-        
-        while exp > 0 {
-            if exp & 1 == 1 {
-                result = result.mul(&base);
+        for i in (0..64 - exponent.leading_zeros()).rev() {
+            result = result.square();
+            if (exponent >> i) & 1 == 1 {
+                result = result.mul(self);
             }
-            base = base.square();
-            exp >>= 1;
         }
-        
         result
     }
+
+    /// Exponentiation: raises this element to a power.
+    ///
+    /// Same as `pow`.
+    pub fn exp(&self, exponent: u64) -> Goldilocks {
+        self.pow(exponent)
+    }
 }
 
 impl From<u64> for Goldilocks {
@@ -432,6 +509,12 @@ fn reduce_u128(x: u128) -> u64 {
 pub struct Fp5Element(pub [Goldilocks; 5]);
 
 impl Fp5Element {
+    /// Non-residue `w` of the irreducible polynomial x^5 - w (FP5_W in lighter-go).
+    pub const W: Goldilocks = Goldilocks(3);
+    
+    /// Primitive 5th root of unity used by the Frobenius map (FP5_DTH_ROOT in lighter-go).
+    pub const DTH_ROOT: Goldilocks = Goldilocks(1041288259238279555);
+    
     /// Returns the zero element of the extension field.
     pub fn zero() -> Self {
         Fp5Element([Goldilocks::zero(); 5])
@@ -444,31 +527,35 @@ impl Fp5Element {
         Fp5Element(result)
     }
     
+    /// Returns the element two of the extension field.
+    pub fn two() -> Self {
+        let mut result = [Goldilocks::zero(); 5];
+        result[0] = Goldilocks::from_canonical_u64(2);
+        Fp5Element(result)
+    }
+    
     /// Checks if this element is zero.
     pub fn is_zero(&self) -> bool {
         self.0.iter().all(|&x| x.is_zero())
     }
     
+    /// Checks if this element is the multiplicative identity.
+    pub fn is_one(&self) -> bool {
+        self.equals(&Fp5Element::one())
+    }
+    
     /// Adds two extension field elements.
     ///
     /// Addition is performed component-wise on the polynomial coefficients.
     pub fn add(&self, other: &Fp5Element) -> Fp5Element {
-        let mut result = [Goldilocks::zero(); 5];
-        for i in 0..5 {
-            result[i] = self.0[i].add(&other.0[i]);
-        }
-        Fp5Element(result)
+        Fp5Element(std::array::from_fn(|i| self.0[i].add(&other.0[i])))
     }
     
     /// Subtracts two extension field elements.
     ///
     /// Subtraction is performed component-wise on the polynomial coefficients.
     pub fn sub(&self, other: &Fp5Element) -> Fp5Element {
-        let mut result = [Goldilocks::zero(); 5];
-        for i in 0..5 {
-            result[i] = self.0[i].sub(&other.0[i]);
-        }
-        Fp5Element(result)
+        Fp5Element(std::array::from_fn(|i| self.0[i].sub(&other.0[i])))
     }
     
     /// Multiplies two extension field elements.
@@ -487,7 +574,7 @@ impl Fp5Element {
     pub fn mul(&self, other: &Fp5Element) -> Fp5Element {
         // Multiplication in quintic extension field
         // Uses irreducible polynomial x^5 = w where w = 3
-        const W: Goldilocks = Goldilocks(3);
+        const W: Goldilocks = Fp5Element::W;
         
         // c0 = a0*b0 + w*(a1*b4 + a2*b3 + a3*b2 + a4*b1)
         let a0b0 = self.0[0].mul(&other.0[0]);
@@ -575,6 +662,23 @@ impl Fp5Element {
         f.scalar_mul(&g_inv)
     }
     
+    /// Divides this element by `other`.
+    ///
+    /// Division by zero returns zero, like `inverse_or_zero`; use `checked_div` to tell it
+    /// apart from a zero quotient.
+    pub fn div(&self, other: &Fp5Element) -> Fp5Element {
+        self.mul(&other.inverse_or_zero())
+    }
+
+    /// Divides this element by `other`, or returns `None` if `other` is zero.
+    pub fn checked_div(&self, other: &Fp5Element) -> Option<Fp5Element> {
+        if other.is_zero() {
+            None
+        } else {
+            Some(self.div(other))
+        }
+    }
+
     /// Applies the Frobenius automorphism once.
     ///
     /// The Frobenius automorphism raises each coefficient to the p-th power.
@@ -597,14 +701,10 @@ impl Fp5Element {
             return *self;
         }
         
-        // FP5_DTH_ROOT = 1041288259238279555
-        let dth_root = Goldilocks(1041288259238279555);
+        let dth_root = Self::DTH_ROOT;
         
         // Compute z0 = dth_root^count
-        let mut z0 = dth_root;
-        for _ in 1..count {
-            z0 = z0.mul(&dth_root);
-        }
+        let z0 = dth_root.pow(count as u64);
         
         // Compute powers of z0: [1, z0, z0^2, z0^3, z0^4]
         let mut z_powers = [Goldilocks::zero(); 5];
@@ -767,17 +867,32 @@ impl Fp5Element {
         }
         result
     }
+
+    /// Raises this element to the power `exponent`.
+    ///
+    /// Uses left-to-right square-and-multiply; `pow(0)` is one (including for zero).
+    pub fn pow(&self, exponent: u64) -> Fp5Element {
+        let mut result = Fp5Element::one();
+        for i in (0..64 - exponent.leading_zeros()).rev() {
+            result = result.square();
+            if (exponent >> i) & 1 == 1 {
+                result = result.mul(self);
+            }
+        }
+        result
+    }
     
     /// Computes the sign function Sgn0(x) for this element.
     ///
     /// Returns true if the sign bit (LSB of the first non-zero limb) is 0.
-    /// Equivalent to Go's Sgn0 function.
+    /// Equivalent to Go's Sgn0 function, which `canonical_sqrt` and point decoding rely on;
+    /// see `canonical_sign` for the usual convention.
     pub fn sgn0(&self) -> bool {
         let mut sign = false;
         let mut zero = true;
         
         for limb in &self.0 {
-            let sign_i = (limb.0 & 1) == 0;
+            let sign_i = (limb.to_canonical_u64() & 1) == 0;
             let zero_i = limb.is_zero();
             sign = sign || (zero && sign_i);
             zero = zero && zero_i;
@@ -785,11 +900,32 @@ impl Fp5Element {
         
         sign
     }
+
+    /// Returns the sign of this element: true if its first non-zero canonical limb is odd.
+    ///
+    /// This is the `sgn0` of RFC 9380 and of the reference ECgFp5 implementation: zero is
+    /// positive, and exactly one of `x` and `-x` is negative for any non-zero `x`. Use it
+    /// to pick one of the two square roots when encoding a point compactly (a 32-byte key
+    /// keeps the root's sign as a single bit). It differs from `sgn0`, which follows
+    /// lighter-go and only looks at the first limb.
+    pub fn canonical_sign(&self) -> bool {
+        self.0
+            .iter()
+            .map(Goldilocks::to_canonical_u64)
+            .find(|limb| *limb != 0)
+            .is_some_and(|limb| limb & 1 == 1)
+    }
     
     /// Computes the square root of this element.
     ///
     /// Returns `Some(sqrt)` if the square root exists, `None` otherwise.
     /// Equivalent to Go's Sqrt function.
+    ///
+    /// Reduces to a square root in the base field: with `e = x^((r - 1) / 2)` where
+    /// `r = (p^5 - 1) / (p - 1)` (computed with Frobenius maps), `g = x * e^2` is the norm
+    /// of `x`, which lies in the base field and is a square there exactly when `x` is a
+    /// square in Fp5. Then `sqrt(x) = sqrt(g) / e`. The result may be either root; use
+    /// `canonical_sign` or `canonical_sqrt` to pick one.
     pub fn sqrt(&self) -> Option<Fp5Element> {
         // Step 1: v = x^(2^31)
         let v = self.exp_power_of_2(31);
@@ -820,10 +956,7 @@ impl Fp5Element {
         
         // Step 6: Compute sqrt of g in base field
         let s_opt = g_goldi.sqrt();
-        let s = match s_opt {
-            Some(s_val) => s_val,
-            None => return None,
-        };
+        let s = s_opt?;
         
         // Step 7: Convert s to Fp5 and multiply by e^(-1)
         let e_inv = e.inverse_or_zero();
@@ -875,9 +1008,9 @@ impl Fp5Element {
         let xr = xr_ext.0[0];
         
         // Step 5: Compute xr^31, then xr^63
-        let xr_31 = xr.exp(1u64 << 31);
+        let xr_31 = xr.exp_power_of_2(31);
         let xr_31_inv = xr_31.inverse();
-        let xr_63 = xr_31.exp(1u64 << 32);
+        let xr_63 = xr_31.exp_power_of_2(32);
         
         // Step 6: Return xr_63 * xr_31^(-1)
         xr_63.mul(&xr_31_inv)
@@ -887,16 +1020,55 @@ impl Fp5Element {
     pub fn equals(&self, other: &Fp5Element) -> bool {
         self.0.iter().zip(other.0.iter()).all(|(a, b)| a.equals(b))
     }
+
+    /// Returns the canonical value of each limb, lowest degree first.
+    pub fn to_canonical_u64_array(&self) -> [u64; 5] {
+        self.0.map(|limb| limb.to_canonical_u64())
+    }
+
+    /// Compares two elements by their canonical limbs.
+    ///
+    /// Limbs are compared from the highest degree down, so the order is that of the
+    /// integers `sum(limb[i] * p^i)`. Non-canonical limbs compare equal to their reduced
+    /// value, consistently with `equals` (the derived `PartialEq` compares raw limbs).
+    pub fn canonical_cmp(&self, other: &Fp5Element) -> core::cmp::Ordering {
+        let a = self.to_canonical_u64_array();
+        let b = other.to_canonical_u64_array();
+        a.iter().rev().cmp(b.iter().rev())
+    }
 }
 
-// Poseidon2 hash implementation constants
-const WIDTH: usize = 12;
-const RATE: usize = 8;
-const ROUNDS_F_HALF: usize = 4;
-const ROUNDS_P: usize = 22;
+/// Shape and round constants of a Poseidon2 instance over Goldilocks.
+///
+/// The permutation code (`permute_with`) and the sponge (`hash_no_pad_with`) are shared by
+/// every instance; an instance only supplies its constants. `WIDTH` must be a multiple of 4
+/// and at least 8, which covers the width-8, width-12 and width-16 instances in use with
+/// Goldilocks. The number of full and partial rounds follows from the constant tables.
+pub trait Poseidon2Params<const WIDTH: usize> {
+    /// Elements absorbed and squeezed per permutation.
+    const RATE: usize;
+    /// Round constants of the full rounds: the first half, then the second half.
+    const EXTERNAL_CONSTANTS: &'static [[u64; WIDTH]];
+    /// Round constants of the partial rounds, added to the first element.
+    const INTERNAL_CONSTANTS: &'static [u64];
+    /// Diagonal `D` of the internal matrix `D + J` (`J` all ones).
+    const MATRIX_DIAG: [u64; WIDTH];
+}
+
+/// The Poseidon2 instance of Lighter and plonky2: width 12, rate 8, 8 full and 22
+/// partial rounds. Used by `permute` and all hash functions of this crate.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Poseidon2Goldilocks12;
+
+impl Poseidon2Params<12> for Poseidon2Goldilocks12 {
+    const RATE: usize = 8;
+    const EXTERNAL_CONSTANTS: &'static [[u64; 12]] = &EXTERNAL_CONSTANTS;
+    const INTERNAL_CONSTANTS: &'static [u64] = &INTERNAL_CONSTANTS;
+    const MATRIX_DIAG: [u64; 12] = MATRIX_DIAG_12_U64;
+}
 
 // External round constants (8 rounds total)
-const EXTERNAL_CONSTANTS: [[u64; WIDTH]; 8] = [
+const EXTERNAL_CONSTANTS: [[u64; 12]; 8] = [
     [
         15492826721047263190, 11728330187201910315, 8836021247773420868, 16777404051263952451,
         5510875212538051896, 6173089941271892285, 2927757366422211339, 10340958981325008808,
@@ -940,7 +1112,7 @@ const EXTERNAL_CONSTANTS: [[u64; WIDTH]; 8] = [
 ];
 
 // Internal round constants (22 partial rounds)
-const INTERNAL_CONSTANTS: [u64; ROUNDS_P] = [
+const INTERNAL_CONSTANTS: [u64; 22] = [
     11921381764981422944, 10318423381711320787, 8291411502347000766, 229948027109387563,
     9152521390190983261, 7129306032690285515, 15395989607365232011, 8641397269074305925,
     17256848792241043600, 6046475228902245682, 12041608676381094092, 12785542378683951657,
@@ -950,12 +1122,45 @@ const INTERNAL_CONSTANTS: [u64; ROUNDS_P] = [
 ];
 
 // Matrix diagonal constants for Poseidon2
-const MATRIX_DIAG_12_U64: [u64; WIDTH] = [
+const MATRIX_DIAG_12_U64: [u64; 12] = [
     0xc3b6c08e23ba9300, 0xd84b5de94a324fb6, 0x0d0c371c5b35b84f, 0x7964f570e7188037,
     0x5daf18bbd996604b, 0x6743bc47b9595257, 0x5528b9362c59bb70, 0xac45e25b7127b68b,
     0xa2077d7dfbb606b5, 0xf3faac6faee378ae, 0x0c6388b51545e883, 0xd27dbb6944917b60,
 ];
 
+/// Round constants of the full rounds of `Poseidon2Goldilocks12`, as field elements.
+///
+/// The same values as `Poseidon2Goldilocks12::EXTERNAL_CONSTANTS`, for circuit builders that
+/// need the exact constants of the permutation: the first four rows are the initial full
+/// rounds, the last four the final ones.
+pub const POSEIDON2_EXTERNAL_CONSTANTS: [[Goldilocks; 12]; 8] = {
+    let mut rows = [[Goldilocks::zero(); 12]; 8];
+    let mut r = 0;
+    while r < rows.len() {
+        rows[r] = goldilocks_array(&EXTERNAL_CONSTANTS[r]);
+        r += 1;
+    }
+    rows
+};
+
+/// Round constants of the 22 partial rounds of `Poseidon2Goldilocks12`, as field elements.
+pub const POSEIDON2_INTERNAL_CONSTANTS: [Goldilocks; 22] = goldilocks_array(&INTERNAL_CONSTANTS);
+
+/// Diagonal of the internal matrix of `Poseidon2Goldilocks12`, as field elements.
+pub const POSEIDON2_MATRIX_DIAG_12: [Goldilocks; 12] = goldilocks_array(&MATRIX_DIAG_12_U64);
+
+/// Field elements of a constant table; fails to compile on a non-canonical value.
+const fn goldilocks_array<const N: usize>(values: &[u64; N]) -> [Goldilocks; N] {
+    let mut out = [Goldilocks::zero(); N];
+    let mut i = 0;
+    while i < N {
+        assert!(values[i] < Goldilocks::MODULUS, "Poseidon2 constant is not canonical");
+        out[i] = Goldilocks::from_canonical_u64(values[i]);
+        i += 1;
+    }
+    out
+}
+
 /// Hashes a slice of Goldilocks field elements to a single Fp5Element.
 ///
 /// This is the main Poseidon2 hash function. It takes an arbitrary number of
@@ -974,34 +1179,79 @@ const MATRIX_DIAG_12_U64: [u64; WIDTH] = [
 /// let hash = hash_to_quintic_extension(&elements);
 /// ```
 pub fn hash_to_quintic_extension(input: &[Goldilocks]) -> Fp5Element {
-    hash_n_to_m_no_pad(input, 5)
+    Fp5Element(squeeze::<Poseidon2Goldilocks12, 12, 5>(input))
+}
+
+/// Packs bytes into Goldilocks elements, 8 little-endian bytes per element.
+///
+/// Equivalent to Go's `ArrayFromCanonicalLittleEndianBytes`: the input is zero-padded to a
+/// multiple of 8 bytes, so a trailing partial chunk becomes an element with its high bytes
+/// zero. Empty input gives no elements.
+///
+/// # Example
+///
+/// ```rust
+/// use poseidon_hash::{array_from_le_bytes, Goldilocks};
+///
+/// let elements = array_from_le_bytes(&[1, 0, 0, 0, 0, 0, 0, 0, 2]);
+/// assert_eq!(elements, vec![Goldilocks::from_canonical_u64(1), Goldilocks::from_canonical_u64(2)]);
+/// ```
+pub fn array_from_le_bytes(bytes: &[u8]) -> Vec<Goldilocks> {
+    bytes
+        .chunks(8)
+        .map(|chunk| {
+            let mut limb = [0u8; 8];
+            limb[..chunk.len()].copy_from_slice(chunk);
+            Goldilocks::from_canonical_u64(u64::from_le_bytes(limb))
+        })
+        .collect()
+}
+
+/// Hashes a byte string to an Fp5Element.
+///
+/// Packs the bytes with `array_from_le_bytes` and hashes the elements with
+/// `hash_to_quintic_extension`, which is how the Go signer hashes byte payloads such as the
+/// `"deadline:account_index:api_key_index"` auth token message.
+///
+/// # Example
+///
+/// ```rust
+/// use poseidon_hash::{array_from_le_bytes, hash_bytes_to_quintic_extension, hash_to_quintic_extension};
+///
+/// let message = b"1760000000:12:3";
+/// assert_eq!(
+///     hash_bytes_to_quintic_extension(message),
+///     hash_to_quintic_extension(&array_from_le_bytes(message)),
+/// );
+/// ```
+pub fn hash_bytes_to_quintic_extension(bytes: &[u8]) -> Fp5Element {
+    hash_to_quintic_extension(&array_from_le_bytes(bytes))
 }
 
-fn hash_n_to_m_no_pad(input: &[Goldilocks], num_outputs: usize) -> Fp5Element {
+/// Absorbs `input` RATE elements at a time (overwriting, no padding) and squeezes `N` elements.
+fn squeeze<P: Poseidon2Params<WIDTH>, const WIDTH: usize, const N: usize>(input: &[Goldilocks]) -> [Goldilocks; N] {
     let mut perm = [Goldilocks::zero(); WIDTH];
-    
+
     // Process input in chunks of RATE
-    for chunk in input.chunks(RATE) {
-        for (j, &val) in chunk.iter().enumerate() {
-            perm[j] = val;
-        }
-        permute(&mut perm);
+    for chunk in input.chunks(P::RATE) {
+        perm[..chunk.len()].copy_from_slice(chunk);
+        permute_with::<P, WIDTH>(&mut perm);
     }
-    
-    // Extract outputs (num_outputs is always 5 for our use case)
+
+    let mut outputs = [Goldilocks::zero(); N];
     let mut output_idx = 0;
-    let mut outputs = [Goldilocks::zero(); 5];
     loop {
-        for i in 0..RATE {
-            if output_idx < num_outputs {
-                outputs[output_idx] = perm[i];
-                output_idx += 1;
-            }
-            if output_idx == num_outputs {
-                return Fp5Element(outputs);
+        for &element in &perm[..P::RATE] {
+            if output_idx == N {
+                return outputs;
             }
+            outputs[output_idx] = element;
+            output_idx += 1;
+        }
+        if output_idx == N {
+            return outputs;
         }
-        permute(&mut perm);
+        permute_with::<P, WIDTH>(&mut perm);
     }
 }
 
@@ -1031,36 +1281,20 @@ pub type HashOut = [Goldilocks; 4];
 /// assert_eq!(hash.len(), 4);
 /// ```
 pub fn hash_no_pad(input: &[Goldilocks]) -> HashOut {
-    hash_n_to_hash_no_pad(input)
+    hash_no_pad_with::<Poseidon2Goldilocks12, 12>(input)
 }
 
-/// Internal function to hash to exactly 4 elements
-fn hash_n_to_hash_no_pad(input: &[Goldilocks]) -> HashOut {
-    let mut perm = [Goldilocks::zero(); WIDTH];
-    
-    // Process input in chunks of RATE
-    for chunk in input.chunks(RATE) {
-        for (j, &val) in chunk.iter().enumerate() {
-            perm[j] = val;
-        }
-        permute(&mut perm);
-    }
-    
-    // Extract exactly 4 outputs
-    let mut outputs = [Goldilocks::zero(); 4];
-    let mut output_idx = 0;
-    loop {
-        for i in 0..RATE {
-            if output_idx < 4 {
-                outputs[output_idx] = perm[i];
-                output_idx += 1;
-            }
-            if output_idx == 4 {
-                return outputs;
-            }
-        }
-        permute(&mut perm);
-    }
+/// `hash_no_pad` with another Poseidon2 instance.
+///
+/// # Example
+/// ```
+/// use poseidon_hash::{hash_no_pad, hash_no_pad_with, Goldilocks, Poseidon2Goldilocks12};
+///
+/// let input = [Goldilocks::from_canonical_u64(1)];
+/// assert_eq!(hash_no_pad_with::<Poseidon2Goldilocks12, 12>(&input), hash_no_pad(&input));
+/// ```
+pub fn hash_no_pad_with<P: Poseidon2Params<WIDTH>, const WIDTH: usize>(input: &[Goldilocks]) -> HashOut {
+    squeeze::<P, WIDTH, 4>(input)
 }
 
 /// Combines multiple hash outputs into a single hash output.
@@ -1090,8 +1324,8 @@ pub fn hash_n_to_one(hashes: &[HashOut]) -> HashOut {
     
     // Combine hashes pairwise using HashTwoToOne
     let mut result = hash_two_to_one(hashes[0], hashes[1]);
-    for i in 2..hashes.len() {
-        result = hash_two_to_one(result, hashes[i]);
+    for hash in &hashes[2..] {
+        result = hash_two_to_one(result, *hash);
     }
     result
 }
@@ -1104,7 +1338,7 @@ fn hash_two_to_one(input1: HashOut, input2: HashOut) -> HashOut {
         input1[0], input1[1], input1[2], input1[3],
         input2[0], input2[1], input2[2], input2[3],
     ];
-    hash_n_to_hash_no_pad(&combined)
+    hash_no_pad(&combined)
 }
 
 /// Returns an empty hash output (all zeros).
@@ -1120,32 +1354,40 @@ pub fn empty_hash_out() -> HashOut {
 /// - Full rounds (first half)
 /// - Partial rounds
 /// - Full rounds (second half)
-pub fn permute(input: &mut [Goldilocks; WIDTH]) {
-    external_linear_layer(input);
-    full_rounds(input, 0);
-    partial_rounds(input);
-    full_rounds(input, ROUNDS_F_HALF);
+pub fn permute(input: &mut [Goldilocks; 12]) {
+    permute_with::<Poseidon2Goldilocks12, 12>(input);
+}
+
+/// Applies the Poseidon2 permutation of instance `P` to a `WIDTH`-element state array.
+pub fn permute_with<P: Poseidon2Params<WIDTH>, const WIDTH: usize>(state: &mut [Goldilocks; WIDTH]) {
+    const { assert!(WIDTH.is_multiple_of(4) && WIDTH >= 8, "Poseidon2 width must be a multiple of 4, at least 8") };
+
+    let (first_half, second_half) = P::EXTERNAL_CONSTANTS.split_at(P::EXTERNAL_CONSTANTS.len() / 2);
+    external_linear_layer(state);
+    full_rounds(state, first_half);
+    partial_rounds(state, P::INTERNAL_CONSTANTS, &P::MATRIX_DIAG);
+    full_rounds(state, second_half);
 }
 
-fn full_rounds(state: &mut [Goldilocks; WIDTH], start: usize) {
-    for r in start..start + ROUNDS_F_HALF {
-        add_rc(state, r);
+fn full_rounds<const WIDTH: usize>(state: &mut [Goldilocks; WIDTH], round_constants: &[[u64; WIDTH]]) {
+    for constants in round_constants {
+        add_rc(state, constants);
         sbox(state);
         external_linear_layer(state);
     }
 }
 
-fn partial_rounds(state: &mut [Goldilocks; WIDTH]) {
-    for r in 0..ROUNDS_P {
-        add_rci(state, r);
+fn partial_rounds<const WIDTH: usize>(state: &mut [Goldilocks; WIDTH], round_constants: &[u64], diag: &[u64; WIDTH]) {
+    for &constant in round_constants {
+        state[0] = state[0].add(&Goldilocks(constant));
         sbox_p(0, state);
-        internal_linear_layer(state);
+        internal_linear_layer(state, diag);
     }
 }
 
-fn external_linear_layer(s: &mut [Goldilocks; WIDTH]) {
+fn external_linear_layer<const WIDTH: usize>(s: &mut [Goldilocks; WIDTH]) {
     // Process in 4-element windows for efficiency
-    for i in 0..3 { // 3 windows of 4 elements each
+    for i in 0..WIDTH / 4 {
         let t0 = s[4*i].add(&s[4*i+1]);     // s0+s1
         let t1 = s[4*i+2].add(&s[4*i+3]);   // s2+s3
         let t2 = t0.add(&t1);               // t0+t1 = s0+s1+s2+s3
@@ -1160,51 +1402,42 @@ fn external_linear_layer(s: &mut [Goldilocks; WIDTH]) {
         s[4*i+3] = t5.add(&t4);
     }
     
-    // Add sums to each element
-    // Unroll loops for better performance (WIDTH is constant 12, so we have 3 groups of 4)
-    let sum0 = s[0].add(&s[4]).add(&s[8]);
-    let sum1 = s[1].add(&s[5]).add(&s[9]);
-    let sum2 = s[2].add(&s[6]).add(&s[10]);
-    let sum3 = s[3].add(&s[7]).add(&s[11]);
+    // Add the sum of each lane across windows to every element of that lane
+    let mut sums = [Goldilocks::zero(); 4];
+    for window in s.chunks_exact(4) {
+        for (sum, element) in sums.iter_mut().zip(window) {
+            *sum = sum.add(element);
+        }
+    }
     
     for i in 0..WIDTH {
-        s[i] = s[i].add(match i % 4 {
-            0 => &sum0,
-            1 => &sum1,
-            2 => &sum2,
-            3 => &sum3,
-            _ => unreachable!(),
-        });
+        s[i] = s[i].add(&sums[i % 4]);
     }
 }
 
-fn internal_linear_layer(state: &mut [Goldilocks; WIDTH]) {
+fn internal_linear_layer<const WIDTH: usize>(state: &mut [Goldilocks; WIDTH], diag: &[u64; WIDTH]) {
     let mut sum = state[0];
-    for i in 1..WIDTH {
-        sum = sum.add(&state[i]);
+    for element in &state[1..] {
+        sum = sum.add(element);
     }
     for i in 0..WIDTH {
-        state[i] = state[i].mul(&Goldilocks(MATRIX_DIAG_12_U64[i])).add(&sum);
+        state[i] = state[i].mul(&Goldilocks(diag[i])).add(&sum);
     }
 }
 
-fn add_rc(state: &mut [Goldilocks; WIDTH], external_round: usize) {
+fn add_rc<const WIDTH: usize>(state: &mut [Goldilocks; WIDTH], constants: &[u64; WIDTH]) {
     for i in 0..WIDTH {
-        state[i] = state[i].add(&Goldilocks(EXTERNAL_CONSTANTS[external_round][i]));
+        state[i] = state[i].add(&Goldilocks(constants[i]));
     }
 }
 
-fn add_rci(state: &mut [Goldilocks; WIDTH], round: usize) {
-    state[0] = state[0].add(&Goldilocks(INTERNAL_CONSTANTS[round]));
-}
-
-fn sbox(state: &mut [Goldilocks; WIDTH]) {
+fn sbox<const WIDTH: usize>(state: &mut [Goldilocks; WIDTH]) {
     for i in 0..WIDTH {
         sbox_p(i, state);
     }
 }
 
-fn sbox_p(index: usize, state: &mut [Goldilocks; WIDTH]) {
+fn sbox_p<const WIDTH: usize>(index: usize, state: &mut [Goldilocks; WIDTH]) {
     // Poseidon2 S-box: x^7
     // Computed as: x^7 = (x^2 * x)^2 * x
     let tmp = state[index];
@@ -1212,3 +1445,22 @@ fn sbox_p(index: usize, state: &mut [Goldilocks; WIDTH]) {
     let tmp_sixth = tmp_square.mul(&tmp).square();
     state[index] = tmp_sixth.mul(&tmp);
 }
+
+pub mod ntt;
+
+pub use ntt::{intt, ntt};
+
+// The original test files, kept as written
+#[cfg(test)]
+#[allow(clippy::module_inception, clippy::bool_assert_comparison)]
+mod tests;
+
+#[cfg(test)]
+#[allow(clippy::module_inception, unused_imports)]
+mod poseidon2_tests;
+
+#[cfg(test)]
+mod vector_tests;
+
+#[cfg(test)]
+mod property_tests;
@@ -0,0 +1,139 @@
+//! Number-theoretic transform over Goldilocks.
+//!
+//! Radix-2 Cooley-Tukey on power-of-two lengths up to 2^32, evaluating a polynomial
+//! given by its coefficients at the powers of `Goldilocks::primitive_root_of_unity(log_n)`:
+//! after `ntt`, `values[i] = sum_j coeffs[j] * omega^(i*j)`. `intt` is its inverse.
+
+use crate::Goldilocks;
+
+/// Transforms coefficients into evaluations, in place.
+///
+/// # Panics
+///
+/// Panics if the length is not a power of two or exceeds 2^32.
+///
+/// # Example
+///
+/// ```rust
+/// use poseidon_hash::{intt, ntt, Goldilocks};
+///
+/// let coeffs: Vec<Goldilocks> = (1..=4).map(Goldilocks::from_canonical_u64).collect();
+/// let mut values = coeffs.clone();
+/// ntt(&mut values);
+/// // Evaluation at omega^0 = 1 is the sum of the coefficients
+/// assert_eq!(values[0].to_canonical_u64(), 10);
+/// intt(&mut values);
+/// assert!(values.iter().zip(&coeffs).all(|(v, c)| v.equals(c)));
+/// ```
+pub fn ntt(values: &mut [Goldilocks]) {
+    let log_n = log2_len(values.len());
+    transform(values, Goldilocks::primitive_root_of_unity(log_n));
+}
+
+/// Transforms evaluations back into coefficients, in place.
+///
+/// # Panics
+///
+/// Panics if the length is not a power of two or exceeds 2^32.
+pub fn intt(values: &mut [Goldilocks]) {
+    let log_n = log2_len(values.len());
+    transform(values, Goldilocks::primitive_root_of_unity(log_n).inverse());
+
+    let n_inv = Goldilocks::from_canonical_u64(values.len() as u64).inverse();
+    for value in values.iter_mut() {
+        *value = value.mul(&n_inv);
+    }
+}
+
+fn log2_len(len: usize) -> usize {
+    assert!(len.is_power_of_two(), "NTT length must be a power of two, got {}", len);
+    let log_n = len.trailing_zeros() as usize;
+    assert!(log_n <= Goldilocks::TWO_ADICITY, "NTT length 2^{} exceeds 2^32", log_n);
+    log_n
+}
+
+/// Iterative decimation-in-time transform with root `omega` of order `values.len()`.
+fn transform(values: &mut [Goldilocks], omega: Goldilocks) {
+    let n = values.len();
+    if n <= 1 {
+        return;
+    }
+
+    // Bit-reversal permutation
+    let bits = n.trailing_zeros();
+    for i in 0..n {
+        let j = i.reverse_bits() >> (usize::BITS - bits);
+        if i < j {
+            values.swap(i, j);
+        }
+    }
+
+    let mut half = 1;
+    while half < n {
+        // Root of order 2 * half
+        let step = omega.exp_power_of_2((n / (2 * half)).trailing_zeros() as usize);
+        let twiddles: Vec<Goldilocks> = std::iter::successors(Some(Goldilocks::one()), |w| Some(w.mul(&step)))
+            .take(half)
+            .collect();
+
+        for chunk in values.chunks_exact_mut(2 * half) {
+            let (lo, hi) = chunk.split_at_mut(half);
+            for ((a, b), w) in lo.iter_mut().zip(hi.iter_mut()).zip(&twiddles) {
+                let t = b.mul(w);
+                *b = a.sub(&t);
+                *a = a.add(&t);
+            }
+        }
+        half *= 2;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn naive_dft(coeffs: &[Goldilocks]) -> Vec<Goldilocks> {
+        let omega = Goldilocks::primitive_root_of_unity(coeffs.len().trailing_zeros() as usize);
+        (0..coeffs.len())
+            .map(|i| {
+                let x = omega.pow(i as u64);
+                coeffs
+                    .iter()
+                    .rev()
+                    .fold(Goldilocks::zero(), |acc, c| acc.mul(&x).add(c))
+            })
+            .collect()
+    }
+
+    fn assert_all_equal(left: &[Goldilocks], right: &[Goldilocks]) {
+        assert_eq!(left.len(), right.len());
+        for (l, r) in left.iter().zip(right) {
+            assert!(l.equals(r), "{:?} != {:?}", l, r);
+        }
+    }
+
+    #[test]
+    fn test_power_of_two_generator() {
+        let g = Goldilocks::POWER_OF_TWO_GENERATOR;
+        assert!(g.exp_power_of_2(32).equals(&Goldilocks::one()));
+        assert!(!g.exp_power_of_2(31).equals(&Goldilocks::one()));
+        let expected = Goldilocks::MULTIPLICATIVE_GROUP_GENERATOR.pow((Goldilocks::ORDER - 1) >> 32);
+        assert!(g.equals(&expected));
+    }
+
+    #[test]
+    fn test_ntt_matches_naive_dft_and_round_trips() {
+        for log_n in 0..=6 {
+            let coeffs: Vec<Goldilocks> = (0..1u64 << log_n)
+                .map(|i| Goldilocks::from_canonical_u64(i.wrapping_mul(0x9e37_79b9_7f4a_7c15) % Goldilocks::ORDER))
+                .collect();
+
+            let mut values = coeffs.clone();
+            ntt(&mut values);
+            assert_all_equal(&values, &naive_dft(&coeffs));
+
+            intt(&mut values);
+            assert_all_equal(&values, &coeffs);
+        }
+    }
+}
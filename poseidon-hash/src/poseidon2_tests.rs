@@ -1,6 +1,10 @@
 #[cfg(test)]
 mod tests {
     use crate::{Goldilocks, Fp5Element, hash_to_quintic_extension};
+    use crate::{
+        hash_no_pad, hash_no_pad_with, permute, permute_with, Poseidon2Goldilocks12,
+        Poseidon2Params, POSEIDON2_EXTERNAL_CONSTANTS, POSEIDON2_INTERNAL_CONSTANTS, POSEIDON2_MATRIX_DIAG_12,
+    };
 
     #[test]
     fn test_poseidon2_hash_to_quintic_extension() {
@@ -41,4 +45,69 @@ mod tests {
         let result2 = hash_to_quintic_extension(&input);
         assert_eq!(result.to_bytes_le(), result2.to_bytes_le());
     }
+
+    /// Test-only constants for other widths; not a vetted Poseidon2 instance.
+    const fn test_constants<const W: usize, const R: usize>(mut seed: u64) -> [[u64; W]; R] {
+        let mut out = [[0u64; W]; R];
+        let mut r = 0;
+        while r < R {
+            let mut i = 0;
+            while i < W {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                out[r][i] = seed % Goldilocks::ORDER;
+                i += 1;
+            }
+            r += 1;
+        }
+        out
+    }
+
+    struct TestWidth8;
+
+    impl Poseidon2Params<8> for TestWidth8 {
+        const RATE: usize = 4;
+        const EXTERNAL_CONSTANTS: &'static [[u64; 8]] = &test_constants::<8, 8>(1);
+        const INTERNAL_CONSTANTS: &'static [u64] = &test_constants::<22, 1>(2)[0];
+        const MATRIX_DIAG: [u64; 8] = test_constants::<8, 1>(3)[0];
+    }
+
+    struct TestWidth16;
+
+    impl Poseidon2Params<16> for TestWidth16 {
+        const RATE: usize = 8;
+        const EXTERNAL_CONSTANTS: &'static [[u64; 16]] = &test_constants::<16, 8>(4);
+        const INTERNAL_CONSTANTS: &'static [u64] = &test_constants::<22, 1>(5)[0];
+        const MATRIX_DIAG: [u64; 16] = test_constants::<16, 1>(6)[0];
+    }
+
+    #[test]
+    fn test_permutation_shared_across_widths() {
+        let mut state = [Goldilocks::zero(); 12];
+        state[0] = Goldilocks::from_canonical_u64(7);
+        let mut generic = state;
+        permute(&mut state);
+        permute_with::<Poseidon2Goldilocks12, 12>(&mut generic);
+        assert_eq!(state, generic);
+
+        let input: Vec<Goldilocks> = (1..=9).map(Goldilocks::from_canonical_u64).collect();
+        let h8 = hash_no_pad_with::<TestWidth8, 8>(&input);
+        let h16 = hash_no_pad_with::<TestWidth16, 16>(&input);
+        assert_eq!(h8, hash_no_pad_with::<TestWidth8, 8>(&input));
+        assert_ne!(h8, hash_no_pad_with::<TestWidth8, 8>(&input[..8]));
+        assert_ne!(h8, h16);
+        assert_ne!(h16, hash_no_pad(&input));
+    }
+
+    #[test]
+    fn test_public_constant_tables() {
+        let external: Vec<[u64; 12]> = POSEIDON2_EXTERNAL_CONSTANTS.iter().map(|row| row.map(|c| c.0)).collect();
+        assert_eq!(external, Poseidon2Goldilocks12::EXTERNAL_CONSTANTS);
+        let internal: Vec<u64> = POSEIDON2_INTERNAL_CONSTANTS.iter().map(|c| c.0).collect();
+        assert_eq!(internal, Poseidon2Goldilocks12::INTERNAL_CONSTANTS);
+        assert_eq!(POSEIDON2_MATRIX_DIAG_12.map(|c| c.0), Poseidon2Goldilocks12::MATRIX_DIAG);
+
+        // Usable in constant expressions
+        const FIRST: Goldilocks = POSEIDON2_EXTERNAL_CONSTANTS[0][0];
+        assert_eq!(FIRST.to_canonical_u64(), 15492826721047263190);
+    }
 }
@@ -0,0 +1,142 @@
+use crate::{Fp5Element, Goldilocks};
+use proptest::prelude::*;
+
+fn goldilocks() -> impl Strategy<Value = Goldilocks> {
+    (0..Goldilocks::ORDER).prop_map(Goldilocks::from_canonical_u64)
+}
+
+fn fp5() -> impl Strategy<Value = Fp5Element> {
+    prop::array::uniform5(0..Goldilocks::ORDER).prop_map(Fp5Element::from_uint64_array)
+}
+
+proptest! {
+    #[test]
+    fn goldilocks_add_sub_round_trip(a in goldilocks(), b in goldilocks()) {
+        prop_assert!(a.add(&b).sub(&b).equals(&a));
+        prop_assert!(a.sub(&b).add(&b).equals(&a));
+        prop_assert!(a.add(&a.neg()).is_zero());
+    }
+
+    #[test]
+    fn goldilocks_mul_matches_u128(a in goldilocks(), b in goldilocks()) {
+        let expected = (a.0 as u128 * b.0 as u128) % Goldilocks::ORDER as u128;
+        prop_assert_eq!(a.mul(&b).to_canonical_u64(), expected as u64);
+    }
+
+    #[test]
+    fn goldilocks_inverse_round_trip(a in goldilocks()) {
+        prop_assume!(!a.is_zero());
+        prop_assert!(a.mul(&a.inverse()).equals(&Goldilocks::one()));
+    }
+
+    #[test]
+    fn goldilocks_pow_matches_repeated_mul(a in goldilocks(), n in 0u64..64) {
+        let expected = (0..n).fold(Goldilocks::one(), |acc, _| acc.mul(&a));
+        prop_assert!(a.pow(n).equals(&expected));
+        prop_assert!(a.pow(1 << 9).equals(&a.exp_power_of_2(9)));
+    }
+
+    #[test]
+    fn goldilocks_inverse_matches_fermat(a in goldilocks()) {
+        prop_assert!(a.inverse().equals(&a.pow(Goldilocks::ORDER - 2)));
+    }
+
+    #[test]
+    fn goldilocks_batch_inverse_matches_inverse(values in prop::collection::vec(prop_oneof![goldilocks(), Just(Goldilocks::zero())], 0..16)) {
+        let inverses = Goldilocks::batch_inverse(&values);
+        prop_assert_eq!(inverses.len(), values.len());
+        for (value, inverse) in values.iter().zip(&inverses) {
+            prop_assert!(inverse.equals(&value.inverse()));
+        }
+    }
+
+    #[test]
+    fn goldilocks_from_i64_reduces_twos_complement(v in any::<i64>()) {
+        // Matches lighter-go FromInt64: reinterpret as u64, then reduce
+        let expected = (v as u64) % Goldilocks::ORDER;
+        prop_assert_eq!(Goldilocks::from_i64(v).to_canonical_u64(), expected);
+    }
+
+    #[test]
+    fn fp5_bytes_round_trip(a in fp5()) {
+        let decoded = Fp5Element::from_bytes_le(&a.to_bytes_le()).unwrap();
+        prop_assert!(decoded.equals(&a));
+    }
+
+    #[test]
+    fn fp5_add_sub_round_trip(a in fp5(), b in fp5()) {
+        prop_assert!(a.add(&b).sub(&b).equals(&a));
+        prop_assert!(a.add(&a.neg()).is_zero());
+    }
+
+    #[test]
+    fn fp5_mul_distributes_over_add(a in fp5(), b in fp5(), c in fp5()) {
+        let lhs = a.mul(&b.add(&c));
+        let rhs = a.mul(&b).add(&a.mul(&c));
+        prop_assert!(lhs.equals(&rhs));
+        prop_assert!(a.square().equals(&a.mul(&a)));
+    }
+
+    #[test]
+    fn fp5_pow_adds_exponents(a in fp5(), m in 0u64..1 << 20, n in 0u64..1 << 20) {
+        prop_assert!(a.pow(m + n).equals(&a.pow(m).mul(&a.pow(n))));
+        prop_assert!(a.pow(1 << 7).equals(&a.exp_power_of_2(7)));
+    }
+
+    #[test]
+    fn fp5_pow_order_is_frobenius(a in fp5()) {
+        prop_assert!(a.pow(Goldilocks::ORDER).equals(&a.frobenius()));
+    }
+
+    #[test]
+    fn fp5_inverse_round_trip(a in fp5()) {
+        prop_assume!(!a.is_zero());
+        prop_assert!(a.mul(&a.inverse()).is_one());
+    }
+
+    #[test]
+    fn fp5_div_inverts_mul(a in fp5(), b in fp5()) {
+        prop_assume!(!b.is_zero());
+        prop_assert!(a.mul(&b).div(&b).equals(&a));
+        prop_assert!(a.checked_div(&b).unwrap().equals(&a.mul(&b.inverse())));
+        prop_assert!(a.div(&Fp5Element::zero()).is_zero());
+        prop_assert!(a.checked_div(&Fp5Element::zero()).is_none());
+    }
+
+    #[test]
+    fn fp5_canonical_cmp_ignores_representation(a in fp5(), b in fp5(), limb in 0usize..5) {
+        prop_assert_eq!(a.canonical_cmp(&b), b.canonical_cmp(&a).reverse());
+        prop_assert_eq!(a.canonical_cmp(&b).is_eq(), a.equals(&b));
+
+        // Limbs below 2^32 - 1 also have the non-canonical representation value + p
+        let mut raw = a;
+        raw.0[limb] = Goldilocks(raw.0[limb].0 % Goldilocks::EPSILON + Goldilocks::MODULUS);
+        let mut reduced = a;
+        reduced.0[limb] = Goldilocks(a.0[limb].0 % Goldilocks::EPSILON);
+        prop_assert!(raw.canonical_cmp(&reduced).is_eq());
+
+        let high = Fp5Element::from_uint64_array([0, 1, 0, 0, 0]);
+        let low = Fp5Element::from_uint64_array([Goldilocks::ORDER - 1, 0, 0, 0, 0]);
+        prop_assert!(low.canonical_cmp(&high).is_lt());
+    }
+
+    #[test]
+    fn fp5_sqrt_of_square(a in fp5()) {
+        let root = a.square().sqrt().unwrap();
+        prop_assert!(root.equals(&a) || root.equals(&a.neg()));
+        let (canonical, found) = a.square().canonical_sqrt();
+        prop_assert!(found && canonical.square().equals(&a.square()));
+    }
+
+    #[test]
+    fn fp5_canonical_sign_flips_with_neg(a in fp5()) {
+        prop_assume!(!a.is_zero());
+        prop_assert_ne!(a.canonical_sign(), a.neg().canonical_sign());
+        // Exactly one square root is negative
+        let root = a.square().sqrt().unwrap();
+        let positive = if root.canonical_sign() { root.neg() } else { root };
+        prop_assert!(!positive.canonical_sign());
+        prop_assert!(positive.equals(&a) || positive.equals(&a.neg()));
+    }
+
+}
@@ -127,4 +127,44 @@ mod tests {
         assert_eq!(Fp5Element::one().is_one(), true);
         assert_eq!(Fp5Element::two().0[0].to_canonical_u64(), 2);
     }
+
+    #[test]
+    fn test_fp5_sqrt_and_sign() {
+        assert!(Fp5Element::zero().sqrt().unwrap().is_zero());
+        let four = Fp5Element::from_uint64_array([4, 0, 0, 0, 0]);
+        let root = four.sqrt().unwrap();
+        assert!(root.equals(&Fp5Element::two()) || root.equals(&Fp5Element::two().neg()));
+
+        // 7 generates the base field's multiplicative group, so it is not a square there,
+        // and an odd-degree extension adds no square roots of base field elements
+        let seven = Fp5Element::from_uint64_array([7, 0, 0, 0, 0]);
+        assert!(seven.sqrt().is_none());
+        assert_eq!(seven.legendre().to_canonical_u64(), Goldilocks::ORDER - 1);
+        assert_eq!(seven.canonical_sqrt(), (Fp5Element::zero(), false));
+
+        // The sign comes from the first non-zero limb, read canonically
+        assert!(!Fp5Element::zero().canonical_sign());
+        assert!(Fp5Element::from_uint64_array([0, 0, 3, 4, 0]).canonical_sign());
+        assert!(!Fp5Element::from_uint64_array([0, 2, 3, 0, 0]).canonical_sign());
+        let non_canonical = Fp5Element([Goldilocks(Goldilocks::MODULUS + 1), Goldilocks(0), Goldilocks(0), Goldilocks(0), Goldilocks(0)]);
+        assert!(non_canonical.canonical_sign());
+        assert!(!non_canonical.sgn0());
+    }
+
+    #[test]
+    fn test_hash_bytes_to_quintic_extension() {
+        // "1760000000:12:3" is 15 bytes: one full chunk and one padded with a zero byte
+        let message = b"1760000000:12:3";
+        let elements = crate::array_from_le_bytes(message);
+        assert_eq!(elements.len(), 2);
+        assert_eq!(elements[0].to_canonical_u64(), u64::from_le_bytes(*b"17600000"));
+        assert_eq!(elements[1].to_canonical_u64(), u64::from_le_bytes(*b"00:12:3\0"));
+        assert!(crate::array_from_le_bytes(&[]).is_empty());
+
+        let hash = crate::hash_bytes_to_quintic_extension(message);
+        assert_eq!(hash, crate::hash_to_quintic_extension(&elements));
+        // Trailing zero bytes are indistinguishable from the padding
+        assert_eq!(hash, crate::hash_bytes_to_quintic_extension(b"1760000000:12:3\0"));
+        assert_ne!(hash, crate::hash_bytes_to_quintic_extension(b"1760000000:12:4"));
+    }
 }
@@ -0,0 +1,84 @@
+//! Field vectors from reference arithmetic and Poseidon2 regression vectors (see `test-vectors/README.md`).
+
+use crate::{hash_no_pad, hash_to_quintic_extension, permute, Fp5Element, Goldilocks};
+use serde_json::Value;
+
+const GOLDILOCKS_VECTORS: &str = include_str!("../../test-vectors/goldilocks.json");
+const FP5_VECTORS: &str = include_str!("../../test-vectors/fp5.json");
+const POSEIDON2_VECTORS: &str = include_str!("../../test-vectors/poseidon2.json");
+
+fn cases(raw: &str) -> Vec<Value> {
+    let file: Value = serde_json::from_str(raw).expect("vector file is valid JSON");
+    file["cases"].as_array().expect("vector file has cases").clone()
+}
+
+fn goldilocks(value: &Value) -> Goldilocks {
+    Goldilocks::from_canonical_u64(value.as_u64().expect("u64 field element"))
+}
+
+fn goldilocks_vec(value: &Value) -> Vec<Goldilocks> {
+    value.as_array().expect("array of field elements").iter().map(goldilocks).collect()
+}
+
+fn fp5(value: &Value) -> Fp5Element {
+    let limbs = goldilocks_vec(value);
+    Fp5Element([limbs[0], limbs[1], limbs[2], limbs[3], limbs[4]])
+}
+
+fn canonical(elements: &[Goldilocks]) -> Vec<u64> {
+    elements.iter().map(|x| x.to_canonical_u64()).collect()
+}
+
+#[test]
+fn test_goldilocks_vectors() {
+    for case in cases(GOLDILOCKS_VECTORS) {
+        let op = case["op"].as_str().unwrap();
+        let result = match op {
+            "add" => goldilocks(&case["a"]).add(&goldilocks(&case["b"])),
+            "sub" => goldilocks(&case["a"]).sub(&goldilocks(&case["b"])),
+            "mul" => goldilocks(&case["a"]).mul(&goldilocks(&case["b"])),
+            "square" => goldilocks(&case["a"]).square(),
+            "neg" => goldilocks(&case["a"]).neg(),
+            "inverse" => goldilocks(&case["a"]).inverse(),
+            "exp" => goldilocks(&case["a"]).exp(case["b"].as_u64().unwrap()),
+            "from_i64" => Goldilocks::from_i64(case["a"].as_i64().unwrap()),
+            other => panic!("unknown goldilocks op {}", other),
+        };
+        assert_eq!(result.to_canonical_u64(), case["expected"].as_u64().unwrap(), "case {}", case);
+    }
+}
+
+#[test]
+fn test_fp5_vectors() {
+    for case in cases(FP5_VECTORS) {
+        let op = case["op"].as_str().unwrap();
+        let result = match op {
+            "add" => fp5(&case["a"]).add(&fp5(&case["b"])),
+            "sub" => fp5(&case["a"]).sub(&fp5(&case["b"])),
+            "mul" => fp5(&case["a"]).mul(&fp5(&case["b"])),
+            "square" => fp5(&case["a"]).square(),
+            "inverse" => fp5(&case["a"]).inverse_or_zero(),
+            other => panic!("unknown fp5 op {}", other),
+        };
+        assert!(result.equals(&fp5(&case["expected"])), "case {}", case);
+    }
+}
+
+#[test]
+fn test_poseidon2_vectors() {
+    for case in cases(POSEIDON2_VECTORS) {
+        let input = goldilocks_vec(&case["input"]);
+        let expected: Vec<u64> = canonical(&goldilocks_vec(&case["expected"]));
+        let result = match case["op"].as_str().unwrap() {
+            "hash_to_quintic_extension" => canonical(&hash_to_quintic_extension(&input).0),
+            "hash_no_pad" => canonical(&hash_no_pad(&input)),
+            "permute" => {
+                let mut state: [Goldilocks; 12] = input.try_into().expect("12-element state");
+                permute(&mut state);
+                canonical(&state)
+            }
+            other => panic!("unknown poseidon2 op {}", other),
+        };
+        assert_eq!(result, expected, "case {}", case);
+    }
+}
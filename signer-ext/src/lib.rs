@@ -1,11 +1,11 @@
+
 mod signer;
 use std::os::raw::{c_char, c_int, c_longlong};
-use signer::{KeyManager, Result};
+use signer::{KeyManager, Result, SignedTx};
 use serde_json::json;
 use std::ffi::{CStr, CString};
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use hex;
 
 #[repr(C)]
 pub struct StrOrErr {
@@ -13,6 +13,19 @@ pub struct StrOrErr {
     pub error:*mut c_char,
 }
 
+/// Result of the `Sign*Sig` functions: the signature and hash of a transaction without its
+/// JSON. On success `error` is null; on failure `sig` and `tx_hash` are null.
+#[repr(C)]
+pub struct SigOrErr {
+    /// Standard base64 of the 80-byte signature, the `Sig` field of the transaction.
+    pub sig: *mut c_char,
+    /// Lower-case hex of the 40-byte hash that was signed.
+    pub tx_hash: *mut c_char,
+    /// `ExpiredAt` the hash commits to; the payload must carry the same value.
+    pub expired_at: c_longlong,
+    pub error: *mut c_char,
+}
+
 // --- helpers ----------------------------------------------------------
 
 fn into_str_or_err(result: Result<String>) -> StrOrErr {
@@ -28,8 +41,35 @@ fn into_str_or_err(result: Result<String>) -> StrOrErr {
     }
 }
 
+fn into_sig_or_err(result: Result<SignedTx>) -> SigOrErr {
+    match result {
+        Ok(signed) => SigOrErr {
+            sig: CString::new(signed.signature_base64()).unwrap().into_raw(),
+            tx_hash: CString::new(hex::encode(signed.tx_hash)).unwrap().into_raw(),
+            expired_at: signed.expired_at(),
+            error: std::ptr::null_mut(),
+        },
+        Err(e) => SigOrErr {
+            sig: std::ptr::null_mut(),
+            tx_hash: std::ptr::null_mut(),
+            expired_at: 0,
+            error: CString::new(e.to_string()).unwrap().into_raw(),
+        },
+    }
+}
+
+/// Reads a NUL-terminated string argument; a null pointer reads as empty, which the
+/// key and JSON parsers then reject.
+fn string_arg(ptr: *const c_char) -> String {
+    if ptr.is_null() {
+        return String::new();
+    }
+    unsafe { CStr::from_ptr(ptr) }.to_string_lossy().to_string()
+}
+
 /// Called by C# to free returned `char*`
 #[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)] // only pointers returned by this library are passed back
 pub extern "C" fn FreeMessage(ptr: *mut c_char) {
     if !ptr.is_null() {
         unsafe { drop(CString::from_raw(ptr)); }
@@ -40,7 +80,7 @@ pub extern "C" fn FreeMessage(ptr: *mut c_char) {
 
 #[no_mangle]
 pub extern "C" fn GetPubKey(private_key: *const c_char) -> StrOrErr {
-    let pk = unsafe { CStr::from_ptr(private_key) }.to_string_lossy().to_string();
+    let pk = string_arg(private_key);
     let mgr = match KeyManager::from_hex(&pk) {
         Ok(m) => m,
         Err(e) => return into_str_or_err(Err(e)),
@@ -57,7 +97,7 @@ pub extern "C" fn CreateAuthToken(
     account_index: c_longlong,
     deadline: c_longlong,
 ) -> StrOrErr {
-    let pk = unsafe { CStr::from_ptr(private_key) }.to_string_lossy().to_string();
+    let pk = string_arg(private_key);
     let mgr = match KeyManager::from_hex(&pk) {
         Ok(m) => m,
         Err(e) => return into_str_or_err(Err(e)),
@@ -88,10 +128,170 @@ pub extern "C" fn SignCreateOrder(
     order_expiry: c_longlong,
     nonce: c_longlong,
 ) -> StrOrErr {
-    let pk = unsafe { CStr::from_ptr(private_key) }.to_string_lossy().to_string();
+    let pk = string_arg(private_key);
+    let js = create_order_json(
+        api_key_index, account_index, market_index, client_order_index, base_amount, price, is_ask,
+        order_type, time_in_force, reduce_only, trigger_price, order_expiry, nonce,
+    );
+    build_transaction(&pk, &js, 14, chain_id as u32)
+}
+
+#[no_mangle]
+pub extern "C" fn SignCancelOrder(
+    private_key: *const c_char,
+    chain_id: c_int,
+    api_key_index: c_int,
+    account_index: c_longlong,
+    market_index: c_int,
+    order_index: c_longlong,
+    nonce: c_longlong,
+) -> StrOrErr {
+    let pk = string_arg(private_key);
+    let js = cancel_order_json(api_key_index, account_index, market_index, order_index, nonce);
+    build_transaction(&pk, &js, 15, chain_id as u32)
+}
+
+#[no_mangle]
+pub extern "C" fn SignModifyOrder(
+    private_key: *const c_char,
+    chain_id: c_int,
+    api_key_index: c_int,
+    account_index: c_longlong,
+    market_index: c_int,
+    client_order_index: c_longlong,
+    new_base_amount: c_longlong,
+    new_price: c_int,
+    reduce_only: c_int,
+    new_order_expiry: c_longlong,
+    nonce: c_longlong,
+) -> StrOrErr {
+    let pk = string_arg(private_key);
+    let js = modify_order_json(
+        api_key_index, account_index, market_index, client_order_index, new_base_amount, new_price,
+        reduce_only, new_order_expiry, nonce,
+    );
+    build_transaction(&pk, &js, 17, chain_id as u32)
+}
 
+#[no_mangle]
+pub extern "C" fn SignJsonData(
+    private_key: *const c_char,
+    json_data: *const c_char,
+    tx_type: c_int,
+    chain_id: c_int,
+)-> StrOrErr {
+    let pk = string_arg(private_key);
+    let js = string_arg(json_data);
+
+    build_transaction(&pk, &js, tx_type as u32, chain_id as u32)
+}
+
+// --- signature-only variants -------------------------------------------
+//
+// Same arguments as the functions above, but instead of the signed tx_info JSON they return
+// the base64 `Sig`, the hex tx hash and the `ExpiredAt` that was signed, for hosts that build
+// their own payload. Free `sig`, `tx_hash` and `error` with `FreeMessage`.
+
+#[no_mangle]
+pub extern "C" fn SignCreateOrderSig(
+    private_key: *const c_char,
+    chain_id: c_int,
+    api_key_index: c_int,
+    account_index: c_longlong,
+    market_index: c_int,
+    client_order_index: c_longlong,
+    base_amount: c_longlong,
+    price: c_int,
+    is_ask: c_int,
+    order_type: c_int,
+    time_in_force: c_int,
+    reduce_only: c_int,
+    trigger_price: c_int,
+    order_expiry: c_longlong,
+    nonce: c_longlong,
+) -> SigOrErr {
+    let pk = string_arg(private_key);
+    let js = create_order_json(
+        api_key_index, account_index, market_index, client_order_index, base_amount, price, is_ask,
+        order_type, time_in_force, reduce_only, trigger_price, order_expiry, nonce,
+    );
+    sign_only(&pk, &js, 14, chain_id as u32)
+}
+
+#[no_mangle]
+pub extern "C" fn SignCancelOrderSig(
+    private_key: *const c_char,
+    chain_id: c_int,
+    api_key_index: c_int,
+    account_index: c_longlong,
+    market_index: c_int,
+    order_index: c_longlong,
+    nonce: c_longlong,
+) -> SigOrErr {
+    let pk = string_arg(private_key);
+    let js = cancel_order_json(api_key_index, account_index, market_index, order_index, nonce);
+    sign_only(&pk, &js, 15, chain_id as u32)
+}
+
+#[no_mangle]
+pub extern "C" fn SignModifyOrderSig(
+    private_key: *const c_char,
+    chain_id: c_int,
+    api_key_index: c_int,
+    account_index: c_longlong,
+    market_index: c_int,
+    client_order_index: c_longlong,
+    new_base_amount: c_longlong,
+    new_price: c_int,
+    reduce_only: c_int,
+    new_order_expiry: c_longlong,
+    nonce: c_longlong,
+) -> SigOrErr {
+    let pk = string_arg(private_key);
+    let js = modify_order_json(
+        api_key_index, account_index, market_index, client_order_index, new_base_amount, new_price,
+        reduce_only, new_order_expiry, nonce,
+    );
+    sign_only(&pk, &js, 17, chain_id as u32)
+}
+
+#[no_mangle]
+pub extern "C" fn SignJsonDataSig(
+    private_key: *const c_char,
+    json_data: *const c_char,
+    tx_type: c_int,
+    chain_id: c_int,
+) -> SigOrErr {
+    let pk = string_arg(private_key);
+    let js = string_arg(json_data);
+
+    sign_only(&pk, &js, tx_type as u32, chain_id as u32)
+}
+
+// --- tx_info builders ---------------------------------------------------
+
+/// ExpiredAt of a transaction signed now: 10 minutes - 1 second, in milliseconds.
+fn expired_at_from_now() -> i64 {
     let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as i64;
-    let expired_at = now + 599_000; // 10 minutes - 1 second (in milliseconds)
+    now + 599_000
+}
+
+#[allow(clippy::too_many_arguments)]
+fn create_order_json(
+    api_key_index: c_int,
+    account_index: c_longlong,
+    market_index: c_int,
+    client_order_index: c_longlong,
+    base_amount: c_longlong,
+    price: c_int,
+    is_ask: c_int,
+    order_type: c_int,
+    time_in_force: c_int,
+    reduce_only: c_int,
+    trigger_price: c_int,
+    order_expiry: c_longlong,
+    nonce: c_longlong,
+) -> String {
     let tx_info = json!({
             "AccountIndex": account_index,
             "ApiKeyIndex": api_key_index,
@@ -105,47 +305,34 @@ pub extern "C" fn SignCreateOrder(
             "ReduceOnly": reduce_only,
             "TriggerPrice": trigger_price,
             "OrderExpiry": order_expiry, // NilOrderExpiry for market orders
-            "ExpiredAt": expired_at,
+            "ExpiredAt": expired_at_from_now(),
             "Nonce": nonce,
             "Sig": ""
         });
-    let js = serde_json::to_string(&tx_info).unwrap();
-    build_transaction(&pk, &js, 14, chain_id as u32)
+    serde_json::to_string(&tx_info).unwrap()
 }
 
-#[no_mangle]
-pub extern "C" fn SignCancelOrder(
-    private_key: *const c_char,
-    chain_id: c_int,
+fn cancel_order_json(
     api_key_index: c_int,
     account_index: c_longlong,
     market_index: c_int,
     order_index: c_longlong,
     nonce: c_longlong,
-) -> StrOrErr {
-
-    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as i64;
-    let expired_at = now + 599_000; // 10 minutes - 1 second (in milliseconds)
-
-    let pk = unsafe { CStr::from_ptr(private_key) }.to_string_lossy().to_string();
+) -> String {
     let tx_info = json!({
         "AccountIndex": account_index,
         "ApiKeyIndex": api_key_index,
         "MarketIndex": market_index,
         "Index": order_index,
-        "ExpiredAt": expired_at,
+        "ExpiredAt": expired_at_from_now(),
         "Nonce": nonce,
         "Sig": ""
     });
-
-    let js = serde_json::to_string(&tx_info).unwrap();
-    build_transaction(&pk, &js, 15, chain_id as u32)
+    serde_json::to_string(&tx_info).unwrap()
 }
 
-#[no_mangle]
-pub extern "C" fn SignModifyOrder(
-    private_key: *const c_char,
-    chain_id: c_int,
+#[allow(clippy::too_many_arguments)]
+fn modify_order_json(
     api_key_index: c_int,
     account_index: c_longlong,
     market_index: c_int,
@@ -155,9 +342,7 @@ pub extern "C" fn SignModifyOrder(
     reduce_only: c_int,
     new_order_expiry: c_longlong,
     nonce: c_longlong,
-) -> StrOrErr {
-    let pk = unsafe { CStr::from_ptr(private_key) }.to_string_lossy().to_string();
-
+) -> String {
     let tx_info = json!({
         "AccountIndex": account_index,
         "ApiKeyIndex": api_key_index,
@@ -171,36 +356,25 @@ pub extern "C" fn SignModifyOrder(
         "Nonce": nonce,
         "Sig": ""
     });
-
-    let js = serde_json::to_string(&tx_info).unwrap();
-
-    build_transaction(&pk, &js, 17, chain_id as u32)
-}
-
-#[no_mangle]
-pub extern "C" fn SignJsonData(
-    private_key: *const c_char,
-    json_data: *const c_char,
-    tx_type: c_int,
-    chain_id: c_int,
-)-> StrOrErr {
-    let pk = unsafe { CStr::from_ptr(private_key) }.to_string_lossy().to_string();
-    let js = unsafe { CStr::from_ptr(json_data) }.to_string_lossy().to_string();
-
-    build_transaction(&pk, &js, tx_type as u32, chain_id as u32)
+    serde_json::to_string(&tx_info).unwrap()
 }
 
 fn build_transaction(pk:&str, tx_json: &str, tx_type: u32, lighter_chain_id: u32)->StrOrErr{
 
-    let mgr = match KeyManager::from_hex(&pk) {
+    let mgr = match KeyManager::from_hex(pk) {
         Ok(m) => m,
         Err(e) => return into_str_or_err(Err(e)),
     };
-    let signature = mgr.sign_transaction(&tx_json, tx_type, lighter_chain_id, false);
+    let signature = mgr.sign_transaction(tx_json, tx_type, lighter_chain_id, false);
 
     into_str_or_err(signature)
 }
 
+fn sign_only(pk: &str, tx_json: &str, tx_type: u32, lighter_chain_id: u32) -> SigOrErr {
+    let signed = KeyManager::from_hex(pk).and_then(|mgr| mgr.sign_tx(tx_json, tx_type, lighter_chain_id, false));
+    into_sig_or_err(signed)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -231,4 +405,77 @@ mod tests {
             panic!("{} failures occurred while generating auth tokens", failed);
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_oversized_price_is_rejected() {
+        let mgr = KeyManager::from_hex("bda332f3aaa2d9cfdd8920830ea37efce9636c671a426bd4cb9815007e2a2917604ab47857cbb200")
+            .expect("invalid private key");
+        let order = |price: i64| {
+            format!(r#"{{"Nonce":1,"ExpiredAt":0,"AccountIndex":1,"ApiKeyIndex":0,"MarketIndex":0,"BaseAmount":1000,"Price":{}}}"#, price)
+        };
+
+        assert!(mgr.sign_transaction(&order(305_000), 14, 304, true).is_ok());
+        // 2^32 + 305000 used to be signed as 305000
+        assert!(mgr.sign_transaction(&order(4_295_272_296), 14, 304, false).is_err());
+        assert!(mgr.sign_transaction(&order(-1), 14, 304, false).is_err());
+    }
+
+    #[test]
+    fn test_sig_variant_returns_signature_and_hash() {
+        use base64::Engine;
+
+        let private_key = "bda332f3aaa2d9cfdd8920830ea37efce9636c671a426bd4cb9815007e2a2917604ab47857cbb200";
+        let mgr = KeyManager::from_hex(private_key).expect("invalid private key");
+        let key = CString::new(private_key).unwrap();
+        let tx = r#"{"Nonce":3,"ExpiredAt":1700000000000,"AccountIndex":1,"ApiKeyIndex":0,"MarketIndex":0,"Index":7}"#;
+        let tx_c = CString::new(tx).unwrap();
+
+        let out = SignJsonDataSig(key.as_ptr(), tx_c.as_ptr(), 15, 304);
+        assert!(out.error.is_null());
+        let sig = unsafe { CStr::from_ptr(out.sig) }.to_str().unwrap().to_string();
+        let tx_hash = unsafe { CStr::from_ptr(out.tx_hash) }.to_str().unwrap().to_string();
+        FreeMessage(out.sig);
+        FreeMessage(out.tx_hash);
+
+        // The hash is the one the JSON variant signs, and the signature verifies over it
+        assert_eq!(out.expired_at, 1_700_000_000_000);
+        assert_eq!(tx_hash, hex::encode(mgr.sign_tx(tx, 15, 304, false).unwrap().tx_hash));
+        let signature = base64::engine::general_purpose::STANDARD.decode(&sig).unwrap();
+        let message = hex::decode(&tx_hash).unwrap();
+        assert!(goldilocks_crypto::verify_signature(&signature, &message, &mgr.public_key_bytes()).unwrap());
+
+        let out = SignCancelOrderSig(key.as_ptr(), 304, 0, 1, 0, 7, 3);
+        assert!(out.error.is_null() && out.expired_at > 0);
+        FreeMessage(out.sig);
+        FreeMessage(out.tx_hash);
+
+        let unsupported = SignJsonDataSig(key.as_ptr(), tx_c.as_ptr(), 99, 304);
+        assert!(unsupported.sig.is_null() && unsupported.tx_hash.is_null() && !unsupported.error.is_null());
+        FreeMessage(unsupported.error);
+    }
+
+    #[test]
+    fn test_auth_token_verifies_under_the_public_key() {
+        let private_key = "bda332f3aaa2d9cfdd8920830ea37efce9636c671a426bd4cb9815007e2a2917604ab47857cbb200";
+        let key = CString::new(private_key).unwrap();
+
+        // verify_sign once checked the signature against the private key bytes and failed
+        let out = CreateAuthToken(key.as_ptr(), 304, 3, 1, 1_700_000_000);
+        assert!(out.error.is_null());
+        let token = unsafe { CStr::from_ptr(out.msg) }.to_str().unwrap().to_string();
+        FreeMessage(out.msg);
+        let out = GetPubKey(key.as_ptr());
+        let public_key = hex::decode(unsafe { CStr::from_ptr(out.msg) }.to_str().unwrap()).unwrap();
+        FreeMessage(out.msg);
+
+        let (auth_data, signature) = token.rsplit_once(':').unwrap();
+        assert_eq!(auth_data, "1700000000:1:3");
+        let message = poseidon_hash::hash_bytes_to_quintic_extension(auth_data.as_bytes()).to_bytes_le();
+        let signature = hex::decode(signature).unwrap();
+        assert!(goldilocks_crypto::verify_signature(&signature, &message, &public_key).unwrap());
+
+        let null = CreateAuthToken(std::ptr::null(), 304, 3, 1, 1_700_000_000);
+        assert!(null.msg.is_null() && !null.error.is_null());
+        FreeMessage(null.error);
+    }
+}
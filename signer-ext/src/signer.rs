@@ -1,5 +1,6 @@
-use goldilocks_crypto::{schnorr::{sign_with_nonce},schnorr::verify_signature, ScalarField, Goldilocks};
+use goldilocks_crypto::{schnorr::{sign_with_nonce},schnorr::verify_signature, ScalarField};
 use thiserror::Error;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
 use base64::Engine;
 use serde_json::{json,Value};
 
@@ -14,34 +15,75 @@ pub enum SignerError {
     #[error("Hex decode error: {0}")]
     HexDecode(#[from] hex::FromHexError),
     #[error("API error: {0}")]
+    #[allow(clippy::upper_case_acronyms)]
     API(String),
 }
 
 pub type Result<T> = std::result::Result<T, SignerError>;
 
+/// Reads a 32-bit price field (0 when missing); a negative or larger price is an error
+/// rather than truncated into a different signed price.
+fn price_field(tx_value: &Value, name: &str) -> Result<u32> {
+    match &tx_value[name] {
+        Value::Null => Ok(0),
+        value => value
+            .as_u64()
+            .and_then(|units| u32::try_from(units).ok())
+            .ok_or_else(|| SignerError::API(format!("{} must be a price between 0 and {}, got {}", name, u32::MAX, value))),
+    }
+}
+
+/// Holds an API private key and signs with it.
+///
+/// The key is wiped from memory when the manager is dropped. `Debug` shows only the first
+/// 4 bytes of the public key.
 pub struct KeyManager {
     private_key: ScalarField,
 }
 
+impl Zeroize for KeyManager {
+    fn zeroize(&mut self) {
+        self.private_key.zeroize();
+    }
+}
+
+impl Drop for KeyManager {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for KeyManager {}
+
+impl std::fmt::Debug for KeyManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KeyManager")
+            .field("public_key", &hex::encode(&self.public_key_bytes()[..4]))
+            .finish_non_exhaustive()
+    }
+}
+
 impl KeyManager {
+    /// Key from its 40 little-endian bytes; zero and values not below the group order are
+    /// rejected with `CryptoError::InvalidPrivateKey`
     pub fn new(private_key_bytes: &[u8]) -> Result<Self> {
         if private_key_bytes.len() != 40 {
             return Err(SignerError::Crypto(goldilocks_crypto::CryptoError::InvalidPrivateKeyLength(private_key_bytes.len())));
         }
         // Use all 40 bytes for 5-limb scalar
-        let private_key = ScalarField::from_bytes_le(private_key_bytes)
+        let mut private_key = ScalarField::from_bytes_le(private_key_bytes)
             .map_err(|_| SignerError::Crypto(goldilocks_crypto::CryptoError::InvalidPrivateKeyLength(private_key_bytes.len())))?;
+        if !private_key.is_valid() {
+            private_key.zeroize();
+            return Err(SignerError::Crypto(goldilocks_crypto::CryptoError::InvalidPrivateKey));
+        }
         Ok(Self { private_key })
     }
     
     pub fn from_hex(hex_str: &str) -> Result<Self> {
-        let hex_str = if hex_str.starts_with("0x") {
-            &hex_str[2..]
-        } else {
-            hex_str
-        };
+        let hex_str = hex_str.strip_prefix("0x").unwrap_or(hex_str);
         
-        let bytes = hex::decode(hex_str)?;
+        let bytes = Zeroizing::new(hex::decode(hex_str)?);
         Self::new(&bytes)
     }
 
@@ -49,29 +91,22 @@ impl KeyManager {
     pub fn public_key_bytes(&self) -> [u8; 40] {
         use goldilocks_crypto::schnorr::Point;
         // Public key = generator * private_key, encoded as Fp5Element
-        let generator = Point::generator();
-        let public_point = generator.mul(&self.private_key);
+        let public_point = Point::mul_generator(&self.private_key);
         let public_fp5 = public_point.encode();
         public_fp5.to_bytes_le()
     }
 
-    /// Get the private key as bytes (40 bytes)
-    pub fn private_key_bytes(&self) -> [u8; 40] {
-        self.private_key.to_bytes_le()
-    }
-
     pub fn sign(&self, message: &[u8; 40]) -> Result<[u8; 80]> {
         // Generate cryptographically secure random nonce
-        let nonce_scalar = ScalarField::sample_crypto();
-        let nonce_bytes = nonce_scalar.to_bytes_le();
-        self.sign_with_fixed_nonce(message, &nonce_bytes)
+        let nonce_bytes = Zeroizing::new(ScalarField::sample_crypto().to_bytes_le());
+        self.sign_with_fixed_nonce(message, &nonce_bytes[..])
     }
     
     fn sign_with_fixed_nonce(&self, message: &[u8; 40], nonce_bytes: &[u8]) -> Result<[u8; 80]> {
-        let pk_bytes = self.private_key.to_bytes_le();
+        let pk_bytes = Zeroizing::new(self.private_key.to_bytes_le());
         
         // Pass message directly - sign_with_nonce will convert it properly
-        let signature = sign_with_nonce(&pk_bytes, message, nonce_bytes)?;
+        let signature = sign_with_nonce(&pk_bytes[..], message, nonce_bytes)?;
         let mut result = [0u8; 80];
         result.copy_from_slice(&signature);
         Ok(result)
@@ -87,39 +122,8 @@ impl KeyManager {
         // Match Go: ConstructAuthToken format "deadline:account_index:api_key_index"
         let auth_data = format!("{}:{}:{}", deadline, account_index, api_key_index);
         
-        // Convert message bytes to Goldilocks elements
-        let auth_bytes = auth_data.as_bytes();
-        
-        // CRITICAL: Pad each 8-byte chunk individually
-        // Calculate missing bytes: (8 - len(in) % 8) % 8, then pad the last chunk with zeros
-        let missing = (8 - auth_bytes.len() % 8) % 8;
-        
-        let mut elements = Vec::new();
-        
-        // Process in chunks of 8 bytes (one Goldilocks element per 8 bytes)
-        let mut i = 0;
-        while i < auth_bytes.len() {
-            let next_start = (i + 8).min(auth_bytes.len());
-            let chunk = &auth_bytes[i..next_start];
-            
-            let mut bytes = [0u8; 8];
-            bytes[..chunk.len()].copy_from_slice(chunk);
-            
-            // Pad only the last chunk if needed
-            if chunk.len() < 8 && missing > 0 {
-                bytes[chunk.len()..].fill(0);
-            }
-            
-            // Read as little-endian u64, then convert to Goldilocks
-            let val = u64::from_le_bytes(bytes);
-            elements.push(Goldilocks::from_canonical_u64(val));
-            
-            i = next_start;
-        }
-        
-        // Hash the elements using Poseidon2 (matching Go's HashToQuinticExtension)
-        use poseidon_hash::hash_to_quintic_extension;
-        let hash_fp5 = hash_to_quintic_extension(&elements);
+        // Pack into Goldilocks elements and hash (matching Go's HashToQuinticExtension)
+        let hash_fp5 = poseidon_hash::hash_bytes_to_quintic_extension(auth_data.as_bytes());
         
         // Convert Fp5Element to 40-byte array for signing
         let message_bytes = hash_fp5.to_bytes_le();
@@ -129,8 +133,8 @@ impl KeyManager {
 
         if verify_sign {
            
-            let pubkey = self.private_key_bytes();
-            let sig_ok = verify_signature(&signature,  &message_bytes, &pubkey).unwrap();
+            let pubkey = self.public_key_bytes();
+            let sig_ok = verify_signature(&signature,  &message_bytes, &pubkey).unwrap_or(false);
 
             if !sig_ok {
                 // If signature doesn't match, return error (or log)
@@ -139,12 +143,23 @@ impl KeyManager {
                 ));
             }
         }
-        let signature_hex = hex::encode(&signature);
+        let signature_hex = hex::encode(signature);
         
         Ok(format!("{}:{}", auth_data, signature_hex))
     }
 
+    /// Signs `tx_json` and returns it as JSON with the `Sig` field filled in.
     pub fn sign_transaction(&self, tx_json: &str, tx_type: u32, lighter_chain_id: u32, verify_sign:bool) -> Result<String> {
+        let signed = self.sign_tx(tx_json, tx_type, lighter_chain_id, verify_sign)?;
+        let mut final_tx_info = signed.tx_info;
+        final_tx_info["Sig"] = json!(base64::engine::general_purpose::STANDARD.encode(signed.signature));
+
+        Ok(serde_json::to_string(&final_tx_info)?)
+    }
+
+    /// Hashes and signs `tx_json`, keeping the parsed transaction, its hash and the signature
+    /// apart for callers that build their own payload.
+    pub fn sign_tx(&self, tx_json: &str, tx_type: u32, lighter_chain_id: u32, verify_sign:bool) -> Result<SignedTx> {
                 // Parse the transaction JSON to extract fields
         let tx_value: Value = serde_json::from_str(tx_json)?;
 
@@ -167,10 +182,7 @@ impl KeyManager {
         let market_index = tx_value["MarketIndex"].as_u64().unwrap_or(0) as u32;
         let client_order_index = tx_value["ClientOrderIndex"].as_i64().unwrap_or(0);
         let base_amount = tx_value["BaseAmount"].as_i64().unwrap_or(0);
-        let price = tx_value["Price"]
-            .as_u64()
-            .or_else(|| tx_value["Price"].as_i64().map(|v| v as u64))
-            .unwrap_or(0) as u32;
+        let price = price_field(&tx_value, "Price")?;
         let is_ask = tx_value["IsAsk"]
             .as_u64()
             .or_else(|| tx_value["IsAsk"].as_i64().map(|v| v as u64))
@@ -187,10 +199,7 @@ impl KeyManager {
             .as_u64()
             .or_else(|| tx_value["ReduceOnly"].as_i64().map(|v| v as u64))
             .unwrap_or(0) as u32;
-        let trigger_price = tx_value["TriggerPrice"]
-            .as_u64()
-            .or_else(|| tx_value["TriggerPrice"].as_i64().map(|v| v as u64))
-            .unwrap_or(0) as u32;
+        let trigger_price = price_field(&tx_value, "TriggerPrice")?;
         let order_expiry = tx_value["OrderExpiry"].as_i64().unwrap_or(0);
         
                 vec![
@@ -256,12 +265,7 @@ impl KeyManager {
                     return Err(SignerError::API("PubKey must be 40 bytes".to_string()));
                 }
                 // Convert 40-byte public key to 5 Goldilocks elements (8 bytes per element)
-                let mut pubkey_elems = Vec::new();
-                for i in 0..5 {
-                    let chunk = &pubkey_bytes[i*8..(i+1)*8];
-                    let val = u64::from_le_bytes(chunk.try_into().unwrap());
-                    pubkey_elems.push(Goldilocks::from_canonical_u64(val));
-                }
+                let pubkey_elems = poseidon_hash::array_from_le_bytes(&pubkey_bytes);
 
                 let mut elems = vec![
                     Goldilocks::from_canonical_u64(lighter_chain_id as u64),
@@ -329,14 +333,31 @@ impl KeyManager {
             }
         }
 
-        
+        Ok(SignedTx {
+            tx_info: tx_value,
+            tx_hash: message_array,
+            signature,
+        })
+    }
+}
 
-        let signature = self.sign(&message_array)?;
-        let mut final_tx_info = tx_value;
-        final_tx_info["Sig"] = json!(base64::engine::general_purpose::STANDARD.encode(&signature));
-        
-        let encode_json = serde_json::to_string(&final_tx_info);
-        Ok(encode_json.unwrap())
+/// A transaction signed by `KeyManager::sign_tx`.
+pub struct SignedTx {
+    /// The transaction as parsed, with `Sig` as it was given.
+    pub tx_info: Value,
+    /// The 40-byte Poseidon2 hash that was signed.
+    pub tx_hash: [u8; 40],
+    pub signature: [u8; 80],
+}
+
+impl SignedTx {
+    /// Standard base64 of the signature, the `Sig` field of the transaction.
+    pub fn signature_base64(&self) -> String {
+        base64::engine::general_purpose::STANDARD.encode(self.signature)
+    }
 
+    /// The `ExpiredAt` that was hashed, 0 if the transaction had none.
+    pub fn expired_at(&self) -> i64 {
+        self.tx_info["ExpiredAt"].as_i64().unwrap_or(0)
     }
 }
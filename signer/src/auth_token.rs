@@ -0,0 +1,154 @@
+//! Auth tokens for account-scoped API reads.
+//!
+//! A token is `deadline:account_index:api_key_index:signature`, where the signature (hex) is
+//! over the Poseidon2 hash of everything before it. Later formats are expected to add a
+//! version prefix (`v2:...`); `AuthToken` keeps the prefix as `version` and signs and verifies
+//! the whole text before the signature, so a prefixed token round-trips unchanged.
+
+use crate::{KeyManager, PublicKey, Result, SignerError};
+use goldilocks_crypto::Signature;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A parsed auth token.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuthToken {
+    /// Format version: 0 for the unprefixed format, `N` for a `vN:` prefix.
+    pub version: u32,
+    /// Expiry, in seconds since the epoch.
+    pub deadline: i64,
+    pub account_index: i64,
+    pub api_key_index: u8,
+    pub signature: Signature,
+}
+
+impl AuthToken {
+    /// Signs a token for `deadline` (seconds) in the unprefixed format.
+    pub fn sign(key: &KeyManager, deadline: i64, account_index: i64, api_key_index: u8) -> Result<Self> {
+        let hash = Self::signing_hash(deadline, account_index, api_key_index);
+        Ok(Self {
+            version: 0,
+            deadline,
+            account_index,
+            api_key_index,
+            signature: key.sign(&hash)?,
+        })
+    }
+
+    /// The 40-byte hash `sign` signs, for signing the token with a key held elsewhere.
+    pub fn signing_hash(deadline: i64, account_index: i64, api_key_index: u8) -> [u8; 40] {
+        let message = Self::message(0, deadline, account_index, api_key_index);
+        poseidon_hash::hash_bytes_to_quintic_extension(message.as_bytes()).to_bytes_le()
+    }
+
+    /// Parses a token, checking its layout but neither its expiry nor its signature.
+    pub fn parse(token: &str) -> Result<Self> {
+        let invalid = |reason: &str| SignerError::InvalidAuthToken(format!("{}: {:?}", reason, token));
+        let token = token.trim();
+
+        let (version, body) = match token.split_once(':') {
+            Some((prefix, rest)) if prefix.starts_with('v') => {
+                let version = prefix[1..].parse::<u32>().ok().filter(|v| *v > 0).ok_or_else(|| invalid("bad version prefix"))?;
+                (version, rest)
+            }
+            _ => (0, token),
+        };
+
+        let fields: Vec<&str> = body.split(':').collect();
+        let [deadline, account_index, api_key_index, signature] = fields[..] else {
+            return Err(invalid("expected deadline:account_index:api_key_index:signature"));
+        };
+        Ok(Self {
+            version,
+            deadline: deadline.parse().map_err(|_| invalid("bad deadline"))?,
+            account_index: account_index.parse().map_err(|_| invalid("bad account index"))?,
+            api_key_index: api_key_index.parse().map_err(|_| invalid("bad api key index"))?,
+            signature: Signature::from_hex(signature)?,
+        })
+    }
+
+    /// The signed text: the token without its signature.
+    fn message(version: u32, deadline: i64, account_index: i64, api_key_index: u8) -> String {
+        let body = format!("{}:{}:{}", deadline, account_index, api_key_index);
+        match version {
+            0 => body,
+            version => format!("v{}:{}", version, body),
+        }
+    }
+
+    /// Whether the signature was made by `public_key`.
+    pub fn verify(&self, public_key: &PublicKey) -> Result<bool> {
+        let message = Self::message(self.version, self.deadline, self.account_index, self.api_key_index);
+        let hash = poseidon_hash::hash_bytes_to_quintic_extension(message.as_bytes()).to_bytes_le();
+        Ok(goldilocks_crypto::verify_signature(self.signature.as_bytes(), &hash, &public_key.to_bytes())?)
+    }
+
+    pub fn expires_at(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.deadline.max(0) as u64)
+    }
+
+    /// Time left before expiry, zero once expired.
+    pub fn remaining(&self) -> Duration {
+        self.expires_at().duration_since(SystemTime::now()).unwrap_or_default()
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.remaining().is_zero()
+    }
+
+    /// Whether the token is still valid `margin` from now, e.g. to renew it before reuse.
+    pub fn is_valid_for(&self, margin: Duration) -> bool {
+        self.remaining() > margin
+    }
+}
+
+impl fmt::Display for AuthToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = Self::message(self.version, self.deadline, self.account_index, self.api_key_index);
+        write!(f, "{}:{}", message, self.signature.to_hex())
+    }
+}
+
+impl FromStr for AuthToken {
+    type Err = SignerError;
+
+    fn from_str(token: &str) -> Result<Self> {
+        Self::parse(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auth_token_round_trip() {
+        let key = KeyManager::generate();
+        let deadline = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64 + 600;
+        let token = AuthToken::sign(&key, deadline, 12, 3).unwrap();
+        let text = token.to_string();
+        assert!(text.starts_with(&format!("{}:12:3:", deadline)));
+
+        let parsed: AuthToken = text.parse().unwrap();
+        assert_eq!(parsed, token);
+        assert!(parsed.verify(&key.public_key()).unwrap());
+        assert!(!parsed.verify(&KeyManager::generate().public_key()).unwrap());
+        assert!(parsed.is_valid_for(Duration::from_secs(500)));
+        assert!(!parsed.is_valid_for(Duration::from_secs(700)));
+
+        // The prefix is part of the signed text
+        let versioned = AuthToken::parse(&format!("v2:{}", text)).unwrap();
+        assert_eq!(versioned.version, 2);
+        assert_eq!(versioned.to_string(), format!("v2:{}", text));
+        assert!(!versioned.verify(&key.public_key()).unwrap());
+
+        let expired = AuthToken { deadline: 1, ..token };
+        assert!(expired.is_expired());
+        assert_eq!(expired.remaining(), Duration::ZERO);
+
+        assert!(AuthToken::parse("1:2:3").is_err());
+        assert!(AuthToken::parse(&format!("v0:{}", text)).is_err());
+        assert!(AuthToken::parse(&text.replace(":12:", ":x:")).is_err());
+    }
+}
@@ -1,5 +1,14 @@
-use goldilocks_crypto::{schnorr::{sign_with_nonce}, ScalarField, Goldilocks};
+use goldilocks_crypto::{schnorr::{sign_with_nonce, Point}, Fp5Element, ScalarField, Goldilocks};
+pub use goldilocks_crypto::Signature;
+pub use goldilocks_crypto::{VrfOutput, VrfProof};
+pub use goldilocks_crypto::musig;
+use rand::{CryptoRng, RngCore};
+use std::fmt;
 use thiserror::Error;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+mod auth_token;
+pub use auth_token::AuthToken;
 
 #[derive(Error, Debug)]
 pub enum SignerError {
@@ -11,126 +20,402 @@ pub enum SignerError {
     SystemTime(#[from] std::time::SystemTimeError),
     #[error("Hex decode error: {0}")]
     HexDecode(#[from] hex::FromHexError),
+    #[error("Invalid auth token: {0}")]
+    InvalidAuthToken(String),
 }
 
 pub type Result<T> = std::result::Result<T, SignerError>;
 
+/// A Schnorr public key (an encoded ECgFp5 point), always held in canonical form.
+///
+/// The 40-byte encoding is five little-endian Goldilocks limbs. A limb may be given in
+/// non-canonical form (`v + p`); parsing reduces every limb, so two encodings of the same
+/// key compare equal.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct PublicKey([u8; 40]);
+
+impl PublicKey {
+    /// Parses a 40-byte encoding and checks that it decodes to a curve point.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != 40 {
+            return Err(SignerError::Crypto(goldilocks_crypto::CryptoError::InvalidPrivateKeyLength(bytes.len())));
+        }
+
+        let mut canonical = [0u8; 40];
+        for (src, dst) in bytes.chunks_exact(8).zip(canonical.chunks_exact_mut(8)) {
+            let limb = u64::from_le_bytes(src.try_into().expect("8-byte chunk"));
+            dst.copy_from_slice(&Goldilocks::from_canonical_u64(limb).to_canonical_u64().to_le_bytes());
+        }
+
+        let encoded = Fp5Element::from_bytes_le(&canonical)
+            .map_err(|_| SignerError::Crypto(goldilocks_crypto::CryptoError::InvalidPublicKey))?;
+        Point::decode(&encoded).ok_or(SignerError::Crypto(goldilocks_crypto::CryptoError::InvalidPublicKey))?;
+
+        Ok(Self(canonical))
+    }
+
+    /// Parses hex, with or without `0x`, in either case.
+    pub fn from_hex(hex_str: &str) -> Result<Self> {
+        let hex_str = hex_str.strip_prefix("0x").unwrap_or(hex_str);
+        Self::from_bytes(&hex::decode(hex_str)?)
+    }
+
+    /// Canonical 40-byte encoding.
+    pub fn to_bytes(&self) -> [u8; 40] {
+        self.0
+    }
+
+    /// Canonical encoding as lowercase hex without `0x`.
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.0)
+    }
+
+    /// First 4 bytes of the encoding in hex (8 characters), enough to tell keys apart in
+    /// logs and to match against the full key.
+    pub fn fingerprint(&self) -> String {
+        hex::encode(&self.0[..4])
+    }
+
+    /// Check a `KeyManager::vrf_prove` proof for `message`: the proven output if the proof
+    /// is valid, `None` if it is not.
+    pub fn vrf_verify(&self, message: &[u8], proof: &VrfProof) -> Result<Option<VrfOutput>> {
+        Ok(goldilocks_crypto::vrf_verify(&self.0, message, proof)?)
+    }
+}
+
+impl fmt::Display for PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+/// Domain separation tag of `KeyManager::derive_api_key`.
+const API_KEY_KDF_TAG: &[u8] = b"lighter-api-key-v1";
+
+/// Shortest master secret `KeyManager::derive_api_key` accepts, in bytes.
+pub const MIN_MASTER_KEY_LEN: usize = 32;
+
+/// Holds an API private key and signs with it.
+///
+/// The key is wiped from memory when the manager is dropped. `Debug` and `Display` show
+/// only the fingerprint of the public key.
 pub struct KeyManager {
     private_key: ScalarField,
 }
 
+impl Zeroize for KeyManager {
+    fn zeroize(&mut self) {
+        self.private_key.zeroize();
+    }
+}
+
+impl Drop for KeyManager {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for KeyManager {}
+
+impl fmt::Debug for KeyManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyManager")
+            .field("public_key", &self.fingerprint())
+            .finish_non_exhaustive()
+    }
+}
+
+impl fmt::Display for KeyManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "KeyManager({})", self.fingerprint())
+    }
+}
+
 impl KeyManager {
+    /// Key from its 40 little-endian bytes
+    ///
+    /// Fails with `CryptoError::InvalidPrivateKey` unless the key is nonzero and below the
+    /// group order; a larger value would sign as the key reduced modulo the order.
     pub fn new(private_key_bytes: &[u8]) -> Result<Self> {
         if private_key_bytes.len() != 40 {
             return Err(SignerError::Crypto(goldilocks_crypto::CryptoError::InvalidPrivateKeyLength(private_key_bytes.len())));
         }
         // Use all 40 bytes for 5-limb scalar
-        let private_key = ScalarField::from_bytes_le(private_key_bytes)
+        let mut private_key = ScalarField::from_bytes_le(private_key_bytes)
             .map_err(|_| SignerError::Crypto(goldilocks_crypto::CryptoError::InvalidPrivateKeyLength(private_key_bytes.len())))?;
+        if !private_key.is_valid() {
+            private_key.zeroize();
+            return Err(SignerError::Crypto(goldilocks_crypto::CryptoError::InvalidPrivateKey));
+        }
         Ok(Self { private_key })
     }
     
-        pub fn from_hex(hex_str: &str) -> Result<Self> {
-        let hex_str = if hex_str.starts_with("0x") {
-            &hex_str[2..]
-        } else {
-            hex_str
-        };
+    /// Key from hex, with or without `0x`; see `new`
+    pub fn from_hex(hex_str: &str) -> Result<Self> {
+        let hex_str = hex_str.strip_prefix("0x").unwrap_or(hex_str);
         
-        let bytes = hex::decode(hex_str)?;
+        let bytes = Zeroizing::new(hex::decode(hex_str)?);
         Self::new(&bytes)
     }
 
     /// Generate a new random key pair
     pub fn generate() -> Self {
-        let random_scalar = ScalarField::sample_crypto();
-        Self { private_key: random_scalar }
+        Self::generate_with_rng(&mut rand::thread_rng())
+    }
+
+    /// Generate a key pair from `rng`
+    ///
+    /// A seeded generator gives reproducible keys for tests and fuzzing; WASM and other
+    /// targets without `thread_rng` can pass their own entropy source.
+    pub fn generate_with_rng<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
+        Self { private_key: ScalarField::sample(rng) }
+    }
+
+    /// Derive the key of API key slot `api_key_index` from a master secret
+    ///
+    /// The same secret and index always give the same key and different indices give
+    /// unrelated keys, so one backed-up secret (at least 32 bytes) provisions every slot.
+    /// The key is a Poseidon2 hash of a domain tag, the length-prefixed secret, the index and
+    /// a counter, taken as a scalar when it is below the group order (retried with the next
+    /// counter otherwise, so the key is uniform).
+    pub fn derive_api_key(master_key: &[u8], api_key_index: u8) -> Result<Self> {
+        if master_key.len() < MIN_MASTER_KEY_LEN {
+            return Err(SignerError::Crypto(goldilocks_crypto::CryptoError::InvalidPrivateKeyLength(master_key.len())));
+        }
+
+        let mut input = poseidon_hash::array_from_le_bytes(API_KEY_KDF_TAG);
+        input.push(Goldilocks::from_canonical_u64(master_key.len() as u64));
+        input.extend(poseidon_hash::array_from_le_bytes(master_key));
+        input.push(Goldilocks::from_canonical_u64(api_key_index as u64));
+        input.push(Goldilocks::zero());
+        let counter = input.len() - 1;
+
+        let mut round = 0u64;
+        let private_key = loop {
+            input[counter] = Goldilocks::from_canonical_u64(round);
+            let candidate_bytes = Zeroizing::new(poseidon_hash::hash_to_quintic_extension(&input).to_bytes_le());
+            let mut candidate = ScalarField::from_bytes_le(&candidate_bytes[..]).expect("40-byte hash");
+            // A borrow when subtracting N means candidate < N
+            let (_, borrow) = candidate.sub_inner(&ScalarField::N);
+            if borrow != 0 && !candidate.is_zero() {
+                break candidate;
+            }
+            candidate.zeroize();
+            round += 1;
+        };
+        input.iter_mut().for_each(|element| *element = Goldilocks::zero());
+
+        Ok(Self { private_key })
+    }
+
+    /// Get the public key
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey::from_bytes(&self.public_key_bytes()).expect("derived public key is a valid point")
+    }
+
+    /// Fingerprint of the public key, see `PublicKey::fingerprint`
+    pub fn fingerprint(&self) -> String {
+        self.public_key().fingerprint()
     }
 
     /// Get the public key as bytes (40 bytes)
     pub fn public_key_bytes(&self) -> [u8; 40] {
-        use goldilocks_crypto::schnorr::Point;
         // Public key = generator * private_key, encoded as Fp5Element
-        let generator = Point::generator();
-        let public_point = generator.mul(&self.private_key);
+        let public_point = Point::mul_generator(&self.private_key);
         let public_fp5 = public_point.encode();
         public_fp5.to_bytes_le()
     }
 
     /// Get the private key as bytes (40 bytes)
+    ///
+    /// The returned copy is not wiped automatically; wrap it in `zeroize::Zeroizing`
+    /// if it needs to outlive the call site.
     pub fn private_key_bytes(&self) -> [u8; 40] {
         self.private_key.to_bytes_le()
     }
 
-    pub fn sign(&self, message: &[u8; 40]) -> Result<[u8; 80]> {
-        // Generate cryptographically secure random nonce
-        let nonce_scalar = ScalarField::sample_crypto();
-        let nonce_bytes = nonce_scalar.to_bytes_le();
-        self.sign_with_fixed_nonce(message, &nonce_bytes)
+    /// Sign a 40-byte message hash with a fresh random nonce
+    pub fn sign(&self, message: &[u8; 40]) -> Result<Signature> {
+        self.sign_with_rng(message, &mut rand::thread_rng())
+    }
+
+    /// Sign a 40-byte message hash with a nonce drawn from `rng`
+    ///
+    /// The nonce must never repeat across messages under the same key, or the private key
+    /// can be recovered: seed a deterministic generator only in tests.
+    pub fn sign_with_rng<R: RngCore + CryptoRng + ?Sized>(&self, message: &[u8; 40], rng: &mut R) -> Result<Signature> {
+        let nonce_bytes = Zeroizing::new(ScalarField::sample(rng).to_bytes_le());
+        self.sign_with_fixed_nonce(message, &nonce_bytes[..])
     }
     
-    
-    
-    fn sign_with_fixed_nonce(&self, message: &[u8; 40], nonce_bytes: &[u8]) -> Result<[u8; 80]> {
-        let pk_bytes = self.private_key.to_bytes_le();
+    fn sign_with_fixed_nonce(&self, message: &[u8; 40], nonce_bytes: &[u8]) -> Result<Signature> {
+        let pk_bytes = Zeroizing::new(self.private_key.to_bytes_le());
         
         // Pass message directly - sign_with_nonce will convert it properly
-        let signature = sign_with_nonce(&pk_bytes, message, nonce_bytes)?;
-        let mut result = [0u8; 80];
-        result.copy_from_slice(&signature);
-        Ok(result)
+        let signature = sign_with_nonce(&pk_bytes[..], message, nonce_bytes)?;
+        Ok(Signature::from_bytes(&signature)?)
     }
     
+    /// This key's share of a multi-party signature, see `musig`
+    ///
+    /// `nonce` is consumed: each secret nonce signs exactly one message.
+    pub fn musig_partial_sign(&self, session: &musig::SigningSession, nonce: musig::SecretNonce) -> Result<musig::PartialSignature> {
+        let pk_bytes = Zeroizing::new(self.private_key.to_bytes_le());
+        Ok(session.partial_sign(&pk_bytes[..], nonce)?)
+    }
+
+    /// Evaluate the VRF on `message`: a pseudorandom output fixed by this key and the
+    /// message, and a proof of it that anyone can check with `PublicKey::vrf_verify`
+    pub fn vrf_prove(&self, message: &[u8]) -> Result<(VrfOutput, VrfProof)> {
+        let pk_bytes = Zeroizing::new(self.private_key.to_bytes_le());
+        Ok(goldilocks_crypto::vrf_prove(&pk_bytes[..], message)?)
+    }
+
+    /// Create an auth token string, see `AuthToken`
     pub fn create_auth_token(
         &self,
         deadline: i64,
         account_index: i64,
         api_key_index: u8,
     ) -> Result<String> {
-        // Match Go: ConstructAuthToken format "deadline:account_index:api_key_index"
-        let auth_data = format!("{}:{}:{}", deadline, account_index, api_key_index);
-        
-        // Convert message bytes to Goldilocks elements
-        let auth_bytes = auth_data.as_bytes();
-        
-        // CRITICAL: Pad each 8-byte chunk individually
-        // Calculate missing bytes: (8 - len(in) % 8) % 8, then pad the last chunk with zeros
-        let missing = (8 - auth_bytes.len() % 8) % 8;
-        
-        let mut elements = Vec::new();
-        
-        // Process in chunks of 8 bytes (one Goldilocks element per 8 bytes)
-        let mut i = 0;
-        while i < auth_bytes.len() {
-            let next_start = (i + 8).min(auth_bytes.len());
-            let chunk = &auth_bytes[i..next_start];
-            
-            let mut bytes = [0u8; 8];
-            bytes[..chunk.len()].copy_from_slice(chunk);
-            
-            // Pad only the last chunk if needed
-            if chunk.len() < 8 && missing > 0 {
-                bytes[chunk.len()..].fill(0);
-            }
-            
-            // Read as little-endian u64, then convert to Goldilocks
-            let val = u64::from_le_bytes(bytes);
-            elements.push(Goldilocks::from_canonical_u64(val));
-            
-            i = next_start;
+        // Match Go: ConstructAuthToken format "deadline:account_index:api_key_index:signature"
+        Ok(AuthToken::sign(self, deadline, account_index, api_key_index)?.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const P: u64 = 0xffff_ffff_0000_0001;
+
+    #[test]
+    fn test_public_key_canonical_parsing() {
+        let key = KeyManager::generate();
+        let public_key = key.public_key();
+        assert_eq!(public_key.to_bytes(), key.public_key_bytes());
+
+        // Same key with an upper-case hex prefix and one limb written as v + p
+        let mut bytes = key.public_key_bytes();
+        let limb = u64::from_le_bytes(bytes[8..16].try_into().unwrap());
+        if let Some(alias) = limb.checked_add(P) {
+            bytes[8..16].copy_from_slice(&alias.to_le_bytes());
         }
-        
-        // Hash the elements using Poseidon2 (matching Go's HashToQuinticExtension)
-        use poseidon_hash::hash_to_quintic_extension;
-        let hash_fp5 = hash_to_quintic_extension(&elements);
-        
-        // Convert Fp5Element to 40-byte array for signing
-        let message_bytes = hash_fp5.to_bytes_le();
-        
-        // Sign the hash
-        let signature = self.sign(&message_bytes)?;
-        let signature_hex = hex::encode(&signature);
-        
-        Ok(format!("{}:{}", auth_data, signature_hex))
+        let parsed = PublicKey::from_hex(&format!("0x{}", hex::encode_upper(bytes))).unwrap();
+        assert_eq!(parsed, public_key);
+        assert_eq!(parsed.to_string(), hex::encode(key.public_key_bytes()));
+
+        assert!(PublicKey::from_bytes(&[0u8; 39]).is_err());
+    }
+
+    #[test]
+    fn test_out_of_range_private_keys() {
+        let key = KeyManager::generate();
+        let hex_key = hex::encode(key.private_key_bytes());
+        assert_eq!(KeyManager::from_hex(&format!("0x{}", hex_key)).unwrap().public_key(), key.public_key());
+
+        // N itself signs like zero, N + 1 like one
+        let order = ScalarField::N.to_bytes_le();
+        let mut order_plus_one = order;
+        order_plus_one[0] += 1;
+        for bytes in [[0u8; 40], order, order_plus_one, [0xff; 40]] {
+            assert!(matches!(
+                KeyManager::new(&bytes),
+                Err(SignerError::Crypto(goldilocks_crypto::CryptoError::InvalidPrivateKey))
+            ));
+        }
+        assert!(KeyManager::from_hex(&ScalarField::NEG_ONE.to_hex()).is_ok());
+    }
+
+    #[test]
+    fn test_debug_output_is_redacted() {
+        let key = KeyManager::generate();
+        let private_hex = hex::encode(key.private_key_bytes());
+        let fingerprint = key.fingerprint();
+        assert_eq!(fingerprint, key.public_key().to_hex()[..8]);
+
+        for output in [format!("{:?}", key), format!("{:#?}", key), key.to_string()] {
+            assert!(output.contains(&fingerprint), "{}", output);
+            assert!(!output.contains(&private_hex[..16]), "{}", output);
+        }
+        let scalar = ScalarField::from_hex(&private_hex).unwrap();
+        assert_eq!(format!("{:?} {}", scalar, scalar), "ScalarField(<redacted>) ScalarField(<redacted>)");
+    }
+
+    #[test]
+    fn test_two_party_signature() {
+        let (server, approver) = (KeyManager::generate(), KeyManager::generate());
+        let context = musig::KeyAggContext::new(&[server.public_key_bytes(), approver.public_key_bytes()]).unwrap();
+        let message = [9u8; 40];
+
+        let server_nonce = musig::SecretNonce::generate(&mut rand::thread_rng());
+        let approver_nonce = musig::SecretNonce::generate(&mut rand::thread_rng());
+        let aggregate_nonce = musig::AggregateNonce::sum(&[server_nonce.public_nonce(), approver_nonce.public_nonce()]).unwrap();
+
+        let session = musig::SigningSession::new(&context, &aggregate_nonce, &message).unwrap();
+        let partials = [
+            server.musig_partial_sign(&session, server_nonce).unwrap(),
+            approver.musig_partial_sign(&session, approver_nonce).unwrap(),
+        ];
+        let signature = session.aggregate(&partials).unwrap();
+        assert!(goldilocks_crypto::verify_signature(signature.as_bytes(), &message, &context.aggregate_public_key()).unwrap());
+        assert!(!goldilocks_crypto::verify_signature(signature.as_bytes(), &message, &server.public_key_bytes()).unwrap());
+    }
+
+    #[test]
+    fn test_vrf_round_trip() {
+        let key = KeyManager::generate();
+        let (output, proof) = key.vrf_prove(b"draw 17").unwrap();
+        assert_eq!(key.vrf_prove(b"draw 17").unwrap(), (output, proof));
+        assert_eq!(key.public_key().vrf_verify(b"draw 17", &proof).unwrap(), Some(output));
+        assert_eq!(VrfProof::from_hex(&proof.to_hex()).unwrap(), proof);
+
+        assert_eq!(key.public_key().vrf_verify(b"draw 18", &proof).unwrap(), None);
+        assert_eq!(KeyManager::generate().public_key().vrf_verify(b"draw 17", &proof).unwrap(), None);
+        assert_ne!(key.vrf_prove(b"draw 18").unwrap().0, output);
+    }
+
+    #[test]
+    fn test_seeded_rng_is_reproducible() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let key = KeyManager::generate_with_rng(&mut StdRng::seed_from_u64(1));
+        let same = KeyManager::generate_with_rng(&mut StdRng::seed_from_u64(1));
+        assert_eq!(key.private_key_bytes(), same.private_key_bytes());
+        assert_ne!(key.private_key_bytes(), KeyManager::generate_with_rng(&mut StdRng::seed_from_u64(2)).private_key_bytes());
+
+        let message = [7u8; 40];
+        let a = key.sign_with_rng(&message, &mut StdRng::seed_from_u64(3)).unwrap();
+        let b = key.sign_with_rng(&message, &mut StdRng::seed_from_u64(3)).unwrap();
+        assert_eq!(a.as_bytes(), b.as_bytes());
+        assert!(goldilocks_crypto::verify_signature(a.as_bytes(), &message, &key.public_key_bytes()).unwrap());
+    }
+
+    #[test]
+    fn test_derive_api_key() {
+        let master = [42u8; 32];
+        let key = KeyManager::derive_api_key(&master, 3).unwrap();
+        assert_eq!(key.private_key_bytes(), KeyManager::derive_api_key(&master, 3).unwrap().private_key_bytes());
+        // Pinned: changing the derivation would silently change provisioned keys
+        assert_eq!(
+            hex::encode(key.private_key_bytes()),
+            "0c333ad3a3b978c94da5e87fe19fc643349a43f7144c6665ef857b2ae1ba70ca8005ef3bad218d67"
+        );
+
+        let mut keys: Vec<[u8; 40]> = (0..=255u8)
+            .map(|index| KeyManager::derive_api_key(&master, index).unwrap().public_key_bytes())
+            .collect();
+        keys.sort();
+        keys.dedup();
+        assert_eq!(keys.len(), 256);
+
+        let mut other = master;
+        other[31] ^= 1;
+        assert_ne!(KeyManager::derive_api_key(&other, 3).unwrap().private_key_bytes(), key.private_key_bytes());
+        assert!(KeyManager::derive_api_key(&master[..31], 3).is_err());
+
+        let message = [7u8; 40];
+        let signature = key.sign(&message).unwrap();
+        assert!(goldilocks_crypto::verify_signature(signature.as_bytes(), &message, &key.public_key_bytes()).unwrap());
     }
 }